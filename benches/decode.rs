@@ -0,0 +1,70 @@
+//! Compares decoding a witness-sized vector and a matrix from bincode and from the raw codec.
+//!
+//! Run with `cargo bench --bench decode`; `DECODE_LEN` sets the vector length and matrix
+//! rows (default 1 << 20).
+
+use std::time::{Duration, Instant};
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::de::DeserializeOwned;
+use spmvm_test_example::{
+    data::RawCodec,
+    generate::{random_matrix, random_vector, Shape},
+    SparseMatrix,
+};
+
+const RUNS: usize = 5;
+
+fn main() {
+    let len = std::env::var("DECODE_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(1 << 20);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+    let vector: Vec<Fr> = random_vector(&mut rng, len);
+    compare("vector", &vector);
+
+    let shape = Shape {
+        rows: len,
+        cols: len,
+        nnz_per_row: 4,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    compare("matrix", &matrix);
+}
+
+/// Prints the median decode time of `value` in each encoding, and the speedup of raw.
+fn compare<T>(label: &str, value: &T)
+where
+    T: serde::Serialize + DeserializeOwned + RawCodec + PartialEq,
+{
+    let bincode_bytes = bincode::serialize(value).unwrap();
+    let mut raw_bytes = Vec::new();
+    value.write_raw(&mut raw_bytes).unwrap();
+
+    let bincode = median(|| bincode::deserialize::<T>(&bincode_bytes).unwrap() == *value);
+    let raw = median(|| T::read_raw(&raw_bytes).unwrap() == *value);
+    println!(
+        "{label}: bincode {bincode:?} ({} bytes), raw {raw:?} ({} bytes), {:.2}x",
+        bincode_bytes.len(),
+        raw_bytes.len(),
+        bincode.as_secs_f64() / raw.as_secs_f64()
+    );
+}
+
+/// Median over [`RUNS`] of the time `decode` takes, which must succeed. The check is timed
+/// too, equally for both encodings.
+fn median(mut decode: impl FnMut() -> bool) -> Duration {
+    let mut durations: Vec<_> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            assert!(decode(), "decoded value differs");
+            start.elapsed()
+        })
+        .collect();
+    durations.sort_unstable();
+    durations[RUNS / 2]
+}
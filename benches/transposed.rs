@@ -0,0 +1,87 @@
+//! Compares `A^T y` computed directly with `multiply_vec_transposed` against building the
+//! transpose and multiplying by it, in time and in the memory each allocates at its peak.
+//!
+//! Run with `cargo bench --bench transposed`; `TRANSPOSED_LEN` sets the rows and columns of
+//! the matrix (default 1 << 20).
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    SparseMatrix,
+};
+
+const RUNS: usize = 5;
+
+/// The system allocator, keeping track of the bytes allocated and of their peak.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn main() {
+    let len = std::env::var("TRANSPOSED_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(1 << 20);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let shape = Shape {
+        rows: len,
+        cols: len,
+        nnz_per_row: 4,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let y: Vec<Fr> = random_vector(&mut rng, len);
+
+    let expected = matrix.transpose().multiply_vec(&y);
+    let (direct, direct_peak) = measure(|| matrix.multiply_vec_transposed(&y) == expected);
+    let (transposed, transposed_peak) = measure(|| matrix.transpose().multiply_vec(&y) == expected);
+    println!(
+        "direct {direct:?} (peak {direct_peak} bytes), transpose then multiply {transposed:?} \
+         (peak {transposed_peak} bytes), {:.2}x",
+        transposed.as_secs_f64() / direct.as_secs_f64()
+    );
+}
+
+/// Median over [`RUNS`] of the time `multiply` takes, whose result must be right, and the most
+/// memory it allocated on top of what was already allocated. The check is timed too, equally
+/// for both.
+fn measure(mut multiply: impl FnMut() -> bool) -> (Duration, usize) {
+    let mut peak = 0;
+    let mut durations: Vec<_> = (0..RUNS)
+        .map(|_| {
+            let before = ALLOCATED.load(Ordering::Relaxed);
+            PEAK.store(before, Ordering::Relaxed);
+            let start = Instant::now();
+            assert!(multiply(), "product differs");
+            let elapsed = start.elapsed();
+            peak = peak.max(PEAK.load(Ordering::Relaxed) - before);
+            elapsed
+        })
+        .collect();
+    durations.sort_unstable();
+    (durations[RUNS / 2], peak)
+}
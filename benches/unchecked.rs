@@ -0,0 +1,57 @@
+//! Compares the parallel kernel with the one that skips the bounds checks on the vector once
+//! the matrix is validated.
+//!
+//! Run with `cargo bench --bench unchecked`; `UNCHECKED_LEN` sets the rows and columns of the
+//! matrix (default 1 << 20).
+
+use std::time::{Duration, Instant};
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    SparseMatrix,
+};
+
+const RUNS: usize = 5;
+
+fn main() {
+    let len = std::env::var("UNCHECKED_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(1 << 20);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let shape = Shape {
+        rows: len,
+        cols: len,
+        nnz_per_row: 4,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let z: Vec<Fr> = random_vector(&mut rng, len);
+
+    let start = Instant::now();
+    let validated = matrix.validated().unwrap();
+    let validation = start.elapsed();
+    let expected = matrix.multiply_vec_serial(&z);
+    let checked = median(|| matrix.multiply_vec(&z) == expected);
+    let unchecked = median(|| validated.multiply_vec(&z) == expected);
+    println!(
+        "checked {checked:?}, unchecked {unchecked:?} after validating in {validation:?}, {:.2}x",
+        checked.as_secs_f64() / unchecked.as_secs_f64()
+    );
+}
+
+/// Median over [`RUNS`] of the time `multiply` takes, whose result must be right. The check
+/// is timed too, equally for both kernels.
+fn median(mut multiply: impl FnMut() -> bool) -> Duration {
+    let mut durations: Vec<_> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            assert!(multiply(), "product differs");
+            start.elapsed()
+        })
+        .collect();
+    durations.sort_unstable();
+    durations[RUNS / 2]
+}
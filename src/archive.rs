@@ -0,0 +1,212 @@
+//! # Archived Matrices
+//!
+//! An alternative on-disk representation of [`SparseMatrix`], written with rkyv, that is used
+//! straight from the mapped file: nothing is deserialized or copied, and each entry is decoded
+//! as the kernel reaches it. Archives live next to the bincode files that `convert` made them
+//! from, as `<label>.rkyv`.
+
+use std::{fs::File, marker::PhantomData, mem::size_of, ops::Range};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use ff::PrimeField;
+use halo2curves::serde::SerdeObject;
+use memmap2::Mmap;
+use rkyv::{rancor, util::AlignedVec, Archive, Serialize};
+
+use crate::{
+    data::{init_config, DataConfig, DataError},
+    sparse::CsrView,
+    SparseMatrix, ARECIBO_CONFIG,
+};
+
+/// Extension of archived matrix files.
+pub const ARCHIVE_EXTENSION: &str = "rkyv";
+
+/// The archived form of a [`SparseMatrix`]. Values are kept in their raw in-memory
+/// representation, so decoding one is a copy rather than a conversion.
+#[derive(Debug, Archive, Serialize)]
+pub struct RawMatrix {
+    /// Raw bytes of every value, `value_bytes` each.
+    pub data: Vec<u8>,
+    pub value_bytes: u32,
+    pub indices: Vec<u64>,
+    pub indptr: Vec<u64>,
+    pub cols: u64,
+}
+
+impl RawMatrix {
+    pub fn new<F: PrimeField + SerdeObject>(matrix: &SparseMatrix<F>) -> Self {
+        let value_bytes = size_of::<F>();
+        let mut data = Vec::with_capacity(matrix.data.len() * value_bytes);
+        for value in &matrix.data {
+            value
+                .write_raw(&mut data)
+                .expect("writing to a Vec cannot fail");
+        }
+        Self {
+            data,
+            value_bytes: value_bytes as u32,
+            indices: matrix.indices.iter().map(|&i| i as u64).collect(),
+            indptr: matrix.indptr.iter().map(|&i| i as u64).collect(),
+            cols: matrix.cols as u64,
+        }
+    }
+
+    /// Encodes `matrix` as the bytes of an archive.
+    pub fn to_bytes<F: PrimeField + SerdeObject>(matrix: &SparseMatrix<F>) -> AlignedVec {
+        rkyv::to_bytes::<rancor::Error>(&Self::new(matrix))
+            .expect("in-memory archiving cannot fail")
+    }
+}
+
+impl ArchivedRawMatrix {
+    /// Validates `bytes` as an archive of values of `F` and views it. `bytes` must be 16-byte
+    /// aligned, as mapped files and the result of [`RawMatrix::to_bytes`] are.
+    pub fn access<F: PrimeField>(bytes: &[u8]) -> Result<&Self, rancor::Error> {
+        let archived = rkyv::access::<Self, rancor::Error>(bytes)?;
+        archived.check_shape(size_of::<F>())?;
+        Ok(archived)
+    }
+
+    /// Checks what rkyv cannot: that the values are `value_bytes` wide and every row and column
+    /// points inside the matrix, so [`CsrView::entry`] never reads out of bounds.
+    fn check_shape(&self, value_bytes: usize) -> Result<(), rancor::Error> {
+        use rancor::Source as _;
+        if self.value_bytes.to_native() as usize != value_bytes {
+            return Err(rancor::Error::new(std::io::Error::other(format!(
+                "values are {} bytes wide, not {value_bytes}",
+                self.value_bytes.to_native()
+            ))));
+        }
+        let nnz = self.indices.len() as u64;
+        let ok = self.indptr.first().map(|i| i.to_native()) == Some(0)
+            && self.indptr.last().map(|i| i.to_native()) == Some(nnz)
+            && self.indptr.windows(2).all(|ptrs| ptrs[0] <= ptrs[1])
+            && self.data.len() as u64 == nnz * self.value_bytes.to_native() as u64
+            && self
+                .indices
+                .iter()
+                .all(|i| i.to_native() < self.cols.to_native());
+        match ok {
+            true => Ok(()),
+            false => Err(rancor::Error::new(std::io::Error::other(
+                "indices or offsets point outside the matrix",
+            ))),
+        }
+    }
+}
+
+impl<F: PrimeField + SerdeObject> CsrView<F> for ArchivedRawMatrix {
+    fn rows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    fn cols(&self) -> usize {
+        self.cols.to_native() as usize
+    }
+
+    fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn row_range(&self, row: usize) -> Range<usize> {
+        self.indptr[row].to_native() as usize..self.indptr[row + 1].to_native() as usize
+    }
+
+    fn entry(&self, k: usize) -> (F, usize) {
+        let width = size_of::<F>();
+        // `access` checked that the values are this wide, and archives are written from valid
+        // values, so reading them back unchecked is safe; a corrupt value only yields wrong
+        // products.
+        let value = F::from_raw_bytes_unchecked(&self.data[k * width..(k + 1) * width]);
+        (value, self.indices[k].to_native() as usize)
+    }
+}
+
+/// An archive of values of `F` mapped into memory, validated once when it was opened.
+pub struct MappedMatrix<F> {
+    map: Mmap,
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField + SerdeObject> MappedMatrix<F> {
+    /// Maps and validates the archive at `path`, whose values must be as wide as `F`.
+    pub fn open(path: Utf8PathBuf) -> Result<Self, DataError> {
+        let io_error = |source| DataError::Io {
+            path: path.clone(),
+            source,
+        };
+        let file = File::open(&path).map_err(io_error)?;
+        // SAFETY: the map is only sound while no one else truncates or writes to the file.
+        // Archives are written once by `convert` and not modified while a run reads them.
+        let map = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+        if let Err(err) = ArchivedRawMatrix::access::<F>(&map) {
+            return Err(DataError::InvalidArchive {
+                path,
+                message: err.to_string(),
+            });
+        }
+        Ok(Self {
+            map,
+            field: PhantomData,
+        })
+    }
+
+    /// The archived matrix, read directly from the mapped bytes.
+    pub fn view(&self) -> &ArchivedRawMatrix {
+        // SAFETY: the bytes were validated by `open`, and the map is never written to.
+        unsafe { rkyv::access_unchecked::<ArchivedRawMatrix>(&self.map) }
+    }
+}
+
+/// `label` with the archive extension, as in `A_0.rkyv`.
+pub fn archive_label(label: impl AsRef<Utf8Path>) -> Utf8PathBuf {
+    label.as_ref().with_extension(ARCHIVE_EXTENSION)
+}
+
+impl DataConfig {
+    /// Archives `matrix` to `section/<label>.rkyv`, returning the path written.
+    pub fn write_archive<F: PrimeField + SerdeObject>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        matrix: &SparseMatrix<F>,
+    ) -> Result<Utf8PathBuf, DataError> {
+        self.write_bytes(section, archive_label(label), &RawMatrix::to_bytes(matrix))
+    }
+
+    /// Maps the archive at `section/<label>.rkyv`.
+    pub fn open_archive<F: PrimeField + SerdeObject>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<MappedMatrix<F>, DataError> {
+        MappedMatrix::open(self.file_path(section, archive_label(label))?)
+    }
+}
+
+/// Archives `matrix` relative to the global [`ARECIBO_CONFIG`]; see [`DataConfig::write_archive`].
+pub fn write_arecibo_archive<F: PrimeField + SerdeObject>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+    matrix: &SparseMatrix<F>,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.write_archive(section, label, matrix)
+}
+
+/// Maps an archive relative to the global [`ARECIBO_CONFIG`]; see [`DataConfig::open_archive`].
+pub fn open_arecibo_archive<F: PrimeField + SerdeObject>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<MappedMatrix<F>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let file_path = mutex
+        .lock()
+        .unwrap()
+        .file_path(section, archive_label(label))?;
+
+    MappedMatrix::open(file_path)
+}
@@ -0,0 +1,12 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use spmvm_test_example::cli::{self, Cli};
+
+/// cargo run --release -- bench <HASH>
+fn main() -> ExitCode {
+    match Cli::try_parse() {
+        Ok(cli) => cli::run(cli),
+        Err(err) => cli::usage_error(err),
+    }
+}
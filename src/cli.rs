@@ -0,0 +1,1204 @@
+//! # Command Line Interface
+//!
+//! Argument parsing for the `spmvm` binary, and the subcommands it dispatches to.
+//! Parsing lives in the library so that flags can be tested with [`Cli::parse_from`].
+#![allow(non_snake_case)]
+
+mod bench;
+mod cache;
+mod check;
+mod compare;
+mod convert;
+mod cross_term;
+mod generate;
+mod inspect;
+mod list;
+mod manifest;
+mod outcome;
+mod r1cs;
+mod regen;
+mod stats;
+mod verify;
+
+use std::{fmt, io, process::ExitCode, time::Duration};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use halo2curves::bn256;
+use thiserror::Error;
+
+pub use bench::{BenchArgs, Layout};
+pub use cache::{CacheAction, CacheArgs};
+pub use check::CheckArgs;
+pub use compare::CompareArgs;
+pub use convert::{ConvertArgs, ConvertTarget};
+pub use cross_term::CrossTermArgs;
+pub use generate::GenerateArgs;
+pub use inspect::{ImportVectorArgs, InspectAction, InspectArgs, VectorArgs};
+pub use list::ListArgs;
+pub use manifest::{ManifestAction, ManifestArgs};
+pub use outcome::{CircuitResult, HashResult, RunResult, Status, Tally};
+pub use r1cs::R1csArgs;
+pub use regen::RegenArgs;
+pub use stats::{SpySize, StatsArgs};
+pub use verify::VerifyArgs;
+
+#[cfg(feature = "sprs")]
+use crate::sparse::sprs_multiply_vec;
+use crate::{
+    data::{
+        format_size, index_gaps, label_indices, matrices_section, open_arecibo_vector,
+        read_arecibo_cached, remove_arecibo_orphans, result_section, source, verification_totals,
+        witness_section, Circuit, DataFormat, ReadPath, DEFAULT_LOCK_TIMEOUT,
+    },
+    diff::{diff_vectors, verify_stream, VectorDiff, DEFAULT_DIFF_LIMIT},
+    hex::field_from_hex,
+    read_arecibo_data_with_format, set_config,
+    sparse::{
+        ChunkPolicy, CscMatrix, EllMatrix, MatrixError, DEFAULT_BLOCK_COLS, DEFAULT_ELL_WIDTH,
+    },
+    timing::Measurement,
+    DataConfig, DataError, SparseMatrix,
+};
+
+/// Benchmark and verify the sparse matrix / vector products of arecibo dumps.
+#[derive(Debug, Parser)]
+#[command(name = "spmvm", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Flags shared by every subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct GlobalArgs {
+    /// Root of the data directory [default: $ARECIBO_DATA_DIR, then ~/.arecibo_data]; an
+    /// http://, https://, s3://, or file:// URL is fetched from into a directory under the
+    /// default root
+    #[arg(long, global = true, value_name = "PATH", value_parser = parse_data_dir)]
+    pub data_dir: Option<Utf8PathBuf>,
+    /// Number of threads used by the multiplication kernels; 0 uses every logical core
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    pub threads: usize,
+    /// Number of witnesses to run [default: every witness in the section]
+    #[arg(long, global = true, value_name = "N")]
+    pub iterations: Option<usize>,
+    /// Index of the first witness to run
+    #[arg(long, global = true, value_name = "K", default_value_t = 0)]
+    pub start: usize,
+    /// Matrices to load, multiply, and verify, as a comma-separated subset of A,B,C
+    #[arg(
+        long,
+        global = true,
+        value_name = "LIST",
+        value_enum,
+        value_delimiter = ',',
+        ignore_case = true,
+        default_values_t = MatrixName::ALL
+    )]
+    pub matrices: Vec<MatrixName>,
+    /// Format of listings and of the final `RESULT` line
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+    /// Memory-map data files instead of streaming them, falling back for files that cannot be mapped
+    #[arg(long, global = true)]
+    pub mmap: bool,
+    /// Compress the data files written by `generate` and `regen-results` with zstd, as `<label>.zst`
+    #[arg(long, global = true)]
+    pub compress: bool,
+    /// Encoding of the matrices, witnesses, and expected results to read; `raw` reads the
+    /// `<label>.raw` files written by `convert --to raw`
+    #[arg(long, global = true, value_enum, default_value_t = DataFormat::Bincode)]
+    pub data_format: DataFormat,
+    /// Load the matrices from their bincode files every time, neither reading nor writing the
+    /// cache under `<root>/cache`
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    /// Hash every data file read and check it against the `manifest.json` of its section,
+    /// failing on a mismatch; the cost is printed before the `RESULT` line
+    #[arg(long, global = true)]
+    pub verify_files: bool,
+    /// Check every invariant of each matrix right after it is loaded, failing with the
+    /// offending row and entry instead of panicking part way through a product; `stats`
+    /// always does
+    #[arg(long, global = true)]
+    pub validate: bool,
+    /// Seconds to wait for another process writing the same section or cache entry to
+    /// finish before giving up
+    #[arg(long, global = true, value_name = "SECS", default_value_t = DEFAULT_LOCK_TIMEOUT.as_secs())]
+    pub lock_timeout: u64,
+    /// Fetch the files of SECTION missing from the data root from URL, which serves the files of
+    /// that section directly; may be repeated
+    #[arg(long, global = true, value_name = "SECTION=URL", value_parser = parse_section_url)]
+    pub section_url: Vec<(String, String)>,
+}
+
+fn parse_data_dir(arg: &str) -> Result<Utf8PathBuf, String> {
+    if source::is_url(arg) {
+        source::check_url(arg)?;
+    }
+    Ok(arg.into())
+}
+
+fn parse_section_url(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((section, url)) if !section.is_empty() && source::is_url(url) => {
+            source::check_url(url)?;
+            Ok((section.to_string(), url.to_string()))
+        }
+        _ => Err(format!("expected SECTION=URL, got {arg}")),
+    }
+}
+
+/// Output formats selectable with `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// Errors reported by the subcommands.
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Data(#[from] DataError),
+    /// A requested witness index is not present in the witness section.
+    #[error("witness _{index} not found, {}", describe_indices(available))]
+    WitnessNotFound { index: usize, available: Vec<usize> },
+    /// A report could not be written or read.
+    #[error("failed to access report {path}: {source}")]
+    Report {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// `compare` found regressions above the threshold, or reports that do not line up.
+    #[error("comparison failed: {regressions} regressions, {mismatches} mismatched entries")]
+    CompareFailed {
+        regressions: usize,
+        mismatches: usize,
+    },
+    /// Some products did not match their expected results.
+    #[error("{mismatches} products did not match the expected results")]
+    VerificationFailed { mismatches: usize },
+    /// Some witnesses did not satisfy the relation their products were checked against.
+    #[error("{witnesses} witnesses do not satisfy the relation")]
+    Unsatisfied { witnesses: usize },
+    /// Files or sections of the dump are missing; they have already been listed.
+    #[error("dump {hash} is incomplete: {missing} files or sections are missing")]
+    IncompleteDump { hash: String, missing: usize },
+    /// Labels a subcommand needs are missing from their section.
+    #[error("{section} is missing {}", describe_labels(labels))]
+    MissingLabels {
+        section: String,
+        labels: Vec<String>,
+    },
+    /// A relaxed instance read from `label` does not fit the matrices it is checked with.
+    #[error("invalid relaxed instance {label}: {reason}")]
+    InvalidRelaxed { label: String, reason: String },
+    /// `regen-results` would replace existing expected results without `--force`.
+    #[error(
+        "{section} already holds {}; pass --force to overwrite",
+        describe_labels(labels)
+    )]
+    WouldOverwrite {
+        section: String,
+        labels: Vec<String>,
+    },
+    /// The `--threads` pool could not be started.
+    #[error("failed to start the thread pool: {0}")]
+    ThreadPool(#[source] rayon::ThreadPoolBuildError),
+    /// Flags that parsed but do not make sense together.
+    #[error("{0}")]
+    InvalidArgs(String),
+    /// Matrices and witnesses read from the dump that do not fit together.
+    #[error("{context}: {source}")]
+    Incompatible {
+        context: String,
+        #[source]
+        source: MatrixError,
+    },
+    /// The witness section exists but holds no `_N` labels.
+    #[error("witness section {0} contains no witnesses")]
+    NoWitnesses(String),
+    /// Some of the matrices could not be loaded; every failed one is listed.
+    #[error("failed to load {}", describe_load_failures(.0))]
+    MatrixLoad(Vec<(MatrixName, DataError)>),
+    /// A matrix does not fit the format of another library it was converted to.
+    #[error("{name} does not convert to {target}: {message}")]
+    Unconvertible {
+        name: MatrixName,
+        target: &'static str,
+        message: String,
+    },
+    /// Some of the loaded matrices break an invariant; every invalid one is listed.
+    #[error("invalid {}", describe_invalid_matrices(.0))]
+    InvalidMatrices(Vec<(MatrixName, MatrixError)>),
+}
+
+impl CliError {
+    fn report(path: &Utf8Path, source: io::Error) -> Self {
+        CliError::Report {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
+fn describe_indices(indices: &[usize]) -> String {
+    match (indices.first(), indices.last()) {
+        (Some(first), Some(last)) => format!("section contains {first}..={last}"),
+        _ => "section is empty".to_string(),
+    }
+}
+
+/// Names the first few of `labels`, counting the rest.
+fn describe_labels(labels: &[String]) -> String {
+    const SHOWN: usize = 3;
+    match labels.len().checked_sub(SHOWN) {
+        Some(rest) if rest > 0 => format!("{} and {rest} more", labels[..SHOWN].join(", ")),
+        _ => labels.join(", "),
+    }
+}
+
+/// Pairs each matrix that failed to load with its error.
+fn describe_load_failures(failures: &[(MatrixName, DataError)]) -> String {
+    failures
+        .iter()
+        .map(|(name, err)| format!("{name}: {}", describe(err)))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Pairs each invalid matrix with the invariant it breaks.
+fn describe_invalid_matrices(invalid: &[(MatrixName, MatrixError)]) -> String {
+    invalid
+        .iter()
+        .map(|(name, err)| format!("{name}: {err}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum Command {
+    /// Time `A z`, `B z`, and `C z` for each witness and check them against the expected results
+    Bench(BenchArgs),
+    /// Check `A z`, `B z`, and `C z` against the expected results, without timing
+    Verify(VerifyArgs),
+    /// Print the shape, sparsity, row balance, and memory footprint of `A`, `B`, and `C`
+    Stats(StatsArgs),
+    /// Check that the matrices, witnesses, and expected results of a dump are all present
+    Check(CheckArgs),
+    /// Check that `A z ∘ B z = C z` holds for each witness, without any expected results
+    CheckR1cs(R1csArgs),
+    /// Time the folding cross term of two witnesses, and check it against the dumped one
+    CrossTerm(CrossTermArgs),
+    /// Compare two reports written by `bench --output` and flag regressions
+    Compare(CompareArgs),
+    /// List the sections of the data root, or the labels of one section
+    List(ListArgs),
+    /// Write a seeded random dump with matrices, witnesses, and expected results
+    Generate(GenerateArgs),
+    /// Recompute the expected results of a dump with the serial reference kernel
+    RegenResults(RegenArgs),
+    /// Re-encode a dump next to its files, for `--data-format raw` or `bench --archived`
+    Convert(ConvertArgs),
+    /// Manage the cache of matrices that loads faster than their bincode files
+    Cache(CacheArgs),
+    /// Manage the checksums that `--verify-files` checks the files of a dump against
+    Manifest(ManifestArgs),
+    /// Print the contents of a data file, such as a witness, for debugging
+    Inspect(InspectArgs),
+    /// Write a vector exported by `inspect vector --out` back to a data file
+    ImportVector(ImportVectorArgs),
+}
+
+/// Selects the dump to operate on.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct HashArgs {
+    /// Hash identifying the dump, as in `sparse_matrices_<HASH>`
+    pub hash: String,
+    /// Circuit of the dump to operate on; the secondary one is stored under the hash
+    /// `<HASH>_secondary`, and `both` runs the subcommand once for each
+    #[arg(long, value_enum, default_value_t = CircuitSelection::Primary)]
+    pub circuit: CircuitSelection,
+}
+
+/// The circuits selectable with `--circuit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CircuitSelection {
+    #[default]
+    Primary,
+    Secondary,
+    Both,
+}
+
+impl CircuitSelection {
+    pub fn circuits(self) -> &'static [Circuit] {
+        match self {
+            CircuitSelection::Primary => &Circuit::BOTH[..1],
+            CircuitSelection::Secondary => &Circuit::BOTH[1..],
+            CircuitSelection::Both => &Circuit::BOTH,
+        }
+    }
+}
+
+impl From<Circuit> for CircuitSelection {
+    fn from(circuit: Circuit) -> Self {
+        match circuit {
+            Circuit::Primary => CircuitSelection::Primary,
+            Circuit::Secondary => CircuitSelection::Secondary,
+        }
+    }
+}
+
+/// Flags controlling how mismatching products are reported.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct DiffArgs {
+    /// Number of differing positions to print per mismatching product
+    #[arg(long, value_name = "K", default_value_t = DEFAULT_DIFF_LIMIT)]
+    pub diff_limit: usize,
+}
+
+/// Runs the parsed command line, printing any error and the final `RESULT` line,
+/// and mapping the outcome to an exit code.
+pub fn run(cli: Cli) -> ExitCode {
+    let format = cli.global.format;
+    let verify_files = cli.global.verify_files;
+    let mut tally = Tally::default();
+    let outcome = try_run(cli, &mut tally);
+    if verify_files {
+        print_verification();
+    }
+    let result = match outcome {
+        Ok(()) => RunResult {
+            result: Status::Ok,
+            tally,
+            error: None,
+        },
+        Err(err) => {
+            let message = error_message(&err);
+            eprintln!("error: {message}");
+            RunResult {
+                result: err.status(),
+                tally,
+                error: Some(message),
+            }
+        }
+    };
+    println!("{}", result.render(format));
+    result.result.into()
+}
+
+/// The message a run failing with `err` prints.
+fn error_message(err: &CliError) -> String {
+    match err {
+        CliError::Data(err) => describe(err),
+        err => err.to_string(),
+    }
+}
+
+/// Prints what `--verify-files` has cost: the hashing throughput, and how long reads waited
+/// for hashing to catch up with decoding.
+fn print_verification() {
+    let totals = verification_totals();
+    let throughput = match totals.hashing.as_secs_f64() {
+        secs if secs > 0.0 => format!("{}/s", format_size((totals.bytes as f64 / secs) as u64)),
+        _ => "-".to_string(),
+    };
+    println!(
+        "verified {} files ({}): hashed at {throughput}, reads waited {:.3?} for hashing",
+        totals.files,
+        format_size(totals.bytes),
+        totals.waiting
+    );
+}
+
+/// Reports a command line that failed to parse.
+/// Help and version requests are printed as usual and succeed; anything else is a usage error.
+pub fn usage_error(err: clap::Error) -> ExitCode {
+    let _ = err.print();
+    if !err.use_stderr() {
+        return ExitCode::SUCCESS;
+    }
+    let result = RunResult {
+        result: Status::Usage,
+        tally: Tally::default(),
+        error: Some(err.kind().to_string()),
+    };
+    // `--format` itself may not have parsed, so the line is always text.
+    println!("{}", result.render(Format::Text));
+    Status::Usage.into()
+}
+
+fn try_run(cli: Cli, tally: &mut Tally) -> Result<(), CliError> {
+    let Cli { global, command } = cli;
+
+    // Subcommands reading a dump run on the dedicated pool, never on rayon's global one.
+    let global = &global;
+    let writes = command.writes_data(global);
+    match command {
+        Command::Bench(args) => {
+            let pool = init(global, writes)?;
+            pool.install(|| bench::run(global, &args, &pool, tally))
+        }
+        Command::Verify(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| verify::verify(global, args, tally),
+            )
+        }),
+        Command::Stats(args) => {
+            let pool = init(global, writes)?;
+            pool.install(|| stats::run(global, &args, &pool, tally))
+        }
+        Command::Check(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| check::check(global, args, tally),
+            )
+        }),
+        Command::CheckR1cs(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| r1cs::check_r1cs(global, args, tally),
+            )
+        }),
+        Command::CrossTerm(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| cross_term::run(global, args, tally),
+            )
+        }),
+        Command::Compare(args) => compare::compare(&args, tally),
+        Command::List(args) => init(global, writes)?.install(|| list::list(&args, global.format)),
+        Command::Generate(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| generate::generate(args, tally),
+            )
+        }),
+        Command::RegenResults(args) => init(global, writes)?.install(|| {
+            for_each_circuit(
+                &args,
+                |args| &mut args.dump,
+                tally,
+                |args, _, tally| regen::regen_results(global, args, tally),
+            )
+        }),
+        Command::Convert(args) => {
+            init(global, writes)?.install(|| convert::run(global, &args, tally))
+        }
+        Command::Cache(args) => init(global, writes)?.install(|| cache::cache(&args)),
+        Command::Manifest(args) => init(global, writes)?.install(|| manifest::manifest(&args)),
+        Command::Inspect(args) => {
+            init(global, writes)?.install(|| inspect::inspect(&args, global.format))
+        }
+        Command::ImportVector(args) => {
+            init(global, writes)?.install(|| inspect::import_vector(&args))
+        }
+    }
+}
+
+/// Runs `run` on `args` once for each circuit selected with `--circuit`, with the hash of
+/// the [`HashArgs`] that `dump` picks out of them replaced by the hash of the circuit.
+/// With both circuits, `run` is also told which one it runs, each run gets a heading and a
+/// line with its outcome, and the secondary circuit still runs if the primary one fails; the
+/// first failure is returned once both have run.
+fn for_each_circuit<A: Clone>(
+    args: &A,
+    dump: impl Fn(&mut A) -> &mut HashArgs,
+    tally: &mut Tally,
+    mut run: impl FnMut(&A, Option<Circuit>, &mut Tally) -> Result<(), CliError>,
+) -> Result<(), CliError> {
+    let mut args = args.clone();
+    let HashArgs { hash, circuit } = dump(&mut args).clone();
+    let circuits = circuit.circuits();
+    if let [circuit] = circuits {
+        dump(&mut args).hash = circuit.hash(&hash);
+        return run(&args, None, tally);
+    }
+
+    let mut failure = None;
+    for &circuit in circuits {
+        let circuit_hash = circuit.hash(&hash);
+        println!("== {} circuit: {circuit_hash} ==", circuit.as_str());
+        *dump(&mut args) = HashArgs {
+            hash: circuit_hash,
+            circuit: CircuitSelection::from(circuit),
+        };
+        let mut circuit_tally = Tally::default();
+        let outcome = run(&args, Some(circuit), &mut circuit_tally);
+        let result = CircuitResult {
+            circuit,
+            result: outcome
+                .as_ref()
+                .map_or_else(CliError::status, |()| Status::Ok),
+            matrices: circuit_tally.matrices,
+            witnesses: circuit_tally.witnesses,
+            mismatches: circuit_tally.mismatches,
+            error: outcome.as_ref().err().map(error_message),
+        };
+        println!("{}\n", result.render_text());
+        tally.add(&circuit_tally);
+        tally.circuits.push(result);
+        match outcome {
+            Err(err) if failure.is_none() => failure = Some(err),
+            Err(err) => eprintln!(
+                "error: {} circuit: {}",
+                circuit.as_str(),
+                error_message(&err)
+            ),
+            Ok(()) => {}
+        }
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+impl Command {
+    /// Whether the subcommand may write into the data root: those that do first clean up the
+    /// temporary files that interrupted writes left behind.
+    fn writes_data(&self, global: &GlobalArgs) -> bool {
+        match self {
+            Command::Bench(_)
+            | Command::Verify(_)
+            | Command::Stats(_)
+            | Command::CheckR1cs(_)
+            | Command::CrossTerm(_) => {
+                !global.no_cache && global.data_format == DataFormat::Bincode
+            }
+            Command::Check(_) | Command::Compare(_) | Command::List(_) | Command::Inspect(_) => {
+                false
+            }
+            Command::Generate(_)
+            | Command::RegenResults(_)
+            | Command::Convert(_)
+            | Command::Cache(_)
+            | Command::Manifest(_)
+            | Command::ImportVector(_) => true,
+        }
+    }
+}
+
+/// Sets up the global data configuration for subcommands that read a dump, and builds the
+/// pool of `--threads` threads they run on. Subcommands that `writes` data first remove the
+/// temporary files of interrupted writes.
+fn init(global: &GlobalArgs, writes: bool) -> Result<rayon::ThreadPool, CliError> {
+    let mut config = DataConfig::resolve(global.data_dir.clone())?
+        .with_read_path(read_path(global))
+        .with_compression(global.compress)
+        .with_format(global.data_format)
+        .with_verify_files(global.verify_files)
+        .with_lock_timeout(Duration::from_secs(global.lock_timeout));
+    for (section, url) in &global.section_url {
+        config = config.with_section_source(section, source::open_source(url)?);
+    }
+    // Nothing has read data yet, so the global config cannot already be set.
+    set_config(config).expect("data config initialized twice");
+    if writes {
+        match remove_arecibo_orphans()? {
+            0 => {}
+            removed => {
+                eprintln!("removed {removed} orphaned temporary files left by interrupted writes")
+            }
+        }
+    }
+
+    build_pool(global.threads)
+}
+
+/// The read path selected with `--mmap`.
+fn read_path(global: &GlobalArgs) -> ReadPath {
+    match global.mmap {
+        true => ReadPath::Mmap,
+        false => ReadPath::Buffered,
+    }
+}
+
+/// Builds a pool of `threads` threads, or of one per logical core for 0.
+fn build_pool(threads: usize) -> Result<rayon::ThreadPool, CliError> {
+    // Rayon would read `RAYON_NUM_THREADS` for 0, so the core count is resolved here.
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(CliError::ThreadPool)
+}
+
+/// Renders a [`DataError`] as a message pointing the user at what to fix.
+fn describe(err: &DataError) -> String {
+    match err {
+        DataError::NoDataRoot | DataError::NotADirectory(_) | DataError::NonUtf8Path(_) => {
+            format!("{err}")
+        }
+        DataError::SectionNotFound(path) => {
+            format!("no section at {path}; is the hash correct and the dump complete?")
+        }
+        DataError::LabelNotFound(path) => format!("missing data file {path}"),
+        DataError::Io { path, source } => format!("could not read {path}: {source}"),
+        DataError::Deserialize {
+            path,
+            offset: Some(offset),
+            source,
+        } => {
+            format!("{path} is corrupt or has an unexpected format at byte {offset}: {source}")
+        }
+        DataError::Deserialize { path, source, .. } => {
+            format!("{path} is corrupt or has an unexpected format: {source}")
+        }
+        DataError::Serialize { path, source } => format!("could not encode {path}: {source}"),
+        DataError::InvalidArchive { path, message } | DataError::InvalidRaw { path, message } => {
+            format!("{path} is corrupt, reconvert it: {message}")
+        }
+        DataError::InvalidHeader { path, message } => {
+            format!("{path} cannot be read here: {message}")
+        }
+        DataError::InvalidManifest { path, message } => {
+            format!("{path} is corrupt, recreate it with `manifest create`: {message}")
+        }
+        DataError::ChecksumMismatch {
+            path,
+            expected,
+            actual,
+        } => format!(
+            "{path} does not match its manifest, so it was corrupted or changed after it was \
+             written: expected hash {expected}, found {actual}"
+        ),
+        DataError::Fetch { location, message } => {
+            format!("could not fetch {location}: {message}; is the source reachable?")
+        }
+        DataError::Locked { path, timeout } => format!(
+            "another process holds the lock {path} and did not release it within {}s; \
+             wait for it to finish or raise --lock-timeout",
+            timeout.as_secs()
+        ),
+    }
+}
+
+/// One of the matrices of a dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[value(rename_all = "UPPER")]
+pub enum MatrixName {
+    A,
+    B,
+    C,
+}
+
+impl MatrixName {
+    pub const ALL: [MatrixName; 3] = [MatrixName::A, MatrixName::B, MatrixName::C];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatrixName::A => "A",
+            MatrixName::B => "B",
+            MatrixName::C => "C",
+        }
+    }
+
+    /// Name of the product of this matrix with a witness, as in `AZ`.
+    pub fn product(self) -> &'static str {
+        match self {
+            MatrixName::A => "AZ",
+            MatrixName::B => "BZ",
+            MatrixName::C => "CZ",
+        }
+    }
+
+    /// [`CliError::InvalidMatrices`] for this matrix alone.
+    fn invalid(self, err: MatrixError) -> CliError {
+        CliError::InvalidMatrices(vec![(self, err)])
+    }
+
+    /// Label of this matrix in the matrices section.
+    fn label(self) -> String {
+        format!("{self}_0")
+    }
+
+    /// Label of the expected product with witness `i` in the result section.
+    fn product_label(self, i: usize) -> String {
+        format!("{}_{i}", self.product())
+    }
+}
+
+impl fmt::Display for MatrixName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Kernels that compute the products.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// [`SparseMatrix::multiply_vec_serial`], on the calling thread
+    Serial,
+    /// [`SparseMatrix::multiply_vec`], on the `--threads` pool
+    Parallel,
+    /// [`sprs_multiply_vec`], the product of sprs, on the calling thread: each matrix is
+    /// converted to a `CsMat` first, which is timed along with the product
+    #[cfg(feature = "sprs")]
+    Sprs,
+    /// [`TaggedMatrix::multiply_vec`], on the `--threads` pool: entries of 1 and -1 are added
+    /// and subtracted rather than multiplied; `bench` tags them before timing starts
+    Tagged,
+    /// [`ValidatedMatrix::multiply_vec`], on the `--threads` pool: the matrix is validated,
+    /// and its products skip the bounds checks on the vector; `bench` validates before timing
+    /// starts
+    Unchecked,
+    /// [`BlockedMatrix::multiply_vec`], on the `--threads` pool: the matrix is cut into blocks
+    /// of columns, multiplied one after another so that the slice of the vector each reads
+    /// stays in cache; `bench` cuts them before timing starts
+    Blocked,
+    /// [`EllMatrix::multiply_vec`], on the `--threads` pool: rows of few entries are padded
+    /// to the same number and multiplied a slot at a time, the longer ones as CSR; `bench`
+    /// stores them so before timing starts
+    Ell,
+}
+
+impl Backend {
+    /// The product of `M` with `vector`. The unchecked kernel validates `M` first, and fails
+    /// if it is invalid rather than running any other kernel in its place.
+    fn multiply_vec(
+        self,
+        M: &SparseMatrix<bn256::Fr>,
+        vector: &[bn256::Fr],
+    ) -> Result<Vec<bn256::Fr>, MatrixError> {
+        Ok(match self {
+            Backend::Serial => M.multiply_vec_serial(vector),
+            Backend::Parallel => M.multiply_vec(vector),
+            Backend::Tagged => M.tag_coefficients().multiply_vec(vector),
+            Backend::Unchecked => M.validated()?.multiply_vec(vector),
+            Backend::Blocked => M.to_blocked(DEFAULT_BLOCK_COLS).multiply_vec(vector),
+            Backend::Ell => EllMatrix::from_csr(M, DEFAULT_ELL_WIDTH).multiply_vec(vector),
+            #[cfg(feature = "sprs")]
+            Backend::Sprs => sprs_multiply_vec(&sprs::CsMat::from(M), vector),
+        })
+    }
+
+    /// The product of `M` with `vector` into `out`, the parallel kernels on the threads of
+    /// `pool`, the plain one in tasks the size of `policy`. They reuse the buffer of `out`;
+    /// the serial reference shares no code with them, and neither does sprs, so those allocate
+    /// a new one. The tagged kernel tags `M` first, the unchecked one validates it, failing if
+    /// it is invalid, the blocked one cuts it into blocks of [`DEFAULT_BLOCK_COLS`], and the
+    /// ELL one pads rows of up to [`DEFAULT_ELL_WIDTH`]; `bench` does any of them once
+    /// beforehand instead.
+    fn multiply_vec_into(
+        self,
+        M: &SparseMatrix<bn256::Fr>,
+        vector: &[bn256::Fr],
+        out: &mut Vec<bn256::Fr>,
+        pool: &rayon::ThreadPool,
+        policy: ChunkPolicy,
+    ) -> Result<(), MatrixError> {
+        match self {
+            Backend::Serial => *out = M.multiply_vec_serial(vector),
+            Backend::Parallel => {
+                M.in_pool(pool, |M| M.multiply_vec_chunked_into(vector, out, policy))
+            }
+            Backend::Tagged => M.in_pool(pool, |M| {
+                M.tag_coefficients().multiply_vec_into(vector, out)
+            }),
+            Backend::Unchecked => {
+                let validated = M.validated()?;
+                pool.install(|| validated.multiply_vec_into(vector, out))
+            }
+            Backend::Blocked => M.in_pool(pool, |M| {
+                M.to_blocked(DEFAULT_BLOCK_COLS)
+                    .multiply_vec_into(vector, out)
+            }),
+            Backend::Ell => M.in_pool(pool, |M| {
+                EllMatrix::from_csr(M, DEFAULT_ELL_WIDTH).multiply_vec_into(vector, out)
+            }),
+            #[cfg(feature = "sprs")]
+            Backend::Sprs => *out = sprs_multiply_vec(&sprs::CsMat::from(M), vector),
+        }
+        Ok(())
+    }
+
+    /// The product of the CSC matrix `M` with `vector`, with the kernel of the same name. Only
+    /// CSR matrices are converted for the other kernels, so those are the parallel one here.
+    fn multiply_csc(self, M: &CscMatrix<bn256::Fr>, vector: &[bn256::Fr]) -> Vec<bn256::Fr> {
+        match self {
+            Backend::Serial => M.multiply_vec_serial(vector),
+            Backend::Parallel
+            | Backend::Tagged
+            | Backend::Unchecked
+            | Backend::Blocked
+            | Backend::Ell => M.multiply_vec(vector),
+            #[cfg(feature = "sprs")]
+            Backend::Sprs => unreachable!("bench rejects --backend sprs with --layout csc"),
+        }
+    }
+}
+
+/// The matrices selected with `--matrices`, without duplicates, in `A`, `B`, `C` order.
+fn selected_matrices(global: &GlobalArgs) -> Vec<MatrixName> {
+    let mut selected = global.matrices.clone();
+    selected.sort_unstable();
+    selected.dedup();
+    selected
+}
+
+/// The matrices left out by `--matrices`.
+fn skipped_matrices(global: &GlobalArgs) -> impl Iterator<Item = MatrixName> {
+    let selected = selected_matrices(global);
+    MatrixName::ALL
+        .into_iter()
+        .filter(move |name| !selected.contains(name))
+}
+
+/// The loaded matrices of a dump, in `A`, `B`, `C` order.
+type Matrices = Vec<(MatrixName, SparseMatrix<bn256::Fr>)>;
+
+/// The products of each of [`Matrices`] with one witness, in the same order.
+type Products = Vec<(MatrixName, Vec<bn256::Fr>)>;
+
+/// Loads the matrices selected with `--matrices`, each on its own thread; the others are
+/// never deserialized. Bincode matrices go through the cache unless `--no-cache` is given.
+/// With text output, the time each one took is printed, and whether it came from the cache.
+fn load_matrices(global: &GlobalArgs, hash: &str) -> Result<Matrices, CliError> {
+    load_matrices_validated(global, hash, global.validate)
+}
+
+/// Like [`load_matrices`], validating the matrices with `validate` whether or not `--validate`
+/// was given.
+fn load_matrices_validated(
+    global: &GlobalArgs,
+    hash: &str,
+    validate: bool,
+) -> Result<Matrices, CliError> {
+    let section = &matrices_section(hash);
+    let cached = !global.no_cache && global.data_format == DataFormat::Bincode;
+    let loads: Vec<_> = std::thread::scope(|s| {
+        let handles: Vec<_> = selected_matrices(global)
+            .into_iter()
+            .map(|name| {
+                let load = move || match cached {
+                    true => read_arecibo_cached(hash, &name.label())
+                        .map(|(M, status)| (M, Some(status))),
+                    false => {
+                        read_arecibo_data_with_format(section, name.label()).map(|M| (M, None))
+                    }
+                };
+                (name, s.spawn(move || Measurement::time(name.label(), load)))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(name, handle)| {
+                let load = handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                (name, load)
+            })
+            .collect()
+    });
+
+    let mut matrices = Vec::with_capacity(loads.len());
+    let mut failures = Vec::new();
+    for (name, (load, measurement)) in loads {
+        match load {
+            Ok((M, status)) => {
+                if global.format == Format::Text {
+                    match status {
+                        Some(status) => println!(
+                            "loaded {name} in {:?} ({})",
+                            measurement.duration,
+                            status.as_str()
+                        ),
+                        None => println!("loaded {name} in {:?}", measurement.duration),
+                    }
+                }
+                matrices.push((name, M));
+            }
+            Err(err) => failures.push((name, err)),
+        }
+    }
+    if !failures.is_empty() {
+        return Err(CliError::MatrixLoad(failures));
+    }
+    if validate {
+        validate_matrices(global, &matrices)?;
+    }
+    Ok(matrices)
+}
+
+/// Checks every invariant of each of `matrices`, printing the time it took with text output.
+fn validate_matrices(global: &GlobalArgs, matrices: &Matrices) -> Result<(), CliError> {
+    let mut invalid = Vec::new();
+    for (name, M) in matrices {
+        let (result, measurement) = Measurement::time(name.label(), || M.validate());
+        match result {
+            Ok(()) if global.format == Format::Text => {
+                println!("validated {name} in {:?}", measurement.duration)
+            }
+            Ok(()) => {}
+            Err(err) => invalid.push((*name, err)),
+        }
+    }
+    match invalid.is_empty() {
+        true => Ok(()),
+        false => Err(CliError::InvalidMatrices(invalid)),
+    }
+}
+
+fn multiply_all(
+    matrices: &Matrices,
+    witness: &[bn256::Fr],
+    backend: Backend,
+) -> Result<Products, CliError> {
+    check_witness(matrices, witness)?;
+    matrices
+        .iter()
+        .map(|(name, M)| {
+            let product = backend.multiply_vec(M, witness);
+            Ok((*name, product.map_err(|err| name.invalid(err))?))
+        })
+        .collect()
+}
+
+/// Checks that `witness` has an element for every column of each of `matrices`, which the
+/// kernels assert rather than report.
+fn check_witness(matrices: &Matrices, witness: &[bn256::Fr]) -> Result<(), CliError> {
+    match matrices.iter().find(|(_, M)| M.num_cols() != witness.len()) {
+        Some((name, M)) => Err(CliError::Incompatible {
+            context: name.to_string(),
+            source: MatrixError::VectorLength {
+                cols: M.num_cols(),
+                len: witness.len(),
+            },
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Parses a field element given on the command line, in decimal if it fits a `u64`, or as
+/// `0x` hex.
+fn parse_coefficient(value: &str) -> Result<bn256::Fr, String> {
+    match value.parse::<u64>() {
+        Ok(value) => Ok(bn256::Fr::from(value)),
+        Err(_) => field_from_hex(value),
+    }
+}
+
+/// The products of `A`, `B`, and `C` with `witness` in a single pass, with
+/// [`SparseMatrix::multiply_vec_fused`]; `--fused` needs all three selected.
+fn multiply_fused(matrices: &Matrices, witness: &[bn256::Fr]) -> Result<Products, CliError> {
+    let [(a, A), (b, B), (c, C)] = &matrices[..] else {
+        return Err(CliError::InvalidArgs(
+            "--fused multiplies A, B, and C together, so needs all three selected".to_string(),
+        ));
+    };
+    let (az, bz, cz) = SparseMatrix::multiply_vec_fused(A, B, C, witness).map_err(|source| {
+        CliError::Incompatible {
+            context: "--fused".to_string(),
+            source,
+        }
+    })?;
+    Ok(vec![(*a, az), (*b, bz), (*c, cz)])
+}
+
+/// Picks the witness indices to run.
+/// With `--iterations`, every selected witness must be present; otherwise every witness
+/// from `--start` on is discovered from the section, warning about numbering gaps. Flags
+/// selecting no witness at all are a usage error.
+fn select_witnesses(global: &GlobalArgs, hash: &str) -> Result<Vec<usize>, CliError> {
+    let section = witness_section(hash);
+    let available = label_indices(&section, "_")?;
+
+    if let Some(iterations) = global.iterations {
+        let end = global.start.checked_add(iterations).ok_or_else(|| {
+            CliError::InvalidArgs(format!(
+                "--start {} with --iterations {iterations} overflows",
+                global.start
+            ))
+        })?;
+        if iterations == 0 {
+            return Err(CliError::InvalidArgs(format!(
+                "--iterations 0 selects no witnesses; the {}",
+                describe_indices(&available)
+            )));
+        }
+        let selected = global.start..end;
+        return match selected
+            .clone()
+            .find(|i| available.binary_search(i).is_err())
+        {
+            Some(index) => Err(CliError::WitnessNotFound { index, available }),
+            None => Ok(selected.collect()),
+        };
+    }
+
+    if available.is_empty() {
+        return Err(CliError::NoWitnesses(section));
+    }
+    let gaps = index_gaps(&available);
+    if !gaps.is_empty() {
+        eprintln!("warning: {section} has no witnesses {gaps:?}, skipping them");
+    }
+
+    let selected: Vec<usize> = available
+        .iter()
+        .copied()
+        .filter(|i| *i >= global.start)
+        .collect();
+    if selected.is_empty() {
+        return Err(CliError::InvalidArgs(format!(
+            "--start {} selects no witnesses; the {}",
+            global.start,
+            describe_indices(&available)
+        )));
+    }
+    Ok(selected)
+}
+
+fn read_witness(hash: &str, i: usize) -> Result<Vec<bn256::Fr>, DataError> {
+    read_arecibo_data_with_format(witness_section(hash), format!("_{i}"))
+}
+
+/// Reads the dumped products of `matrices` with witness `i`.
+fn read_expected_products(
+    hash: &str,
+    matrices: impl IntoIterator<Item = MatrixName>,
+    i: usize,
+) -> Result<Products, DataError> {
+    let section = result_section(hash);
+    matrices
+        .into_iter()
+        .map(|name| {
+            Ok((
+                name,
+                read_arecibo_data_with_format(&section, name.product_label(i))?,
+            ))
+        })
+        .collect()
+}
+
+/// A product that did not match its expected result.
+struct Failure {
+    witness: usize,
+    matrix: MatrixName,
+    diff: VectorDiff<bn256::Fr>,
+}
+
+/// Diffs the products of witness `i` against the dumped expected results,
+/// printing and returning the ones that differ.
+fn diff_products(
+    hash: &str,
+    i: usize,
+    products: &Products,
+    args: &DiffArgs,
+) -> Result<Vec<Failure>, CliError> {
+    let expected = read_expected_products(hash, products.iter().map(|(name, _)| *name), i)?;
+    Ok(diff_against(i, products, &expected, args))
+}
+
+/// Multiplies `witness`, witness `i`, by each of `matrices` in turn with `multiply`, as for
+/// `--low-memory`, once it is checked to fit them. With `verify`, each product is compared against its expected result as that
+/// is streamed from disk; either way it is dropped before the next one is computed, so at most
+/// one product and a chunk of its expected result are in memory at once. Products that differ
+/// are printed and returned.
+fn multiply_streamed(
+    hash: &str,
+    i: usize,
+    matrices: &Matrices,
+    witness: &[bn256::Fr],
+    verify: bool,
+    args: &DiffArgs,
+    mut multiply: impl FnMut(MatrixName, &SparseMatrix<bn256::Fr>) -> Result<Vec<bn256::Fr>, CliError>,
+) -> Result<Vec<Failure>, CliError> {
+    check_witness(matrices, witness)?;
+    let mut failures = Vec::new();
+    for (matrix, M) in matrices {
+        let actual = multiply(*matrix, M)?;
+        if verify {
+            failures.extend(diff_streamed(hash, i, *matrix, &actual, args)?);
+        }
+    }
+    Ok(failures)
+}
+
+/// Compares `actual`, the product of `matrix` and witness `i`, against its expected result as
+/// that is streamed from disk, printing and returning the failure if they differ.
+fn diff_streamed(
+    hash: &str,
+    i: usize,
+    matrix: MatrixName,
+    actual: &[bn256::Fr],
+    args: &DiffArgs,
+) -> Result<Option<Failure>, CliError> {
+    let label = matrix.product_label(i);
+    let (path, expected) = open_arecibo_vector::<bn256::Fr>(result_section(hash), label)?;
+    let diff = verify_stream(expected, actual, args.diff_limit)
+        .map_err(|source| DataError::from_stream(path, source))?;
+    if diff.is_equal() {
+        return Ok(None);
+    }
+    println!("witness {i} {}: {diff}", matrix.product());
+    Ok(Some(Failure {
+        witness: i,
+        matrix,
+        diff,
+    }))
+}
+
+/// Diffs the products of witness `i` against `expected`, in the same order,
+/// printing and returning the ones that differ.
+fn diff_against(
+    i: usize,
+    products: &Products,
+    expected: &Products,
+    args: &DiffArgs,
+) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    for ((matrix, actual), (_, expected)) in products.iter().zip(expected) {
+        let diff = diff_vectors(actual, expected, args.diff_limit);
+        if !diff.is_equal() {
+            println!("witness {i} {}: {diff}", matrix.product());
+            failures.push(Failure {
+                witness: i,
+                matrix: *matrix,
+                diff,
+            });
+        }
+    }
+    failures
+}
+
+/// Lists every failure and turns them into an error, if there are any.
+fn summarize_failures(failures: &[Failure]) -> Result<(), CliError> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    println!("{} products did not match:", failures.len());
+    for failure in failures {
+        println!(
+            "  witness {} {}: {} differing positions",
+            failure.witness,
+            failure.matrix.product(),
+            failure.diff.mismatches
+        );
+    }
+    Err(CliError::VerificationFailed {
+        mismatches: failures.len(),
+    })
+}
+
+/// Serializes subcommand output for `--format json`.
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).expect("output is serializable")
+}
@@ -0,0 +1,1003 @@
+//! The `bench` subcommand: time the products of every selected witness.
+
+use std::time::Duration;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use halo2curves::bn256;
+use rayon::ThreadPool;
+
+#[cfg(feature = "rkyv")]
+mod archived;
+mod batch;
+mod hashes;
+mod profile;
+mod spmm;
+mod stdin;
+mod sweep;
+mod tune;
+
+use super::{
+    check, check_witness, diff_against, diff_products, for_each_circuit, load_matrices,
+    multiply_all, multiply_fused, multiply_streamed, read_expected_products, read_path,
+    read_witness, select_witnesses, skipped_matrices, summarize_failures, Backend,
+    CircuitSelection, CliError, DiffArgs, Failure, GlobalArgs, HashArgs, Matrices, MatrixName,
+    Products, Tally,
+};
+use crate::{
+    data::{has_section, read_arecibo_labels, result_section, witness_section, Circuit},
+    report::{BenchReport, MatrixTiming},
+    sparse::{
+        sparsify, BlockedMatrix, ChunkPolicy, Coefficient, CscMatrix, EllMatrix, MatrixError,
+        Partition, TaggedMatrix, ValidatedMatrix, DEFAULT_BLOCK_COLS, DEFAULT_ELL_WIDTH,
+    },
+    timing::{self, Measurement, Work},
+    vec_ops::unpermute_into,
+    SparseMatrix,
+};
+
+/// Flags of the `bench` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct BenchArgs {
+    /// Hashes identifying the dumps to bench, as in `sparse_matrices_<HASH>`; with more than
+    /// one, the dumps are benched one after another and compared
+    #[arg(value_name = "HASH", required_unless_present = "all")]
+    pub hashes: Vec<String>,
+    /// Bench every dump under the data root, each `sparse_matrices_<HASH>` section but those
+    /// of secondary circuits, and compare them
+    #[arg(long, conflicts_with = "hashes")]
+    pub all: bool,
+    /// Circuit of each dump to bench; `both` benches the primary and the secondary one
+    #[arg(long, value_enum, default_value_t = CircuitSelection::Primary)]
+    pub circuit: CircuitSelection,
+    /// Number of times each product is repeated per witness
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub repeat: u32,
+    /// Number of untimed rounds of products to run first, cycling through the selected witnesses
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub warmup: usize,
+    /// Also write the timings as a JSON report to this file
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<Utf8PathBuf>,
+    /// Also write one CSV row per timed run to this file
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<Utf8PathBuf>,
+    /// Append to the `--csv` file instead of overwriting it, writing the header only if it is new
+    #[arg(long, requires = "csv")]
+    pub csv_append: bool,
+    /// Only time the products; do not read or compare the expected results
+    #[arg(long)]
+    pub no_verify: bool,
+    /// Skip checking that every selected witness and its expected results exist before starting
+    #[arg(long)]
+    pub no_preflight: bool,
+    /// Kernel to time
+    #[arg(long, value_enum, default_value_t = Backend::Parallel)]
+    pub backend: Backend,
+    /// Columns of each block of `--backend blocked`; 262144 by default, 8 MiB of the witness
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub block_cols: Option<u32>,
+    /// Most entries of a row that `--backend ell` pads, rather than leaving it to CSR; 8 by
+    /// default
+    #[arg(long, value_name = "N")]
+    pub ell_width: Option<usize>,
+    /// How the parallel kernel splits the rows between threads: by row count, or into runs of
+    /// about equal nonzeros
+    #[arg(long, value_enum, default_value_t = Partition::Nnz, conflicts_with_all = ["backend", "layout"])]
+    pub partition: Partition,
+    /// How many rows each task of the parallel kernel multiplies: `auto`, which follows
+    /// `--partition nnz`, `rows=N` to never split a task below N rows, or `nnz=N` for runs of
+    /// about N nonzeros
+    #[arg(long, value_name = "POLICY", value_parser = parse_chunk_policy, conflicts_with_all = ["partition", "backend", "layout"])]
+    pub chunk_policy: Option<ChunkPolicy>,
+    /// Time a few chunk policies on the first witness before the timed runs, and time every
+    /// witness with the fastest; the choice is recorded in `--output` reports
+    #[arg(long, conflicts_with_all = ["chunk_policy", "partition", "backend", "layout", "sweep_threads", "parallel_witnesses", "spmm", "low_memory", "witness_stdin"])]
+    pub tune: bool,
+    /// Layout to store the matrices in while they are multiplied; they are converted from CSR
+    /// before any timing starts
+    #[arg(long, value_enum, default_value_t = Layout::Csr, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "low_memory", "witness_stdin"])]
+    pub layout: Layout,
+    /// Time every witness once per thread count in this comma-separated list, instead of once
+    /// with `--threads`, and tabulate the speedups
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub sweep_threads: Option<Vec<usize>>,
+    /// Multiply all selected witnesses at once, timing each batch as a whole; every witness is
+    /// held in memory for the duration
+    #[arg(long, conflicts_with_all = ["sweep_threads", "output", "csv"])]
+    pub parallel_witnesses: bool,
+    /// Multiply each matrix by all selected witnesses at once, reading it once per round rather
+    /// than once per witness, and time that against the products one witness at a time; every
+    /// witness is held in memory for the duration
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "output", "csv", "backend", "layout", "low_memory", "witness_stdin"])]
+    pub spmm: bool,
+    /// Also time `AZ`, `BZ`, and `CZ` computed together in one pass over the rows, next to the
+    /// separate products, and check that the two are bit-identical
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "spmm", "backend", "layout", "low_memory", "witness_stdin"])]
+    pub fused: bool,
+    /// Also time the products with only the nonzero entries of each witness, next to the dense
+    /// products, printing the share of entries that are nonzero, and check that the two are
+    /// bit-identical
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "spmm", "backend", "layout", "low_memory", "witness_stdin"])]
+    pub sparse_witness: bool,
+    /// After the timed runs, time the products of one witness again a chunk of 4096 rows at a
+    /// time, and print the 20 slowest chunks of each matrix with a histogram of all of them;
+    /// the profile is added to any `--output` report
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "spmm", "layout", "low_memory", "witness_stdin"])]
+    pub profile_rows: bool,
+    /// Witness that `--profile-rows` profiles; the first selected one by default
+    #[arg(long, value_name = "N", requires = "profile_rows")]
+    pub profile_witness: Option<usize>,
+    /// Sort the rows of each matrix by their first column before timing starts, and time the
+    /// products of the sorted matrices, which are put back in order before they are verified
+    #[arg(long, conflicts_with_all = ["layout", "sweep_threads", "parallel_witnesses", "spmm", "low_memory", "witness_stdin"])]
+    pub reorder: bool,
+    /// Multiply the archives written by `convert` in place, without loading the matrices
+    #[cfg(feature = "rkyv")]
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses", "spmm", "fused", "sparse_witness", "profile_rows", "reorder", "partition", "chunk_policy", "tune", "backend", "layout", "warmup", "low_memory", "witness_stdin"])]
+    pub archived: bool,
+    /// Compute one product at a time and compare it as its expected result is streamed from
+    /// disk, never holding that result whole; slower, but memory stays bounded by the matrices,
+    /// one witness, and one product
+    #[arg(long, conflicts_with_all = ["sweep_threads", "parallel_witnesses"])]
+    pub low_memory: bool,
+    /// Time the products of one witness read from stdin, in bincode or raw, instead of those
+    /// of the dump; they are only verified against `--expected-dir`
+    #[arg(long, conflicts_with_all = ["all", "sweep_threads", "parallel_witnesses", "low_memory"])]
+    pub witness_stdin: bool,
+    /// Directory holding the expected products of the witness on stdin, as `AZ`, `BZ`, and `CZ`
+    #[arg(long, value_name = "DIR", requires = "witness_stdin")]
+    pub expected_dir: Option<Utf8PathBuf>,
+    #[command(flatten)]
+    pub diff: DiffArgs,
+}
+
+fn parse_chunk_policy(value: &str) -> Result<ChunkPolicy, String> {
+    let parse = |size: &str| match size.parse() {
+        Ok(0) | Err(_) => Err(format!("expected a positive size in {value}")),
+        Ok(size) => Ok(size),
+    };
+    match value.split_once('=') {
+        None if value == "auto" => Ok(ChunkPolicy::Auto),
+        Some(("rows", rows)) => Ok(ChunkPolicy::Rows(parse(rows)?)),
+        Some(("nnz", nnz)) => Ok(ChunkPolicy::Nnz(parse(nnz)?)),
+        _ => Err(format!("expected auto, rows=N, or nnz=N, not {value}")),
+    }
+}
+
+/// The chunk policy the parallel kernel runs with: `--chunk-policy`, or that of `--partition`.
+fn chunk_policy(args: &BenchArgs) -> ChunkPolicy {
+    args.chunk_policy.unwrap_or(args.partition.into())
+}
+
+/// Layouts of the matrices that `bench` can time the products in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// [`SparseMatrix`], row by row, as the matrices are loaded
+    Csr,
+    /// [`CscMatrix`], column by column, scattering into one partial product per thread
+    Csc,
+}
+
+/// The matrices of a dump in the form their products are timed in.
+enum Converted<'a> {
+    /// As loaded.
+    Csr,
+    /// Converted with `--layout csc`.
+    Csc(Vec<CscMatrix<bn256::Fr>>),
+    /// Tagged with `--backend tagged`.
+    Tagged(Vec<TaggedMatrix<'a, bn256::Fr>>),
+    /// Validated with `--backend unchecked`.
+    Validated(Vec<ValidatedMatrix<'a, bn256::Fr>>),
+    /// Cut into blocks of columns with `--backend blocked`.
+    Blocked(Vec<BlockedMatrix<bn256::Fr>>),
+    /// Padded with `--backend ell`.
+    Ell(Vec<EllMatrix<bn256::Fr>>),
+    /// Each matrix with its rows permuted by `--reorder`, and the permutation.
+    Reordered(Vec<(Vec<usize>, SparseMatrix<bn256::Fr>)>),
+}
+
+/// One matrix of [`Converted`] other than [`Converted::Csr`].
+#[derive(Clone, Copy)]
+enum ConvertedMatrix<'a> {
+    Csc(&'a CscMatrix<bn256::Fr>),
+    Tagged(&'a TaggedMatrix<'a, bn256::Fr>),
+    Validated(&'a ValidatedMatrix<'a, bn256::Fr>),
+    Blocked(&'a BlockedMatrix<bn256::Fr>),
+    Ell(&'a EllMatrix<bn256::Fr>),
+}
+
+impl Converted<'_> {
+    /// The `k`-th matrix, or `None` if the matrices are multiplied in CSR, as loaded or
+    /// reordered.
+    fn get(&self, k: usize) -> Option<ConvertedMatrix<'_>> {
+        match self {
+            Converted::Csr | Converted::Reordered(_) => None,
+            Converted::Csc(converted) => Some(ConvertedMatrix::Csc(&converted[k])),
+            Converted::Tagged(tagged) => Some(ConvertedMatrix::Tagged(&tagged[k])),
+            Converted::Validated(validated) => Some(ConvertedMatrix::Validated(&validated[k])),
+            Converted::Blocked(blocked) => Some(ConvertedMatrix::Blocked(&blocked[k])),
+            Converted::Ell(ell) => Some(ConvertedMatrix::Ell(&ell[k])),
+        }
+    }
+
+    /// The permutation of the `k`-th matrix and the matrix it gives, if `--reorder` made one.
+    fn reordered(&self, k: usize) -> Option<(&[usize], &SparseMatrix<bn256::Fr>)> {
+        match self {
+            Converted::Reordered(reordered) => {
+                let (perm, M) = &reordered[k];
+                Some((perm, M))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Converts `matrices` to `--layout`, tags, validates, blocks, or pads them for `--backend
+/// tagged`, `unchecked`, `blocked`, or `ell`, or sorts their rows for `--reorder`, printing the
+/// time it took, which is returned with the converted matrices unless they are multiplied as
+/// loaded.
+fn convert<'a>(
+    matrices: &'a Matrices,
+    args: &BenchArgs,
+) -> Result<(Converted<'a>, Option<Duration>), CliError> {
+    if args.backend == Backend::Unchecked {
+        let (results, measurement) = Measurement::time("validate", || {
+            matrices
+                .iter()
+                .map(|(name, M)| (*name, M.validated()))
+                .collect::<Vec<_>>()
+        });
+        let mut validated = Vec::with_capacity(results.len());
+        let mut invalid = Vec::new();
+        for (name, result) in results {
+            match result {
+                Ok(M) => validated.push(M),
+                Err(err) => invalid.push((name, err)),
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(CliError::InvalidMatrices(invalid));
+        }
+        println!(
+            "validated {} matrices in {:?}, not included below",
+            validated.len(),
+            measurement.duration
+        );
+        return Ok((Converted::Validated(validated), Some(measurement.duration)));
+    }
+    if args.backend == Backend::Tagged {
+        let (tagged, measurement) = Measurement::time("tag", || {
+            matrices
+                .iter()
+                .map(|(_, M)| M.tag_coefficients())
+                .collect::<Vec<_>>()
+        });
+        let nnz: usize = matrices.iter().map(|(_, M)| M.nnz()).sum();
+        let units: usize = tagged
+            .iter()
+            .map(|M| M.count(Coefficient::One) + M.count(Coefficient::MinusOne))
+            .sum();
+        println!(
+            "tagged {} matrices in {:?}, not included below; {units} of {nnz} nonzeros are 1 or -1",
+            tagged.len(),
+            measurement.duration
+        );
+        return Ok((Converted::Tagged(tagged), Some(measurement.duration)));
+    }
+    if args.backend == Backend::Blocked {
+        let block_cols = block_cols(args);
+        let (blocked, measurement) = Measurement::time("to_blocked", || {
+            matrices
+                .iter()
+                .map(|(_, M)| M.to_blocked(block_cols))
+                .collect::<Vec<_>>()
+        });
+        let blocks: usize = blocked.iter().map(|M| M.blocks().len()).sum();
+        println!(
+            "cut {} matrices into {blocks} blocks of {block_cols} columns in {:?}, not included \
+             below",
+            blocked.len(),
+            measurement.duration
+        );
+        return Ok((Converted::Blocked(blocked), Some(measurement.duration)));
+    }
+    if args.backend == Backend::Ell {
+        let width = args.ell_width.unwrap_or(DEFAULT_ELL_WIDTH);
+        let (ell, measurement) = Measurement::time("to_ell", || {
+            matrices
+                .iter()
+                .map(|(_, M)| EllMatrix::from_csr(M, width))
+                .collect::<Vec<_>>()
+        });
+        println!(
+            "padded {} matrices to ELL of at most {width} entries per row in {:?}, not included \
+             below",
+            ell.len(),
+            measurement.duration
+        );
+        for ((name, M), ell) in matrices.iter().zip(&ell) {
+            let shape = ell.shape();
+            println!(
+                "{name}: width {}, {} padding slots, {} rows and {} of {} nonzeros spilled",
+                shape.width,
+                shape.padding,
+                shape.spilled_rows,
+                shape.spilled_nnz,
+                M.nnz()
+            );
+        }
+        return Ok((Converted::Ell(ell), Some(measurement.duration)));
+    }
+    if args.reorder {
+        let (reordered, measurement) = Measurement::time("reorder", || {
+            matrices
+                .iter()
+                .map(|(_, M)| {
+                    let perm = M.locality_ordering();
+                    let reordered = M.permute_rows(&perm);
+                    (perm, reordered)
+                })
+                .collect::<Vec<_>>()
+        });
+        println!(
+            "reordered the rows of {} matrices by first column in {:?}, not included below",
+            reordered.len(),
+            measurement.duration
+        );
+        return Ok((Converted::Reordered(reordered), Some(measurement.duration)));
+    }
+    if args.layout == Layout::Csr {
+        return Ok((Converted::Csr, None));
+    }
+    let (converted, measurement) = Measurement::time("to_csc", || {
+        matrices.iter().map(|(_, M)| M.to_csc()).collect::<Vec<_>>()
+    });
+    println!(
+        "converted {} matrices to CSC in {:?}, not included below",
+        converted.len(),
+        measurement.duration
+    );
+    Ok((Converted::Csc(converted), Some(measurement.duration)))
+}
+
+/// Columns of each block of `--backend blocked`.
+fn block_cols(args: &BenchArgs) -> usize {
+    args.block_cols
+        .map_or(DEFAULT_BLOCK_COLS, |cols| cols as usize)
+}
+
+/// The products of `matrices`, or of their `converted` form, with `witness`.
+fn multiply_layout(
+    matrices: &Matrices,
+    converted: &Converted,
+    witness: &[bn256::Fr],
+    backend: Backend,
+) -> Result<Products, CliError> {
+    check_witness(matrices, witness)?;
+    Ok(match converted {
+        Converted::Csr => multiply_all(matrices, witness, backend)?,
+        Converted::Csc(converted) => matrices
+            .iter()
+            .zip(converted)
+            .map(|((name, _), M)| (*name, backend.multiply_csc(M, witness)))
+            .collect(),
+        Converted::Tagged(tagged) => matrices
+            .iter()
+            .zip(tagged)
+            .map(|((name, _), M)| (*name, M.multiply_vec(witness)))
+            .collect(),
+        Converted::Validated(validated) => matrices
+            .iter()
+            .zip(validated)
+            .map(|((name, _), M)| (*name, M.multiply_vec(witness)))
+            .collect(),
+        Converted::Blocked(blocked) => matrices
+            .iter()
+            .zip(blocked)
+            .map(|((name, _), M)| (*name, M.multiply_vec(witness)))
+            .collect(),
+        Converted::Ell(ell) => matrices
+            .iter()
+            .zip(ell)
+            .map(|((name, _), M)| (*name, M.multiply_vec(witness)))
+            .collect(),
+        Converted::Reordered(reordered) => {
+            // Each product is put back in order through the buffer of the one before.
+            let mut unpermuted = Vec::new();
+            matrices
+                .iter()
+                .zip(reordered)
+                .map(|((name, _), (perm, M))| {
+                    let product = backend.multiply_vec(M, witness);
+                    let mut product = product.map_err(|err| name.invalid(err))?;
+                    unpermute_into(&product, perm, &mut unpermuted);
+                    std::mem::swap(&mut product, &mut unpermuted);
+                    Ok((*name, product))
+                })
+                .collect::<Result<_, CliError>>()?
+        }
+    })
+}
+
+/// Runs `bench` over the dumps selected by `args`.
+pub(super) fn run(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    pool: &ThreadPool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    if args.witness_stdin && (args.hashes.len() != 1 || args.circuit == CircuitSelection::Both) {
+        return Err(CliError::InvalidArgs(
+            "--witness-stdin reads a single witness, so takes a single hash and circuit"
+                .to_string(),
+        ));
+    }
+    #[cfg(feature = "sprs")]
+    if args.backend == Backend::Sprs && args.layout != Layout::Csr {
+        return Err(CliError::InvalidArgs(
+            "--backend sprs multiplies CSR matrices, so cannot be combined with --layout csc"
+                .to_string(),
+        ));
+    }
+    if args.block_cols.is_some() && args.backend != Backend::Blocked {
+        return Err(CliError::InvalidArgs(
+            "--block-cols sets the width of the blocks of --backend blocked".to_string(),
+        ));
+    }
+    if args.ell_width.is_some() && args.backend != Backend::Ell {
+        return Err(CliError::InvalidArgs(
+            "--ell-width sets the rows padded by --backend ell".to_string(),
+        ));
+    }
+    let prepares = match args.backend {
+        Backend::Tagged => Some(("tagged", "tags")),
+        Backend::Unchecked => Some(("unchecked", "validates")),
+        Backend::Blocked => Some(("blocked", "blocks")),
+        Backend::Ell => Some(("ell", "pads")),
+        Backend::Serial | Backend::Parallel => None,
+        #[cfg(feature = "sprs")]
+        Backend::Sprs => None,
+    };
+    if let Some((backend, verb)) = prepares.filter(|_| {
+        args.layout != Layout::Csr
+            || args.sweep_threads.is_some()
+            || args.parallel_witnesses
+            || args.low_memory
+            || args.witness_stdin
+            || args.reorder
+    }) {
+        return Err(CliError::InvalidArgs(format!(
+            "--backend {backend} {verb} the CSR matrices once before timing starts, so cannot be \
+             combined with --layout csc, --sweep-threads, --parallel-witnesses, --low-memory, \
+             --witness-stdin, or --reorder"
+        )));
+    }
+    // `--all` conflicts with any hash, so a single one is a single dump.
+    let [hash] = &args.hashes[..] else {
+        return hashes::bench_hashes(global, args, pool, tally);
+    };
+    let dump = HashArgs {
+        hash: hash.clone(),
+        circuit: args.circuit,
+    };
+    for_each_circuit(
+        &dump,
+        |dump| dump,
+        tally,
+        |dump, circuit, tally| {
+            let args = circuit_outputs(args, circuit);
+            bench(global, &args, &mut Target::new(&dump.hash, pool), tally)
+        },
+    )
+}
+
+/// The arguments to bench `circuit` of a `--circuit both` run with: the circuit is added to
+/// the names of the `--output` and `--csv` files, as in `report.secondary.json`, so that the
+/// reports of the two circuits do not overwrite each other.
+fn circuit_outputs(args: &BenchArgs, circuit: Option<Circuit>) -> BenchArgs {
+    let Some(circuit) = circuit else {
+        return args.clone();
+    };
+    BenchArgs {
+        output: args
+            .output
+            .as_ref()
+            .map(|path| tagged(path, circuit.as_str())),
+        csv: args.csv.as_ref().map(|path| tagged(path, circuit.as_str())),
+        ..args.clone()
+    }
+}
+
+/// `path` with `tag` inserted before its extension, as in `report.<tag>.json`.
+fn tagged(path: &Utf8Path, tag: &str) -> Utf8PathBuf {
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => format!("{stem}.{tag}.{extension}"),
+        _ => format!("{}.{tag}", path.file_name().unwrap_or_default()),
+    };
+    path.with_file_name(name)
+}
+
+/// Whether `bench` should check its products, warning if the expected results are missing.
+fn should_verify(hash: &str, args: &BenchArgs) -> Result<bool, CliError> {
+    if args.no_verify {
+        return Ok(false);
+    }
+    let section = result_section(hash);
+    if !has_section(&section)? {
+        eprintln!("warning: {section} not found, timing without verification");
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Runs `--warmup` untimed rounds of products over `witnesses` to fault in pages and warm caches.
+/// With `verify`, every round is checked, so a broken kernel fails before any timing starts.
+fn warmup(
+    hash: &str,
+    matrices: &Matrices,
+    converted: &Converted,
+    witnesses: &[usize],
+    args: &BenchArgs,
+    verify: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let rounds = args.warmup;
+    for (round, &i) in witnesses.iter().cycle().take(rounds).enumerate() {
+        let witness = read_witness(hash, i)?;
+        let failures = if args.low_memory {
+            multiply_streamed(
+                hash,
+                i,
+                matrices,
+                &witness,
+                verify,
+                &args.diff,
+                |name, M| {
+                    let product = args.backend.multiply_vec(M, &witness);
+                    product.map_err(|err| name.invalid(err))
+                },
+            )?
+        } else {
+            let products = multiply_layout(matrices, converted, &witness, args.backend)?;
+            match verify {
+                true => diff_products(hash, i, &products, &args.diff)?,
+                false => Vec::new(),
+            }
+        };
+        tally.mismatches += failures.len();
+        summarize_failures(&failures)?;
+        println!("warmup {round}: witness {i}");
+    }
+    if rounds > 0 {
+        println!();
+    }
+    Ok(())
+}
+
+/// Multiplies `M`, or its `converted` form if given, by `witness` into `product` `--repeat`
+/// times with the `--backend` kernel on the threads of `pool`, returning a [`Measurement`]
+/// of every run, or why the kernel cannot multiply `M`.
+fn timed_multiply(
+    label: &str,
+    M: &SparseMatrix<bn256::Fr>,
+    converted: Option<ConvertedMatrix>,
+    witness: &[bn256::Fr],
+    args: &BenchArgs,
+    pool: &ThreadPool,
+    product: &mut Vec<bn256::Fr>,
+) -> Result<Vec<Measurement>, MatrixError> {
+    let work = Work::multiply_vec(M);
+    (0..args.repeat)
+        .map(|_| {
+            let (result, measurement) = Measurement::time(label, || match converted {
+                Some(ConvertedMatrix::Csc(csc)) => {
+                    *product = pool.install(|| args.backend.multiply_csc(csc, witness));
+                    Ok(())
+                }
+                Some(ConvertedMatrix::Tagged(tagged)) => {
+                    pool.install(|| tagged.multiply_vec_into(witness, product));
+                    Ok(())
+                }
+                Some(ConvertedMatrix::Validated(validated)) => {
+                    pool.install(|| validated.multiply_vec_into(witness, product));
+                    Ok(())
+                }
+                Some(ConvertedMatrix::Blocked(blocked)) => {
+                    pool.install(|| blocked.multiply_vec_into(witness, product));
+                    Ok(())
+                }
+                Some(ConvertedMatrix::Ell(ell)) => {
+                    pool.install(|| ell.multiply_vec_into(witness, product));
+                    Ok(())
+                }
+                None => {
+                    let policy = chunk_policy(args);
+                    args.backend
+                        .multiply_vec_into(M, witness, product, pool, policy)
+                }
+            });
+            result.map(|()| measurement.with_work(work))
+        })
+        .collect()
+}
+
+/// The dump that a run of `bench` times, the `--threads` pool it times the products on, and
+/// the reports it made of it so far.
+pub(super) struct Target<'a> {
+    pub hash: &'a str,
+    pub pool: &'a ThreadPool,
+    pub reports: Vec<BenchReport>,
+}
+
+impl<'a> Target<'a> {
+    pub fn new(hash: &'a str, pool: &'a ThreadPool) -> Self {
+        Self {
+            hash,
+            pool,
+            reports: Vec::new(),
+        }
+    }
+}
+
+/// Benches the dump of `target`, adding the report of the run to it if it made one.
+pub(super) fn bench(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    target: &mut Target,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    if args.witness_stdin {
+        return stdin::bench_stdin(global, args, target, tally);
+    }
+    let hash = target.hash;
+    let witnesses = select_witnesses(global, hash)?;
+    let verify = should_verify(hash, args)?;
+    if !args.no_preflight {
+        check::preflight(global, hash, &witnesses, verify)?;
+    }
+    #[cfg(feature = "rkyv")]
+    if args.archived {
+        return archived::archived(global, args, target, &witnesses, verify, tally);
+    }
+    let matrices = load_matrices(global, hash)?;
+    tally.matrices = matrices.len();
+    if args.sweep_threads.is_some() {
+        return sweep::sweep(global, args, target, &matrices, &witnesses, verify, tally);
+    }
+    let (converted, conversion) = convert(&matrices, args)?;
+    warmup(hash, &matrices, &converted, &witnesses, args, verify, tally)?;
+    if args.parallel_witnesses {
+        return batch::batch(global, args, hash, &matrices, &witnesses, verify, tally);
+    }
+    if args.spmm {
+        return spmm::spmm(global, args, hash, &matrices, &witnesses, verify, tally);
+    }
+
+    let pool = target.pool;
+    let tuned = match args.tune {
+        true => Some(BenchArgs {
+            chunk_policy: Some(tune::tune(hash, &matrices, &witnesses, args, pool)?),
+            ..args.clone()
+        }),
+        false => None,
+    };
+    let args = tuned.as_ref().unwrap_or(args);
+    let threads = pool.current_num_threads();
+    let mut report = new_report(global, args, hash, threads, &matrices);
+    report.conversion_ns = conversion.map(|duration| duration.as_nanos() as u64);
+    let mut iterations = Vec::with_capacity(witnesses.len());
+    let mut failures = Vec::new();
+    let mut decoding = Duration::ZERO;
+    // The products live across all witnesses, so the kernels write into the same buffers.
+    let mut buffers = product_buffers(&matrices);
+    let profiled = args.profile_witness.or(witnesses.first().copied());
+    for i in witnesses {
+        // Decoding is timed on its own, so that it never counts towards the products.
+        let (witness, decode) = Measurement::time("decode", || read_witness(hash, i));
+        let witness = witness?;
+        println!("decoded witness {i} in {:?}", decode.duration);
+        decoding += decode.duration;
+
+        let mut measurements = Vec::new();
+        let streamed = if args.low_memory {
+            multiply_streamed(
+                hash,
+                i,
+                &matrices,
+                &witness,
+                verify,
+                &args.diff,
+                |name, M| {
+                    let mut product = Vec::new();
+                    let label = name.product();
+                    let runs = timed_multiply(label, M, None, &witness, args, pool, &mut product);
+                    measurements.extend(runs.map_err(|err| name.invalid(err))?);
+                    Ok(product)
+                },
+            )?
+        } else {
+            time_products(
+                &matrices,
+                &converted,
+                &witness,
+                args,
+                pool,
+                &mut measurements,
+                &mut buffers,
+            )?;
+            let products = &buffers.products;
+            let mut failed = match args.fused {
+                true => time_fused(i, &matrices, &witness, args, products, &mut measurements)?,
+                false => Vec::new(),
+            };
+            if args.sparse_witness {
+                let measurements = &mut measurements;
+                failed.extend(time_sparse(
+                    i,
+                    &matrices,
+                    &witness,
+                    args,
+                    pool,
+                    products,
+                    measurements,
+                ));
+            }
+            failed
+        };
+        println!("{}", timing::iteration_report(i, &measurements));
+        record(&mut report, &matrices, i, threads, &measurements);
+        iterations.push(measurements);
+
+        let failed = match verify && !args.low_memory {
+            true => {
+                let mut failed = diff_products(hash, i, &buffers.products, &args.diff)?;
+                failed.extend(streamed);
+                failed
+            }
+            false => streamed,
+        };
+        tally.mismatches += failed.len();
+        failures.extend(failed);
+        tally.witnesses += 1;
+    }
+    print!("{}", timing::summary_report(&iterations));
+    println!(
+        "decoding {} witnesses took: {decoding:?}, not included above",
+        iterations.len()
+    );
+    print_skipped(global);
+
+    if let (true, Some(i)) = (args.profile_rows, profiled) {
+        let failed = profile::profile_rows(hash, i, &matrices, &mut report, verify, &args.diff)?;
+        tally.mismatches += failed.len();
+        failures.extend(failed);
+    }
+    write_reports(args, report, target)?;
+    summarize_failures(&failures)
+}
+
+/// A selected witness with its expected products, read before any timing starts.
+struct Input {
+    index: usize,
+    witness: Vec<bn256::Fr>,
+    /// `None` when not verifying.
+    expected: Option<Products>,
+}
+
+/// Reads every one of `witnesses` in parallel, and with `verify` their expected products by
+/// `names`.
+fn load_inputs(
+    hash: &str,
+    names: &[MatrixName],
+    witnesses: &[usize],
+    verify: bool,
+) -> Result<Vec<Input>, CliError> {
+    let names = || names.iter().copied();
+    let labels: Vec<_> = witnesses.iter().map(|i| format!("_{i}")).collect();
+    let loaded: Vec<Vec<bn256::Fr>> = read_arecibo_labels(witness_section(hash), &labels)?;
+    witnesses
+        .iter()
+        .zip(loaded)
+        .map(|(&index, witness)| {
+            Ok(Input {
+                index,
+                witness,
+                expected: match verify {
+                    true => Some(read_expected_products(hash, names(), index)?),
+                    false => None,
+                },
+            })
+        })
+        .collect()
+}
+
+/// The buffers that [`time_products`] fills witness after witness.
+struct ProductBuffers {
+    products: Products,
+    /// Where the product of a reordered matrix is put back in order, to be swapped with it.
+    unpermuted: Vec<bn256::Fr>,
+}
+
+/// Empty products of `matrices`, for [`time_products`] to fill witness after witness.
+fn product_buffers(matrices: &Matrices) -> ProductBuffers {
+    ProductBuffers {
+        products: matrices
+            .iter()
+            .map(|(name, _)| (*name, Vec::new()))
+            .collect(),
+        unpermuted: Vec::new(),
+    }
+}
+
+/// Times the product of each matrix, or of its `converted` form, with `witness` on the threads
+/// of `pool`, into the `buffers` from [`product_buffers`], so that no product is allocated once
+/// they have grown to size. The products of reordered matrices are put back in order after
+/// they are timed.
+fn time_products(
+    matrices: &Matrices,
+    converted: &Converted,
+    witness: &[bn256::Fr],
+    args: &BenchArgs,
+    pool: &ThreadPool,
+    measurements: &mut Vec<Measurement>,
+    buffers: &mut ProductBuffers,
+) -> Result<(), CliError> {
+    check_witness(matrices, witness)?;
+    let products = &mut buffers.products;
+    for (k, ((name, M), (_, product))) in matrices.iter().zip(products).enumerate() {
+        let reordered = converted.reordered(k);
+        let M = reordered.map_or(M, |(_, M)| M);
+        let converted = converted.get(k);
+        let runs = timed_multiply(name.product(), M, converted, witness, args, pool, product);
+        measurements.extend(runs.map_err(|err| name.invalid(err))?);
+        if let Some((perm, _)) = reordered {
+            unpermute_into(product, perm, &mut buffers.unpermuted);
+            std::mem::swap(product, &mut buffers.unpermuted);
+        }
+    }
+    Ok(())
+}
+
+/// Times the products of `A`, `B`, and `C` with witness `i` in one pass, `--repeat` times, as
+/// `fused`, printing how long they took next to the separate `products` measured so far, and
+/// returns where the fused products differ from those.
+fn time_fused(
+    i: usize,
+    matrices: &Matrices,
+    witness: &[bn256::Fr],
+    args: &BenchArgs,
+    products: &Products,
+    measurements: &mut Vec<Measurement>,
+) -> Result<Vec<Failure>, CliError> {
+    let separate: Duration = measurements.iter().map(|m| m.duration).sum();
+    let work = matrices
+        .iter()
+        .fold(Work { nnz: 0, bytes: 0 }, |work, (_, M)| {
+            let product = Work::multiply_vec(M);
+            Work {
+                nnz: work.nnz + product.nnz,
+                bytes: work.bytes + product.bytes,
+            }
+        });
+    let mut fused = Vec::new();
+    let mut total = Duration::ZERO;
+    for _ in 0..args.repeat {
+        let (result, measurement) =
+            Measurement::time("fused", || multiply_fused(matrices, witness));
+        total += measurement.duration;
+        measurements.push(measurement.with_work(work));
+        fused = result?;
+    }
+    println!(
+        "fused: {:.2}x the separate products",
+        separate.as_secs_f64() / total.as_secs_f64()
+    );
+    Ok(diff_against(i, &fused, products, &args.diff))
+}
+
+/// Times the product of each of `matrices` with the nonzero entries of witness `i` on the
+/// threads of `pool`, `--repeat` times, as `AZ sparse` and so on, printing the share of entries
+/// that are nonzero and how each product compares with the dense one in `measurements`, and
+/// returns where the sparse products differ from the dense `products`.
+fn time_sparse(
+    i: usize,
+    matrices: &Matrices,
+    witness: &[bn256::Fr],
+    args: &BenchArgs,
+    pool: &ThreadPool,
+    products: &Products,
+    measurements: &mut Vec<Measurement>,
+) -> Vec<Failure> {
+    let entries = sparsify(witness);
+    let density = entries.len() as f64 / witness.len().max(1) as f64;
+    println!(
+        "witness {i}: {} of {} entries are nonzero, {:.1}%",
+        entries.len(),
+        witness.len(),
+        100.0 * density
+    );
+    let mut sparse = Products::new();
+    for (name, M) in matrices.iter() {
+        let dense: Duration = measurements
+            .iter()
+            .filter(|m| m.label == name.product())
+            .map(|m| m.duration)
+            .sum();
+        let label = format!("{} sparse", name.product());
+        let mut product = Vec::new();
+        let mut total = Duration::ZERO;
+        for _ in 0..args.repeat {
+            let (result, measurement) = Measurement::time(&label, || {
+                M.in_pool(pool, |M| M.multiply_sparse_vec(&entries))
+            });
+            total += measurement.duration;
+            measurements.push(measurement);
+            product = result;
+        }
+        println!(
+            "{label}: {:.2}x the dense product",
+            dense.as_secs_f64() / total.as_secs_f64()
+        );
+        sparse.push((*name, product));
+    }
+    diff_against(i, &sparse, products, &args.diff)
+}
+
+/// An empty report with an entry for each matrix.
+fn new_report(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    hash: &str,
+    threads: usize,
+    matrices: &Matrices,
+) -> BenchReport {
+    let mut report = BenchReport::new(hash, threads);
+    report.read_path = read_path(global);
+    report.chunk_policy = chunk_policy(args);
+    report.matrices = matrices
+        .iter()
+        .map(|(name, M)| MatrixTiming::new(name.as_str(), M))
+        .collect();
+    report
+}
+
+/// Records the `measurements` of witness `i`, made with `threads` threads, in the entries
+/// [`new_report`] created for `matrices`.
+fn record(
+    report: &mut BenchReport,
+    matrices: &Matrices,
+    i: usize,
+    threads: usize,
+    measurements: &[Measurement],
+) {
+    for (entry, (name, _)) in report.matrices.iter_mut().zip(matrices) {
+        entry.record(i, threads, name.product(), measurements);
+    }
+}
+
+fn print_skipped(global: &GlobalArgs) {
+    for name in skipped_matrices(global) {
+        println!("{name}: skipped, not selected by --matrices");
+    }
+}
+
+/// Writes the `--output` and `--csv` reports, if requested, then adds `report` to `target`.
+fn write_reports(
+    args: &BenchArgs,
+    report: BenchReport,
+    target: &mut Target,
+) -> Result<(), CliError> {
+    if let Some(path) = &args.output {
+        report
+            .write_json(path)
+            .map_err(|source| CliError::report(path, source))?;
+    }
+    if let Some(path) = &args.csv {
+        report
+            .write_csv_file(path, args.csv_append)
+            .map_err(|source| CliError::report(path, source))?;
+    }
+    target.reports.push(report);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! `bench --archived`: time products read straight from the archives that `convert` wrote.
+
+use halo2curves::bn256;
+
+use super::{load_inputs, print_skipped, write_reports, BenchArgs, Target};
+use crate::{
+    archive::{open_arecibo_archive, MappedMatrix},
+    cli::{
+        diff_against, selected_matrices, summarize_failures, CliError, GlobalArgs, MatrixName,
+        Tally,
+    },
+    data::{matrices_section, ReadPath},
+    report::{BenchReport, MatrixTiming},
+    sparse::{multiply_view, CsrView},
+    timing::{self, Measurement},
+};
+
+pub(super) fn archived(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    target: &mut Target,
+    witnesses: &[usize],
+    verify: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = target.hash;
+    let section = matrices_section(hash);
+    let mut archives: Vec<(MatrixName, MappedMatrix<bn256::Fr>)> = Vec::new();
+    for name in selected_matrices(global) {
+        let (archive, measurement) = Measurement::time(name.label(), || {
+            open_arecibo_archive(&section, name.label())
+        });
+        println!("mapped {name} in {:?}", measurement.duration);
+        archives.push((name, archive?));
+    }
+    tally.matrices = archives.len();
+
+    let threads = rayon::current_num_threads();
+    let mut report = BenchReport::new(hash, threads);
+    report.read_path = ReadPath::Mmap;
+    for (name, archive) in &archives {
+        let view: &dyn CsrView<bn256::Fr> = archive.view();
+        report.matrices.push(MatrixTiming::new(name.as_str(), view));
+    }
+
+    // The archive stands in for the matrix, so its products go in the matrix's slot.
+    let names: Vec<_> = archives.iter().map(|(name, _)| *name).collect();
+    let inputs = load_inputs(hash, &names, witnesses, verify)?;
+    let mut iterations = Vec::with_capacity(inputs.len());
+    let mut failures = Vec::new();
+    for input in &inputs {
+        let mut measurements = Vec::new();
+        let mut products = Vec::with_capacity(archives.len());
+        for (name, archive) in &archives {
+            let mut product = Vec::new();
+            for _ in 0..args.repeat {
+                let (result, measurement) = Measurement::time(name.product(), || {
+                    multiply_view::<bn256::Fr, _>(archive.view(), &input.witness)
+                });
+                measurements.push(measurement);
+                product = result;
+            }
+            products.push((*name, product));
+        }
+        println!("{}", timing::iteration_report(input.index, &measurements));
+        for (entry, name) in report.matrices.iter_mut().zip(&names) {
+            entry.record(input.index, threads, name.product(), &measurements);
+        }
+        iterations.push(measurements);
+
+        if let Some(expected) = &input.expected {
+            failures.extend(diff_against(input.index, &products, expected, &args.diff));
+        }
+    }
+    tally.witnesses = inputs.len();
+    tally.mismatches = failures.len();
+    print!("{}", timing::summary_report(&iterations));
+    print_skipped(global);
+
+    write_reports(args, report, target)?;
+    summarize_failures(&failures)
+}
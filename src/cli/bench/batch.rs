@@ -0,0 +1,80 @@
+//! `bench --parallel-witnesses`: push every witness through at once and time the batch.
+
+use std::{mem::size_of, time::Instant};
+
+use halo2curves::bn256;
+
+use super::{load_inputs, print_skipped, BenchArgs};
+use crate::{
+    cli::{
+        diff_against, format_size, multiply_all, summarize_failures, CliError, GlobalArgs,
+        Matrices, Products, Tally,
+    },
+    statistics::Summary,
+};
+
+pub(super) fn batch(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    hash: &str,
+    matrices: &Matrices,
+    witnesses: &[usize],
+    verify: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    println!(
+        "holding {} witnesses and their products in memory: about {}",
+        witnesses.len(),
+        format_size(resident_bytes(matrices, witnesses.len(), verify) as u64)
+    );
+    let names: Vec<_> = matrices.iter().map(|(name, _)| *name).collect();
+    let inputs = load_inputs(hash, &names, witnesses, verify)?;
+
+    // Each witness owns a slot, so products land in witness order whatever order they finish in.
+    let mut slots: Vec<Option<Result<Products, CliError>>> = inputs.iter().map(|_| None).collect();
+    let mut durations = Vec::with_capacity(args.repeat as usize);
+    for round in 0..args.repeat {
+        let start = Instant::now();
+        rayon::scope(|s| {
+            for (slot, input) in slots.iter_mut().zip(&inputs) {
+                s.spawn(move |_| {
+                    *slot = Some(multiply_all(matrices, &input.witness, args.backend))
+                });
+            }
+        });
+        let duration = start.elapsed();
+        println!(
+            "batch {round}: {} witnesses took {duration:?} ({:.2} witnesses/s)",
+            inputs.len(),
+            inputs.len() as f64 / duration.as_secs_f64()
+        );
+        durations.push(duration);
+    }
+    if let (Some(summary), true) = (Summary::from_durations(&durations), durations.len() > 1) {
+        println!(
+            "batch overall: {summary} ({:.2} witnesses/s at the median)",
+            inputs.len() as f64 / summary.median.as_secs_f64()
+        );
+    }
+    print_skipped(global);
+
+    let mut failures = Vec::new();
+    for (input, slot) in inputs.iter().zip(slots) {
+        let products = slot.expect("every spawned multiplication has finished")?;
+        if let Some(expected) = &input.expected {
+            failures.extend(diff_against(input.index, &products, expected, &args.diff));
+        }
+    }
+    tally.witnesses = inputs.len();
+    tally.mismatches = failures.len();
+    summarize_failures(&failures)
+}
+
+/// Estimated bytes of `witnesses` witnesses resident at once with their products under
+/// `matrices`, and with `verify` their expected products too.
+pub(super) fn resident_bytes(matrices: &Matrices, witnesses: usize, verify: bool) -> usize {
+    let cols = matrices.iter().map(|(_, M)| M.cols).max().unwrap_or(0);
+    let rows: usize = matrices.iter().map(|(_, M)| M.num_rows()).sum();
+    let products = if verify { 2 * rows } else { rows };
+    witnesses * (cols + products) * size_of::<bn256::Fr>()
+}
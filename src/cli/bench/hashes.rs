@@ -0,0 +1,149 @@
+//! `bench HASH1 HASH2 ...` and `bench --all`: bench several dumps in one run and compare them.
+
+use rayon::ThreadPool;
+
+use super::{bench, circuit_outputs, tagged, BenchArgs, Target};
+use crate::{
+    cli::{
+        error_message, for_each_circuit, CliError, GlobalArgs, HashArgs, HashResult, Status, Tally,
+    },
+    data::{list_sections, matrices_hash, Circuit},
+    report::{HashReport, MultiBenchReport},
+};
+
+/// Benches every dump selected by `args` one after another on the same pool, then compares
+/// them in a table. A dump that fails is reported and the rest are still benched; the first
+/// failure is returned once all have run.
+pub(super) fn bench_hashes(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    pool: &ThreadPool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hashes = match args.all {
+        true => all_hashes()?,
+        false => args.hashes.clone(),
+    };
+    if hashes.is_empty() {
+        return Err(CliError::InvalidArgs(
+            "--all found no sparse_matrices_* sections under the data root".to_string(),
+        ));
+    }
+
+    let mut report = MultiBenchReport::new();
+    let mut failure = None;
+    for hash in hashes {
+        println!("=== {hash} ===");
+        // The JSON report of every dump goes into the one written below, but each dump gets
+        // its own CSV, as the rows do not say which dump they belong to.
+        let dump_args = BenchArgs {
+            output: None,
+            csv: args.csv.as_ref().map(|path| tagged(path, &hash)),
+            ..args.clone()
+        };
+        let dump = HashArgs {
+            hash: hash.clone(),
+            circuit: args.circuit,
+        };
+        let mut reports = Vec::new();
+        let mut dump_tally = Tally::default();
+        let outcome = for_each_circuit(
+            &dump,
+            |dump| dump,
+            &mut dump_tally,
+            |dump, circuit, tally| {
+                let mut target = Target::new(&dump.hash, pool);
+                let outcome = bench(
+                    global,
+                    &circuit_outputs(&dump_args, circuit),
+                    &mut target,
+                    tally,
+                );
+                reports.append(&mut target.reports);
+                outcome
+            },
+        );
+
+        let result = HashResult {
+            hash: hash.clone(),
+            result: outcome
+                .as_ref()
+                .map_or_else(CliError::status, |()| Status::Ok),
+            tally: dump_tally,
+            error: outcome.as_ref().err().map(error_message),
+        };
+        println!("{}\n", result.render_text());
+        tally.add(&result.tally);
+        report.hashes.push(HashReport {
+            hash,
+            reports,
+            error: result.error.clone(),
+        });
+        tally.hashes.push(result);
+        match outcome {
+            Err(err) if failure.is_none() => failure = Some(err),
+            Err(err) => eprintln!("error: {}", error_message(&err)),
+            Ok(()) => {}
+        }
+    }
+    print!("{}", comparison_table(&report));
+
+    if let Some(path) = &args.output {
+        report
+            .write_json(path)
+            .map_err(|source| CliError::report(path, source))?;
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+/// The hash of every dump under the data root; secondary circuits are left to `--circuit`.
+fn all_hashes() -> Result<Vec<String>, CliError> {
+    Ok(list_sections()?
+        .iter()
+        .filter_map(|section| matrices_hash(&section.name))
+        .filter(|hash| Circuit::of(hash).0 == Circuit::Primary)
+        .map(str::to_string)
+        .collect())
+}
+
+/// Tabulates the shape of every matrix of every dump in `report` with the median of its
+/// products. Dumps without timings get a line saying why.
+fn comparison_table(report: &MultiBenchReport) -> String {
+    let width = report
+        .hashes
+        .iter()
+        .flat_map(|dump| {
+            dump.reports
+                .iter()
+                .map(|r| r.hash.len())
+                .chain([dump.hash.len()])
+        })
+        .max()
+        .unwrap_or(0)
+        .max("hash".len());
+    let mut table = format!(
+        "{:<width$}  {:<6} {:>10} {:>10} {:>14}\n",
+        "hash", "matrix", "rows", "nnz", "median"
+    );
+    for dump in &report.hashes {
+        if dump.reports.is_empty() {
+            let why = match dump.error {
+                Some(_) => "failed",
+                None => "no timings per matrix",
+            };
+            table += &format!("{:<width$}  {why}\n", dump.hash);
+        }
+        for circuit in &dump.reports {
+            for matrix in &circuit.matrices {
+                let median = matrix
+                    .median()
+                    .map_or_else(|| "-".to_string(), |median| format!("{median:?}"));
+                table += &format!(
+                    "{:<width$}  {:<6} {:>10} {:>10} {median:>14}\n",
+                    circuit.hash, matrix.name, matrix.rows, matrix.nnz
+                );
+            }
+        }
+    }
+    table
+}
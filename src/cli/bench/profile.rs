@@ -0,0 +1,119 @@
+//! `bench --profile-rows`: time the products of one witness a chunk of rows at a time, to tell
+//! a few slow rows apart from a cost spread over all of them.
+
+use std::time::{Duration, Instant};
+
+use ff::Field;
+use halo2curves::bn256;
+use rayon::prelude::*;
+
+use crate::{
+    cli::{diff_products, read_witness, CliError, DiffArgs, Failure, Matrices, Products},
+    report::{BenchReport, ChunkTiming, RowProfile},
+    SparseMatrix,
+};
+
+/// Rows of each timed chunk.
+const PROFILE_CHUNK_ROWS: usize = 4096;
+
+/// Number of the slowest chunks printed and kept in the report.
+const SLOWEST_CHUNKS: usize = 20;
+
+/// Widest bar of the histogram, in characters.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Times the products of witness `i` with `matrices` chunk by chunk, printing the slowest
+/// chunks and a histogram of each and adding them to the entries of `report`, and with
+/// `verify` returns where the products differ from the expected results.
+pub(super) fn profile_rows(
+    hash: &str,
+    i: usize,
+    matrices: &Matrices,
+    report: &mut BenchReport,
+    verify: bool,
+    diff: &DiffArgs,
+) -> Result<Vec<Failure>, CliError> {
+    let witness = read_witness(hash, i)?;
+    println!("\nprofiling witness {i} in chunks of {PROFILE_CHUNK_ROWS} rows");
+    let mut products = Products::new();
+    for ((name, M), entry) in matrices.iter().zip(&mut report.matrices) {
+        let (product, chunks) = time_chunks(M, &witness);
+        let profile = RowProfile::new(i, PROFILE_CHUNK_ROWS, chunks, SLOWEST_CHUNKS);
+        print_profile(name.product(), &profile);
+        entry.row_profile = Some(profile);
+        products.push((*name, product));
+    }
+    match verify {
+        true => diff_products(hash, i, &products, diff),
+        false => Ok(Vec::new()),
+    }
+}
+
+/// The product of `M` and `witness`, with the time each chunk of rows took. The chunks run in
+/// parallel, each timed on the thread that computes it.
+fn time_chunks(
+    M: &SparseMatrix<bn256::Fr>,
+    witness: &[bn256::Fr],
+) -> (Vec<bn256::Fr>, Vec<ChunkTiming>) {
+    let mut product = vec![bn256::Fr::ZERO; M.num_rows()];
+    let chunks = product
+        .par_chunks_mut(PROFILE_CHUNK_ROWS)
+        .enumerate()
+        .map(|(k, out)| {
+            let rows = k * PROFILE_CHUNK_ROWS..k * PROFILE_CHUNK_ROWS + out.len();
+            let start = Instant::now();
+            M.multiply_rows_into(rows.clone(), witness, out);
+            let duration = start.elapsed();
+            ChunkTiming {
+                start_row: rows.start,
+                end_row: rows.end,
+                nnz: M.indptr[rows.end] - M.indptr[rows.start],
+                duration_ns: duration.as_nanos() as u64,
+                thread: rayon::current_thread_index().unwrap_or(0),
+            }
+        })
+        .collect();
+    (product, chunks)
+}
+
+/// Prints the slowest chunks of `profile`, the histogram of all of them, and the work of each
+/// thread.
+fn print_profile(product: &str, profile: &RowProfile) {
+    println!(
+        "{product}: {} chunks took {:?} in all",
+        profile.chunks,
+        Duration::from_nanos(profile.total_ns)
+    );
+    println!("  slowest chunks:");
+    for chunk in &profile.slowest {
+        println!(
+            "    rows {}..{}: {} nnz, {:?}",
+            chunk.start_row,
+            chunk.end_row,
+            chunk.nnz,
+            Duration::from_nanos(chunk.duration_ns)
+        );
+    }
+    println!("  chunk times:");
+    let most = profile.histogram.iter().map(|bin| bin.chunks).max();
+    for bin in &profile.histogram {
+        let width = bin.chunks * HISTOGRAM_WIDTH / most.unwrap_or(1).max(1);
+        println!(
+            "    >= {:>10}: {:>6} {}",
+            format!("{:?}", Duration::from_nanos(bin.min_ns)),
+            bin.chunks,
+            "#".repeat(width)
+        );
+    }
+    println!("  work per thread:");
+    for work in &profile.threads {
+        println!(
+            "    thread {}: {} chunks, {} rows, {} nnz, busy {:?}",
+            work.thread,
+            work.chunks,
+            work.rows,
+            work.nnz,
+            Duration::from_nanos(work.busy_ns)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! `bench --spmm`: multiply each matrix by every witness at once and time it against the
+//! products one witness at a time.
+
+use std::time::{Duration, Instant};
+
+use halo2curves::bn256;
+
+use super::{batch::resident_bytes, load_inputs, print_skipped, BenchArgs, Input};
+use crate::{
+    cli::{
+        diff_against, format_size, multiply_all, summarize_failures, Backend, CliError, Failure,
+        GlobalArgs, Matrices, MatrixName, Products, Tally,
+    },
+    statistics::Summary,
+};
+
+pub(super) fn spmm(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    hash: &str,
+    matrices: &Matrices,
+    witnesses: &[usize],
+    verify: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    println!(
+        "holding {} witnesses and their products in memory: about {}",
+        witnesses.len(),
+        format_size(resident_bytes(matrices, witnesses.len(), verify) as u64)
+    );
+    let names: Vec<_> = matrices.iter().map(|(name, _)| *name).collect();
+    let inputs = load_inputs(hash, &names, witnesses, verify)?;
+    let vectors: Vec<_> = inputs.iter().map(|input| input.witness.clone()).collect();
+
+    let (mut singles, mut multiples) = (Vec::new(), Vec::new());
+    let mut products = Vec::new();
+    for round in 0..args.repeat {
+        let start = Instant::now();
+        for input in &inputs {
+            multiply_all(matrices, &input.witness, Backend::Parallel)?;
+        }
+        let single = start.elapsed();
+
+        let start = Instant::now();
+        let outputs: Vec<_> = matrices
+            .iter()
+            .map(|(_, M)| M.multiply_mat(&vectors))
+            .collect();
+        let multiple = start.elapsed();
+        println!(
+            "round {round}: one at a time took {single:?} ({}), spmm took {multiple:?} ({}, {:.2}x)",
+            throughput(inputs.len(), single),
+            throughput(inputs.len(), multiple),
+            single.as_secs_f64() / multiple.as_secs_f64()
+        );
+        singles.push(single);
+        multiples.push(multiple);
+        products = outputs;
+    }
+    if let (Some(single), Some(multiple), true) = (
+        Summary::from_durations(&singles),
+        Summary::from_durations(&multiples),
+        args.repeat > 1,
+    ) {
+        println!(
+            "one at a time overall: {single} ({})",
+            throughput(inputs.len(), single.median)
+        );
+        println!(
+            "spmm overall: {multiple} ({}, {:.2}x at the median)",
+            throughput(inputs.len(), multiple.median),
+            single.median.as_secs_f64() / multiple.median.as_secs_f64()
+        );
+    }
+    print_skipped(global);
+
+    let failures = verify_products(&inputs, &names, products, args);
+    tally.witnesses = inputs.len();
+    tally.mismatches = failures.len();
+    summarize_failures(&failures)
+}
+
+/// Witnesses a second that `witnesses` witnesses in `duration` come to.
+fn throughput(witnesses: usize, duration: Duration) -> String {
+    format!(
+        "{:.2} witnesses/s",
+        witnesses as f64 / duration.as_secs_f64()
+    )
+}
+
+/// Compares the `products` of every matrix with every input, as [`SparseMatrix::multiply_mat`]
+/// gives them, with the expected products of each input that has them.
+///
+/// [`SparseMatrix::multiply_mat`]: crate::SparseMatrix::multiply_mat
+fn verify_products(
+    inputs: &[Input],
+    names: &[MatrixName],
+    products: Vec<Vec<Vec<bn256::Fr>>>,
+    args: &BenchArgs,
+) -> Vec<Failure> {
+    let mut columns: Vec<_> = products.into_iter().map(Vec::into_iter).collect();
+    let mut failures = Vec::new();
+    for input in inputs {
+        let products: Products = names
+            .iter()
+            .zip(&mut columns)
+            .map(|(name, column)| (*name, column.next().expect("a product per input")))
+            .collect();
+        if let Some(expected) = &input.expected {
+            failures.extend(diff_against(input.index, &products, expected, &args.diff));
+        }
+    }
+    failures
+}
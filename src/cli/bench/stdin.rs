@@ -0,0 +1,111 @@
+//! `bench --witness-stdin`: time the products of one witness piped in on stdin.
+
+use std::{io, mem::size_of};
+
+use camino::Utf8Path;
+use ff::{Field, PrimeField};
+use halo2curves::bn256;
+
+use super::{
+    new_report, print_skipped, product_buffers, record, time_products, write_reports, BenchArgs,
+    Converted, Target,
+};
+use crate::{
+    cli::{
+        diff_against, load_matrices, multiply_all, summarize_failures, CliError, GlobalArgs,
+        Matrices, Products, Tally,
+    },
+    data::{raw::ARRAY_HEADER_BYTES, read_data_file, read_stream},
+    timing::{self, Measurement},
+};
+
+/// What the witness on stdin is called in errors.
+const STDIN_NAME: &str = "<stdin>";
+
+/// Index the witness on stdin is reported under, as if it were `_0`.
+const STDIN_WITNESS: usize = 0;
+
+pub(super) fn bench_stdin(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    target: &mut Target,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let matrices = load_matrices(global, target.hash)?;
+    tally.matrices = matrices.len();
+    let cols = matrices.iter().map(|(_, M)| M.cols).max().unwrap_or(0);
+    let (witness, decode) = Measurement::time("decode", || {
+        read_stream::<Vec<bn256::Fr>>(io::stdin().lock(), STDIN_NAME, witness_limit(cols))
+    });
+    let witness = witness?;
+    println!("decoded the witness on stdin in {:?}", decode.duration);
+    if let Some((name, M)) = matrices.iter().find(|(_, M)| M.cols != witness.len()) {
+        return Err(CliError::InvalidArgs(format!(
+            "the witness on stdin has {} elements, but {name} has {} columns",
+            witness.len(),
+            M.cols
+        )));
+    }
+    let expected = match &args.expected_dir {
+        Some(dir) => Some(read_expected_dir(dir, &matrices)?),
+        None => None,
+    };
+
+    for round in 0..args.warmup {
+        multiply_all(&matrices, &witness, args.backend)?;
+        println!("warmup {round}: witness on stdin");
+    }
+    if args.warmup > 0 {
+        println!();
+    }
+
+    let threads = target.pool.current_num_threads();
+    let mut report = new_report(global, args, target.hash, threads, &matrices);
+    let mut measurements = Vec::new();
+    let mut buffers = product_buffers(&matrices);
+    time_products(
+        &matrices,
+        &Converted::Csr,
+        &witness,
+        args,
+        target.pool,
+        &mut measurements,
+        &mut buffers,
+    )?;
+    println!("{}", timing::iteration_report(STDIN_WITNESS, &measurements));
+    record(
+        &mut report,
+        &matrices,
+        STDIN_WITNESS,
+        threads,
+        &measurements,
+    );
+    print_skipped(global);
+    tally.witnesses = 1;
+    write_reports(args, report, target)?;
+
+    let Some(expected) = expected else {
+        println!("no --expected-dir given, so the products were not verified");
+        return Ok(());
+    };
+    let failures = diff_against(STDIN_WITNESS, &buffers.products, &expected, &args.diff);
+    tally.mismatches = failures.len();
+    summarize_failures(&failures)
+}
+
+/// The most bytes a witness of `cols` elements decodes from in either format: a length
+/// prefix or array header, then every element at the larger of its bincode and raw sizes.
+/// Decoding stdin stops there, as decoding a file stops at its end.
+fn witness_limit(cols: usize) -> u64 {
+    let bincode = bincode::serialized_size(&bn256::Fr::ZERO).expect("field elements serialize");
+    let raw = size_of::<<bn256::Fr as PrimeField>::Repr>() as u64;
+    ARRAY_HEADER_BYTES as u64 + cols as u64 * bincode.max(raw)
+}
+
+/// Reads the expected products of `matrices` from the files in `dir` named after them.
+fn read_expected_dir(dir: &Utf8Path, matrices: &Matrices) -> Result<Products, CliError> {
+    matrices
+        .iter()
+        .map(|(name, _)| Ok((*name, read_data_file(dir.join(name.product()))?)))
+        .collect()
+}
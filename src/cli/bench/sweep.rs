@@ -0,0 +1,125 @@
+//! `bench --sweep-threads`: time the same witnesses on pools of increasing size.
+
+use std::time::Duration;
+
+use super::{
+    load_inputs, new_report, print_skipped, product_buffers, record, time_products, write_reports,
+    BenchArgs, Converted, Target,
+};
+use crate::{
+    cli::{
+        build_pool, diff_against, multiply_all, summarize_failures, CliError, GlobalArgs, Matrices,
+        MatrixName, Tally,
+    },
+    statistics::Summary,
+    timing::{self, Measurement},
+};
+
+pub(super) fn sweep(
+    global: &GlobalArgs,
+    args: &BenchArgs,
+    target: &mut Target,
+    matrices: &Matrices,
+    witnesses: &[usize],
+    verify: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = target.hash;
+    let counts = args.sweep_threads.as_deref().unwrap_or_default();
+    let names = || matrices.iter().map(|(name, _)| *name);
+    let inputs = load_inputs(hash, &names().collect::<Vec<_>>(), witnesses, verify)?;
+
+    let mut pools = Vec::with_capacity(counts.len());
+    for &count in counts {
+        pools.push(build_pool(count)?);
+    }
+    let max_threads = pools.iter().map(|pool| pool.current_num_threads()).max();
+    let mut report = new_report(global, args, hash, max_threads.unwrap_or(1), matrices);
+    let mut medians = Vec::new();
+    let mut failures = Vec::new();
+    let mut buffers = product_buffers(matrices);
+    for pool in &pools {
+        let threads = pool.current_num_threads();
+        let iterations = pool.install(|| {
+            for round in inputs.iter().cycle().take(args.warmup) {
+                multiply_all(matrices, &round.witness, args.backend)?;
+            }
+
+            let mut iterations = Vec::with_capacity(inputs.len());
+            for input in &inputs {
+                let mut measurements = Vec::new();
+                let witness = &input.witness;
+                time_products(
+                    matrices,
+                    &Converted::Csr,
+                    witness,
+                    args,
+                    pool,
+                    &mut measurements,
+                    &mut buffers,
+                )?;
+                record(&mut report, matrices, input.index, threads, &measurements);
+                if let Some(expected) = &input.expected {
+                    let products = &buffers.products;
+                    failures.extend(diff_against(input.index, products, expected, &args.diff));
+                }
+                iterations.push(measurements);
+            }
+            Ok::<_, CliError>(iterations)
+        })?;
+
+        print!("threads {threads}: {}", timing::summary_report(&iterations));
+        for name in names() {
+            medians.push((threads, name, median(&iterations, name)));
+        }
+    }
+    tally.witnesses = inputs.len();
+    tally.mismatches = failures.len();
+
+    println!();
+    print!("{}", speedup_table(&medians));
+    print_skipped(global);
+
+    write_reports(args, report, target)?;
+    summarize_failures(&failures)
+}
+
+/// Median of every run of the product of `name` over all witnesses.
+fn median(iterations: &[Vec<Measurement>], name: MatrixName) -> Duration {
+    let durations: Vec<_> = iterations
+        .iter()
+        .flatten()
+        .filter(|m| m.label == name.product())
+        .map(|m| m.duration)
+        .collect();
+    Summary::from_durations(&durations).map_or(Duration::ZERO, |summary| summary.median)
+}
+
+/// Tabulates the median of each matrix per thread count, with the speedup over 1 thread,
+/// or over the first count swept if 1 thread was not.
+fn speedup_table(medians: &[(usize, MatrixName, Duration)]) -> String {
+    let base_threads = match medians.iter().find(|(threads, ..)| *threads == 1) {
+        Some(_) => 1,
+        None => medians.first().map_or(1, |(threads, ..)| *threads),
+    };
+    let mut table = format!(
+        "{:>7}  {:<6} {:>14} {:>9}\n",
+        "threads",
+        "matrix",
+        "median",
+        format!("vs {base_threads}t")
+    );
+    for &(threads, name, median) in medians {
+        let base = medians
+            .iter()
+            .find(|(t, n, _)| *t == base_threads && *n == name)
+            .map_or(median, |(.., base)| *base);
+        let speedup = base.as_secs_f64() / median.as_secs_f64().max(f64::MIN_POSITIVE);
+        table += &format!(
+            "{threads:>7}  {:<6} {:>14} {speedup:>8.2}x\n",
+            name.as_str(),
+            format!("{median:?}")
+        );
+    }
+    table
+}
@@ -0,0 +1,63 @@
+//! `bench --tune`: pick how many rows each task of the parallel products multiplies by timing
+//! a few chunk policies on the first witness.
+
+use std::{iter, time::Instant};
+
+use rayon::ThreadPool;
+
+use super::{chunk_policy, BenchArgs};
+use crate::{
+    cli::{read_witness, CliError, Matrices},
+    sparse::ChunkPolicy,
+    statistics::Summary,
+};
+
+/// Rows below which no task is split, as tried by `--tune`.
+const TUNE_ROWS: [usize; 3] = [64, 1024, 16384];
+
+/// Nonzeros per task, as tried by `--tune`.
+const TUNE_NNZ: [usize; 3] = [1 << 12, 1 << 16, 1 << 20];
+
+/// Times the products of the first of `witnesses` with every one of `matrices` on `pool`
+/// `--repeat` times under each candidate policy, printing the median of each, and returns the
+/// fastest. Without witnesses there is nothing to time, so the policy of `args` stands.
+pub(super) fn tune(
+    hash: &str,
+    matrices: &Matrices,
+    witnesses: &[usize],
+    args: &BenchArgs,
+    pool: &ThreadPool,
+) -> Result<ChunkPolicy, CliError> {
+    let Some(&i) = witnesses.first() else {
+        return Ok(chunk_policy(args));
+    };
+    let witness = read_witness(hash, i)?;
+    let candidates = iter::once(ChunkPolicy::Auto)
+        .chain(TUNE_ROWS.map(ChunkPolicy::Rows))
+        .chain(TUNE_NNZ.map(ChunkPolicy::Nnz));
+
+    println!("tuning the chunk policy on witness {i}:");
+    let mut product = Vec::new();
+    let mut fastest = None;
+    for policy in candidates {
+        let runs: Vec<_> = (0..args.repeat)
+            .map(|_| {
+                let start = Instant::now();
+                for (_, M) in matrices {
+                    M.in_pool(pool, |M| {
+                        M.multiply_vec_chunked_into(&witness, &mut product, policy)
+                    });
+                }
+                start.elapsed()
+            })
+            .collect();
+        let median = Summary::from_durations(&runs).map_or_else(Default::default, |s| s.median);
+        println!("  {policy}: {median:?}");
+        if fastest.is_none_or(|(_, best)| median < best) {
+            fastest = Some((policy, median));
+        }
+    }
+    let (policy, _) = fastest.expect("there is always a candidate");
+    println!("chose {policy}\n");
+    Ok(policy)
+}
@@ -0,0 +1,32 @@
+//! The `cache` subcommand: manage the on-disk cache of matrices.
+
+use clap::{Args, Subcommand};
+
+use super::{format_size, CliError};
+use crate::data::clear_arecibo_cache;
+
+/// Flags of the `cache` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum CacheAction {
+    /// Remove the cached matrices of one dump, or of every dump
+    Clear {
+        /// Hash of the dump to clear [default: clear the whole cache]
+        hash: Option<String>,
+    },
+}
+
+pub(super) fn cache(args: &CacheArgs) -> Result<(), CliError> {
+    match &args.action {
+        CacheAction::Clear { hash } => {
+            let (files, bytes) = clear_arecibo_cache(hash.as_deref())?;
+            println!("removed {files} cache files ({})", format_size(bytes));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! The `check` subcommand, and the preflight `bench` runs: make sure a dump is complete
+//! before spending time on it.
+
+use clap::Args;
+
+use super::{load_matrices, selected_matrices, CliError, GlobalArgs, HashArgs, Tally};
+use crate::{
+    data::{check_dump, has_section, label_indices, result_section, witness_section, DumpCheck},
+    hex::bytes_to_hex,
+};
+
+/// Flags of the `check` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Also load every selected matrix and print the hash of its contents, which is the same
+    /// whatever format or cache it was read from, to tell whether two dumps hold the same one
+    #[arg(long)]
+    pub content_hash: bool,
+    /// Also load every selected matrix and list the coordinates it stores more than once,
+    /// which usually means a bug in whatever wrote it
+    #[arg(long)]
+    pub duplicates: bool,
+}
+
+/// Most duplicated coordinates of a matrix that `check --duplicates` and `stats` list.
+pub(super) const DUPLICATES_LISTED: usize = 50;
+
+pub(super) fn check(
+    global: &GlobalArgs,
+    args: &CheckArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let section = witness_section(hash);
+    let available = if has_section(&section)? {
+        label_indices(&section, "_")?
+    } else {
+        Vec::new()
+    };
+    // Check up to the highest witness found, so that gaps in the numbering are reported too.
+    let witnesses: Vec<usize> = match global.iterations {
+        Some(iterations) => (global.start..global.start + iterations).collect(),
+        None => match available.last() {
+            Some(&last) => (global.start..=last).collect(),
+            None => Vec::new(),
+        },
+    };
+
+    let check = check_dump(hash, &matrix_names(global), &witnesses, true)?;
+    tally.witnesses = witnesses.len();
+    println!("{section}: {} entries", check.witness_entries);
+    if let Some(entries) = check.result_entries {
+        println!("{}: {entries} entries", result_section(hash));
+    }
+    require_complete(hash, &check)?;
+    println!("dump {hash} is complete for {} witnesses", witnesses.len());
+
+    if args.content_hash || args.duplicates {
+        let matrices = load_matrices(global, hash)?;
+        tally.matrices = matrices.len();
+        for (name, M) in &matrices {
+            if args.content_hash {
+                let content_hash = bytes_to_hex(&M.content_hash());
+                println!("{}: content hash {content_hash}", name.as_str());
+            }
+            if args.duplicates {
+                let duplicates = M.find_duplicates();
+                match duplicates.len() {
+                    0 => println!("{name}: no duplicate entries"),
+                    _ => {
+                        println!("{name}: {}", describe_duplicates(&duplicates));
+                        print_duplicates(&duplicates, "  ");
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How many coordinates of [`SparseMatrix::find_duplicates`] are stored more than once, and
+/// how many entries more than one each that makes.
+///
+/// [`SparseMatrix::find_duplicates`]: crate::SparseMatrix::find_duplicates
+pub(super) fn describe_duplicates(duplicates: &[(usize, usize, usize)]) -> String {
+    let extra: usize = duplicates.iter().map(|&(_, _, count)| count - 1).sum();
+    format!(
+        "{} coordinates stored more than once, {extra} extra entries",
+        duplicates.len()
+    )
+}
+
+/// Prints the first [`DUPLICATES_LISTED`] of `duplicates` after `indent`, one per line, and
+/// how many more there are.
+pub(super) fn print_duplicates(duplicates: &[(usize, usize, usize)], indent: &str) {
+    for &(row, col, count) in duplicates.iter().take(DUPLICATES_LISTED) {
+        println!("{indent}row {row}, column {col}: {count} entries");
+    }
+    if duplicates.len() > DUPLICATES_LISTED {
+        println!(
+            "{indent}... and {} more",
+            duplicates.len() - DUPLICATES_LISTED
+        );
+    }
+}
+
+/// Checks that the selected matrices and every one of `witnesses` are present before a run, along
+/// with their expected products if `with_results` is set.
+pub(super) fn preflight(
+    global: &GlobalArgs,
+    hash: &str,
+    witnesses: &[usize],
+    with_results: bool,
+) -> Result<(), CliError> {
+    let check = check_dump(hash, &matrix_names(global), witnesses, with_results)?;
+    require_complete(hash, &check)
+}
+
+fn matrix_names(global: &GlobalArgs) -> Vec<&'static str> {
+    selected_matrices(global)
+        .into_iter()
+        .map(|name| name.as_str())
+        .collect()
+}
+
+/// Lists every missing piece of `check`, failing if there are any.
+fn require_complete(hash: &str, check: &DumpCheck) -> Result<(), CliError> {
+    if check.is_complete() {
+        return Ok(());
+    }
+    println!("missing from dump {hash}:");
+    for path in &check.missing {
+        println!("  {path}");
+    }
+    Err(CliError::IncompleteDump {
+        hash: hash.to_string(),
+        missing: check.missing.len(),
+    })
+}
@@ -0,0 +1,82 @@
+//! The `compare` subcommand: diff two JSON reports written by `bench --output`.
+
+use std::collections::BTreeSet;
+
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use super::{CliError, Tally};
+use crate::{compare::Comparison, report::BenchReport};
+
+/// Flags of the `compare` subcommand.
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct CompareArgs {
+    /// Report to compare against
+    pub baseline: Utf8PathBuf,
+    /// Report to check for regressions
+    pub candidate: Utf8PathBuf,
+    /// Fail if any median slows down by more than this many percent
+    #[arg(long, value_name = "PERCENT")]
+    pub threshold: Option<f64>,
+}
+
+pub(super) fn compare(args: &CompareArgs, tally: &mut Tally) -> Result<(), CliError> {
+    let read = |path: &Utf8PathBuf| {
+        BenchReport::read_json(path).map_err(|source| CliError::report(path, source))
+    };
+    let comparison = Comparison::new(&read(&args.baseline)?, &read(&args.candidate)?);
+
+    // Sweeps are compared per thread count, which then gets a column of its own.
+    let sweep = comparison.deltas.iter().any(|d| d.threads.is_some());
+    let threads = |threads: Option<usize>| match (sweep, threads) {
+        (false, _) => String::new(),
+        (true, Some(threads)) => format!("{threads:>7} "),
+        (true, None) => format!("{:>7} ", "-"),
+    };
+    println!(
+        "{}{:<8} {:<7} {:>14} {:>14} {:>9}",
+        match sweep {
+            true => format!("{:>7} ", "threads"),
+            false => String::new(),
+        },
+        "witness",
+        "matrix",
+        "baseline",
+        "candidate",
+        "delta"
+    );
+    for delta in &comparison.deltas {
+        println!(
+            "{}{:<8} {:<7} {:>14} {:>14} {:>+8.2}%",
+            threads(delta.threads),
+            delta.witness,
+            delta.matrix,
+            format!("{:?}", delta.baseline),
+            format!("{:?}", delta.candidate),
+            delta.percent()
+        );
+    }
+    for mismatch in &comparison.mismatches {
+        println!("mismatch: {mismatch}");
+    }
+
+    let regressions = match args.threshold {
+        Some(threshold) => comparison.regressions(threshold).count(),
+        None => 0,
+    };
+    let mismatches = comparison.mismatches.len();
+    let matrices: BTreeSet<_> = comparison.deltas.iter().map(|d| &d.matrix).collect();
+    let witnesses: BTreeSet<_> = comparison.deltas.iter().map(|d| d.witness).collect();
+    tally.matrices = matrices.len();
+    tally.witnesses = witnesses.len();
+    tally.mismatches = regressions + mismatches;
+    if regressions > 0 || mismatches > 0 {
+        println!("verdict: FAIL");
+        return Err(CliError::CompareFailed {
+            regressions,
+            mismatches,
+        });
+    }
+    println!("verdict: PASS");
+    Ok(())
+}
@@ -0,0 +1,465 @@
+//! The `convert` subcommand: re-encode a dump next to its files, in the raw codec for
+//! `--data-format raw`, in row chunks for `verify --chunked`, or as archives for zero-copy
+//! reads, or as Matrix Market or scipy npz files for other tools; or import a matrix from one
+//! of those into it.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use ff::PrimeField;
+use halo2curves::bn256;
+
+use super::{
+    for_each_circuit, load_matrices, read_expected_products, read_witness, select_witnesses,
+    selected_matrices, CircuitSelection, CliError, GlobalArgs, HashArgs, MatrixName, Tally,
+};
+#[cfg(feature = "rkyv")]
+use crate::archive::write_arecibo_archive;
+use crate::{
+    data::{
+        arecibo_file_path, arecibo_output_path, atomic_write, detect_file, format_size,
+        has_section, lock_arecibo_section, matrices_section,
+        parallel::{decode_fields, decode_usizes},
+        result_section, witness_section, write_arecibo_data, write_arecibo_data_raw,
+        write_arecibo_matrix_raw, FileFormat, IndexEncoding,
+    },
+    read_arecibo_data,
+    sparse::{
+        ChunkedShape, ChunkedWriter, CHUNKED_EXTENSION, MATRIX_MARKET_EXTENSION, NPZ_EXTENSION,
+    },
+    DataError, SparseMatrix,
+};
+
+/// Flags of the `convert` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ConvertArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Encoding to write
+    #[arg(long, value_enum, required_unless_present_any = ["from_mtx", "from_npz"])]
+    pub to: Option<ConvertTarget>,
+    /// Import the Matrix Market file at this path as the `--matrix` of the dump instead,
+    /// replacing any matrix already there
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["to", "delta_indices"])]
+    pub from_mtx: Option<Utf8PathBuf>,
+    /// Import the scipy npz file at this path as the `--matrix` of the dump instead, like
+    /// `--from-mtx`
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["to", "delta_indices", "from_mtx"]
+    )]
+    pub from_npz: Option<Utf8PathBuf>,
+    /// Matrix of the dump that `--from-mtx` or `--from-npz` writes
+    #[arg(long, value_enum, default_value_t = MatrixName::A)]
+    pub matrix: MatrixName,
+    /// Rows in each chunk written by `--to chunked`
+    #[arg(long, value_name = "N", default_value_t = 1 << 16, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rows_per_chunk: u64,
+    /// Store the column indices written by `--to raw` as per-row differences, which are
+    /// usually far smaller; every row's columns must be sorted
+    #[arg(long)]
+    pub delta_indices: bool,
+    /// Drop the entries stored as zero from every matrix written, as `stats` counts them;
+    /// the products are unchanged
+    #[arg(long)]
+    pub prune: bool,
+}
+
+/// Encodings `convert` can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConvertTarget {
+    /// The matrices, witnesses, and expected results as `<label>.raw`
+    Raw,
+    /// The matrices as `<label>.chunked`, in groups of `--rows-per-chunk` rows, for
+    /// `verify --chunked`; they are converted a chunk at a time, never loaded whole
+    Chunked,
+    /// The matrices as `<label>.rkyv`, for `bench --archived`
+    #[cfg(feature = "rkyv")]
+    Rkyv,
+    /// The matrices as `<label>.mtx`, in Matrix Market format with hex values, for scipy and
+    /// other tools
+    Mtx,
+    /// The matrices as `<label>.npz`, in scipy's `save_npz` layout with each value as a row of
+    /// 32 little-endian bytes
+    Npz,
+    /// Nothing: the matrices are converted to nalgebra-sparse `CsrMatrix`es and back, printing
+    /// how many rows had to be sorted, to debug the conversion
+    #[cfg(feature = "nalgebra")]
+    Nalgebra,
+}
+
+/// Runs `convert` on the circuits selected by `args`.
+pub(super) fn run(
+    global: &GlobalArgs,
+    args: &ConvertArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let importing = args.from_mtx.is_some() || args.from_npz.is_some();
+    if importing && args.dump.circuit == CircuitSelection::Both {
+        return Err(CliError::InvalidArgs(
+            "--from-mtx and --from-npz import a single matrix, so take a single circuit"
+                .to_string(),
+        ));
+    }
+    for_each_circuit(
+        args,
+        |args| &mut args.dump,
+        tally,
+        |args, _, tally| convert(global, args, tally),
+    )
+}
+
+fn convert(global: &GlobalArgs, args: &ConvertArgs, tally: &mut Tally) -> Result<(), CliError> {
+    if args.delta_indices && args.to != Some(ConvertTarget::Raw) {
+        return Err(CliError::InvalidArgs(
+            "--delta-indices only applies to --to raw".to_string(),
+        ));
+    }
+    if args.prune && args.to == Some(ConvertTarget::Chunked) {
+        return Err(CliError::InvalidArgs(
+            "--prune loads each matrix whole, so does not apply to --to chunked, which never \
+             does"
+                .to_string(),
+        ));
+    }
+    let Some(to) = args.to else {
+        return import_matrix(args, tally);
+    };
+    match to {
+        ConvertTarget::Raw => {
+            let encoding = if args.delta_indices {
+                IndexEncoding::Delta
+            } else {
+                IndexEncoding::Plain
+            };
+            convert_raw(global, &args.dump.hash, encoding, args.prune, tally)
+        }
+        ConvertTarget::Chunked => {
+            convert_chunked(global, &args.dump.hash, args.rows_per_chunk as usize, tally)
+        }
+        #[cfg(feature = "rkyv")]
+        ConvertTarget::Rkyv => convert_rkyv(global, &args.dump.hash, args.prune, tally),
+        ConvertTarget::Mtx | ConvertTarget::Npz => {
+            convert_to(global, &args.dump.hash, to, args.prune, tally)
+        }
+        #[cfg(feature = "nalgebra")]
+        ConvertTarget::Nalgebra => convert_nalgebra(global, &args.dump.hash, tally),
+    }
+}
+
+/// Writes the selected matrices, with their indices stored as `encoding` says, witnesses, and,
+/// if the dump has them, expected results in the raw codec.
+fn convert_raw(
+    global: &GlobalArgs,
+    hash: &str,
+    encoding: IndexEncoding,
+    prune: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let section = matrices_section(hash);
+    for (name, M) in load_matrices(global, hash)? {
+        let M = pruned(name, M, prune);
+        let path = write_arecibo_matrix_raw(&section, name.label(), &M, encoding)?;
+        print_written(name.as_str(), &path);
+        tally.matrices += 1;
+    }
+
+    let results = has_section(result_section(hash))?.then(|| result_section(hash));
+    for i in select_witnesses(global, hash)? {
+        let path = write_arecibo_data_raw(
+            witness_section(hash),
+            format!("_{i}"),
+            &read_witness(hash, i)?,
+        )?;
+        print_written(&format!("witness {i}"), &path);
+        if let Some(section) = &results {
+            for (name, product) in read_expected_products(hash, selected_matrices(global), i)? {
+                write_arecibo_data_raw(section, name.product_label(i), &product)?;
+            }
+        }
+        tally.witnesses += 1;
+    }
+    Ok(())
+}
+
+/// Writes the selected matrices in chunks of `rows_per_chunk` rows.
+fn convert_chunked(
+    global: &GlobalArgs,
+    hash: &str,
+    rows_per_chunk: usize,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let section = matrices_section(hash);
+    for name in selected_matrices(global) {
+        let source = arecibo_file_path(&section, name.label())?;
+        let label = format!("{}.{CHUNKED_EXTENSION}", name.label());
+        let target = arecibo_output_path(&section, label)?;
+        let _lock = lock_arecibo_section(&section)?;
+        let detected = detect_file(&source)?;
+        let shape = if detected.format != FileFormat::Bincode {
+            // Only plain bincode can be seeked through, so anything else is chunked from memory.
+            eprintln!("warning: {source} is {detected}, so it is loaded whole to be chunked");
+            let M: SparseMatrix<bn256::Fr> = read_arecibo_data(&section, name.label())?;
+            atomic_write(&target, |file| {
+                M.write_chunked(rows_per_chunk, BufWriter::new(file))
+                    .map(drop)
+                    .map_err(|source| DataError::Io {
+                        path: target.clone(),
+                        source,
+                    })
+            })?;
+            ChunkedShape {
+                rows: M.num_rows(),
+                cols: M.cols,
+                nnz: M.nnz(),
+                rows_per_chunk,
+            }
+        } else {
+            atomic_write(&target, |file| {
+                chunk_bincode(
+                    &source,
+                    detected.data_offset(),
+                    &target,
+                    BufWriter::new(file),
+                    rows_per_chunk,
+                )
+            })?
+        };
+        print_written(name.as_str(), &target);
+        println!("  {} chunks of {rows_per_chunk} rows", shape.chunks());
+        tally.matrices += 1;
+    }
+    Ok(())
+}
+
+/// Rewrites the bincode matrix at `offset` in `source` through `writer` to `target` in chunks
+/// of `rows_per_chunk` rows. bincode lays a matrix out as its `data`, `indices`, and `indptr`
+/// arrays, each after its `u64` length, and then `cols`: only `indptr` is read whole, and the
+/// slices of the other two that each chunk needs are seeked to.
+fn chunk_bincode(
+    source: &Utf8Path,
+    offset: u64,
+    target: &Utf8Path,
+    writer: impl Write,
+    rows_per_chunk: usize,
+) -> Result<ChunkedShape, DataError> {
+    let read_error = |err| DataError::from_stream(source.to_owned(), err);
+    let write_error = |err| DataError::Io {
+        path: target.to_owned(),
+        source: err,
+    };
+    let width = <bn256::Fr as PrimeField>::Repr::default().as_ref().len() as u64;
+    let mut file = BufReader::new(File::open(source).map_err(read_error)?);
+    file.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+
+    let nnz = read_u64(&mut file).map_err(read_error)?;
+    let data_start = offset + 8;
+    file.seek(SeekFrom::Start(data_start + nnz * width))
+        .map_err(read_error)?;
+    if read_u64(&mut file).map_err(read_error)? != nnz {
+        return Err(read_error(invalid_data(
+            "data and indices differ in length",
+        )));
+    }
+    let indices_start = data_start + nnz * width + 8;
+    file.seek(SeekFrom::Start(indices_start + nnz * 8))
+        .map_err(read_error)?;
+    let indptr_len = read_u64(&mut file).map_err(read_error)?;
+    let indptr = decode_usizes(&read_bytes(&mut file, indptr_len * 8).map_err(read_error)?)
+        .map_err(|message| read_error(invalid_data(message)))?;
+    let cols = read_u64(&mut file).map_err(read_error)?;
+    let (Some(&0), Some(&last)) = (indptr.first(), indptr.last()) else {
+        return Err(read_error(invalid_data(
+            "indptr is empty or does not start at 0",
+        )));
+    };
+    if last as u64 != nnz || indptr.windows(2).any(|ptrs| ptrs[0] > ptrs[1]) {
+        return Err(read_error(invalid_data("indptr is out of order")));
+    }
+
+    let shape = ChunkedShape {
+        rows: indptr.len() - 1,
+        cols: cols as usize,
+        nnz: nnz as usize,
+        rows_per_chunk,
+    };
+    let mut writer = ChunkedWriter::new(writer, shape).map_err(write_error)?;
+    for i in 0..shape.chunks() {
+        let rows = shape.chunk_rows(i);
+        let (start, end) = (indptr[rows.start] as u64, indptr[rows.end] as u64);
+        file.seek(SeekFrom::Start(data_start + start * width))
+            .map_err(read_error)?;
+        let data = read_bytes(&mut file, (end - start) * width).map_err(read_error)?;
+        file.seek(SeekFrom::Start(indices_start + start * 8))
+            .map_err(read_error)?;
+        let indices = read_bytes(&mut file, (end - start) * 8).map_err(read_error)?;
+        let chunk = SparseMatrix {
+            data: decode_fields(&data).map_err(|message| read_error(invalid_data(message)))?,
+            indices: decode_usizes(&indices)
+                .map_err(|message| read_error(invalid_data(message)))?,
+            indptr: indptr[rows.start..=rows.end]
+                .iter()
+                .map(|k| k - start as usize)
+                .collect(),
+            cols: shape.cols,
+        };
+        writer
+            .write_chunk::<bn256::Fr>(&chunk)
+            .map_err(write_error)?;
+    }
+    writer.finish().map_err(write_error)?;
+    Ok(shape)
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads the next `len` bytes, failing on a length no file could hold rather than allocating it.
+fn read_bytes(reader: &mut impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Archives the selected matrices.
+#[cfg(feature = "rkyv")]
+fn convert_rkyv(
+    global: &GlobalArgs,
+    hash: &str,
+    prune: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let section = matrices_section(hash);
+    for (name, M) in load_matrices(global, hash)? {
+        let M = pruned(name, M, prune);
+        let path = write_arecibo_archive(&section, name.label(), &M)?;
+        print_written(name.as_str(), &path);
+        tally.matrices += 1;
+    }
+    Ok(())
+}
+
+/// Writes the selected matrices as the Matrix Market or npz files of `to`.
+fn convert_to(
+    global: &GlobalArgs,
+    hash: &str,
+    to: ConvertTarget,
+    prune: bool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let section = matrices_section(hash);
+    for (name, M) in load_matrices(global, hash)? {
+        let M = pruned(name, M, prune);
+        let extension = match to {
+            ConvertTarget::Npz => NPZ_EXTENSION,
+            _ => MATRIX_MARKET_EXTENSION,
+        };
+        let label = format!("{}.{extension}", name.label());
+        let path = arecibo_output_path(&section, label)?;
+        let _lock = lock_arecibo_section(&section)?;
+        atomic_write(&path, |file| {
+            let written = match to {
+                ConvertTarget::Npz => M.write_npz(file),
+                _ => M.write_matrix_market(file),
+            };
+            written.map_err(|source| DataError::Io {
+                path: path.clone(),
+                source,
+            })
+        })?;
+        print_written(name.as_str(), &path);
+        tally.matrices += 1;
+    }
+    Ok(())
+}
+
+/// Converts the selected matrices to nalgebra-sparse `CsrMatrix`es and back, writing nothing,
+/// and prints how many rows were sorted on the way and whether they came back unchanged.
+#[cfg(feature = "nalgebra")]
+fn convert_nalgebra(global: &GlobalArgs, hash: &str, tally: &mut Tally) -> Result<(), CliError> {
+    for (name, M) in load_matrices(global, hash)? {
+        let unsorted = M
+            .indptr
+            .windows(2)
+            .filter(|ptrs| !M.indices[ptrs[0]..ptrs[1]].is_sorted())
+            .count();
+        let csr = M.to_nalgebra_csr().map_err(|err| CliError::Unconvertible {
+            name,
+            target: "a nalgebra CsrMatrix",
+            message: err.to_string(),
+        })?;
+        let round_trip = if SparseMatrix::from_nalgebra_csr(&csr) == M {
+            "unchanged"
+        } else {
+            "with those rows sorted"
+        };
+        println!(
+            "{name}: converted to a {}x{} CsrMatrix of {} entries, sorting {unsorted} rows, and \
+             back {round_trip}",
+            csr.nrows(),
+            csr.ncols(),
+            csr.nnz()
+        );
+        tally.matrices += 1;
+    }
+    Ok(())
+}
+
+/// Reads the `--from-mtx` or `--from-npz` file and writes it as the `--matrix` of the dump.
+fn import_matrix(args: &ConvertArgs, tally: &mut Tally) -> Result<(), CliError> {
+    let source = args
+        .from_mtx
+        .as_ref()
+        .or(args.from_npz.as_ref())
+        .expect("--to, --from-mtx, or --from-npz is required");
+    let file = File::open(source).map_err(|err| DataError::from_stream(source.clone(), err))?;
+    let M = match args.from_npz {
+        Some(_) => SparseMatrix::<bn256::Fr>::from_npz(file),
+        None => SparseMatrix::from_matrix_market(file),
+    };
+    let M = M.map_err(|err| DataError::from_stream(source.clone(), err))?;
+    println!(
+        "{}: read {source} ({}x{}, {} entries)",
+        args.matrix,
+        M.num_rows(),
+        M.cols,
+        M.nnz()
+    );
+    let M = pruned(args.matrix, M, args.prune);
+    let path = write_arecibo_data(matrices_section(&args.dump.hash), args.matrix.label(), &M)?;
+    print_written(args.matrix.as_str(), &path);
+    tally.matrices += 1;
+    Ok(())
+}
+
+/// `M` without its explicit zeros with `--prune`, printing how many there were.
+fn pruned(
+    name: MatrixName,
+    mut M: SparseMatrix<bn256::Fr>,
+    prune: bool,
+) -> SparseMatrix<bn256::Fr> {
+    if prune {
+        println!("{name}: pruned {} explicit zeros", M.prune_in_place());
+    }
+    M
+}
+
+fn print_written(what: &str, path: &Utf8Path) {
+    let bytes = path.metadata().map_or(0, |metadata| metadata.len());
+    println!("{what}: wrote {path} ({})", format_size(bytes));
+}
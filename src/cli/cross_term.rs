@@ -0,0 +1,91 @@
+//! The `cross-term` subcommand: time the cross term that folding two witnesses commits to,
+//! and compare it against the dumped one when there is one.
+
+use clap::Args;
+use halo2curves::bn256;
+
+use super::{
+    load_matrices, parse_coefficient, read_witness, CliError, DiffArgs, GlobalArgs, HashArgs, Tally,
+};
+use crate::{
+    data::{cross_term_section, has_section, list_labels},
+    diff::diff_vectors,
+    folding::cross_term,
+    read_arecibo_data_with_format,
+    sparse::MatrixError,
+    timing::Measurement,
+    SparseMatrix,
+};
+
+/// Flags of the `cross-term` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct CrossTermArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Index of the first witness, folded as `(z_i, u1)`
+    #[arg(long = "i", value_name = "I", default_value_t = 0)]
+    pub i: usize,
+    /// Index of the second witness, folded as `(z_j, u2)`
+    #[arg(long = "j", value_name = "J", default_value_t = 1)]
+    pub j: usize,
+    /// Scalar of the first instance, in decimal or `0x` hex; 1 for a fresh R1CS instance
+    #[arg(long, value_name = "U", value_parser = parse_coefficient, default_value = "1")]
+    pub u1: bn256::Fr,
+    /// Scalar of the second instance, in decimal or `0x` hex
+    #[arg(long, value_name = "U", value_parser = parse_coefficient, default_value = "1")]
+    pub u2: bn256::Fr,
+    #[command(flatten)]
+    pub diff: DiffArgs,
+}
+
+pub(super) fn run(
+    global: &GlobalArgs,
+    args: &CrossTermArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let (i, j) = (args.i, args.j);
+    let matrices = load_matrices(global, hash)?;
+    tally.matrices = matrices.len();
+    let [(_, A), (_, B), (_, C)] = &matrices[..] else {
+        return Err(CliError::InvalidArgs(
+            "cross-term multiplies A, B, and C, so needs all three selected".to_string(),
+        ));
+    };
+    let (z1, z2) = (read_witness(hash, i)?, read_witness(hash, j)?);
+    tally.witnesses = 2;
+
+    let (products, multiply) = Measurement::time("products", || {
+        let first = SparseMatrix::multiply_vec_fused(A, B, C, &z1)?;
+        let second = SparseMatrix::multiply_vec_fused(A, B, C, &z2)?;
+        Ok((first, second))
+    });
+    let (first, second) = products.map_err(|source: MatrixError| CliError::Incompatible {
+        context: format!("witnesses {i} and {j}"),
+        source,
+    })?;
+    let (t, combination) =
+        Measurement::time("cross term", || cross_term(first, second, args.u1, args.u2));
+    println!(
+        "products of witnesses {i} and {j} took: {:?}\ncross term took: {:?}\ntotal: {:?}",
+        multiply.duration,
+        combination.duration,
+        multiply.duration + combination.duration
+    );
+
+    let section = cross_term_section(hash);
+    let label = format!("T_{i}_{j}");
+    if !has_section(&section)? || !list_labels(&section)?.contains(&label) {
+        println!("{section}/{label} not found, nothing to compare against");
+        return Ok(());
+    }
+    let expected: Vec<bn256::Fr> = read_arecibo_data_with_format(&section, &label)?;
+    let diff = diff_vectors(&t, &expected, args.diff.diff_limit);
+    if diff.is_equal() {
+        println!("cross term of witnesses {i} and {j}: PASS");
+        return Ok(());
+    }
+    println!("cross term of witnesses {i} and {j}: FAIL\n{label}: {diff}");
+    tally.mismatches = 1;
+    Err(CliError::VerificationFailed { mismatches: 1 })
+}
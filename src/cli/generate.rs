@@ -0,0 +1,80 @@
+//! The `generate` subcommand: write a complete synthetic dump under the data root.
+
+use clap::Args;
+use halo2curves::bn256;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use super::{CliError, HashArgs, MatrixName, Tally};
+use crate::{
+    data::{matrices_section, result_section, witness_section},
+    generate::{random_vector, Shape},
+    write_arecibo_data, SparseMatrix,
+};
+
+/// Flags of the `generate` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Number of rows of each matrix
+    #[arg(long, value_name = "R", default_value_t = 1024)]
+    pub rows: usize,
+    /// Number of columns of each matrix, and length of each witness
+    #[arg(long, value_name = "C", default_value_t = 1024)]
+    pub cols: usize,
+    /// Number of nonzeros in every row
+    #[arg(long, value_name = "K", default_value_t = 8)]
+    pub nnz_per_row: usize,
+    /// Number of witnesses to generate
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub witnesses: usize,
+    /// Seed of the random generator; the same seed writes byte-identical files
+    #[arg(long, value_name = "S", default_value_t = 0)]
+    pub seed: u64,
+}
+
+pub(super) fn generate(args: &GenerateArgs, tally: &mut Tally) -> Result<(), CliError> {
+    let shape = Shape {
+        rows: args.rows,
+        cols: args.cols,
+        nnz_per_row: args.nnz_per_row,
+    };
+    if shape.nnz_per_row > shape.cols {
+        return Err(CliError::InvalidArgs(format!(
+            "--nnz-per-row {} exceeds --cols {}",
+            shape.nnz_per_row, shape.cols
+        )));
+    }
+    let hash = &args.dump.hash;
+    let mut rng = ChaCha20Rng::seed_from_u64(args.seed);
+
+    let matrices: [SparseMatrix<bn256::Fr>; 3] = std::array::from_fn(|_| {
+        SparseMatrix::random(shape.rows, shape.cols, shape.nnz_per_row, &mut rng)
+    });
+    let section = matrices_section(hash);
+    for (name, M) in MatrixName::ALL.into_iter().zip(&matrices) {
+        write_arecibo_data(&section, name.label(), M)?;
+    }
+    tally.matrices = matrices.len();
+
+    for i in 0..args.witnesses {
+        let witness: Vec<bn256::Fr> = random_vector(&mut rng, shape.cols);
+        write_arecibo_data(witness_section(hash), format!("_{i}"), &witness)?;
+        for (name, M) in MatrixName::ALL.into_iter().zip(&matrices) {
+            let label = name.product_label(i);
+            write_arecibo_data(
+                result_section(hash),
+                label,
+                &M.multiply_vec_serial(&witness),
+            )?;
+        }
+        tally.witnesses += 1;
+    }
+
+    println!(
+        "generated dump {hash}: three {} x {} matrices with {} nonzeros per row, {} witnesses",
+        shape.rows, shape.cols, shape.nnz_per_row, args.witnesses
+    );
+    Ok(())
+}
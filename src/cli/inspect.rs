@@ -0,0 +1,164 @@
+//! The `inspect` subcommand: print the contents of data files for debugging small cases, and
+//! `import-vector`, which writes a vector exported by `inspect vector --out` back.
+
+use std::{fs::File, io};
+
+use camino::Utf8PathBuf;
+use clap::{Args, Subcommand};
+use halo2curves::bn256;
+use serde::Serialize;
+
+use super::{format_size, to_json, CliError, Format};
+use crate::{
+    data::write_arecibo_data,
+    hex::{field_from_hex, field_to_hex},
+    read_arecibo_data_with_format, DataError,
+};
+
+/// Flags of the `inspect` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct InspectArgs {
+    #[command(subcommand)]
+    pub action: InspectAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum InspectAction {
+    /// Print the length and elements of a vector of field elements, such as a witness or a
+    /// product, as big-endian hex; `--format json` prints them as JSON
+    Vector(VectorArgs),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct VectorArgs {
+    /// Section holding the vector, as in `witness_<HASH>`
+    pub section: String,
+    /// Label of the vector in the section, as in `_0`
+    pub label: String,
+    /// Print only the first and last N elements
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+    /// Write every element to this file as a JSON array of hex strings instead, which
+    /// `import-vector` reads back
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<Utf8PathBuf>,
+}
+
+/// Flags of the `import-vector` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ImportVectorArgs {
+    /// JSON array of hex strings to read, as `inspect vector --out` writes
+    pub path: Utf8PathBuf,
+    /// Section to write the vector to
+    pub section: String,
+    /// Label to write the vector as, replacing any file already there
+    pub label: String,
+}
+
+/// A vector as printed in JSON: its length, and the elements shown with their positions.
+#[derive(Serialize)]
+struct VectorInfo<'a> {
+    section: &'a str,
+    label: &'a str,
+    len: usize,
+    elements: Vec<Element>,
+}
+
+#[derive(Serialize)]
+struct Element {
+    index: usize,
+    value: String,
+}
+
+pub(super) fn inspect(args: &InspectArgs, format: Format) -> Result<(), CliError> {
+    match &args.action {
+        InspectAction::Vector(args) => inspect_vector(args, format),
+    }
+}
+
+fn inspect_vector(args: &VectorArgs, format: Format) -> Result<(), CliError> {
+    let vector: Vec<bn256::Fr> = read_arecibo_data_with_format(&args.section, &args.label)?;
+    let name = format!("{}/{}", args.section, args.label);
+    if let Some(path) = &args.out {
+        let hex: Vec<_> = vector.iter().map(field_to_hex).collect();
+        let file = File::create(path).map_err(|source| DataError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        serde_json::to_writer(io::BufWriter::new(file), &hex).map_err(|err| DataError::Io {
+            path: path.clone(),
+            source: err.into(),
+        })?;
+        let bytes = path.metadata().map_or(0, |metadata| metadata.len());
+        println!(
+            "{name}: wrote {} elements to {path} ({})",
+            vector.len(),
+            format_size(bytes)
+        );
+        return Ok(());
+    }
+
+    // With a limit, the elements shown are the first and last `limit`, without overlap.
+    let shown: Vec<usize> = match args.limit {
+        Some(limit) if 2 * limit < vector.len() => (0..limit)
+            .chain(vector.len() - limit..vector.len())
+            .collect(),
+        _ => (0..vector.len()).collect(),
+    };
+    match format {
+        Format::Text => {
+            println!("{name}: {} elements", vector.len());
+            let width = vector.len().saturating_sub(1).to_string().len();
+            for (k, &i) in shown.iter().enumerate() {
+                if k > 0 && i != shown[k - 1] + 1 {
+                    println!("{:>width$}  ... {} more", "", i - shown[k - 1] - 1);
+                }
+                println!("{i:>width$}  {}", field_to_hex(&vector[i]));
+            }
+        }
+        Format::Json => {
+            let info = VectorInfo {
+                section: &args.section,
+                label: &args.label,
+                len: vector.len(),
+                elements: shown
+                    .into_iter()
+                    .map(|index| Element {
+                        index,
+                        value: field_to_hex(&vector[index]),
+                    })
+                    .collect(),
+            };
+            println!("{}", to_json(&info));
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn import_vector(args: &ImportVectorArgs) -> Result<(), CliError> {
+    let path = &args.path;
+    let invalid = |message: String| {
+        DataError::from_stream(
+            path.clone(),
+            io::Error::new(io::ErrorKind::InvalidData, message),
+        )
+    };
+    let file = File::open(path).map_err(|err| DataError::from_stream(path.clone(), err))?;
+    let hex: Vec<String> = serde_json::from_reader(io::BufReader::new(file))
+        .map_err(|err| invalid(format!("expected a JSON array of hex strings: {err}")))?;
+    let vector = hex
+        .iter()
+        .enumerate()
+        .map(|(i, hex)| field_from_hex(hex).map_err(|err| invalid(format!("element {i}: {err}"))))
+        .collect::<Result<Vec<bn256::Fr>, _>>()?;
+    let written = write_arecibo_data(&args.section, &args.label, &vector)?;
+    let bytes = written.metadata().map_or(0, |metadata| metadata.len());
+    println!(
+        "{}/{}: wrote {} elements to {written} ({})",
+        args.section,
+        args.label,
+        vector.len(),
+        format_size(bytes)
+    );
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! The `list` subcommand: enumerate the sections of the data root, or the labels of one section
+//! with the format detected for each.
+
+use clap::Args;
+use serde::Serialize;
+
+use super::{format_size, to_json, CliError, Format};
+use crate::data::{list_label_formats, list_sections, Detected};
+
+/// Flags of the `list` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ListArgs {
+    /// Section to list the labels of [default: list every section]
+    pub section: Option<String>,
+}
+
+/// A label as listed in JSON, with the fields of [`Detected`] beside it.
+#[derive(Serialize)]
+struct LabelInfo<'a> {
+    label: &'a str,
+    #[serde(flatten)]
+    detected: &'a Detected,
+}
+
+pub(super) fn list(args: &ListArgs, format: Format) -> Result<(), CliError> {
+    match &args.section {
+        Some(section) => {
+            let labels = list_label_formats(section)?;
+            match format {
+                Format::Text => {
+                    let width = labels.iter().map(|(label, _)| label.len()).max();
+                    for (label, detected) in &labels {
+                        println!("{label:<width$}  {detected}", width = width.unwrap_or(0));
+                    }
+                }
+                Format::Json => {
+                    let labels: Vec<_> = labels
+                        .iter()
+                        .map(|(label, detected)| LabelInfo { label, detected })
+                        .collect();
+                    println!("{}", to_json(&labels));
+                }
+            }
+        }
+        None => {
+            let sections = list_sections()?;
+            match format {
+                Format::Text => {
+                    let width = sections.iter().map(|s| s.name.len()).max().unwrap_or(0);
+                    for section in &sections {
+                        println!(
+                            "{:<width$}  {:>6} files  {:>10}",
+                            section.name,
+                            section.files,
+                            format_size(section.bytes)
+                        );
+                    }
+                }
+                Format::Json => println!("{}", to_json(&sections)),
+            }
+        }
+    }
+    Ok(())
+}
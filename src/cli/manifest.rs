@@ -0,0 +1,53 @@
+//! The `manifest` subcommand: checksum the files of dumps written before manifests existed.
+
+use std::time::Instant;
+
+use clap::{Args, Subcommand};
+
+use super::{format_size, CliError};
+use crate::data::{
+    create_arecibo_manifest, has_section, matrices_section, result_section, witness_section,
+};
+
+/// Flags of the `manifest` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ManifestArgs {
+    #[command(subcommand)]
+    pub action: ManifestAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum ManifestAction {
+    /// Hash every file of the matrices, witness, and result sections of a dump into their
+    /// `manifest.json`, replacing any they had
+    Create {
+        /// Hash identifying the dump, as in `sparse_matrices_<HASH>`
+        hash: String,
+    },
+}
+
+pub(super) fn manifest(args: &ManifestArgs) -> Result<(), CliError> {
+    match &args.action {
+        ManifestAction::Create { hash } => {
+            for section in [
+                matrices_section(hash),
+                witness_section(hash),
+                result_section(hash),
+            ] {
+                if !has_section(&section)? {
+                    continue;
+                }
+                let start = Instant::now();
+                let manifest = create_arecibo_manifest(&section)?;
+                let bytes: u64 = manifest.files.values().map(|entry| entry.bytes).sum();
+                println!(
+                    "{section}: hashed {} files ({}) in {:.3?}",
+                    manifest.files.len(),
+                    format_size(bytes),
+                    start.elapsed()
+                );
+            }
+        }
+    }
+    Ok(())
+}
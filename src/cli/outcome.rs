@@ -0,0 +1,181 @@
+//! The final `RESULT` line and exit status of a run, for scripts driving the binary.
+
+use std::process::ExitCode;
+
+use serde::Serialize;
+
+use super::{CliError, Format};
+use crate::data::Circuit;
+
+/// How a run ended, and the exit code it maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Everything that was checked passed.
+    Ok,
+    /// Some products did not match, or `compare` found regressions.
+    Fail,
+    /// The dump or a report was missing or corrupt.
+    Error,
+    /// The command line could not be parsed, or its flags ask for something that cannot be
+    /// done.
+    Usage,
+}
+
+impl Status {
+    pub fn code(self) -> u8 {
+        match self {
+            Status::Ok => 0,
+            Status::Fail => 1,
+            Status::Error => 2,
+            Status::Usage => 3,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Fail => "fail",
+            Status::Error => "error",
+            Status::Usage => "usage",
+        }
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status.code())
+    }
+}
+
+impl CliError {
+    /// The status a run failing with this error ends with.
+    pub fn status(&self) -> Status {
+        match self {
+            CliError::VerificationFailed { .. }
+            | CliError::Unsatisfied { .. }
+            | CliError::CompareFailed { .. } => Status::Fail,
+            CliError::InvalidArgs(_) | CliError::WouldOverwrite { .. } => Status::Usage,
+            CliError::ThreadPool(_) => Status::Error,
+            CliError::Data(_)
+            | CliError::WitnessNotFound { .. }
+            | CliError::Report { .. }
+            | CliError::IncompleteDump { .. }
+            | CliError::Incompatible { .. }
+            | CliError::MissingLabels { .. }
+            | CliError::InvalidRelaxed { .. }
+            | CliError::NoWitnesses(_)
+            | CliError::MatrixLoad(_)
+            | CliError::Unconvertible { .. }
+            | CliError::InvalidMatrices(_) => Status::Error,
+        }
+    }
+}
+
+/// Counts accumulated while a subcommand runs, reported even if it fails part way.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    /// Number of matrices loaded.
+    pub matrices: usize,
+    /// Number of witnesses fully processed.
+    pub witnesses: usize,
+    /// Number of products that did not match, or of flagged `compare` entries.
+    pub mismatches: usize,
+    /// The outcome of each circuit, for `--circuit both`; the counts above are their totals.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub circuits: Vec<CircuitResult>,
+    /// The outcome of each dump, for `bench` over several hashes; the counts above are
+    /// their totals.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hashes: Vec<HashResult>,
+}
+
+impl Tally {
+    /// Adds the counts of `other` to these.
+    pub fn add(&mut self, other: &Tally) {
+        self.matrices += other.matrices;
+        self.witnesses += other.witnesses;
+        self.mismatches += other.mismatches;
+    }
+}
+
+/// How one circuit of a `--circuit both` run ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CircuitResult {
+    pub circuit: Circuit,
+    pub result: Status,
+    pub matrices: usize,
+    pub witnesses: usize,
+    pub mismatches: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How one dump of a `bench` run over several hashes ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HashResult {
+    pub hash: String,
+    pub result: Status,
+    #[serde(flatten)]
+    pub tally: Tally,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HashResult {
+    /// Renders the line printed after the dump was benched, as
+    /// `abc: ok matrices=3 witnesses=16 mismatches=0`.
+    pub fn render_text(&self) -> String {
+        format!(
+            "{}: {} matrices={} witnesses={} mismatches={}",
+            self.hash,
+            self.result.as_str(),
+            self.tally.matrices,
+            self.tally.witnesses,
+            self.tally.mismatches
+        )
+    }
+}
+
+impl CircuitResult {
+    /// Renders the line printed after the circuit ran, as
+    /// `primary circuit: ok matrices=3 witnesses=16 mismatches=0`.
+    pub fn render_text(&self) -> String {
+        format!(
+            "{} circuit: {} matrices={} witnesses={} mismatches={}",
+            self.circuit.as_str(),
+            self.result.as_str(),
+            self.matrices,
+            self.witnesses,
+            self.mismatches
+        )
+    }
+}
+
+/// The single line summarizing a run, always printed last to stdout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunResult {
+    pub result: Status,
+    #[serde(flatten)]
+    pub tally: Tally,
+    /// The error the run failed with, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunResult {
+    /// Renders the line as `RESULT ok matrices=3 witnesses=16 mismatches=0`, or as a JSON object.
+    /// The text form leaves out the error, which has already been printed to stderr.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => format!(
+                "RESULT {} matrices={} witnesses={} mismatches={}",
+                self.result.as_str(),
+                self.tally.matrices,
+                self.tally.witnesses,
+                self.tally.mismatches
+            ),
+            Format::Json => serde_json::to_string(self).expect("result line is serializable"),
+        }
+    }
+}
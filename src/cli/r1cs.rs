@@ -0,0 +1,194 @@
+//! The `check-r1cs` subcommand: check that the products of every selected witness satisfy
+//! the R1CS relation `AZ ∘ BZ = CZ`, or with `--relaxed` the relaxed relation
+//! `AZ ∘ BZ = u CZ + E` of a folded instance, computed from the matrices alone, so that no
+//! expected results are needed.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use clap::Args;
+use halo2curves::bn256;
+use rayon::prelude::*;
+
+use super::{load_matrices, read_witness, select_witnesses, CliError, GlobalArgs, HashArgs, Tally};
+use crate::{
+    data::{has_section, list_labels, relaxed_section},
+    diff::DEFAULT_DIFF_LIMIT,
+    hex::field_to_hex,
+    read_arecibo_data_with_format,
+    vec_ops::{add_vec, hadamard, scale_vec},
+    SparseMatrix,
+};
+
+/// Flags of the `check-r1cs` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct R1csArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Number of violated constraints to print per witness that does not satisfy the relation
+    #[arg(long, value_name = "K", default_value_t = DEFAULT_DIFF_LIMIT)]
+    pub limit: usize,
+    /// Check the relaxed relation `AZ ∘ BZ = u CZ + E` instead, reading the error vector `E_i`
+    /// and the scalar `u_i` of each witness, a vector of one element, from `--relaxed-section`
+    #[arg(long)]
+    pub relaxed: bool,
+    /// Section holding the relaxed instances; `relaxed_<HASH>` by default
+    #[arg(long, value_name = "SECTION", requires = "relaxed")]
+    pub relaxed_section: Option<String>,
+}
+
+/// The relaxed instance of a witness.
+struct Relaxed {
+    e: Vec<bn256::Fr>,
+    u: bn256::Fr,
+}
+
+pub(super) fn check_r1cs(
+    global: &GlobalArgs,
+    args: &R1csArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let witnesses = select_witnesses(global, hash)?;
+    let section = match args.relaxed {
+        true => {
+            let section = match &args.relaxed_section {
+                Some(section) => section.clone(),
+                None => relaxed_section(hash),
+            };
+            check_relaxed_labels(&section, &witnesses)?;
+            Some(section)
+        }
+        false => None,
+    };
+    let matrices = load_matrices(global, hash)?;
+    tally.matrices = matrices.len();
+    let [(_, A), (_, B), (_, C)] = &matrices[..] else {
+        return Err(CliError::InvalidArgs(
+            "check-r1cs multiplies A, B, and C, so needs all three selected".to_string(),
+        ));
+    };
+    let relation = match section {
+        Some(_) => "AZ ∘ BZ = u CZ + E",
+        None => "AZ ∘ BZ = CZ",
+    };
+
+    let mut unsatisfied = 0;
+    for &i in &witnesses {
+        let witness = read_witness(hash, i)?;
+        let (az, bz, cz) =
+            SparseMatrix::multiply_vec_fused(A, B, C, &witness).map_err(|source| {
+                CliError::Incompatible {
+                    context: format!("witness {i}"),
+                    source,
+                }
+            })?;
+        let relaxed = match &section {
+            Some(section) => Some(read_relaxed(section, i, cz.len())?),
+            None => None,
+        };
+        let rhs = match &relaxed {
+            Some(Relaxed { e, u }) => Cow::Owned(add_vec(&scale_vec(*u, &cz), e)),
+            None => Cow::Borrowed(&cz[..]),
+        };
+        let violated = violations(&hadamard(&az, &bz), &rhs);
+        tally.witnesses += 1;
+        if violated.is_empty() {
+            println!("witness {i}: satisfied");
+            continue;
+        }
+        match &relaxed {
+            Some(Relaxed { u, .. }) => println!(
+                "witness {i}: NOT satisfied with u = {}, {} of {} constraints violated",
+                field_to_hex(u),
+                violated.len(),
+                cz.len()
+            ),
+            None => println!(
+                "witness {i}: NOT satisfied, {} of {} constraints violated",
+                violated.len(),
+                cz.len()
+            ),
+        }
+        for &row in violated.iter().take(args.limit) {
+            let values = format!(
+                "AZ {}, BZ {}, CZ {}",
+                field_to_hex(&az[row]),
+                field_to_hex(&bz[row]),
+                field_to_hex(&cz[row])
+            );
+            match &relaxed {
+                Some(Relaxed { e, .. }) => {
+                    println!("  [{row}] {values}, E {}", field_to_hex(&e[row]))
+                }
+                None => println!("  [{row}] {values}"),
+            }
+        }
+        if violated.len() > args.limit {
+            println!("  ... and {} more", violated.len() - args.limit);
+        }
+        tally.mismatches += 1;
+        unsatisfied += 1;
+    }
+
+    println!(
+        "checked {} witnesses against {relation}: {} satisfied, {} not",
+        witnesses.len(),
+        witnesses.len() - unsatisfied,
+        unsatisfied
+    );
+    match unsatisfied {
+        0 => Ok(()),
+        witnesses => Err(CliError::Unsatisfied { witnesses }),
+    }
+}
+
+/// Checks that `section` holds `E_i` and `u_i` for each of `witnesses` before anything is
+/// loaded, naming every missing label.
+fn check_relaxed_labels(section: &str, witnesses: &[usize]) -> Result<(), CliError> {
+    let present: HashSet<String> = match has_section(section)? {
+        true => list_labels(section)?.into_iter().collect(),
+        false => HashSet::new(),
+    };
+    let missing: Vec<String> = witnesses
+        .iter()
+        .flat_map(|i| [format!("E_{i}"), format!("u_{i}")])
+        .filter(|label| !present.contains(label))
+        .collect();
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(CliError::MissingLabels {
+            section: section.to_string(),
+            labels: missing,
+        }),
+    }
+}
+
+/// Reads the relaxed instance of witness `i` from `section`, checking that `E_i` has an element
+/// for each of the `rows` constraints and `u_i` a single one.
+fn read_relaxed(section: &str, i: usize, rows: usize) -> Result<Relaxed, CliError> {
+    let e: Vec<bn256::Fr> = read_arecibo_data_with_format(section, format!("E_{i}"))?;
+    if e.len() != rows {
+        return Err(CliError::InvalidRelaxed {
+            label: format!("{section}/E_{i}"),
+            reason: format!("{} elements for {rows} constraints", e.len()),
+        });
+    }
+    let u: Vec<bn256::Fr> = read_arecibo_data_with_format(section, format!("u_{i}"))?;
+    let [u] = u[..] else {
+        return Err(CliError::InvalidRelaxed {
+            label: format!("{section}/u_{i}"),
+            reason: format!("{} elements, not the single one of a scalar", u.len()),
+        });
+    };
+    Ok(Relaxed { e, u })
+}
+
+/// The rows at which `lhs` differs from `rhs`, in order.
+fn violations(lhs: &[bn256::Fr], rhs: &[bn256::Fr]) -> Vec<usize> {
+    lhs.par_iter()
+        .zip(rhs)
+        .enumerate()
+        .filter(|(_, (lhs, rhs))| lhs != rhs)
+        .map(|(row, _)| row)
+        .collect()
+}
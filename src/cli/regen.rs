@@ -0,0 +1,64 @@
+//! The `regen-results` subcommand: recompute the expected products of a dump with the
+//! serial reference kernel.
+
+use clap::Args;
+
+use super::{
+    load_matrices, read_witness, select_witnesses, selected_matrices, CliError, GlobalArgs,
+    HashArgs, Tally,
+};
+use crate::{
+    data::{has_section, list_labels, result_section},
+    write_arecibo_data,
+};
+
+/// Flags of the `regen-results` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct RegenArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Overwrite expected results that already exist
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub(super) fn regen_results(
+    global: &GlobalArgs,
+    args: &RegenArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let section = result_section(hash);
+    let witnesses = select_witnesses(global, hash)?;
+    let matrices = selected_matrices(global);
+
+    if !args.force && has_section(&section)? {
+        let existing = list_labels(&section)?;
+        let clobbered: Vec<_> = witnesses
+            .iter()
+            .flat_map(|&i| matrices.iter().map(move |name| name.product_label(i)))
+            .filter(|label| existing.contains(label))
+            .collect();
+        if !clobbered.is_empty() {
+            return Err(CliError::WouldOverwrite {
+                section,
+                labels: clobbered,
+            });
+        }
+    }
+
+    let matrices = load_matrices(global, hash)?;
+    tally.matrices = matrices.len();
+    for i in witnesses {
+        let witness = read_witness(hash, i)?;
+        let mut labels = Vec::new();
+        for (name, M) in &matrices {
+            let label = name.product_label(i);
+            write_arecibo_data(&section, &label, &M.multiply_vec_serial(&witness))?;
+            labels.push(label);
+        }
+        println!("witness {i}: wrote {}", labels.join(", "));
+        tally.witnesses += 1;
+    }
+    Ok(())
+}
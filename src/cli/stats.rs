@@ -0,0 +1,276 @@
+//! The `stats` subcommand: describe the structure of `A`, `B`, and `C`, and draw it with
+//! `--spy`.
+
+use std::{fs::File, io::BufWriter};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use itertools::Itertools as _;
+use rayon::ThreadPool;
+use serde::Serialize;
+
+use super::{
+    check::{describe_duplicates, print_duplicates},
+    for_each_circuit, format_size, load_matrices_validated, skipped_matrices, to_json,
+    CircuitSelection, CliError, Format, GlobalArgs, HashArgs, MatrixName, Tally,
+};
+use crate::{
+    data::raw::{index_width, raw_matrix_bytes},
+    sparse::DEFAULT_ELL_WIDTH,
+    spy::{Occupancy, DEFAULT_SPY_SIZE},
+    statistics::MatrixStats,
+    DataError,
+};
+
+/// Flags of the `stats` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    /// Also draw where the entries of each matrix are as a grayscale PNG at this path, darker
+    /// where they are denser; with several matrices, each is written to the path with `_A`
+    /// and so on before the extension
+    #[arg(long, value_name = "PATH")]
+    pub spy: Option<Utf8PathBuf>,
+    /// Most pixels across and down a `--spy` image; smaller matrices get one per entry
+    #[arg(
+        long,
+        value_name = "WxH",
+        value_parser = parse_spy_size,
+        default_value_t = SpySize::default()
+    )]
+    pub spy_size: SpySize,
+    /// Most entries per row of the ELL layout whose padding and spilled rows are reported
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_ELL_WIDTH)]
+    pub ell_width: usize,
+}
+
+/// The grid of a `--spy` image, as parsed from `WxH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpySize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for SpySize {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_SPY_SIZE,
+            height: DEFAULT_SPY_SIZE,
+        }
+    }
+}
+
+impl std::fmt::Display for SpySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+fn parse_spy_size(value: &str) -> Result<SpySize, String> {
+    let parse = |side: &str| match side.parse() {
+        Ok(0) | Err(_) => Err(format!("expected WxH with positive sides, not {value}")),
+        Ok(side) => Ok(side),
+    };
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected WxH, as in 1024x768, not {value}"))?;
+    Ok(SpySize {
+        width: parse(width)?,
+        height: parse(height)?,
+    })
+}
+
+#[derive(Serialize)]
+struct NamedStats {
+    name: &'static str,
+    #[serde(flatten)]
+    stats: MatrixStats,
+    /// Size of the matrix as a bincode dump, with 8-byte indices.
+    bincode_bytes: u64,
+    /// Size of the matrix in the raw codec, with `index_bytes`-byte indices.
+    raw_bytes: u64,
+    index_bytes: usize,
+}
+
+/// Runs `stats` on the circuits selected by `args`.
+pub(super) fn run(
+    global: &GlobalArgs,
+    args: &StatsArgs,
+    pool: &ThreadPool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    if args.spy.is_some() && args.dump.circuit == CircuitSelection::Both {
+        return Err(CliError::InvalidArgs(
+            "--spy writes one image per matrix, so takes a single circuit".to_string(),
+        ));
+    }
+    for_each_circuit(
+        args,
+        |args| &mut args.dump,
+        tally,
+        |args, _, tally| stats(global, args, pool, tally),
+    )
+}
+
+/// Prints the statistics of the matrices of `args`, computed on the `--threads` `pool`.
+fn stats(
+    global: &GlobalArgs,
+    args: &StatsArgs,
+    pool: &ThreadPool,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let matrices = load_matrices_validated(global, &args.dump.hash, true)?;
+    tally.matrices = matrices.len();
+
+    let stats: Vec<_> = matrices
+        .iter()
+        .map(|(name, M)| NamedStats {
+            name: name.as_str(),
+            stats: MatrixStats {
+                ell: M.ell_shape(args.ell_width),
+                ..MatrixStats::new_in_pool(M, pool)
+            },
+            bincode_bytes: bincode::serialized_size(M).expect("matrices always serialize"),
+            raw_bytes: raw_matrix_bytes(M),
+            index_bytes: index_width(&M.indices).max(index_width(&M.indptr)),
+        })
+        .collect();
+
+    match global.format {
+        Format::Text => {
+            for (
+                NamedStats {
+                    name,
+                    stats,
+                    bincode_bytes,
+                    raw_bytes,
+                    index_bytes,
+                },
+                (_, M),
+            ) in stats.iter().zip(&matrices)
+            {
+                println!(
+                    "{name}: {} x {} with {} nonzeros, density {:.3e}",
+                    stats.rows, stats.cols, stats.nnz, stats.density
+                );
+                println!(
+                    "   nonzeros per row: min {}, max {}, mean {:.2}; {} empty rows",
+                    stats.min_row_nnz, stats.max_row_nnz, stats.mean_row_nnz, stats.empty_rows
+                );
+                let distribution = &stats.row_distribution;
+                println!(
+                    "   rows by nonzeros: {}",
+                    distribution.histogram.iter().join(", ")
+                );
+                println!(
+                    "   imbalance: gini {:.3}; the {} densest rows hold {:.1}% of nonzeros",
+                    distribution.imbalance,
+                    distribution.top_rows,
+                    100.0 * distribution.top_share
+                );
+                println!(
+                    "   coefficients: {} are 1 and {} are -1, {:.1}% of nonzeros",
+                    stats.ones,
+                    stats.minus_ones,
+                    unit_percent(stats)
+                );
+                println!(
+                    "   explicit zeros: {}, {:.1}% of nonzeros",
+                    stats.explicit_zeros,
+                    percent(stats.explicit_zeros, stats.nnz)
+                );
+                if stats.duplicates > 0 {
+                    let duplicates = M.find_duplicates();
+                    println!("   duplicates: {}", describe_duplicates(&duplicates));
+                    print_duplicates(&duplicates, "     ");
+                }
+                println!(
+                    "   structure: {}, {} pattern; {} of {} diagonal entries nonzero",
+                    match (stats.lower_triangular, stats.upper_triangular) {
+                        (true, true) => "diagonal",
+                        (true, false) => "lower triangular",
+                        (false, true) => "upper triangular",
+                        (false, false) => "not triangular",
+                    },
+                    if stats.symmetric_pattern {
+                        "symmetric"
+                    } else {
+                        "asymmetric"
+                    },
+                    stats.diagonal_nonzeros,
+                    stats.rows.min(stats.cols)
+                );
+                println!(
+                    "   ell of width {}: {} padding slots, {:.1}% of the array; {} rows \
+                     spilled, {:.1}% of nonzeros",
+                    stats.ell.width,
+                    stats.ell.padding,
+                    percent(stats.ell.padding, stats.ell.slots(stats.rows)),
+                    stats.ell.spilled_rows,
+                    percent(stats.ell.spilled_nnz, stats.nnz)
+                );
+                println!("   memory: {}", format_size(stats.memory_bytes as u64));
+                println!(
+                    "   disk: {} as bincode, {} raw with {index_bytes}-byte indices",
+                    format_size(*bincode_bytes),
+                    format_size(*raw_bytes)
+                );
+            }
+            for name in skipped_matrices(global) {
+                println!("{name}: skipped, not selected by --matrices");
+            }
+        }
+        Format::Json => println!("{}", to_json(&stats)),
+    }
+
+    if let Some(path) = &args.spy {
+        for (name, M) in &matrices {
+            let target = match matrices.len() {
+                1 => path.clone(),
+                _ => spy_path(path, *name),
+            };
+            let occupancy = Occupancy::new(M, args.spy_size.width, args.spy_size.height);
+            let io_error = |source| DataError::Io {
+                path: target.clone(),
+                source,
+            };
+            let file = File::create(&target).map_err(io_error)?;
+            occupancy
+                .write_png(BufWriter::new(file))
+                .map_err(io_error)?;
+            // JSON output is the stats alone, so where the images went goes to stderr.
+            let message = format!(
+                "{name}: drew {target} ({}x{})",
+                occupancy.width, occupancy.height
+            );
+            match global.format {
+                Format::Text => println!("{message}"),
+                Format::Json => eprintln!("{message}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Percentage of the nonzeros of a matrix that are 1 or −1.
+fn unit_percent(stats: &MatrixStats) -> f64 {
+    percent(stats.ones + stats.minus_ones, stats.nnz)
+}
+
+/// `part` as a percentage of `whole`, or 0 of nothing.
+fn percent(part: usize, whole: usize) -> f64 {
+    match whole {
+        0 => 0.0,
+        whole => 100.0 * part as f64 / whole as f64,
+    }
+}
+
+/// `path` with `_<name>` before its extension, as in `spy_A.png` for `spy.png`.
+fn spy_path(path: &Utf8Path, name: MatrixName) -> Utf8PathBuf {
+    let file_name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => format!("{stem}_{name}.{extension}"),
+        _ => format!("{}_{name}", path.file_name().unwrap_or_default()),
+    };
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,310 @@
+//! The `verify` subcommand: check every selected witness without timing anything.
+
+use camino::Utf8PathBuf;
+use clap::Args;
+use ff::Field as _;
+use halo2curves::bn256;
+
+use super::{
+    diff_against, diff_products, diff_streamed, load_matrices, multiply_all, multiply_fused,
+    multiply_streamed, parse_coefficient, read_expected_products, read_witness, select_witnesses,
+    selected_matrices, summarize_failures, Backend, CliError, DiffArgs, Failure, GlobalArgs,
+    HashArgs, Matrices, MatrixName, Products, Tally,
+};
+use crate::{
+    data::{arecibo_file_path, matrices_section},
+    diff::diff_vectors,
+    hex::field_to_hex,
+    sparse::{
+        ChunkedMatrix, MatrixError, TaggedMatrix, CHUNKED_EXTENSION, DEFAULT_PRETTY_COLS,
+        DEFAULT_PRETTY_ROWS,
+    },
+    DataError, SparseMatrix,
+};
+
+/// Flags of the `verify` subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub dump: HashArgs,
+    #[command(flatten)]
+    pub diff: DiffArgs,
+    /// Check the parallel kernel against the serial reference instead of the dumped results
+    #[arg(long)]
+    pub cross_check: bool,
+    /// Compute one product at a time and compare it as its expected result is streamed from
+    /// disk, never holding that result whole; slower, but memory stays bounded by the matrices,
+    /// one witness, and one product
+    #[arg(long, conflicts_with = "cross_check")]
+    pub low_memory: bool,
+    /// Multiply the `<label>.chunked` files written by `convert --to chunked`, reading one chunk
+    /// of rows at a time, and stream the expected results as `--low-memory` does; memory stays
+    /// bounded by one witness, one product, and one chunk, even for matrices too big to load
+    #[arg(long, conflicts_with_all = ["cross_check", "low_memory"])]
+    pub chunked: bool,
+    /// Check one product per witness, `(A + r B + r^2 C) z`, against the same combination of
+    /// the expected results, instead of each product on its own; `r` is decimal or `0x` hex,
+    /// and of the matrices selected, the k-th is scaled by `r^k`
+    #[arg(
+        long,
+        value_name = "R",
+        value_parser = parse_coefficient,
+        conflicts_with_all = ["cross_check", "low_memory", "chunked"]
+    )]
+    pub combined: Option<bn256::Fr>,
+    /// Check the fused kernel, which computes `AZ`, `BZ`, and `CZ` in one pass, against the
+    /// separate products of the parallel kernel instead of the dumped results; they must be
+    /// bit-identical
+    #[arg(long, conflicts_with_all = ["cross_check", "low_memory", "chunked", "combined"])]
+    pub fused: bool,
+    /// Check the tagged kernel, which adds and subtracts the entries of 1 and -1 rather than
+    /// multiplying them, against the products of the parallel kernel instead of the dumped
+    /// results; they must be bit-identical
+    #[arg(long, conflicts_with_all = ["cross_check", "low_memory", "chunked", "combined", "fused"])]
+    pub tagged: bool,
+}
+
+pub(super) fn verify(
+    global: &GlobalArgs,
+    args: &VerifyArgs,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let witnesses = select_witnesses(global, hash)?;
+    if let Some(r) = args.combined {
+        return verify_combined(global, args, &witnesses, r, tally);
+    }
+    let (matrices, chunked) = if args.chunked {
+        (Vec::new(), open_chunked(global, hash)?)
+    } else {
+        (load_matrices(global, hash)?, Vec::new())
+    };
+    tally.matrices = matrices.len() + chunked.len();
+    // `--tagged` tags every matrix once, not once per witness.
+    let tagged: Vec<_> = match args.tagged {
+        true => matrices.iter().map(|(_, M)| M.tag_coefficients()).collect(),
+        false => Vec::new(),
+    };
+
+    let mut failures = Vec::new();
+    let mut failed_witnesses = 0;
+    // The first witness that does not fit the matrices, reported once the rest are verified.
+    let mut incompatible = None;
+    for &i in &witnesses {
+        let witness = read_witness(hash, i)?;
+        let failed = verify_witness(args, i, &witness, &matrices, &chunked, &tagged);
+        let failed = match failed {
+            Err(err @ CliError::Incompatible { .. }) => {
+                println!("witness {i}: FAIL ({err})");
+                tally.witnesses += 1;
+                failed_witnesses += 1;
+                incompatible.get_or_insert(err);
+                continue;
+            }
+            failed => failed?,
+        };
+        tally.witnesses += 1;
+        tally.mismatches += failed.len();
+        if failed.is_empty() {
+            println!("witness {i}: PASS");
+        } else {
+            let labels: Vec<_> = failed.iter().map(|f| f.matrix.product()).collect();
+            println!("witness {i}: FAIL ({})", labels.join(", "));
+            print_small_matrices(&failed, &matrices);
+            failed_witnesses += 1;
+        }
+        failures.extend(failed);
+    }
+
+    println!(
+        "verified {} witnesses: {} passed, {} failed, {} mismatching vectors",
+        witnesses.len(),
+        witnesses.len() - failed_witnesses,
+        failed_witnesses,
+        failures.len()
+    );
+    let summary = summarize_failures(&failures);
+    incompatible.map_or(summary, Err)
+}
+
+/// Multiplies `witness`, witness `i`, as `args` ask, and returns the products that differ
+/// from what they are checked against.
+fn verify_witness(
+    args: &VerifyArgs,
+    i: usize,
+    witness: &[bn256::Fr],
+    matrices: &Matrices,
+    chunked: &[(MatrixName, Utf8PathBuf, ChunkedMatrix)],
+    tagged: &[TaggedMatrix<'_, bn256::Fr>],
+) -> Result<Vec<Failure>, CliError> {
+    let hash = &args.dump.hash;
+    if args.chunked {
+        return multiply_chunked(hash, i, chunked, witness, &args.diff);
+    }
+    if args.low_memory {
+        return multiply_streamed(hash, i, matrices, witness, true, &args.diff, |name, M| {
+            let product = Backend::Parallel.multiply_vec(M, witness);
+            product.map_err(|err| name.invalid(err))
+        });
+    }
+    let products = multiply_all(matrices, witness, Backend::Parallel)?;
+    Ok(if args.cross_check {
+        let expected = multiply_all(matrices, witness, Backend::Serial)?;
+        diff_against(i, &products, &expected, &args.diff)
+    } else if args.fused {
+        let fused = multiply_fused(matrices, witness)?;
+        diff_against(i, &fused, &products, &args.diff)
+    } else if args.tagged {
+        let tagged: Products = matrices
+            .iter()
+            .zip(tagged)
+            .map(|((name, _), M)| (*name, M.multiply_vec(witness)))
+            .collect();
+        diff_against(i, &tagged, &products, &args.diff)
+    } else {
+        diff_products(hash, i, &products, &args.diff)?
+    })
+}
+
+/// Checks the product of the random combination of the matrices by `r` against the same
+/// combination of their expected results, for each of `witnesses`.
+fn verify_combined(
+    global: &GlobalArgs,
+    args: &VerifyArgs,
+    witnesses: &[usize],
+    r: bn256::Fr,
+    tally: &mut Tally,
+) -> Result<(), CliError> {
+    let hash = &args.dump.hash;
+    let matrices = load_matrices(global, hash)?;
+    tally.matrices = matrices.len();
+    let coefficients: Vec<bn256::Fr> =
+        std::iter::successors(Some(bn256::Fr::ONE), |power| Some(*power * r))
+            .take(matrices.len())
+            .collect();
+    let combination: Vec<_> = matrices
+        .iter()
+        .map(|(_, M)| M)
+        .zip(coefficients.iter().copied())
+        .collect();
+    let terms: Vec<_> = matrices
+        .iter()
+        .enumerate()
+        .map(|(k, (name, _))| match k {
+            0 => name.as_str().to_string(),
+            1 => format!("r {}", name.as_str()),
+            k => format!("r^{k} {}", name.as_str()),
+        })
+        .collect();
+    let label = terms.join(" + ");
+
+    let mut failed_witnesses = 0;
+    for &i in witnesses {
+        let witness = read_witness(hash, i)?;
+        let actual =
+            SparseMatrix::multiply_vec_combined(&combination, &witness).map_err(|source| {
+                CliError::Incompatible {
+                    context: format!("witness {i}"),
+                    source,
+                }
+            })?;
+        let products = read_expected_products(hash, matrices.iter().map(|(name, _)| *name), i)?;
+        // A product of the wrong length makes the combination as long as the longest.
+        let len = products.iter().map(|(_, product)| product.len()).max();
+        let mut expected = vec![bn256::Fr::ZERO; len.unwrap_or(0)];
+        for ((_, product), coefficient) in products.iter().zip(&coefficients) {
+            for (sum, value) in expected.iter_mut().zip(product) {
+                *sum += *coefficient * value;
+            }
+        }
+
+        let diff = diff_vectors(&actual, &expected, args.diff.diff_limit);
+        tally.witnesses += 1;
+        if diff.is_equal() {
+            println!("witness {i}: PASS");
+        } else {
+            println!("witness {i}: FAIL ({label})\nwitness {i} combined: {diff}");
+            tally.mismatches += 1;
+            failed_witnesses += 1;
+        }
+    }
+
+    println!(
+        "verified {} witnesses against {label} with r = {}: {} passed, {} failed",
+        witnesses.len(),
+        field_to_hex(&r),
+        witnesses.len() - failed_witnesses,
+        failed_witnesses
+    );
+    match failed_witnesses {
+        0 => Ok(()),
+        mismatches => Err(CliError::VerificationFailed { mismatches }),
+    }
+}
+
+/// Draws the matrix of every failed product that is small enough to draw whole, so that a
+/// mismatch on a test fixture can be worked out by hand.
+fn print_small_matrices(failed: &[Failure], matrices: &Matrices) {
+    for failure in failed {
+        let Some((name, M)) = matrices.iter().find(|(name, _)| *name == failure.matrix) else {
+            continue;
+        };
+        let pretty = M.pretty(DEFAULT_PRETTY_ROWS, DEFAULT_PRETTY_COLS);
+        if pretty.is_complete() {
+            println!("{}: {pretty}", name.as_str());
+        }
+    }
+}
+
+/// Opens the chunked file of every selected matrix, reading only their headers.
+fn open_chunked(
+    global: &GlobalArgs,
+    hash: &str,
+) -> Result<Vec<(MatrixName, Utf8PathBuf, ChunkedMatrix)>, CliError> {
+    let section = matrices_section(hash);
+    let mut chunked = Vec::new();
+    for name in selected_matrices(global) {
+        let label = format!("{}.{CHUNKED_EXTENSION}", name.label());
+        let path = arecibo_file_path(&section, label)?;
+        let matrix = ChunkedMatrix::open(&path)
+            .map_err(|source| DataError::from_stream(path.clone(), source))?;
+        let shape = matrix.shape();
+        println!(
+            "opened {}: {} rows in {} chunks of {}",
+            name.as_str(),
+            shape.rows,
+            shape.chunks(),
+            shape.rows_per_chunk
+        );
+        chunked.push((name, path, matrix));
+    }
+    Ok(chunked)
+}
+
+/// Multiplies witness `i` by each chunked matrix in turn, comparing every product against its
+/// expected result before computing the next.
+fn multiply_chunked(
+    hash: &str,
+    i: usize,
+    chunked: &[(MatrixName, Utf8PathBuf, ChunkedMatrix)],
+    witness: &[bn256::Fr],
+    args: &DiffArgs,
+) -> Result<Vec<Failure>, CliError> {
+    let mut failures = Vec::new();
+    for (name, path, matrix) in chunked {
+        if matrix.shape().cols != witness.len() {
+            return Err(CliError::Incompatible {
+                context: name.to_string(),
+                source: MatrixError::VectorLength {
+                    cols: matrix.shape().cols,
+                    len: witness.len(),
+                },
+            });
+        }
+        let actual = matrix
+            .multiply_vec_streaming(witness)
+            .map_err(|source| DataError::from_stream(path.clone(), source))?;
+        failures.extend(diff_streamed(hash, i, *name, &actual, args)?);
+    }
+    Ok(failures)
+}
@@ -0,0 +1,201 @@
+//! # Report Comparison
+//!
+//! Matches the entries of two [`BenchReport`]s by `(witness, matrix)`, and by
+//! thread count when both are thread sweeps, and computes the change in median
+//! duration of each. Entries that exist in only
+//! one report, or matrices whose shapes differ, are reported as [`Mismatch`]es
+//! rather than silently dropped from the comparison.
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use crate::{
+    report::{BenchReport, MatrixTiming},
+    statistics::Summary,
+};
+
+/// Change in median duration of one product between the two reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    pub witness: usize,
+    pub matrix: String,
+    /// Thread count of the entries, when comparing two thread sweeps.
+    pub threads: Option<usize>,
+    pub baseline: Duration,
+    pub candidate: Duration,
+}
+
+impl Delta {
+    /// Relative change from baseline to candidate, in percent; positive means slower.
+    pub fn percent(&self) -> f64 {
+        let baseline = self.baseline.as_nanos() as f64;
+        let candidate = self.candidate.as_nanos() as f64;
+        if baseline == 0.0 {
+            return 0.0;
+        }
+        (candidate - baseline) / baseline * 100.0
+    }
+}
+
+/// A way in which the two reports cannot be compared like for like.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Hash {
+        baseline: String,
+        candidate: String,
+    },
+    Shape {
+        matrix: String,
+        baseline: (usize, usize, usize),
+        candidate: (usize, usize, usize),
+    },
+    MissingInCandidate {
+        witness: usize,
+        matrix: String,
+        threads: Option<usize>,
+    },
+    MissingInBaseline {
+        witness: usize,
+        matrix: String,
+        threads: Option<usize>,
+    },
+}
+
+/// Describes an entry, as in `A witness 3` or `A witness 3 at 8 threads`.
+fn entry(matrix: &str, witness: usize, threads: Option<usize>) -> String {
+    match threads {
+        Some(threads) => format!("{matrix} witness {witness} at {threads} threads"),
+        None => format!("{matrix} witness {witness}"),
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Hash {
+                baseline,
+                candidate,
+            } => write!(f, "hash differs: baseline {baseline}, candidate {candidate}"),
+            Mismatch::Shape {
+                matrix,
+                baseline,
+                candidate,
+            } => write!(
+                f,
+                "{matrix} shape (rows, cols, nnz) differs: baseline {baseline:?}, candidate {candidate:?}"
+            ),
+            Mismatch::MissingInCandidate {
+                witness,
+                matrix,
+                threads,
+            } => write!(
+                f,
+                "{} is missing from the candidate",
+                entry(matrix, *witness, *threads)
+            ),
+            Mismatch::MissingInBaseline {
+                witness,
+                matrix,
+                threads,
+            } => write!(
+                f,
+                "{} is missing from the baseline",
+                entry(matrix, *witness, *threads)
+            ),
+        }
+    }
+}
+
+/// The result of comparing a candidate report against a baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub deltas: Vec<Delta>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+type Medians<'a> = BTreeMap<(&'a str, usize, Option<usize>), Duration>;
+
+/// Median of every entry, keyed by thread count as well if `by_threads`.
+fn medians(report: &BenchReport, by_threads: bool) -> Medians<'_> {
+    let mut medians = BTreeMap::new();
+    for matrix in &report.matrices {
+        for timing in &matrix.witnesses {
+            let durations: Vec<_> = timing
+                .durations_ns
+                .iter()
+                .map(|ns| Duration::from_nanos(*ns))
+                .collect();
+            if let Some(summary) = Summary::from_durations(&durations) {
+                let threads = by_threads.then(|| report.threads_of(timing));
+                medians.insert(
+                    (matrix.name.as_str(), timing.witness, threads),
+                    summary.median,
+                );
+            }
+        }
+    }
+    medians
+}
+
+fn shape(matrix: &MatrixTiming) -> (usize, usize, usize) {
+    (matrix.rows, matrix.cols, matrix.nnz)
+}
+
+impl Comparison {
+    pub fn new(baseline: &BenchReport, candidate: &BenchReport) -> Self {
+        let mut mismatches = Vec::new();
+        if baseline.hash != candidate.hash {
+            mismatches.push(Mismatch::Hash {
+                baseline: baseline.hash.clone(),
+                candidate: candidate.hash.clone(),
+            });
+        }
+        for b in &baseline.matrices {
+            let c = candidate.matrices.iter().find(|c| c.name == b.name);
+            if let Some(c) = c.filter(|c| shape(c) != shape(b)) {
+                mismatches.push(Mismatch::Shape {
+                    matrix: b.name.clone(),
+                    baseline: shape(b),
+                    candidate: shape(c),
+                });
+            }
+        }
+
+        // Reports of single runs match regardless of their thread counts.
+        let by_threads = baseline.is_sweep() && candidate.is_sweep();
+        let baseline = medians(baseline, by_threads);
+        let candidate = medians(candidate, by_threads);
+        let mut deltas = Vec::new();
+        for (&key @ (matrix, witness, threads), &b) in &baseline {
+            match candidate.get(&key) {
+                Some(&c) => deltas.push(Delta {
+                    witness,
+                    matrix: matrix.to_string(),
+                    threads,
+                    baseline: b,
+                    candidate: c,
+                }),
+                None => mismatches.push(Mismatch::MissingInCandidate {
+                    witness,
+                    matrix: matrix.to_string(),
+                    threads,
+                }),
+            }
+        }
+        for &key @ (matrix, witness, threads) in candidate.keys() {
+            if !baseline.contains_key(&key) {
+                mismatches.push(Mismatch::MissingInBaseline {
+                    witness,
+                    matrix: matrix.to_string(),
+                    threads,
+                });
+            }
+        }
+
+        Self { deltas, mismatches }
+    }
+
+    /// The deltas whose median slowed down by more than `threshold` percent.
+    pub fn regressions(&self, threshold: f64) -> impl Iterator<Item = &Delta> {
+        self.deltas.iter().filter(move |d| d.percent() > threshold)
+    }
+}
@@ -0,0 +1,29 @@
+//! The CRC-32 that zip entries are checked with.
+
+/// The CRC-32 of every byte value.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xedb88320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// The CRC-32 of consecutive `parts` of a stream of bytes.
+pub fn crc32<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u32 {
+    let crc = parts.into_iter().flatten().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
@@ -0,0 +1,1637 @@
+//! # Arecibo Data
+//!
+//! This module locates, reads, and writes the data files that arecibo dumps to disk:
+//! sparse matrices, witnesses, and the expected results of multiplying them.
+//! Files are organized as `<root>/<section>/<label>` and encoded with bincode; those this crate
+//! writes start with a [`header`] naming their format, which reads dispatch on.
+//! Reads of large files report their [`progress`] on stderr. Field data can also be stored
+//! with the faster [`raw`] codec. Large files of either encoding are decoded in [`parallel`], and
+//! matrices can be kept in a [`cache`] that loads faster. Files missing from the root can be
+//! fetched from a [`source`] elsewhere, such as a web server.
+
+pub mod atomic;
+pub mod cache;
+pub mod counter;
+pub mod delta;
+pub mod header;
+pub mod lock;
+pub mod manifest;
+pub mod parallel;
+pub mod progress;
+pub mod raw;
+pub mod source;
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bincode::Options as _;
+use camino::{Utf8DirEntry, Utf8Path, Utf8PathBuf};
+use ff::PrimeField;
+use once_cell::sync::OnceCell;
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::SparseMatrix;
+
+pub use atomic::atomic_write;
+pub use cache::{clear_arecibo_cache, read_arecibo_cached, CacheStatus, ContentHash, CACHE_DIR};
+pub use counter::{
+    arecibo_label_counters, counted_label, read_arecibo_data_counted, write_arecibo_data_counted,
+    Counter,
+};
+pub use header::{Detected, ElementType, FileFormat, Header, Tagged, HEADER_BYTES};
+pub use lock::{lock_arecibo_section, FileLock, DEFAULT_LOCK_TIMEOUT, LOCK_FILE};
+pub use manifest::{create_arecibo_manifest, verification_totals, Manifest, MANIFEST_FILE};
+use manifest::{read_verified, verify_file, Hasher, HashingWriter};
+pub use parallel::{ParallelDecode, PARALLEL_DECODE_MIN_BYTES};
+pub use progress::{format_size, ProgressReader, ProgressStyle};
+pub use raw::{IndexEncoding, RawCodec};
+pub use source::{DataSource, LocalSource, REMOTE_DIR};
+
+/// Path to the directory where Arecibo data will be stored.
+pub static ARECIBO_DATA: &str = ".arecibo_data";
+
+/// Environment variable overriding the default data root of `$HOME/.arecibo_data`.
+pub static ARECIBO_DATA_DIR_ENV: &str = "ARECIBO_DATA_DIR";
+
+/// Global configuration for Arecibo data storage, including root directory and counters.
+/// This configuration is initialized on first use.
+pub static ARECIBO_CONFIG: OnceCell<Mutex<DataConfig>> = OnceCell::new();
+
+/// One of the two circuits that arecibo dumps the matrices of, with different dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Circuit {
+    Primary,
+    /// Stored under the hash `<hash>_secondary`, as in `sparse_matrices_<hash>_secondary`.
+    Secondary,
+}
+
+/// What [`Circuit::hash`] appends to the hash of a dump for its secondary circuit.
+const SECONDARY_SUFFIX: &str = "_secondary";
+
+impl Circuit {
+    pub const BOTH: [Circuit; 2] = [Circuit::Primary, Circuit::Secondary];
+
+    /// The hash naming the sections of this circuit of the dump identified by `hash`.
+    pub fn hash(self, hash: &str) -> String {
+        match self {
+            Circuit::Primary => hash.to_string(),
+            Circuit::Secondary => format!("{hash}{SECONDARY_SUFFIX}"),
+        }
+    }
+
+    /// The circuit whose sections `hash` names, and the hash of its dump; the inverse of
+    /// [`Circuit::hash`].
+    pub fn of(hash: &str) -> (Circuit, &str) {
+        match hash.strip_suffix(SECONDARY_SUFFIX) {
+            Some(dump) if !dump.is_empty() => (Circuit::Secondary, dump),
+            _ => (Circuit::Primary, hash),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Circuit::Primary => "primary",
+            Circuit::Secondary => "secondary",
+        }
+    }
+}
+
+/// Prefix of the sections made by [`matrices_section`].
+const MATRICES_PREFIX: &str = "sparse_matrices_";
+
+/// Section holding the `A_0`, `B_0`, and `C_0` matrices of the dump identified by `hash`.
+pub fn matrices_section(hash: &str) -> String {
+    format!("{MATRICES_PREFIX}{hash}")
+}
+
+/// The hash of the dump whose matrices `section` holds, if it is a [`matrices_section`].
+pub fn matrices_hash(section: &str) -> Option<&str> {
+    section.strip_prefix(MATRICES_PREFIX)
+}
+
+/// Section holding the witnesses `_0`, `_1`, ... of the dump identified by `hash`.
+pub fn witness_section(hash: &str) -> String {
+    format!("witness_{hash}")
+}
+
+/// Section holding the expected products `AZ_i`, `BZ_i`, `CZ_i` of the dump identified by `hash`.
+pub fn result_section(hash: &str) -> String {
+    format!("result_{hash}")
+}
+
+/// Section holding the error vectors `E_i` and scalars `u_i`, the latter as vectors of one
+/// element, of the relaxed instances of the dump identified by `hash`.
+pub fn relaxed_section(hash: &str) -> String {
+    format!("relaxed_{hash}")
+}
+
+/// Section holding the cross terms `T_i_j` of the witnesses `_i` and `_j` of the dump
+/// identified by `hash`.
+pub fn cross_term_section(hash: &str) -> String {
+    format!("cross_term_{hash}")
+}
+
+/// Errors that can occur while locating or decoding Arecibo data files.
+#[derive(Debug, Error)]
+pub enum DataError {
+    /// No data root was given and the home directory could not be determined.
+    #[error("no home directory; set {ARECIBO_DATA_DIR_ENV} or pass --data-dir")]
+    NoDataRoot,
+    /// The data root exists but is not a directory.
+    #[error("data root is not a directory: {0}")]
+    NotADirectory(Utf8PathBuf),
+    /// A path is not valid UTF-8 and cannot be used as a data location.
+    #[error("path is not valid UTF-8: {0}")]
+    NonUtf8Path(PathBuf),
+    /// The section directory is missing from the data root.
+    #[error("section directory does not exist: {0}")]
+    SectionNotFound(Utf8PathBuf),
+    /// The section exists but does not contain the requested label.
+    #[error("data file does not exist: {0}")]
+    LabelNotFound(Utf8PathBuf),
+    /// The file exists but could not be opened or read.
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// The file was read but its contents are not a valid encoding of the requested type.
+    /// `offset` is how far into the (decompressed) encoding decoding got, when it is known.
+    #[error("failed to deserialize {path}{}: {source}", at_offset(.offset))]
+    Deserialize {
+        path: Utf8PathBuf,
+        offset: Option<u64>,
+        #[source]
+        source: bincode::Error,
+    },
+    /// The file is not a valid archive, or describes an inconsistent matrix.
+    #[error("{path} is not a valid archive: {message}")]
+    InvalidArchive { path: Utf8PathBuf, message: String },
+    /// The file is not a valid encoding in the raw codec.
+    #[error("{path} is not a valid raw file: {message}")]
+    InvalidRaw { path: Utf8PathBuf, message: String },
+    /// The header of the file is from a later version, or says it holds something other than
+    /// what is being read.
+    #[error("{path} has an unusable header: {message}")]
+    InvalidHeader { path: Utf8PathBuf, message: String },
+    /// The manifest of a section could not be parsed.
+    #[error("{path} is not a valid manifest: {message}")]
+    InvalidManifest { path: Utf8PathBuf, message: String },
+    /// The file differs from the checksum its section's manifest records for it.
+    #[error("{path} does not match its manifest: expected hash {expected}, found {actual}")]
+    ChecksumMismatch {
+        path: Utf8PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// Another process held the lock needed to write the file for longer than `timeout`.
+    #[error("another process holds the lock {path}; gave up after waiting {timeout:?}")]
+    Locked {
+        path: Utf8PathBuf,
+        timeout: Duration,
+    },
+    /// A file could not be fetched from the [`source`] of its section, or the source could
+    /// not be used at all.
+    #[error("cannot fetch {location}: {message}")]
+    Fetch { location: String, message: String },
+    /// The value could not be encoded while writing it to the file.
+    #[error("failed to serialize {path}: {source}")]
+    Serialize {
+        path: Utf8PathBuf,
+        #[source]
+        source: bincode::Error,
+    },
+}
+
+impl DataError {
+    /// The path of the section or file that caused the error, if there is one.
+    pub fn path(&self) -> Option<&Utf8Path> {
+        match self {
+            DataError::NoDataRoot | DataError::NonUtf8Path(_) | DataError::Fetch { .. } => None,
+            DataError::NotADirectory(path)
+            | DataError::SectionNotFound(path)
+            | DataError::LabelNotFound(path) => Some(path),
+            DataError::Io { path, .. }
+            | DataError::Deserialize { path, .. }
+            | DataError::InvalidArchive { path, .. }
+            | DataError::InvalidRaw { path, .. }
+            | DataError::InvalidHeader { path, .. }
+            | DataError::InvalidManifest { path, .. }
+            | DataError::ChecksumMismatch { path, .. }
+            | DataError::Locked { path, .. }
+            | DataError::Serialize { path, .. } => Some(path),
+        }
+    }
+
+    /// Wraps an error met while decoding a stream opened with [`DataConfig::open_vector`]:
+    /// invalid or truncated data is corrupt, anything else failed to read.
+    pub fn from_stream(path: Utf8PathBuf, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => DataError::Deserialize {
+                path,
+                offset: None,
+                source: Box::new(bincode::ErrorKind::Io(source)),
+            },
+            _ => DataError::Io { path, source },
+        }
+    }
+
+    /// Sorts a bincode failure `offset` bytes into the file into an I/O error or a decoding
+    /// error. A truncated file surfaces as `UnexpectedEof`, which is a problem with the data,
+    /// not the disk.
+    fn from_bincode(path: Utf8PathBuf, offset: Option<u64>, err: bincode::Error) -> Self {
+        match *err {
+            bincode::ErrorKind::Io(source) if source.kind() != io::ErrorKind::UnexpectedEof => {
+                DataError::Io { path, source }
+            }
+            _ => DataError::Deserialize {
+                path,
+                offset,
+                source: err,
+            },
+        }
+    }
+}
+
+/// How data files are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadPath {
+    /// Stream the file through a [`BufReader`].
+    #[default]
+    Buffered,
+    /// Map the file into memory and decode the mapped bytes, falling back to
+    /// [`ReadPath::Buffered`] for files that cannot be mapped.
+    Mmap,
+}
+
+/// How field data files are encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    /// The bincode files that arecibo dumps, stored as `<label>`.
+    #[default]
+    Bincode,
+    /// The [`raw`] codec, stored as `<label>.raw`.
+    Raw,
+}
+
+/// Configuration for managing Arecibo data files, including the root directory, the
+/// [`source`]s that files missing from it are fetched from, and the witness and cross-term
+/// [`counter`]s that labels written more than once are numbered with.
+#[derive(Debug, Clone, Default)]
+pub struct DataConfig {
+    root_dir: Utf8PathBuf,
+    source: Option<Arc<dyn DataSource>>,
+    section_sources: BTreeMap<String, Arc<dyn DataSource>>,
+    read_path: ReadPath,
+    compress: bool,
+    format: DataFormat,
+    parallel_decode_min: u64,
+    verify_files: bool,
+    lock_timeout: Duration,
+    witness_counter: usize,
+    cross_term_counter: usize,
+}
+
+impl DataConfig {
+    /// Creates a configuration rooted at `root_dir`, without touching the filesystem.
+    pub fn new(root_dir: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            root_dir: root_dir.into(),
+            source: None,
+            section_sources: BTreeMap::new(),
+            read_path: ReadPath::default(),
+            compress: false,
+            format: DataFormat::default(),
+            parallel_decode_min: PARALLEL_DECODE_MIN_BYTES,
+            verify_files: false,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            witness_counter: 0,
+            cross_term_counter: 0,
+        }
+    }
+
+    /// Makes [`DataConfig::read_with_format`] read files encoded in `format`.
+    pub fn with_format(self, format: DataFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Makes [`DataConfig::read_with_format`] decode uncompressed bincode files of at least
+    /// `bytes` in parallel, rather than [`PARALLEL_DECODE_MIN_BYTES`]; `u64::MAX` never does.
+    pub fn with_parallel_decode_min(self, bytes: u64) -> Self {
+        Self {
+            parallel_decode_min: bytes,
+            ..self
+        }
+    }
+
+    /// How [`DataConfig::read_with_format`] expects files to be encoded.
+    pub fn format(&self) -> DataFormat {
+        self.format
+    }
+
+    /// Makes [`DataConfig::write`] compress with zstd, storing `label` as `label.zst`.
+    pub fn with_compression(self, compress: bool) -> Self {
+        Self { compress, ..self }
+    }
+
+    /// Makes [`DataConfig::read`] use `read_path`.
+    pub fn with_read_path(self, read_path: ReadPath) -> Self {
+        Self { read_path, ..self }
+    }
+
+    /// How [`DataConfig::read`] reads files.
+    pub fn read_path(&self) -> ReadPath {
+        self.read_path
+    }
+
+    /// Creates a configuration rooted at `root_dir`, creating the directory if it does not exist.
+    pub fn open(root_dir: impl Into<Utf8PathBuf>) -> Result<Self, DataError> {
+        let root_dir = root_dir.into();
+        if !root_dir.exists() {
+            fs::create_dir_all(&root_dir).map_err(|source| DataError::Io {
+                path: root_dir.clone(),
+                source,
+            })?;
+        } else if !root_dir.is_dir() {
+            return Err(DataError::NotADirectory(root_dir));
+        }
+
+        Ok(Self::new(root_dir))
+    }
+
+    /// Picks the data root with precedence `data_dir` > `$ARECIBO_DATA_DIR` > `$HOME/.arecibo_data`,
+    /// and opens it with [`DataConfig::open`]. A root given as a URL is fetched from with
+    /// [`source::open_source`], into a directory for it under [`REMOTE_DIR`] of the local
+    /// root that would be picked without it.
+    pub fn resolve(data_dir: Option<Utf8PathBuf>) -> Result<Self, DataError> {
+        let env_dir = std::env::var_os(ARECIBO_DATA_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| utf8_path(PathBuf::from(dir)))
+            .transpose()?;
+        let (url, env_dir) = match (data_dir, env_dir) {
+            (Some(dir), env_dir) if source::is_url(dir.as_str()) => (Some(dir), env_dir),
+            (Some(dir), _) => return Self::open(dir),
+            (None, Some(dir)) if source::is_url(dir.as_str()) => (Some(dir), None),
+            (None, env_dir) => (None, env_dir),
+        };
+        let local_dir = match env_dir.filter(|dir| !source::is_url(dir.as_str())) {
+            Some(dir) => dir,
+            None => utf8_path(
+                home::home_dir()
+                    .ok_or(DataError::NoDataRoot)?
+                    .join(ARECIBO_DATA),
+            )?,
+        };
+        let Some(url) = url else {
+            return Self::open(local_dir);
+        };
+
+        // Open the source first, so that an unsupported URL fails before the cache is made.
+        let source = source::open_source(url.as_str())?;
+        let root_dir = local_dir
+            .join(REMOTE_DIR)
+            .join(source::cache_name(url.as_str()));
+        Ok(Self::open(root_dir)?.with_source(source))
+    }
+
+    /// The directory all sections are resolved against.
+    pub fn root_dir(&self) -> &Utf8Path {
+        &self.root_dir
+    }
+
+    /// Whether `section` exists under the data root, or can be fetched from its source.
+    pub fn has_section(&self, section: impl AsRef<Utf8Path>) -> bool {
+        let section = section.as_ref();
+        self.root_dir.join(section).is_dir() || matches!(self.fetch_listing(section), Ok(true))
+    }
+
+    /// Reads and deserializes the file stored under `section/label`.
+    pub fn read<T: DeserializeOwned + Tagged>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<T, DataError> {
+        read_verified(self.verify_files, self.file_path(section, label)?, |path| {
+            read_file(path, self.read_path)
+        })
+    }
+
+    /// Reads the raw file `section/<label>.raw`.
+    pub fn read_raw<T: ParallelDecode + RawCodec + Tagged>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<T, DataError> {
+        let file_path = self.file_path(section, raw_label(label))?;
+        read_verified(self.verify_files, file_path, |path| {
+            read_file_parallel(path, self.read_path, self.parallel_decode_min)
+        })
+    }
+
+    /// Reads `section/label` like [`DataConfig::read`] or [`DataConfig::read_raw`], by the
+    /// configured [`DataFormat`], which picks the file; it is decoded as its header says.
+    /// Large bincode files are decoded with [`ParallelDecode`].
+    pub fn read_with_format<T: ParallelDecode + RawCodec + Tagged>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<T, DataError> {
+        match self.format {
+            DataFormat::Bincode => {
+                read_verified(self.verify_files, self.file_path(section, label)?, |path| {
+                    read_file_parallel(path, self.read_path, self.parallel_decode_min)
+                })
+            }
+            DataFormat::Raw => self.read_raw(section, label),
+        }
+    }
+
+    /// The path of the existing file stored under `section/label`, first fetching it from the
+    /// source of the section if it is missing.
+    pub fn file_path(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let (section, label) = (section.as_ref(), label.as_ref());
+        let section_path = self.root_dir.join(section);
+        let fetchable = self.fetch_listing(section)?;
+        if !section_path.exists() {
+            return Err(DataError::SectionNotFound(section_path));
+        }
+
+        // A label repeated with a counter is a different label; see `DataConfig::read_counted`.
+        let file_path = section_path.join(label);
+        if file_path.exists() {
+            return Ok(file_path);
+        }
+        let compressed = compressed_path(&file_path);
+        if compressed.exists() {
+            return Ok(compressed);
+        }
+        if fetchable {
+            if let Some(fetched) = self.fetch(section, label)? {
+                return Ok(fetched);
+            }
+        }
+        Err(DataError::LabelNotFound(file_path))
+    }
+
+    /// Lists the numeric suffixes of the labels in `section` that are named `<prefix><N>`, sorted.
+    /// Labels that do not match the pattern exactly (including zero-padded numbers) are ignored,
+    /// and those its source could fetch are included.
+    pub fn label_indices(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        prefix: &str,
+    ) -> Result<Vec<usize>, DataError> {
+        let section = section.as_ref();
+        let fetchable = self.fetchable_files(section)?;
+        let section_path = self.root_dir.join(section);
+        if !section_path.is_dir() {
+            return Err(DataError::SectionNotFound(section_path));
+        }
+
+        let mut indices = Vec::new();
+        for entry in read_dir(&section_path)? {
+            let label = stored_label(entry.file_name());
+            indices.extend(label.strip_prefix(prefix).and_then(parse_index));
+        }
+        for name in &fetchable {
+            indices.extend(
+                stored_label(name)
+                    .strip_prefix(prefix)
+                    .and_then(parse_index),
+            );
+        }
+        indices.sort_unstable();
+        indices.dedup();
+
+        Ok(indices)
+    }
+
+    /// Lists the sections under the root with their file counts and sizes, sorted naturally.
+    /// Entries of the root that are not directories are ignored. Sources cannot be listed, so
+    /// sections not fetched from them yet are missing.
+    pub fn sections(&self) -> Result<Vec<SectionInfo>, DataError> {
+        let mut sections = Vec::new();
+        for entry in read_dir(&self.root_dir)? {
+            if !entry.path().is_dir() || [CACHE_DIR, REMOTE_DIR].contains(&entry.file_name()) {
+                continue;
+            }
+            let mut info = SectionInfo {
+                name: entry.file_name().to_string(),
+                files: 0,
+                bytes: 0,
+            };
+            for file in read_dir(entry.path())? {
+                let metadata = file.path().metadata().map_err(|source| DataError::Io {
+                    path: file.path().to_owned(),
+                    source,
+                })?;
+                if metadata.is_file() && is_data_file(file.file_name()) {
+                    info.files += 1;
+                    info.bytes += metadata.len();
+                }
+            }
+            sections.push(info);
+        }
+        sections.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+
+        Ok(sections)
+    }
+
+    /// Lists the labels stored in `section`, sorted naturally so that `_10` follows `_9`.
+    /// A compressed `A_0.zst` is listed as `A_0`.
+    /// Subdirectories of the section are ignored, and the labels that its source could fetch
+    /// are listed as well.
+    pub fn labels(&self, section: impl AsRef<Utf8Path>) -> Result<Vec<String>, DataError> {
+        let section = section.as_ref();
+        let fetchable = self.fetchable_files(section)?;
+        let section_path = self.root_dir.join(section);
+        if !section_path.is_dir() {
+            return Err(DataError::SectionNotFound(section_path));
+        }
+
+        let mut labels = Vec::new();
+        for entry in read_dir(&section_path)? {
+            if entry.path().is_file() && is_data_file(entry.file_name()) {
+                labels.push(stored_label(entry.file_name()).to_string());
+            }
+        }
+        labels.extend(fetchable.iter().map(|name| stored_label(name).to_string()));
+        labels.sort_by(|a, b| natural_cmp(a, b));
+        labels.dedup();
+
+        Ok(labels)
+    }
+
+    /// Lists the labels in `section` that match `pattern`, sorted naturally. In the pattern,
+    /// `*` stands for any run of characters and `?` for any one, but neither for a `.`, so
+    /// `_*` matches `_0` and `_10` and not `_0.raw` or `_0.chunked`. With
+    /// [`DataFormat::Raw`], the pattern is matched against the labels of the `.raw` files.
+    pub fn matching_labels(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        pattern: &str,
+    ) -> Result<Vec<String>, DataError> {
+        let raw_suffix = format!(".{}", raw::RAW_EXTENSION);
+        let mut labels: Vec<_> = self
+            .labels(section)?
+            .into_iter()
+            .filter_map(|label| match self.format {
+                DataFormat::Bincode => Some(label),
+                DataFormat::Raw => label.strip_suffix(&raw_suffix).map(str::to_string),
+            })
+            .filter(|label| glob_matches(pattern, label))
+            .collect();
+        labels.sort_by(|a, b| natural_cmp(a, b));
+
+        Ok(labels)
+    }
+
+    /// Reads every label of `section` matching `pattern`, as [`DataConfig::matching_labels`]
+    /// lists them, in parallel; see [`DataConfig::read_labels`]. A pattern matching nothing
+    /// reads nothing.
+    pub fn read_many<T: ParallelDecode + RawCodec + Tagged + Send>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        pattern: &str,
+    ) -> Result<Vec<(String, T)>, DataError> {
+        let labels = self.matching_labels(section.as_ref(), pattern)?;
+        let values = self.read_labels(section, &labels)?;
+
+        Ok(labels.into_iter().zip(values).collect())
+    }
+
+    /// Reads each of `labels` of `section` with [`DataConfig::read_with_format`], decoding the
+    /// files in parallel, and returns them in the same order. The first error met is returned.
+    pub fn read_labels<T: ParallelDecode + RawCodec + Tagged + Send>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        labels: &[impl AsRef<Utf8Path> + Sync],
+    ) -> Result<Vec<T>, DataError> {
+        let section = section.as_ref();
+        labels
+            .par_iter()
+            .map(|label| self.read_with_format(section, label))
+            .collect()
+    }
+
+    /// Detects what the file stored under `section/label` holds, from its header or, for
+    /// legacy files, its first bytes.
+    pub fn detect_format(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<Detected, DataError> {
+        detect_file(&self.file_path(section, label)?)
+    }
+
+    /// Checks that the dump of `hash` holds each of `matrices` (named as in `A`, stored as `A_0`),
+    /// and for each of `witnesses` the witness `_i` and, with `with_results`, its products with
+    /// those matrices (stored as `AZ_i`).
+    /// Every missing file or section is collected instead of stopping at the first.
+    pub fn check_dump(
+        &self,
+        hash: &str,
+        matrices: &[&str],
+        witnesses: &[usize],
+        with_results: bool,
+    ) -> Result<DumpCheck, DataError> {
+        let mut missing = Vec::new();
+        let matrix_labels = matrices.iter().map(|name| format!("{name}_0"));
+        self.find_missing(&matrices_section(hash), matrix_labels, &mut missing)?;
+        let witness_labels = witnesses.iter().map(|i| format!("_{i}"));
+        let witness_entries =
+            self.find_missing(&witness_section(hash), witness_labels, &mut missing)?;
+        let result_entries = if with_results {
+            let result_labels = witnesses
+                .iter()
+                .flat_map(|i| matrices.iter().map(move |name| format!("{name}Z_{i}")));
+            Some(self.find_missing(&result_section(hash), result_labels, &mut missing)?)
+        } else {
+            None
+        };
+
+        Ok(DumpCheck {
+            witness_entries,
+            result_entries,
+            missing,
+        })
+    }
+
+    /// Pushes the paths of `labels` absent from `section`, or of the section itself, onto `missing`.
+    /// Returns the number of entries in the section.
+    fn find_missing(
+        &self,
+        section: &str,
+        labels: impl IntoIterator<Item = String>,
+        missing: &mut Vec<Utf8PathBuf>,
+    ) -> Result<usize, DataError> {
+        if !self.has_section(section) {
+            missing.push(section.into());
+            return Ok(0);
+        }
+        let present: HashSet<_> = self.labels(section)?.into_iter().collect();
+        for label in labels {
+            if !present.contains(&label) {
+                missing.push(Utf8Path::new(section).join(label));
+            }
+        }
+        Ok(present.len())
+    }
+
+    /// Serializes `value` into `section/label`, creating the section directory if needed.
+    /// This is the inverse of [`DataConfig::read`]; returns the path that was written.
+    pub fn write<T: Serialize + Tagged + ?Sized>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        value: &T,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let _lock = self.lock_section(section.as_ref())?;
+        self.write_locked(section, label, value)
+    }
+
+    /// Writes like [`DataConfig::write`], for a caller that already holds the lock of the
+    /// section.
+    fn write_locked<T: Serialize + Tagged + ?Sized>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        value: &T,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let label = Utf8PathBuf::from(label.as_ref());
+        let (label, stale) = match self.compress {
+            true => (compressed_path(&label), label),
+            false => (label.clone(), compressed_path(&label)),
+        };
+        let file_path = self.output_path(section, label)?;
+        let io_error = |source| DataError::Io {
+            path: file_path.clone(),
+            source,
+        };
+        let format = match self.compress {
+            true => FileFormat::Zstd,
+            false => FileFormat::Bincode,
+        };
+        let serialize = |writer: &mut dyn Write| {
+            bincode::serialize_into(writer, value).map_err(|err| match *err {
+                bincode::ErrorKind::Io(source) => io_error(source),
+                _ => DataError::Serialize {
+                    path: file_path.clone(),
+                    source: err,
+                },
+            })
+        };
+
+        let entry = atomic_write(&file_path, |file| {
+            let mut writer = BufWriter::new(HashingWriter {
+                inner: file,
+                hasher: Hasher::default(),
+            });
+            Header::new::<T>(format)
+                .write(&mut writer)
+                .map_err(io_error)?;
+            if self.compress {
+                let mut encoder =
+                    zstd::Encoder::new(&mut writer, COMPRESSION_LEVEL).map_err(io_error)?;
+                serialize(&mut encoder)?;
+                encoder.finish().map_err(io_error)?;
+            } else {
+                serialize(&mut writer)?;
+            }
+            let hashed = writer
+                .into_inner()
+                .map_err(|err| io_error(err.into_error()))?;
+            Ok(hashed.hasher.finish())
+        })?;
+        // The other form of the label would otherwise shadow or be shadowed by this one.
+        let stale = file_path.with_file_name(stale.as_str());
+        match fs::remove_file(&stale) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(io_error(err)),
+            _ => {}
+        }
+        self.record_in_manifest(&file_path, entry, stale.file_name())?;
+
+        Ok(file_path)
+    }
+
+    /// Opens the vector of `F` stored under `section/label` in the configured [`DataFormat`]
+    /// for streaming, returning its path and a reader of its bincode encoding: a `u64` length,
+    /// then the representation of every element. Raw files, told apart by their header, are
+    /// converted on the fly.
+    pub fn open_vector<F: PrimeField>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<(Utf8PathBuf, Box<dyn Read>), DataError> {
+        let file_path = match self.format {
+            DataFormat::Bincode => self.file_path(section, label)?,
+            DataFormat::Raw => self.file_path(section, raw_label(label))?,
+        };
+        let io_error = |source| DataError::Io {
+            path: file_path.clone(),
+            source,
+        };
+        verify_file(self.verify_files, &file_path)?;
+        let (file, detected) = open_detected(&file_path)?;
+        expect_element::<Vec<F>>(&file_path, detected)?;
+        let compressed = match detected.format {
+            FileFormat::Bincode | FileFormat::Raw => false,
+            FileFormat::Zstd => true,
+            _ => return Err(unreadable_format(file_path, detected)),
+        };
+        let mut reader = stream(file, &file_path, compressed).map_err(io_error)?;
+        if detected.format == FileFormat::Raw {
+            let len = raw::read_vector_header::<F>(&mut reader).map_err(|message| {
+                DataError::InvalidRaw {
+                    path: file_path.clone(),
+                    message,
+                }
+            })?;
+            reader = Box::new(io::Cursor::new(len.to_le_bytes()).chain(reader));
+        }
+        Ok((file_path, reader))
+    }
+
+    /// Encodes `value` with the raw codec into `section/<label>.raw`, returning the path written.
+    pub fn write_raw<T: RawCodec + Tagged>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        value: &T,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let header = Header::new::<T>(FileFormat::Raw);
+        self.write_raw_with(section, label, header, |writer| value.write_raw(writer))
+    }
+
+    /// Encodes `matrix` with the raw codec into `section/<label>.raw`, with its column indices
+    /// stored as `encoding` says, returning the path written.
+    pub fn write_raw_matrix<F: PrimeField>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        matrix: &SparseMatrix<F>,
+        encoding: IndexEncoding,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let format = match encoding {
+            IndexEncoding::Plain => FileFormat::Raw,
+            IndexEncoding::Delta => FileFormat::RawDelta,
+        };
+        let header = Header::new::<SparseMatrix<F>>(format);
+        self.write_raw_with(section, label, header, |writer| {
+            matrix.write_raw_encoded(writer, encoding)
+        })
+    }
+
+    fn write_raw_with(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        header: Header,
+        write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let file_path = self.output_path(section, raw_label(label))?;
+        let _lock = self.lock_output(&file_path)?;
+        let entry = atomic_write(&file_path, |file| {
+            let mut writer = BufWriter::new(HashingWriter {
+                inner: file,
+                hasher: Hasher::default(),
+            });
+            let hashed = header
+                .write(&mut writer)
+                .and_then(|()| write(&mut writer))
+                .and_then(|()| writer.into_inner().map_err(|err| err.into_error()))
+                .map_err(|source| DataError::Io {
+                    path: file_path.clone(),
+                    source,
+                })?;
+            Ok(hashed.hasher.finish())
+        })?;
+        self.record_in_manifest(&file_path, entry, None)?;
+
+        Ok(file_path)
+    }
+
+    /// Writes `bytes` as they are to `section/label`, creating the section directory if needed.
+    pub fn write_bytes(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+        bytes: &[u8],
+    ) -> Result<Utf8PathBuf, DataError> {
+        let file_path = self.output_path(section, label)?;
+        let _lock = self.lock_output(&file_path)?;
+        atomic_write(&file_path, |file| {
+            file.write_all(bytes).map_err(|source| DataError::Io {
+                path: file_path.clone(),
+                source,
+            })
+        })?;
+        let mut hasher = Hasher::default();
+        hasher.update(bytes);
+        self.record_in_manifest(&file_path, hasher.finish(), None)?;
+
+        Ok(file_path)
+    }
+
+    /// The path of the file `section/label`, for [`atomic_write`] to write; the section
+    /// directory is created if needed.
+    pub fn output_path(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: impl AsRef<Utf8Path>,
+    ) -> Result<Utf8PathBuf, DataError> {
+        let section_path = self.root_dir.join(section.as_ref());
+        fs::create_dir_all(&section_path).map_err(|source| DataError::Io {
+            path: section_path.clone(),
+            source,
+        })?;
+
+        Ok(section_path.join(label.as_ref()))
+    }
+}
+
+fn utf8_path(path: PathBuf) -> Result<Utf8PathBuf, DataError> {
+    Utf8PathBuf::from_path_buf(path).map_err(DataError::NonUtf8Path)
+}
+
+/// Builds the default global configuration from the environment, see [`DataConfig::resolve`].
+pub fn init_config() -> Result<Mutex<DataConfig>, DataError> {
+    DataConfig::resolve(None).map(Mutex::new)
+}
+
+/// Installs `config` as the global configuration used by [`read_arecibo_data`].
+/// This must happen before the first read; otherwise the rejected config is handed back.
+pub fn set_config(config: DataConfig) -> Result<(), DataConfig> {
+    ARECIBO_CONFIG
+        .set(Mutex::new(config))
+        .map_err(|mutex| mutex.into_inner().unwrap())
+}
+
+/// Reads `section/label` relative to the global [`ARECIBO_CONFIG`].
+/// The config is cloned out of its lock before the file is located, so reads on several
+/// threads run concurrently even while one of them fetches its file from a remote root.
+pub fn read_arecibo_data<T: DeserializeOwned + Tagged>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<T, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+    let file_path = config.file_path(section, label)?;
+
+    read_verified(config.verify_files, file_path, |path| {
+        read_file(path, config.read_path)
+    })
+}
+
+/// Reads every label of `section` matching `pattern` relative to the global
+/// [`ARECIBO_CONFIG`], in parallel and in natural order: `"_*"` reads every witness, and
+/// `"AZ_*"` every expected product of `A`. See [`DataConfig::read_many`].
+pub fn read_arecibo_data_many<T: ParallelDecode + RawCodec + Tagged + Send>(
+    section: impl AsRef<Utf8Path>,
+    pattern: &str,
+) -> Result<Vec<(String, T)>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    // The config is cloned so that the reads do not hold the lock.
+    let config = mutex.lock().unwrap().clone();
+
+    config.read_many(section, pattern)
+}
+
+/// Reads each of `labels` of `section` relative to the global [`ARECIBO_CONFIG`], in
+/// parallel; see [`DataConfig::read_labels`].
+pub fn read_arecibo_labels<T: ParallelDecode + RawCodec + Tagged + Send>(
+    section: impl AsRef<Utf8Path>,
+    labels: &[impl AsRef<Utf8Path> + Sync],
+) -> Result<Vec<T>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.read_labels(section, labels)
+}
+
+/// Reads `section/label` relative to the global [`ARECIBO_CONFIG`] with [`ReadPath::Mmap`],
+/// whatever its configured read path.
+pub fn read_arecibo_data_mmap<T: DeserializeOwned + Tagged>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<T, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+    let file_path = config.file_path(section, label)?;
+
+    read_verified(config.verify_files, file_path, |path| {
+        read_file(path, ReadPath::Mmap)
+    })
+}
+
+/// Reads `section/label` relative to the global [`ARECIBO_CONFIG`] in its configured
+/// [`DataFormat`]; see [`DataConfig::read_with_format`].
+pub fn read_arecibo_data_with_format<T: ParallelDecode + RawCodec + Tagged>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<T, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+    let file_path = match config.format {
+        DataFormat::Bincode => config.file_path(section, label)?,
+        DataFormat::Raw => config.file_path(section, raw_label(label))?,
+    };
+
+    read_verified(config.verify_files, file_path, |path| {
+        read_file_parallel(path, config.read_path, config.parallel_decode_min)
+    })
+}
+
+/// Opens `section/label` relative to the global [`ARECIBO_CONFIG`] for streaming;
+/// see [`DataConfig::open_vector`].
+pub fn open_arecibo_vector<F: PrimeField>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<(Utf8PathBuf, Box<dyn Read>), DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.open_vector::<F>(section, label)
+}
+
+/// Encodes `value` with the raw codec relative to the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::write_raw`].
+pub fn write_arecibo_data_raw<T: RawCodec + Tagged>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+    value: &T,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    // Waiting for the section lock must not hold up other users of the config.
+    let config = mutex.lock().unwrap().clone();
+
+    config.write_raw(section, label, value)
+}
+
+/// Encodes `matrix` with the raw codec relative to the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::write_raw_matrix`].
+pub fn write_arecibo_matrix_raw<F: PrimeField>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+    matrix: &SparseMatrix<F>,
+    encoding: IndexEncoding,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.write_raw_matrix(section, label, matrix, encoding)
+}
+
+/// `label` with the raw extension appended, as in `A_0.raw`.
+fn raw_label(label: impl AsRef<Utf8Path>) -> Utf8PathBuf {
+    format!("{}.{}", label.as_ref(), raw::RAW_EXTENSION).into()
+}
+
+/// Reads the rest of the raw file at `file_path` from `file`, reporting progress on stderr if it
+/// is large, and decodes it.
+fn read_raw_file<T: RawCodec>(file: File, file_path: Utf8PathBuf) -> Result<T, DataError> {
+    let bytes = read_whole(file, &file_path).map_err(|source| DataError::Io {
+        path: file_path.clone(),
+        source,
+    })?;
+    decode_raw(&bytes, file_path)
+}
+
+/// Decodes `bytes`, the raw data of the file at `file_path`.
+fn decode_raw<T: RawCodec>(bytes: &[u8], file_path: Utf8PathBuf) -> Result<T, DataError> {
+    T::read_raw(bytes).map_err(|message| DataError::InvalidRaw {
+        path: file_path,
+        message,
+    })
+}
+
+/// Extension of zstd-compressed data files.
+pub const COMPRESSED_EXTENSION: &str = "zst";
+
+/// Level that [`DataConfig::write`] compresses at; zstd's default.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Bound on how many times larger than its file a compressed dump may decompress to, so that
+/// its decoding can be limited like an uncompressed file's. Dumps of field elements barely
+/// compress, and even their index arrays stay far below this.
+const MAX_COMPRESSION_RATIO: u64 = 1024;
+
+/// `path` with the compressed extension appended, as in `A_0.zst`.
+fn compressed_path(path: &Utf8Path) -> Utf8PathBuf {
+    format!("{path}.{COMPRESSED_EXTENSION}").into()
+}
+
+/// Whether the file `file_name` in a section holds data, rather than being its manifest, its
+/// lock, or the temporary file of a write.
+fn is_data_file(file_name: &str) -> bool {
+    file_name != MANIFEST_FILE && !lock::is_lock_file(file_name) && !atomic::is_temp_file(file_name)
+}
+
+/// The label stored in the file `file_name`, without any compressed extension.
+fn stored_label(file_name: &str) -> &str {
+    file_name
+        .strip_suffix(COMPRESSED_EXTENSION)
+        .and_then(|name| name.strip_suffix('.'))
+        .unwrap_or(file_name)
+}
+
+/// Opens the file at `file_path` and detects what it holds from its [`header`], leaving it
+/// positioned after the header if it has one. A headerless file is taken for zstd by its
+/// extension as well as by its magic bytes.
+fn open_detected(file_path: &Utf8Path) -> Result<(File, Detected), DataError> {
+    let io_error = |source| DataError::Io {
+        path: file_path.to_owned(),
+        source,
+    };
+    let mut file = File::open(file_path).map_err(io_error)?;
+    let (_, detected) = detect_start(&mut file, file_path)?;
+    file.seek(SeekFrom::Start(detected.data_offset()))
+        .map_err(io_error)?;
+    Ok((file, detected))
+}
+
+/// Reads the first bytes of `reader`, the file at `file_path`, and detects what it holds from
+/// them, returning them with what was detected.
+fn detect_start(
+    reader: &mut impl Read,
+    file_path: &Utf8Path,
+) -> Result<(Vec<u8>, Detected), DataError> {
+    let mut start = Vec::with_capacity(HEADER_BYTES);
+    reader
+        .take(HEADER_BYTES as u64)
+        .read_to_end(&mut start)
+        .map_err(|source| DataError::Io {
+            path: file_path.to_owned(),
+            source,
+        })?;
+    let mut detected =
+        header::detect(file_path.as_str(), &start).map_err(|message| DataError::InvalidHeader {
+            path: file_path.to_owned(),
+            message,
+        })?;
+    if detected.header.is_none() && file_path.extension() == Some(COMPRESSED_EXTENSION) {
+        detected.format = FileFormat::Zstd;
+    }
+    Ok((start, detected))
+}
+
+/// Decodes a `T` streamed from `reader`, such as stdin, in whichever format its header names,
+/// or detected from its first bytes like a file's; `name` stands for the file in errors.
+/// A file fails to decode rather than be read past its end, but a stream has no length, so it
+/// fails rather than decode past `limit` bytes instead, counted after decompression.
+pub fn read_stream<T: DeserializeOwned + RawCodec + Tagged>(
+    reader: impl Read,
+    name: impl Into<Utf8PathBuf>,
+    limit: u64,
+) -> Result<T, DataError> {
+    let file_path = name.into();
+    let io_error = |source| DataError::Io {
+        path: file_path.clone(),
+        source,
+    };
+    let mut reader = BufReader::new(reader);
+    let (start, detected) = detect_start(&mut reader, &file_path)?;
+    expect_element::<T>(&file_path, detected)?;
+    // The stream cannot seek back, so what detection read past the header is put in front.
+    let offset = detected.data_offset();
+    let data = io::Cursor::new(start[offset as usize..].to_vec()).chain(reader);
+    match detected.format {
+        FileFormat::Bincode => deserialize_counted(data, limit, file_path, offset),
+        FileFormat::Zstd => {
+            let decoder = zstd::Decoder::new(data).map_err(io_error)?;
+            deserialize_counted(decoder, limit, file_path, 0)
+        }
+        FileFormat::Raw | FileFormat::RawDelta => {
+            let mut bytes = Vec::new();
+            data.take(limit.saturating_add(1))
+                .read_to_end(&mut bytes)
+                .map_err(io_error)?;
+            if bytes.len() as u64 > limit {
+                return Err(DataError::InvalidRaw {
+                    path: file_path,
+                    message: format!("longer than the limit of {limit} bytes"),
+                });
+            }
+            decode_raw(&bytes, file_path)
+        }
+        FileFormat::Chunked | FileFormat::Rkyv | FileFormat::MatrixMarket | FileFormat::Npz => {
+            Err(unreadable_format(file_path, detected))
+        }
+    }
+}
+
+/// Detects what the file at `file_path` holds; see [`header::detect`].
+pub fn detect_file(file_path: &Utf8Path) -> Result<Detected, DataError> {
+    open_detected(file_path).map(|(_, detected)| detected)
+}
+
+/// Reads the file at `file_path`, under a data root or not, decoding it as its header says,
+/// like [`DataConfig::read_with_format`].
+pub fn read_data_file<T: ParallelDecode + RawCodec + Tagged>(
+    file_path: impl Into<Utf8PathBuf>,
+) -> Result<T, DataError> {
+    read_file_parallel(
+        file_path.into(),
+        ReadPath::default(),
+        PARALLEL_DECODE_MIN_BYTES,
+    )
+}
+
+/// Fails unless the file at `file_path`, detected as `detected`, holds a `T`. Files without a
+/// header do not say, and are decoded on trust as before.
+fn expect_element<T: Tagged + ?Sized>(
+    file_path: &Utf8Path,
+    detected: Detected,
+) -> Result<(), DataError> {
+    match detected.header {
+        Some(header) => header
+            .expect::<T>()
+            .map_err(|message| DataError::InvalidHeader {
+                path: file_path.to_owned(),
+                message,
+            }),
+        None => Ok(()),
+    }
+}
+
+/// The error for a file whose format a read cannot decode.
+fn unreadable_format(file_path: Utf8PathBuf, detected: Detected) -> DataError {
+    DataError::InvalidHeader {
+        path: file_path,
+        message: format!("holds {}, which this read does not decode", detected.format),
+    }
+}
+
+/// Deserializes the bincode file at `file_path` by `read_path`. Compressed files, recognized by
+/// their header, extension, or magic bytes, are always streamed through a decoder.
+fn read_file<T: DeserializeOwned + Tagged>(
+    file_path: Utf8PathBuf,
+    read_path: ReadPath,
+) -> Result<T, DataError> {
+    let (file, detected) = open_detected(&file_path)?;
+    expect_element::<T>(&file_path, detected)?;
+    let offset = detected.data_offset();
+    match (detected.format, read_path) {
+        (FileFormat::Zstd, _) => read_buffered(file, file_path, offset, true),
+        (FileFormat::Bincode, ReadPath::Buffered) => read_buffered(file, file_path, offset, false),
+        (FileFormat::Bincode, ReadPath::Mmap) => read_mapped(file, file_path, offset),
+        _ => Err(unreadable_format(file_path, detected)),
+    }
+}
+
+/// Decodes the file at `file_path` in whichever format its header names: raw files with the
+/// raw codec, and bincode like [`read_file`], except that uncompressed files of at least
+/// `parallel_min` bytes are read whole and decoded with [`ParallelDecode`].
+fn read_file_parallel<T: ParallelDecode + RawCodec + Tagged>(
+    file_path: Utf8PathBuf,
+    read_path: ReadPath,
+    parallel_min: u64,
+) -> Result<T, DataError> {
+    let io_error = |source| DataError::Io {
+        path: file_path.clone(),
+        source,
+    };
+    let (file, detected) = open_detected(&file_path)?;
+    expect_element::<T>(&file_path, detected)?;
+    match detected.format {
+        FileFormat::Raw | FileFormat::RawDelta => return read_raw_file(file, file_path),
+        FileFormat::Bincode => {}
+        FileFormat::Zstd => return read_file(file_path, read_path),
+        FileFormat::Chunked | FileFormat::Rkyv | FileFormat::MatrixMarket | FileFormat::Npz => {
+            return Err(unreadable_format(file_path, detected))
+        }
+    }
+    let offset = detected.data_offset();
+    let len = file.metadata().map_err(io_error)?.len() - offset;
+    if len < parallel_min {
+        return read_file(file_path, read_path);
+    }
+
+    let decoded = match read_path {
+        // SAFETY: as in `read_mapped`; the map is dropped once the value is decoded.
+        ReadPath::Mmap => match unsafe { memmap2::Mmap::map(&file) } {
+            Ok(map) => T::decode_parallel(&map[offset as usize..]),
+            Err(err) => {
+                eprintln!("warning: cannot map {file_path} ({err}), reading it buffered");
+                T::decode_parallel(&read_whole(file, &file_path).map_err(io_error)?)
+            }
+        },
+        ReadPath::Buffered => T::decode_parallel(&read_whole(file, &file_path).map_err(io_error)?),
+    };
+    decoded.map_err(|message| {
+        DataError::from_bincode(
+            file_path,
+            None,
+            Box::new(bincode::ErrorKind::Custom(message)),
+        )
+    })
+}
+
+/// Reads all of `file`, reporting progress on stderr if it is large.
+fn read_whole(file: File, file_path: &Utf8Path) -> io::Result<Vec<u8>> {
+    let len = file.metadata()?.len();
+    let mut bytes = Vec::with_capacity(len as usize);
+    if len < progress::PROGRESS_MIN_BYTES {
+        (&file).read_to_end(&mut bytes)?;
+    } else {
+        let label = progress_label(file_path);
+        let style = ProgressStyle::detect();
+        ProgressReader::new(file, label, len, style, io::stderr()).read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// Decodes the mapped bytes of `file` from `offset` on, reading it buffered instead if it
+/// cannot be mapped, as on some network filesystems. No progress is reported for mapped files.
+fn read_mapped<T: DeserializeOwned>(
+    file: File,
+    file_path: Utf8PathBuf,
+    offset: u64,
+) -> Result<T, DataError> {
+    // SAFETY: the map is only sound while no one else truncates or writes to the file.
+    // Dumps are written once by arecibo or `generate` and never modified while being read,
+    // and the map is dropped as soon as the value has been copied out of it.
+    match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(map) => {
+            let bytes = &map[offset as usize..];
+            deserialize_counted(bytes, bytes.len() as u64, file_path, offset)
+        }
+        Err(err) => {
+            eprintln!("warning: cannot map {file_path} ({err}), reading it buffered");
+            read_buffered(file, file_path, offset, false)
+        }
+    }
+}
+
+/// Streams `file` from `offset`, where it is positioned, through a [`BufReader`],
+/// decompressing it if `compressed`, and reporting progress through the file on stderr if it
+/// is large.
+fn read_buffered<T: DeserializeOwned>(
+    file: File,
+    file_path: Utf8PathBuf,
+    offset: u64,
+    compressed: bool,
+) -> Result<T, DataError> {
+    let io_error = |source| DataError::Io {
+        path: file_path.clone(),
+        source,
+    };
+    let len = file.metadata().map_err(io_error)?.len() - offset;
+    let (limit, start) = match compressed {
+        true => (len.saturating_mul(MAX_COMPRESSION_RATIO), 0),
+        false => (len, offset),
+    };
+    let reader = stream(file, &file_path, compressed).map_err(io_error)?;
+    deserialize_counted(reader, limit, file_path, start)
+}
+
+/// Deserializes `reader` as `bincode::deserialize_from` would, except that decoding fails
+/// rather than reading past `limit` bytes, and that a failure records the offset decoding got
+/// to, counted from `start`. Without the limit, a corrupt length prefix makes bincode allocate
+/// as much as it claims before finding out the file is shorter.
+fn deserialize_counted<T: DeserializeOwned>(
+    reader: impl Read,
+    limit: u64,
+    file_path: Utf8PathBuf,
+    start: u64,
+) -> Result<T, DataError> {
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit);
+    let mut counted = Counted {
+        inner: reader,
+        bytes: start,
+    };
+    options
+        .deserialize_from(&mut counted)
+        .map_err(|err| DataError::from_bincode(file_path, Some(counted.bytes), err))
+}
+
+/// A reader that counts the bytes read through it.
+struct Counted<R> {
+    inner: R,
+    bytes: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes += read as u64;
+        Ok(read)
+    }
+}
+
+fn at_offset(offset: &Option<u64>) -> String {
+    offset.map_or(String::new(), |offset| format!(" at byte {offset}"))
+}
+
+/// Buffers `file`, decompressing it if `compressed`, and reporting progress through the file
+/// on stderr if it is large.
+fn stream(file: File, file_path: &Utf8Path, compressed: bool) -> io::Result<Box<dyn Read>> {
+    let len = file.metadata()?.len();
+    let reader: Box<dyn Read> = if len < progress::PROGRESS_MIN_BYTES {
+        Box::new(file)
+    } else {
+        let label = progress_label(file_path);
+        let style = ProgressStyle::detect();
+        Box::new(ProgressReader::new(file, label, len, style, io::stderr()))
+    };
+    Ok(match compressed {
+        true => Box::new(zstd::Decoder::new(reader)?),
+        false => Box::new(BufReader::new(reader)),
+    })
+}
+
+/// Names a file by its section and label, as in `sparse_matrices_abc/A_0`.
+fn progress_label(file_path: &Utf8Path) -> String {
+    let section = file_path.parent().and_then(Utf8Path::file_name);
+    match (section, file_path.file_name()) {
+        (Some(section), Some(label)) => format!("{section}/{label}"),
+        _ => file_path.to_string(),
+    }
+}
+
+/// A section of the data root, as listed by [`DataConfig::sections`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionInfo {
+    pub name: String,
+    /// Number of regular files in the section, besides its manifest.
+    pub files: usize,
+    /// Total size of those files.
+    pub bytes: u64,
+}
+
+/// How complete the dump of one hash is, as found by [`DataConfig::check_dump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpCheck {
+    /// Number of entries in the witness section.
+    pub witness_entries: usize,
+    /// Number of entries in the result section, if results were checked.
+    pub result_entries: Option<usize>,
+    /// Missing sections and labels, relative to the root.
+    pub missing: Vec<Utf8PathBuf>,
+}
+
+impl DumpCheck {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Reads the entries of the directory at `path`.
+fn read_dir(path: &Utf8Path) -> Result<Vec<Utf8DirEntry>, DataError> {
+    let io_error = |source| DataError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    path.read_dir_utf8()
+        .map_err(io_error)?
+        .map(|entry| entry.map_err(io_error))
+        .collect()
+}
+
+/// Compares strings so that runs of ASCII digits are ordered by value: `_2` sorts before `_10`.
+/// Numbers that differ only by leading zeros, such as `_1` and `_01`, fall back to plain string order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_runs, mut b_runs) = (digit_runs(a), digit_runs(b));
+    loop {
+        let (x, y) = match (a_runs.next(), b_runs.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+        let is_number = |run: &str| run.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if is_number(x) && is_number(y) {
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Whether `label` matches the glob `pattern`, whose `*` and `?` never match a `.`; see
+/// [`DataConfig::matching_labels`].
+fn glob_matches(pattern: &str, label: &str) -> bool {
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => label.is_empty(),
+        Some('*') => {
+            // The star takes every prefix of `label` up to its first `.` in turn.
+            let ends = label.char_indices().map(|(i, _)| i).chain([label.len()]);
+            ends.take_while(|&end| !label[..end].contains('.'))
+                .any(|end| glob_matches(chars.as_str(), &label[end..]))
+        }
+        Some(p) => {
+            let mut rest = label.chars();
+            match rest.next() {
+                Some(c) if c == p || (p == '?' && c != '.') => {
+                    glob_matches(chars.as_str(), rest.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Splits `s` into maximal runs of ASCII digits and of everything else.
+fn digit_runs(mut s: &str) -> impl Iterator<Item = &str> {
+    std::iter::from_fn(move || {
+        let digit = s.chars().next()?.is_ascii_digit();
+        let end = s
+            .find(|c: char| c.is_ascii_digit() != digit)
+            .unwrap_or(s.len());
+        let (run, rest) = s.split_at(end);
+        s = rest;
+        Some(run)
+    })
+}
+
+/// Parses a canonical decimal index, rejecting signs and leading zeros.
+fn parse_index(s: &str) -> Option<usize> {
+    let index: usize = s.parse().ok()?;
+    (s == index.to_string()).then_some(index)
+}
+
+/// Returns the indices in `0..=max(indices)` that are absent from the sorted `indices`.
+pub fn index_gaps(indices: &[usize]) -> Vec<usize> {
+    let Some(&last) = indices.last() else {
+        return Vec::new();
+    };
+    (0..last)
+        .filter(|i| indices.binary_search(i).is_err())
+        .collect()
+}
+
+/// Whether `section` exists relative to the global [`ARECIBO_CONFIG`].
+pub fn has_section(section: impl AsRef<Utf8Path>) -> Result<bool, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    Ok(config.has_section(section))
+}
+
+/// Lists the indices of `<prefix><N>` labels in `section` relative to the global [`ARECIBO_CONFIG`].
+pub fn label_indices(section: impl AsRef<Utf8Path>, prefix: &str) -> Result<Vec<usize>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.label_indices(section, prefix)
+}
+
+/// Locates the file of `section/label` relative to the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::file_path`].
+pub fn arecibo_file_path(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.file_path(section, label)
+}
+
+/// The path of `section/label` relative to the global [`ARECIBO_CONFIG`], for
+/// [`atomic_write`] to write; see [`DataConfig::output_path`].
+pub fn arecibo_output_path(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap();
+
+    config.output_path(section, label)
+}
+
+/// Removes the temporary files that interrupted writes left under the root of the global
+/// [`ARECIBO_CONFIG`]; see [`DataConfig::remove_orphaned_temp_files`].
+pub fn remove_arecibo_orphans() -> Result<usize, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap();
+
+    config.remove_orphaned_temp_files()
+}
+
+/// Writes `value` to `section/label` relative to the global [`ARECIBO_CONFIG`].
+/// Returns the path of the written file.
+pub fn write_arecibo_data<T: Serialize + Tagged + ?Sized>(
+    section: impl AsRef<Utf8Path>,
+    label: impl AsRef<Utf8Path>,
+    value: &T,
+) -> Result<Utf8PathBuf, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    // Waiting for the section lock must not hold up other users of the config.
+    let config = mutex.lock().unwrap().clone();
+
+    config.write(section, label, value)
+}
+
+/// Lists the sections under the root of the global [`ARECIBO_CONFIG`].
+pub fn list_sections() -> Result<Vec<SectionInfo>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.sections()
+}
+
+/// Lists the labels in `section` relative to the global [`ARECIBO_CONFIG`], each with what
+/// its file holds; see [`DataConfig::detect_format`].
+pub fn list_label_formats(
+    section: impl AsRef<Utf8Path>,
+) -> Result<Vec<(String, Detected)>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    let section = section.as_ref();
+    config
+        .labels(section)?
+        .into_iter()
+        .map(|label| {
+            let detected = config.detect_format(section, &label)?;
+            Ok((label, detected))
+        })
+        .collect()
+}
+
+/// Lists the labels in `section` relative to the global [`ARECIBO_CONFIG`].
+pub fn list_labels(section: impl AsRef<Utf8Path>) -> Result<Vec<String>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.labels(section)
+}
+
+/// Checks the dump of `hash` relative to the global [`ARECIBO_CONFIG`]; see [`DataConfig::check_dump`].
+pub fn check_dump(
+    hash: &str,
+    matrices: &[&str],
+    witnesses: &[usize],
+    with_results: bool,
+) -> Result<DumpCheck, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap().clone();
+
+    config.check_dump(hash, matrices, witnesses, with_results)
+}
@@ -0,0 +1,136 @@
+//! Atomic replacement of data files, so that an interrupted write never leaves a truncated file
+//! where a complete one is expected.
+//!
+//! [`atomic_write`] writes to a `<name>.<pid>.tmp` sibling of the destination, syncs it, and
+//! only then renames it into place and syncs the directory; the destination holds either the
+//! old contents or the new ones. A write killed halfway leaves its temporary file behind, which
+//! [`DataConfig::remove_orphaned_temp_files`] removes once it has gone untouched for
+//! [`ORPHAN_MIN_AGE`].
+
+use std::{
+    fs::{self, File},
+    io, process,
+    time::{Duration, SystemTime},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{read_dir, DataConfig, DataError, CACHE_DIR};
+
+/// Extension of the temporary files that writes go to before being renamed into place.
+pub const TEMP_EXTENSION: &str = "tmp";
+
+/// How long a temporary file must have gone unmodified before it is taken for the leftover of
+/// an interrupted write rather than one still in progress in another process.
+pub const ORPHAN_MIN_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Writes the file at `path` through `write`, which is handed a fresh temporary file next to
+/// it. The temporary file replaces `path` only once `write` has succeeded and the file has
+/// been synced to disk; on failure it is removed, and `path` is left as it was.
+pub fn atomic_write<T>(
+    path: &Utf8Path,
+    write: impl FnOnce(&mut File) -> Result<T, DataError>,
+) -> Result<T, DataError> {
+    let temp = temp_path(path);
+    let mut file = File::create(&temp).map_err(|source| DataError::Io {
+        path: temp.clone(),
+        source,
+    })?;
+    let io_error = |source| DataError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let written = write(&mut file).and_then(|value| {
+        file.sync_all().map_err(io_error)?;
+        Ok(value)
+    });
+    drop(file);
+    let value = match written.and_then(|value| {
+        fs::rename(&temp, path).map_err(io_error)?;
+        Ok(value)
+    }) {
+        Ok(value) => value,
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            return Err(err);
+        }
+    };
+    sync_dir(path).map_err(io_error)?;
+    Ok(value)
+}
+
+/// Whether `name` is that of a temporary file, which listings of a section skip.
+pub(super) fn is_temp_file(name: &str) -> bool {
+    Utf8Path::new(name).extension() == Some(TEMP_EXTENSION)
+}
+
+/// The temporary sibling of `path` that this process writes it through.
+fn temp_path(path: &Utf8Path) -> Utf8PathBuf {
+    let name = path.file_name().unwrap_or_default();
+    path.with_file_name(format!("{name}.{}.{TEMP_EXTENSION}", process::id()))
+}
+
+/// Syncs the directory holding `path`, so that a rename into it survives a crash.
+#[cfg(unix)]
+fn sync_dir(path: &Utf8Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+/// Directories cannot be opened to be synced on other platforms, whose renames are durable
+/// once they return.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Utf8Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl DataConfig {
+    /// Removes the temporary files that interrupted writes left in the sections of the data
+    /// root and of its cache, returning how many were removed. Files modified within the last
+    /// [`ORPHAN_MIN_AGE`] may belong to a write still in progress, and are kept.
+    pub fn remove_orphaned_temp_files(&self) -> Result<usize, DataError> {
+        if !self.root_dir.is_dir() {
+            return Ok(0);
+        }
+        let mut dirs = Vec::new();
+        for entry in read_dir(&self.root_dir)? {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            if entry.file_name() == CACHE_DIR {
+                for cached in read_dir(entry.path())? {
+                    if cached.path().is_dir() {
+                        dirs.push(cached.into_path());
+                    }
+                }
+            }
+            dirs.push(entry.into_path());
+        }
+
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for dir in dirs {
+            for entry in read_dir(&dir)? {
+                if !is_temp_file(entry.file_name()) {
+                    continue;
+                }
+                let io_error = |source| DataError::Io {
+                    path: entry.path().to_owned(),
+                    source,
+                };
+                let modified = entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .map_err(io_error)?;
+                let age = now.duration_since(modified).unwrap_or_default();
+                if entry.path().is_file() && age >= ORPHAN_MIN_AGE {
+                    fs::remove_file(entry.path()).map_err(io_error)?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
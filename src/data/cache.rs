@@ -0,0 +1,296 @@
+//! An on-disk cache of matrices in the [`raw`](super::raw) codec.
+//!
+//! The first load of `sparse_matrices_<hash>/A_0` also writes `cache/<hash>/A_0.raw` under the
+//! root, next to a `A_0.source.json` stamp recording the size and modification time of the file
+//! it came from and the [content hash](SparseMatrix::content_hash) of what was cached. Later
+//! loads read the cache instead, as long as the stamp still matches; a changed source, or a
+//! cache file that no longer decodes, is rebuilt from the source. With
+//! [`DataConfig::with_verify_files`], a cache file that decodes is also checked against its
+//! content hash, which catches corruption the decoder cannot see.
+
+use std::{
+    fs,
+    io::{self, Write},
+    time::UNIX_EPOCH,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    atomic_write, init_config, lock::is_lock_file, manifest::read_verified, matrices_section,
+    raw_label, read_dir, read_file_parallel, DataConfig, DataError, ParallelDecode, RawCodec,
+    Tagged, ARECIBO_CONFIG,
+};
+use crate::{hex::bytes_to_hex, SparseMatrix};
+
+/// Directory under the root holding the cache, one subdirectory per hash.
+/// [`DataConfig::sections`] does not list it.
+pub const CACHE_DIR: &str = "cache";
+
+/// Where a cached load came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Read from a fresh cache file.
+    Hit,
+    /// Read from the source, which had not been cached yet.
+    Miss,
+    /// Read from the source, which changed since it was cached, or whose cache file is corrupt.
+    Stale,
+}
+
+impl CacheStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "cache hit",
+            CacheStatus::Miss => "cache miss",
+            CacheStatus::Stale => "cache stale",
+        }
+    }
+}
+
+/// Values the cache holds, which it checks by a hash of their contents.
+pub trait ContentHash {
+    fn content_hash(&self) -> [u8; 32];
+}
+
+impl<F: PrimeField> ContentHash for SparseMatrix<F> {
+    fn content_hash(&self) -> [u8; 32] {
+        SparseMatrix::content_hash(self)
+    }
+}
+
+/// What a cache file was built from, and the content hash of what it holds, in hex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheStamp {
+    #[serde(flatten)]
+    source: SourceStamp,
+    content_hash: String,
+}
+
+impl CacheStamp {
+    /// The stamp saved at `path`, if there is one that parses. Stamps written before content
+    /// hashes were saved do not, so their caches are rebuilt.
+    fn saved(path: &Utf8Path) -> Option<Self> {
+        let json = fs::read(path).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Identifies one version of a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct SourceStamp {
+    bytes: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl SourceStamp {
+    fn of(path: &Utf8Path) -> Result<Self, DataError> {
+        let io_error = |source| DataError::Io {
+            path: path.to_owned(),
+            source,
+        };
+        let metadata = path.metadata().map_err(io_error)?;
+        let modified = metadata.modified().map_err(io_error)?;
+        let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(Self {
+            bytes: metadata.len(),
+            modified_secs: since_epoch.as_secs(),
+            modified_nanos: since_epoch.subsec_nanos(),
+        })
+    }
+}
+
+impl DataConfig {
+    /// The cache of the dump `hash`, as in `<root>/cache/<hash>`.
+    pub fn cache_dir(&self, hash: &str) -> Utf8PathBuf {
+        self.root_dir.join(cache_section(hash))
+    }
+
+    /// Reads the matrix `label` of the dump `hash` from the cache if it is fresh, and otherwise
+    /// from `sparse_matrices_<hash>`, caching it for next time. Failing to write the cache
+    /// only warns, since the matrix itself was read. The source is only verified against its
+    /// manifest when it is read, so a fresh cache is verified against its content hash instead.
+    ///
+    /// Only one process populates an entry at a time: another one wanting it waits for the
+    /// [lock](super::lock) of the entry, and then reads the cache that was written meanwhile.
+    /// If the lock is not released in time, the source is read without caching it. A lock file
+    /// left behind by a run that crashed is not stale: the operating system released its lock
+    /// with the process, so the next load takes it at once rather than waiting out the timeout.
+    pub fn read_cached<T: ParallelDecode + RawCodec + Tagged + ContentHash>(
+        &self,
+        hash: &str,
+        label: &str,
+    ) -> Result<(T, CacheStatus), DataError> {
+        let source = self.file_path(matrices_section(hash), label)?;
+        let stamp = SourceStamp::of(&source)?;
+        let cache_dir = self.cache_dir(hash);
+        let stamp_file = cache_dir.join(format!("{label}.source.json"));
+
+        let unusable = match self.read_fresh(&cache_dir, label, &stamp_file, stamp) {
+            Ok(value) => return Ok((value, CacheStatus::Hit)),
+            Err(unusable) => unusable,
+        };
+        let lock = fs::create_dir_all(&cache_dir)
+            .map_err(|source| DataError::Io {
+                path: cache_dir.clone(),
+                source,
+            })
+            .and_then(|()| self.lock_entry(&cache_dir.join(label)));
+        let status = match &lock {
+            Ok(_) => match self.read_fresh(&cache_dir, label, &stamp_file, stamp) {
+                // Another process cached it while this one waited for the lock.
+                Ok(value) => return Ok((value, CacheStatus::Hit)),
+                Err((status, corrupt)) => {
+                    if let Some(err) = corrupt {
+                        eprintln!("warning: {err}, rebuilding it");
+                    }
+                    status
+                }
+            },
+            Err(err) => {
+                eprintln!("warning: cannot cache {label} of {hash}: {err}");
+                unusable.0
+            }
+        };
+
+        let value = read_verified(self.verify_files, source, |source| {
+            read_file_parallel(source, self.read_path, self.parallel_decode_min)
+        })?;
+        if lock.is_ok() {
+            if let Err(err) = self.write_cache(hash, label, &stamp_file, stamp, &value) {
+                eprintln!("warning: cannot cache {label} of {hash}: {err}");
+            }
+        }
+        Ok((value, status))
+    }
+
+    /// Reads the cache of `label` in `cache_dir` if `stamp_file` says it was built from the
+    /// source `stamp` describes, checking its content hash if verifying files. Otherwise says
+    /// why it cannot be used, with the error of a cache file that failed to decode or check.
+    fn read_fresh<T: ParallelDecode + RawCodec + Tagged + ContentHash>(
+        &self,
+        cache_dir: &Utf8Path,
+        label: &str,
+        stamp_file: &Utf8Path,
+        stamp: SourceStamp,
+    ) -> Result<T, (CacheStatus, Option<DataError>)> {
+        match CacheStamp::saved(stamp_file) {
+            Some(saved) if saved.source == stamp => {
+                let cached = cache_dir.join(raw_label(label));
+                let value: T =
+                    read_file_parallel(cached.clone(), self.read_path, self.parallel_decode_min)
+                        .map_err(|err| (CacheStatus::Stale, Some(err)))?;
+                if self.verify_files {
+                    let actual = bytes_to_hex(&value.content_hash());
+                    if actual != saved.content_hash {
+                        let err = DataError::InvalidRaw {
+                            path: cached,
+                            message: format!(
+                                "content hash {actual} differs from the {} it was cached with",
+                                saved.content_hash
+                            ),
+                        };
+                        return Err((CacheStatus::Stale, Some(err)));
+                    }
+                }
+                Ok(value)
+            }
+            Some(_) => Err((CacheStatus::Stale, None)),
+            None => Err((CacheStatus::Miss, None)),
+        }
+    }
+
+    /// Caches `value` as `label` of `hash`, then writes the `stamp` that vouches for it to
+    /// `stamp_file`. The old stamp goes first, so an interrupted write is never taken for a
+    /// fresh one.
+    fn write_cache<T: RawCodec + Tagged + ContentHash>(
+        &self,
+        hash: &str,
+        label: &str,
+        stamp_file: &Utf8Path,
+        stamp: SourceStamp,
+        value: &T,
+    ) -> Result<(), DataError> {
+        let io_error = |source| DataError::Io {
+            path: stamp_file.to_owned(),
+            source,
+        };
+        match fs::remove_file(stamp_file) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(io_error(err)),
+            _ => {}
+        }
+        self.write_raw(cache_section(hash), label, value)?;
+        let stamp = CacheStamp {
+            source: stamp,
+            content_hash: bytes_to_hex(&value.content_hash()),
+        };
+        let json = serde_json::to_vec(&stamp).expect("stamps always serialize");
+        atomic_write(stamp_file, |file| file.write_all(&json).map_err(io_error))
+    }
+
+    /// Removes the cache of `hash`, or the whole cache, returning the number of files and bytes
+    /// removed. A missing cache removes nothing.
+    pub fn clear_cache(&self, hash: Option<&str>) -> Result<(usize, u64), DataError> {
+        let dir = match hash {
+            Some(hash) => self.cache_dir(hash),
+            None => self.root_dir.join(CACHE_DIR),
+        };
+        if !dir.is_dir() {
+            return Ok((0, 0));
+        }
+        let (files, bytes) = count_files(&dir)?;
+        fs::remove_dir_all(&dir).map_err(|source| DataError::Io { path: dir, source })?;
+        Ok((files, bytes))
+    }
+}
+
+/// The cache of `hash` as a section of the root, so that [`DataConfig::write_raw`] can fill it.
+fn cache_section(hash: &str) -> Utf8PathBuf {
+    Utf8Path::new(CACHE_DIR).join(hash)
+}
+
+/// Counts the regular files under `dir` and their total size, recursively, leaving out locks.
+fn count_files(dir: &Utf8Path) -> Result<(usize, u64), DataError> {
+    let (mut files, mut bytes) = (0, 0);
+    for entry in read_dir(dir)? {
+        let path = entry.path();
+        let metadata = path.metadata().map_err(|source| DataError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        if metadata.is_dir() {
+            let (sub_files, sub_bytes) = count_files(path)?;
+            files += sub_files;
+            bytes += sub_bytes;
+        } else if metadata.is_file() && !is_lock_file(entry.file_name()) {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// Reads the matrix `label` of `hash` through the cache of the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::read_cached`].
+pub fn read_arecibo_cached<T: ParallelDecode + RawCodec + Tagged + ContentHash>(
+    hash: &str,
+    label: &str,
+) -> Result<(T, CacheStatus), DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    // The config is cloned so that loads on several threads run concurrently.
+    let config = mutex.lock().unwrap().clone();
+
+    config.read_cached(hash, label)
+}
+
+/// Removes the cache of `hash`, or the whole cache, relative to the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::clear_cache`].
+pub fn clear_arecibo_cache(hash: Option<&str>) -> Result<(usize, u64), DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap();
+
+    config.clear_cache(hash)
+}
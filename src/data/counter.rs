@@ -0,0 +1,121 @@
+//! Labels written more than once, told apart by a counter.
+//!
+//! arecibo dumps a label each time it writes it, as `<label>_<counter>`: the witnesses of
+//! successive steps become `witness_0_0`, `witness_0_1`, and so on. To the rest of this module
+//! a counted label is an ordinary label, so counted and uncounted ones coexist in a section;
+//! the functions here build them, find the counters a label has, and allocate the next one.
+//!
+//! [`DataConfig`] keeps a witness and a cross-term counter, the next of each it hands out. A
+//! counted write holds the [lock](super::lock) of its section while it picks the larger of
+//! that counter and the one after the last the label has on disk, so that it never overwrites
+//! what another process or an earlier run wrote.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{init_config, read_arecibo_data, DataConfig, DataError, Tagged, ARECIBO_CONFIG};
+
+/// The counters of a [`DataConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    Witness,
+    CrossTerm,
+}
+
+/// The label that `label` is stored under when written with `counter`.
+pub fn counted_label(label: &str, counter: usize) -> String {
+    format!("{label}_{counter}")
+}
+
+impl DataConfig {
+    /// The next value of `counter`, which counted writes allocate from.
+    pub fn counter(&self, counter: Counter) -> usize {
+        match counter {
+            Counter::Witness => self.witness_counter,
+            Counter::CrossTerm => self.cross_term_counter,
+        }
+    }
+
+    /// Lists the counters that `label` was written with in `section`, sorted; how many
+    /// there are is the length. `label` itself, if stored without a counter, is not one.
+    pub fn label_counters(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: &str,
+    ) -> Result<Vec<usize>, DataError> {
+        self.label_indices(section, &format!("{label}_"))
+    }
+
+    /// Reads `label` as written with `counter` into `section`; see [`DataConfig::read`].
+    pub fn read_counted<T: DeserializeOwned + Tagged>(
+        &self,
+        section: impl AsRef<Utf8Path>,
+        label: &str,
+        counter: usize,
+    ) -> Result<T, DataError> {
+        self.read(section, counted_label(label, counter))
+    }
+
+    /// Writes `value` as `label` with the next value of `counter`, and returns that value with
+    /// the path written. The value is the larger of `counter` and the one after the last
+    /// counter `label` has in `section`, and `counter` moves past it.
+    pub fn write_counted<T: Serialize + Tagged + ?Sized>(
+        &mut self,
+        counter: Counter,
+        section: impl AsRef<Utf8Path>,
+        label: &str,
+        value: &T,
+    ) -> Result<(usize, Utf8PathBuf), DataError> {
+        let section = section.as_ref();
+        let _lock = self.lock_section(section)?;
+        let after_last = match self.label_counters(section, label)?.last() {
+            Some(last) => last + 1,
+            None => 0,
+        };
+        let value_counter = self.counter(counter).max(after_last);
+        let path = self.write_locked(section, counted_label(label, value_counter), value)?;
+        let next = match counter {
+            Counter::Witness => &mut self.witness_counter,
+            Counter::CrossTerm => &mut self.cross_term_counter,
+        };
+        *next = value_counter + 1;
+
+        Ok((value_counter, path))
+    }
+}
+
+/// Lists the counters of `label` in `section` relative to the global [`ARECIBO_CONFIG`];
+/// see [`DataConfig::label_counters`].
+pub fn arecibo_label_counters(
+    section: impl AsRef<Utf8Path>,
+    label: &str,
+) -> Result<Vec<usize>, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let config = mutex.lock().unwrap();
+
+    config.label_counters(section, label)
+}
+
+/// Reads `label` as written with `counter` into `section` relative to the global
+/// [`ARECIBO_CONFIG`]; see [`read_arecibo_data`].
+pub fn read_arecibo_data_counted<T: DeserializeOwned + Tagged>(
+    section: impl AsRef<Utf8Path>,
+    label: &str,
+    counter: usize,
+) -> Result<T, DataError> {
+    read_arecibo_data(section, counted_label(label, counter))
+}
+
+/// Writes `value` as `label` with the next value of `counter` of the global
+/// [`ARECIBO_CONFIG`]; see [`DataConfig::write_counted`].
+pub fn write_arecibo_data_counted<T: Serialize + Tagged + ?Sized>(
+    counter: Counter,
+    section: impl AsRef<Utf8Path>,
+    label: &str,
+    value: &T,
+) -> Result<(usize, Utf8PathBuf), DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    let mut config = mutex.lock().unwrap();
+
+    config.write_counted(counter, section, label, value)
+}
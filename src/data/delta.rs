@@ -0,0 +1,130 @@
+//! Delta encoding of the column indices of a CSR matrix, for the [`raw`](super::raw) codec.
+//!
+//! The columns of a row are sorted and usually close together, so each row is stored as its
+//! first column followed by the differences between consecutive columns, every one of them
+//! as an unsigned LEB128 varint: seven bits per byte, least significant first, with the top
+//! bit set on every byte but the last. Most differences then take one or two bytes instead
+//! of four or eight.
+//!
+//! Decoding finds where each row starts with one pass over the top bits, guided by `indptr`,
+//! and then has rayon decode the rows in parallel.
+
+use rayon::prelude::*;
+
+/// Width recorded in the header of a delta-encoded array, in place of the element width: the
+/// elements have none, so the header is followed by the `u64` length of the encoding in bytes.
+pub const DELTA_WIDTH: u16 = 0;
+
+/// Encodes `indices`, whose rows are delimited by `indptr`. A row whose columns are not sorted
+/// cannot be encoded as differences, and is named in the error.
+pub fn encode_deltas(indices: &[usize], indptr: &[usize]) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(indices.len() * 2);
+    for (row, ptrs) in indptr.windows(2).enumerate() {
+        let columns = &indices[ptrs[0]..ptrs[1]];
+        if columns.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(format!(
+                "row {row} has unsorted column indices; sort them before delta encoding"
+            ));
+        }
+        let mut previous = 0;
+        for &column in columns {
+            write_varint(&mut bytes, (column - previous) as u64);
+            previous = column;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes the output of [`encode_deltas`] back into the indices of the rows delimited by
+/// `indptr`, which must account for every value in `bytes`.
+pub fn decode_deltas(bytes: &[u8], indptr: &[usize]) -> Result<Vec<usize>, String> {
+    let starts = row_starts(bytes, indptr)?;
+    starts
+        .par_windows(2)
+        .flat_map_iter(|range| Row {
+            bytes: &bytes[range[0]..range[1]],
+            previous: 0,
+        })
+        .collect()
+}
+
+/// The offset in `bytes` at which each row starts, and the end of the last one.
+fn row_starts(bytes: &[u8], indptr: &[usize]) -> Result<Vec<usize>, String> {
+    let mut starts = Vec::with_capacity(indptr.len());
+    let mut rows = indptr.iter().peekable();
+    let mut values = 0;
+    while rows.next_if_eq(&&0).is_some() {
+        starts.push(0);
+    }
+    for (offset, &byte) in bytes.iter().enumerate() {
+        if byte & 0x80 == 0 {
+            values += 1;
+            while rows.next_if_eq(&&values).is_some() {
+                starts.push(offset + 1);
+            }
+        }
+    }
+    match (rows.next(), starts.last()) {
+        (None, Some(&end)) if end == bytes.len() => Ok(starts),
+        _ => Err(format!(
+            "found {values} delta-encoded indices, which indptr does not account for"
+        )),
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// The columns of one row, decoded as they are iterated.
+struct Row<'a> {
+    bytes: &'a [u8],
+    previous: usize,
+}
+
+impl Iterator for Row<'_> {
+    type Item = Result<usize, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        // The row ends on a terminating byte, so every varint in it is complete.
+        let len = self.bytes.iter().position(|byte| byte & 0x80 == 0)? + 1;
+        let (varint, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        let column = read_varint(varint)
+            .and_then(|delta| usize::try_from(delta).ok())
+            .and_then(|delta| self.previous.checked_add(delta));
+        match column {
+            Some(column) => {
+                self.previous = column;
+                Some(Ok(column))
+            }
+            None => {
+                self.bytes = &[];
+                Some(Err(
+                    "a delta-encoded index does not fit in usize".to_string()
+                ))
+            }
+        }
+    }
+}
+
+/// Decodes one complete varint, or `None` if it overflows a `u64`.
+fn read_varint(varint: &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, &byte) in varint.iter().enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        let shift = 7 * i as u32;
+        if shift >= 64 || (shift > 0 && bits >> (64 - shift) != 0) {
+            return None;
+        }
+        value |= bits << shift;
+    }
+    Some(value)
+}
@@ -0,0 +1,262 @@
+//! A self-describing header in front of every data file this crate writes, so that what a file
+//! holds can be told without trying to decode it.
+//!
+//! The header is [`HEADER_BYTES`] long: the magic `SPMD`, a `u16` version, a format tag, and an
+//! element type tag, all little-endian. Reads dispatch on the format it names, and files
+//! without one — every dump arecibo itself writes — are taken for legacy bincode, or for the
+//! headerless raw or zstd files of earlier versions, recognized by their own magic bytes or
+//! their extension.
+//! Chunked files and rkyv archives are read in place at fixed offsets, and Matrix Market and
+//! npz files are for other tools, so they are written without the header; [`detect`] still
+//! recognizes them.
+
+use std::{fmt, io};
+
+use ff::PrimeField;
+use serde::Serialize;
+
+use super::raw::{RAW_EXTENSION, RAW_MAGIC};
+use crate::{
+    sparse::{
+        CheckedSparseMatrix, CHUNKED_EXTENSION, CHUNKED_MAGIC, MATRIX_MARKET_BANNER,
+        MATRIX_MARKET_EXTENSION, NPZ_EXTENSION,
+    },
+    SparseMatrix,
+};
+
+/// Magic bytes opening every header.
+pub const HEADER_MAGIC: [u8; 4] = *b"SPMD";
+
+/// Version of the layout described in the [module docs](self).
+pub const HEADER_VERSION: u16 = 1;
+
+/// Size of the header.
+pub const HEADER_BYTES: usize = 8;
+
+/// First bytes of every zstd frame.
+pub(super) const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Extension of rkyv archives, which have no magic bytes of their own to be recognized by.
+/// The archive module, which defines it, is only built with the `rkyv` feature.
+const ARCHIVE_EXTENSION: &str = "rkyv";
+
+/// How the bytes after the header are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileFormat {
+    Bincode = 1,
+    /// bincode compressed with zstd.
+    Zstd = 2,
+    /// The [`raw`](super::raw) codec.
+    Raw = 3,
+    /// The raw codec, with the column indices of the matrix
+    /// [delta-encoded](super::delta).
+    RawDelta = 4,
+    /// The chunked layout of [`ChunkedMatrix`](crate::sparse::ChunkedMatrix).
+    Chunked = 5,
+    /// An rkyv archive, for `bench --archived`.
+    Rkyv = 6,
+    /// A [Matrix Market](crate::sparse::SparseMatrix::write_matrix_market) text file.
+    MatrixMarket = 7,
+    /// A scipy [npz](crate::sparse::SparseMatrix::write_npz) file.
+    Npz = 8,
+}
+
+/// What a file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ElementType {
+    /// A vector of field elements: a witness or an expected product.
+    Vector = 1,
+    /// A sparse matrix of field elements.
+    Matrix = 2,
+}
+
+/// Values whose files record an [`ElementType`] in their header.
+pub trait Tagged {
+    const ELEMENT_TYPE: ElementType;
+}
+
+impl<F: PrimeField> Tagged for Vec<F> {
+    const ELEMENT_TYPE: ElementType = ElementType::Vector;
+}
+
+impl<F: PrimeField> Tagged for [F] {
+    const ELEMENT_TYPE: ElementType = ElementType::Vector;
+}
+
+impl<F: PrimeField> Tagged for SparseMatrix<F> {
+    const ELEMENT_TYPE: ElementType = ElementType::Matrix;
+}
+
+impl<F: PrimeField> Tagged for CheckedSparseMatrix<F> {
+    const ELEMENT_TYPE: ElementType = ElementType::Matrix;
+}
+
+/// The decoded header of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Header {
+    pub version: u16,
+    pub format: FileFormat,
+    pub element: ElementType,
+}
+
+impl Header {
+    /// The header this version writes for `T` encoded as `format`.
+    pub fn new<T: Tagged + ?Sized>(format: FileFormat) -> Self {
+        Self {
+            version: HEADER_VERSION,
+            format,
+            element: T::ELEMENT_TYPE,
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; HEADER_BYTES] {
+        let mut bytes = [0; HEADER_BYTES];
+        bytes[..4].copy_from_slice(&HEADER_MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6] = self.format as u8;
+        bytes[7] = self.element as u8;
+        bytes
+    }
+
+    pub fn write(self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Decodes the header at the start of `bytes`, or `None` if they do not start with one.
+    /// A header from a later version, or with tags this version does not know, is an error.
+    pub fn parse(bytes: &[u8]) -> Result<Option<Self>, String> {
+        let Some(bytes) = bytes.get(..HEADER_BYTES) else {
+            return Ok(None);
+        };
+        if bytes[..4] != HEADER_MAGIC {
+            return Ok(None);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version > HEADER_VERSION {
+            return Err(format!(
+                "header version {version} is newer than this build reads ({HEADER_VERSION})"
+            ));
+        }
+        let format = match bytes[6] {
+            1 => FileFormat::Bincode,
+            2 => FileFormat::Zstd,
+            3 => FileFormat::Raw,
+            4 => FileFormat::RawDelta,
+            5 => FileFormat::Chunked,
+            6 => FileFormat::Rkyv,
+            7 => FileFormat::MatrixMarket,
+            8 => FileFormat::Npz,
+            tag => return Err(format!("unknown format tag {tag}")),
+        };
+        let element = match bytes[7] {
+            1 => ElementType::Vector,
+            2 => ElementType::Matrix,
+            tag => return Err(format!("unknown element type tag {tag}")),
+        };
+        Ok(Some(Self {
+            version,
+            format,
+            element,
+        }))
+    }
+
+    /// Checks that the file holds a `T`.
+    pub fn expect<T: Tagged + ?Sized>(self) -> Result<(), String> {
+        match self.element == T::ELEMENT_TYPE {
+            true => Ok(()),
+            false => Err(format!(
+                "holds a {}, not a {}",
+                self.element,
+                T::ELEMENT_TYPE
+            )),
+        }
+    }
+}
+
+/// What the first bytes of a file, and its name, say it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Detected {
+    pub format: FileFormat,
+    /// The header of the file, `None` for legacy files without one.
+    pub header: Option<Header>,
+}
+
+impl Detected {
+    /// Where the encoding starts in the file, after the header if there is one.
+    pub fn data_offset(&self) -> u64 {
+        match self.header {
+            Some(_) => HEADER_BYTES as u64,
+            None => 0,
+        }
+    }
+}
+
+/// Detects the format of the file named `file_name` that starts with `bytes`, which should
+/// be at least [`HEADER_BYTES`] long unless the file is shorter. A file without a header is
+/// recognized by its magic bytes, then by its extension, and is otherwise legacy bincode.
+pub fn detect(file_name: &str, bytes: &[u8]) -> Result<Detected, String> {
+    if let Some(header) = Header::parse(bytes)? {
+        return Ok(Detected {
+            format: header.format,
+            header: Some(header),
+        });
+    }
+    let extension = file_name.rsplit_once('.').map(|(_, extension)| extension);
+    let format = match (bytes.get(..4), extension) {
+        (Some(magic), _) if magic == ZSTD_MAGIC => FileFormat::Zstd,
+        (Some(magic), _) if magic == RAW_MAGIC => FileFormat::Raw,
+        (Some(magic), _) if magic == CHUNKED_MAGIC => FileFormat::Chunked,
+        (Some(magic), _) if magic == &MATRIX_MARKET_BANNER.as_bytes()[..4] => {
+            FileFormat::MatrixMarket
+        }
+        (_, Some(RAW_EXTENSION)) => FileFormat::Raw,
+        (_, Some(CHUNKED_EXTENSION)) => FileFormat::Chunked,
+        (_, Some(ARCHIVE_EXTENSION)) => FileFormat::Rkyv,
+        (_, Some(MATRIX_MARKET_EXTENSION)) => FileFormat::MatrixMarket,
+        (_, Some(NPZ_EXTENSION)) => FileFormat::Npz,
+        _ => FileFormat::Bincode,
+    };
+    Ok(Detected {
+        format,
+        header: None,
+    })
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FileFormat::Bincode => "bincode",
+            FileFormat::Zstd => "bincode+zstd",
+            FileFormat::Raw => "raw",
+            FileFormat::RawDelta => "raw with delta indices",
+            FileFormat::Chunked => "chunked",
+            FileFormat::Rkyv => "rkyv",
+            FileFormat::MatrixMarket => "matrix market",
+            FileFormat::Npz => "npz",
+        })
+    }
+}
+
+impl fmt::Display for ElementType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ElementType::Vector => "vector",
+            ElementType::Matrix => "matrix",
+        })
+    }
+}
+
+impl fmt::Display for Detected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.header {
+            Some(header) => write!(
+                f,
+                "{} {}, header v{}",
+                self.format, header.element, header.version
+            ),
+            None => write!(f, "{}, no header", self.format),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Advisory locks, so that concurrent runs against the same root do not write the same files
+//! at once.
+//!
+//! Every write into a section holds the lock file [`LOCK_FILE`] of that section while it
+//! writes and records the file in the manifest, and populating the cache of a matrix holds
+//! `<label>.lock` in the cache of its dump while it reads the source, so that a second run
+//! waits for the first and then reuses what it cached. Reads never take a lock.
+//!
+//! The locks are advisory, through [`File::try_lock`]: they only keep out other users of this
+//! crate, and are released by the operating system if the process holding one dies. The lock
+//! files themselves are never removed, and one left behind holds no lock, so there are no stale
+//! locks to clear. A lock not released within the timeout set with
+//! [`DataConfig::with_lock_timeout`] fails with [`DataError::Locked`].
+
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{init_config, DataConfig, DataError, ARECIBO_CONFIG};
+
+/// Name of the lock file in each section. [`DataConfig::labels`] does not list it.
+pub const LOCK_FILE: &str = ".lock";
+
+/// Extension of the lock files of cache entries.
+const LOCK_EXTENSION: &str = "lock";
+
+/// How long to wait for a lock by default, enough for another run to cache a large matrix.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How often a held lock is retried.
+const RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A held lock, released when dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    // Closing the file releases the lock.
+    _file: File,
+}
+
+/// Takes the lock on the file at `path`, creating it if needed, and waits up to `timeout` for
+/// another holder to release it.
+pub fn lock_file(path: &Utf8Path, timeout: Duration) -> Result<FileLock, DataError> {
+    let io_error = |source| DataError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(io_error)?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(FileLock { _file: file }),
+            Err(TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                thread::sleep(RETRY_INTERVAL)
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(DataError::Locked {
+                    path: path.to_owned(),
+                    timeout,
+                })
+            }
+            Err(TryLockError::Error(source)) => return Err(io_error(source)),
+        }
+    }
+}
+
+impl DataConfig {
+    /// Makes writes wait up to `lock_timeout` for another process to release the lock they
+    /// need, rather than [`DEFAULT_LOCK_TIMEOUT`].
+    pub fn with_lock_timeout(self, lock_timeout: Duration) -> Self {
+        Self {
+            lock_timeout,
+            ..self
+        }
+    }
+
+    /// Takes the lock that writes into `section` hold, creating the section if needed.
+    pub fn lock_section(&self, section: impl AsRef<Utf8Path>) -> Result<FileLock, DataError> {
+        let section_path = self.root_dir.join(section.as_ref());
+        fs::create_dir_all(&section_path).map_err(|source| DataError::Io {
+            path: section_path.clone(),
+            source,
+        })?;
+        self.lock_in(&section_path)
+    }
+
+    /// Takes the lock of the section holding the file at `path`.
+    pub(super) fn lock_output(&self, path: &Utf8Path) -> Result<FileLock, DataError> {
+        self.lock_in(path.parent().unwrap_or(&self.root_dir))
+    }
+
+    /// Takes the lock of the cache entry at `path`, whose population excludes other runs.
+    pub(super) fn lock_entry(&self, path: &Utf8Path) -> Result<FileLock, DataError> {
+        lock_file(&lock_path(path), self.lock_timeout)
+    }
+
+    fn lock_in(&self, section_path: &Utf8Path) -> Result<FileLock, DataError> {
+        lock_file(&section_path.join(LOCK_FILE), self.lock_timeout)
+    }
+}
+
+/// The lock file of the cache entry at `path`.
+fn lock_path(path: &Utf8Path) -> Utf8PathBuf {
+    let name = path.file_name().unwrap_or_default();
+    path.with_file_name(format!("{name}.{LOCK_EXTENSION}"))
+}
+
+/// Whether `name` is that of a lock file, which listings and counts of files skip.
+pub(super) fn is_lock_file(name: &str) -> bool {
+    name == LOCK_FILE || Utf8Path::new(name).extension() == Some(LOCK_EXTENSION)
+}
+
+/// Takes the lock of `section` relative to the global [`ARECIBO_CONFIG`]; see
+/// [`DataConfig::lock_section`].
+pub fn lock_arecibo_section(section: impl AsRef<Utf8Path>) -> Result<FileLock, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(init_config)?;
+    // Waiting for the lock must not hold up other users of the config.
+    let config = mutex.lock().unwrap().clone();
+
+    config.lock_section(section)
+}
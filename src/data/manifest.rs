@@ -0,0 +1,330 @@
+//! Checksums of the files of each section, to catch dumps corrupted while being copied.
+//!
+//! Every section written through [`DataConfig`] gets a `manifest.json` listing the size and
+//! hash of each of its files, kept up to date as files are written; `manifest create` builds
+//! one for a dump written elsewhere. With [`DataConfig::with_verify_files`], every file read is
+//! hashed on another thread while it is decoded, and a file that does not match its manifest
+//! fails to load.
+//!
+//! The hash is BLAKE3, named in the manifest so that it can change without misreading old
+//! manifests.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use super::{atomic_write, read_dir, DataConfig, DataError, ARECIBO_CONFIG, CACHE_DIR};
+
+/// Name of the manifest in each section. [`DataConfig::labels`] does not list it.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The hash the files are checksummed with.
+pub const HASH_ALGORITHM: &str = "blake3";
+
+/// Bytes hashed at a time, so that hashing keeps pace with a concurrent read of the same file.
+const HASH_CHUNK_BYTES: usize = 1 << 20;
+
+/// Files verified so far by this process, see [`verification_totals`].
+static TOTALS: Mutex<VerifyTotals> = Mutex::new(VerifyTotals {
+    files: 0,
+    bytes: 0,
+    hashing: Duration::ZERO,
+    waiting: Duration::ZERO,
+});
+
+/// The checksums of the files of one section, by file name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub algorithm: String,
+    pub files: BTreeMap<String, FileEntry>,
+}
+
+/// Size and hash of one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub bytes: u64,
+    pub hash: String,
+}
+
+/// What verifying files has cost so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyTotals {
+    pub files: usize,
+    pub bytes: u64,
+    /// Time spent hashing, on the threads beside the reads.
+    pub hashing: Duration,
+    /// Time reads spent waiting for hashing to finish after decoding; what verifying added.
+    pub waiting: Duration,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            algorithm: HASH_ALGORITHM.to_string(),
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Reads the manifest of the section at `section_path`, if it has one.
+    pub fn read(section_path: &Utf8Path) -> Result<Option<Self>, DataError> {
+        let path = section_path.join(MANIFEST_FILE);
+        let json = match fs::read(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(DataError::Io { path, source }),
+        };
+        let manifest: Self =
+            serde_json::from_slice(&json).map_err(|err| DataError::InvalidManifest {
+                path: path.clone(),
+                message: err.to_string(),
+            })?;
+        if manifest.algorithm != HASH_ALGORITHM {
+            return Err(DataError::InvalidManifest {
+                path,
+                message: format!("unsupported hash {}", manifest.algorithm),
+            });
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Writes the manifest into the section at `section_path`, replacing any other at once.
+    pub fn write(&self, section_path: &Utf8Path) -> Result<(), DataError> {
+        let path = section_path.join(MANIFEST_FILE);
+        let json = serde_json::to_vec_pretty(self).expect("manifests always serialize");
+        atomic_write(&path, |file| {
+            file.write_all(&json).map_err(|source| DataError::Io {
+                path: path.clone(),
+                source,
+            })
+        })
+    }
+}
+
+/// A hash being computed over bytes as they are written or read.
+pub struct Hasher {
+    state: blake3::Hasher,
+    bytes: u64,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self {
+            state: blake3::Hasher::new(),
+            bytes: 0,
+        }
+    }
+}
+
+impl Hasher {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+        self.bytes += bytes.len() as u64;
+    }
+
+    pub fn finish(&self) -> FileEntry {
+        FileEntry {
+            bytes: self.bytes,
+            hash: self.state.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// A writer that hashes the bytes written through it.
+pub(super) struct HashingWriter<W> {
+    pub inner: W,
+    pub hasher: Hasher,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hashes the file at `path` a chunk at a time.
+pub fn hash_file(path: &Utf8Path) -> Result<FileEntry, DataError> {
+    let io_error = |source| DataError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let mut file = File::open(path).map_err(io_error)?;
+    let mut hasher = Hasher::default();
+    let mut chunk = vec![0; HASH_CHUNK_BYTES];
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => return Ok(hasher.finish()),
+            Ok(read) => hasher.update(&chunk[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(source) => return Err(io_error(source)),
+        }
+    }
+}
+
+/// Decodes the file at `path` with `decode`, first checking it against its manifest if
+/// `verify_files`, as [`DataConfig::with_verify_files`] asks. The file is hashed on another
+/// thread while `decode` reads it, so verifying costs only what hashing takes beyond decoding.
+pub(super) fn read_verified<T>(
+    verify_files: bool,
+    path: Utf8PathBuf,
+    decode: impl FnOnce(Utf8PathBuf) -> Result<T, DataError>,
+) -> Result<T, DataError> {
+    let Some(expected) = expected_entry(verify_files, &path)? else {
+        return decode(path);
+    };
+
+    thread::scope(|scope| {
+        let hashing = scope.spawn(|| {
+            let start = Instant::now();
+            hash_file(&path).map(|actual| (actual, start.elapsed()))
+        });
+        let value = decode(path.clone());
+        let start = Instant::now();
+        let (actual, hashed_in) = hashing.join().expect("hashing panicked")?;
+        record_verification(&actual, hashed_in, start.elapsed());
+
+        check_entry(&path, expected, actual)?;
+        value
+    })
+}
+
+/// Checks the file at `path` against its manifest before it is streamed, if `verify_files`.
+/// A stream cannot wait for its hash at the end, so the whole file is hashed up front.
+pub(super) fn verify_file(verify_files: bool, path: &Utf8Path) -> Result<(), DataError> {
+    let Some(expected) = expected_entry(verify_files, path)? else {
+        return Ok(());
+    };
+    let start = Instant::now();
+    let actual = hash_file(path)?;
+    let hashed_in = start.elapsed();
+    record_verification(&actual, hashed_in, hashed_in);
+
+    check_entry(path, expected, actual)
+}
+
+/// The manifest entry to verify the file at `path` against, if `verify_files`. A file missing
+/// from its manifest is read unverified, with a warning.
+fn expected_entry(verify_files: bool, path: &Utf8Path) -> Result<Option<FileEntry>, DataError> {
+    if !verify_files {
+        return Ok(None);
+    }
+    let expected = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => {
+            Manifest::read(dir)?.and_then(|manifest| manifest.files.get(name).cloned())
+        }
+        _ => None,
+    };
+    if expected.is_none() {
+        eprintln!("warning: {path} is not in a manifest, so it is not verified");
+    }
+    Ok(expected)
+}
+
+fn record_verification(actual: &FileEntry, hashing: Duration, waiting: Duration) {
+    let mut totals = TOTALS.lock().unwrap();
+    totals.files += 1;
+    totals.bytes += actual.bytes;
+    totals.hashing += hashing;
+    totals.waiting += waiting;
+}
+
+fn check_entry(path: &Utf8Path, expected: FileEntry, actual: FileEntry) -> Result<(), DataError> {
+    if actual == expected {
+        return Ok(());
+    }
+    Err(DataError::ChecksumMismatch {
+        path: path.to_owned(),
+        expected: expected.hash,
+        actual: actual.hash,
+    })
+}
+
+/// What verifying files has cost this process so far.
+pub fn verification_totals() -> VerifyTotals {
+    *TOTALS.lock().unwrap()
+}
+
+impl DataConfig {
+    /// Makes every read hash the file it decodes and compare it against the manifest of its
+    /// section, failing with [`DataError::ChecksumMismatch`] if they differ. Files without
+    /// a manifest entry are read with a warning. Cached matrices are checked against the
+    /// content hash they were cached with instead, see [`DataConfig::read_cached`].
+    pub fn with_verify_files(self, verify_files: bool) -> Self {
+        Self {
+            verify_files,
+            ..self
+        }
+    }
+
+    /// Records `entry` for the file at `path` in the manifest of its section, dropping the
+    /// entry of `replaced` if given. The caller holds the lock of the section, which keeps
+    /// other writers from updating the manifest meanwhile. Sections of the cache are rebuilt
+    /// from their sources rather than verified, so they get no manifest.
+    pub(super) fn record_in_manifest(
+        &self,
+        path: &Utf8Path,
+        entry: FileEntry,
+        replaced: Option<&str>,
+    ) -> Result<(), DataError> {
+        let (Some(section_path), Some(name)) = (path.parent(), path.file_name()) else {
+            return Ok(());
+        };
+        if section_path
+            .strip_prefix(self.root_dir.join(CACHE_DIR))
+            .is_ok()
+        {
+            return Ok(());
+        }
+        let mut manifest = Manifest::read(section_path)?.unwrap_or_default();
+        if let Some(replaced) = replaced {
+            manifest.files.remove(replaced);
+        }
+        manifest.files.insert(name.to_string(), entry);
+        manifest.write(section_path)
+    }
+
+    /// Hashes every file of `section` into a new manifest, replacing any it had.
+    pub fn create_manifest(&self, section: impl AsRef<Utf8Path>) -> Result<Manifest, DataError> {
+        let section_path = self.root_dir.join(section.as_ref());
+        if !section_path.is_dir() {
+            return Err(DataError::SectionNotFound(section_path));
+        }
+        let _lock = self.lock_section(section)?;
+        let mut manifest = Manifest::default();
+        for entry in read_dir(&section_path)? {
+            let name = entry.file_name();
+            if entry.path().is_file() && super::is_data_file(name) {
+                manifest
+                    .files
+                    .insert(name.to_string(), hash_file(entry.path())?);
+            }
+        }
+        manifest.write(&section_path)?;
+        Ok(manifest)
+    }
+}
+
+/// Hashes every file of `section` relative to the global [`ARECIBO_CONFIG`] into a new
+/// manifest; see [`DataConfig::create_manifest`].
+pub fn create_arecibo_manifest(section: impl AsRef<Utf8Path>) -> Result<Manifest, DataError> {
+    let mutex = ARECIBO_CONFIG.get_or_try_init(super::init_config)?;
+    // The hashing takes a while, so other users of the config are not held up by it.
+    let config = mutex.lock().unwrap().clone();
+
+    config.create_manifest(section)
+}
@@ -0,0 +1,126 @@
+//! Parallel decoding of large bincode files of field data.
+//!
+//! bincode stores a `Vec<F>` as its `u64` length followed by the 32-byte canonical
+//! representation of every element, and a `usize` as a `u64`, so element boundaries sit at
+//! fixed offsets. Instead of deserializing one element at a time on one core, the payload is
+//! split into chunks that rayon decodes with [`PrimeField::from_repr`], and the chunks are
+//! stitched back together in order. Like `bincode::deserialize`, bytes past the value are
+//! ignored.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::SparseMatrix;
+
+/// Files smaller than this are deserialized serially; splitting them up costs more than it saves.
+pub const PARALLEL_DECODE_MIN_BYTES: u64 = 1 << 20;
+
+/// Elements decoded per rayon task; small enough to balance, big enough to amortize.
+const CHUNK_ELEMENTS: usize = 1 << 14;
+
+/// Values whose bincode encoding can be decoded in parallel.
+pub trait ParallelDecode: DeserializeOwned {
+    /// Decodes a value from the bincode encoding at the start of `bytes`, exactly as
+    /// `bincode::deserialize` would; the message says what is wrong otherwise.
+    fn decode_parallel(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// For fields serialized as their 32-byte representation, as halo2curves' fields are.
+impl<F: PrimeField<Repr = [u8; 32]> + DeserializeOwned> ParallelDecode for Vec<F> {
+    fn decode_parallel(bytes: &[u8]) -> Result<Self, String> {
+        Fields { bytes }.fields()
+    }
+}
+
+/// For fields serialized as their 32-byte representation, as halo2curves' fields are.
+impl<F: PrimeField<Repr = [u8; 32]> + DeserializeOwned> ParallelDecode for SparseMatrix<F> {
+    fn decode_parallel(bytes: &[u8]) -> Result<Self, String> {
+        let mut fields = Fields { bytes };
+        let matrix = SparseMatrix::new_unchecked(
+            fields.fields()?,
+            fields.usizes()?,
+            fields.usizes()?,
+            fields.usize()?,
+        );
+        matrix.check_lengths().map_err(|err| err.to_string())?;
+        Ok(matrix)
+    }
+}
+
+/// Decodes `payload`, a whole number of field element representations, in parallel chunks.
+pub fn decode_fields<F: PrimeField>(payload: &[u8]) -> Result<Vec<F>, String> {
+    let width = F::Repr::default().as_ref().len();
+    payload
+        .par_chunks(CHUNK_ELEMENTS * width)
+        .flat_map_iter(|chunk| {
+            chunk.chunks_exact(width).map(|bytes| {
+                let mut repr = F::Repr::default();
+                repr.as_mut().copy_from_slice(bytes);
+                Option::<F>::from(F::from_repr(repr))
+            })
+        })
+        .collect::<Option<Vec<F>>>()
+        .ok_or_else(|| "a value is not a canonical field element".to_string())
+}
+
+/// Decodes `payload`, a whole number of little-endian `u64`s, in parallel chunks.
+pub fn decode_usizes(payload: &[u8]) -> Result<Vec<usize>, String> {
+    payload
+        .par_chunks_exact(8)
+        .map(|bytes| usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap())).ok())
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(|| "an index does not fit in usize".to_string())
+}
+
+/// Decodes `payload`, a whole number of little-endian `u32`s, in parallel chunks.
+pub fn decode_u32s(payload: &[u8]) -> Vec<usize> {
+    payload
+        .par_chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .collect()
+}
+
+/// The bincode encoding not decoded yet.
+struct Fields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    /// Splits off the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err(format!(
+                "expected {len} more bytes, found {}",
+                self.bytes.len()
+            ));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        let bytes = self.take(8)?.try_into().unwrap();
+        usize::try_from(u64::from_le_bytes(bytes))
+            .map_err(|_| "a length does not fit in usize".to_string())
+    }
+
+    /// Splits off a sequence of `width`-byte elements, after its length.
+    fn sequence(&mut self, width: usize) -> Result<&'a [u8], String> {
+        let count = self.usize()?;
+        let len = count
+            .checked_mul(width)
+            .ok_or_else(|| format!("a sequence of {count} elements is too long"))?;
+        self.take(len)
+    }
+
+    fn fields<F: PrimeField>(&mut self) -> Result<Vec<F>, String> {
+        let width = F::Repr::default().as_ref().len();
+        decode_fields(self.sequence(width)?)
+    }
+
+    fn usizes(&mut self) -> Result<Vec<usize>, String> {
+        decode_usizes(self.sequence(8)?)
+    }
+}
@@ -0,0 +1,138 @@
+//! Progress reporting for reads of large data files, which can take minutes with no other output.
+
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    time::{Duration, Instant},
+};
+
+/// Files smaller than this are read without reporting progress.
+pub const PROGRESS_MIN_BYTES: u64 = 64 << 20;
+
+/// How often a [`ProgressStyle::Redraw`] line is redrawn.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How a [`ProgressReader`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStyle {
+    /// Redraws a single line in place, for terminals.
+    Redraw,
+    /// Prints a line at every quarter of the file, so logs stay readable.
+    Lines,
+}
+
+impl ProgressStyle {
+    /// [`ProgressStyle::Redraw`] if stderr, where progress is reported, is a terminal.
+    pub fn detect() -> Self {
+        match io::stderr().is_terminal() {
+            true => ProgressStyle::Redraw,
+            false => ProgressStyle::Lines,
+        }
+    }
+}
+
+/// Wraps a reader of a `total`-byte file, reporting the bytes read so far to `out`
+/// under `label`, with the throughput since the first read.
+pub struct ProgressReader<R, W> {
+    inner: R,
+    label: String,
+    total: u64,
+    read: u64,
+    style: ProgressStyle,
+    out: W,
+    start: Instant,
+    /// When the line was last drawn, for [`ProgressStyle::Redraw`].
+    drawn: Option<Instant>,
+    /// Quarters of the file reported so far, for [`ProgressStyle::Lines`].
+    quarters: u64,
+}
+
+impl<R: Read, W: Write> ProgressReader<R, W> {
+    pub fn new(
+        inner: R,
+        label: impl Into<String>,
+        total: u64,
+        style: ProgressStyle,
+        out: W,
+    ) -> Self {
+        Self {
+            inner,
+            label: label.into(),
+            total,
+            read: 0,
+            style,
+            out,
+            start: Instant::now(),
+            drawn: None,
+            quarters: 0,
+        }
+    }
+
+    /// Renders the bytes read so far, e.g. `A_0: 1.50 GiB of 6.00 GiB (25%), 812.00 MiB/s`.
+    fn status(&self) -> String {
+        let percent = match self.total {
+            0 => 100,
+            total => self.read * 100 / total,
+        };
+        let secs = self.start.elapsed().as_secs_f64().max(f64::MIN_POSITIVE);
+        format!(
+            "{}: {} of {} ({percent}%), {}/s",
+            self.label,
+            format_size(self.read),
+            format_size(self.total),
+            format_size((self.read as f64 / secs) as u64)
+        )
+    }
+
+    /// Reports progress if it is due. Failures to report are ignored; they must not fail the read.
+    fn report(&mut self) {
+        let done = self.read >= self.total;
+        match self.style {
+            ProgressStyle::Redraw => {
+                let due = self.drawn.is_none_or(|at| at.elapsed() >= REDRAW_INTERVAL);
+                if due || done {
+                    let status = self.status();
+                    let end = if done { "\n" } else { "" };
+                    let _ = write!(self.out, "\r\x1b[K{status}{end}");
+                    let _ = self.out.flush();
+                    self.drawn = Some(Instant::now());
+                }
+            }
+            ProgressStyle::Lines => {
+                let quarters = match self.total {
+                    0 => 4,
+                    total => (self.read * 4 / total).min(4),
+                };
+                if quarters > self.quarters {
+                    self.quarters = quarters;
+                    let _ = writeln!(self.out, "reading {}", self.status());
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for ProgressReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.read += n as u64;
+            self.report();
+        }
+        Ok(n)
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.50 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.2} {}", UNITS[unit])
+}
@@ -0,0 +1,274 @@
+//! The raw codec: field elements stored flat as their canonical little-endian representations,
+//! so that decoding is one read of the file and a pass of [`decode_fields`].
+//!
+//! A raw file is a sequence of arrays, each a [`ARRAY_HEADER_BYTES`]-byte header — the magic
+//! `SPMV`, a `u16` version, the `u16` width of one element, and the `u64` element count, all
+//! little-endian — followed by the elements. A vector is one array of field elements; a
+//! matrix is its `data` array, its `indices` and `indptr` arrays of integers, and a
+//! one-element array holding `cols`.
+//!
+//! Integer arrays are written as `u32` when every value fits, which halves the index bytes of
+//! any realistic matrix, and as `u64` otherwise; the width in the header says which, and both
+//! are read back. Written with [`IndexEncoding::Delta`], the `indices` array is instead
+//! [delta-encoded](super::delta): its header carries the width [`DELTA_WIDTH`] and is followed
+//! by the `u64` length of the encoding in bytes, then the encoding.
+
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
+
+pub use super::delta::DELTA_WIDTH;
+use super::{
+    delta::{decode_deltas, encode_deltas},
+    parallel::{decode_fields, decode_u32s, decode_usizes},
+};
+use crate::SparseMatrix;
+
+/// Magic bytes opening every array.
+pub const RAW_MAGIC: [u8; 4] = *b"SPMV";
+
+/// Version of the layout described in the [module docs](self).
+pub const RAW_VERSION: u16 = 1;
+
+/// Extension of raw files, stored next to the bincode files as `<label>.raw`.
+pub const RAW_EXTENSION: &str = "raw";
+
+/// Size of the header in front of every array.
+pub const ARRAY_HEADER_BYTES: usize = 16;
+
+/// Values that can be stored with the raw codec.
+pub trait RawCodec: Sized {
+    /// Writes `self` in the raw layout.
+    fn write_raw(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+    /// Decodes a value from the whole of `bytes`; the message says what is wrong otherwise.
+    fn read_raw(bytes: &[u8]) -> Result<Self, String>;
+}
+
+impl<F: PrimeField> RawCodec for Vec<F> {
+    fn write_raw(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write_fields(writer, self)
+    }
+
+    fn read_raw(bytes: &[u8]) -> Result<Self, String> {
+        let mut arrays = Arrays { bytes };
+        let vector = arrays.fields()?;
+        arrays.finish()?;
+        Ok(vector)
+    }
+}
+
+/// How the column indices of a matrix are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexEncoding {
+    /// As integers, like `indptr`.
+    #[default]
+    Plain,
+    /// As the differences between consecutive columns of each row, which must be sorted.
+    Delta,
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+    /// Writes the matrix in the raw layout, with its column indices stored as `encoding` says.
+    /// [`IndexEncoding::Delta`] fails with [`io::ErrorKind::InvalidInput`] on a row whose
+    /// columns are not sorted.
+    pub fn write_raw_encoded(
+        &self,
+        writer: &mut dyn Write,
+        encoding: IndexEncoding,
+    ) -> io::Result<()> {
+        write_fields(writer, &self.data)?;
+        match encoding {
+            IndexEncoding::Plain => write_usizes(writer, &self.indices)?,
+            IndexEncoding::Delta => {
+                let deltas = encode_deltas(&self.indices, &self.indptr)
+                    .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+                write_header(writer, DELTA_WIDTH as usize, self.indices.len())?;
+                writer.write_all(&(deltas.len() as u64).to_le_bytes())?;
+                writer.write_all(&deltas)?;
+            }
+        }
+        write_usizes(writer, &self.indptr)?;
+        write_usizes(writer, &[self.cols])
+    }
+}
+
+impl<F: PrimeField> RawCodec for SparseMatrix<F> {
+    fn write_raw(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.write_raw_encoded(writer, IndexEncoding::Plain)
+    }
+
+    fn read_raw(bytes: &[u8]) -> Result<Self, String> {
+        let mut arrays = Arrays { bytes };
+        let data = arrays.fields()?;
+        // Delta-encoded indices can only be decoded once `indptr` delimits their rows.
+        let indices = arrays.next_any()?;
+        let indptr = arrays.usizes()?;
+        let indices = match indices {
+            (width, deltas) if width == DELTA_WIDTH as usize => decode_deltas(deltas, &indptr)?,
+            (width, payload) => decode_integers(width, payload)?,
+        };
+        let cols = match arrays.usizes()?[..] {
+            [cols] => cols,
+            ref shape => return Err(format!("expected 1 shape entry, found {}", shape.len())),
+        };
+        arrays.finish()?;
+        let matrix = SparseMatrix::new_unchecked(data, indices, indptr, cols);
+        matrix.check_lengths().map_err(|err| err.to_string())?;
+        Ok(matrix)
+    }
+}
+
+fn write_header(writer: &mut dyn Write, width: usize, count: usize) -> io::Result<()> {
+    writer.write_all(&RAW_MAGIC)?;
+    writer.write_all(&RAW_VERSION.to_le_bytes())?;
+    writer.write_all(&(width as u16).to_le_bytes())?;
+    writer.write_all(&(count as u64).to_le_bytes())
+}
+
+fn write_fields<F: PrimeField>(writer: &mut dyn Write, values: &[F]) -> io::Result<()> {
+    let width = F::Repr::default().as_ref().len();
+    write_header(writer, width, values.len())?;
+    for value in values {
+        writer.write_all(value.to_repr().as_ref())?;
+    }
+    Ok(())
+}
+
+fn write_usizes(writer: &mut dyn Write, values: &[usize]) -> io::Result<()> {
+    let width = index_width(values);
+    write_header(writer, width, values.len())?;
+    for &value in values {
+        if width == 4 {
+            writer.write_all(&(value as u32).to_le_bytes())?;
+        } else {
+            writer.write_all(&(value as u64).to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Bytes per element the raw codec stores `values` with: 4 if every one fits in a `u32`,
+/// and 8 otherwise.
+pub fn index_width(values: &[usize]) -> usize {
+    if values.iter().all(|&value| u32::try_from(value).is_ok()) {
+        4
+    } else {
+        8
+    }
+}
+
+/// Size of `matrix` in the raw codec, without encoding it.
+pub fn raw_matrix_bytes<F: PrimeField>(matrix: &SparseMatrix<F>) -> u64 {
+    let width = F::Repr::default().as_ref().len();
+    let arrays = [
+        (width, matrix.data.len()),
+        (index_width(&matrix.indices), matrix.indices.len()),
+        (index_width(&matrix.indptr), matrix.indptr.len()),
+        (index_width(&[matrix.cols]), 1),
+    ];
+    arrays
+        .iter()
+        .map(|&(width, count)| (ARRAY_HEADER_BYTES + width * count) as u64)
+        .sum()
+}
+
+/// Reads the header of a raw vector from `reader`, leaving it at the first element, and
+/// returns the number of elements.
+pub fn read_vector_header<F: PrimeField>(reader: &mut impl Read) -> Result<u64, String> {
+    let mut header = [0; ARRAY_HEADER_BYTES];
+    reader
+        .read_exact(&mut header)
+        .map_err(|err| format!("cannot read the array header: {err}"))?;
+    parse_header(&header, F::Repr::default().as_ref().len())
+}
+
+/// Checks an array header for elements `width` bytes wide, returning the element count.
+fn parse_header(header: &[u8; ARRAY_HEADER_BYTES], width: usize) -> Result<u64, String> {
+    let (found, count) = parse_any_header(header)?;
+    if found != width {
+        return Err(format!("elements are {found} bytes wide, expected {width}"));
+    }
+    Ok(count)
+}
+
+/// Checks an array header, returning the width of its elements and their count.
+fn parse_any_header(header: &[u8; ARRAY_HEADER_BYTES]) -> Result<(usize, u64), String> {
+    if header[..4] != RAW_MAGIC {
+        return Err("not a raw file".to_string());
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != RAW_VERSION {
+        return Err(format!("unsupported version {version}"));
+    }
+    let width = u16::from_le_bytes([header[6], header[7]]) as usize;
+    Ok((width, u64::from_le_bytes(header[8..].try_into().unwrap())))
+}
+
+/// Decodes the payload of an array of `u32`s or `u64`s.
+fn decode_integers(width: usize, payload: &[u8]) -> Result<Vec<usize>, String> {
+    match width {
+        4 => Ok(decode_u32s(payload)),
+        8 => decode_usizes(payload),
+        _ => Err(format!("integers are {width} bytes wide, expected 4 or 8")),
+    }
+}
+
+/// The arrays of a raw file not decoded yet.
+struct Arrays<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Arrays<'a> {
+    /// Splits off the next array, checking that its elements are `width` bytes wide.
+    fn next(&mut self, width: usize) -> Result<&'a [u8], String> {
+        let (found, payload) = self.next_any()?;
+        if found != width {
+            return Err(format!("elements are {found} bytes wide, expected {width}"));
+        }
+        Ok(payload)
+    }
+
+    /// Splits off the next array, returning the width of its elements with it.
+    fn next_any(&mut self) -> Result<(usize, &'a [u8]), String> {
+        let Some((header, rest)) = self.bytes.split_first_chunk::<ARRAY_HEADER_BYTES>() else {
+            return Err("truncated array header".to_string());
+        };
+        let (width, count) = parse_any_header(header)?;
+        let (len, rest) = if width == DELTA_WIDTH as usize {
+            let Some((len, rest)) = rest.split_first_chunk::<8>() else {
+                return Err("truncated delta-encoded array".to_string());
+            };
+            (usize::try_from(u64::from_le_bytes(*len)).ok(), rest)
+        } else {
+            let len = usize::try_from(count)
+                .ok()
+                .and_then(|count| count.checked_mul(width));
+            (len, rest)
+        };
+        let len = len
+            .filter(|&len| len <= rest.len())
+            .ok_or_else(|| format!("truncated array of {count} elements"))?;
+        let (payload, rest) = rest.split_at(len);
+        self.bytes = rest;
+        Ok((width, payload))
+    }
+
+    fn fields<F: PrimeField>(&mut self) -> Result<Vec<F>, String> {
+        let width = F::Repr::default().as_ref().len();
+        decode_fields(self.next(width)?)
+    }
+
+    fn usizes(&mut self) -> Result<Vec<usize>, String> {
+        let (width, payload) = self.next_any()?;
+        decode_integers(width, payload)
+    }
+
+    /// Checks that every byte has been decoded.
+    fn finish(self) -> Result<(), String> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            extra => Err(format!("{extra} trailing bytes")),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Where the files of a data root come from when they are not under it yet.
+//!
+//! A [`DataConfig`] reads files from its root directory. Given a [`DataSource`] with
+//! [`DataConfig::with_source`], or one for a single section with
+//! [`DataConfig::with_section_source`], a file missing from the root is fetched from the
+//! source into the root when it is first located, so the root becomes a cache of the source
+//! that later runs read without fetching. A source cannot be listed, so each section it serves
+//! must have a [manifest](super::manifest): the manifest is fetched first, lists the labels of
+//! the section, and every file fetched is checked against it as it is written.
+//!
+//! [`LocalSource`] fetches from another directory, such as a slow network mount, and
+//! [`http::HttpSource`], with the `http` feature, from a web server or an S3 bucket. Only
+//! `file://`, `http://`, `https://`, and `s3://` URLs are supported, see [`check_url`].
+
+use std::{
+    fmt, fs,
+    io::{self, BufWriter, Read, Write},
+    sync::Arc,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{
+    atomic_write, compressed_path,
+    manifest::{Hasher, HashingWriter},
+    progress, progress_label, DataConfig, DataError, Manifest, ProgressReader, ProgressStyle,
+    MANIFEST_FILE,
+};
+use crate::hex::bytes_to_hex;
+
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "http")]
+pub mod s3;
+
+/// Subdirectory of the local root that the files of a remote data root are cached under, in
+/// a directory named after its URL.
+pub const REMOTE_DIR: &str = "remote";
+
+/// Somewhere the files of sections can be read from.
+pub trait DataSource: fmt::Debug + Send + Sync {
+    /// Where the source is, as in a URL, for messages.
+    fn location(&self) -> String;
+
+    /// Opens the file stored as `label` in `section`, or `None` if there is none. `section`
+    /// is empty for a source given for a single section, which serves its files directly.
+    fn open(&self, section: &str, label: &str) -> Result<Option<Box<dyn Read + Send>>, DataError>;
+}
+
+/// The files of another data root directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalSource {
+    pub root_dir: Utf8PathBuf,
+}
+
+impl DataSource for LocalSource {
+    fn location(&self) -> String {
+        self.root_dir.to_string()
+    }
+
+    fn open(&self, section: &str, label: &str) -> Result<Option<Box<dyn Read + Send>>, DataError> {
+        let path = self.root_dir.join(section).join(label);
+        match fs::File::open(&path) {
+            Ok(file) => Ok(Some(Box::new(file))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(DataError::Io { path, source }),
+        }
+    }
+}
+
+/// Whether `location`, as given for a data root, is a URL rather than a directory.
+pub fn is_url(location: &str) -> bool {
+    location.contains("://")
+}
+
+/// Checks that `url` has a scheme a source can be opened for: `file://`, `http://`,
+/// `https://`, or `s3://`.
+pub fn check_url(url: &str) -> Result<(), String> {
+    let scheme = url.split_once("://").map_or(url, |(scheme, _)| scheme);
+    match scheme {
+        "file" | "http" | "https" | "s3" => Ok(()),
+        _ => Err(format!(
+            "{url}: expected an http://, https://, s3://, or file:// URL"
+        )),
+    }
+}
+
+/// The source at `url`: a `file://` URL names a [`LocalSource`], and an `http://`,
+/// `https://`, or `s3://` one an [`http::HttpSource`], which needs the `http` feature. Other
+/// schemes are rejected, as by [`check_url`].
+pub fn open_source(url: &str) -> Result<Arc<dyn DataSource>, DataError> {
+    check_url(url).map_err(|message| DataError::Fetch {
+        location: url.to_string(),
+        message,
+    })?;
+    if let Some(dir) = url.strip_prefix("file://") {
+        return Ok(Arc::new(LocalSource {
+            root_dir: dir.into(),
+        }));
+    }
+    #[cfg(feature = "http")]
+    {
+        Ok(Arc::new(http::HttpSource::new(url)?))
+    }
+    #[cfg(not(feature = "http"))]
+    Err(DataError::Fetch {
+        location: url.to_string(),
+        message: "fetching over the network needs the `http` feature".to_string(),
+    })
+}
+
+/// The directory under [`REMOTE_DIR`] that the files from `url` are cached in, as in
+/// `http_example.com_8080_dumps_<hash>` for `http://example.com:8080/dumps/`. The readable
+/// part alone would be the same for `/a_b` and `/a/b`, so it ends with a hash of the URL.
+pub fn cache_name(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let hash = blake3::hash(url.as_bytes());
+    format!(
+        "{}_{}",
+        url.replace("://", "_").replace(['/', ':'], "_"),
+        bytes_to_hex(&hash.as_bytes()[..8])
+    )
+}
+
+impl DataConfig {
+    /// Fetches the files missing from the root from `source`.
+    pub fn with_source(self, source: Arc<dyn DataSource>) -> Self {
+        Self {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    /// Fetches the files missing from `section` from `source`, which serves the files of that
+    /// section alone, instead of from the source of the root.
+    pub fn with_section_source(
+        mut self,
+        section: impl Into<String>,
+        source: Arc<dyn DataSource>,
+    ) -> Self {
+        self.section_sources.insert(section.into(), source);
+        self
+    }
+
+    /// The source the files of `section` are fetched from, with the name it knows the
+    /// section by.
+    fn source_of<'a>(&'a self, section: &'a str) -> Option<(&'a dyn DataSource, &'a str)> {
+        match self.section_sources.get(section) {
+            Some(source) => Some((source.as_ref(), "")),
+            None => self.source.as_deref().map(|source| (source, section)),
+        }
+    }
+
+    /// Fetches the manifest of `section` from its source if the root does not have one yet,
+    /// and returns whether the files it lists can be fetched: false if `section` has no source
+    /// or its source does not serve it.
+    pub(super) fn fetch_listing(&self, section: &Utf8Path) -> Result<bool, DataError> {
+        let Some((source, remote)) = self.source_of(section.as_str()) else {
+            return Ok(false);
+        };
+        let section_path = self.root_dir.join(section);
+        if section_path.join(MANIFEST_FILE).is_file() {
+            return Ok(true);
+        }
+        let Some(mut reader) = source.open(remote, MANIFEST_FILE)? else {
+            return Ok(false);
+        };
+        // Manifests are small, and fetching this one first leaves nothing behind if it fails.
+        let mut json = Vec::new();
+        reader
+            .read_to_end(&mut json)
+            .map_err(|err| fetch_error(source, remote, MANIFEST_FILE, err))?;
+
+        let _lock = self.lock_section(section)?;
+        let path = section_path.join(MANIFEST_FILE);
+        if !path.is_file() {
+            atomic_write(&path, |file| {
+                file.write_all(&json).map_err(|source| DataError::Io {
+                    path: path.clone(),
+                    source,
+                })
+            })?;
+        }
+        Manifest::read(&section_path)?;
+        Ok(true)
+    }
+
+    /// Fetches the file of `label` in `section` from its source into the root, whether it is
+    /// stored plain or compressed, and returns its path; or `None` if the manifest fetched by
+    /// [`DataConfig::fetch_listing`] does not list it. The file is checked against the
+    /// manifest as it is written, and only put in place if it matches.
+    pub(super) fn fetch(
+        &self,
+        section: &Utf8Path,
+        label: &Utf8Path,
+    ) -> Result<Option<Utf8PathBuf>, DataError> {
+        let Some((source, remote)) = self.source_of(section.as_str()) else {
+            return Ok(None);
+        };
+        let section_path = self.root_dir.join(section);
+        let Some(manifest) = Manifest::read(&section_path)? else {
+            return Ok(None);
+        };
+        let plain = section_path.join(label);
+        let compressed = compressed_path(&plain);
+        let Some((path, expected)) = [plain, compressed].into_iter().find_map(|path| {
+            let entry = manifest.files.get(path.file_name()?)?.clone();
+            Some((path, entry))
+        }) else {
+            return Ok(None);
+        };
+
+        // Other threads and runs fetching the same file wait for this one.
+        let _lock = self.lock_entry(&path)?;
+        if path.is_file() {
+            return Ok(Some(path));
+        }
+        let name = path.file_name().unwrap_or_default();
+        let Some(reader) = source.open(remote, name)? else {
+            return Err(fetch_error(
+                source,
+                remote,
+                name,
+                "listed in the manifest, but not served",
+            ));
+        };
+        eprintln!(
+            "fetching {} ({}) from {}",
+            progress_label(&path),
+            progress::format_size(expected.bytes),
+            source.location()
+        );
+        let mut reader: Box<dyn Read> = match expected.bytes < progress::PROGRESS_MIN_BYTES {
+            true => reader,
+            false => Box::new(ProgressReader::new(
+                reader,
+                progress_label(&path),
+                expected.bytes,
+                ProgressStyle::detect(),
+                io::stderr(),
+            )),
+        };
+        atomic_write(&path, |file| {
+            let mut writer = HashingWriter {
+                inner: BufWriter::new(file),
+                hasher: Hasher::default(),
+            };
+            io::copy(&mut reader, &mut writer)
+                .map_err(|err| fetch_error(source, remote, name, err))?;
+            writer.flush().map_err(|source| DataError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            let actual = writer.hasher.finish();
+            match actual == expected {
+                true => Ok(()),
+                false => Err(DataError::ChecksumMismatch {
+                    path: path.clone(),
+                    expected: expected.hash.clone(),
+                    actual: actual.hash,
+                }),
+            }
+        })?;
+        Ok(Some(path))
+    }
+
+    /// The labels the fetched manifest of `section` lists, if it has a source.
+    pub(super) fn fetchable_files(&self, section: &Utf8Path) -> Result<Vec<String>, DataError> {
+        if !self.fetch_listing(section)? {
+            return Ok(Vec::new());
+        }
+        let manifest = Manifest::read(&self.root_dir.join(section))?;
+        Ok(manifest.map_or_else(Vec::new, |manifest| manifest.files.into_keys().collect()))
+    }
+}
+
+/// The error of fetching `label` of `section` from `source`.
+fn fetch_error(
+    source: &dyn DataSource,
+    section: &str,
+    label: &str,
+    message: impl fmt::Display,
+) -> DataError {
+    let location = source.location();
+    let location = location.trim_end_matches('/');
+    DataError::Fetch {
+        location: match section {
+            "" => format!("{location}/{label}"),
+            section => format!("{location}/{section}/{label}"),
+        },
+        message: message.to_string(),
+    }
+}
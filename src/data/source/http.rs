@@ -0,0 +1,338 @@
+//! Fetching files over HTTP, from a web server or an S3 bucket.
+//!
+//! Files are streamed in ranges of [`HttpSource::chunk_bytes`], one request each, so that a
+//! dropped connection only costs the range it was in: the range is requested again from
+//! where the stream stopped, up to [`RETRIES`] times. Servers that ignore ranges send the
+//! whole file in one response, which is read as it is.
+//!
+//! Requests go through [`ureq`], with TLS from rustls for `https://` URLs. An `s3://` URL is
+//! read through the HTTPS endpoint of its bucket, signing requests when there are credentials
+//! to; see [`s3`](super::s3).
+
+use std::{
+    io::{self, Read},
+    time::{Duration, SystemTime},
+};
+
+use ureq::{http::Response as HttpResponse, Agent, Body, BodyReader};
+
+use super::{check_url, s3::S3Config, DataSource};
+use crate::data::DataError;
+
+/// Bytes requested at a time by default.
+pub const DEFAULT_CHUNK_BYTES: u64 = 64 << 20;
+
+/// How many times a range is requested again after its stream fails.
+pub const RETRIES: usize = 3;
+
+/// How long connecting, or waiting for the response, may take before the range is requested
+/// again.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a whole range may take to arrive before it is requested again from where it
+/// stopped; about 37 KB/s at the default chunk size.
+const RANGE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A data root or section served over HTTP.
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    /// The URL as given, for messages.
+    url: String,
+    /// The scheme and authority requests go to, as in `https://example.com:8443`.
+    origin: String,
+    /// The authority alone, which requests to S3 sign.
+    authority: String,
+    /// Path of the root on the server, percent-encoded and without a trailing `/`.
+    path: String,
+    /// How to sign requests, for an `s3://` URL.
+    s3: Option<S3Config>,
+    agent: Agent,
+    /// Bytes requested at a time.
+    pub chunk_bytes: u64,
+}
+
+impl HttpSource {
+    /// The source at `url`, an `http://`, `https://`, or `s3://` URL. The bucket of an
+    /// `s3://` URL is found, and its requests signed, as the environment says; see
+    /// [`S3Config::from_env`].
+    pub fn new(url: &str) -> Result<Self, DataError> {
+        match url.strip_prefix("s3://") {
+            Some(_) => Self::s3(url, S3Config::from_env()),
+            None => Self::at(url, url, None),
+        }
+    }
+
+    /// The source at `url`, an `s3://bucket/prefix` URL, read as `config` says.
+    pub fn s3(url: &str, config: S3Config) -> Result<Self, DataError> {
+        let Some(rest) = url.strip_prefix("s3://") else {
+            return Err(DataError::Fetch {
+                location: url.to_string(),
+                message: format!("{url}: expected an s3:// URL"),
+            });
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(DataError::Fetch {
+                location: url.to_string(),
+                message: "no bucket".to_string(),
+            });
+        }
+        let bucket_url = config.bucket_url(bucket, prefix);
+        Self::at(url, &bucket_url, Some(config))
+    }
+
+    /// The source named `url` that requests go to `http_url` for.
+    fn at(url: &str, http_url: &str, s3: Option<S3Config>) -> Result<Self, DataError> {
+        let invalid = |message: &str| DataError::Fetch {
+            location: url.to_string(),
+            message: message.to_string(),
+        };
+        let Some((scheme, rest)) = ["http", "https"]
+            .into_iter()
+            .find_map(|scheme| Some((scheme, http_url.strip_prefix(scheme)?.strip_prefix("://")?)))
+        else {
+            return Err(invalid(&check_url(url).err().unwrap_or_else(|| {
+                format!("{url}: expected an http:// or https:// URL")
+            })));
+        };
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                port.parse::<u16>().map_err(|_| invalid("invalid port"))?;
+                host
+            }
+            None => authority,
+        };
+        if host.is_empty() {
+            return Err(invalid("no host"));
+        }
+        let config = Agent::config_builder()
+            .http_status_as_error(false)
+            // Every range is a request of its own, so connections are not kept for later.
+            .max_idle_connections(0)
+            .timeout_connect(Some(TIMEOUT))
+            .timeout_recv_response(Some(TIMEOUT))
+            .timeout_recv_body(Some(RANGE_TIMEOUT))
+            .user_agent("spmvm")
+            .build();
+        Ok(Self {
+            url: url.to_string(),
+            origin: format!("{scheme}://{authority}"),
+            authority: authority.to_string(),
+            path: encode_path(path.trim_end_matches('/')),
+            s3,
+            agent: Agent::new_with_config(config),
+            chunk_bytes: DEFAULT_CHUNK_BYTES,
+        })
+    }
+
+    /// Requests `chunk_bytes` at a time instead of [`DEFAULT_CHUNK_BYTES`].
+    pub fn with_chunk_bytes(self, chunk_bytes: u64) -> Self {
+        Self {
+            chunk_bytes: chunk_bytes.max(1),
+            ..self
+        }
+    }
+
+    /// Requests the range of `path` starting at `start`.
+    fn request(&self, path: &str, start: u64) -> io::Result<Response> {
+        let end = start + self.chunk_bytes - 1;
+        let range = format!("bytes={start}-{end}");
+        let mut request = self
+            .agent
+            .get(format!("{}{path}", self.origin))
+            .header("range", &range);
+        if let Some(s3) = &self.s3 {
+            let signed = s3.signed_headers(&self.authority, path, &range, SystemTime::now());
+            for (name, value) in signed {
+                request = request.header(name, value);
+            }
+        }
+        let response = request.call().map_err(ureq::Error::into_io)?;
+        Ok(Response::new(response))
+    }
+}
+
+/// `path` with everything but unreserved characters and `/` percent-encoded, as S3 signs it.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+impl DataSource for HttpSource {
+    fn location(&self) -> String {
+        self.url.clone()
+    }
+
+    fn open(&self, section: &str, label: &str) -> Result<Option<Box<dyn Read + Send>>, DataError> {
+        let path = match section {
+            "" => format!("{}/{}", self.path, encode_path(label)),
+            section => format!(
+                "{}/{}/{}",
+                self.path,
+                encode_path(section),
+                encode_path(label)
+            ),
+        };
+        let fetch_error = |err: io::Error| DataError::Fetch {
+            location: format!("{}{path}", self.origin),
+            message: err.to_string(),
+        };
+        let response = self.request(&path, 0).map_err(fetch_error)?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        let mut reader = RangeReader {
+            source: self.clone(),
+            path: path.clone(),
+            offset: 0,
+            total: None,
+            body: None,
+            sized: false,
+        };
+        reader.start(response).map_err(fetch_error)?;
+        Ok(Some(Box::new(reader)))
+    }
+}
+
+/// The status, headers, and body of a response.
+struct Response {
+    status: u16,
+    content_length: Option<u64>,
+    /// The size of the whole file, from the `Content-Range` of a partial response.
+    total: Option<u64>,
+    body: BodyReader<'static>,
+}
+
+impl Response {
+    fn new(response: HttpResponse<Body>) -> Self {
+        let header = |name: &str| {
+            let value = response.headers().get(name)?;
+            value.to_str().ok().map(str::trim).map(str::to_string)
+        };
+        let content_length = header("content-length").and_then(|length| length.parse().ok());
+        // As in `bytes 0-99/1000`, or `bytes */1000` for a range past the end.
+        let total = header("content-range")
+            .and_then(|range| range.rsplit_once('/').and_then(|(_, t)| t.parse().ok()));
+        Self {
+            status: response.status().as_u16(),
+            content_length,
+            total,
+            body: response.into_body().into_reader(),
+        }
+    }
+}
+
+/// A file streamed a range at a time.
+struct RangeReader {
+    source: HttpSource,
+    path: String,
+    /// Bytes of the file read so far.
+    offset: u64,
+    /// Size of the file, once a response has said.
+    total: Option<u64>,
+    /// The rest of the body of the current response.
+    body: Option<io::Take<BodyReader<'static>>>,
+    /// Whether the current response gave its length, so that a body ending early is a
+    /// dropped connection rather than the end of the file.
+    sized: bool,
+}
+
+impl RangeReader {
+    /// Takes over `response` to the range starting at the offset.
+    fn start(&mut self, response: Response) -> io::Result<()> {
+        self.sized = response.content_length.is_some();
+        let length = response.content_length.unwrap_or(u64::MAX);
+        match response.status {
+            206 => {
+                self.total = response.total.or(self.total);
+                self.body = Some(response.body.take(length));
+            }
+            // The whole file, from a server that ignores ranges; skip what was read already.
+            200 => {
+                self.total = response.content_length;
+                let mut body = response.body.take(length);
+                io::copy(&mut (&mut body).take(self.offset), &mut io::sink())?;
+                self.body = Some(body);
+            }
+            // The file ends where the range starts.
+            416 => self.total = Some(response.total.unwrap_or(self.offset)),
+            status => {
+                return Err(io::Error::other(format!(
+                    "the server answered HTTP {status}"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.total.is_some_and(|total| self.offset >= total)
+    }
+
+    /// Requests the range after what was read, requesting it again if that fails.
+    fn next_range(&mut self) -> io::Result<()> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .source
+                .request(&self.path, self.offset)
+                .and_then(|response| self.start(response));
+            match result {
+                Err(err) => attempt = retry(attempt, err)?,
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// The next attempt after `attempt` failed with `err`, or `err` once out of [`RETRIES`].
+fn retry(attempt: usize, err: io::Error) -> io::Result<usize> {
+    match attempt < RETRIES {
+        true => Ok(attempt + 1),
+        false => Err(err),
+    }
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut attempt = 0;
+        loop {
+            if let Some(body) = &mut self.body {
+                match body.read(buf) {
+                    Ok(0) => {
+                        let cut_short = self.sized && body.limit() > 0;
+                        self.body = None;
+                        if cut_short {
+                            attempt = retry(attempt, io::ErrorKind::UnexpectedEof.into())?;
+                        } else if !self.sized && self.total.is_none() {
+                            // A body of unknown length runs to the end of the file.
+                            self.total = Some(self.offset);
+                        }
+                    }
+                    Ok(read) => {
+                        self.offset += read as u64;
+                        return Ok(read);
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        self.body = None;
+                        attempt = retry(attempt, err)?;
+                    }
+                }
+            }
+            if self.is_done() {
+                return Ok(0);
+            }
+            self.next_range()?;
+        }
+    }
+}
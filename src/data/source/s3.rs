@@ -0,0 +1,178 @@
+//! Reading `s3://` URLs, through the HTTPS endpoint of the bucket.
+//!
+//! `s3://bucket/prefix` is read from `https://bucket.s3.<region>.amazonaws.com/prefix`, or
+//! from `<endpoint>/bucket/prefix` given an endpoint of its own, such as a MinIO server. As
+//! for the AWS command line, the region comes from `AWS_REGION` or `AWS_DEFAULT_REGION`, and
+//! the endpoint from `AWS_ENDPOINT_URL`. Given `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`,
+//! and `AWS_SESSION_TOKEN` for temporary credentials, requests are signed with Signature
+//! Version 4; without them they are anonymous, which buckets allowing public reads accept.
+
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ring::{digest, hmac};
+
+use crate::hex::bytes_to_hex;
+
+/// The region of buckets when the environment names none.
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// The hash of an empty payload, as every request is a `GET`.
+const EMPTY_PAYLOAD_SHA256: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// The keys requests are signed with.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// The token of temporary credentials.
+    pub session_token: Option<String>,
+}
+
+impl fmt::Debug for Credentials {
+    // The secret stays out of messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where buckets are, and the credentials to read them with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Config {
+    pub region: String,
+    /// The URL of an endpoint serving buckets by path, instead of AWS.
+    pub endpoint: Option<String>,
+    /// Anonymous requests are sent without credentials.
+    pub credentials: Option<Credentials>,
+}
+
+impl S3Config {
+    /// The config given by the environment, as described in the [module docs](self).
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// The config given by `var`, which looks up a variable of the environment.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let credentials = match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            (Some(access_key_id), Some(secret_access_key)) => Some(Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            }),
+            _ => None,
+        };
+        Self {
+            region: var("AWS_REGION")
+                .or_else(|| var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            endpoint: var("AWS_ENDPOINT_URL"),
+            credentials,
+        }
+    }
+
+    /// The `http://` or `https://` URL that `prefix` of `bucket` is read from.
+    pub fn bucket_url(&self, bucket: &str, prefix: &str) -> String {
+        match &self.endpoint {
+            Some(endpoint) => format!("{}/{bucket}/{prefix}", endpoint.trim_end_matches('/')),
+            None => format!("https://{bucket}.s3.{}.amazonaws.com/{prefix}", self.region),
+        }
+    }
+
+    /// The headers that sign a `GET` of `path` from `host` with `range`, sent at `time`, or
+    /// none for anonymous requests. `path` must already be percent-encoded, as it is sent.
+    pub fn signed_headers(
+        &self,
+        host: &str,
+        path: &str,
+        range: &str,
+        time: SystemTime,
+    ) -> Vec<(&'static str, String)> {
+        let Some(credentials) = &self.credentials else {
+            return Vec::new();
+        };
+        let date_time = amz_date(time);
+        let date = &date_time[..8];
+        let mut headers = vec![
+            ("host", host.to_string()),
+            ("range", range.to_string()),
+            ("x-amz-content-sha256", EMPTY_PAYLOAD_SHA256.to_string()),
+            ("x-amz-date", date_time.clone()),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        // The headers are in order of their names, as signing requires.
+        let names = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        let names = names.join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect();
+        let canonical_request =
+            format!("GET\n{path}\n\n{canonical_headers}\n{names}\n{EMPTY_PAYLOAD_SHA256}");
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{date_time}\n{scope}\n{}",
+            bytes_to_hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+        );
+        let key = [date, self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+                |key, part| sign(&key, part).as_ref().to_vec(),
+            );
+        let signature = bytes_to_hex(sign(&key, &string_to_sign).as_ref());
+
+        // The range goes out with the rest of the request.
+        headers.retain(|(name, _)| *name != "range");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope},SignedHeaders={names},Signature={signature}",
+                credentials.access_key_id
+            ),
+        ));
+        headers
+    }
+}
+
+fn sign(key: &[u8], message: &str) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), message.as_bytes())
+}
+
+/// `time` in UTC as signatures give it, as in `20130524T000000Z`.
+pub fn amz_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    // The civil date of a count of days since 1970-01-01, from Howard Hinnant's
+    // `civil_from_days`, counting in eras of 400 years that start on March 1.
+    let days = days + 719468;
+    let (era, day_of_era) = (days / 146097, days % 146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,136 @@
+//! # Vector Diffs
+//!
+//! Compares a computed product against its expected value and summarizes how
+//! they differ, without printing vectors that may hold millions of elements.
+
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use crate::{data::parallel::decode_fields, hex::field_to_hex};
+
+/// Number of differing positions listed by default.
+pub const DEFAULT_DIFF_LIMIT: usize = 10;
+
+/// Number of expected elements [`verify_stream`] decodes at a time.
+pub const STREAM_CHUNK_ELEMENTS: usize = 1 << 16;
+
+/// How a computed vector differs from the expected one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorDiff<F> {
+    pub actual_len: usize,
+    pub expected_len: usize,
+    /// Number of differing positions within the common length.
+    pub mismatches: usize,
+    /// The first differing positions, as `(index, actual, expected)`.
+    pub first: Vec<(usize, F, F)>,
+}
+
+impl<F: PrimeField> VectorDiff<F> {
+    /// Whether the two vectors were identical.
+    pub fn is_equal(&self) -> bool {
+        self.actual_len == self.expected_len && self.mismatches == 0
+    }
+}
+
+/// Compares `actual` against `expected`, recording at most `limit` differing positions.
+/// Positions are only compared up to the shorter length; a length difference is reported separately.
+pub fn diff_vectors<F: PrimeField>(actual: &[F], expected: &[F], limit: usize) -> VectorDiff<F> {
+    let differs = |(_, (a, e)): &(usize, (&F, &F))| a != e;
+    let mismatches = actual
+        .par_iter()
+        .zip(expected)
+        .enumerate()
+        .filter(differs)
+        .count();
+    let first = actual
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(differs)
+        .take(limit)
+        .map(|(i, (a, e))| (i, *a, *e))
+        .collect();
+
+    VectorDiff {
+        actual_len: actual.len(),
+        expected_len: expected.len(),
+        mismatches,
+        first,
+    }
+}
+
+/// Compares `actual` against the expected vector read from `reader` in its bincode encoding, a
+/// `u64` length followed by the representation of every element, like [`diff_vectors`].
+/// The expected vector is decoded [`STREAM_CHUNK_ELEMENTS`] at a time and never held whole, and
+/// elements past the end of `actual` are not read at all.
+///
+/// Truncated input fails with [`io::ErrorKind::UnexpectedEof`], and an element that is not
+/// canonical with [`io::ErrorKind::InvalidData`].
+pub fn verify_stream<F: PrimeField>(
+    mut reader: impl Read,
+    actual: &[F],
+    limit: usize,
+) -> io::Result<VectorDiff<F>> {
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    let expected_len = usize::try_from(u64::from_le_bytes(len))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let mut diff = VectorDiff {
+        actual_len: actual.len(),
+        expected_len,
+        mismatches: 0,
+        first: Vec::new(),
+    };
+    let width = F::Repr::default().as_ref().len();
+    let compared = expected_len.min(actual.len());
+    let mut chunk = vec![0; STREAM_CHUNK_ELEMENTS.min(compared) * width];
+    for start in (0..compared).step_by(STREAM_CHUNK_ELEMENTS) {
+        let end = (start + STREAM_CHUNK_ELEMENTS).min(compared);
+        let bytes = &mut chunk[..(end - start) * width];
+        reader.read_exact(bytes)?;
+        let expected: Vec<F> = decode_fields(bytes)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+
+        let part = diff_vectors(&actual[start..end], &expected, limit - diff.first.len());
+        diff.mismatches += part.mismatches;
+        diff.first
+            .extend(part.first.into_iter().map(|(i, a, e)| (start + i, a, e)));
+    }
+    Ok(diff)
+}
+
+impl<F: PrimeField> fmt::Display for VectorDiff<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.actual_len != self.expected_len {
+            writeln!(
+                f,
+                "length {} differs from expected length {}",
+                self.actual_len, self.expected_len
+            )?;
+        }
+        write!(
+            f,
+            "{} of {} positions differ",
+            self.mismatches,
+            self.actual_len.min(self.expected_len)
+        )?;
+        for (i, actual, expected) in &self.first {
+            write!(
+                f,
+                "\n  [{i}] got {}, expected {}",
+                field_to_hex(actual),
+                field_to_hex(expected)
+            )?;
+        }
+        if self.mismatches > self.first.len() {
+            write!(f, "\n  ... and {} more", self.mismatches - self.first.len())?;
+        }
+        Ok(())
+    }
+}
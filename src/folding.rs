@@ -0,0 +1,52 @@
+//! # Folding
+//!
+//! The cross term that Nova's folding scheme commits to when it folds two relaxed R1CS
+//! instances, computed from the products this tool benchmarks.
+
+use ff::PrimeField;
+
+use crate::{
+    sparse::{FusedProducts, MatrixError},
+    vec_ops::{add_vec_assign, hadamard_assign, sub_scaled_vec_assign},
+    SparseMatrix,
+};
+
+/// The cross term `T = AZ₁ ∘ BZ₂ + AZ₂ ∘ BZ₁ − u₁ CZ₂ − u₂ CZ₁` of the instances `(z1, u1)` and
+/// `(z2, u2)`, from the six products of `a`, `b`, and `c` with the two witnesses, computed
+/// with [`SparseMatrix::multiply_vec_fused`]. The matrices must have the same shape, and the
+/// witnesses an element for every column.
+pub fn compute_cross_term<F: PrimeField>(
+    a: &SparseMatrix<F>,
+    b: &SparseMatrix<F>,
+    c: &SparseMatrix<F>,
+    z1: &[F],
+    z2: &[F],
+    u1: F,
+    u2: F,
+) -> Result<Vec<F>, MatrixError> {
+    let first = SparseMatrix::multiply_vec_fused(a, b, c, z1)?;
+    let second = SparseMatrix::multiply_vec_fused(a, b, c, z2)?;
+    Ok(cross_term(first, second, u1, u2))
+}
+
+/// The cross term of two instances from their products, `first` with `(z1, u1)` and `second`
+/// with `(z2, u2)`. It is added up in place in the buffers of `AZ₁` and `AZ₂`, which it takes
+/// ownership of, so that nothing is allocated beyond the products.
+///
+/// # Panics
+///
+/// If the products are not all of the same length.
+pub fn cross_term<F: PrimeField>(
+    first: FusedProducts<F>,
+    second: FusedProducts<F>,
+    u1: F,
+    u2: F,
+) -> Vec<F> {
+    let ((mut t, bz1, cz1), (mut scratch, bz2, cz2)) = (first, second);
+    hadamard_assign(&mut t, &bz2);
+    hadamard_assign(&mut scratch, &bz1);
+    add_vec_assign(&mut t, &scratch);
+    sub_scaled_vec_assign(&mut t, u1, &cz2);
+    sub_scaled_vec_assign(&mut t, u2, &cz1);
+    t
+}
@@ -0,0 +1,67 @@
+//! # Synthetic Data
+//!
+//! Seeded random matrices and witnesses, so that the tool can be exercised
+//! without a real arecibo dump. The same seed always yields the same values.
+
+use ff::PrimeField;
+use rand::{seq::index, Rng, RngCore};
+
+use crate::{sparse::CooMatrix, SparseMatrix};
+
+/// Shape of a synthetic dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape {
+    pub rows: usize,
+    pub cols: usize,
+    /// Number of nonzeros in every row; at most `cols`.
+    pub nnz_per_row: usize,
+}
+
+/// A matrix of the given shape, as [`SparseMatrix::random`] draws it.
+///
+/// # Panics
+///
+/// If `nnz_per_row > cols`.
+pub fn random_matrix<F: PrimeField>(rng: &mut impl Rng, shape: Shape) -> SparseMatrix<F> {
+    SparseMatrix::random(shape.rows, shape.cols, shape.nnz_per_row, rng)
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+    /// A `rows x cols` matrix with `nnz_per_row` random entries at distinct, sorted columns in
+    /// every row, each a random nonzero element.
+    ///
+    /// # Panics
+    ///
+    /// If `nnz_per_row > cols`.
+    pub fn random(rows: usize, cols: usize, nnz_per_row: usize, rng: &mut impl RngCore) -> Self {
+        assert!(
+            nnz_per_row <= cols,
+            "cannot place {nnz_per_row} nonzeros in {cols} columns"
+        );
+
+        let mut coo = CooMatrix::with_capacity(rows, cols, rows * nnz_per_row);
+        for row in 0..rows {
+            let mut columns = index::sample(rng, cols, nnz_per_row).into_vec();
+            columns.sort_unstable();
+            for col in columns {
+                coo.push(row, col, random_nonzero(rng));
+            }
+        }
+        coo.into_csr()
+    }
+}
+
+/// A random nonzero field element, drawing again in the unlikely case of zero.
+fn random_nonzero<F: PrimeField>(rng: &mut impl RngCore) -> F {
+    loop {
+        let value = F::random(&mut *rng);
+        if !bool::from(value.is_zero()) {
+            return value;
+        }
+    }
+}
+
+/// A vector of `len` random field elements.
+pub fn random_vector<F: PrimeField>(rng: &mut impl Rng, len: usize) -> Vec<F> {
+    (0..len).map(|_| F::random(&mut *rng)).collect()
+}
@@ -0,0 +1,66 @@
+//! # Hex Formatting
+//!
+//! Renders field elements as `0x`-prefixed big-endian hex, the way they are
+//! usually written down, for diffs and inspection output, and parses them back.
+
+use std::fmt::Write as _;
+
+use ff::PrimeField;
+
+/// Digits [`field_to_short_hex`] keeps at either end of a long value.
+pub const SHORT_HEX_DIGITS: usize = 4;
+
+/// Formats `value` as `0x`-prefixed big-endian hex.
+/// `PrimeField::to_repr` is little-endian for the halo2curves fields used here, so it is reversed.
+pub fn field_to_hex<F: PrimeField>(value: &F) -> String {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+    let mut out = String::with_capacity(2 + 2 * bytes.len());
+    out.push_str("0x");
+    for byte in bytes.iter().rev() {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out
+}
+
+/// Formats `bytes` as lowercase hex in their order, without a prefix, as hashes are written.
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out
+}
+
+/// Formats `value` as `0x`-prefixed hex without leading zeros, keeping only the first and last
+/// [`SHORT_HEX_DIGITS`] digits of a longer one, as in `0x3064…0000`, to fit in a grid.
+pub fn field_to_short_hex<F: PrimeField>(value: &F) -> String {
+    let hex = field_to_hex(value);
+    let digits = hex[2..].trim_start_matches('0');
+    match digits.len() {
+        0 => "0x0".to_string(),
+        len if len <= 2 * SHORT_HEX_DIGITS + 1 => format!("0x{digits}"),
+        len => format!(
+            "0x{}…{}",
+            &digits[..SHORT_HEX_DIGITS],
+            &digits[len - SHORT_HEX_DIGITS..]
+        ),
+    }
+}
+
+/// Parses `0x`-prefixed big-endian hex, as [`field_to_hex`] writes it, into the field element
+/// with that canonical value, which must be below the modulus. Leading zeros may be left out.
+pub fn field_from_hex<F: PrimeField>(hex: &str) -> Result<F, String> {
+    let invalid = || format!("{hex} is not the hex of a field element");
+    let digits = hex.strip_prefix("0x").ok_or_else(invalid)?;
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+    if digits.is_empty() || digits.len() > 2 * bytes.len() {
+        return Err(invalid());
+    }
+    for (i, digit) in digits.chars().rev().enumerate() {
+        let digit = digit.to_digit(16).ok_or_else(invalid)?;
+        bytes[i / 2] |= (digit as u8) << (4 * (i % 2));
+    }
+    Option::from(F::from_repr(repr)).ok_or_else(|| format!("{hex} is not below the modulus"))
+}
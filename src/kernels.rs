@@ -0,0 +1,32 @@
+//! # Field-Specific Kernels
+//!
+//! Products that rely on the representation of one field, which the generic kernels of
+//! [`sparse`](crate::sparse) cannot. Each falls back on the generic kernel for every other
+//! field, so callers generic over the field can use them all the same.
+
+pub mod bn256;
+
+use std::any::{Any, TypeId};
+
+use ff::PrimeField;
+use halo2curves::bn256::Fr;
+
+use crate::SparseMatrix;
+
+/// [`SparseMatrix::multiply_vec`], with [`bn256::multiply_vec`] in its place when `F` is
+/// [`halo2curves::bn256::Fr`]. The product is the same either way.
+pub fn multiply_vec_delayed<F: PrimeField>(matrix: &SparseMatrix<F>, vector: &[F]) -> Vec<F> {
+  if TypeId::of::<F>() != TypeId::of::<Fr>() {
+    return matrix.multiply_vec(vector);
+  }
+  let matrix = (matrix as &dyn Any)
+    .downcast_ref::<SparseMatrix<Fr>>()
+    .expect("the matrix is of the field checked above");
+  // SAFETY: `F` is `Fr`, as checked above, so the slice is already one of `Fr`; the vector is
+  // viewed as such rather than copied, which would cost as much as the product saves.
+  let vector = unsafe { std::slice::from_raw_parts(vector.as_ptr().cast::<Fr>(), vector.len()) };
+  let product: Box<dyn Any> = Box::new(bn256::multiply_vec(matrix, vector));
+  *product
+    .downcast()
+    .expect("the product is of the field of the matrix")
+}
@@ -0,0 +1,98 @@
+//! The product over [`Fr`] with the reduction modulo `r` delayed to the end of each row.
+//!
+//! The generic kernel reduces every product of an entry with the vector, though the sum of a
+//! row is all that is needed. Here the entries and the vector are multiplied as the 256-bit
+//! integers of their Montgomery form, and the 512-bit products are added up unreduced in 576
+//! bits. Each product is below `r^2 < 2^508`, so a row would need `2^68` entries to overflow
+//! the sum, and the sum is reduced once, at the end of the row.
+
+use std::array;
+
+use ff::{Field as _, FromUniformBytes as _};
+use halo2curves::{bn256::Fr, serde::SerdeObject as _};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+
+use crate::SparseMatrix;
+
+/// `R^-2`, for `R = 2^256` the Montgomery radix: the unreduced sum of products of Montgomery
+/// forms `a R` and `b R` is `a b R^2`.
+static R_INV_SQUARED: Lazy<Fr> = Lazy::new(|| {
+  let r = Fr::from(2).pow_vartime([256]);
+  r.square().invert().expect("R is not zero")
+});
+
+/// `2^512`, the weight of the top limb of a [`Wide`] sum.
+static TWO_512: Lazy<Fr> = Lazy::new(|| Fr::from(2).pow_vartime([512]));
+
+/// Multiply by a dense vector; uses rayon to parallelize over rows, reducing the sum of each
+/// row once rather than every product in it. The product is that of
+/// [`SparseMatrix::multiply_vec`].
+///
+/// # Panics
+///
+/// If the length of `vector` is not the number of columns.
+pub fn multiply_vec(matrix: &SparseMatrix<Fr>, vector: &[Fr]) -> Vec<Fr> {
+  assert_eq!(matrix.num_cols(), vector.len(), "invalid shape");
+
+  let vector: Vec<[u64; 4]> = vector.par_iter().map(montgomery_limbs).collect();
+  matrix
+    .indptr
+    .par_windows(2)
+    .map(|ptrs| {
+      let mut sum = Wide::default();
+      for k in ptrs[0]..ptrs[1] {
+        let value = montgomery_limbs(&matrix.data[k]);
+        sum.add_product(&value, &vector[matrix.indices[k]]);
+      }
+      sum.reduce()
+    })
+    .collect()
+}
+
+/// The limbs of the Montgomery form `a R mod r` of `a`, least significant first, as they are
+/// stored, without the reduction out of that form.
+fn montgomery_limbs(value: &Fr) -> [u64; 4] {
+  let mut bytes = [0; 32];
+  value
+    .write_raw(&mut &mut bytes[..])
+    .expect("an element is 32 bytes");
+  array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+}
+
+/// An unreduced sum of products of Montgomery forms, in 9 limbs, least significant first.
+#[derive(Debug, Default, Clone, Copy)]
+struct Wide([u64; 9]);
+
+impl Wide {
+  /// Adds the 512-bit product of `a` and `b`.
+  fn add_product(&mut self, a: &[u64; 4], b: &[u64; 4]) {
+    let mut product = [0u64; 8];
+    for (i, &a) in a.iter().enumerate() {
+      let mut carry = 0u128;
+      for (j, &b) in b.iter().enumerate() {
+        let t = a as u128 * b as u128 + product[i + j] as u128 + carry;
+        product[i + j] = t as u64;
+        carry = t >> 64;
+      }
+      product[i + 4] = carry as u64;
+    }
+    let mut carry = 0u128;
+    for (limb, product) in self.0.iter_mut().zip(product) {
+      let t = *limb as u128 + product as u128 + carry;
+      *limb = t as u64;
+      carry = t >> 64;
+    }
+    self.0[8] += carry as u64;
+  }
+
+  /// The sum as an element: reduced modulo `r`, and out of the Montgomery form.
+  fn reduce(self) -> Fr {
+    let mut bytes = [0; 64];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(&self.0[..8]) {
+      chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    let low = Fr::from_uniform_bytes(&bytes);
+    (low + Fr::from(self.0[8]) * *TWO_512) * *R_INV_SQUARED
+  }
+}
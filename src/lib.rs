@@ -0,0 +1,31 @@
+//! # spmvm-test-example
+//!
+//! A harness for benchmarking and verifying the sparse matrix / dense vector
+//! multiplications (`A z`, `B z`, `C z`) at the heart of Nova, using matrices
+//! and witnesses dumped by arecibo.
+
+#[cfg(feature = "rkyv")]
+pub mod archive;
+pub mod cli;
+pub mod compare;
+pub mod crc;
+pub mod data;
+pub mod diff;
+pub mod folding;
+pub mod generate;
+pub mod hex;
+#[cfg(feature = "delayed-reduction")]
+pub mod kernels;
+pub mod report;
+pub mod sparse;
+pub mod spy;
+pub mod statistics;
+pub mod timing;
+pub mod vec_ops;
+
+pub use data::{
+    init_config, read_arecibo_data, read_arecibo_data_many, read_arecibo_data_mmap,
+    read_arecibo_data_with_format, set_config, write_arecibo_data, write_arecibo_data_raw,
+    DataConfig, DataError, DataFormat, ARECIBO_CONFIG, ARECIBO_DATA, ARECIBO_DATA_DIR_ENV,
+};
+pub use sparse::SparseMatrix;
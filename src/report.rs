@@ -0,0 +1,395 @@
+//! # Benchmark Reports
+//!
+//! The machine-readable schema for benchmark results. The binary writes a
+//! [`BenchReport`] as JSON with `bench --output`; downstream tooling can read
+//! it back with the same types. The same report can be flattened into CSV
+//! rows with `bench --csv` for spreadsheets.
+
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::ReadPath,
+    sparse::{ChunkPolicy, CsrView},
+    statistics::Summary,
+    timing::Measurement,
+};
+
+/// Column names of [`BenchReport::write_csv`], in order.
+pub const CSV_HEADER: &str = "witness,matrix,duration_ns,nnz,rows,cols,threads";
+
+/// The results of one `bench` run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Hash identifying the dump that was benchmarked.
+    pub hash: String,
+    /// Seconds since the Unix epoch at which the report was created.
+    pub timestamp: u64,
+    /// Number of threads in the pool that ran the products; the largest count for a sweep.
+    pub threads: usize,
+    /// How the data files were read. Reports from before `--mmap` read them buffered.
+    #[serde(default)]
+    pub read_path: ReadPath,
+    /// How many rows each task of the parallel products multiplied, as given or chosen by
+    /// `--tune`. Reports from before chunk policies read as auto.
+    #[serde(default)]
+    pub chunk_policy: ChunkPolicy,
+    /// Nanoseconds it took to convert the matrices for the kernel before any timing, as for
+    /// `--layout csc` or `--backend blocked`; none if they were multiplied as loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion_ns: Option<u64>,
+    pub matrices: Vec<MatrixTiming>,
+}
+
+/// The results of one `bench` run over several dumps, as written by `bench --output` when
+/// given more than one hash or `--all`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiBenchReport {
+    /// Seconds since the Unix epoch at which the report was created.
+    pub timestamp: u64,
+    /// One entry per dump, in the order they were benchmarked.
+    pub hashes: Vec<HashReport>,
+}
+
+/// The results of one of the dumps of a [`MultiBenchReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashReport {
+    /// Hash identifying the dump, as given to `bench`.
+    pub hash: String,
+    /// One report per circuit benchmarked. Empty if the dump failed before any timing, or
+    /// was benchmarked with `--parallel-witnesses`, which times batches rather than matrices.
+    pub reports: Vec<BenchReport>,
+    /// Why benchmarking the dump failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Shape of one benchmarked matrix and the timings of its products.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixTiming {
+    /// Name of the matrix, e.g. `A`.
+    pub name: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub nnz: usize,
+    pub witnesses: Vec<WitnessTiming>,
+    /// The product of one witness timed a chunk of rows at a time, with `bench --profile-rows`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_profile: Option<RowProfile>,
+}
+
+/// Every timed run of one product for one witness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessTiming {
+    /// Index of the witness, as in the `_N` label.
+    pub witness: usize,
+    /// Number of threads the runs used. Reports from before thread sweeps lack it, in which
+    /// case it reads as 0 and [`BenchReport::threads`] applies.
+    #[serde(default)]
+    pub threads: usize,
+    /// Duration of each run in nanoseconds, in the order they ran.
+    pub durations_ns: Vec<u64>,
+}
+
+/// The product of one witness with a matrix, timed a chunk of rows at a time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowProfile {
+    /// Index of the witness, as in the `_N` label.
+    pub witness: usize,
+    /// Rows of each chunk; the last one may have fewer.
+    pub chunk_rows: usize,
+    /// Number of chunks timed.
+    pub chunks: usize,
+    /// The time of every chunk added up, in nanoseconds. The chunks run in parallel, so this
+    /// is more than the product took.
+    pub total_ns: u64,
+    /// The slowest chunks, slowest first.
+    pub slowest: Vec<ChunkTiming>,
+    /// How many chunks took how long, fastest first.
+    pub histogram: Vec<HistogramBin>,
+    /// The chunks each thread of the pool multiplied, by thread; threads that multiplied none
+    /// are left out.
+    #[serde(default)]
+    pub threads: Vec<ThreadWork>,
+}
+
+/// The time one chunk of rows took.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkTiming {
+    /// First row of the chunk.
+    pub start_row: usize,
+    /// One past the last row of the chunk.
+    pub end_row: usize,
+    /// Number of stored entries in the rows of the chunk.
+    pub nnz: usize,
+    pub duration_ns: u64,
+    /// Index in the pool of the thread that multiplied the chunk.
+    #[serde(default)]
+    pub thread: usize,
+}
+
+/// The chunks of a [`RowProfile`] that one thread multiplied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadWork {
+    /// Index of the thread in the pool.
+    pub thread: usize,
+    pub chunks: usize,
+    pub rows: usize,
+    pub nnz: usize,
+    /// The time of its chunks added up, in nanoseconds.
+    pub busy_ns: u64,
+}
+
+/// The chunks of a [`RowProfile`] that took at least `min_ns` nanoseconds and less than twice it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    /// A power of two, or 0 for the bin of chunks that took under a nanosecond.
+    pub min_ns: u64,
+    pub chunks: usize,
+}
+
+impl RowProfile {
+    /// Summarizes the timings of the `chunks` of `chunk_rows` rows of the product of `witness`,
+    /// keeping the `slowest` slowest of them, binning every one by powers of two, and adding
+    /// up the work of each thread.
+    pub fn new(
+        witness: usize,
+        chunk_rows: usize,
+        mut chunks: Vec<ChunkTiming>,
+        slowest: usize,
+    ) -> Self {
+        let bin = |ns: u64| match ns {
+            0 => 0,
+            ns => ns.ilog2() + 1,
+        };
+        let bins: Vec<_> = chunks.iter().map(|chunk| bin(chunk.duration_ns)).collect();
+        let histogram = match (bins.iter().min(), bins.iter().max()) {
+            (Some(&fastest), Some(&slowest)) => (fastest..=slowest)
+                .map(|b| HistogramBin {
+                    min_ns: match b {
+                        0 => 0,
+                        b => 1 << (b - 1),
+                    },
+                    chunks: bins.iter().filter(|&&other| other == b).count(),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut threads: BTreeMap<usize, ThreadWork> = BTreeMap::new();
+        for chunk in &chunks {
+            let work = threads.entry(chunk.thread).or_insert(ThreadWork {
+                thread: chunk.thread,
+                chunks: 0,
+                rows: 0,
+                nnz: 0,
+                busy_ns: 0,
+            });
+            work.chunks += 1;
+            work.rows += chunk.end_row - chunk.start_row;
+            work.nnz += chunk.nnz;
+            work.busy_ns += chunk.duration_ns;
+        }
+        let total_ns = chunks.iter().map(|chunk| chunk.duration_ns).sum();
+        let count = chunks.len();
+        chunks.sort_by(|a, b| {
+            b.duration_ns
+                .cmp(&a.duration_ns)
+                .then(a.start_row.cmp(&b.start_row))
+        });
+        chunks.truncate(slowest);
+        Self {
+            witness,
+            chunk_rows,
+            chunks: count,
+            total_ns,
+            slowest: chunks,
+            histogram,
+            threads: threads.into_values().collect(),
+        }
+    }
+}
+
+impl MatrixTiming {
+    /// Creates an entry for `matrix` with no timings yet.
+    pub fn new<F: PrimeField>(
+        name: impl Into<String>,
+        matrix: &(impl CsrView<F> + ?Sized),
+    ) -> Self {
+        Self {
+            name: name.into(),
+            rows: matrix.rows(),
+            cols: matrix.cols(),
+            nnz: matrix.nnz(),
+            witnesses: Vec::new(),
+            row_profile: None,
+        }
+    }
+
+    /// Median duration of every run of the product, over all witnesses and thread counts.
+    pub fn median(&self) -> Option<Duration> {
+        let durations: Vec<_> = self
+            .witnesses
+            .iter()
+            .flat_map(|timing| &timing.durations_ns)
+            .map(|&ns| Duration::from_nanos(ns))
+            .collect();
+        Summary::from_durations(&durations).map(|summary| summary.median)
+    }
+
+    /// Records the runs of `product` among `measurements` for `witness`, made with `threads` threads.
+    pub fn record(
+        &mut self,
+        witness: usize,
+        threads: usize,
+        product: &str,
+        measurements: &[Measurement],
+    ) {
+        self.witnesses.push(WitnessTiming {
+            witness,
+            threads,
+            durations_ns: measurements
+                .iter()
+                .filter(|m| m.label == product)
+                .map(|m| m.duration.as_nanos() as u64)
+                .collect(),
+        });
+    }
+}
+
+impl BenchReport {
+    /// Creates an empty report for `hash`, stamped with the current time.
+    pub fn new(hash: impl Into<String>, threads: usize) -> Self {
+        Self {
+            hash: hash.into(),
+            timestamp: now(),
+            threads,
+            read_path: ReadPath::default(),
+            chunk_policy: ChunkPolicy::default(),
+            conversion_ns: None,
+            matrices: Vec::new(),
+        }
+    }
+
+    /// Writes the report to `path` as pretty-printed JSON.
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_json(self, path)
+    }
+
+    /// Number of threads behind `timing`, falling back to [`BenchReport::threads`].
+    pub fn threads_of(&self, timing: &WitnessTiming) -> usize {
+        match timing.threads {
+            0 => self.threads,
+            threads => threads,
+        }
+    }
+
+    /// Whether the entries were timed with more than one thread count, as by `bench --sweep-threads`.
+    pub fn is_sweep(&self) -> bool {
+        let mut counts = self
+            .matrices
+            .iter()
+            .flat_map(|matrix| &matrix.witnesses)
+            .map(|timing| self.threads_of(timing));
+        counts
+            .next()
+            .is_some_and(|first| counts.any(|threads| threads != first))
+    }
+
+    /// Reads a report previously written by [`BenchReport::write_json`].
+    pub fn read_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        read_json(path)
+    }
+
+    /// Writes one CSV row per timed run, preceded by [`CSV_HEADER`] if `header` is set.
+    pub fn write_csv(&self, mut writer: impl Write, header: bool) -> io::Result<()> {
+        if header {
+            writeln!(writer, "{CSV_HEADER}")?;
+        }
+        for matrix in &self.matrices {
+            for timing in &matrix.witnesses {
+                for duration in &timing.durations_ns {
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{}",
+                        timing.witness,
+                        matrix.name,
+                        duration,
+                        matrix.nnz,
+                        matrix.rows,
+                        matrix.cols,
+                        self.threads_of(timing)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the CSV rows to `path`. With `append`, rows are added to the end of an
+    /// existing file and the header is only written if the file was empty or missing.
+    pub fn write_csv_file(&self, path: impl AsRef<Path>, append: bool) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let header = file.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(file);
+        self.write_csv(&mut writer, header)?;
+        writer.flush()
+    }
+}
+
+impl MultiBenchReport {
+    /// Creates a report of no dumps yet, stamped with the current time.
+    pub fn new() -> Self {
+        Self {
+            timestamp: now(),
+            hashes: Vec::new(),
+        }
+    }
+
+    /// Writes the report to `path` as pretty-printed JSON.
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_json(self, path)
+    }
+
+    /// Reads a report previously written by [`MultiBenchReport::write_json`].
+    pub fn read_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        read_json(path)
+    }
+}
+
+impl Default for MultiBenchReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn write_json(report: &impl Serialize, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, report)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<T> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
@@ -4,15 +4,78 @@
 //! Specifically, we implement sparse matrix / dense vector multiplication
 //! to compute the `A z`, `B z`, and `C z` in Nova.
 
-use ff::PrimeField;
+mod batched;
+mod blocked;
+mod canonical;
+mod checked;
+mod chunked;
+mod content;
+mod coo;
+mod csc;
+mod ell;
+mod iter;
+mod matmul;
+mod matrix_market;
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+mod npz;
+mod ops;
+mod partition;
+mod pool;
+mod pretty;
+mod prune;
+mod reorder;
+mod rows;
+mod sparse_vec;
+#[cfg(feature = "sprs")]
+mod sprs;
+mod structure;
+mod tagged;
+mod transpose;
+mod validate;
+
+use std::ops::{Index, Range};
+
+use ff::{Field as _, PrimeField};
+use halo2curves::bn256;
 use itertools::Itertools as _;
 use rayon::prelude::*;
 use ref_cast::RefCast;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "sprs")]
+pub use self::sprs::{sprs_multiply_vec, SprsError};
+pub use batched::FusedProducts;
+pub use blocked::{BlockedMatrix, DEFAULT_BLOCK_COLS};
+pub use checked::CheckedSparseMatrix;
+pub use chunked::{
+  ChunkedMatrix, ChunkedShape, ChunkedWriter, CHUNKED_EXTENSION, CHUNKED_HEADER_BYTES,
+  CHUNKED_MAGIC, CHUNKED_VERSION,
+};
+pub use content::CONTENT_CHUNK_ELEMENTS;
+pub use coo::CooMatrix;
+pub use csc::CscMatrix;
+pub use ell::{EllMatrix, EllShape, DEFAULT_ELL_WIDTH};
+pub use iter::{Entries, ParEntries};
+pub use matmul::DEFAULT_MATMUL_NNZ_LIMIT;
+pub use matrix_market::{MATRIX_MARKET_BANNER, MATRIX_MARKET_EXTENSION};
+pub use npz::NPZ_EXTENSION;
+pub use partition::{ChunkPolicy, Partition, PARTITION_CHUNKS_PER_THREAD};
+pub use pretty::{
+  Pretty, DEFAULT_PRETTY_COLS, DEFAULT_PRETTY_ROWS, MAX_PRETTY_COLS, MAX_PRETTY_ROWS,
+};
+pub use rows::RowView;
+pub use sparse_vec::{sparsify, SPARSE_VEC_MAX_DENSITY};
+pub use tagged::{Coefficient, TaggedMatrix};
+pub use validate::{MatrixError, ValidatedMatrix};
+
 /// CSR format sparse matrix, We follow the names used by scipy.
 /// Detailed explanation here: <https://stackoverflow.com/questions/52299420/scipy-csr-matrix-understand-indptr>
+///
+/// Deserializing checks [`SparseMatrix::check_lengths`], so a matrix read from a corrupt file
+/// fails to load instead of panicking when it is first multiplied.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MatrixParts<F>")]
 pub struct SparseMatrix<F: PrimeField> {
   /// all non-zero values in the matrix
   pub data: Vec<F>,
@@ -24,11 +87,111 @@ pub struct SparseMatrix<F: PrimeField> {
   pub cols: usize,
 }
 
+/// The fields of a [`SparseMatrix`] as they are deserialized, before they are checked.
+#[derive(Deserialize)]
+struct MatrixParts<F: PrimeField> {
+  data: Vec<F>,
+  indices: Vec<usize>,
+  indptr: Vec<usize>,
+  cols: usize,
+}
+
+impl<F: PrimeField> TryFrom<MatrixParts<F>> for SparseMatrix<F> {
+  type Error = MatrixError;
+
+  fn try_from(parts: MatrixParts<F>) -> Result<Self, MatrixError> {
+    let matrix = SparseMatrix::new_unchecked(parts.data, parts.indices, parts.indptr, parts.cols);
+    matrix.check_lengths()?;
+    Ok(matrix)
+  }
+}
+
+/// Most elements [`SparseMatrix::to_dense`] allocates, a `bn256::Fr` matrix of 256 MiB.
+pub const DEFAULT_DENSE_LIMIT: usize = 1 << 23;
+
 /// Wrapper type for encode rows of [`SparseMatrix`]
 #[derive(Debug, Clone, RefCast)]
 #[repr(transparent)]
 pub struct RowData([usize; 2]);
 
+/// Read access to the entries of a CSR matrix, however it is stored: a [`SparseMatrix`],
+/// or a view of one that decodes its entries on the fly, like an archived file.
+pub trait CsrView<F: PrimeField>: Sync {
+  /// Number of rows.
+  fn rows(&self) -> usize;
+  /// Number of columns.
+  fn cols(&self) -> usize;
+  /// Number of stored entries.
+  fn nnz(&self) -> usize;
+  /// Positions of the entries of `row`.
+  fn row_range(&self, row: usize) -> Range<usize>;
+  /// Value and column of the entry at position `k`.
+  fn entry(&self, k: usize) -> (F, usize);
+}
+
+impl<F: PrimeField> CsrView<F> for SparseMatrix<F> {
+  fn rows(&self) -> usize {
+    self.num_rows()
+  }
+
+  fn cols(&self) -> usize {
+    self.num_cols()
+  }
+
+  fn nnz(&self) -> usize {
+    SparseMatrix::nnz(self)
+  }
+
+  fn row_range(&self, row: usize) -> Range<usize> {
+    self.indptr[row]..self.indptr[row + 1]
+  }
+
+  fn entry(&self, k: usize) -> (F, usize) {
+    (self.data[k], self.indices[k])
+  }
+}
+
+/// Multiply any [`CsrView`] by a dense vector; uses rayon to parallelize over rows.
+pub fn multiply_view<F: PrimeField, M: CsrView<F> + ?Sized>(matrix: &M, vector: &[F]) -> Vec<F> {
+  assert_eq!(matrix.cols(), vector.len(), "invalid shape");
+
+  (0..matrix.rows())
+    .into_par_iter()
+    .map(|row| {
+      matrix
+        .row_range(row)
+        .map(|k| {
+          let (val, col_idx) = matrix.entry(k);
+          val * vector[col_idx]
+        })
+        .sum()
+    })
+    .collect()
+}
+
+/// The element at `(row, col)`, as [`SparseMatrix::get`] finds it. This is only for the
+/// scalar field of BN254, the one the dumps hold: an absent entry is a reference to a zero
+/// that must live in a constant, which a generic field, possibly interior mutable, cannot.
+///
+/// # Panics
+///
+/// If `(row, col)` is out of bounds.
+impl Index<(usize, usize)> for SparseMatrix<bn256::Fr> {
+  type Output = bn256::Fr;
+
+  fn index(&self, (row, col): (usize, usize)) -> &bn256::Fr {
+    let rows = self.num_rows();
+    match self.position(row, col) {
+      Some(Some(k)) => &self.data[k],
+      Some(None) => const { &bn256::Fr::ZERO },
+      None => panic!(
+        "({row}, {col}) is out of bounds of a {rows}x{} matrix",
+        self.cols
+      ),
+    }
+  }
+}
+
 /// [`SparseMatrix`]s are often large, and this helps with cloning bottlenecks
 impl<F: PrimeField> Clone for SparseMatrix<F> {
   fn clone(&self) -> Self {
@@ -42,6 +205,117 @@ impl<F: PrimeField> Clone for SparseMatrix<F> {
 }
 
 impl<F: PrimeField> SparseMatrix<F> {
+  /// The `n x n` identity matrix.
+  pub fn identity(n: usize) -> Self {
+    Self::new_unchecked(vec![F::ONE; n], (0..n).collect(), (0..=n).collect(), n)
+  }
+
+  /// The `rows x cols` matrix with no entries.
+  pub fn zero(rows: usize, cols: usize) -> Self {
+    Self::new_unchecked(Vec::new(), Vec::new(), vec![0; rows + 1], cols)
+  }
+
+  /// Builds the matrix of the dense `rows`, storing only their non-zero entries. Every row
+  /// must be as long as the first, which gives the number of columns.
+  pub fn from_dense(rows: &[Vec<F>]) -> Self {
+    let cols = rows.first().map_or(0, Vec::len);
+    let mut matrix = Self::new_unchecked(
+      Vec::new(),
+      Vec::new(),
+      Vec::with_capacity(rows.len() + 1),
+      cols,
+    );
+    matrix.indptr.push(0);
+    for (i, row) in rows.iter().enumerate() {
+      assert_eq!(row.len(), cols, "row {i} is not as long as the first");
+      for (col, value) in row.iter().enumerate() {
+        if !bool::from(value.is_zero()) {
+          matrix.data.push(*value);
+          matrix.indices.push(col);
+        }
+      }
+      matrix.indptr.push(matrix.data.len());
+    }
+    matrix
+  }
+
+  /// The dense rows of the matrix, refusing matrices of more than [`DEFAULT_DENSE_LIMIT`]
+  /// elements; see [`SparseMatrix::to_dense_with_limit`].
+  pub fn to_dense(&self) -> Result<Vec<Vec<F>>, String> {
+    self.to_dense_with_limit(DEFAULT_DENSE_LIMIT)
+  }
+
+  /// The dense rows of the matrix, with entries at the same position added up, or an error
+  /// if it has more than `limit` elements, so that a real dump is not expanded by accident.
+  pub fn to_dense_with_limit(&self, limit: usize) -> Result<Vec<Vec<F>>, String> {
+    let rows = self.num_rows();
+    match rows.checked_mul(self.cols) {
+      Some(elements) if elements <= limit => {}
+      _ => {
+        return Err(format!(
+          "a {rows}x{} matrix is over the limit of {limit} dense elements",
+          self.cols
+        ))
+      }
+    }
+    let dense = self
+      .indptr
+      .windows(2)
+      .map(|ptrs| {
+        let mut row = vec![F::ZERO; self.cols];
+        for (value, &col) in self.get_row_unchecked(ptrs.try_into().unwrap()) {
+          row[col] += value;
+        }
+        row
+      })
+      .collect();
+    Ok(dense)
+  }
+
+  /// Number of rows, one less than the length of `indptr`, or 0 if it is empty, as it is in
+  /// a matrix that was never filled in.
+  pub fn num_rows(&self) -> usize {
+    self.indptr.len().saturating_sub(1)
+  }
+
+  /// Number of columns.
+  pub fn num_cols(&self) -> usize {
+    self.cols
+  }
+
+  /// The number of rows and of columns.
+  pub fn shape(&self) -> (usize, usize) {
+    (self.num_rows(), self.num_cols())
+  }
+
+  /// Number of stored (structurally non-zero) entries.
+  pub fn nnz(&self) -> usize {
+    self.data.len()
+  }
+
+  /// Whether no entries are stored, although the matrix may still have rows and columns.
+  pub fn is_empty(&self) -> bool {
+    self.nnz() == 0
+  }
+
+  /// Number of stored entries in each row, in parallel.
+  pub fn row_nnz_iter(&self) -> impl IndexedParallelIterator<Item = usize> + '_ {
+    self.indptr.par_windows(2).map(|ptrs| ptrs[1] - ptrs[0])
+  }
+
+  /// Bytes held by the `data`, `indices`, and `indptr` arrays.
+  pub fn memory_footprint(&self) -> usize {
+    self.data.len() * std::mem::size_of::<F>()
+      + (self.indices.len() + self.indptr.len()) * std::mem::size_of::<usize>()
+  }
+
+  /// Estimated bytes moved by one [`SparseMatrix::multiply_vec`]: the whole matrix is read,
+  /// one vector element is gathered per nonzero, and one output element is written per row.
+  pub fn multiply_vec_traffic(&self) -> usize {
+    let rows = self.num_rows();
+    self.memory_footprint() + (self.nnz() + rows) * std::mem::size_of::<F>()
+  }
+
   /// Retrieves the data for row slice [i..j] from `ptrs`.
   /// We assume that `ptrs` is indexed from `indptrs` and do not check if the
   /// returned slice is actually a valid row.
@@ -51,31 +325,84 @@ impl<F: PrimeField> SparseMatrix<F> {
       .zip_eq(&self.indices[ptrs[0]..ptrs[1]])
   }
 
-  /// Multiply by a dense vector; uses rayon to parallelize.
+  /// The entries of row `i` as `(column, value)` pairs, in the order they are stored, or
+  /// `None` if there is no row `i`.
+  pub fn row(&self, i: usize) -> Option<impl Iterator<Item = (usize, &F)>> {
+    if i >= self.num_rows() {
+      return None;
+    }
+    let ptrs = RowData([self.indptr[i], self.indptr[i + 1]]);
+    Some(self.row_view(&ptrs).iter())
+  }
+
+  /// The element at `(row, col)`, which is zero if no entry is stored there, or `None` if it
+  /// is out of bounds. The row is binary-searched, which needs its columns sorted, as they
+  /// are in every matrix this crate builds; a row found to be unsorted is scanned instead.
+  pub fn get(&self, row: usize, col: usize) -> Option<F> {
+    let position = self.position(row, col)?;
+    Some(position.map_or(F::ZERO, |k| self.data[k]))
+  }
+
+  /// The position in `data` of the entry at `(row, col)`, `Some(None)` if there is none, or
+  /// `None` if `(row, col)` is out of bounds.
+  fn position(&self, row: usize, col: usize) -> Option<Option<usize>> {
+    if row >= self.num_rows() || col >= self.cols {
+      return None;
+    }
+    let start = self.indptr[row];
+    let indices = &self.indices[start..self.indptr[row + 1]];
+    let found = match indices.binary_search(&col) {
+      Ok(k) => Some(k),
+      Err(_) if indices.is_sorted() => None,
+      Err(_) => indices.iter().position(|&c| c == col),
+    };
+    Some(found.map(|k| start + k))
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize, over runs of rows with about equal
+  /// numbers of nonzeros, see [`Partition`].
   pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
-    assert_eq!(self.cols, vector.len(), "invalid shape");
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
 
     self.multiply_vec_unchecked(vector)
   }
 
-  /// Multiply by a dense vector; uses rayon to parallelize.
+  /// Multiply by a dense vector on the current thread, without rayon.
+  /// This is the reference that expected results are computed with, so it shares no code
+  /// with the parallel kernel.
+  pub fn multiply_vec_serial(&self, vector: &[F]) -> Vec<F> {
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+
+    let mut result = Vec::with_capacity(self.num_rows());
+    for row in 0..self.num_rows() {
+      let mut sum = F::ZERO;
+      for k in self.indptr[row]..self.indptr[row + 1] {
+        sum += self.data[k] * vector[self.indices[k]];
+      }
+      result.push(sum);
+    }
+    result
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize, as [`SparseMatrix::multiply_vec`].
   /// This does not check that the shape of the matrix/vector are compatible.
   fn multiply_vec_unchecked(&self, vector: &[F]) -> Vec<F> {
-    let mut sink: Vec<F> = Vec::with_capacity(self.indptr.len() - 1);
+    let mut sink: Vec<F> = Vec::with_capacity(self.num_rows());
     self.multiply_vec_into_unchecked(vector, &mut sink);
     sink
   }
 
+  /// Multiply by a dense vector into `out`, which is cleared and resized to the number of
+  /// rows; uses rayon to parallelize, as [`SparseMatrix::multiply_vec`]. Filling `out` reuses
+  /// its capacity, so this saves an allocation over [`SparseMatrix::multiply_vec`] only when
+  /// the caller holds on to `out` across products.
+  pub fn multiply_vec_into(&self, vector: &[F], out: &mut Vec<F>) {
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+
+    self.multiply_vec_into_unchecked(vector, out);
+  }
+
   fn multiply_vec_into_unchecked(&self, vector: &[F], sink: &mut Vec<F>) {
-    self
-      .indptr
-      .par_windows(2)
-      .map(|ptrs| {
-        self
-          .get_row_unchecked(ptrs.try_into().unwrap())
-          .map(|(val, col_idx)| *val * vector[*col_idx])
-          .sum()
-      })
-      .collect_into_vec(sink);
+    self.multiply_vec_chunked_unchecked(vector, sink, ChunkPolicy::Auto);
   }
-}
\ No newline at end of file
+}
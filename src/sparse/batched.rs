@@ -0,0 +1,82 @@
+//! Products that share one pass over their inputs: a matrix with many vectors at once reads
+//! the matrix once rather than once per vector, which is most of the memory traffic of a
+//! product, and several matrices of the same shape with one vector, row by row, read each
+//! element of the vector while it is still in cache for the next matrix.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::{MatrixError, RowData, SparseMatrix};
+
+/// The products `(a z, b z, c z)` from [`SparseMatrix::multiply_vec_fused`].
+pub type FusedProducts<F> = (Vec<F>, Vec<F>, Vec<F>);
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The products of the matrix with each of `vectors`, computed together: every row is walked
+  /// once, in parallel over the rows, adding each entry into one sum per vector. Equal to
+  /// calling [`SparseMatrix::multiply_vec`] on each vector in turn.
+  ///
+  /// # Panics
+  ///
+  /// If a vector does not have an element for every column.
+  pub fn multiply_mat(&self, vectors: &[Vec<F>]) -> Vec<Vec<F>> {
+    for vector in vectors {
+      assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+    }
+    let k = vectors.len();
+    if k == 0 {
+      return Vec::new();
+    }
+
+    // The `k` sums of each row sit together, so that a row writes to one place.
+    let mut sums = vec![F::ZERO; self.num_rows() * k];
+    sums
+      .par_chunks_mut(k)
+      .zip(self.rows())
+      .for_each(|(sums, row)| {
+        for (col, value) in row.iter() {
+          for (sum, vector) in sums.iter_mut().zip(vectors) {
+            *sum += *value * vector[col];
+          }
+        }
+      });
+    (0..k)
+      .into_par_iter()
+      .map(|j| sums.iter().skip(j).step_by(k).copied().collect())
+      .collect()
+  }
+
+  /// The products `a z`, `b z`, and `c z`, computed together in parallel over the rows: each
+  /// row of the three is dotted with `z` in turn before moving on, in the same order as
+  /// [`SparseMatrix::multiply_vec`] adds up a row, so the results are bit-identical to it. The
+  /// matrices must have the same shape, and `z` an element for every column.
+  pub fn multiply_vec_fused(
+    a: &Self,
+    b: &Self,
+    c: &Self,
+    z: &[F],
+  ) -> Result<FusedProducts<F>, MatrixError> {
+    if let Some(other) = [b, c].into_iter().find(|other| other.shape() != a.shape()) {
+      return Err(MatrixError::ShapeMismatch {
+        left: a.shape(),
+        right: other.shape(),
+      });
+    }
+    if z.len() != a.num_cols() {
+      return Err(MatrixError::VectorLength {
+        cols: a.num_cols(),
+        len: z.len(),
+      });
+    }
+
+    let dot = |matrix: &Self, row: usize| {
+      let ptrs = RowData([matrix.indptr[row], matrix.indptr[row + 1]]);
+      matrix.row_view(&ptrs).dot(z)
+    };
+    let (az, (bz, cz)) = (0..a.num_rows())
+      .into_par_iter()
+      .map(|row| (dot(a, row), (dot(b, row), dot(c, row))))
+      .unzip();
+    Ok((az, bz, cz))
+  }
+}
@@ -0,0 +1,105 @@
+//! Matrices cut into blocks of columns, for products with vectors too long to stay in cache.
+//! The product goes through one block at a time, over every row, so that only the slice of
+//! the vector under the block is read while it runs, and adds each block into the output.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// Columns of each block of [`SparseMatrix::to_blocked`] unless told otherwise: 2^18 elements
+/// of 32 bytes are 8 MiB of the vector, which fit in the last-level cache of most servers.
+pub const DEFAULT_BLOCK_COLS: usize = 1 << 18;
+
+/// A [`SparseMatrix`] stored as blocks of `block_cols` adjacent columns, the last one possibly
+/// narrower, each a CSR matrix of every row with the columns numbered from the start of the
+/// block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedMatrix<F: PrimeField> {
+  rows: usize,
+  cols: usize,
+  block_cols: usize,
+  blocks: Vec<SparseMatrix<F>>,
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix in blocks of `block_cols` columns, keeping the order of the entries of each
+  /// row within a block. A width of at least the number of columns gives a single block.
+  ///
+  /// # Panics
+  ///
+  /// If `block_cols` is 0.
+  pub fn to_blocked(&self, block_cols: usize) -> BlockedMatrix<F> {
+    assert!(block_cols > 0, "blocks must have columns");
+
+    let count = self.num_cols().div_ceil(block_cols);
+    let mut blocks: Vec<_> = (0..count)
+      .map(|b| {
+        let width = block_cols.min(self.num_cols() - b * block_cols);
+        SparseMatrix::new_unchecked(Vec::new(), Vec::new(), vec![0], width)
+      })
+      .collect();
+    for row in 0..self.num_rows() {
+      for k in self.indptr[row]..self.indptr[row + 1] {
+        let col = self.indices[k];
+        let block = &mut blocks[col / block_cols];
+        block.data.push(self.data[k]);
+        block.indices.push(col % block_cols);
+      }
+      for block in &mut blocks {
+        block.indptr.push(block.indices.len());
+      }
+    }
+    BlockedMatrix {
+      rows: self.num_rows(),
+      cols: self.num_cols(),
+      block_cols,
+      blocks,
+    }
+  }
+}
+
+impl<F: PrimeField> BlockedMatrix<F> {
+  /// Number of rows.
+  pub fn num_rows(&self) -> usize {
+    self.rows
+  }
+
+  /// Number of columns.
+  pub fn num_cols(&self) -> usize {
+    self.cols
+  }
+
+  /// Columns of every block but the last.
+  pub fn block_cols(&self) -> usize {
+    self.block_cols
+  }
+
+  /// The blocks, from the first columns to the last.
+  pub fn blocks(&self) -> &[SparseMatrix<F>] {
+    &self.blocks
+  }
+
+  /// Multiply by a dense vector, one block after another, each in parallel over the rows.
+  /// The product is that of [`SparseMatrix::multiply_vec`].
+  pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
+    let mut out = Vec::with_capacity(self.rows);
+    self.multiply_vec_into(vector, &mut out);
+    out
+  }
+
+  /// [`BlockedMatrix::multiply_vec`] into `out`, which is cleared and resized to the number of
+  /// rows.
+  pub fn multiply_vec_into(&self, vector: &[F], out: &mut Vec<F>) {
+    assert_eq!(self.cols, vector.len(), "invalid shape");
+
+    out.clear();
+    out.resize(self.rows, F::ZERO);
+    for (block, window) in self.blocks.iter().zip(vector.chunks(self.block_cols)) {
+      out
+        .par_iter_mut()
+        .zip(block.rows())
+        .for_each(|(sum, row)| *sum += row.dot(window));
+    }
+  }
+}
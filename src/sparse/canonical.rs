@@ -0,0 +1,136 @@
+//! Canonical rows: the columns of every row strictly increasing, so sorted and each stored at
+//! most once. Lookups by binary search, delta-encoded indices, and merging rows entry by entry
+//! all rely on it; [`SparseMatrix::canonicalize`] establishes it.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::{MatrixError, SparseMatrix};
+
+/// Rows of each task of [`SparseMatrix::canonicalize`].
+const CANONICAL_CHUNK_ROWS: usize = 4096;
+
+/// Sorts the entries of a row by column and adds up those of the same column into one, moving
+/// them to the front of `data` and `indices`, and returns how many are left.
+fn canonicalize_row<F: PrimeField>(data: &mut [F], indices: &mut [usize]) -> usize {
+  if indices.windows(2).all(|pair| pair[0] < pair[1]) {
+    return indices.len();
+  }
+  let mut entries: Vec<(usize, F)> = indices.iter().copied().zip(data.iter().copied()).collect();
+  entries.sort_by_key(|&(col, _)| col);
+  let mut len = 0;
+  for (col, value) in entries {
+    if len > 0 && indices[len - 1] == col {
+      data[len - 1] += value;
+    } else {
+      indices[len] = col;
+      data[len] = value;
+      len += 1;
+    }
+  }
+  len
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Whether the columns of every row are strictly increasing, checked in parallel.
+  pub fn is_canonical(&self) -> bool {
+    self.first_not_canonical().is_none()
+  }
+
+  /// Sorts the entries of every row by column and adds up the entries of each column into
+  /// one, in parallel over chunks of rows. A sum that comes to zero is kept as an explicit
+  /// zero; [`SparseMatrix::prune`] drops those. Rows already canonical are left as they are.
+  pub fn canonicalize(&mut self) {
+    let rows = self.num_rows();
+    let starts: Vec<usize> = (0..rows)
+      .step_by(CANONICAL_CHUNK_ROWS)
+      .chain([rows])
+      .collect();
+    let mut shares = Vec::with_capacity(starts.len());
+    let (mut data_rest, mut indices_rest) = (&mut self.data[..], &mut self.indices[..]);
+    for chunk in starts.windows(2) {
+      let len = self.indptr[chunk[1]] - self.indptr[chunk[0]];
+      let (data_share, rest) = std::mem::take(&mut data_rest).split_at_mut(len);
+      data_rest = rest;
+      let (index_share, rest) = std::mem::take(&mut indices_rest).split_at_mut(len);
+      indices_rest = rest;
+      shares.push((&self.indptr[chunk[0]..=chunk[1]], data_share, index_share));
+    }
+    let lens: Vec<usize> = shares
+      .into_par_iter()
+      .flat_map_iter(|(indptr, data, indices)| {
+        let base = indptr[0];
+        indptr.windows(2).map(move |ptrs| {
+          let row = ptrs[0] - base..ptrs[1] - base;
+          canonicalize_row(&mut data[row.clone()], &mut indices[row])
+        })
+      })
+      .collect();
+
+    // Close the gaps left by merged entries, moving every row down to where the last ended.
+    let mut end = 0;
+    for (row, len) in lens.into_iter().enumerate() {
+      let start = self.indptr[row];
+      self.data.copy_within(start..start + len, end);
+      self.indices.copy_within(start..start + len, end);
+      self.indptr[row] = end;
+      end += len;
+    }
+    self.indptr[rows] = end;
+    self.data.truncate(end);
+    self.indices.truncate(end);
+  }
+
+  /// Every coordinate stored more than once, as `(row, col, count)` in order of row and
+  /// column, found in parallel over the rows. Rows need not be sorted: those that are not are
+  /// sorted in a copy. [`SparseMatrix::canonicalize`] would merge them.
+  pub fn find_duplicates(&self) -> Vec<(usize, usize, usize)> {
+    self
+      .indptr
+      .par_windows(2)
+      .enumerate()
+      .flat_map_iter(|(row, ptrs)| {
+        let cols = &self.indices[ptrs[0]..ptrs[1]];
+        let mut duplicates = Vec::new();
+        if cols.windows(2).all(|pair| pair[0] < pair[1]) {
+          return duplicates;
+        }
+        let mut sorted = cols.to_vec();
+        sorted.sort_unstable();
+        for run in sorted.chunk_by(|a, b| a == b) {
+          if run.len() > 1 {
+            duplicates.push((row, run[0], run.len()));
+          }
+        }
+        duplicates
+      })
+      .collect()
+  }
+
+  /// Checks the invariants, as [`SparseMatrix::validate`], and that every row is canonical,
+  /// as [`SparseMatrix::is_canonical`].
+  pub fn validate_canonical(&self) -> Result<(), MatrixError> {
+    self.validate()?;
+    match self.first_not_canonical() {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+
+  /// The first entry whose column does not come after the one before it in its row.
+  fn first_not_canonical(&self) -> Option<MatrixError> {
+    self
+      .indptr
+      .par_windows(2)
+      .enumerate()
+      .find_map_first(|(row, ptrs)| {
+        let position = (ptrs[0] + 1..ptrs[1]).find(|&k| self.indices[k - 1] >= self.indices[k])?;
+        Some(MatrixError::NotCanonical {
+          row,
+          position,
+          col: self.indices[position],
+          previous: self.indices[position - 1],
+        })
+      })
+  }
+}
@@ -0,0 +1,185 @@
+//! Deserializing a matrix with its invariants checked as the entries stream in.
+//!
+//! Deserializing a [`SparseMatrix`] only checks [`SparseMatrix::check_lengths`], and
+//! [`SparseMatrix::validate`] is a second pass over every entry. A [`CheckedSparseMatrix`]
+//! checks `indptr` as it is read instead, failing at the first pointer out of order, where a
+//! reader that counts bytes, as the loaders in [`data`](crate::data) do, reports how far it got.
+//! The length of `indices` is checked against `data` before any index is read, so that a
+//! corrupt length is not allocated. The bound on the column indices is checked once `cols`,
+//! which comes last, is read, against the largest index, which is tracked as they stream in.
+//!
+//! This reads the fields in order, as bincode gives them, and costs a comparison or two per
+//! element, so the trusted paths deserialize a plain [`SparseMatrix`] instead.
+
+use std::{fmt, marker::PhantomData};
+
+use ff::PrimeField;
+use serde::{
+  de::{self, DeserializeSeed, SeqAccess, Visitor},
+  Deserialize, Deserializer,
+};
+
+use super::{MatrixError, SparseMatrix};
+
+/// Most elements reserved up front for an array whose length is only claimed by the input.
+const MAX_RESERVED: usize = 1 << 16;
+
+/// A [`SparseMatrix`] whose every invariant was checked as it was deserialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedSparseMatrix<F: PrimeField>(pub SparseMatrix<F>);
+
+impl<F: PrimeField> CheckedSparseMatrix<F> {
+  pub fn into_inner(self) -> SparseMatrix<F> {
+    self.0
+  }
+}
+
+impl<'de, F: PrimeField + Deserialize<'de>> Deserialize<'de> for CheckedSparseMatrix<F> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_struct(
+      "SparseMatrix",
+      &["data", "indices", "indptr", "cols"],
+      MatrixVisitor(PhantomData),
+    )
+  }
+}
+
+struct MatrixVisitor<F>(PhantomData<F>);
+
+impl<'de, F: PrimeField + Deserialize<'de>> Visitor<'de> for MatrixVisitor<F> {
+  type Value = CheckedSparseMatrix<F>;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("a sparse matrix")
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let data: Vec<F> = seq
+      .next_element()?
+      .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+    let nnz = data.len();
+    let Indices { indices, max } = seq
+      .next_element_seed(IndicesSeed { nnz })?
+      .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+    let indptr = seq
+      .next_element_seed(IndptrSeed { nnz })?
+      .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+    let cols: usize = seq
+      .next_element()?
+      .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+
+    let matrix = SparseMatrix::new_unchecked(data, indices, indptr, cols);
+    if max.is_some_and(|max| max >= cols) {
+      // Only now is it known that an index is out of bounds; find the first one.
+      let err = matrix.validate().expect_err("an index is out of bounds");
+      return Err(de::Error::custom(err));
+    }
+    Ok(CheckedSparseMatrix(matrix))
+  }
+}
+
+/// The column indices, and the largest of them.
+struct Indices {
+  indices: Vec<usize>,
+  max: Option<usize>,
+}
+
+/// Reads `indices`, which must hold `nnz` entries.
+struct IndicesSeed {
+  nnz: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for IndicesSeed {
+  type Value = Indices;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Indices, D::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de> Visitor<'de> for IndicesSeed {
+  type Value = Indices;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} column indices", self.nnz)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Indices, A::Error> {
+    let mismatch = |indices| {
+      de::Error::custom(MatrixError::LengthMismatch {
+        data: self.nnz,
+        indices,
+      })
+    };
+    if let Some(len) = seq.size_hint().filter(|&len| len != self.nnz) {
+      return Err(mismatch(len));
+    }
+    // `data` was read, so `nnz` entries are no more than the input held.
+    let mut indices = Vec::with_capacity(self.nnz);
+    let mut max = None;
+    while let Some(index) = seq.next_element::<usize>()? {
+      if indices.len() == self.nnz {
+        return Err(mismatch(self.nnz + 1));
+      }
+      max = max.max(Some(index));
+      indices.push(index);
+    }
+    if indices.len() != self.nnz {
+      return Err(mismatch(indices.len()));
+    }
+    Ok(Indices { indices, max })
+  }
+}
+
+/// Reads `indptr`, which must delimit `nnz` entries.
+struct IndptrSeed {
+  nnz: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for IndptrSeed {
+  type Value = Vec<usize>;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<usize>, D::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de> Visitor<'de> for IndptrSeed {
+  type Value = Vec<usize>;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("row pointers")
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<usize>, A::Error> {
+    let mut indptr: Vec<usize> = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(MAX_RESERVED));
+    while let Some(end) = seq.next_element::<usize>()? {
+      let err = match indptr.last() {
+        None if end != 0 => Some(MatrixError::IndptrStart { start: end }),
+        Some(&start) if end < start => Some(MatrixError::IndptrDecreasing {
+          row: indptr.len() - 1,
+          start,
+          end,
+        }),
+        _ if end > self.nnz => Some(MatrixError::IndptrPastEnd {
+          row: indptr.len() - 1,
+          end,
+          nnz: self.nnz,
+        }),
+        _ => None,
+      };
+      if let Some(err) = err {
+        return Err(de::Error::custom(err));
+      }
+      indptr.push(end);
+    }
+    match indptr.last() {
+      None => Err(de::Error::custom(MatrixError::EmptyIndptr)),
+      Some(&end) if end != self.nnz => Err(de::Error::custom(MatrixError::IndptrEnd {
+        end,
+        nnz: self.nnz,
+      })),
+      Some(_) => Ok(indptr),
+    }
+  }
+}
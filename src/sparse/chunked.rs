@@ -0,0 +1,254 @@
+//! Matrices stored on disk in groups of rows, so that one larger than memory can still be
+//! multiplied: [`ChunkedMatrix::multiply_vec_streaming`] only ever holds one group.
+//!
+//! A chunked file starts with a [`CHUNKED_HEADER_BYTES`]-byte header: the magic `SPMC`, a `u16`
+//! version, two zero bytes, and the `u64` rows, columns, nonzeros, and rows per chunk of the
+//! matrix, all little-endian. Each chunk follows as its `u64` length in bytes and the rows it
+//! holds as a [`SparseMatrix`] in the [`raw`](crate::data::raw) codec, with its `indptr`
+//! starting at zero. Every chunk holds the same number of rows except possibly the last.
+
+use std::{
+  fs::File,
+  io::{self, BufReader, Read, Write},
+  ops::Range,
+  path::{Path, PathBuf},
+};
+
+use ff::PrimeField;
+
+use super::SparseMatrix;
+use crate::data::RawCodec;
+
+/// Magic bytes opening every chunked file.
+pub const CHUNKED_MAGIC: [u8; 4] = *b"SPMC";
+
+/// Version of the layout described in the [module docs](self).
+pub const CHUNKED_VERSION: u16 = 1;
+
+/// Extension of chunked files, stored next to the bincode files as `<label>.chunked`.
+pub const CHUNKED_EXTENSION: &str = "chunked";
+
+/// Size of the header of a chunked file.
+pub const CHUNKED_HEADER_BYTES: usize = 40;
+
+/// Dimensions of a chunked matrix, and how its rows are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkedShape {
+  pub rows: usize,
+  pub cols: usize,
+  pub nnz: usize,
+  /// Rows in every chunk but possibly the last; at least 1.
+  pub rows_per_chunk: usize,
+}
+
+impl ChunkedShape {
+  /// Number of chunks the rows are split into.
+  pub fn chunks(&self) -> usize {
+    self.rows.div_ceil(self.rows_per_chunk)
+  }
+
+  /// The rows held by chunk `i`.
+  pub fn chunk_rows(&self, i: usize) -> Range<usize> {
+    let start = i * self.rows_per_chunk;
+    start..(start + self.rows_per_chunk).min(self.rows)
+  }
+
+  fn to_bytes(self) -> [u8; CHUNKED_HEADER_BYTES] {
+    let mut header = [0; CHUNKED_HEADER_BYTES];
+    header[..4].copy_from_slice(&CHUNKED_MAGIC);
+    header[4..6].copy_from_slice(&CHUNKED_VERSION.to_le_bytes());
+    let fields = [self.rows, self.cols, self.nnz, self.rows_per_chunk];
+    for (bytes, field) in header[8..].chunks_exact_mut(8).zip(fields) {
+      bytes.copy_from_slice(&(field as u64).to_le_bytes());
+    }
+    header
+  }
+
+  fn from_bytes(header: &[u8; CHUNKED_HEADER_BYTES]) -> io::Result<Self> {
+    if header[..4] != CHUNKED_MAGIC {
+      return Err(invalid_data("not a chunked matrix"));
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != CHUNKED_VERSION {
+      return Err(invalid_data(format!("unsupported version {version}")));
+    }
+    let mut fields = header[8..].chunks_exact(8).map(|bytes| {
+      usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+        .map_err(|_| invalid_data("a dimension does not fit in usize"))
+    });
+    let mut next = || fields.next().unwrap();
+    let shape = Self {
+      rows: next()?,
+      cols: next()?,
+      nnz: next()?,
+      rows_per_chunk: next()?,
+    };
+    if shape.rows_per_chunk == 0 {
+      return Err(invalid_data("chunks of 0 rows"));
+    }
+    Ok(shape)
+  }
+}
+
+/// Writes a chunked file one chunk at a time, checking that the chunks add up to the shape
+/// announced in the header.
+pub struct ChunkedWriter<W: Write> {
+  writer: W,
+  shape: ChunkedShape,
+  rows: usize,
+  nnz: usize,
+  buffer: Vec<u8>,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+  /// Writes the header for a matrix of `shape` to `writer`.
+  ///
+  /// # Panics
+  ///
+  /// If `shape.rows_per_chunk` is 0.
+  pub fn new(mut writer: W, shape: ChunkedShape) -> io::Result<Self> {
+    assert!(
+      shape.rows_per_chunk > 0,
+      "chunks must hold at least one row"
+    );
+    writer.write_all(&shape.to_bytes())?;
+    Ok(Self {
+      writer,
+      shape,
+      rows: 0,
+      nnz: 0,
+      buffer: Vec::new(),
+    })
+  }
+
+  /// Appends the next chunk, whose rows must be [`ChunkedShape::chunk_rows`] of it.
+  ///
+  /// # Panics
+  ///
+  /// If `chunk` has a different number of rows or columns than the shape calls for.
+  pub fn write_chunk<F: PrimeField>(&mut self, chunk: &SparseMatrix<F>) -> io::Result<()> {
+    let expected = self.shape.chunk_rows(self.rows / self.shape.rows_per_chunk);
+    let rows = chunk.num_rows();
+    assert_eq!(
+      rows,
+      expected.len(),
+      "chunk of {rows} rows, expected rows {expected:?}"
+    );
+    assert_eq!(chunk.cols, self.shape.cols, "invalid shape");
+
+    self.buffer.clear();
+    chunk.write_raw(&mut self.buffer)?;
+    self
+      .writer
+      .write_all(&(self.buffer.len() as u64).to_le_bytes())?;
+    self.writer.write_all(&self.buffer)?;
+    self.rows += rows;
+    self.nnz += chunk.nnz();
+    Ok(())
+  }
+
+  /// Checks that every chunk was written and returns the writer, flushed.
+  pub fn finish(mut self) -> io::Result<W> {
+    if (self.rows, self.nnz) != (self.shape.rows, self.shape.nnz) {
+      return Err(invalid_data(format!(
+        "wrote {} rows and {} nonzeros of {} and {}",
+        self.rows, self.nnz, self.shape.rows, self.shape.nnz
+      )));
+    }
+    self.writer.flush()?;
+    Ok(self.writer)
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The rows in `rows` as a matrix of their own, with the same columns.
+  pub fn slice_rows(&self, rows: Range<usize>) -> SparseMatrix<F> {
+    let entries = self.indptr[rows.start]..self.indptr[rows.end];
+    SparseMatrix::new_unchecked(
+      self.data[entries.clone()].to_vec(),
+      self.indices[entries.clone()].to_vec(),
+      self.indptr[rows.start..=rows.end]
+        .iter()
+        .map(|k| k - entries.start)
+        .collect(),
+      self.cols,
+    )
+  }
+
+  /// Writes the matrix as a chunked file of `rows_per_chunk`-row chunks to `writer`.
+  pub fn write_chunked<W: Write>(&self, rows_per_chunk: usize, writer: W) -> io::Result<W> {
+    let shape = ChunkedShape {
+      rows: self.num_rows(),
+      cols: self.cols,
+      nnz: self.nnz(),
+      rows_per_chunk,
+    };
+    let mut writer = ChunkedWriter::new(writer, shape)?;
+    for i in 0..shape.chunks() {
+      writer.write_chunk(&self.slice_rows(shape.chunk_rows(i)))?;
+    }
+    writer.finish()
+  }
+}
+
+/// A chunked file, multiplied by streaming its chunks from disk.
+#[derive(Debug, Clone)]
+pub struct ChunkedMatrix {
+  path: PathBuf,
+  shape: ChunkedShape,
+}
+
+impl ChunkedMatrix {
+  /// Opens the chunked file at `path`, reading only its header.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref().to_path_buf();
+    let mut header = [0; CHUNKED_HEADER_BYTES];
+    File::open(&path)?.read_exact(&mut header)?;
+    let shape = ChunkedShape::from_bytes(&header)?;
+    Ok(Self { path, shape })
+  }
+
+  pub fn shape(&self) -> ChunkedShape {
+    self.shape
+  }
+
+  /// Multiply by a dense vector, reading and multiplying one chunk at a time; each chunk uses
+  /// rayon to parallelize over its rows. Only `vector`, the product, and one chunk are held in
+  /// memory. A corrupt chunk fails with [`io::ErrorKind::InvalidData`].
+  pub fn multiply_vec_streaming<F: PrimeField>(&self, vector: &[F]) -> io::Result<Vec<F>> {
+    assert_eq!(self.shape.cols, vector.len(), "invalid shape");
+
+    let mut reader = BufReader::new(File::open(&self.path)?);
+    reader.read_exact(&mut [0; CHUNKED_HEADER_BYTES])?;
+    let mut result = Vec::with_capacity(self.shape.rows);
+    let mut bytes = Vec::new();
+    for i in 0..self.shape.chunks() {
+      let mut len = [0; 8];
+      reader.read_exact(&mut len)?;
+      let len = usize::try_from(u64::from_le_bytes(len))
+        .map_err(|_| invalid_data("a chunk length does not fit in usize"))?;
+      bytes.resize(len, 0);
+      reader.read_exact(&mut bytes)?;
+
+      let chunk = SparseMatrix::<F>::read_raw(&bytes)
+        .map_err(|message| invalid_data(format!("chunk {i}: {message}")))?;
+      let rows = self.shape.chunk_rows(i).len();
+      if chunk.indptr.len() != rows + 1 || chunk.cols != self.shape.cols {
+        return Err(invalid_data(format!("chunk {i} does not hold {rows} rows")));
+      }
+      let ordered = chunk.indptr[0] == 0
+        && chunk.indptr[rows] == chunk.data.len()
+        && chunk.indices.len() == chunk.data.len()
+        && chunk.indptr.windows(2).all(|ptrs| ptrs[0] <= ptrs[1]);
+      if !ordered || chunk.indices.iter().any(|&col| col >= self.shape.cols) {
+        return Err(invalid_data(format!("chunk {i} has entries out of range")));
+      }
+      result.extend(chunk.multiply_vec(vector));
+    }
+    Ok(result)
+  }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,84 @@
+//! Comparing and hashing matrices in parallel, since comparing a dump's matrices one element
+//! at a time takes longer than loading them.
+//!
+//! The content hash is BLAKE3, the hash of the [manifests](crate::data::manifest), over a
+//! tree of two levels: each array is cut into chunks of [`CONTENT_CHUNK_ELEMENTS`] elements,
+//! which are hashed in parallel, and their hashes are hashed in order after the shape. The
+//! chunks do not depend on the number of threads, so neither does the hash. Values
+//! are hashed as their canonical representation, and indices and pointers as `u64`s, little
+//! endian, so the hash does not depend on the platform either.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// Elements of an array compared or hashed by a task at a time.
+pub const CONTENT_CHUNK_ELEMENTS: usize = 1 << 16;
+
+/// Names the layout of the hashed bytes, so that changing it changes every hash.
+const CONTENT_HASH_DOMAIN: &[u8] = b"spmvm-test-example sparse matrix v1";
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Whether the matrices are equal, as `==` says, comparing chunks of the arrays in parallel.
+  /// Matrices of different shapes or numbers of entries are told apart without reading them.
+  pub fn par_eq(&self, other: &Self) -> bool {
+    self.shape() == other.shape()
+      && self.nnz() == other.nnz()
+      && par_slices_eq(&self.indptr, &other.indptr)
+      && par_slices_eq(&self.indices, &other.indices)
+      && par_slices_eq(&self.data, &other.data)
+  }
+
+  /// A BLAKE3 hash of the shape and the arrays, equal for matrices that are `==`, and stable
+  /// across runs, thread counts, and platforms.
+  pub fn content_hash(&self) -> [u8; 32] {
+    let data = chunk_hashes(&self.data, |value, bytes| {
+      bytes.extend_from_slice(value.to_repr().as_ref())
+    });
+    let indices = chunk_hashes(&self.indices, push_u64);
+    let indptr = chunk_hashes(&self.indptr, push_u64);
+
+    let mut state = blake3::Hasher::new();
+    state.update(CONTENT_HASH_DOMAIN);
+    for len in [
+      self.num_rows(),
+      self.num_cols(),
+      self.data.len(),
+      self.indices.len(),
+      self.indptr.len(),
+    ] {
+      state.update(&(len as u64).to_le_bytes());
+    }
+    for hash in data.iter().chain(&indices).chain(&indptr) {
+      state.update(hash);
+    }
+    *state.finalize().as_bytes()
+  }
+}
+
+fn par_slices_eq<T: PartialEq + Sync>(a: &[T], b: &[T]) -> bool {
+  a.len() == b.len()
+    && a
+      .par_chunks(CONTENT_CHUNK_ELEMENTS)
+      .zip(b.par_chunks(CONTENT_CHUNK_ELEMENTS))
+      .all(|(a, b)| a == b)
+}
+
+/// The hash of every chunk of `items`, encoding each item with `encode`.
+fn chunk_hashes<T: Sync>(items: &[T], encode: impl Fn(&T, &mut Vec<u8>) + Sync) -> Vec<[u8; 32]> {
+  items
+    .par_chunks(CONTENT_CHUNK_ELEMENTS)
+    .map(|chunk| {
+      let mut bytes = Vec::new();
+      for item in chunk {
+        encode(item, &mut bytes);
+      }
+      *blake3::hash(&bytes).as_bytes()
+    })
+    .collect()
+}
+
+fn push_u64(value: &usize, bytes: &mut Vec<u8>) {
+  bytes.extend_from_slice(&(*value as u64).to_le_bytes());
+}
@@ -0,0 +1,118 @@
+//! Matrices in coordinate (COO, or triplet) form: a list of `(row, col, value)` entries in any
+//! order, which is how matrices are most easily built, and which [`CooMatrix::into_csr`] turns
+//! into a [`SparseMatrix`] for multiplying.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// A `rows x cols` matrix as a list of entries, in any order and possibly at the same position
+/// more than once. We follow scipy's `coo_matrix`: entries at the same position are added up
+/// when converting to CSR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CooMatrix<F: PrimeField> {
+  pub rows: usize,
+  pub cols: usize,
+  /// The `(row, col, value)` entries.
+  pub entries: Vec<(usize, usize, F)>,
+}
+
+impl<F: PrimeField> CooMatrix<F> {
+  /// An empty `rows x cols` matrix.
+  pub fn new(rows: usize, cols: usize) -> Self {
+    Self::with_capacity(rows, cols, 0)
+  }
+
+  /// An empty `rows x cols` matrix with room for `capacity` entries.
+  pub fn with_capacity(rows: usize, cols: usize, capacity: usize) -> Self {
+    Self {
+      rows,
+      cols,
+      entries: Vec::with_capacity(capacity),
+    }
+  }
+
+  /// The `rows x cols` matrix of the entries `(row, col, value)`.
+  ///
+  /// # Panics
+  ///
+  /// If an entry is out of bounds.
+  pub fn from_triplets(rows: usize, cols: usize, triplets: Vec<(usize, usize, F)>) -> Self {
+    for &(row, col, _) in &triplets {
+      assert_in_bounds(row, col, rows, cols);
+    }
+    Self {
+      rows,
+      cols,
+      entries: triplets,
+    }
+  }
+
+  /// Adds `value` at `(row, col)`, on top of any entry already there.
+  ///
+  /// # Panics
+  ///
+  /// If `(row, col)` is out of bounds.
+  pub fn push(&mut self, row: usize, col: usize, value: F) {
+    assert_in_bounds(row, col, self.rows, self.cols);
+    self.entries.push((row, col, value));
+  }
+
+  /// Number of entries, counting each position as often as it was pushed.
+  pub fn nnz(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// The matrix in CSR form; see [`CooMatrix::into_csr`].
+  pub fn to_csr(&self) -> SparseMatrix<F> {
+    self.clone().into_csr()
+  }
+
+  /// The matrix in CSR form, with the columns of each row sorted and entries at the same
+  /// position added up into one, as by [`SparseMatrix::canonicalize`]. Sums that come to zero
+  /// are kept as explicit entries, as scipy's `sum_duplicates` keeps them.
+  pub fn into_csr(self) -> SparseMatrix<F> {
+    // A counting sort by row puts every entry in its row, and canonicalizing sorts each row.
+    let mut indptr = vec![0; self.rows + 1];
+    for &(row, _, _) in &self.entries {
+      indptr[row + 1] += 1;
+    }
+    for row in 0..self.rows {
+      indptr[row + 1] += indptr[row];
+    }
+    let mut next = indptr.clone();
+    let mut data = vec![F::ZERO; self.entries.len()];
+    let mut indices = vec![0; self.entries.len()];
+    for (row, col, value) in self.entries {
+      data[next[row]] = value;
+      indices[next[row]] = col;
+      next[row] += 1;
+    }
+    let mut matrix = SparseMatrix::try_new(data, indices, indptr, self.cols)
+      .expect("the entries are in bounds and counted by row");
+    matrix.canonicalize();
+    matrix
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix in COO form, with an entry for every stored value, in row-major order.
+  pub fn to_coo(&self) -> CooMatrix<F> {
+    CooMatrix {
+      rows: self.num_rows(),
+      cols: self.cols,
+      entries: self
+        .par_iter()
+        .map(|(row, col, &value)| (row, col, value))
+        .collect(),
+    }
+  }
+}
+
+fn assert_in_bounds(row: usize, col: usize, rows: usize, cols: usize) {
+  assert!(
+    row < rows && col < cols,
+    "entry ({row}, {col}) is out of bounds of a {rows}x{cols} matrix"
+  );
+}
@@ -0,0 +1,98 @@
+//! Matrices in CSC form, column by column, which is the natural layout for products with the
+//! transpose. A product with a vector scatters each column into the rows it touches, and since
+//! columns share rows, every thread scatters into its own partial output and the partials are
+//! added up at the end.
+
+use ff::PrimeField;
+use itertools::Itertools as _;
+
+use super::{transpose::scatter_blocks, SparseMatrix};
+
+/// CSC format sparse matrix, with the names of [`SparseMatrix`] and so of scipy: `indices`
+/// holds row indices, and `indptr` delimits columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CscMatrix<F: PrimeField> {
+  /// all non-zero values in the matrix, column by column
+  pub data: Vec<F>,
+  /// row indices
+  pub indices: Vec<usize>,
+  /// column information
+  pub indptr: Vec<usize>,
+  /// number of rows
+  pub rows: usize,
+}
+
+impl<F: PrimeField> CscMatrix<F> {
+  /// Number of columns.
+  pub fn cols(&self) -> usize {
+    self.indptr.len() - 1
+  }
+
+  /// Number of stored (structurally non-zero) entries.
+  pub fn nnz(&self) -> usize {
+    self.data.len()
+  }
+
+  /// Retrieves the values and row indices of the column delimited by `ptrs`, which is
+  /// assumed to come from `indptr`.
+  pub fn get_col_unchecked(&self, ptrs: &[usize; 2]) -> impl Iterator<Item = (&F, &usize)> {
+    self.data[ptrs[0]..ptrs[1]]
+      .iter()
+      .zip_eq(&self.indices[ptrs[0]..ptrs[1]])
+  }
+
+  /// The matrix in CSR form, with the columns of each row sorted.
+  pub fn to_csr(&self) -> SparseMatrix<F> {
+    // The arrays of a CSC matrix are those of its transpose in CSR form.
+    let transpose = SparseMatrix::new_unchecked(
+      self.data.clone(),
+      self.indices.clone(),
+      self.indptr.clone(),
+      self.rows,
+    );
+    transpose.transpose()
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize, with one block of columns and one
+  /// partial output per thread.
+  pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
+    assert_eq!(self.cols(), vector.len(), "invalid shape");
+
+    scatter_blocks(self.cols(), self.rows, |cols, partial| {
+      self.scatter(vector, cols, partial)
+    })
+  }
+
+  /// Multiply by a dense vector on the current thread, without rayon.
+  pub fn multiply_vec_serial(&self, vector: &[F]) -> Vec<F> {
+    assert_eq!(self.cols(), vector.len(), "invalid shape");
+
+    let mut result = vec![F::ZERO; self.rows];
+    self.scatter(vector, 0..self.cols(), &mut result);
+    result
+  }
+
+  /// Adds the products of the columns in `cols` with their elements of `vector` to `output`.
+  fn scatter(&self, vector: &[F], cols: std::ops::Range<usize>, output: &mut [F]) {
+    for col in cols {
+      let ptrs = [self.indptr[col], self.indptr[col + 1]];
+      for (value, &row) in self.get_col_unchecked(&ptrs) {
+        output[row] += *value * vector[col];
+      }
+    }
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix in CSC form, with the rows of each column sorted; see
+  /// [`SparseMatrix::transpose`].
+  pub fn to_csc(&self) -> CscMatrix<F> {
+    let transpose = self.transpose();
+    CscMatrix {
+      data: transpose.data,
+      indices: transpose.indices,
+      indptr: transpose.indptr,
+      rows: transpose.cols,
+    }
+  }
+}
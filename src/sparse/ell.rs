@@ -0,0 +1,163 @@
+//! Matrices in ELLPACK form, every row padded to the same number of entries, with the rows
+//! too long for that kept in CSR on the side. Rows of the same length multiply in lockstep,
+//! which suits GPUs and lets the CPU prefetch; a few long rows would pad every other one, so
+//! they are left out.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::SparseMatrix;
+
+/// Most entries per row [`crate::statistics::MatrixStats`] reports an ELL layout with: R1CS
+/// constraints mostly have a handful of terms.
+pub const DEFAULT_ELL_WIDTH: usize = 8;
+
+/// Rows of each task of [`EllMatrix::multiply_vec`].
+const ELL_CHUNK_ROWS: usize = 1024;
+
+/// How a matrix splits between the padded array and the CSR tail of an [`EllMatrix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EllShape {
+  /// Entries of each row of the padded array: the longest row that fits, at most the width
+  /// asked for.
+  pub width: usize,
+  /// Slots of the padded array that hold no entry, including those of the spilled rows.
+  pub padding: usize,
+  /// Rows longer than the width asked for, left to the tail.
+  pub spilled_rows: usize,
+  /// Entries of the spilled rows.
+  pub spilled_nnz: usize,
+}
+
+impl EllShape {
+  /// Slots of the padded array, entries and padding.
+  pub fn slots(&self, rows: usize) -> usize {
+    self.width * rows
+  }
+}
+
+/// An [`EllMatrix`]'s split of a [`SparseMatrix`]: every row of at most `width` entries is
+/// stored in padded arrays of `width` slots per row, column by column, and the longer rows in
+/// a CSR tail in which the other rows are empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EllMatrix<F: PrimeField> {
+  rows: usize,
+  shape: EllShape,
+  /// Slot `s` of row `r` is at `s * rows + r`; padding is a zero in column 0.
+  data: Vec<F>,
+  indices: Vec<usize>,
+  tail: SparseMatrix<F>,
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// How [`EllMatrix::from_csr`] would split the matrix with `max_width`, without building it.
+  pub fn ell_shape(&self, max_width: usize) -> EllShape {
+    let (width, fitting_nnz, spilled_rows) = self
+      .row_nnz_iter()
+      .map(|nnz| match nnz <= max_width {
+        true => (nnz, nnz, 0),
+        false => (0, 0, 1),
+      })
+      .reduce(|| (0, 0, 0), |a, b| (a.0.max(b.0), a.1 + b.1, a.2 + b.2));
+    EllShape {
+      width,
+      padding: width * self.num_rows() - fitting_nnz,
+      spilled_rows,
+      spilled_nnz: self.nnz() - fitting_nnz,
+    }
+  }
+}
+
+impl<F: PrimeField> EllMatrix<F> {
+  /// Stores every row of `matrix` with at most `max_width` entries in the padded arrays, and
+  /// every longer row in the tail.
+  pub fn from_csr(matrix: &SparseMatrix<F>, max_width: usize) -> Self {
+    let rows = matrix.num_rows();
+    let shape = matrix.ell_shape(max_width);
+    let mut data = vec![F::ZERO; shape.slots(rows)];
+    let mut indices = vec![0; shape.slots(rows)];
+    let mut tail = SparseMatrix::new_unchecked(
+      Vec::with_capacity(shape.spilled_nnz),
+      Vec::with_capacity(shape.spilled_nnz),
+      vec![0],
+      matrix.num_cols(),
+    );
+    for row in 0..rows {
+      let entries = matrix.indptr[row]..matrix.indptr[row + 1];
+      if entries.len() > max_width {
+        tail.data.extend_from_slice(&matrix.data[entries.clone()]);
+        tail.indices.extend_from_slice(&matrix.indices[entries]);
+      } else {
+        for (slot, k) in entries.enumerate() {
+          data[slot * rows + row] = matrix.data[k];
+          indices[slot * rows + row] = matrix.indices[k];
+        }
+      }
+      tail.indptr.push(tail.indices.len());
+    }
+    Self {
+      rows,
+      shape,
+      data,
+      indices,
+      tail,
+    }
+  }
+
+  /// Number of rows.
+  pub fn num_rows(&self) -> usize {
+    self.rows
+  }
+
+  /// Number of columns.
+  pub fn num_cols(&self) -> usize {
+    self.tail.num_cols()
+  }
+
+  /// How the matrix is split between the padded arrays and the tail.
+  pub fn shape(&self) -> EllShape {
+    self.shape
+  }
+
+  /// The rows too long for the padded arrays, with every other row empty.
+  pub fn tail(&self) -> &SparseMatrix<F> {
+    &self.tail
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize over chunks of rows, each going
+  /// through the padded arrays a slot at a time and then through its rows of the tail. The
+  /// product is that of [`SparseMatrix::multiply_vec`].
+  pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
+    let mut out = Vec::with_capacity(self.rows);
+    self.multiply_vec_into(vector, &mut out);
+    out
+  }
+
+  /// [`EllMatrix::multiply_vec`] into `out`, which is cleared and resized to the number of
+  /// rows.
+  pub fn multiply_vec_into(&self, vector: &[F], out: &mut Vec<F>) {
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+
+    out.clear();
+    out.resize(self.rows, F::ZERO);
+    out
+      .par_chunks_mut(ELL_CHUNK_ROWS)
+      .enumerate()
+      .for_each(|(chunk, out)| {
+        let start = chunk * ELL_CHUNK_ROWS;
+        for slot in 0..self.shape.width {
+          let base = slot * self.rows + start;
+          for (i, sum) in out.iter_mut().enumerate() {
+            *sum += self.data[base + i] * vector[self.indices[base + i]];
+          }
+        }
+        for (i, sum) in out.iter_mut().enumerate() {
+          let row = start + i;
+          for k in self.tail.indptr[row]..self.tail.indptr[row + 1] {
+            *sum += self.tail.data[k] * vector[self.tail.indices[k]];
+          }
+        }
+      });
+  }
+}
@@ -0,0 +1,152 @@
+//! Iterating over the entries of a matrix as `(row, col, &value)` triples, in row-major order,
+//! serially and in parallel.
+//!
+//! Both walk the positions of `data`, moving to the next row as they pass its end in `indptr`,
+//! so finding the row of an entry costs nothing on average. The parallel iterator splits on
+//! positions rather than rows, so that a few dense rows do not end up in one task, and finds
+//! the rows at either side of a split by binary search.
+
+use std::ops::Range;
+
+use ff::PrimeField;
+use rayon::iter::{
+  plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer},
+  IndexedParallelIterator, ParallelIterator,
+};
+
+use super::SparseMatrix;
+
+/// The entries of a [`SparseMatrix`], from [`SparseMatrix::iter`].
+#[derive(Debug, Clone)]
+pub struct Entries<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+  /// Positions left to yield.
+  positions: Range<usize>,
+  /// The row of the first position left, and of the last.
+  front_row: usize,
+  back_row: usize,
+}
+
+impl<'a, F: PrimeField> Entries<'a, F> {
+  fn new(matrix: &'a SparseMatrix<F>, positions: Range<usize>) -> Self {
+    let row_of = |k: usize| {
+      matrix
+        .indptr
+        .partition_point(|&ptr| ptr <= k)
+        .saturating_sub(1)
+    };
+    Self {
+      matrix,
+      front_row: row_of(positions.start),
+      back_row: row_of(positions.end.saturating_sub(1)),
+      positions,
+    }
+  }
+
+  fn entry(&self, row: usize, k: usize) -> (usize, usize, &'a F) {
+    (row, self.matrix.indices[k], &self.matrix.data[k])
+  }
+}
+
+impl<'a, F: PrimeField> Iterator for Entries<'a, F> {
+  type Item = (usize, usize, &'a F);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let k = self.positions.next()?;
+    while self.matrix.indptr[self.front_row + 1] <= k {
+      self.front_row += 1;
+    }
+    Some(self.entry(self.front_row, k))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.positions.size_hint()
+  }
+}
+
+impl<F: PrimeField> DoubleEndedIterator for Entries<'_, F> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    let k = self.positions.next_back()?;
+    while self.matrix.indptr[self.back_row] > k {
+      self.back_row -= 1;
+    }
+    Some(self.entry(self.back_row, k))
+  }
+}
+
+impl<F: PrimeField> ExactSizeIterator for Entries<'_, F> {}
+
+/// The entries of a [`SparseMatrix`] in parallel, from [`SparseMatrix::par_iter`].
+#[derive(Debug, Clone)]
+pub struct ParEntries<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+}
+
+impl<'a, F: PrimeField> ParallelIterator for ParEntries<'a, F> {
+  type Item = (usize, usize, &'a F);
+
+  fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+    bridge(self, consumer)
+  }
+
+  fn opt_len(&self) -> Option<usize> {
+    Some(self.matrix.nnz())
+  }
+}
+
+impl<F: PrimeField> IndexedParallelIterator for ParEntries<'_, F> {
+  fn len(&self) -> usize {
+    self.matrix.nnz()
+  }
+
+  fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+    bridge(self, consumer)
+  }
+
+  fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+    callback.callback(EntriesProducer {
+      matrix: self.matrix,
+      positions: 0..self.matrix.nnz(),
+    })
+  }
+}
+
+struct EntriesProducer<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+  positions: Range<usize>,
+}
+
+impl<'a, F: PrimeField> Producer for EntriesProducer<'a, F> {
+  type Item = (usize, usize, &'a F);
+  type IntoIter = Entries<'a, F>;
+
+  fn into_iter(self) -> Entries<'a, F> {
+    Entries::new(self.matrix, self.positions)
+  }
+
+  fn split_at(self, index: usize) -> (Self, Self) {
+    let middle = self.positions.start + index;
+    let left = Self {
+      matrix: self.matrix,
+      positions: self.positions.start..middle,
+    };
+    let right = Self {
+      matrix: self.matrix,
+      positions: middle..self.positions.end,
+    };
+    (left, right)
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The entries as `(row, col, &value)`, row by row, and within a row in the order they are
+  /// stored.
+  pub fn iter(&self) -> Entries<'_, F> {
+    Entries::new(self, 0..self.nnz())
+  }
+
+  /// The entries as [`SparseMatrix::iter`] gives them, in parallel.
+  pub fn par_iter(&self) -> ParEntries<'_, F> {
+    ParEntries { matrix: self }
+  }
+}
@@ -0,0 +1,180 @@
+//! Products of two sparse matrices, by Gustavson's algorithm: row `i` of `A B` is the sum of
+//! the rows `k` of `B` scaled by the entries `(i, k)` of `A`, added up in an accumulator that
+//! every thread keeps for the rows it computes.
+//!
+//! The rows of `A` are computed in parallel, twice: a symbolic pass counts the columns of
+//! every row of the product, which lays out the output and is checked against a limit before
+//! anything is allocated, and a numeric pass adds the rows up and writes them in place.
+
+use std::collections::HashMap;
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::{MatrixError, RowData, SparseMatrix};
+
+/// Most entries [`SparseMatrix::matmul`] lets a product have, about 640 MiB of `bn256::Fr`
+/// entries and their columns.
+pub const DEFAULT_MATMUL_NNZ_LIMIT: usize = 1 << 24;
+
+/// Most columns an accumulator keeps a slot for each of; wider products are added up in a
+/// hash map instead, so that every thread does not hold a row as wide as the product.
+const DENSE_ACCUMULATOR_COLS: usize = 1 << 18;
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The product `A B`, with the columns of each row sorted, refusing products of more than
+  /// [`DEFAULT_MATMUL_NNZ_LIMIT`] entries; see [`SparseMatrix::matmul_with_limit`].
+  pub fn matmul(&self, other: &Self) -> Result<Self, MatrixError> {
+    self.matmul_with_limit(other, DEFAULT_MATMUL_NNZ_LIMIT)
+  }
+
+  /// The product `A B`, with the columns of each row sorted, or an error if `A` does not have
+  /// a column for every row of `B`, or the product would have more than `limit` entries.
+  /// Entries that cancel out are kept as explicit zeros.
+  pub fn matmul_with_limit(&self, other: &Self, limit: usize) -> Result<Self, MatrixError> {
+    if self.num_cols() != other.num_rows() {
+      return Err(MatrixError::ProductShape {
+        left: self.shape(),
+        right: other.shape(),
+      });
+    }
+    let rows = self.num_rows();
+    let accumulator = || Accumulator::new(other.cols);
+
+    let counts: Vec<usize> = (0..rows)
+      .into_par_iter()
+      .map_init(accumulator, |accumulator, row| {
+        self.accumulate_row(other, row, accumulator, |_, _| F::ZERO);
+        let count = accumulator.len();
+        accumulator.drain_sorted(|_, _| {});
+        count
+      })
+      .collect();
+    let nnz: usize = counts.par_iter().sum();
+    if nnz > limit {
+      return Err(MatrixError::TooManyEntries { nnz, limit });
+    }
+    let mut indptr = Vec::with_capacity(rows + 1);
+    indptr.push(0);
+    for (row, count) in counts.iter().enumerate() {
+      indptr.push(indptr[row] + count);
+    }
+
+    let mut data = vec![F::ZERO; nnz];
+    let mut indices = vec![0; nnz];
+    let mut shares = Vec::with_capacity(rows);
+    let (mut data_rest, mut indices_rest) = (&mut data[..], &mut indices[..]);
+    for &count in &counts {
+      let (data_share, rest) = std::mem::take(&mut data_rest).split_at_mut(count);
+      data_rest = rest;
+      let (index_share, rest) = std::mem::take(&mut indices_rest).split_at_mut(count);
+      indices_rest = rest;
+      shares.push((data_share, index_share));
+    }
+    shares.into_par_iter().enumerate().for_each_init(
+      accumulator,
+      |accumulator, (row, (data_share, index_share))| {
+        self.accumulate_row(other, row, accumulator, |a, b| a * b);
+        let mut position = 0;
+        accumulator.drain_sorted(|col, value| {
+          (data_share[position], index_share[position]) = (value, col);
+          position += 1;
+        });
+      },
+    );
+
+    Ok(Self::new_unchecked(data, indices, indptr, other.cols))
+  }
+
+  /// Adds `product(a, b)` into `accumulator` for every entry `a` at `(row, k)` and `b` at
+  /// `(k, j)` of `other`, at column `j`.
+  fn accumulate_row(
+    &self,
+    other: &Self,
+    row: usize,
+    accumulator: &mut Accumulator<F>,
+    product: impl Fn(F, F) -> F,
+  ) {
+    let ptrs = RowData([self.indptr[row], self.indptr[row + 1]]);
+    for (k, a) in self.row_view(&ptrs).iter() {
+      let other_ptrs = RowData([other.indptr[k], other.indptr[k + 1]]);
+      for (col, b) in other.row_view(&other_ptrs).iter() {
+        accumulator.add(col, product(*a, *b));
+      }
+    }
+  }
+}
+
+/// The entries of one row of a product as they are added up.
+enum Accumulator<F> {
+  /// A slot for every column, with the columns occupied so far.
+  Dense {
+    values: Vec<F>,
+    occupied: Vec<bool>,
+    cols: Vec<usize>,
+  },
+  Sparse(HashMap<usize, F>),
+}
+
+impl<F: PrimeField> Accumulator<F> {
+  fn new(cols: usize) -> Self {
+    match cols <= DENSE_ACCUMULATOR_COLS {
+      true => Accumulator::Dense {
+        values: vec![F::ZERO; cols],
+        occupied: vec![false; cols],
+        cols: Vec::new(),
+      },
+      false => Accumulator::Sparse(HashMap::new()),
+    }
+  }
+
+  fn add(&mut self, col: usize, value: F) {
+    match self {
+      Accumulator::Dense {
+        values,
+        occupied,
+        cols,
+      } => {
+        if !occupied[col] {
+          occupied[col] = true;
+          cols.push(col);
+        }
+        values[col] += value;
+      }
+      Accumulator::Sparse(entries) => *entries.entry(col).or_insert(F::ZERO) += value,
+    }
+  }
+
+  /// Number of columns added to so far.
+  fn len(&self) -> usize {
+    match self {
+      Accumulator::Dense { cols, .. } => cols.len(),
+      Accumulator::Sparse(entries) => entries.len(),
+    }
+  }
+
+  /// Passes every column added to, in order, and its sum to `emit`, leaving the accumulator
+  /// empty for the next row.
+  fn drain_sorted(&mut self, mut emit: impl FnMut(usize, F)) {
+    match self {
+      Accumulator::Dense {
+        values,
+        occupied,
+        cols,
+      } => {
+        cols.sort_unstable();
+        for col in cols.drain(..) {
+          emit(col, std::mem::replace(&mut values[col], F::ZERO));
+          occupied[col] = false;
+        }
+      }
+      Accumulator::Sparse(entries) => {
+        let mut sorted: Vec<_> = entries.drain().collect();
+        sorted.sort_unstable_by_key(|&(col, _)| col);
+        for (col, value) in sorted {
+          emit(col, value);
+        }
+      }
+    }
+  }
+}
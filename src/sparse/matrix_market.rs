@@ -0,0 +1,263 @@
+//! Reading and writing matrices in the [Matrix Market] coordinate format, which most
+//! sparse-matrix tooling and collections of test matrices speak.
+//!
+//! A file opens with a `%%MatrixMarket matrix coordinate <field> <symmetry>` banner, then
+//! `%` comment lines, then a `<rows> <cols> <entries>` size line, then one `<row> <col> <value>`
+//! line per entry with 1-based indices. Values become field elements:
+//!
+//! - `integer` values `x` map to `F::from(x)`, and negative ones `-x` to `-F::from(x)`; values
+//!   written in `0x` hex, as [`SparseMatrix::write_matrix_market`] writes them, are the field
+//!   element with that canonical value, which must be below the modulus;
+//! - `real` values are accepted only if they are such an integer, as in `3` or `-2.0e1`, and
+//!   rejected otherwise, as a fraction has no faithful image in the field;
+//! - `pattern` files, which give no values, get 1 for every entry.
+//!
+//! `symmetric` files list the lower triangle, and each entry off the diagonal is mirrored;
+//! `general` files list every entry. Entries may come in any order, and entries repeated at
+//! the same position are added up, as scipy does.
+//!
+//! Written files are `integer` and `general`, with every entry as its `0x` hex, so that any
+//! matrix reads back exactly; a comment after the banner names the modulus of the field.
+//!
+//! [Matrix Market]: https://math.nist.gov/MatrixMarket/formats.html
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+use ff::PrimeField;
+
+use super::{CooMatrix, SparseMatrix};
+use crate::hex::{field_from_hex, field_to_hex};
+
+/// The banner opening every Matrix Market file.
+pub const MATRIX_MARKET_BANNER: &str = "%%MatrixMarket";
+
+/// Extension of Matrix Market files.
+pub const MATRIX_MARKET_EXTENSION: &str = "mtx";
+
+/// The magnitude up to which every integer is exact as an `f64`, and so as a `real` value.
+const MAX_EXACT_REAL: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+  Integer,
+  Real,
+  Pattern,
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Reads a matrix in the Matrix Market coordinate format from `reader`, which is buffered
+  /// here; see the [module docs](self) for what is accepted. Each row of the result has its
+  /// columns sorted, with no column twice.
+  pub fn from_matrix_market(reader: impl Read) -> io::Result<Self> {
+    let mut lines = Lines {
+      reader: BufReader::new(reader),
+      line: String::new(),
+      number: 0,
+    };
+    let (field, symmetric) = lines.banner()?;
+    if !lines.advance()? {
+      return Err(lines.error("no size line"));
+    }
+    let [rows, cols, entries] =
+      parse_numbers(lines.text()).map_err(|message| lines.error(message))?;
+    if symmetric && rows != cols {
+      return Err(lines.error(format!("a symmetric matrix is square, not {rows}x{cols}")));
+    }
+
+    let capacity = entries.saturating_mul(1 + symmetric as usize).min(1 << 20);
+    let mut coo = CooMatrix::with_capacity(rows, cols, capacity);
+    for _ in 0..entries {
+      if !lines.advance()? {
+        return Err(lines.error(format!("the file ends before its {entries} entries")));
+      }
+      let (row, col, value) =
+        parse_entry(lines.text(), rows, cols, field).map_err(|message| lines.error(message))?;
+      coo.push(row, col, value);
+      if symmetric && row != col {
+        coo.push(col, row, value);
+      }
+    }
+    if lines.advance()? {
+      return Err(lines.error(format!("more than the {entries} entries announced")));
+    }
+    Ok(coo.into_csr())
+  }
+
+  /// Writes the matrix to `writer`, which is buffered here, in the Matrix Market coordinate
+  /// format with 1-based indices and every entry in hex; see the [module docs](self).
+  pub fn write_matrix_market(&self, writer: impl Write) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    writeln!(
+      writer,
+      "{MATRIX_MARKET_BANNER} matrix coordinate integer general"
+    )?;
+    writeln!(
+      writer,
+      "% Entries are elements of the prime field of modulus {}, written in hex.",
+      F::MODULUS
+    )?;
+    writeln!(writer, "{} {} {}", self.num_rows(), self.cols, self.nnz())?;
+    for (row, col, value) in self.iter() {
+      writeln!(writer, "{} {} {}", row + 1, col + 1, field_to_hex(value))?;
+    }
+    writer.flush()
+  }
+}
+
+/// The lines of a file that are not comments or blank, numbered for errors.
+struct Lines<R> {
+  reader: R,
+  line: String,
+  /// Number of the current line, from 1.
+  number: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+  /// Reads the banner, returning the field of the values and whether the matrix is symmetric.
+  fn banner(&mut self) -> io::Result<(Field, bool)> {
+    self.number += 1;
+    self.reader.read_line(&mut self.line)?;
+    // The banner is case-insensitive.
+    let line = self.line.to_ascii_lowercase();
+    let banner = MATRIX_MARKET_BANNER.to_ascii_lowercase();
+    let words: Vec<_> = line.split_whitespace().collect();
+    let (format, field, symmetry) = match words[..] {
+      [first, "matrix", format, field, symmetry] if first == banner => (format, field, symmetry),
+      _ => return Err(self.error(format!("expected a {MATRIX_MARKET_BANNER} matrix banner"))),
+    };
+    if format != "coordinate" {
+      return Err(self.error(format!(
+        "{format} files are not supported, only coordinate ones"
+      )));
+    }
+    let field = match field {
+      "integer" => Field::Integer,
+      "real" => Field::Real,
+      "pattern" => Field::Pattern,
+      field => return Err(self.error(format!("{field} values are not supported"))),
+    };
+    let symmetric = match symmetry {
+      "general" => false,
+      "symmetric" => true,
+      symmetry => return Err(self.error(format!("{symmetry} matrices are not supported"))),
+    };
+    Ok((field, symmetric))
+  }
+
+  /// Moves to the next line that is not a comment or blank, returning false at the end of the
+  /// file.
+  fn advance(&mut self) -> io::Result<bool> {
+    loop {
+      self.line.clear();
+      if self.reader.read_line(&mut self.line)? == 0 {
+        return Ok(false);
+      }
+      self.number += 1;
+      let line = self.text();
+      if !line.is_empty() && !line.starts_with('%') {
+        return Ok(true);
+      }
+    }
+  }
+
+  /// The current line, trimmed.
+  fn text(&self) -> &str {
+    self.line.trim()
+  }
+
+  fn error(&self, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+      io::ErrorKind::InvalidData,
+      format!("line {}: {message}", self.number),
+    )
+  }
+}
+
+/// Parses the `N` whitespace-separated numbers making up `line`.
+fn parse_numbers<const N: usize>(line: &str) -> Result<[usize; N], String> {
+  let mut numbers = [0; N];
+  let mut words = line.split_whitespace();
+  for number in &mut numbers {
+    let word = words
+      .next()
+      .ok_or_else(|| format!("expected {N} numbers"))?;
+    *number = word
+      .parse()
+      .map_err(|_| format!("{word:?} is not a count"))?;
+  }
+  match words.next() {
+    Some(_) => Err(format!("expected {N} numbers")),
+    None => Ok(numbers),
+  }
+}
+
+/// Parses the entry on `line` of a `rows` by `cols` matrix into 0-based indices and its value.
+fn parse_entry<F: PrimeField>(
+  line: &str,
+  rows: usize,
+  cols: usize,
+  field: Field,
+) -> Result<(usize, usize, F), String> {
+  let mut words = line.split_whitespace();
+  let mut index = |what, len| {
+    let word = words
+      .next()
+      .ok_or("expected a row, a column, and a value")?;
+    match word.parse::<usize>() {
+      Ok(index) if (1..=len).contains(&index) => Ok(index - 1),
+      _ => Err(format!("{what} {word} is not between 1 and {len}")),
+    }
+  };
+  let (row, col) = (index("row", rows)?, index("column", cols)?);
+  let value = match (field, words.next()) {
+    (Field::Pattern, None) => F::ONE,
+    (Field::Integer, Some(value)) => parse_integer(value)?,
+    (Field::Real, Some(value)) => parse_real(value)?,
+    _ => return Err("expected a row, a column, and a value".to_string()),
+  };
+  match words.next() {
+    Some(_) => Err("unexpected text after the entry".to_string()),
+    None => Ok((row, col, value)),
+  }
+}
+
+/// Maps the integer `word` into the field, with `-x` as `-F::from(x)`.
+fn parse_integer<F: PrimeField>(word: &str) -> Result<F, String> {
+  let (negative, magnitude) = match word.strip_prefix('-') {
+    Some(magnitude) => (true, magnitude),
+    None => (false, word.strip_prefix('+').unwrap_or(word)),
+  };
+  if magnitude.starts_with("0x") {
+    let value: F = field_from_hex(magnitude)?;
+    return Ok(if negative { -value } else { value });
+  }
+  let magnitude: u64 = magnitude
+    .parse()
+    .map_err(|_| format!("{word} is not an integer that fits in 64 bits"))?;
+  Ok(signed(negative, magnitude))
+}
+
+/// Maps the real `word` into the field if it is an integer exactly, as [`parse_integer`] does.
+fn parse_real<F: PrimeField>(word: &str) -> Result<F, String> {
+  let value: f64 = word
+    .parse()
+    .map_err(|_| format!("{word} is not a number"))?;
+  if !value.is_finite() || value.fract() != 0.0 {
+    return Err(format!(
+      "{word} is not an integer, and only integers map into the field"
+    ));
+  }
+  if value.abs() > MAX_EXACT_REAL {
+    return Err(format!(
+      "{word} is too large to be read exactly as a real; store it as an integer"
+    ));
+  }
+  Ok(signed(value < 0.0, value.abs() as u64))
+}
+
+pub(super) fn signed<F: PrimeField>(negative: bool, magnitude: u64) -> F {
+  match negative {
+    true => -F::from(magnitude),
+    false => F::from(magnitude),
+  }
+}
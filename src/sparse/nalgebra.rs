@@ -0,0 +1,37 @@
+//! Conversions between [`SparseMatrix`] and the [`CsrMatrix`] of nalgebra-sparse.
+//!
+//! nalgebra requires the columns of each row to be sorted, in bounds, and each there at most
+//! once, and its constructors fail on anything else. Converting to it sorts the columns of every
+//! row, so rows in any order convert, and fails on a column out of bounds or repeated within its
+//! row rather than adding such entries up; the error says which.
+
+use ff::PrimeField;
+use nalgebra_sparse::{CsrMatrix, SparseFormatError};
+
+use super::SparseMatrix;
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Copies this matrix into a [`CsrMatrix`], sorting the columns of each row. Fails if a
+  /// column is not below `cols` or appears twice in a row, or if `indptr` does not delimit
+  /// `indices`.
+  pub fn to_nalgebra_csr(&self) -> Result<CsrMatrix<F>, SparseFormatError> {
+    CsrMatrix::try_from_unsorted_csr_data(
+      self.indptr.len().saturating_sub(1),
+      self.cols,
+      self.indptr.clone(),
+      self.indices.clone(),
+      self.data.clone(),
+    )
+  }
+
+  /// Copies `matrix`, whose rows are sorted, as nalgebra keeps them.
+  pub fn from_nalgebra_csr(matrix: &CsrMatrix<F>) -> Self {
+    let (indptr, indices, data) = matrix.csr_data();
+    SparseMatrix {
+      data: data.to_vec(),
+      indices: indices.to_vec(),
+      indptr: indptr.to_vec(),
+      cols: matrix.ncols(),
+    }
+  }
+}
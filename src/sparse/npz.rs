@@ -0,0 +1,325 @@
+//! Reading and writing matrices as scipy's [`save_npz`] does: a zip of the `.npy` arrays
+//! `data`, `indices`, `indptr`, `shape`, and `format`, holding `b"csr"`.
+//!
+//! Field elements have no numpy type, so `data` is written as a `uint8` array of shape
+//! `(nnz, 32)`, each row the canonical little-endian bytes of an entry, as
+//! [`PrimeField::to_repr`] gives them. scipy's `load_npz` expects `data` to be 1-dimensional,
+//! so a notebook loads the arrays with `numpy.load` instead; `tests/fixtures/npz/load.py`
+//! shows how. The indices are written as `int64`, and `shape` as `(rows, cols)`.
+//!
+//! Reading takes `data` in that layout, or as a 1-dimensional array of integers, which map
+//! into the field as [Matrix Market](super::matrix_market) integers do, so that matrices saved
+//! by scipy itself can be read; and the indices as integers of any width. Entries may be
+//! stored or deflated, as `save_npz(..., compressed=True)`, its default, writes them.
+//!
+//! [`save_npz`]: https://docs.scipy.org/doc/scipy/reference/generated/scipy.sparse.save_npz.html
+
+mod inflate;
+mod zip;
+
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
+
+use super::{matrix_market::signed, SparseMatrix};
+
+/// Extension of npz files.
+pub const NPZ_EXTENSION: &str = "npz";
+
+/// The magic bytes every `.npy` array starts with.
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// `.npy` headers are padded so the array after them is aligned to this.
+const NPY_ALIGNMENT: usize = 64;
+
+/// A numpy array, as read from a `.npy` file.
+struct Array<'a> {
+  name: &'a str,
+  /// The kind of the elements, as in `i` for signed integers.
+  kind: char,
+  /// Bytes per element.
+  size: usize,
+  big_endian: bool,
+  shape: Vec<usize>,
+  bytes: &'a [u8],
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Reads a matrix in scipy's npz layout from `reader`; see the [module docs](self) for what
+  /// is accepted. The rows are checked to be in bounds, and are then canonicalized, as by
+  /// [`SparseMatrix::canonicalize`].
+  pub fn from_npz(mut reader: impl Read) -> io::Result<Self> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Self::decode_npz(&bytes).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+  }
+
+  /// Writes the matrix to `writer` in scipy's npz layout, with every entry stored.
+  pub fn write_npz(&self, writer: impl Write) -> io::Result<()> {
+    let rows = self.num_rows();
+    let width = F::Repr::default().as_ref().len();
+    let mut data = Vec::with_capacity(self.nnz() * width);
+    for value in &self.data {
+      data.extend_from_slice(value.to_repr().as_ref());
+    }
+    let entries = [
+      (
+        "indices.npy",
+        npy("<i8", &[self.nnz()], &int64_bytes(&self.indices)),
+      ),
+      (
+        "indptr.npy",
+        npy("<i8", &[rows + 1], &int64_bytes(&self.indptr)),
+      ),
+      ("format.npy", npy("|S3", &[], b"csr")),
+      (
+        "shape.npy",
+        npy("<i8", &[2], &int64_bytes(&[rows, self.cols])),
+      ),
+      ("data.npy", npy("|u1", &[self.nnz(), width], &data)),
+    ];
+    zip::write(io::BufWriter::new(writer), &entries)
+  }
+
+  fn decode_npz(bytes: &[u8]) -> Result<Self, String> {
+    let entries = zip::read(bytes)?;
+    let find = |name: &str| {
+      let entry = entries
+        .iter()
+        .find(|(entry, _)| entry.strip_suffix(".npy") == Some(name));
+      entry.map(|(_, contents)| contents)
+    };
+    let array = |name: &'static str| parse_npy(name, find(name).ok_or(format!("no {name} array"))?);
+    // Arrays saved with `numpy.savez` alone have no `format`, and are taken to be csr.
+    if find("format").is_some() {
+      let format = array("format")?.text()?;
+      if format != "csr" {
+        return Err(format!(
+          "a {format} matrix, and only csr matrices are supported"
+        ));
+      }
+    }
+    let shape = array("shape")?.integers()?;
+    let [rows, cols] = shape[..] else {
+      return Err("shape does not have 2 dimensions".to_string());
+    };
+    let indices = array("indices")?.integers()?;
+    let indptr = array("indptr")?.integers()?;
+    let data = array("data")?.field_elements()?;
+
+    if indptr.len() != rows + 1 {
+      return Err(format!(
+        "indptr has {} entries, not one more than the {rows} rows",
+        indptr.len()
+      ));
+    }
+    let mut matrix = Self::try_new(data, indices, indptr, cols).map_err(|err| err.to_string())?;
+    matrix.canonicalize();
+    Ok(matrix)
+  }
+}
+
+fn int64_bytes(values: &[usize]) -> Vec<u8> {
+  values
+    .iter()
+    .flat_map(|&value| (value as i64).to_le_bytes())
+    .collect()
+}
+
+/// The `.npy` file of the C-ordered array of type `descr` and `shape` holding `bytes`.
+fn npy(descr: &str, shape: &[usize], bytes: &[u8]) -> Vec<u8> {
+  let shape = match shape {
+    [len] => format!("({len},)"),
+    shape => format!(
+      "({})",
+      shape
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+    ),
+  };
+  let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+  // The magic, version, and header length take 10 bytes, and the header ends in a newline.
+  let unpadded = NPY_MAGIC.len() + 4 + header.len() + 1;
+  header.extend(std::iter::repeat_n(
+    ' ',
+    unpadded.next_multiple_of(NPY_ALIGNMENT) - unpadded,
+  ));
+  header.push('\n');
+
+  let mut npy = Vec::with_capacity(NPY_MAGIC.len() + 4 + header.len() + bytes.len());
+  npy.extend_from_slice(NPY_MAGIC);
+  npy.extend([1, 0]);
+  npy.extend((header.len() as u16).to_le_bytes());
+  npy.extend(header.as_bytes());
+  npy.extend_from_slice(bytes);
+  npy
+}
+
+/// Parses the `.npy` file `bytes` of the array `name`.
+fn parse_npy<'a>(name: &'a str, bytes: &'a [u8]) -> Result<Array<'a>, String> {
+  let corrupt = || format!("{name} is not a valid .npy array");
+  let rest = bytes.strip_prefix(NPY_MAGIC).ok_or_else(corrupt)?;
+  let (header_len, rest) = match rest {
+    [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+    [2 | 3, _, a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+    _ => return Err(corrupt()),
+  };
+  let header = rest.get(..header_len).ok_or_else(corrupt)?;
+  let header = std::str::from_utf8(header).map_err(|_| corrupt())?;
+
+  let descr = header_value(header, "descr").ok_or_else(corrupt)?;
+  let descr = descr.trim_matches(['\'', '"']);
+  let mut chars = descr.chars();
+  let big_endian = match chars.next() {
+    Some('>') => true,
+    Some('<' | '|' | '=') => false,
+    _ => return Err(format!("{name} has the unsupported type {descr}")),
+  };
+  let kind = chars.next().ok_or_else(corrupt)?;
+  let size: usize = chars
+    .as_str()
+    .parse()
+    .map_err(|_| format!("{name} has the unsupported type {descr}"))?;
+  if header_value(header, "fortran_order") != Some("False") {
+    return Err(format!("{name} is in Fortran order"));
+  }
+  let shape = header_value(header, "shape").ok_or_else(corrupt)?;
+  let shape = shape
+    .trim_start_matches('(')
+    .trim_end_matches(')')
+    .split(',')
+    .map(str::trim)
+    .filter(|len| !len.is_empty())
+    .map(|len| len.parse().map_err(|_| corrupt()))
+    .collect::<Result<Vec<usize>, _>>()?;
+
+  let bytes = &rest[header_len..];
+  let expected = shape
+    .iter()
+    .try_fold(size, |len, &dim| len.checked_mul(dim))
+    .ok_or_else(corrupt)?;
+  if bytes.len() != expected {
+    return Err(format!(
+      "{name} holds {} bytes, not the {expected} of its shape",
+      bytes.len()
+    ));
+  }
+  Ok(Array {
+    name,
+    kind,
+    size,
+    big_endian,
+    shape,
+    bytes,
+  })
+}
+
+/// The text of the value of `key` in the Python dict literal `header`, up to the next key.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+  let start = header.find(&format!("'{key}'"))? + key.len() + 2;
+  let value = header[start..].trim_start().strip_prefix(':')?.trim_start();
+  let end = match value.chars().next()? {
+    '(' => value.find(')')? + 1,
+    quote @ ('\'' | '"') => value[1..].find(quote)? + 2,
+    _ => value.find([',', '}'])?,
+  };
+  Some(value[..end].trim())
+}
+
+impl Array<'_> {
+  /// The elements of a 1-dimensional integer array, as `i128` so every width fits.
+  fn signed(&self) -> Result<Vec<i128>, String> {
+    if self.shape.len() != 1 || !matches!(self.kind, 'i' | 'u') || !(1..=8).contains(&self.size) {
+      return Err(format!(
+        "{} is not a 1-dimensional integer array",
+        self.name
+      ));
+    }
+    Ok(
+      self
+        .bytes
+        .chunks_exact(self.size)
+        .map(|element| {
+          let mut bytes = [0; 8];
+          bytes[..self.size].copy_from_slice(element);
+          if self.big_endian {
+            bytes[..self.size].reverse();
+          }
+          let negative = self.kind == 'i' && bytes[self.size - 1] & 0x80 != 0;
+          if negative {
+            bytes[self.size..].fill(0xff);
+          }
+          match negative {
+            true => i64::from_le_bytes(bytes) as i128,
+            false => u64::from_le_bytes(bytes) as i128,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// The elements of a 1-dimensional array of indices, which must not be negative.
+  fn integers(&self) -> Result<Vec<usize>, String> {
+    self
+      .signed()?
+      .into_iter()
+      .map(|value| {
+        usize::try_from(value).map_err(|_| format!("{} holds {value}, not an index", self.name))
+      })
+      .collect()
+  }
+
+  /// The elements of `data` as field elements.
+  fn field_elements<F: PrimeField>(&self) -> Result<Vec<F>, String> {
+    let width = F::Repr::default().as_ref().len();
+    if self.shape.len() == 1 {
+      return Ok(
+        self
+          .signed()?
+          .into_iter()
+          .map(|value| signed(value < 0, value.unsigned_abs() as u64))
+          .collect(),
+      );
+    }
+    if self.kind != 'u' || self.size != 1 || self.shape.len() != 2 || self.shape[1] != width {
+      return Err(format!(
+        "{} is neither integers nor a uint8 array of shape (nnz, {width})",
+        self.name
+      ));
+    }
+    self
+      .bytes
+      .chunks_exact(width)
+      .enumerate()
+      .map(|(k, bytes)| {
+        let mut repr = F::Repr::default();
+        repr.as_mut().copy_from_slice(bytes);
+        Option::from(F::from_repr(repr))
+          .ok_or_else(|| format!("{} row {k} is not below the modulus", self.name))
+      })
+      .collect()
+  }
+
+  /// The text of a 0-dimensional byte or unicode string array.
+  fn text(&self) -> Result<String, String> {
+    match self.kind {
+      'S' => Ok(
+        String::from_utf8_lossy(self.bytes)
+          .trim_end_matches('\0')
+          .to_string(),
+      ),
+      'U' if self.size.is_multiple_of(4) => Ok(
+        self
+          .bytes
+          .chunks_exact(4)
+          .filter_map(|c| char::from_u32(u32::from_le_bytes(c.try_into().unwrap())))
+          .collect::<String>()
+          .trim_end_matches('\0')
+          .to_string(),
+      ),
+      _ => Err(format!("{} is not a string", self.name)),
+    }
+  }
+}
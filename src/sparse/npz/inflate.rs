@@ -0,0 +1,247 @@
+//! Decompressing [deflate] streams, as `numpy.savez_compressed` and so scipy's `save_npz`
+//! write them by default. This follows zlib's `puff`: codes are decoded a bit at a time, which
+//! is slower than a table-driven decoder but short, and fast enough to load a matrix.
+//!
+//! [deflate]: https://www.rfc-editor.org/rfc/rfc1951
+
+/// Longest code, in bits.
+const MAX_BITS: usize = 15;
+
+/// Base lengths of the length symbols 257 to 285, and how many extra bits each takes.
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+  163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances of the distance symbols 0 to 29, and how many extra bits each takes.
+const DISTANCE_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+  3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Order in which the lengths of the code length code are given.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses the raw deflate `input`, which should hold `expected` bytes.
+pub(super) fn inflate(input: &[u8], expected: usize) -> Result<Vec<u8>, String> {
+  let mut bits = Bits {
+    input,
+    position: 0,
+    buffer: 0,
+    count: 0,
+  };
+  let mut output = Vec::with_capacity(expected);
+  loop {
+    let last = bits.take(1)? == 1;
+    match bits.take(2)? {
+      0 => stored(&mut bits, &mut output)?,
+      1 => {
+        let (lengths, distances) = fixed_codes();
+        codes(&mut bits, &mut output, &lengths, &distances)?
+      }
+      2 => {
+        let (lengths, distances) = dynamic_codes(&mut bits)?;
+        codes(&mut bits, &mut output, &lengths, &distances)?
+      }
+      _ => return Err("invalid deflate block type".to_string()),
+    }
+    if last {
+      return Ok(output);
+    }
+  }
+}
+
+/// The input, read a bit at a time from the least significant bit of each byte.
+struct Bits<'a> {
+  input: &'a [u8],
+  position: usize,
+  buffer: u64,
+  count: u32,
+}
+
+impl Bits<'_> {
+  fn take(&mut self, n: u32) -> Result<u32, String> {
+    while self.count < n {
+      let byte = *self
+        .input
+        .get(self.position)
+        .ok_or("the deflate stream ends early")?;
+      self.buffer |= (byte as u64) << self.count;
+      self.position += 1;
+      self.count += 8;
+    }
+    let value = (self.buffer & ((1 << n) - 1)) as u32;
+    self.buffer >>= n;
+    self.count -= n;
+    Ok(value)
+  }
+}
+
+/// A canonical Huffman code: how many codes there are of each length, and the symbols in
+/// order of their codes.
+struct Huffman {
+  counts: [u16; MAX_BITS + 1],
+  symbols: Vec<u16>,
+}
+
+impl Huffman {
+  /// The code giving each symbol the length in `lengths`, where 0 leaves it out. Incomplete
+  /// codes are allowed, as a stream may use a single distance code.
+  fn new(lengths: &[u8]) -> Result<Self, String> {
+    let mut counts = [0u16; MAX_BITS + 1];
+    for &length in lengths {
+      counts[length as usize] += 1;
+    }
+    let mut left = 1i32;
+    for &count in &counts[1..] {
+      left = 2 * left - count as i32;
+      if left < 0 {
+        return Err("an over-subscribed deflate code".to_string());
+      }
+    }
+    let mut offsets = [0u16; MAX_BITS + 1];
+    for length in 1..MAX_BITS {
+      offsets[length + 1] = offsets[length] + counts[length];
+    }
+    let mut symbols = vec![0; lengths.len()];
+    for (symbol, &length) in lengths.iter().enumerate() {
+      if length != 0 {
+        symbols[offsets[length as usize] as usize] = symbol as u16;
+        offsets[length as usize] += 1;
+      }
+    }
+    Ok(Self { counts, symbols })
+  }
+
+  fn decode(&self, bits: &mut Bits) -> Result<u16, String> {
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for &count in &self.counts[1..] {
+      code |= bits.take(1)? as i32;
+      let count = count as i32;
+      if code - first < count {
+        return Ok(self.symbols[(index + code - first) as usize]);
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    Err("an invalid deflate code".to_string())
+  }
+}
+
+/// Copies a stored block, which starts at the next byte.
+fn stored(bits: &mut Bits, output: &mut Vec<u8>) -> Result<(), String> {
+  bits.buffer = 0;
+  bits.count = 0;
+  let header = bits
+    .input
+    .get(bits.position..bits.position + 4)
+    .ok_or("the deflate stream ends early")?;
+  let len = u16::from_le_bytes([header[0], header[1]]);
+  if !len != u16::from_le_bytes([header[2], header[3]]) {
+    return Err("a stored deflate block has a corrupt length".to_string());
+  }
+  let start = bits.position + 4;
+  let block = bits
+    .input
+    .get(start..start + len as usize)
+    .ok_or("the deflate stream ends early")?;
+  output.extend_from_slice(block);
+  bits.position = start + len as usize;
+  Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+  let mut lengths = [0u8; 288];
+  lengths[..144].fill(8);
+  lengths[144..256].fill(9);
+  lengths[256..280].fill(7);
+  lengths[280..].fill(8);
+  let lengths = Huffman::new(&lengths).expect("the fixed code is complete");
+  let distances = Huffman::new(&[5; 30]).expect("the fixed code is complete");
+  (lengths, distances)
+}
+
+/// Reads the codes a dynamic block starts with.
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman), String> {
+  let literals = bits.take(5)? as usize + 257;
+  let distances = bits.take(5)? as usize + 1;
+  let code_lengths = bits.take(4)? as usize + 4;
+  if literals > 286 || distances > 30 {
+    return Err("a deflate block has too many codes".to_string());
+  }
+  let mut lengths = [0u8; 19];
+  for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+    lengths[symbol] = bits.take(3)? as u8;
+  }
+  let code = Huffman::new(&lengths)?;
+
+  let mut lengths = vec![0u8; literals + distances];
+  let mut index = 0;
+  while index < lengths.len() {
+    let symbol = code.decode(bits)?;
+    let (length, repeat) = match symbol {
+      0..=15 => (symbol as u8, 1),
+      16 if index == 0 => return Err("a deflate code repeats no length".to_string()),
+      16 => (lengths[index - 1], 3 + bits.take(2)? as usize),
+      17 => (0, 3 + bits.take(3)? as usize),
+      _ => (0, 11 + bits.take(7)? as usize),
+    };
+    let run = lengths
+      .get_mut(index..index + repeat)
+      .ok_or("a deflate code repeats past its end")?;
+    run.fill(length);
+    index += repeat;
+  }
+  if lengths[256] == 0 {
+    return Err("a deflate block has no end code".to_string());
+  }
+  Ok((
+    Huffman::new(&lengths[..literals])?,
+    Huffman::new(&lengths[literals..])?,
+  ))
+}
+
+/// Decodes the literals and back-references of a block until its end code.
+fn codes(
+  bits: &mut Bits,
+  output: &mut Vec<u8>,
+  lengths: &Huffman,
+  distances: &Huffman,
+) -> Result<(), String> {
+  loop {
+    let symbol = lengths.decode(bits)? as usize;
+    match symbol {
+      0..=255 => output.push(symbol as u8),
+      256 => return Ok(()),
+      _ => {
+        let symbol = symbol - 257;
+        if symbol >= LENGTH_BASE.len() {
+          return Err("an invalid deflate length".to_string());
+        }
+        let len = LENGTH_BASE[symbol] as usize + bits.take(LENGTH_EXTRA[symbol] as u32)? as usize;
+        let symbol = distances.decode(bits)? as usize;
+        if symbol >= DISTANCE_BASE.len() {
+          return Err("an invalid deflate distance".to_string());
+        }
+        let distance =
+          DISTANCE_BASE[symbol] as usize + bits.take(DISTANCE_EXTRA[symbol] as u32)? as usize;
+        if distance > output.len() {
+          return Err("a deflate distance reaches before the start".to_string());
+        }
+        let start = output.len() - distance;
+        for i in 0..len {
+          output.push(output[start + i]);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,187 @@
+//! The zip container of an npz file: writing it with every entry stored, and reading entries
+//! stored or deflated, as numpy writes them. Archives of 4 GiB or more, which need the zip64
+//! records at the end of the file, are not supported.
+
+use std::io::{self, Write};
+
+use super::inflate::inflate;
+use crate::crc::crc32;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_SIGNATURE: u32 = 0x06054b50;
+
+const LOCAL_HEADER_BYTES: usize = 30;
+const CENTRAL_HEADER_BYTES: usize = 46;
+const END_BYTES: usize = 22;
+
+/// The version of the zip format needed to read what is written here.
+const VERSION: u16 = 20;
+
+/// Compression methods.
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// 1980-01-01, the earliest date a zip entry can have, in MS-DOS format.
+const DOS_EPOCH: u16 = (1 << 5) | 1;
+
+/// Id of the extra field holding the sizes and offset of an entry that overflow 32 bits.
+const ZIP64_EXTRA: u16 = 1;
+
+/// Writes a zip archive of `entries`, each a name and its contents, all stored.
+pub(super) fn write(mut writer: impl Write, entries: &[(&str, Vec<u8>)]) -> io::Result<()> {
+  let too_large = || {
+    io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "npz files of 4 GiB or more are not supported",
+    )
+  };
+  let mut central = Vec::new();
+  let mut offset = 0u32;
+  for (name, contents) in entries {
+    let crc = crc32([&contents[..]]);
+    let size = u32::try_from(contents.len()).map_err(|_| too_large())?;
+    let mut header = Vec::with_capacity(LOCAL_HEADER_BYTES + name.len());
+    header.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+    for field in [VERSION, 0, STORED, 0, DOS_EPOCH] {
+      header.extend(field.to_le_bytes());
+    }
+    for field in [crc, size, size] {
+      header.extend(field.to_le_bytes());
+    }
+    header.extend((name.len() as u16).to_le_bytes());
+    header.extend(0u16.to_le_bytes());
+    header.extend(name.as_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(contents)?;
+
+    central.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+    for field in [VERSION, VERSION, 0, STORED, 0, DOS_EPOCH] {
+      central.extend(field.to_le_bytes());
+    }
+    for field in [crc, size, size] {
+      central.extend(field.to_le_bytes());
+    }
+    for field in [name.len() as u16, 0, 0, 0, 0] {
+      central.extend(field.to_le_bytes());
+    }
+    central.extend(0u32.to_le_bytes());
+    central.extend(offset.to_le_bytes());
+    central.extend(name.as_bytes());
+    offset = (header.len() as u64 + size as u64)
+      .checked_add(offset as u64)
+      .and_then(|end| u32::try_from(end).ok())
+      .ok_or_else(too_large)?;
+  }
+  writer.write_all(&central)?;
+
+  let mut end = Vec::with_capacity(END_BYTES);
+  end.extend(END_SIGNATURE.to_le_bytes());
+  let count = entries.len() as u16;
+  for field in [0, 0, count, count] {
+    end.extend(field.to_le_bytes());
+  }
+  end.extend((central.len() as u32).to_le_bytes());
+  end.extend(offset.to_le_bytes());
+  end.extend(0u16.to_le_bytes());
+  writer.write_all(&end)
+}
+
+/// Reads the entries of the zip archive `bytes`, each a name and its contents.
+pub(super) fn read(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+  // The end record is last, unless a comment of up to 64 KiB follows it.
+  let end = (0..=bytes.len().saturating_sub(END_BYTES))
+    .rev()
+    .take(u16::MAX as usize + 1)
+    .find(|&at| u32_at(bytes, at) == Some(END_SIGNATURE))
+    .ok_or("not a zip archive")?;
+  let count = u16_at(bytes, end + 10).ok_or("truncated")? as usize;
+  let central_offset = u32_at(bytes, end + 16).ok_or("truncated")?;
+  if count == u16::MAX as usize || central_offset == u32::MAX {
+    return Err("zip64 archives are not supported".to_string());
+  }
+
+  let mut at = central_offset as usize;
+  let mut entries = Vec::with_capacity(count);
+  for _ in 0..count {
+    if u32_at(bytes, at) != Some(CENTRAL_HEADER_SIGNATURE) {
+      return Err("a corrupt central directory".to_string());
+    }
+    let field = |offset| u16_at(bytes, at + offset).ok_or("truncated");
+    let (flags, method) = (field(8)?, field(10)?);
+    let (name_len, extra_len, comment_len) = (field(28)?, field(30)?, field(32)?);
+    let wide = |offset| u32_at(bytes, at + offset).ok_or("truncated");
+    let (crc, mut compressed, mut size) = (wide(16)?, wide(20)? as u64, wide(24)? as u64);
+    let mut local = wide(42)? as u64;
+    let name_start = at + CENTRAL_HEADER_BYTES;
+    let name = bytes
+      .get(name_start..name_start + name_len as usize)
+      .ok_or("truncated")?;
+    let name = String::from_utf8_lossy(name).into_owned();
+    let extra_start = name_start + name_len as usize;
+    let extra = bytes
+      .get(extra_start..extra_start + extra_len as usize)
+      .ok_or("truncated")?;
+    // The zip64 field holds, in order, only those of these that overflowed.
+    if let Some(mut zip64) = extra_field(extra, ZIP64_EXTRA) {
+      for value in [&mut size, &mut compressed, &mut local] {
+        if *value == u32::MAX as u64 {
+          *value = u64::from_le_bytes(
+            zip64
+              .get(..8)
+              .and_then(|b| b.try_into().ok())
+              .ok_or("a corrupt zip64 field")?,
+          );
+          zip64 = &zip64[8..];
+        }
+      }
+    }
+    if flags & 1 != 0 {
+      return Err(format!("{name} is encrypted"));
+    }
+
+    let local = local as usize;
+    if u32_at(bytes, local) != Some(LOCAL_HEADER_SIGNATURE) {
+      return Err(format!("{name} has a corrupt header"));
+    }
+    let local_name = u16_at(bytes, local + 26).ok_or("truncated")? as usize;
+    let local_extra = u16_at(bytes, local + 28).ok_or("truncated")? as usize;
+    let start = local + LOCAL_HEADER_BYTES + local_name + local_extra;
+    let data = usize::try_from(compressed)
+      .ok()
+      .and_then(|compressed| bytes.get(start..start.checked_add(compressed)?))
+      .ok_or_else(|| format!("{name} is truncated"))?;
+    let size = usize::try_from(size).map_err(|_| format!("{name} is too large"))?;
+    let contents = match method {
+      STORED => data.to_vec(),
+      DEFLATED => inflate(data, size).map_err(|message| format!("{name}: {message}"))?,
+      method => return Err(format!("{name} uses compression method {method}")),
+    };
+    if contents.len() != size || crc32([&contents[..]]) != crc {
+      return Err(format!("{name} is corrupt"));
+    }
+    entries.push((name, contents));
+    at = extra_start + extra_len as usize + comment_len as usize;
+  }
+  Ok(entries)
+}
+
+/// The data of the field `id` among the `extra` fields of an entry.
+fn extra_field(mut extra: &[u8], id: u16) -> Option<&[u8]> {
+  while let (Some(field), Some(len)) = (u16_at(extra, 0), u16_at(extra, 2)) {
+    let data = extra.get(4..4 + len as usize)?;
+    if field == id {
+      return Some(data);
+    }
+    extra = &extra[4 + len as usize..];
+  }
+  None
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+  Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+  Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
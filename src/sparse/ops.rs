@@ -0,0 +1,176 @@
+//! Arithmetic on matrices, for the random linear combinations of `A`, `B`, and `C` that
+//! batched verification works with. Unless asked to prune, these keep every entry of their
+//! inputs: an entry that becomes zero stays stored as an explicit zero.
+
+use std::ops::Range;
+
+use ff::PrimeField;
+use itertools::{Either, Itertools as _};
+use rayon::prelude::*;
+
+use super::{MatrixError, RowData, SparseMatrix};
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix `r A`, with every value multiplied by `r` in parallel. The entries are kept
+  /// even when `r` is zero; nothing is pruned.
+  pub fn scale(&self, r: F) -> Self {
+    let mut scaled = self.clone();
+    scaled.scale_mut(r);
+    scaled
+  }
+
+  /// Multiplies every value by `r` in place, in parallel, like [`SparseMatrix::scale`].
+  pub fn scale_mut(&mut self, r: F) {
+    self.data.par_iter_mut().for_each(|value| *value *= r);
+  }
+
+  /// Multiplies the values of row `row` by `r` in place, keeping its entries.
+  ///
+  /// # Panics
+  ///
+  /// If there is no row `row`.
+  pub fn scale_row(&mut self, row: usize, r: F) {
+    let range = self.row_positions(row);
+    for value in &mut self.data[range] {
+      *value *= r;
+    }
+  }
+
+  /// The sum `A + B` of matrices of the same shape, with the columns of each row sorted and
+  /// the entries at the same position, in either matrix, added up. With `prune`, the sums
+  /// that are zero are left out; otherwise they are kept as explicit zeros, as
+  /// [`SparseMatrix::scale`] keeps them.
+  ///
+  /// Rows are merged in parallel, in two passes: the first counts the entries of every row of
+  /// the sum to lay out the output, the second writes them in place. Rows whose columns are
+  /// not sorted are sorted on the fly, in both passes.
+  pub fn add(&self, other: &Self, prune: bool) -> Result<Self, MatrixError> {
+    if self.shape() != other.shape() {
+      return Err(MatrixError::ShapeMismatch {
+        left: self.shape(),
+        right: other.shape(),
+      });
+    }
+    let rows = self.num_rows();
+    let merged = |row| {
+      merge_sorted(self.sorted_row(row), other.sorted_row(row))
+        .filter(move |(_, value)| !(prune && bool::from(value.is_zero())))
+    };
+
+    let counts: Vec<usize> = (0..rows)
+      .into_par_iter()
+      .map(|row| merged(row).count())
+      .collect();
+    let mut indptr = Vec::with_capacity(rows + 1);
+    indptr.push(0);
+    for (row, count) in counts.iter().enumerate() {
+      indptr.push(indptr[row] + count);
+    }
+
+    let nnz = indptr[rows];
+    let mut data = vec![F::ZERO; nnz];
+    let mut indices = vec![0; nnz];
+    let mut shares = Vec::with_capacity(rows);
+    let (mut data_rest, mut indices_rest) = (&mut data[..], &mut indices[..]);
+    for &count in &counts {
+      let (data_share, rest) = std::mem::take(&mut data_rest).split_at_mut(count);
+      data_rest = rest;
+      let (index_share, rest) = std::mem::take(&mut indices_rest).split_at_mut(count);
+      indices_rest = rest;
+      shares.push((data_share, index_share));
+    }
+    shares
+      .into_par_iter()
+      .enumerate()
+      .for_each(|(row, (data_share, index_share))| {
+        for ((col, value), (data, index)) in merged(row).zip(data_share.iter_mut().zip(index_share))
+        {
+          (*data, *index) = (value, col);
+        }
+      });
+
+    Ok(Self::new_unchecked(data, indices, indptr, self.cols))
+  }
+
+  /// The product `(c_1 M_1 + c_2 M_2 + ...) z` of a linear combination of `matrices`, given
+  /// with their coefficients, without building the combination: each row of the product is
+  /// the sum of the dot products of the rows of the matrices with `z`, scaled by their
+  /// coefficients, computed in parallel over the rows. The matrices must all have the same
+  /// shape, and `z` an element for every column; no matrices give an empty product.
+  pub fn multiply_vec_combined(matrices: &[(&Self, F)], z: &[F]) -> Result<Vec<F>, MatrixError> {
+    let Some(&(first, _)) = matrices.first() else {
+      return Ok(Vec::new());
+    };
+    if let Some((other, _)) = matrices
+      .iter()
+      .find(|(matrix, _)| matrix.shape() != first.shape())
+    {
+      return Err(MatrixError::ShapeMismatch {
+        left: first.shape(),
+        right: other.shape(),
+      });
+    }
+    if z.len() != first.num_cols() {
+      return Err(MatrixError::VectorLength {
+        cols: first.num_cols(),
+        len: z.len(),
+      });
+    }
+
+    Ok(
+      (0..first.num_rows())
+        .into_par_iter()
+        .map(|row| {
+          matrices
+            .iter()
+            .map(|&(matrix, coefficient)| {
+              let ptrs = RowData([matrix.indptr[row], matrix.indptr[row + 1]]);
+              coefficient * matrix.row_view(&ptrs).dot(z)
+            })
+            .sum()
+        })
+        .collect(),
+    )
+  }
+
+  /// The entries of `row` as `(column, value)` pairs sorted by column, sorting a copy of the
+  /// row if it is not sorted already.
+  fn sorted_row(&self, row: usize) -> impl Iterator<Item = (usize, F)> + '_ {
+    let range = self.row_positions(row);
+    let entries = self.indices[range.clone()]
+      .iter()
+      .copied()
+      .zip(self.data[range.clone()].iter().copied());
+    if self.indices[range].is_sorted() {
+      Either::Left(entries)
+    } else {
+      let mut entries: Vec<_> = entries.collect();
+      entries.sort_unstable_by_key(|&(col, _)| col);
+      Either::Right(entries.into_iter())
+    }
+  }
+
+  /// The positions of the entries of `row` in `data` and `indices`.
+  ///
+  /// # Panics
+  ///
+  /// If there is no row `row`.
+  fn row_positions(&self, row: usize) -> Range<usize> {
+    let rows = self.num_rows();
+    assert!(row < rows, "row {row} is out of bounds of the {rows} rows");
+    self.indptr[row]..self.indptr[row + 1]
+  }
+}
+
+/// Merges two sequences of entries sorted by column into one, adding up the values of every
+/// column that appears more than once, in either.
+fn merge_sorted<F: PrimeField>(
+  a: impl Iterator<Item = (usize, F)>,
+  b: impl Iterator<Item = (usize, F)>,
+) -> impl Iterator<Item = (usize, F)> {
+  a.merge_by(b, |x, y| x.0 <= y.0)
+    .coalesce(|(col, value), (next_col, next)| match col == next_col {
+      true => Ok((col, value + next)),
+      false => Err(((col, value), (next_col, next))),
+    })
+}
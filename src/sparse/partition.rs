@@ -0,0 +1,160 @@
+//! How the parallel product splits the rows of a matrix between rayon tasks. Splitting by row
+//! count leaves some threads idle when a few rows carry most of the entries, as in arecibo's
+//! matrices, so by default the rows are cut into runs of about equal nonzeros instead. How
+//! long those runs are is a [`ChunkPolicy`], which `bench --tune` picks by timing a few.
+
+use std::fmt;
+
+use ff::PrimeField;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::SparseMatrix;
+
+/// Runs of rows the nonzero-balanced product cuts the matrix into per thread of the pool, so
+/// that rayon can still steal work from a thread whose runs turn out slow.
+pub const PARTITION_CHUNKS_PER_THREAD: usize = 4;
+
+/// How the parallel product splits the rows of a matrix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Partition {
+  /// Every row on its own, left to rayon to split by row count.
+  Rows,
+  /// Runs of rows with about equal numbers of nonzeros, each multiplied on one thread.
+  /// Every row counts as one more entry, so that long runs of empty rows are split too.
+  #[default]
+  Nnz,
+}
+
+/// How many rows each task of the parallel product multiplies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkPolicy {
+  /// Runs of about equal nonzeros, [`PARTITION_CHUNKS_PER_THREAD`] per thread of the pool.
+  #[default]
+  Auto,
+  /// Every row on its own, but rayon splits no task below this many rows.
+  Rows(usize),
+  /// Runs of about this many nonzeros, every row counting as one more.
+  Nnz(usize),
+}
+
+impl From<Partition> for ChunkPolicy {
+  fn from(partition: Partition) -> Self {
+    match partition {
+      Partition::Rows => ChunkPolicy::Rows(1),
+      Partition::Nnz => ChunkPolicy::Auto,
+    }
+  }
+}
+
+/// The policy as `bench --chunk-policy` takes it: `auto`, `rows=N`, or `nnz=N`.
+impl fmt::Display for ChunkPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ChunkPolicy::Auto => write!(f, "auto"),
+      ChunkPolicy::Rows(rows) => write!(f, "rows={rows}"),
+      ChunkPolicy::Nnz(nnz) => write!(f, "nnz={nnz}"),
+    }
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Multiply by a dense vector into `out`, which is cleared and resized to the number of
+  /// rows, splitting the rows with `partition`. Either way the product is the same.
+  pub fn multiply_vec_partitioned_into(
+    &self,
+    vector: &[F],
+    out: &mut Vec<F>,
+    partition: Partition,
+  ) {
+    self.multiply_vec_chunked_into(vector, out, partition.into());
+  }
+
+  /// Multiply by a dense vector into `out`, which is cleared and resized to the number of
+  /// rows, in tasks of the size `policy` gives. Whatever the policy, the product is the same.
+  pub fn multiply_vec_chunked_into(&self, vector: &[F], out: &mut Vec<F>, policy: ChunkPolicy) {
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+
+    self.multiply_vec_chunked_unchecked(vector, out, policy);
+  }
+
+  /// [`SparseMatrix::multiply_vec_chunked_into`] without checking the shapes.
+  pub(super) fn multiply_vec_chunked_unchecked(
+    &self,
+    vector: &[F],
+    out: &mut Vec<F>,
+    policy: ChunkPolicy,
+  ) {
+    match policy {
+      ChunkPolicy::Auto => {
+        let chunks = rayon::current_num_threads() * PARTITION_CHUNKS_PER_THREAD;
+        self.multiply_vec_balanced_into(vector, out, chunks)
+      }
+      ChunkPolicy::Rows(rows) => self
+        .rows()
+        .with_min_len(rows.max(1))
+        .map(|row| row.dot(vector))
+        .collect_into_vec(out),
+      ChunkPolicy::Nnz(nnz) => {
+        let work = self.nnz() + self.num_rows();
+        self.multiply_vec_balanced_into(vector, out, work.div_ceil(nnz.max(1)))
+      }
+    }
+  }
+
+  /// The first row of each of `chunks` runs of rows with about equal numbers of nonzeros,
+  /// each row counting as one more, followed by the number of rows. `indptr` is already the
+  /// running count of nonzeros, so each boundary is a binary search in it. Runs are never
+  /// empty, and a row with more entries than a run should have makes its run that much
+  /// longer, so there may be fewer than `chunks`.
+  pub fn nnz_partition(&self, chunks: usize) -> Vec<usize> {
+    let rows = self.num_rows();
+    if rows == 0 {
+      return vec![0, 0];
+    }
+    let work = |row: usize| self.indptr[row] + row;
+    let chunks = chunks.clamp(1, rows);
+    let mut boundaries = vec![0];
+    for k in 1..chunks {
+      let target = work(rows) * k / chunks;
+      // The first row at which at least `target` of the work is done; `work` is increasing.
+      let (mut low, mut high) = (0, rows);
+      while low < high {
+        let mid = low + (high - low) / 2;
+        if work(mid) < target {
+          low = mid + 1;
+        } else {
+          high = mid;
+        }
+      }
+      let row = low;
+      if row > *boundaries.last().unwrap() {
+        boundaries.push(row);
+      }
+    }
+    if rows > *boundaries.last().unwrap() {
+      boundaries.push(rows);
+    }
+    boundaries
+  }
+
+  /// The product over the [`SparseMatrix::nnz_partition`] into `chunks` runs, into slices of
+  /// `out` cut along the same boundaries, one run of rows at a time per task.
+  fn multiply_vec_balanced_into(&self, vector: &[F], out: &mut Vec<F>, chunks: usize) {
+    out.clear();
+    out.resize(self.num_rows(), F::ZERO);
+    let boundaries = self.nnz_partition(chunks);
+
+    let mut runs = Vec::with_capacity(boundaries.len() - 1);
+    let mut rest = &mut out[..];
+    for rows in boundaries.windows(2) {
+      let (run, tail) = rest.split_at_mut(rows[1] - rows[0]);
+      runs.push((rows[0]..rows[1], run));
+      rest = tail;
+    }
+    runs
+      .into_par_iter()
+      .for_each(|(rows, run)| self.multiply_rows_into(rows, vector, run));
+  }
+}
@@ -0,0 +1,36 @@
+//! The parallel operations of a [`SparseMatrix`] on a caller's thread pool rather than rayon's
+//! global one, for embedding in a prover that already runs its own. Every parallel operation
+//! runs in whichever pool it is called from, so these are [`ThreadPool::install`] around it.
+
+use ff::PrimeField;
+use rayon::ThreadPool;
+
+use super::{MatrixError, SparseMatrix};
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Runs `op` on the matrix in `pool`, so that every parallel operation it makes uses the
+  /// threads of `pool`. The methods below are this around a single operation.
+  pub fn in_pool<R: Send>(&self, pool: &ThreadPool, op: impl FnOnce(&Self) -> R + Send) -> R {
+    pool.install(|| op(self))
+  }
+
+  /// [`SparseMatrix::multiply_vec`] on the threads of `pool`.
+  pub fn multiply_vec_in_pool(&self, vector: &[F], pool: &ThreadPool) -> Vec<F> {
+    self.in_pool(pool, |matrix| matrix.multiply_vec(vector))
+  }
+
+  /// [`SparseMatrix::multiply_vec_into`] on the threads of `pool`.
+  pub fn multiply_vec_into_in_pool(&self, vector: &[F], out: &mut Vec<F>, pool: &ThreadPool) {
+    self.in_pool(pool, |matrix| matrix.multiply_vec_into(vector, out))
+  }
+
+  /// [`Clone::clone`] on the threads of `pool`.
+  pub fn clone_in_pool(&self, pool: &ThreadPool) -> Self {
+    self.in_pool(pool, Self::clone)
+  }
+
+  /// [`SparseMatrix::validate`] on the threads of `pool`.
+  pub fn validate_in_pool(&self, pool: &ThreadPool) -> Result<(), MatrixError> {
+    self.in_pool(pool, Self::validate)
+  }
+}
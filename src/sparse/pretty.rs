@@ -0,0 +1,98 @@
+//! Rendering a matrix as a grid of its elements, for test failures and diffs of small
+//! matrices. Only a corner of at most [`MAX_PRETTY_ROWS`] by [`MAX_PRETTY_COLS`] elements is
+//! ever drawn, however large the matrix and whatever bounds are asked for, so that printing a
+//! real dump by accident costs a few kilobytes.
+
+use std::fmt;
+
+use ff::PrimeField;
+
+use super::SparseMatrix;
+use crate::hex::field_to_short_hex;
+
+/// Rows and columns the [`Display`](fmt::Display) of a [`SparseMatrix`] draws.
+pub const DEFAULT_PRETTY_ROWS: usize = 16;
+pub const DEFAULT_PRETTY_COLS: usize = 16;
+
+/// Most rows and columns [`SparseMatrix::pretty`] draws, whatever it is asked for.
+pub const MAX_PRETTY_ROWS: usize = 64;
+pub const MAX_PRETTY_COLS: usize = 32;
+
+/// A matrix drawn as a grid, from [`SparseMatrix::pretty`].
+#[derive(Debug, Clone, Copy)]
+pub struct Pretty<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+  max_rows: usize,
+  max_cols: usize,
+}
+
+impl<F: PrimeField> Pretty<'_, F> {
+  /// Whether every element is drawn, with nothing cut off.
+  pub fn is_complete(&self) -> bool {
+    self.matrix.num_rows() <= self.max_rows && self.matrix.num_cols() <= self.max_cols
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix drawn as a grid under a header of its shape and number of entries: the first
+  /// `max_rows` rows and `max_cols` columns, each bound capped at [`MAX_PRETTY_ROWS`] and
+  /// [`MAX_PRETTY_COLS`], with `…` and `⋮` where columns and rows are cut off. Elements are in
+  /// short hex, entries at the same position added up, and `·` is a position with no entry.
+  pub fn pretty(&self, max_rows: usize, max_cols: usize) -> Pretty<'_, F> {
+    Pretty {
+      matrix: self,
+      max_rows: max_rows.min(MAX_PRETTY_ROWS),
+      max_cols: max_cols.min(MAX_PRETTY_COLS),
+    }
+  }
+}
+
+impl<F: PrimeField> fmt::Display for Pretty<'_, F> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (rows, cols) = self.matrix.shape();
+    write!(f, "{rows}x{cols} matrix, {} entries", self.matrix.nnz())?;
+    let (shown_rows, shown_cols) = (rows.min(self.max_rows), cols.min(self.max_cols));
+
+    let mut cells: Vec<Option<F>> = vec![None; shown_rows * shown_cols];
+    for row in 0..shown_rows {
+      for (col, value) in self.matrix.row(row).into_iter().flatten() {
+        if col < shown_cols {
+          *cells[row * shown_cols + col].get_or_insert(F::ZERO) += value;
+        }
+      }
+    }
+    let cells: Vec<String> = cells
+      .iter()
+      .map(|cell| cell.as_ref().map_or("·".to_string(), field_to_short_hex))
+      .collect();
+    let width = cells
+      .iter()
+      .map(|cell| cell.chars().count())
+      .max()
+      .unwrap_or(1);
+
+    for row in 0..shown_rows {
+      let mut line: Vec<_> = cells[row * shown_cols..(row + 1) * shown_cols]
+        .iter()
+        .map(|cell| format!("{cell:>width$}"))
+        .collect();
+      if shown_cols < cols {
+        line.push("…".to_string());
+      }
+      write!(f, "\n{}", line.join(" "))?;
+    }
+    if shown_rows < rows {
+      let line = vec![format!("{:>width$}", "⋮"); shown_cols.max(1)];
+      write!(f, "\n{}", line.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+/// The matrix drawn as by [`SparseMatrix::pretty`], with [`DEFAULT_PRETTY_ROWS`] and
+/// [`DEFAULT_PRETTY_COLS`].
+impl<F: PrimeField> fmt::Display for SparseMatrix<F> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.pretty(DEFAULT_PRETTY_ROWS, DEFAULT_PRETTY_COLS).fmt(f)
+  }
+}
@@ -0,0 +1,95 @@
+//! Dropping the entries stored as zero. Some toolchains write explicit zeros into the CSR
+//! arrays, which every product reads and multiplies for nothing.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// Rows of each task of [`SparseMatrix::prune`].
+const PRUNE_CHUNK_ROWS: usize = 4096;
+
+fn is_zero<F: PrimeField>(value: &F) -> bool {
+  bool::from(value.is_zero())
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Number of stored entries equal to zero, in parallel.
+  pub fn explicit_zeros(&self) -> usize {
+    self.data.par_iter().filter(|value| is_zero(*value)).count()
+  }
+
+  /// The matrix without its explicit zeros, with the order of the other entries of each row
+  /// kept. Built in parallel: the entries each row keeps are counted, the counts summed into
+  /// the new `indptr`, and every chunk of rows then copies its entries into its own share of
+  /// the new arrays.
+  pub fn prune(&self) -> Self {
+    let kept: Vec<usize> = self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        self.data[ptrs[0]..ptrs[1]]
+          .iter()
+          .filter(|value| !is_zero(*value))
+          .count()
+      })
+      .collect();
+    let mut indptr = Vec::with_capacity(kept.len() + 1);
+    indptr.push(0);
+    for count in kept {
+      indptr.push(indptr[indptr.len() - 1] + count);
+    }
+
+    let nnz = indptr[indptr.len() - 1];
+    let mut data = vec![F::ZERO; nnz];
+    let mut indices = vec![0; nnz];
+    let starts: Vec<usize> = (0..self.num_rows())
+      .step_by(PRUNE_CHUNK_ROWS)
+      .chain([self.num_rows()])
+      .collect();
+    let mut shares = Vec::with_capacity(starts.len());
+    let (mut data_rest, mut indices_rest) = (&mut data[..], &mut indices[..]);
+    for rows in starts.windows(2) {
+      let len = indptr[rows[1]] - indptr[rows[0]];
+      let (data_share, rest) = std::mem::take(&mut data_rest).split_at_mut(len);
+      data_rest = rest;
+      let (index_share, rest) = std::mem::take(&mut indices_rest).split_at_mut(len);
+      indices_rest = rest;
+      shares.push((rows[0]..rows[1], data_share, index_share));
+    }
+    shares
+      .into_par_iter()
+      .for_each(|(rows, data_share, index_share)| {
+        let entries =
+          (self.indptr[rows.start]..self.indptr[rows.end]).filter(|&k| !is_zero(&self.data[k]));
+        for ((value, col), k) in data_share.iter_mut().zip(index_share).zip(entries) {
+          *value = self.data[k];
+          *col = self.indices[k];
+        }
+      });
+    SparseMatrix::new_unchecked(data, indices, indptr, self.num_cols())
+  }
+
+  /// Drops the explicit zeros in place, without allocating, as [`SparseMatrix::prune`] would,
+  /// and returns how many there were.
+  pub fn prune_in_place(&mut self) -> usize {
+    let mut kept = 0;
+    let mut start = 0;
+    for row in 0..self.num_rows() {
+      let end = self.indptr[row + 1];
+      for k in start..end {
+        if !is_zero(&self.data[k]) {
+          self.data[kept] = self.data[k];
+          self.indices[kept] = self.indices[k];
+          kept += 1;
+        }
+      }
+      start = end;
+      self.indptr[row + 1] = kept;
+    }
+    let dropped = self.data.len() - kept;
+    self.data.truncate(kept);
+    self.indices.truncate(kept);
+    dropped
+  }
+}
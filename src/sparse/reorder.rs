@@ -0,0 +1,97 @@
+//! Permuting the rows and columns of a matrix, to apply a known re-indexing such as that of the
+//! variables between two versions of a circuit, or to put rows reading nearby columns next to
+//! each other, so that the entries of the vector they share are still in cache for the next
+//! row. The product of a matrix with its rows permuted is the original product permuted the
+//! same way, which [`crate::vec_ops::unpermute_vec`] puts back.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+use crate::vec_ops::is_permutation;
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix whose row `i` is row `perm[i]` of this one, with the same columns.
+  ///
+  /// # Panics
+  ///
+  /// If `perm` is not a permutation of the rows.
+  pub fn permute_rows(&self, perm: &[usize]) -> Self {
+    assert!(is_permutation(perm, self.num_rows()), "invalid permutation");
+
+    let mut data = Vec::with_capacity(self.nnz());
+    let mut indices = Vec::with_capacity(self.nnz());
+    let mut indptr = Vec::with_capacity(self.num_rows() + 1);
+    indptr.push(0);
+    for &row in perm {
+      let entries = self.indptr[row]..self.indptr[row + 1];
+      data.extend_from_slice(&self.data[entries.clone()]);
+      indices.extend_from_slice(&self.indices[entries]);
+      indptr.push(indices.len());
+    }
+    SparseMatrix::new_unchecked(data, indices, indptr, self.num_cols())
+  }
+
+  /// The matrix whose column `j` is column `perm[j]` of this one, with the same rows and the
+  /// columns of each row sorted. Its product with [`crate::vec_ops::permute_vec`] of a vector
+  /// by `perm` is the product of this one with the vector.
+  ///
+  /// # Panics
+  ///
+  /// If `perm` is not a permutation of the columns.
+  pub fn permute_cols(&self, perm: &[usize]) -> Self {
+    assert!(is_permutation(perm, self.num_cols()), "invalid permutation");
+
+    let mut moved_to = vec![0; self.num_cols()];
+    for (j, &col) in perm.iter().enumerate() {
+      moved_to[col] = j;
+    }
+    let rows: Vec<Vec<(usize, F)>> = self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        let mut row: Vec<_> = (ptrs[0]..ptrs[1])
+          .map(|k| (moved_to[self.indices[k]], self.data[k]))
+          .collect();
+        row.sort_by_key(|&(col, _)| col);
+        row
+      })
+      .collect();
+    let mut matrix = SparseMatrix::new_unchecked(
+      Vec::with_capacity(self.nnz()),
+      Vec::with_capacity(self.nnz()),
+      Vec::with_capacity(self.num_rows() + 1),
+      self.num_cols(),
+    );
+    matrix.indptr.push(0);
+    for row in rows {
+      for (col, value) in row {
+        matrix.indices.push(col);
+        matrix.data.push(value);
+      }
+      matrix.indptr.push(matrix.indices.len());
+    }
+    matrix
+  }
+
+  /// A permutation of the rows for [`SparseMatrix::permute_rows`] that sorts them by their
+  /// first column, so that rows starting on the same part of the vector are multiplied
+  /// together. Rows with the same first column keep their order, and empty rows go last.
+  /// A cheap stand-in for reverse Cuthill–McKee, which walks the graph of rows sharing columns.
+  pub fn locality_ordering(&self) -> Vec<usize> {
+    let first_cols: Vec<usize> = self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        self.indices[ptrs[0]..ptrs[1]]
+          .iter()
+          .copied()
+          .min()
+          .unwrap_or(usize::MAX)
+      })
+      .collect();
+    let mut perm: Vec<usize> = (0..self.num_rows()).collect();
+    perm.par_sort_by_key(|&row| first_cols[row]);
+    perm
+  }
+}
@@ -0,0 +1,81 @@
+//! Safe views of the rows of a matrix, the one place the slices of a row are cut out of
+//! `indptr`. The parallel product is a dot product of every row with the vector.
+
+use std::ops::Range;
+
+use ff::PrimeField;
+use itertools::Itertools as _;
+use rayon::prelude::*;
+use ref_cast::RefCast;
+
+use super::{RowData, SparseMatrix};
+
+/// A row of a [`SparseMatrix`], from [`SparseMatrix::rows`].
+#[derive(Debug, Clone, Copy)]
+pub struct RowView<'a, F: PrimeField> {
+  values: &'a [F],
+  cols: &'a [usize],
+}
+
+impl<'a, F: PrimeField> RowView<'a, F> {
+  /// Number of stored entries in the row.
+  pub fn nnz(&self) -> usize {
+    self.values.len()
+  }
+
+  /// The entries of the row as `(column, value)` pairs, in the order they are stored.
+  pub fn iter(&self) -> impl ExactSizeIterator<Item = (usize, &'a F)> + 'a {
+    self.cols.iter().copied().zip_eq(self.values)
+  }
+
+  /// The dot product of the row with `v`, which is indexed by column.
+  ///
+  /// # Panics
+  ///
+  /// If a column of the row is out of bounds of `v`.
+  pub fn dot(&self, v: &[F]) -> F {
+    self.iter().map(|(col, value)| *value * v[col]).sum()
+  }
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Views of the rows, in parallel.
+  pub fn rows(&self) -> impl IndexedParallelIterator<Item = RowView<'_, F>> {
+    self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| self.row_view(RowData::ref_cast(ptrs.try_into().unwrap())))
+  }
+
+  /// Multiply the rows in `rows` by a dense vector on the current thread, writing the product
+  /// of each into `out`, with the same row dot product as [`SparseMatrix::multiply_vec`]. A
+  /// chunk of rows at a time can be timed this way, as `bench --profile-rows` does.
+  ///
+  /// # Panics
+  ///
+  /// If the shapes do not match, `rows` is out of bounds, or `out` is not as long as `rows`.
+  pub fn multiply_rows_into(&self, rows: Range<usize>, vector: &[F], out: &mut [F]) {
+    assert_eq!(self.num_cols(), vector.len(), "invalid shape");
+    assert_eq!(rows.len(), out.len(), "invalid output length");
+    if rows.is_empty() {
+      // A matrix whose `indptr` was never filled in has no rows to slice.
+      return;
+    }
+
+    let ptrs = &self.indptr[rows.start..=rows.end];
+    for (ptrs, out) in ptrs.windows(2).zip(out) {
+      *out = self
+        .row_view(RowData::ref_cast(ptrs.try_into().unwrap()))
+        .dot(vector);
+    }
+  }
+
+  /// The view of the row delimited by `ptrs`, which is assumed to come from `indptr`.
+  pub(super) fn row_view(&self, ptrs: &RowData) -> RowView<'_, F> {
+    let [start, end] = ptrs.0;
+    RowView {
+      values: &self.data[start..end],
+      cols: &self.indices[start..end],
+    }
+  }
+}
@@ -0,0 +1,64 @@
+//! The product with a vector given by its nonzero entries. Witnesses padded with zeros are
+//! mostly zero, and looking up the few entries a row meets skips the multiplications by zero
+//! the dense kernel spends on the rest.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// Density, the share of the entries of a vector that are nonzero, below which
+/// [`SparseMatrix::multiply_sparse_vec`] looks up each entry of a row in the sparse vector;
+/// at or above it the vector is made dense again and multiplied as usual.
+pub const SPARSE_VEC_MAX_DENSITY: f64 = 0.1;
+
+/// The nonzero entries of `z` with their indices, in increasing order of index, as
+/// [`SparseMatrix::multiply_sparse_vec`] takes them.
+pub fn sparsify<F: PrimeField>(z: &[F]) -> Vec<(usize, F)> {
+  z.iter()
+    .enumerate()
+    .filter(|(_, value)| !bool::from(value.is_zero()))
+    .map(|(i, value)| (i, *value))
+    .collect()
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Multiply by the vector of `num_cols` entries whose nonzero ones are `entries`, as from
+  /// [`sparsify`]; uses rayon to parallelize over rows. Below [`SPARSE_VEC_MAX_DENSITY`] each
+  /// entry of a row is looked up in `entries` by binary search, and only those found are
+  /// multiplied; otherwise this is [`SparseMatrix::multiply_vec`] of the dense vector. Either
+  /// way the product is that of the dense vector.
+  ///
+  /// # Panics
+  ///
+  /// If the indices of `entries` are not increasing or not below the number of columns.
+  pub fn multiply_sparse_vec(&self, entries: &[(usize, F)]) -> Vec<F> {
+    let increasing = entries.windows(2).all(|pair| pair[0].0 < pair[1].0);
+    let in_bounds = entries.last().is_none_or(|&(i, _)| i < self.num_cols());
+    assert!(increasing && in_bounds, "invalid sparse vector");
+
+    let density = entries.len() as f64 / self.num_cols().max(1) as f64;
+    if density >= SPARSE_VEC_MAX_DENSITY {
+      let mut dense = vec![F::ZERO; self.num_cols()];
+      for &(i, value) in entries {
+        dense[i] = value;
+      }
+      return self.multiply_vec(&dense);
+    }
+
+    self
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        let mut sum = F::ZERO;
+        for k in ptrs[0]..ptrs[1] {
+          let col = self.indices[k];
+          if let Ok(found) = entries.binary_search_by_key(&col, |&(i, _)| i) {
+            sum += self.data[k] * entries[found].1;
+          }
+        }
+        sum
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,166 @@
+//! Conversions between [`SparseMatrix`] and [`sprs::CsMat`], so that the kernels here can be
+//! cross-checked against sprs and its algorithms run on a dump, and the sprs product that
+//! `bench --backend sprs` times.
+//!
+//! sprs keeps the columns of each row sorted, each at most once, which a [`SparseMatrix`] need
+//! not: converting one sorts the entries of every row and adds up those in the same column. A
+//! [`CsMat`] converts back as it is, with its shape, so empty rows, trailing ones included,
+//! survive the round trip.
+
+use std::{
+  ops::{Add, AddAssign, Mul},
+  slice,
+};
+
+use ff::PrimeField;
+use sprs::{prod::mul_acc_mat_vec_csr, CompressedStorage, CsMat, CsMatI, CsMatViewI, SpIndex};
+
+use super::SparseMatrix;
+
+/// Why a [`CsMat`] does not convert to a [`SparseMatrix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SprsError {
+  /// The matrix is stored by columns.
+  #[error("the matrix is stored by columns (CSC), not by rows")]
+  NotCsr,
+  /// An index of the matrix does not fit in a `usize`.
+  #[error("the {0} of the matrix do not fit in a usize")]
+  Overflow(&'static str),
+}
+
+impl<F: PrimeField> From<&SparseMatrix<F>> for CsMat<F> {
+  /// Copies `matrix`, sorting the columns of each row and adding up the entries in the same
+  /// column.
+  ///
+  /// # Panics
+  ///
+  /// If a column index is not below `matrix.cols`.
+  fn from(matrix: &SparseMatrix<F>) -> Self {
+    let mut indptr = Vec::with_capacity(matrix.indptr.len().max(1));
+    let mut indices = Vec::with_capacity(matrix.indices.len());
+    let mut data = Vec::with_capacity(matrix.data.len());
+    let mut row = Vec::new();
+    indptr.push(0);
+    for ptrs in matrix.indptr.windows(2) {
+      let range = ptrs[0]..ptrs[1];
+      row.clear();
+      row.extend(
+        matrix.indices[range.clone()]
+          .iter()
+          .zip(&matrix.data[range]),
+      );
+      row.sort_by_key(|&(&col, _)| col);
+      let start = indices.len();
+      for (&col, &value) in &row {
+        if indices.len() > start && indices[indices.len() - 1] == col {
+          *data.last_mut().unwrap() += value;
+        } else {
+          indices.push(col);
+          data.push(value);
+        }
+      }
+      indptr.push(indices.len());
+    }
+    CsMat::new((indptr.len() - 1, matrix.cols), indptr, indices, data)
+  }
+}
+
+impl<F: PrimeField, I: SpIndex, Iptr: SpIndex> TryFrom<CsMatI<F, I, Iptr>> for SparseMatrix<F> {
+  type Error = SprsError;
+
+  /// Takes the arrays of `matrix` as they are, failing if it is stored by columns or its
+  /// indices do not fit in a `usize`.
+  fn try_from(matrix: CsMatI<F, I, Iptr>) -> Result<Self, SprsError> {
+    if matrix.storage() != CompressedStorage::CSR {
+      return Err(SprsError::NotCsr);
+    }
+    let cols = matrix.cols();
+    let indptr = matrix
+      .indptr()
+      .to_proper()
+      .iter()
+      .map(|ptr| ptr.try_index())
+      .collect::<Option<Vec<_>>>()
+      .ok_or(SprsError::Overflow("row offsets"))?;
+    let (_, indices, data) = matrix.into_raw_storage();
+    let indices = indices
+      .into_iter()
+      .map(I::try_index)
+      .collect::<Option<Vec<_>>>()
+      .ok_or(SprsError::Overflow("column indices"))?;
+    Ok(SparseMatrix {
+      data,
+      indices,
+      indptr,
+      cols,
+    })
+  }
+}
+
+/// A field element with the arithmetic traits sprs asks of the scalars of its products.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(transparent)]
+struct Scalar<F>(F);
+
+impl<F: PrimeField> Add for Scalar<F> {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self {
+    Scalar(self.0 + other.0)
+  }
+}
+
+impl<F: PrimeField> AddAssign for Scalar<F> {
+  fn add_assign(&mut self, other: Self) {
+    self.0 += other.0;
+  }
+}
+
+impl<'a, F: PrimeField> Mul<&'a Scalar<F>> for &'a Scalar<F> {
+  type Output = Scalar<F>;
+
+  fn mul(self, other: &'a Scalar<F>) -> Scalar<F> {
+    Scalar(self.0 * other.0)
+  }
+}
+
+impl<F: PrimeField> num_traits::Zero for Scalar<F> {
+  fn zero() -> Self {
+    Scalar(F::ZERO)
+  }
+
+  fn is_zero(&self) -> bool {
+    self.0.is_zero_vartime()
+  }
+}
+
+/// Views `elements` as [`Scalar`]s, without copying them.
+fn as_scalars<F>(elements: &[F]) -> &[Scalar<F>] {
+  // SAFETY: `Scalar<F>` is a `#[repr(transparent)]` wrapper of `F`, so a slice of one is laid
+  // out as a slice of the other, and the borrow of `elements` outlives the view.
+  unsafe { slice::from_raw_parts(elements.as_ptr().cast::<Scalar<F>>(), elements.len()) }
+}
+
+/// The product of `matrix` with `vector`, computed by sprs on the calling thread; the
+/// matrix and vector are viewed in place rather than copied.
+///
+/// # Panics
+///
+/// If `vector` is not as long as `matrix` has columns, or `matrix` is stored by columns.
+pub fn sprs_multiply_vec<F: PrimeField>(matrix: &CsMat<F>, vector: &[F]) -> Vec<F> {
+  assert_eq!(matrix.cols(), vector.len(), "invalid shape");
+  let indptr = matrix.indptr();
+  // SAFETY: these are the arrays of `matrix`, whose structure sprs checked when it was built.
+  let view = unsafe {
+    CsMatViewI::new_unchecked(
+      matrix.storage(),
+      matrix.shape(),
+      indptr.raw_storage(),
+      matrix.indices(),
+      as_scalars(matrix.data()),
+    )
+  };
+  let mut product = vec![Scalar(F::ZERO); matrix.rows()];
+  mul_acc_mat_vec_csr(view, as_scalars(vector), &mut product[..]);
+  product.into_iter().map(|Scalar(element)| element).collect()
+}
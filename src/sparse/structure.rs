@@ -0,0 +1,81 @@
+//! Structural queries: the main diagonal, whether a matrix is triangular, and whether its
+//! pattern of entries is symmetric, which tell at a glance whether a matrix still has the shape
+//! it is expected to after a change to the circuit.
+
+use std::borrow::Cow;
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// The distinct columns of a row, sorted, borrowed if they already are.
+fn pattern(cols: &[usize]) -> Cow<'_, [usize]> {
+  if cols.windows(2).all(|pair| pair[0] < pair[1]) {
+    return Cow::Borrowed(cols);
+  }
+  let mut sorted = cols.to_vec();
+  sorted.sort_unstable();
+  sorted.dedup();
+  Cow::Owned(sorted)
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The elements at `(i, i)` for `i` below the smaller of the rows and columns, zero where
+  /// no entry is stored and the sum where several are, in parallel over the rows.
+  pub fn diagonal(&self) -> Vec<F> {
+    let len = self.num_rows().min(self.num_cols());
+    (0..len)
+      .into_par_iter()
+      .map(|i| {
+        let ptrs = [self.indptr[i], self.indptr[i + 1]];
+        self
+          .get_row_unchecked(&ptrs)
+          .filter(|&(_, &col)| col == i)
+          .map(|(value, _)| *value)
+          .sum()
+      })
+      .collect()
+  }
+
+  /// Whether no entry is stored above the main diagonal, in parallel over the rows. Explicit
+  /// zeros count as entries.
+  pub fn is_lower_triangular(&self) -> bool {
+    self.all_entries(|row, col| col <= row)
+  }
+
+  /// Whether no entry is stored below the main diagonal, in parallel over the rows. Explicit
+  /// zeros count as entries.
+  pub fn is_upper_triangular(&self) -> bool {
+    self.all_entries(|row, col| col >= row)
+  }
+
+  /// Whether the matrix is square and an entry is stored at `(j, i)` whenever one is at
+  /// `(i, j)`, whatever their values. Each row is compared in parallel against the same row
+  /// of [`SparseMatrix::transpose`], so this holds a second copy of the matrix while it runs,
+  /// as much memory again as [`SparseMatrix::memory_footprint`]; a hash set of the rows of
+  /// every column would take several times that.
+  pub fn symmetric_pattern(&self) -> bool {
+    if self.num_rows() != self.num_cols() {
+      return false;
+    }
+    let transpose = self.transpose();
+    self
+      .indptr
+      .par_windows(2)
+      .zip(transpose.indptr.par_windows(2))
+      .all(|(ptrs, transposed)| {
+        pattern(&self.indices[ptrs[0]..ptrs[1]])
+          == pattern(&transpose.indices[transposed[0]..transposed[1]])
+      })
+  }
+
+  /// Whether `holds(row, col)` for every stored entry, in parallel over the rows.
+  fn all_entries(&self, holds: impl Fn(usize, usize) -> bool + Sync) -> bool {
+    self.indptr.par_windows(2).enumerate().all(|(row, ptrs)| {
+      self.indices[ptrs[0]..ptrs[1]]
+        .iter()
+        .all(|&col| holds(row, col))
+    })
+  }
+}
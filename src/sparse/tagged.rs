@@ -0,0 +1,115 @@
+//! The product with the coefficients of 1 and −1 picked out beforehand. Circuit compilers
+//! emit mostly those, and adding or subtracting the entry of the vector saves the field
+//! multiplication the plain kernel spends on them.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// The class of an entry of a [`TaggedMatrix`], which decides how it is multiplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Coefficient {
+  /// 1, so the entry of the vector is added.
+  One,
+  /// −1, so the entry of the vector is subtracted.
+  MinusOne,
+  /// Anything else, so the entry of the vector is multiplied by it.
+  Other,
+}
+
+impl Coefficient {
+  /// The class of `value`.
+  pub fn of<F: PrimeField>(value: &F) -> Self {
+    if *value == F::ONE {
+      Coefficient::One
+    } else if *value == -F::ONE {
+      Coefficient::MinusOne
+    } else {
+      Coefficient::Other
+    }
+  }
+}
+
+/// A [`SparseMatrix`] with the [`Coefficient`] of each of its entries, a byte per entry, from
+/// [`SparseMatrix::tag_coefficients`].
+#[derive(Debug, Clone)]
+pub struct TaggedMatrix<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+  tags: Vec<Coefficient>,
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// Tags every entry with its [`Coefficient`], in parallel, for
+  /// [`TaggedMatrix::multiply_vec`].
+  pub fn tag_coefficients(&self) -> TaggedMatrix<'_, F> {
+    TaggedMatrix {
+      matrix: self,
+      tags: self.data.par_iter().map(Coefficient::of).collect(),
+    }
+  }
+
+  /// Number of entries equal to 1 and to −1, in that order, in parallel.
+  pub fn count_unit_coefficients(&self) -> (usize, usize) {
+    self
+      .data
+      .par_iter()
+      .map(|value| match Coefficient::of(value) {
+        Coefficient::One => (1, 0),
+        Coefficient::MinusOne => (0, 1),
+        Coefficient::Other => (0, 0),
+      })
+      .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+  }
+}
+
+impl<'a, F: PrimeField> TaggedMatrix<'a, F> {
+  /// The matrix that was tagged.
+  pub fn matrix(&self) -> &'a SparseMatrix<F> {
+    self.matrix
+  }
+
+  /// The class of every entry, in the order of the entries.
+  pub fn tags(&self) -> &[Coefficient] {
+    &self.tags
+  }
+
+  /// Number of entries of class `class`.
+  pub fn count(&self, class: Coefficient) -> usize {
+    self.tags.par_iter().filter(|&&tag| tag == class).count()
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize over rows, and multiplies only the
+  /// entries tagged [`Coefficient::Other`]. The product is that of
+  /// [`SparseMatrix::multiply_vec`].
+  pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
+    let mut out = Vec::with_capacity(self.matrix.num_rows());
+    self.multiply_vec_into(vector, &mut out);
+    out
+  }
+
+  /// [`TaggedMatrix::multiply_vec`] into `out`, which is cleared and resized to the number of
+  /// rows.
+  pub fn multiply_vec_into(&self, vector: &[F], out: &mut Vec<F>) {
+    let matrix = self.matrix;
+    assert_eq!(matrix.num_cols(), vector.len(), "invalid shape");
+
+    matrix
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        let mut sum = F::ZERO;
+        for k in ptrs[0]..ptrs[1] {
+          let x = vector[matrix.indices[k]];
+          match self.tags[k] {
+            Coefficient::One => sum += x,
+            Coefficient::MinusOne => sum -= x,
+            Coefficient::Other => sum += matrix.data[k] * x,
+          }
+        }
+        sum
+      })
+      .collect_into_vec(out);
+  }
+}
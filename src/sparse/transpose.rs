@@ -0,0 +1,137 @@
+//! Transposing a matrix in parallel, by the two passes of a counting sort on the columns, and
+//! multiplying by the transpose without building it.
+//!
+//! The rows are split into blocks, one per thread. The first pass counts the entries of every
+//! block in every column, which gives where each column starts in the transpose, and within
+//! it where each block's share starts. The second pass scatters every block into its shares,
+//! row by row, so the rows within each column come out sorted. Safe code cannot write through
+//! one slice from several threads, so the output is split up front into the share of every
+//! block in every column; the blocks are made few enough that these shares take no more memory
+//! than the entries.
+
+use std::ops::Range;
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+use super::SparseMatrix;
+
+/// The shares of one block of rows in the values and indices of every column of a transpose.
+type Shares<'a, F> = (Vec<&'a mut [F]>, Vec<&'a mut [usize]>);
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The transpose `A^T`, with the columns of each row sorted, computed in parallel. Entries
+  /// at the same position, if any, stay separate.
+  pub fn transpose(&self) -> Self {
+    let rows = self.num_rows();
+    let blocks = rayon::current_num_threads()
+      .min(self.nnz() / self.cols.max(1))
+      .max(1);
+    let starts: Vec<_> = (0..=blocks).map(|block| rows * block / blocks).collect();
+
+    let counts: Vec<Vec<usize>> = starts
+      .par_windows(2)
+      .map(|range| {
+        let mut counts = vec![0; self.cols];
+        for &col in &self.indices[self.indptr[range[0]]..self.indptr[range[1]]] {
+          counts[col] += 1;
+        }
+        counts
+      })
+      .collect();
+    let mut indptr = Vec::with_capacity(self.cols + 1);
+    indptr.push(0);
+    for col in 0..self.cols {
+      let count: usize = counts.iter().map(|counts| counts[col]).sum();
+      indptr.push(indptr[col] + count);
+    }
+
+    let mut data = vec![F::ZERO; self.nnz()];
+    let mut indices = vec![0; self.nnz()];
+    let mut shares: Vec<Shares<F>> = (0..blocks)
+      .map(|_| (Vec::with_capacity(self.cols), Vec::with_capacity(self.cols)))
+      .collect();
+    let (mut data_rest, mut indices_rest) = (&mut data[..], &mut indices[..]);
+    for col in 0..self.cols {
+      for (counts, (data_shares, index_shares)) in counts.iter().zip(&mut shares) {
+        let (share, rest) = std::mem::take(&mut data_rest).split_at_mut(counts[col]);
+        data_shares.push(share);
+        data_rest = rest;
+        let (share, rest) = std::mem::take(&mut indices_rest).split_at_mut(counts[col]);
+        index_shares.push(share);
+        indices_rest = rest;
+      }
+    }
+
+    shares.into_par_iter().zip(starts.par_windows(2)).for_each(
+      |((mut data_shares, mut index_shares), range)| {
+        for row in range[0]..range[1] {
+          let ptrs = [self.indptr[row], self.indptr[row + 1]];
+          for (value, &col) in self.get_row_unchecked(&ptrs) {
+            push(&mut data_shares[col], *value);
+            push(&mut index_shares[col], row);
+          }
+        }
+      },
+    );
+
+    Self::new_unchecked(data, indices, indptr, rows)
+  }
+
+  /// Multiply the transpose by a dense vector, computing `A^T y` from the rows without
+  /// building `A^T`: each thread scatters a block of rows into its own accumulator of length
+  /// `cols`, and the accumulators are added up pairwise in parallel. This takes one
+  /// accumulator per thread rather than a copy of the matrix, as [`SparseMatrix::transpose`]
+  /// and then [`SparseMatrix::multiply_vec`] would.
+  pub fn multiply_vec_transposed(&self, y: &[F]) -> Vec<F> {
+    let rows = self.num_rows();
+    assert_eq!(rows, y.len(), "invalid shape");
+
+    scatter_blocks(rows, self.cols, |rows, output| {
+      for row in rows {
+        let ptrs = [self.indptr[row], self.indptr[row + 1]];
+        for (value, &col) in self.get_row_unchecked(&ptrs) {
+          output[col] += *value * y[row];
+        }
+      }
+    })
+  }
+}
+
+/// Splits `0..len` into one block per thread, has `scatter` add the products of each block
+/// into its own partial output of length `output_len`, and adds up the partials pairwise in
+/// parallel.
+pub(super) fn scatter_blocks<F: PrimeField>(
+  len: usize,
+  output_len: usize,
+  scatter: impl Fn(Range<usize>, &mut [F]) + Sync,
+) -> Vec<F> {
+  let blocks = rayon::current_num_threads().min(len).max(1);
+  (0..blocks)
+    .into_par_iter()
+    .map(|block| {
+      let mut partial = vec![F::ZERO; output_len];
+      scatter(
+        len * block / blocks..len * (block + 1) / blocks,
+        &mut partial,
+      );
+      partial
+    })
+    .reduce_with(|mut sum, partial| {
+      sum
+        .par_iter_mut()
+        .zip(partial)
+        .for_each(|(sum, value)| *sum += value);
+      sum
+    })
+    .expect("there is at least one block")
+}
+
+/// Writes `value` to the first element of `share`, and moves `share` past it.
+fn push<T>(share: &mut &mut [T], value: T) {
+  let (first, rest) = std::mem::take(share)
+    .split_first_mut()
+    .expect("the counts leave room for every entry");
+  *first = value;
+  *share = rest;
+}
@@ -0,0 +1,217 @@
+//! The invariants of a CSR matrix, and constructing matrices from their raw parts with them
+//! checked. A matrix found to keep them is multiplied without bounds checks on the vector, so
+//! that kernel lives here, next to the check it relies on.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+use thiserror::Error;
+
+use super::SparseMatrix;
+
+/// An invariant of [`SparseMatrix`] that its parts break, and where.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MatrixError {
+  /// `data` and `indices` differ in length.
+  #[error("{data} values but {indices} column indices")]
+  LengthMismatch { data: usize, indices: usize },
+  /// `indptr` has no entries, so not even the end of the last row.
+  #[error("indptr is empty")]
+  EmptyIndptr,
+  /// `indptr` does not start at 0.
+  #[error("indptr starts at {start}, not 0")]
+  IndptrStart { start: usize },
+  /// `indptr` does not end at the length of `data`.
+  #[error("indptr ends at {end} but there are {nnz} values")]
+  IndptrEnd { end: usize, nnz: usize },
+  /// Row `row` ends past the end of `data`; only a [`CheckedSparseMatrix`] being deserialized
+  /// reports this, before it has read the rest of `indptr`.
+  ///
+  /// [`CheckedSparseMatrix`]: super::CheckedSparseMatrix
+  #[error("row {row} ends at {end}, past the {nnz} values")]
+  IndptrPastEnd { row: usize, end: usize, nnz: usize },
+  /// Row `row` ends before it starts.
+  #[error("indptr decreases at row {row}, from {start} to {end}")]
+  IndptrDecreasing {
+    row: usize,
+    start: usize,
+    end: usize,
+  },
+  /// Two matrices combined entry by entry have different shapes.
+  #[error("a {}x{} matrix cannot be combined with a {}x{} one", .left.0, .left.1, .right.0, .right.1)]
+  ShapeMismatch {
+    left: (usize, usize),
+    right: (usize, usize),
+  },
+  /// The left matrix of a product has a different number of columns than the right one has
+  /// rows.
+  #[error("a {}x{} matrix cannot multiply a {}x{} one", .left.0, .left.1, .right.0, .right.1)]
+  ProductShape {
+    left: (usize, usize),
+    right: (usize, usize),
+  },
+  /// A product would store more entries than it is allowed to.
+  #[error("the product would have {nnz} entries, over the limit of {limit}")]
+  TooManyEntries { nnz: usize, limit: usize },
+  /// A vector multiplied by a matrix does not have an element for every column.
+  #[error("a matrix of {cols} columns cannot multiply a vector of {len} elements")]
+  VectorLength { cols: usize, len: usize },
+  /// The entry at `position` of `data` and `indices`, in row `row`, has a column out of bounds.
+  #[error(
+    "row {row}: column {col} of the entry at {position} is out of bounds of the {cols} columns"
+  )]
+  IndexOutOfBounds {
+    row: usize,
+    position: usize,
+    col: usize,
+    cols: usize,
+  },
+  /// The entry at `position`, in row `row`, has a column that does not come after that of the
+  /// entry before it, so the row is not canonical.
+  #[error(
+    "row {row}: column {col} of the entry at {position} does not come after column {previous}"
+  )]
+  NotCanonical {
+    row: usize,
+    position: usize,
+    col: usize,
+    previous: usize,
+  },
+}
+
+impl<F: PrimeField> SparseMatrix<F> {
+  /// The matrix of the given parts, checking every invariant: `data` and `indices` are as
+  /// long as each other, `indptr` starts at 0, never decreases, and ends at the length of
+  /// `data`, and every column index is below `cols`. The columns of a row need not be sorted.
+  pub fn try_new(
+    data: Vec<F>,
+    indices: Vec<usize>,
+    indptr: Vec<usize>,
+    cols: usize,
+  ) -> Result<Self, MatrixError> {
+    let matrix = Self::new_unchecked(data, indices, indptr, cols);
+    matrix.validate()?;
+    Ok(matrix)
+  }
+
+  /// Checks the invariants [`SparseMatrix::try_new`] does, in parallel over the rows, as for a
+  /// matrix that was deserialized, which only checks [`SparseMatrix::check_lengths`]. The
+  /// first broken invariant is reported: a decreasing `indptr` before any column index.
+  pub fn validate(&self) -> Result<(), MatrixError> {
+    self.check_lengths()?;
+    if self.indptr[0] != 0 {
+      return Err(MatrixError::IndptrStart {
+        start: self.indptr[0],
+      });
+    }
+    let decreasing = self
+      .indptr
+      .par_windows(2)
+      .position_first(|ptrs| ptrs[0] > ptrs[1]);
+    if let Some(row) = decreasing {
+      return Err(MatrixError::IndptrDecreasing {
+        row,
+        start: self.indptr[row],
+        end: self.indptr[row + 1],
+      });
+    }
+    let out_of_bounds = self
+      .indptr
+      .par_windows(2)
+      .enumerate()
+      .find_map_first(|(row, ptrs)| {
+        let position = (ptrs[0]..ptrs[1]).find(|&k| self.indices[k] >= self.cols)?;
+        Some(MatrixError::IndexOutOfBounds {
+          row,
+          position,
+          col: self.indices[position],
+          cols: self.cols,
+        })
+      });
+    match out_of_bounds {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+
+  /// Checks the invariants, as [`SparseMatrix::validate`], and returns the matrix for
+  /// [`ValidatedMatrix::multiply_vec`] if it keeps them.
+  pub fn validated(&self) -> Result<ValidatedMatrix<'_, F>, MatrixError> {
+    self.validate()?;
+    Ok(ValidatedMatrix { matrix: self })
+  }
+
+  /// The matrix of the given parts, trusted to keep the invariants [`SparseMatrix::try_new`]
+  /// checks. Nothing is unsafe if they are broken, but products may panic or be wrong.
+  pub fn new_unchecked(data: Vec<F>, indices: Vec<usize>, indptr: Vec<usize>, cols: usize) -> Self {
+    Self {
+      data,
+      indices,
+      indptr,
+      cols,
+    }
+  }
+
+  /// Checks, in constant time, that the arrays agree on the number of entries: `indices` is as
+  /// long as `data`, and `indptr` is non-empty and ends at the length of `data`. This catches
+  /// truncated and mismatched files, not every inconsistency; the rows are not checked.
+  pub fn check_lengths(&self) -> Result<(), MatrixError> {
+    if self.indices.len() != self.data.len() {
+      return Err(MatrixError::LengthMismatch {
+        data: self.data.len(),
+        indices: self.indices.len(),
+      });
+    }
+    match self.indptr.last() {
+      None => Err(MatrixError::EmptyIndptr),
+      Some(&end) if end != self.data.len() => Err(MatrixError::IndptrEnd {
+        end,
+        nnz: self.data.len(),
+      }),
+      Some(_) => Ok(()),
+    }
+  }
+}
+
+/// A [`SparseMatrix`] that [`SparseMatrix::validated`] checked, so that every column index is
+/// below `cols`. It is borrowed for as long as this lives, so it cannot change in the meantime.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidatedMatrix<'a, F: PrimeField> {
+  matrix: &'a SparseMatrix<F>,
+}
+
+impl<'a, F: PrimeField> ValidatedMatrix<'a, F> {
+  /// The matrix that was validated.
+  pub fn matrix(&self) -> &'a SparseMatrix<F> {
+    self.matrix
+  }
+
+  /// Multiply by a dense vector; uses rayon to parallelize over rows, as
+  /// [`SparseMatrix::multiply_vec`], without checking that each column index is in bounds of
+  /// `vector`. The product is that of [`SparseMatrix::multiply_vec`].
+  pub fn multiply_vec(&self, vector: &[F]) -> Vec<F> {
+    let mut out = Vec::with_capacity(self.matrix.num_rows());
+    self.multiply_vec_into(vector, &mut out);
+    out
+  }
+
+  /// [`ValidatedMatrix::multiply_vec`] into `out`, which is cleared and resized to the number
+  /// of rows.
+  pub fn multiply_vec_into(&self, vector: &[F], out: &mut Vec<F>) {
+    let matrix = self.matrix;
+    assert_eq!(matrix.num_cols(), vector.len(), "invalid shape");
+
+    matrix
+      .indptr
+      .par_windows(2)
+      .map(|ptrs| {
+        let row = ptrs[0]..ptrs[1];
+        let entries = matrix.data[row.clone()].iter().zip(&matrix.indices[row]);
+        entries.fold(F::ZERO, |sum, (value, &col)| {
+          // SAFETY: `validated` checked that every column index is below `cols`, which is the
+          // length of `vector`, and `matrix` has been borrowed immutably ever since.
+          sum + *value * unsafe { vector.get_unchecked(col) }
+        })
+      })
+      .collect_into_vec(out);
+  }
+}
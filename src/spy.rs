@@ -0,0 +1,95 @@
+//! # Spy Plots
+//!
+//! Renders where the entries of a [`SparseMatrix`] are as a grayscale image, so that its
+//! structure, banded or scattered, can be seen at a glance. The matrix is divided into a grid
+//! of at most the requested size, one cell per entry for matrices smaller than it, and each
+//! cell is shaded by how many entries fall in it: white for none, then darker the more there
+//! are, with every occupied cell at least light gray so that a lone entry still shows.
+//!
+//! Images are written as 8-bit grayscale PNG by the `image` crate.
+
+use std::io::{self, Write};
+
+use ff::PrimeField;
+use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder, ImageError};
+use rayon::prelude::*;
+
+use crate::SparseMatrix;
+
+/// Width and height of the grid a spy plot is drawn on, unless another is given.
+pub const DEFAULT_SPY_SIZE: usize = 1024;
+
+/// The lightest shade of an occupied cell, so that it stands out from an empty one.
+const LIGHTEST_OCCUPIED: u8 = 223;
+
+/// How many entries of a matrix fall in each cell of a grid laid over it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occupancy {
+    pub width: usize,
+    pub height: usize,
+    /// The counts of the cells, a row of the grid at a time.
+    pub counts: Vec<u32>,
+}
+
+impl Occupancy {
+    /// Counts the entries of `matrix` in each cell of a grid of at most `width` by `height` cells,
+    /// with each thread counting a share of the entries into a grid of its own, and the grids
+    /// added up at the end. Row `r` of the matrix falls in grid row `r * height / rows`, and
+    /// column `c` in grid column `c * width / cols`.
+    pub fn new<F: PrimeField>(matrix: &SparseMatrix<F>, width: usize, height: usize) -> Self {
+        let (rows, cols) = matrix.shape();
+        let (width, height) = (width.min(cols).max(1), height.min(rows).max(1));
+        let cell = |index: usize, len: usize, cells: usize| {
+            (index as u128 * cells as u128 / len as u128) as usize
+        };
+        // Every task holds a whole grid, so there are no more tasks than threads.
+        let share = matrix.nnz().div_ceil(rayon::current_num_threads()).max(1);
+        let counts = matrix
+            .par_iter()
+            .with_min_len(share)
+            .fold(
+                || vec![0u32; width * height],
+                |mut counts, (row, col, _)| {
+                    let index = cell(row, rows, height) * width + cell(col, cols, width);
+                    counts[index] = counts[index].saturating_add(1);
+                    counts
+                },
+            )
+            .reduce_with(|mut sum, counts| {
+                for (sum, count) in sum.iter_mut().zip(counts) {
+                    *sum = sum.saturating_add(count);
+                }
+                sum
+            })
+            .unwrap_or_else(|| vec![0; width * height]);
+        Self {
+            width,
+            height,
+            counts,
+        }
+    }
+
+    /// The shade of each cell, 255 for an empty one down to 0 for the fullest.
+    pub fn pixels(&self) -> Vec<u8> {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1) as u64;
+        self.counts
+            .iter()
+            .map(|&count| match count {
+                0 => u8::MAX,
+                count => LIGHTEST_OCCUPIED - (count as u64 * LIGHTEST_OCCUPIED as u64 / max) as u8,
+            })
+            .collect()
+    }
+
+    /// Writes the image to `writer` as an 8-bit grayscale PNG.
+    pub fn write_png(&self, mut writer: impl Write) -> io::Result<()> {
+        let (width, height) = (self.width as u32, self.height as u32);
+        PngEncoder::new(&mut writer)
+            .write_image(&self.pixels(), width, height, ExtendedColorType::L8)
+            .map_err(|err| match err {
+                ImageError::IoError(err) => err,
+                err => io::Error::other(err),
+            })?;
+        writer.flush()
+    }
+}
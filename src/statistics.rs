@@ -0,0 +1,256 @@
+//! # Statistics
+//!
+//! Summary statistics over repeated [`Duration`] samples, used to tell real
+//! kernel changes apart from run-to-run noise, and over the structure of a
+//! [`SparseMatrix`].
+
+use std::{fmt, time::Duration};
+
+use ff::PrimeField;
+use rayon::{prelude::*, ThreadPool};
+use serde::Serialize;
+
+use crate::{
+    sparse::{EllShape, DEFAULT_ELL_WIDTH},
+    SparseMatrix,
+};
+
+/// Mean, median, standard deviation, and range of a set of duration samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Sample standard deviation; zero for a single sample.
+    pub stddev: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Summary {
+    /// Summarizes `samples`, or returns `None` when there are none.
+    pub fn from_durations(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let count = sorted.len();
+        let mid = count / 2;
+        let median = if count.is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2
+        } else {
+            sorted[mid]
+        };
+
+        let nanos = sorted.iter().map(|d| d.as_nanos() as f64);
+        let mean = nanos.clone().sum::<f64>() / count as f64;
+        let variance = if count > 1 {
+            nanos.map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            count,
+            mean: Duration::from_nanos(mean.round() as u64),
+            median,
+            stddev: Duration::from_nanos(variance.sqrt().round() as u64),
+            min: sorted[0],
+            max: sorted[count - 1],
+        })
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean {:?}, median {:?}, stddev {:?}, min {:?}, max {:?} ({} runs)",
+            self.mean, self.median, self.stddev, self.min, self.max, self.count
+        )
+    }
+}
+
+/// Share of the rows, the densest ones, whose nonzeros [`RowDistribution::top_share`] counts:
+/// 0.1%, and at least one row.
+pub const TOP_ROWS_PER_MILLE: usize = 1;
+
+/// Rows of a [`SparseMatrix`] with between `min` and `max` nonzeros, both included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RowBucket {
+    pub min: usize,
+    pub max: usize,
+    pub rows: usize,
+}
+
+impl fmt::Display for RowBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.min == self.max {
+            true => write!(f, "{}: {}", self.min, self.rows),
+            false => write!(f, "{}-{}: {}", self.min, self.max, self.rows),
+        }
+    }
+}
+
+/// How the nonzeros are spread over the rows, which tells how evenly rows split by count
+/// share the work of a product between threads.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowDistribution {
+    /// Rows by their nonzeros in log-scaled buckets, `0`, `1`, `2`, `3-4`, `5-8`, and so on,
+    /// up to the bucket of the densest row.
+    pub histogram: Vec<RowBucket>,
+    /// Gini coefficient of the nonzeros of the rows: 0 when every row has as many, and close
+    /// to 1 when a few rows hold them all.
+    pub imbalance: f64,
+    /// Number of densest rows that [`RowDistribution::top_share`] counts, see
+    /// [`TOP_ROWS_PER_MILLE`].
+    pub top_rows: usize,
+    /// Fraction of the nonzeros held by the `top_rows` densest rows.
+    pub top_share: f64,
+}
+
+/// Index in [`RowDistribution::histogram`] of the bucket of a row with `nnz` nonzeros.
+fn row_bucket(nnz: usize) -> usize {
+    match nnz {
+        0 => 0,
+        nnz => 1 + (usize::BITS - (nnz - 1).leading_zeros()) as usize,
+    }
+}
+
+impl RowDistribution {
+    /// The distribution of `counts`, the nonzeros of every row, sorted in increasing order.
+    fn from_sorted(counts: &[usize]) -> Self {
+        let mut histogram: Vec<RowBucket> = Vec::new();
+        for &nnz in counts {
+            let bucket = row_bucket(nnz);
+            while histogram.len() <= bucket {
+                let (min, max) = match histogram.len() {
+                    0 => (0, 0),
+                    b => ((1 << (b - 1)) / 2 + 1, 1 << (b - 1)),
+                };
+                histogram.push(RowBucket { min, max, rows: 0 });
+            }
+            histogram[bucket].rows += 1;
+        }
+
+        let rows = counts.len();
+        let nnz: usize = counts.iter().sum();
+        // With the counts in increasing order, G = 2 Σ i x_i / (n Σ x_i) - (n + 1) / n.
+        let weighted: u128 = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (i as u128 + 1) * count as u128)
+            .sum();
+        let imbalance = match nnz {
+            0 => 0.0,
+            nnz => {
+                2.0 * weighted as f64 / (rows as f64 * nnz as f64)
+                    - (rows as f64 + 1.0) / rows as f64
+            }
+        };
+        let top_rows = match rows {
+            0 => 0,
+            rows => (rows * TOP_ROWS_PER_MILLE).div_ceil(1000),
+        };
+        let top: usize = counts[rows - top_rows..].iter().sum();
+        Self {
+            histogram,
+            imbalance,
+            top_rows,
+            top_share: match nnz {
+                0 => 0.0,
+                nnz => top as f64 / nnz as f64,
+            },
+        }
+    }
+}
+
+/// Shape, sparsity, and row balance of a [`SparseMatrix`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatrixStats {
+    pub rows: usize,
+    pub cols: usize,
+    pub nnz: usize,
+    /// Fraction of entries that are stored, `nnz / (rows * cols)`.
+    pub density: f64,
+    pub min_row_nnz: usize,
+    pub max_row_nnz: usize,
+    pub mean_row_nnz: f64,
+    pub empty_rows: usize,
+    /// The nonzeros per row, of which the four fields above are a summary.
+    pub row_distribution: RowDistribution,
+    /// Entries equal to 1, which `--backend tagged` adds rather than multiplies.
+    pub ones: usize,
+    /// Entries equal to −1, which `--backend tagged` subtracts rather than multiplies.
+    pub minus_ones: usize,
+    /// Entries stored as zero, which [`SparseMatrix::prune`] drops.
+    pub explicit_zeros: usize,
+    /// Coordinates stored more than once, see [`SparseMatrix::find_duplicates`].
+    pub duplicates: usize,
+    /// Nonzero elements of [`SparseMatrix::diagonal`].
+    pub diagonal_nonzeros: usize,
+    /// See [`SparseMatrix::is_lower_triangular`].
+    pub lower_triangular: bool,
+    /// See [`SparseMatrix::is_upper_triangular`].
+    pub upper_triangular: bool,
+    /// See [`SparseMatrix::symmetric_pattern`], which builds the transpose.
+    pub symmetric_pattern: bool,
+    /// Bytes held by the matrix, see [`SparseMatrix::memory_footprint`].
+    pub memory_bytes: usize,
+    /// How the matrix would split as an [`EllMatrix`](crate::sparse::EllMatrix) of at most
+    /// [`DEFAULT_ELL_WIDTH`] entries per row.
+    pub ell: EllShape,
+}
+
+impl MatrixStats {
+    pub fn new<F: PrimeField>(matrix: &SparseMatrix<F>) -> Self {
+        let (rows, cols) = matrix.shape();
+        // One pass over `indptr` counts the nonzeros of every row, and the rest is read off
+        // the sorted counts.
+        let mut counts: Vec<usize> = matrix.row_nnz_iter().collect();
+        counts.par_sort_unstable();
+        let row_distribution = RowDistribution::from_sorted(&counts);
+        let ratio = |numerator: usize, denominator: usize| {
+            if denominator == 0 {
+                0.0
+            } else {
+                numerator as f64 / denominator as f64
+            }
+        };
+
+        let (ones, minus_ones) = matrix.count_unit_coefficients();
+
+        Self {
+            rows,
+            cols,
+            nnz: matrix.nnz(),
+            density: ratio(matrix.nnz(), rows * cols),
+            min_row_nnz: counts.first().copied().unwrap_or(0),
+            max_row_nnz: counts.last().copied().unwrap_or(0),
+            mean_row_nnz: ratio(matrix.nnz(), rows),
+            empty_rows: counts.partition_point(|&nnz| nnz == 0),
+            row_distribution,
+            ones,
+            minus_ones,
+            explicit_zeros: matrix.explicit_zeros(),
+            duplicates: matrix.find_duplicates().len(),
+            diagonal_nonzeros: matrix
+                .diagonal()
+                .par_iter()
+                .filter(|value| !bool::from(value.is_zero()))
+                .count(),
+            lower_triangular: matrix.is_lower_triangular(),
+            upper_triangular: matrix.is_upper_triangular(),
+            symmetric_pattern: matrix.symmetric_pattern(),
+            memory_bytes: matrix.memory_footprint(),
+            ell: matrix.ell_shape(DEFAULT_ELL_WIDTH),
+        }
+    }
+
+    /// [`MatrixStats::new`] on the threads of `pool` rather than the current one.
+    pub fn new_in_pool<F: PrimeField>(matrix: &SparseMatrix<F>, pool: &ThreadPool) -> Self {
+        pool.install(|| Self::new(matrix))
+    }
+}
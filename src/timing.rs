@@ -0,0 +1,151 @@
+//! # Timing
+//!
+//! Wall-clock measurements of the multiplication kernels, and the console
+//! report built from them. Each [`Measurement`] covers exactly one operation,
+//! so reports can add them up without double counting. Operations repeated
+//! under the same label are reported as a [`Summary`], and operations that
+//! know how much [`Work`] they did are also reported as throughput.
+
+use std::{
+    fmt::Write as _,
+    time::{Duration, Instant},
+};
+
+use ff::PrimeField;
+
+use crate::{statistics::Summary, SparseMatrix};
+
+/// The amount of work done by one operation, used to turn durations into throughput.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Work {
+    /// Nonzeros processed.
+    pub nnz: usize,
+    /// Estimated bytes read and written.
+    pub bytes: usize,
+}
+
+impl Work {
+    /// The work of one `M z` product.
+    pub fn multiply_vec<F: PrimeField>(matrix: &SparseMatrix<F>) -> Self {
+        Self {
+            nnz: matrix.nnz(),
+            bytes: matrix.multiply_vec_traffic(),
+        }
+    }
+
+    /// Renders the throughput of doing this work in `duration`, in Mnnz/s and GB/s.
+    pub fn throughput(&self, duration: Duration) -> String {
+        let secs = duration.as_secs_f64();
+        format!(
+            "{:.2} Mnnz/s, {:.2} GB/s",
+            self.nnz as f64 / secs / 1e6,
+            self.bytes as f64 / secs / 1e9
+        )
+    }
+}
+
+/// The duration of a single timed operation, such as one `A z` product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    /// What was timed, e.g. `AZ`.
+    pub label: String,
+    pub duration: Duration,
+    /// What the operation did, if known.
+    pub work: Option<Work>,
+}
+
+impl Measurement {
+    pub fn new(label: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            label: label.into(),
+            duration,
+            work: None,
+        }
+    }
+
+    /// Attaches the amount of work done, so reports can show throughput.
+    pub fn with_work(self, work: Work) -> Self {
+        Self {
+            work: Some(work),
+            ..self
+        }
+    }
+
+    /// Runs `f` with a freshly started timer and records its duration under `label`.
+    pub fn time<T>(label: impl Into<String>, f: impl FnOnce() -> T) -> (T, Self) {
+        let start = Instant::now();
+        let value = f();
+        (value, Self::new(label, start.elapsed()))
+    }
+}
+
+/// Sums the durations of `measurements`.
+pub fn total(measurements: &[Measurement]) -> Duration {
+    measurements.iter().map(|m| m.duration).sum()
+}
+
+/// Groups the durations of `measurements` by label, in order of first appearance.
+pub fn group_by_label<'a>(
+    measurements: impl IntoIterator<Item = &'a Measurement>,
+) -> Vec<(&'a str, Vec<Duration>)> {
+    let mut groups: Vec<(&str, Vec<Duration>)> = Vec::new();
+    for m in measurements {
+        match groups.iter_mut().find(|(label, _)| *label == m.label) {
+            Some((_, durations)) => durations.push(m.duration),
+            None => groups.push((&m.label, vec![m.duration])),
+        }
+    }
+    groups
+}
+
+/// Renders the measurements taken for witness `index`, followed by their total.
+/// A label measured more than once is shown as a [`Summary`] of its runs,
+/// with throughput computed from the median.
+pub fn iteration_report(index: usize, measurements: &[Measurement]) -> String {
+    let mut out = format!("timing: {index}\n");
+    for (label, durations) in group_by_label(measurements) {
+        let work = measurements
+            .iter()
+            .find(|m| m.label == label)
+            .and_then(|m| m.work);
+        let typical = match durations[..] {
+            [duration] => {
+                write!(out, "{label} took: {duration:?}").unwrap();
+                duration
+            }
+            _ => {
+                let summary = Summary::from_durations(&durations).unwrap();
+                write!(out, "{label}: {summary}").unwrap();
+                summary.median
+            }
+        };
+        match work {
+            Some(work) => writeln!(out, " ({})", work.throughput(typical)).unwrap(),
+            None => writeln!(out).unwrap(),
+        }
+    }
+    writeln!(out, "total: {:?}", total(measurements)).unwrap();
+    out
+}
+
+/// Renders the grand total over the measurements of every witness.
+/// If any witness repeated a measurement, each label is also summarized across all witnesses.
+pub fn summary_report(iterations: &[Vec<Measurement>]) -> String {
+    let grand_total: Duration = iterations.iter().map(|m| total(m)).sum();
+    let mut out = format!(
+        "grand total over {} witnesses: {:?}\n",
+        iterations.len(),
+        grand_total
+    );
+
+    let repeated = iterations
+        .iter()
+        .any(|m| group_by_label(m).iter().any(|(_, d)| d.len() > 1));
+    if repeated {
+        for (label, durations) in group_by_label(iterations.iter().flatten()) {
+            let summary = Summary::from_durations(&durations).unwrap();
+            writeln!(out, "{label} overall: {summary}").unwrap();
+        }
+    }
+    out
+}
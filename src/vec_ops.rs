@@ -0,0 +1,112 @@
+//! # Vector Operations
+//!
+//! Element-wise arithmetic on the dense vectors that the products give, computed in
+//! parallel, for checking the relations those products are meant to satisfy.
+
+use ff::PrimeField;
+use rayon::prelude::*;
+
+/// The Hadamard product `a ∘ b`, multiplying the vectors element by element.
+///
+/// # Panics
+///
+/// If the vectors are not of the same length.
+pub fn hadamard<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    a.par_iter().zip(b).map(|(a, b)| *a * b).collect()
+}
+
+/// The vector `r v`, with every element multiplied by `r`.
+pub fn scale_vec<F: PrimeField>(r: F, v: &[F]) -> Vec<F> {
+    v.par_iter().map(|value| r * value).collect()
+}
+
+/// The sum `a + b`, element by element.
+///
+/// # Panics
+///
+/// If the vectors are not of the same length.
+pub fn add_vec<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    a.par_iter().zip(b).map(|(a, b)| *a + b).collect()
+}
+
+/// Replaces `a` by `a ∘ b`, without allocating.
+///
+/// # Panics
+///
+/// If the vectors are not of the same length.
+pub fn hadamard_assign<F: PrimeField>(a: &mut [F], b: &[F]) {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    a.par_iter_mut().zip(b).for_each(|(a, b)| *a *= b);
+}
+
+/// Replaces `a` by `a + b`, without allocating.
+///
+/// # Panics
+///
+/// If the vectors are not of the same length.
+pub fn add_vec_assign<F: PrimeField>(a: &mut [F], b: &[F]) {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    a.par_iter_mut().zip(b).for_each(|(a, b)| *a += b);
+}
+
+/// Replaces `a` by `a - r b`, without allocating.
+///
+/// # Panics
+///
+/// If the vectors are not of the same length.
+pub fn sub_scaled_vec_assign<F: PrimeField>(a: &mut [F], r: F, b: &[F]) {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    a.par_iter_mut().zip(b).for_each(|(a, b)| *a -= r * b);
+}
+
+/// Whether `perm` holds every index below `len` exactly once, as the permutations of
+/// [`permute_vec`] and [`crate::SparseMatrix::permute_rows`] must.
+pub fn is_permutation(perm: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    perm.len() == len
+        && perm
+            .iter()
+            .all(|&i| i < len && !std::mem::replace(&mut seen[i], true))
+}
+
+/// The vector whose element `i` is element `perm[i]` of `v`: the witness of
+/// [`crate::SparseMatrix::permute_cols`] with `perm`, or the product of
+/// [`crate::SparseMatrix::permute_rows`].
+///
+/// # Panics
+///
+/// If `perm` is not a permutation of the indices of `v`.
+pub fn permute_vec<F: PrimeField>(v: &[F], perm: &[usize]) -> Vec<F> {
+    assert!(is_permutation(perm, v.len()), "invalid permutation");
+    perm.par_iter().map(|&i| v[i]).collect()
+}
+
+/// The inverse of [`permute_vec`]: the vector whose element `perm[i]` is element `i` of `v`,
+/// which puts the product of a matrix permuted by [`crate::SparseMatrix::permute_rows`] back
+/// in the order of the original.
+///
+/// # Panics
+///
+/// If `perm` is not a permutation of the indices of `v`.
+pub fn unpermute_vec<F: PrimeField>(v: &[F], perm: &[usize]) -> Vec<F> {
+    let mut out = Vec::new();
+    unpermute_into(v, perm, &mut out);
+    out
+}
+
+/// [`unpermute_vec`] into `out`, reusing its buffer, so that putting the products of witness
+/// after witness back in order allocates nothing once it has grown to size.
+///
+/// # Panics
+///
+/// If `perm` is not a permutation of the indices of `v`.
+pub fn unpermute_into<F: PrimeField>(v: &[F], perm: &[usize], out: &mut Vec<F>) {
+    assert!(is_permutation(perm, v.len()), "invalid permutation");
+    out.clear();
+    out.resize(v.len(), F::ZERO);
+    for (value, &i) in v.iter().zip(perm) {
+        out[i] = *value;
+    }
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "rkyv")]
+#![allow(non_snake_case)]
+
+use camino::Utf8PathBuf;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    archive::{ArchivedRawMatrix, MappedMatrix, RawMatrix},
+    generate::{random_matrix, random_vector, Shape},
+    sparse::{multiply_view, CsrView},
+    DataConfig, DataError, SparseMatrix,
+};
+
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(7);
+    let shape = Shape {
+        rows: 40,
+        cols: 30,
+        nnz_per_row: 5,
+    };
+    random_matrix(&mut rng, shape)
+}
+
+#[test]
+fn archived_multiply_matches_bincode_multiply() {
+    let M = matrix();
+    let decoded: SparseMatrix<Fr> = bincode::deserialize(&bincode::serialize(&M).unwrap()).unwrap();
+    let bytes = RawMatrix::to_bytes(&M);
+    let view = ArchivedRawMatrix::access::<Fr>(&bytes).unwrap();
+    assert_eq!(CsrView::<Fr>::rows(view), 40);
+    assert_eq!(CsrView::<Fr>::nnz(view), 200);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(8);
+    for _ in 0..4 {
+        let z: Vec<Fr> = random_vector(&mut rng, 30);
+        assert_eq!(multiply_view::<Fr, _>(view, &z), decoded.multiply_vec(&z));
+        assert_eq!(multiply_view::<Fr, _>(view, &z), M.multiply_vec_serial(&z));
+    }
+}
+
+#[test]
+fn out_of_bounds_columns_are_rejected() {
+    let mut M = matrix();
+    M.indices[3] = M.cols;
+    let bytes = RawMatrix::to_bytes(&M);
+    assert!(ArchivedRawMatrix::access::<Fr>(&bytes).is_err());
+}
+
+#[test]
+fn archives_of_the_wrong_value_width_are_rejected() {
+    let M = matrix();
+    // Values as wide as the data claims, but not as wide as `Fr`.
+    for value_bytes in [0u32, 16, 64] {
+        let raw = RawMatrix {
+            data: vec![0; M.nnz() * value_bytes as usize],
+            value_bytes,
+            indices: M.indices.iter().map(|&i| i as u64).collect(),
+            indptr: M.indptr.iter().map(|&i| i as u64).collect(),
+            cols: M.cols as u64,
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&raw).unwrap();
+        let err = ArchivedRawMatrix::access::<Fr>(&bytes).err().unwrap();
+        assert!(err.to_string().contains("not 32"), "{err}");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("A_0.rkyv");
+        std::fs::write(&path, &bytes).unwrap();
+        let path = Utf8PathBuf::from_path_buf(path).unwrap();
+        let err = MappedMatrix::<Fr>::open(path).err().unwrap();
+        assert!(matches!(err, DataError::InvalidArchive { .. }), "{err:?}");
+    }
+}
+
+#[test]
+fn archives_round_trip_through_the_data_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DataConfig::new(Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap());
+    let M = matrix();
+    let path = config
+        .write_archive("sparse_matrices_abc", "A_0", &M)
+        .unwrap();
+    assert!(path.ends_with("sparse_matrices_abc/A_0.rkyv"));
+
+    let archive = config
+        .open_archive::<Fr>("sparse_matrices_abc", "A_0")
+        .unwrap();
+    let z: Vec<Fr> = random_vector(&mut ChaCha20Rng::seed_from_u64(9), 30);
+    assert_eq!(
+        multiply_view::<Fr, _>(archive.view(), &z),
+        M.multiply_vec(&z)
+    );
+
+    std::fs::write(&path, b"not an archive at all").unwrap();
+    let err = config
+        .open_archive::<Fr>("sparse_matrices_abc", "A_0")
+        .err()
+        .unwrap();
+    assert!(matches!(err, DataError::InvalidArchive { .. }), "{err:?}");
+}
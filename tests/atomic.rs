@@ -0,0 +1,138 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    time::SystemTime,
+};
+
+use common::{stderr, Fixture, HASH};
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+use spmvm_test_example::{
+    data::{
+        atomic::{atomic_write, ORPHAN_MIN_AGE},
+        matrices_section, ElementType, Manifest, Tagged,
+    },
+    DataError,
+};
+
+/// A vector that fails to serialize after a million elements, well past what is buffered
+/// before reaching the file.
+struct FailsMidway;
+
+impl Serialize for FailsMidway {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(2_000_000))?;
+        for i in 0..1_000_000u64 {
+            seq.serialize_element(&i)?;
+        }
+        Err(serde::ser::Error::custom("interrupted"))
+    }
+}
+
+impl Tagged for FailsMidway {
+    const ELEMENT_TYPE: ElementType = ElementType::Vector;
+}
+
+fn temp_files(fixture: &Fixture, section: &str) -> Vec<String> {
+    fs::read_dir(fixture.config.root_dir().join(section))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect()
+}
+
+#[test]
+fn failed_writes_leave_the_destination_untouched() {
+    let fixture = Fixture::empty();
+    let path = fixture.config.root_dir().join("notes");
+    fs::write(&path, "old contents").unwrap();
+
+    let err = atomic_write(&path, |file| {
+        file.write_all(b"half of the new").unwrap();
+        Err::<(), _>(DataError::Io {
+            path: path.clone(),
+            source: io::ErrorKind::WriteZero.into(),
+        })
+    })
+    .unwrap_err();
+    assert!(matches!(err, DataError::Io { .. }), "{err}");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "old contents");
+    assert_eq!(fs::read_dir(fixture.config.root_dir()).unwrap().count(), 1);
+
+    atomic_write(&path, |file| {
+        file.write_all(b"new contents")
+            .map_err(|source| DataError::Io {
+                path: path.clone(),
+                source,
+            })
+    })
+    .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new contents");
+    assert_eq!(fs::read_dir(fixture.config.root_dir()).unwrap().count(), 1);
+}
+
+#[test]
+fn interrupted_data_writes_keep_the_old_file() {
+    let fixture = Fixture::new(1);
+    let section = matrices_section(HASH);
+    let path = fixture.config.root_dir().join(&section).join("A_0");
+    let old = fs::read(&path).unwrap();
+    let manifest = Manifest::read(path.parent().unwrap()).unwrap();
+
+    let err = fixture
+        .config
+        .write(&section, "A_0", &FailsMidway)
+        .unwrap_err();
+    assert!(err.to_string().contains("interrupted"), "{err}");
+    assert_eq!(fs::read(&path).unwrap(), old);
+    assert_eq!(Manifest::read(path.parent().unwrap()).unwrap(), manifest);
+    assert!(temp_files(&fixture, &section).is_empty());
+    assert_eq!(
+        fixture.config.labels(&section).unwrap(),
+        ["A_0", "B_0", "C_0"]
+    );
+}
+
+#[test]
+fn only_stale_temporary_files_are_removed() {
+    let fixture = Fixture::new(1);
+    let section = matrices_section(HASH);
+    let dir = fixture.config.root_dir().join(&section);
+    let stale = SystemTime::now() - ORPHAN_MIN_AGE * 2;
+    File::create(dir.join("A_0.1.tmp"))
+        .unwrap()
+        .set_modified(stale)
+        .unwrap();
+    File::create(dir.join("B_0.2.tmp")).unwrap();
+    assert_eq!(
+        fixture.config.labels(&section).unwrap(),
+        ["A_0", "B_0", "C_0"]
+    );
+
+    assert_eq!(fixture.config.remove_orphaned_temp_files().unwrap(), 1);
+    assert_eq!(temp_files(&fixture, &section), ["B_0.2.tmp"]);
+    assert_eq!(fixture.config.remove_orphaned_temp_files().unwrap(), 0);
+}
+
+#[test]
+fn writing_subcommands_clean_up_orphans() {
+    let fixture = Fixture::new(1);
+    let section = matrices_section(HASH);
+    let orphan = fixture.config.root_dir().join(&section).join("A_0.1.tmp");
+    let stale = SystemTime::now() - ORPHAN_MIN_AGE * 2;
+    File::create(&orphan).unwrap().set_modified(stale).unwrap();
+
+    let output = fixture.run(&["check", HASH]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(orphan.exists());
+
+    let output = fixture.run(&["manifest", "create", HASH]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!orphan.exists());
+    assert!(
+        stderr(&output).contains("removed 1 orphaned temporary files left by interrupted writes"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,66 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{generate::random_vector, SparseMatrix};
+
+#[test]
+fn multiply_mat_equals_one_product_at_a_time() {
+    let mut rng = ChaCha20Rng::seed_from_u64(78);
+    let matrix: SparseMatrix<Fr> = SparseMatrix::random(50, 40, 6, &mut rng);
+    for k in [1, 2, 7, 16] {
+        let vectors: Vec<Vec<Fr>> = (0..k)
+            .map(|_| random_vector(&mut rng, matrix.cols))
+            .collect();
+        let expected: Vec<Vec<Fr>> = vectors.iter().map(|z| matrix.multiply_vec(z)).collect();
+        assert_eq!(matrix.multiply_mat(&vectors), expected, "k = {k}");
+    }
+}
+
+#[test]
+fn multiply_mat_of_nothing() {
+    let matrix: SparseMatrix<Fr> = SparseMatrix::zero(0, 3);
+    let vectors = vec![vec![Fr::from(1); 3]; 2];
+    assert_eq!(
+        matrix.multiply_mat(&vectors),
+        [Vec::<Fr>::new(), Vec::new()]
+    );
+    assert!(matrix.multiply_mat(&[]).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn multiply_mat_checks_every_vector() {
+    let matrix: SparseMatrix<Fr> = SparseMatrix::identity(3);
+    matrix.multiply_mat(&[vec![Fr::from(1); 3], vec![Fr::from(1); 2]]);
+}
+
+#[test]
+fn fused_products_are_bit_identical() {
+    let mut rng = ChaCha20Rng::seed_from_u64(79);
+    let [A, B, C]: [SparseMatrix<Fr>; 3] =
+        std::array::from_fn(|_| SparseMatrix::random(60, 45, 7, &mut rng));
+    let z: Vec<Fr> = random_vector(&mut rng, A.cols);
+    let (az, bz, cz) = SparseMatrix::multiply_vec_fused(&A, &B, &C, &z).unwrap();
+    assert_eq!(
+        (az, bz, cz),
+        (A.multiply_vec(&z), B.multiply_vec(&z), C.multiply_vec(&z))
+    );
+}
+
+#[test]
+fn fused_products_check_shapes() {
+    let (A, B) = (SparseMatrix::<Fr>::identity(3), SparseMatrix::zero(3, 2));
+    let z = vec![Fr::from(1); 3];
+    let err = SparseMatrix::multiply_vec_fused(&A, &A, &B, &z).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "a 3x3 matrix cannot be combined with a 3x2 one"
+    );
+    let err = SparseMatrix::multiply_vec_fused(&A, &A, &A, &z[1..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "a matrix of 3 columns cannot multiply a vector of 2 elements"
+    );
+}
@@ -0,0 +1,94 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::DEFAULT_BLOCK_COLS,
+    SparseMatrix,
+};
+
+#[test]
+fn blocked_matches_the_plain_kernel() {
+    for seed in 0..24 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..300);
+        let shape = Shape {
+            rows: rng.gen_range(0..200),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(16)),
+        };
+        let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec(&z);
+        // Down to a column per block, and up to wider than the matrix.
+        let widths = [1, 3, 64, cols - 1, cols, cols + 1, DEFAULT_BLOCK_COLS];
+        for block_cols in widths.into_iter().filter(|&width| width > 0) {
+            let blocked = M.to_blocked(block_cols);
+            assert_eq!(
+                blocked.multiply_vec(&z),
+                expected,
+                "seed {seed}, {shape:?}, {block_cols} columns"
+            );
+            let mut out = vec![Fr::from(3); 5];
+            blocked.multiply_vec_into(&z, &mut out);
+            assert_eq!(out, M.multiply_vec_serial(&z));
+        }
+    }
+}
+
+#[test]
+fn blocks_hold_their_columns() {
+    let M = SparseMatrix::from_dense(&[
+        (1..=7).map(Fr::from).collect(),
+        vec![Fr::from(0); 7],
+        (0..7).map(|col| Fr::from(col % 2)).collect(),
+    ]);
+    let blocked = M.to_blocked(3);
+    assert_eq!((blocked.num_rows(), blocked.num_cols()), (3, 7));
+    assert_eq!(blocked.block_cols(), 3);
+    let blocks = blocked.blocks();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|block| block.num_cols())
+            .collect::<Vec<_>>(),
+        [3, 3, 1]
+    );
+    assert_eq!(
+        blocks.iter().map(|block| block.nnz()).sum::<usize>(),
+        M.nnz()
+    );
+    assert_eq!(
+        blocks[1],
+        SparseMatrix::from_dense(&[
+            vec![Fr::from(4), Fr::from(5), Fr::from(6)],
+            vec![Fr::from(0); 3],
+            vec![Fr::from(1), Fr::from(0), Fr::from(1)],
+        ])
+    );
+    for block in blocks {
+        assert_eq!(block.validate(), Ok(()));
+    }
+
+    // A block wider than the matrix is the whole matrix.
+    assert_eq!(M.to_blocked(100).blocks(), [M]);
+}
+
+#[test]
+fn blocked_matrices_without_rows_or_columns() {
+    let empty = SparseMatrix::<Fr>::zero(0, 5).to_blocked(2);
+    assert_eq!(empty.blocks().len(), 3);
+    assert_eq!(empty.multiply_vec(&[Fr::from(1); 5]), []);
+    let narrow = SparseMatrix::<Fr>::zero(4, 0).to_blocked(2);
+    assert!(narrow.blocks().is_empty());
+    assert_eq!(narrow.multiply_vec(&[]), [Fr::from(0); 4]);
+}
+
+#[test]
+#[should_panic(expected = "blocks must have columns")]
+fn blocks_of_no_columns() {
+    SparseMatrix::<Fr>::identity(3).to_blocked(0);
+}
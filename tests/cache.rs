@@ -0,0 +1,106 @@
+mod common;
+
+use std::fs;
+
+use common::Fixture;
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{CacheStatus, CACHE_DIR},
+    DataConfig, SparseMatrix,
+};
+
+fn read(config: &DataConfig) -> (SparseMatrix<Fr>, CacheStatus) {
+    config.read_cached("abc", "A_0").unwrap()
+}
+
+fn write(config: &DataConfig, matrix: &SparseMatrix<Fr>) {
+    config.write("sparse_matrices_abc", "A_0", matrix).unwrap();
+}
+
+#[test]
+fn second_load_hits_the_cache() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    write(&config, &SparseMatrix::identity(3));
+
+    assert_eq!(
+        read(&config),
+        (SparseMatrix::identity(3), CacheStatus::Miss)
+    );
+    assert!(config.cache_dir("abc").join("A_0.raw").is_file());
+    assert_eq!(read(&config), (SparseMatrix::identity(3), CacheStatus::Hit));
+
+    // The cache is not a section of the dump.
+    let sections = config.sections().unwrap();
+    assert!(sections.iter().all(|s| s.name != CACHE_DIR), "{sections:?}");
+}
+
+#[test]
+fn changed_sources_are_rebuilt() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    write(&config, &SparseMatrix::identity(3));
+    read(&config);
+
+    write(&config, &SparseMatrix::identity(4));
+    assert_eq!(
+        read(&config),
+        (SparseMatrix::identity(4), CacheStatus::Stale)
+    );
+    assert_eq!(read(&config), (SparseMatrix::identity(4), CacheStatus::Hit));
+}
+
+#[test]
+fn corrupt_caches_are_rebuilt() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    write(&config, &SparseMatrix::identity(3));
+    read(&config);
+
+    fs::write(config.cache_dir("abc").join("A_0.raw"), b"SPMV").unwrap();
+    assert_eq!(
+        read(&config),
+        (SparseMatrix::identity(3), CacheStatus::Stale)
+    );
+    assert_eq!(read(&config), (SparseMatrix::identity(3), CacheStatus::Hit));
+}
+
+#[test]
+fn clearing_removes_one_hash_or_all() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    for hash in ["abc", "def"] {
+        let section = format!("sparse_matrices_{hash}");
+        config
+            .write(&section, "A_0", &SparseMatrix::<Fr>::identity(3))
+            .unwrap();
+        config.read_cached::<SparseMatrix<Fr>>(hash, "A_0").unwrap();
+    }
+
+    let (files, bytes) = config.clear_cache(Some("abc")).unwrap();
+    assert_eq!(files, 2);
+    assert!(bytes > 0);
+    assert!(!config.cache_dir("abc").exists());
+    assert!(config.cache_dir("def").is_dir());
+    assert_eq!(config.clear_cache(Some("abc")).unwrap(), (0, 0));
+
+    assert_eq!(config.clear_cache(None).unwrap().0, 2);
+    assert!(!config.root_dir().join(CACHE_DIR).exists());
+    assert!(config.root_dir().join("sparse_matrices_def/A_0").is_file());
+}
+
+#[test]
+fn verifying_checks_the_content_hash_of_caches() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    write(&config, &SparseMatrix::identity(3));
+    read(&config);
+
+    // A cache file that decodes to the wrong matrix is only caught by its content hash.
+    let cache_section = format!("{CACHE_DIR}/abc");
+    config
+        .write_raw(&cache_section, "A_0", &SparseMatrix::<Fr>::identity(4))
+        .unwrap();
+    assert_eq!(read(&config), (SparseMatrix::identity(4), CacheStatus::Hit));
+    let config = config.with_verify_files(true);
+    assert_eq!(
+        read(&config),
+        (SparseMatrix::identity(3), CacheStatus::Stale)
+    );
+    assert_eq!(read(&config), (SparseMatrix::identity(3), CacheStatus::Hit));
+}
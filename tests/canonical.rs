@@ -0,0 +1,191 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::random_vector,
+    sparse::{CooMatrix, MatrixError},
+    SparseMatrix,
+};
+
+fn fr(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn canonicalize_sorts_rows_and_merges_duplicates() {
+    let mut M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4, 5, 6, 7]),
+        indices: vec![2, 0, 2, 1, 1, 3, 0],
+        indptr: vec![0, 3, 3, 6, 7],
+        cols: 4,
+    };
+    assert!(!M.is_canonical());
+    let z = fr(&[10, 20, 30, 40]);
+    let expected = M.multiply_vec(&z);
+
+    M.canonicalize();
+    assert_eq!(
+        M,
+        SparseMatrix {
+            data: fr(&[2, 4, 9, 6, 7]),
+            indices: vec![0, 2, 1, 3, 0],
+            indptr: vec![0, 2, 2, 4, 5],
+            cols: 4,
+        }
+    );
+    assert!(M.is_canonical());
+    assert_eq!(M.multiply_vec(&z), expected);
+}
+
+#[test]
+fn duplicates_that_cancel_are_kept_as_explicit_zeros() {
+    let mut M = SparseMatrix {
+        data: vec![Fr::from(5), Fr::from(1), -Fr::from(5)],
+        indices: vec![1, 0, 1],
+        indptr: vec![0, 3],
+        cols: 2,
+    };
+    M.canonicalize();
+    // Pruning is separate, so the merged zero stays until `prune` drops it.
+    assert_eq!(M.indices, [0, 1]);
+    assert_eq!(M.data, [Fr::from(1), Fr::from(0)]);
+    assert_eq!(M.explicit_zeros(), 1);
+    assert!(M.prune().is_canonical());
+}
+
+#[test]
+fn canonicalize_keeps_products_of_random_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(97);
+    // Enough rows for several chunks of the parallel pass.
+    for (rows, cols) in [(1, 1), (6, 3), (10_000, 8)] {
+        let (mut data, mut indices, mut indptr) = (Vec::new(), Vec::new(), vec![0]);
+        for _ in 0..rows {
+            let mut row: Vec<usize> = (0..rng.gen_range(0..6))
+                .map(|_| rng.gen_range(0..cols))
+                .collect();
+            row.shuffle(&mut rng);
+            for col in row {
+                data.push(Fr::from(rng.gen_range(1..100u64)));
+                indices.push(col);
+            }
+            indptr.push(indices.len());
+        }
+        let mut M = SparseMatrix::try_new(data, indices, indptr, cols).unwrap();
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec(&z);
+        M.canonicalize();
+        assert!(M.is_canonical(), "{rows}x{cols}");
+        assert_eq!(M.validate_canonical(), Ok(()));
+        assert_eq!(M.multiply_vec(&z), expected, "{rows}x{cols}");
+
+        let canonical = M.clone();
+        M.canonicalize();
+        assert_eq!(M, canonical, "canonicalizing twice changes nothing");
+    }
+}
+
+#[test]
+fn canonical_form_of_degenerate_matrices() {
+    for mut M in [
+        SparseMatrix::<Fr>::zero(0, 3),
+        SparseMatrix::zero(4, 0),
+        SparseMatrix::identity(3),
+    ] {
+        assert!(M.is_canonical());
+        let before = M.clone();
+        M.canonicalize();
+        assert_eq!(M, before);
+    }
+}
+
+#[test]
+fn validate_canonical_reports_the_offending_entry() {
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4]),
+        indices: vec![0, 2, 1, 1],
+        indptr: vec![0, 1, 4],
+        cols: 3,
+    };
+    // Sorted or not, the matrix keeps the invariants the kernels need.
+    assert_eq!(M.validate(), Ok(()));
+    assert_eq!(
+        M.validate_canonical(),
+        Err(MatrixError::NotCanonical {
+            row: 1,
+            position: 2,
+            col: 1,
+            previous: 2,
+        })
+    );
+    // A repeated column is not canonical either.
+    let M = SparseMatrix {
+        data: fr(&[1, 2]),
+        indices: vec![1, 1],
+        indptr: vec![0, 2],
+        cols: 2,
+    };
+    assert!(matches!(
+        M.validate_canonical(),
+        Err(MatrixError::NotCanonical { position: 1, .. })
+    ));
+    // Broken invariants are reported first.
+    let M = SparseMatrix {
+        data: fr(&[1]),
+        indices: vec![5],
+        indptr: vec![0, 1],
+        cols: 2,
+    };
+    assert!(matches!(
+        M.validate_canonical(),
+        Err(MatrixError::IndexOutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn coo_conversion_is_canonical() {
+    let coo = CooMatrix::from_triplets(
+        3,
+        3,
+        vec![
+            (2, 1, Fr::from(1)),
+            (0, 2, Fr::from(2)),
+            (0, 0, Fr::from(3)),
+            (0, 2, Fr::from(4)),
+            (2, 0, Fr::from(5)),
+        ],
+    );
+    let M = coo.to_csr();
+    assert!(M.is_canonical());
+    assert_eq!(M.indices, [0, 2, 0, 1]);
+    assert_eq!(M.data, fr(&[3, 6, 5, 1]));
+    assert_eq!(M.indptr, [0, 2, 2, 4]);
+}
+
+#[test]
+fn find_duplicates_in_unsorted_rows() {
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        indices: vec![2, 0, 2, 1, 1, 3, 1, 0, 0],
+        indptr: vec![0, 3, 3, 5, 7, 9],
+        cols: 4,
+    };
+    assert_eq!(M.find_duplicates(), [(0, 2, 2), (2, 1, 2), (4, 0, 2)]);
+    // Looking for them leaves the matrix as it was.
+    assert_eq!(M.indices, [2, 0, 2, 1, 1, 3, 1, 0, 0]);
+
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4, 5]),
+        indices: vec![1, 0, 1, 0, 1],
+        indptr: vec![0, 5],
+        cols: 2,
+    };
+    assert_eq!(M.find_duplicates(), [(0, 0, 2), (0, 1, 3)]);
+
+    let mut M = M;
+    M.canonicalize();
+    assert!(M.find_duplicates().is_empty());
+    assert!(SparseMatrix::<Fr>::identity(5).find_duplicates().is_empty());
+    assert!(SparseMatrix::<Fr>::zero(0, 2).find_duplicates().is_empty());
+}
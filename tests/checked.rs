@@ -0,0 +1,132 @@
+#![allow(non_snake_case)]
+
+use camino::Utf8PathBuf;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    sparse::CheckedSparseMatrix,
+    DataConfig, DataError, SparseMatrix,
+};
+
+const ROWS: usize = 10_000;
+
+/// Byte offsets of the fields of a bincode matrix with one entry in each of [`ROWS`] rows.
+const INDICES: usize = 8 + 32 * ROWS;
+const INDPTR: usize = INDICES + 8 + 8 * ROWS;
+
+/// A matrix with one entry in each row, serialized as bincode.
+fn serialized() -> Vec<u8> {
+    let matrix = SparseMatrix {
+        data: vec![Fr::from(1); ROWS],
+        indices: vec![0; ROWS],
+        indptr: (0..=ROWS).collect(),
+        cols: 1,
+    };
+    bincode::serialize(&matrix).unwrap()
+}
+
+/// Overwrites the `u64` at `offset` of `bytes` with `value`.
+fn patch(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Reads `bytes` through a data root as a checked matrix, returning the error and the offset
+/// it was found at.
+fn read_checked(bytes: &[u8]) -> (String, u64) {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DataConfig::new(Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap());
+    config
+        .write_bytes("sparse_matrices_abc", "A_0", bytes)
+        .unwrap();
+    let err = config
+        .read::<CheckedSparseMatrix<Fr>>("sparse_matrices_abc", "A_0")
+        .unwrap_err();
+    match &err {
+        DataError::Deserialize {
+            offset: Some(offset),
+            ..
+        } => (err.to_string(), *offset),
+        err => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn valid_matrices_deserialize_unchanged() {
+    let mut rng = ChaCha20Rng::seed_from_u64(66);
+    let shape = Shape {
+        rows: 200,
+        cols: 30,
+        nnz_per_row: 4,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let bytes = bincode::serialize(&M).unwrap();
+    let checked: CheckedSparseMatrix<Fr> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(checked.into_inner(), M);
+
+    let checked: CheckedSparseMatrix<Fr> = bincode::deserialize(&serialized()).unwrap();
+    assert_eq!(checked.0.indptr.len(), ROWS + 1);
+}
+
+#[test]
+fn a_decreasing_indptr_is_rejected_where_it_decreases() {
+    let mut bytes = serialized();
+    patch(&mut bytes, INDPTR + 8 + 8 * 4, 1);
+    // A plain matrix only checks the lengths, and loads.
+    let plain: SparseMatrix<Fr> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(plain.indptr[3..5], [3, 1]);
+
+    let (err, offset) = read_checked(&bytes);
+    assert!(
+        err.contains("indptr decreases at row 3, from 3 to 1"),
+        "{err}"
+    );
+    // Decoding stops after the pointer, not at the end of the file.
+    assert_eq!(offset, (INDPTR + 8 + 8 * 5) as u64, "{err}");
+}
+
+#[test]
+fn a_pointer_past_the_entries_is_rejected_at_once() {
+    let mut bytes = serialized();
+    patch(&mut bytes, INDPTR + 8 + 8 * 2, 1 << 40);
+    let (err, offset) = read_checked(&bytes);
+    assert!(
+        err.contains("row 1 ends at 1099511627776, past the 10000 values"),
+        "{err}"
+    );
+    assert_eq!(offset, (INDPTR + 8 + 8 * 3) as u64, "{err}");
+}
+
+#[test]
+fn a_corrupt_length_is_rejected_before_it_is_allocated() {
+    let mut bytes = serialized();
+    patch(&mut bytes, INDICES, 1 << 60);
+    let err = bincode::deserialize::<CheckedSparseMatrix<Fr>>(&bytes).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("10000 values but 1152921504606846976 column indices"),
+        "{err}"
+    );
+    let (_, offset) = read_checked(&bytes);
+    assert_eq!(offset, (INDICES + 8) as u64);
+
+    // The rows are not known in advance, so reading runs out of input instead, having reserved
+    // little of the claimed length.
+    let mut bytes = serialized();
+    patch(&mut bytes, INDPTR, 1 << 60);
+    assert!(bincode::deserialize::<CheckedSparseMatrix<Fr>>(&bytes).is_err());
+}
+
+#[test]
+fn out_of_bounds_indices_are_named_by_row_and_position() {
+    let mut bytes = serialized();
+    patch(&mut bytes, INDICES + 8 + 8 * 7, 4);
+    patch(&mut bytes, INDICES + 8 + 8 * 9, 2);
+    let err = bincode::deserialize::<CheckedSparseMatrix<Fr>>(&bytes).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("row 7: column 4 of the entry at 7 is out of bounds of the 1 columns"),
+        "{err}"
+    );
+}
@@ -0,0 +1,99 @@
+#![allow(non_snake_case)]
+
+use std::{fs, io};
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::{ChunkedMatrix, ChunkedShape, CHUNKED_HEADER_BYTES},
+    SparseMatrix,
+};
+
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(5);
+    let shape = Shape {
+        rows: 60,
+        cols: 45,
+        nnz_per_row: 5,
+    };
+    random_matrix(&mut rng, shape)
+}
+
+/// Writes `M` in chunks of `rows_per_chunk` rows and opens the result.
+fn chunked(M: &SparseMatrix<Fr>, rows_per_chunk: usize) -> (tempfile::TempDir, ChunkedMatrix) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("M.chunked");
+    M.write_chunked(rows_per_chunk, fs::File::create(&path).unwrap())
+        .unwrap();
+    let chunked = ChunkedMatrix::open(&path).unwrap();
+    (dir, chunked)
+}
+
+#[test]
+fn chunked_multiplies_match_in_memory_multiplies() {
+    let M = matrix();
+    let mut rng = ChaCha20Rng::seed_from_u64(6);
+    let z: Vec<Fr> = random_vector(&mut rng, M.cols);
+    let expected = M.multiply_vec(&z);
+
+    // One row per chunk, a prime number that does not divide the rows, and more than all of them.
+    for rows_per_chunk in [1, 7, 100] {
+        let (_dir, chunked) = chunked(&M, rows_per_chunk);
+        assert_eq!(
+            chunked.shape(),
+            ChunkedShape {
+                rows: 60,
+                cols: 45,
+                nnz: M.nnz(),
+                rows_per_chunk,
+            }
+        );
+        assert_eq!(chunked.shape().chunks(), 60usize.div_ceil(rows_per_chunk));
+        assert_eq!(
+            chunked.multiply_vec_streaming(&z).unwrap(),
+            expected,
+            "{rows_per_chunk} rows per chunk"
+        );
+    }
+}
+
+#[test]
+fn slices_keep_their_rows() {
+    let M = matrix();
+    let slice = M.slice_rows(10..17);
+    assert_eq!(slice.indptr.len(), 8);
+    assert_eq!(slice.indptr[0], 0);
+    assert_eq!(slice.cols, M.cols);
+
+    let z = vec![Fr::from(3); M.cols];
+    assert_eq!(slice.multiply_vec(&z), M.multiply_vec(&z)[10..17]);
+}
+
+#[test]
+fn corrupt_files_are_rejected() {
+    let M = matrix();
+    let (dir, chunked) = chunked(&M, 7);
+    let path = dir.path().join("M.chunked");
+    let bytes = fs::read(&path).unwrap();
+    let z = vec![Fr::from(1); M.cols];
+
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let err = chunked.multiply_vec_streaming(&z).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    // Point the first entry of the first chunk past the last column.
+    let mut bad = bytes.clone();
+    let entry = CHUNKED_HEADER_BYTES + 8 + 16 + 32 * M.indptr[7] + 16;
+    bad[entry..entry + 4].copy_from_slice(&(M.cols as u32).to_le_bytes());
+    fs::write(&path, &bad).unwrap();
+    let err = chunked.multiply_vec_streaming(&z).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("out of range"), "{err}");
+
+    fs::write(&path, b"not a chunked matrix at all, just some text").unwrap();
+    let err = ChunkedMatrix::open(&path).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "not a chunked matrix");
+}
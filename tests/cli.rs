@@ -0,0 +1,992 @@
+use clap::{error::ErrorKind, Parser};
+use halo2curves::bn256::Fr;
+use spmvm_test_example::cli::{
+    Backend, BenchArgs, CacheAction, CacheArgs, CheckArgs, CircuitSelection, Cli, CliError,
+    Command, CompareArgs, ConvertArgs, ConvertTarget, CrossTermArgs, DiffArgs, Format, GlobalArgs,
+    HashArgs, ImportVectorArgs, InspectAction, InspectArgs, Layout, ListArgs, ManifestAction,
+    ManifestArgs, MatrixName, R1csArgs, RegenArgs, RunResult, SpySize, StatsArgs, Status, Tally,
+    VectorArgs, VerifyArgs,
+};
+use spmvm_test_example::{
+    sparse::{ChunkPolicy, MatrixError, Partition},
+    DataError, DataFormat,
+};
+
+fn parse(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("spmvm").chain(args.iter().copied())).unwrap()
+}
+
+fn parse_err(args: &[&str]) -> ErrorKind {
+    Cli::try_parse_from(std::iter::once("spmvm").chain(args.iter().copied()))
+        .unwrap_err()
+        .kind()
+}
+
+fn hash(hash: &str) -> HashArgs {
+    HashArgs {
+        hash: hash.to_string(),
+        circuit: CircuitSelection::Primary,
+    }
+}
+
+#[test]
+fn bench_defaults() {
+    let cli = parse(&["bench", "abc"]);
+    assert_eq!(
+        cli.command,
+        Command::Bench(BenchArgs {
+            hashes: vec!["abc".to_string()],
+            all: false,
+            circuit: CircuitSelection::Primary,
+            repeat: 1,
+            warmup: 0,
+            output: None,
+            csv: None,
+            csv_append: false,
+            no_verify: false,
+            no_preflight: false,
+            backend: Backend::Parallel,
+            block_cols: None,
+            ell_width: None,
+            partition: Partition::Nnz,
+            chunk_policy: None,
+            tune: false,
+            layout: Layout::Csr,
+            sweep_threads: None,
+            parallel_witnesses: false,
+            spmm: false,
+            fused: false,
+            sparse_witness: false,
+            profile_rows: false,
+            profile_witness: None,
+            reorder: false,
+            #[cfg(feature = "rkyv")]
+            archived: false,
+            low_memory: false,
+            witness_stdin: false,
+            expected_dir: None,
+            diff: DiffArgs { diff_limit: 10 },
+        })
+    );
+    assert_eq!(
+        cli.global,
+        GlobalArgs {
+            data_dir: None,
+            threads: 0,
+            iterations: None,
+            start: 0,
+            matrices: MatrixName::ALL.to_vec(),
+            format: Format::Text,
+            mmap: false,
+            compress: false,
+            data_format: DataFormat::Bincode,
+            no_cache: false,
+            verify_files: false,
+            validate: false,
+            lock_timeout: 600,
+            section_url: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn verify_with_global_flags() {
+    let cli = parse(&["--data-dir", "/tmp/dump", "verify", "abc", "--threads", "4"]);
+    assert_eq!(
+        cli.command,
+        Command::Verify(VerifyArgs {
+            dump: hash("abc"),
+            diff: DiffArgs { diff_limit: 10 },
+            cross_check: false,
+            low_memory: false,
+            chunked: false,
+            combined: None,
+            fused: false,
+            tagged: false,
+        })
+    );
+    assert_eq!(cli.global.data_dir.as_deref(), Some("/tmp/dump".into()));
+    assert_eq!(cli.global.threads, 4);
+}
+
+#[test]
+fn stats_with_iterations() {
+    let cli = parse(&["stats", "abc", "--iterations", "3"]);
+    assert_eq!(
+        cli.command,
+        Command::Stats(StatsArgs {
+            dump: hash("abc"),
+            spy: None,
+            spy_size: SpySize::default(),
+            ell_width: 8,
+        })
+    );
+    assert_eq!(cli.global.iterations, Some(3));
+}
+
+#[test]
+fn circuit_selection() {
+    let cli = parse(&["verify", "abc", "--circuit", "both"]);
+    let Command::Verify(args) = cli.command else {
+        panic!("{:?}", cli.command);
+    };
+    assert_eq!(args.dump.circuit, CircuitSelection::Both);
+    assert_eq!(CircuitSelection::Both.circuits().len(), 2);
+    assert_eq!(
+        parse_err(&["stats", "abc", "--circuit", "tertiary"]),
+        ErrorKind::InvalidValue
+    );
+}
+
+#[test]
+fn bench_several_hashes() {
+    assert_eq!(
+        bench_args(&["bench", "a", "b", "c"]).hashes,
+        ["a", "b", "c"]
+    );
+    let args = bench_args(&["bench", "--all"]);
+    assert!(args.all && args.hashes.is_empty());
+    assert_eq!(
+        parse_err(&["bench", "--all", "a"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(parse_err(&["bench"]), ErrorKind::MissingRequiredArgument);
+}
+
+#[test]
+fn witness_on_stdin() {
+    let args = bench_args(&["bench", "abc", "--witness-stdin", "--expected-dir", "out"]);
+    assert!(args.witness_stdin);
+    assert_eq!(args.expected_dir, Some("out".into()));
+    assert_eq!(
+        parse_err(&["bench", "abc", "--expected-dir", "out"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        parse_err(&["bench", "--all", "--witness-stdin"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(
+        parse_err(&["bench", "abc", "--witness-stdin", "--low-memory"]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn start_and_iterations() {
+    let cli = parse(&["bench", "abc", "--start", "4", "--iterations", "3"]);
+    assert_eq!(cli.global.start, 4);
+    assert_eq!(cli.global.iterations, Some(3));
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(
+        parse_err(&[]),
+        ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+    );
+    assert_eq!(parse_err(&["bench"]), ErrorKind::MissingRequiredArgument);
+    assert_eq!(
+        parse_err(&["bench", "abc", "--bogus"]),
+        ErrorKind::UnknownArgument
+    );
+    assert_eq!(parse_err(&["frobnicate"]), ErrorKind::InvalidSubcommand);
+    assert_eq!(
+        parse_err(&["bench", "abc", "--threads", "x"]),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn unsupported_data_urls_are_usage_errors() {
+    for url in ["ftp://example.com", "gs://bucket/dumps"] {
+        assert_eq!(
+            parse_err(&["--data-dir", url, "list"]),
+            ErrorKind::ValueValidation,
+            "{url}"
+        );
+        let section_url = format!("witness_abc={url}");
+        assert_eq!(
+            parse_err(&["--section-url", &section_url, "list"]),
+            ErrorKind::ValueValidation,
+            "{url}"
+        );
+    }
+    for url in [
+        "http://example.com/dumps",
+        "https://example.com/dumps",
+        "s3://bucket/dumps",
+    ] {
+        let cli = parse(&["--data-dir", url, "list"]);
+        assert_eq!(cli.global.data_dir.as_deref(), Some(url.into()));
+    }
+}
+
+#[test]
+fn witness_not_found_message() {
+    let err = CliError::WitnessNotFound {
+        index: 37,
+        available: (0..16).collect(),
+    };
+    assert_eq!(
+        err.to_string(),
+        "witness _37 not found, section contains 0..=15"
+    );
+    let err = CliError::WitnessNotFound {
+        index: 0,
+        available: vec![],
+    };
+    assert_eq!(err.to_string(), "witness _0 not found, section is empty");
+}
+
+#[test]
+fn would_overwrite_message() {
+    let err = |labels: &[&str]| CliError::WouldOverwrite {
+        section: "result_abc".into(),
+        labels: labels.iter().map(|l| l.to_string()).collect(),
+    };
+    assert_eq!(
+        err(&["AZ_0", "BZ_0"]).to_string(),
+        "result_abc already holds AZ_0, BZ_0; pass --force to overwrite"
+    );
+    assert_eq!(
+        err(&["AZ_0", "BZ_0", "CZ_0", "AZ_1", "BZ_1"]).to_string(),
+        "result_abc already holds AZ_0, BZ_0, CZ_0 and 2 more; pass --force to overwrite"
+    );
+}
+
+#[test]
+fn regen_results_flags() {
+    assert_eq!(
+        parse(&["regen-results", "abc", "--force"]).command,
+        Command::RegenResults(RegenArgs {
+            dump: hash("abc"),
+            force: true,
+        })
+    );
+}
+
+fn bench_args(args: &[&str]) -> BenchArgs {
+    match parse(args).command {
+        Command::Bench(args) => args,
+        command => panic!("expected bench, got {command:?}"),
+    }
+}
+
+#[test]
+fn repeat_must_be_positive() {
+    assert_eq!(bench_args(&["bench", "abc", "--repeat", "5"]).repeat, 5);
+    assert_eq!(
+        parse_err(&["bench", "abc", "--repeat", "0"]),
+        ErrorKind::ValueValidation
+    );
+    assert_eq!(
+        parse_err(&["verify", "abc", "--repeat", "2"]),
+        ErrorKind::UnknownArgument
+    );
+}
+
+#[test]
+fn warmup_rounds() {
+    assert_eq!(bench_args(&["bench", "abc", "--warmup", "3"]).warmup, 3);
+    assert_eq!(
+        parse_err(&["bench", "abc", "--warmup", "-1"]),
+        ErrorKind::UnknownArgument
+    );
+}
+
+#[test]
+fn check_and_no_preflight() {
+    assert_eq!(
+        parse(&["check", "abc"]).command,
+        Command::Check(CheckArgs {
+            dump: hash("abc"),
+            content_hash: false,
+            duplicates: false,
+        })
+    );
+    let Command::Check(args) = parse(&["check", "abc", "--content-hash"]).command else {
+        panic!("not check");
+    };
+    assert!(args.content_hash);
+    assert!(bench_args(&["bench", "abc", "--no-preflight"]).no_preflight);
+}
+
+#[test]
+fn check_r1cs_limit() {
+    assert_eq!(
+        parse(&["check-r1cs", "abc"]).command,
+        Command::CheckR1cs(R1csArgs {
+            dump: hash("abc"),
+            limit: 10,
+            relaxed: false,
+            relaxed_section: None,
+        })
+    );
+    let Command::CheckR1cs(args) = parse(&["check-r1cs", "abc", "--limit", "2"]).command else {
+        panic!("not check-r1cs");
+    };
+    assert_eq!(args.limit, 2);
+
+    let Command::CheckR1cs(args) = parse(&[
+        "check-r1cs",
+        "abc",
+        "--relaxed",
+        "--relaxed-section",
+        "folded",
+    ])
+    .command
+    else {
+        panic!("not check-r1cs");
+    };
+    assert!(args.relaxed);
+    assert_eq!(args.relaxed_section.as_deref(), Some("folded"));
+    assert_eq!(
+        parse_err(&["check-r1cs", "abc", "--relaxed-section", "folded"]),
+        ErrorKind::MissingRequiredArgument
+    );
+}
+
+#[test]
+fn cross_term_witnesses_and_scalars() {
+    assert_eq!(
+        parse(&["cross-term", "abc"]).command,
+        Command::CrossTerm(CrossTermArgs {
+            dump: hash("abc"),
+            i: 0,
+            j: 1,
+            u1: Fr::from(1),
+            u2: Fr::from(1),
+            diff: DiffArgs { diff_limit: 10 },
+        })
+    );
+    let Command::CrossTerm(args) =
+        parse(&["cross-term", "abc", "--i", "3", "--j", "2", "--u2", "0x10"]).command
+    else {
+        panic!("not cross-term");
+    };
+    assert_eq!((args.i, args.j, args.u2), (3, 2, Fr::from(16)));
+}
+
+#[test]
+fn csv_append_requires_csv() {
+    let args = bench_args(&["bench", "abc", "--csv", "t.csv", "--csv-append"]);
+    assert_eq!(args.csv.as_deref(), Some("t.csv".into()));
+    assert!(args.csv_append);
+    assert_eq!(
+        parse_err(&["bench", "abc", "--csv-append"]),
+        ErrorKind::MissingRequiredArgument
+    );
+}
+
+#[test]
+fn compare_reports() {
+    let cli = parse(&["compare", "base.json", "cand.json", "--threshold", "5"]);
+    assert_eq!(
+        cli.command,
+        Command::Compare(CompareArgs {
+            baseline: "base.json".into(),
+            candidate: "cand.json".into(),
+            threshold: Some(5.0),
+        })
+    );
+    assert_eq!(
+        parse_err(&["compare", "base.json"]),
+        ErrorKind::MissingRequiredArgument
+    );
+}
+
+#[test]
+fn diff_limit() {
+    assert_eq!(
+        bench_args(&["bench", "abc", "--diff-limit", "3"]).diff,
+        DiffArgs { diff_limit: 3 }
+    );
+    let cli = parse(&["verify", "abc", "--diff-limit", "0"]);
+    assert!(matches!(
+        cli.command,
+        Command::Verify(VerifyArgs {
+            diff: DiffArgs { diff_limit: 0 },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn exit_codes() {
+    let cases = [
+        (CliError::VerificationFailed { mismatches: 2 }, 1),
+        (
+            CliError::CompareFailed {
+                regressions: 1,
+                mismatches: 0,
+            },
+            1,
+        ),
+        (CliError::Data(DataError::NoDataRoot), 2),
+        (CliError::Data(DataError::LabelNotFound("x/_3".into())), 2),
+        (
+            CliError::WitnessNotFound {
+                index: 3,
+                available: vec![],
+            },
+            2,
+        ),
+        (CliError::NoWitnesses("witness_abc".into()), 2),
+        (
+            CliError::Incompatible {
+                context: "witness 0".into(),
+                source: MatrixError::VectorLength { cols: 3, len: 2 },
+            },
+            2,
+        ),
+        (
+            CliError::IncompleteDump {
+                hash: "abc".into(),
+                missing: 4,
+            },
+            2,
+        ),
+        (
+            CliError::Report {
+                path: "r.json".into(),
+                source: std::io::ErrorKind::NotFound.into(),
+            },
+            2,
+        ),
+    ];
+    for (err, code) in cases {
+        assert_eq!(err.status().code(), code, "{err}");
+    }
+    assert_eq!(CliError::InvalidArgs("bad".into()).status().code(), 3);
+    let overwrite = CliError::WouldOverwrite {
+        section: "result_abc".into(),
+        labels: vec!["AZ_0".into()],
+    };
+    assert_eq!(overwrite.status().code(), 3);
+    assert_eq!(Status::Ok.code(), 0);
+    assert_eq!(Status::Usage.code(), 3);
+}
+
+#[test]
+fn result_line() {
+    let result = RunResult {
+        result: Status::Ok,
+        tally: Tally {
+            matrices: 3,
+            witnesses: 16,
+            mismatches: 0,
+            circuits: Vec::new(),
+            hashes: Vec::new(),
+        },
+        error: None,
+    };
+    assert_eq!(
+        result.render(Format::Text),
+        "RESULT ok matrices=3 witnesses=16 mismatches=0"
+    );
+    assert_eq!(
+        result.render(Format::Json),
+        r#"{"result":"ok","matrices":3,"witnesses":16,"mismatches":0}"#
+    );
+
+    let result = RunResult {
+        result: Status::Fail,
+        error: Some("2 products did not match".into()),
+        ..result
+    };
+    assert_eq!(
+        result.render(Format::Json),
+        r#"{"result":"fail","matrices":3,"witnesses":16,"mismatches":0,"error":"2 products did not match"}"#
+    );
+}
+
+#[test]
+fn list_section_is_optional() {
+    assert_eq!(
+        parse(&["list"]).command,
+        Command::List(ListArgs { section: None })
+    );
+    let cli = parse(&["list", "witness_abc", "--format", "json"]);
+    assert_eq!(
+        cli.command,
+        Command::List(ListArgs {
+            section: Some("witness_abc".into())
+        })
+    );
+    assert_eq!(cli.global.format, Format::Json);
+}
+
+#[test]
+fn inspect_vector_takes_a_section_and_label() {
+    let cli = parse(&[
+        "inspect",
+        "vector",
+        "witness_abc",
+        "_0",
+        "--limit",
+        "4",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(
+        cli.command,
+        Command::Inspect(InspectArgs {
+            action: InspectAction::Vector(VectorArgs {
+                section: "witness_abc".into(),
+                label: "_0".into(),
+                limit: Some(4),
+                out: None,
+            })
+        })
+    );
+    assert_eq!(cli.global.format, Format::Json);
+    assert_eq!(
+        parse(&["import-vector", "z.json", "witness_abc", "_3"]).command,
+        Command::ImportVector(ImportVectorArgs {
+            path: "z.json".into(),
+            section: "witness_abc".into(),
+            label: "_3".into(),
+        })
+    );
+    assert_eq!(
+        parse_err(&["inspect", "vector", "witness_abc"]),
+        ErrorKind::MissingRequiredArgument
+    );
+}
+
+#[test]
+fn stats_draws_spy_plots() {
+    let command = parse(&["stats", "abc", "--spy", "s.png", "--spy-size", "64x32"]).command;
+    assert!(matches!(
+        command,
+        Command::Stats(StatsArgs {
+            spy: Some(path),
+            spy_size: SpySize {
+                width: 64,
+                height: 32
+            },
+            ..
+        }) if path == "s.png"
+    ));
+    for size in ["64", "0x8", "ax8"] {
+        assert_eq!(
+            parse_err(&["stats", "abc", "--spy-size", size]),
+            ErrorKind::ValueValidation,
+            "{size}"
+        );
+    }
+}
+
+#[test]
+fn matrices_subset() {
+    let cli = parse(&["bench", "abc", "--matrices", "C,a"]);
+    assert_eq!(cli.global.matrices, [MatrixName::C, MatrixName::A]);
+
+    let err = Cli::try_parse_from(["spmvm", "verify", "abc", "--matrices", "A,D"]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    assert!(
+        err.to_string().contains("[possible values: A, B, C]"),
+        "{err}"
+    );
+}
+
+#[test]
+fn backend_and_cross_check() {
+    assert_eq!(
+        bench_args(&["bench", "abc", "--backend", "serial"]).backend,
+        Backend::Serial
+    );
+    assert_eq!(
+        parse_err(&["bench", "abc", "--backend", "gpu"]),
+        ErrorKind::InvalidValue
+    );
+    assert_eq!(
+        bench_args(&["bench", "abc", "--layout", "csc"]).layout,
+        Layout::Csc
+    );
+    assert_eq!(
+        parse_err(&["bench", "abc", "--layout", "csc", "--low-memory"]),
+        ErrorKind::ArgumentConflict
+    );
+    let cli = parse(&["verify", "abc", "--cross-check"]);
+    assert!(matches!(
+        cli.command,
+        Command::Verify(VerifyArgs {
+            cross_check: true,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn combined_coefficient() {
+    let combined = |args: &[&str]| match parse(args).command {
+        Command::Verify(args) => args.combined,
+        command => panic!("not verify: {command:?}"),
+    };
+    assert_eq!(combined(&["verify", "abc"]), None);
+    assert_eq!(
+        combined(&["verify", "abc", "--combined", "7"]),
+        Some(Fr::from(7))
+    );
+    assert_eq!(
+        combined(&["verify", "abc", "--combined", "0x1f"]),
+        Some(Fr::from(31))
+    );
+    assert_eq!(
+        parse_err(&["verify", "abc", "--combined", "seven"]),
+        ErrorKind::ValueValidation
+    );
+    assert_eq!(
+        parse_err(&["verify", "abc", "--combined", "7", "--low-memory"]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn sweep_threads_list() {
+    assert_eq!(
+        bench_args(&["bench", "abc", "--sweep-threads", "1,2,4"]).sweep_threads,
+        Some(vec![1, 2, 4])
+    );
+    assert_eq!(
+        parse_err(&["bench", "abc", "--sweep-threads", "1,two"]),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn parallel_witnesses_conflicts_with_reports() {
+    assert!(bench_args(&["bench", "abc", "--parallel-witnesses"]).parallel_witnesses);
+    for flag in ["--output", "--csv", "--sweep-threads"] {
+        assert_eq!(
+            parse_err(&["bench", "abc", "--parallel-witnesses", flag, "1"]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+}
+
+#[test]
+fn spmm_conflicts_with_other_modes() {
+    assert!(bench_args(&["bench", "abc", "--spmm"]).spmm);
+    for flags in [
+        &["--parallel-witnesses"][..],
+        &["--low-memory"],
+        &["--layout", "csc"],
+        &["--backend", "serial"],
+        &["--output", "report.json"],
+    ] {
+        let args = [&["bench", "abc", "--spmm"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+}
+
+#[test]
+fn partition_defaults_to_nnz() {
+    assert_eq!(bench_args(&["bench", "abc"]).partition, Partition::Nnz);
+    assert_eq!(
+        bench_args(&["bench", "abc", "--partition", "rows"]).partition,
+        Partition::Rows
+    );
+    for flags in [&["--backend", "serial"][..], &["--layout", "csc"]] {
+        let args = [&["bench", "abc", "--partition", "rows"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+}
+
+#[test]
+fn chunk_policies_and_tuning() {
+    for (value, policy) in [
+        ("auto", ChunkPolicy::Auto),
+        ("rows=64", ChunkPolicy::Rows(64)),
+        ("nnz=4096", ChunkPolicy::Nnz(4096)),
+    ] {
+        let args = bench_args(&["bench", "abc", "--chunk-policy", value]);
+        assert_eq!(args.chunk_policy, Some(policy));
+        assert_eq!(policy.to_string(), value);
+    }
+    for value in ["rows", "rows=0", "nnz=x", "cols=3", "auto=1"] {
+        assert_eq!(
+            parse_err(&["bench", "abc", "--chunk-policy", value]),
+            ErrorKind::ValueValidation,
+            "{value}"
+        );
+    }
+    assert!(bench_args(&["bench", "abc", "--tune"]).tune);
+    for flags in [
+        &["--chunk-policy", "auto"][..],
+        &["--partition", "rows"],
+        &["--backend", "serial"],
+    ] {
+        let args = [&["bench", "abc", "--tune"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+}
+
+#[test]
+fn fused_conflicts_with_other_kernels() {
+    assert!(bench_args(&["bench", "abc", "--fused"]).fused);
+    for flags in [&["--spmm"][..], &["--low-memory"], &["--layout", "csc"]] {
+        let args = [&["bench", "abc", "--fused"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+    assert_eq!(
+        parse_err(&["verify", "abc", "--fused", "--cross-check"]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn sparse_witness_conflicts_with_other_kernels() {
+    assert!(bench_args(&["bench", "abc", "--sparse-witness", "--fused"]).sparse_witness);
+    for flags in [&["--spmm"][..], &["--low-memory"], &["--backend", "serial"]] {
+        let args = [&["bench", "abc", "--sparse-witness"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+}
+
+#[test]
+fn blocked_backend_and_width() {
+    let args = bench_args(&["bench", "abc", "--backend", "blocked", "--block-cols", "4"]);
+    assert_eq!((args.backend, args.block_cols), (Backend::Blocked, Some(4)));
+    assert_eq!(
+        parse_err(&["bench", "abc", "--backend", "blocked", "--block-cols", "0"]),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn ell_backend_and_width() {
+    let args = bench_args(&["bench", "abc", "--backend", "ell", "--ell-width", "3"]);
+    assert_eq!((args.backend, args.ell_width), (Backend::Ell, Some(3)));
+    let command = parse(&["stats", "abc", "--ell-width", "0"]).command;
+    assert!(matches!(
+        command,
+        Command::Stats(StatsArgs { ell_width: 0, .. })
+    ));
+}
+
+#[test]
+fn reorder_flag() {
+    assert!(bench_args(&["bench", "abc", "--reorder", "--backend", "serial"]).reorder);
+    for flag in [
+        "--layout=csc",
+        "--sweep-threads=1,2",
+        "--spmm",
+        "--low-memory",
+    ] {
+        assert_eq!(
+            parse_err(&["bench", "abc", "--reorder", flag]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+}
+
+#[test]
+fn unchecked_backend() {
+    assert_eq!(
+        bench_args(&["bench", "abc", "--backend", "unchecked"]).backend,
+        Backend::Unchecked
+    );
+}
+
+#[test]
+fn tagged_backend_and_verify() {
+    assert_eq!(
+        bench_args(&["bench", "abc", "--backend", "tagged"]).backend,
+        Backend::Tagged
+    );
+    let cli = parse(&["verify", "abc", "--tagged"]);
+    assert!(matches!(
+        cli.command,
+        Command::Verify(VerifyArgs { tagged: true, .. })
+    ));
+    for flag in ["--cross-check", "--low-memory", "--chunked", "--fused"] {
+        assert_eq!(
+            parse_err(&["verify", "abc", "--tagged", flag]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+}
+
+#[test]
+fn profile_rows_flags() {
+    let args = bench_args(&["bench", "abc", "--profile-rows"]);
+    assert!(args.profile_rows);
+    assert_eq!(args.profile_witness, None);
+    let args = bench_args(&["bench", "abc", "--profile-rows", "--profile-witness", "3"]);
+    assert_eq!(args.profile_witness, Some(3));
+    assert_eq!(
+        parse_err(&["bench", "abc", "--profile-witness", "3"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    for flags in [&["--spmm"][..], &["--low-memory"], &["--layout", "csc"]] {
+        let args = [&["bench", "abc", "--profile-rows"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+}
+
+#[test]
+fn convert_needs_a_target() {
+    assert_eq!(
+        parse(&["convert", "abc", "--to", "raw"]).command,
+        Command::Convert(ConvertArgs {
+            dump: hash("abc"),
+            to: Some(ConvertTarget::Raw),
+            from_mtx: None,
+            from_npz: None,
+            matrix: MatrixName::A,
+            rows_per_chunk: 1 << 16,
+            delta_indices: false,
+            prune: false,
+        })
+    );
+    assert_eq!(
+        parse_err(&["convert", "abc"]),
+        ErrorKind::MissingRequiredArgument
+    );
+}
+
+#[test]
+fn convert_imports_matrix_market_files() {
+    let command = parse(&["convert", "abc", "--from-mtx", "B.mtx", "--matrix", "B"]).command;
+    assert!(matches!(
+        command,
+        Command::Convert(ConvertArgs {
+            to: None,
+            from_mtx: Some(path),
+            matrix: MatrixName::B,
+            ..
+        }) if path == "B.mtx"
+    ));
+    assert_eq!(
+        parse_err(&["convert", "abc", "--from-mtx", "B.mtx", "--to", "raw"]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn convert_exports_matrix_market_files() {
+    let command = parse(&["convert", "abc", "--to", "mtx"]).command;
+    assert!(matches!(
+        command,
+        Command::Convert(ConvertArgs {
+            to: Some(ConvertTarget::Mtx),
+            from_mtx: None,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn convert_reads_and_writes_npz_files() {
+    let command = parse(&["convert", "abc", "--from-npz", "A.npz"]).command;
+    assert!(matches!(
+        command,
+        Command::Convert(ConvertArgs {
+            to: None,
+            from_npz: Some(path),
+            ..
+        }) if path == "A.npz"
+    ));
+    let command = parse(&["convert", "abc", "--to", "npz"]).command;
+    assert!(matches!(
+        command,
+        Command::Convert(ConvertArgs {
+            to: Some(ConvertTarget::Npz),
+            ..
+        })
+    ));
+    assert_eq!(
+        parse_err(&[
+            "convert",
+            "abc",
+            "--from-npz",
+            "A.npz",
+            "--from-mtx",
+            "A.mtx"
+        ]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn chunked_conversion_takes_a_chunk_size() {
+    let command = parse(&["convert", "abc", "--to", "chunked", "--rows-per-chunk", "7"]).command;
+    assert!(matches!(
+        command,
+        Command::Convert(ConvertArgs {
+            to: Some(ConvertTarget::Chunked),
+            rows_per_chunk: 7,
+            ..
+        })
+    ));
+    assert_eq!(
+        parse_err(&["convert", "abc", "--to", "chunked", "--rows-per-chunk", "0"]),
+        ErrorKind::ValueValidation
+    );
+    for flag in ["--low-memory", "--cross-check"] {
+        assert_eq!(
+            parse_err(&["verify", "abc", "--chunked", flag]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+}
+
+#[test]
+fn data_format_is_global() {
+    let cli = parse(&["verify", "abc", "--data-format", "raw"]);
+    assert_eq!(cli.global.data_format, DataFormat::Raw);
+    assert_eq!(
+        parse_err(&["--data-format", "json", "verify", "abc"]),
+        ErrorKind::InvalidValue
+    );
+}
+
+#[test]
+fn cache_clear_takes_an_optional_hash() {
+    let clear = |hash: Option<&str>| {
+        Command::Cache(CacheArgs {
+            action: CacheAction::Clear {
+                hash: hash.map(str::to_string),
+            },
+        })
+    };
+    assert_eq!(parse(&["cache", "clear"]).command, clear(None));
+    assert_eq!(
+        parse(&["cache", "clear", "abc"]).command,
+        clear(Some("abc"))
+    );
+    assert!(parse(&["bench", "abc", "--no-cache"]).global.no_cache);
+}
+
+#[test]
+fn manifest_create_takes_a_hash() {
+    assert_eq!(
+        parse(&["manifest", "create", "abc"]).command,
+        Command::Manifest(ManifestArgs {
+            action: ManifestAction::Create {
+                hash: "abc".to_string(),
+            },
+        })
+    );
+    assert!(
+        parse(&["verify", "abc", "--verify-files"])
+            .global
+            .verify_files
+    );
+}
+
+#[test]
+fn low_memory_conflicts_with_preloading_modes() {
+    assert!(bench_args(&["bench", "abc", "--low-memory"]).low_memory);
+    for flags in [&["--parallel-witnesses"][..], &["--sweep-threads", "1,2"]] {
+        let args = [&["bench", "abc", "--low-memory"][..], flags].concat();
+        assert_eq!(parse_err(&args), ErrorKind::ArgumentConflict);
+    }
+    assert_eq!(
+        parse_err(&["verify", "abc", "--low-memory", "--cross-check"]),
+        ErrorKind::ArgumentConflict
+    );
+}
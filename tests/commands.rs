@@ -0,0 +1,2174 @@
+//! End-to-end runs of the `spmvm` subcommands against small fixture dumps.
+
+mod common;
+
+use common::{matrix, stderr, stdout, witness, Fixture, HASH, SECONDARY_HASH};
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{
+        manifest::hash_file, matrices_section, result_section, witness_section, RawCodec, ReadPath,
+        HEADER_BYTES, MANIFEST_FILE,
+    },
+    folding::compute_cross_term,
+    hex::bytes_to_hex,
+    report::{BenchReport, MultiBenchReport},
+    sparse::ChunkPolicy,
+    SparseMatrix,
+};
+
+#[test]
+fn verify_passes_on_a_consistent_dump() {
+    let fixture = Fixture::new(3);
+    let output = fixture.run(&["verify", HASH]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(out.contains("witness 2: PASS"), "{out}");
+    assert!(
+        out.contains("3 passed, 0 failed, 0 mismatching vectors"),
+        "{out}"
+    );
+}
+
+#[test]
+fn verify_combined_checks_one_product_per_witness() {
+    let fixture = Fixture::new(3);
+    let output = fixture.run(&["verify", HASH, "--combined", "0x1234"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(
+        out.contains("verified 3 witnesses against A + r B + r^2 C with r = 0x"),
+        "{out}"
+    );
+    assert!(out.contains("3 passed, 0 failed"), "{out}");
+
+    let mut wrong = common::witness(1);
+    wrong[0] += Fr::from(1);
+    fixture
+        .config
+        .write(result_section(HASH), "BZ_1", &wrong)
+        .unwrap();
+    let output = fixture.run(&["verify", HASH, "--combined", "0x1234"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(out.contains("witness 1: FAIL (A + r B + r^2 C)"), "{out}");
+    assert!(out.contains("witness 1 combined: "), "{out}");
+    assert!(out.contains("2 passed, 1 failed"), "{out}");
+
+    // A witness that does not fit the matrices is an error, not a failed check.
+    fixture
+        .config
+        .write(witness_section(HASH), "_2", &vec![Fr::from(1); 4])
+        .unwrap();
+    let output = fixture.run(&["verify", HASH, "--combined", "0x1234"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stdout(&output));
+    assert!(
+        stderr(&output)
+            .contains("witness 2: a matrix of 3 columns cannot multiply a vector of 4 elements"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(
+        stdout(&output).contains("RESULT error"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn verify_keeps_going_after_a_mismatch() {
+    let fixture = Fixture::new(3);
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "AZ_0", &wrong)
+        .unwrap();
+    fixture
+        .config
+        .write(result_section(HASH), "CZ_0", &wrong)
+        .unwrap();
+
+    let output = fixture.run(&["verify", HASH]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    assert!(out.contains("witness 0: FAIL (AZ, CZ)"), "{out}");
+    // The fixtures are small enough to be drawn.
+    assert!(out.contains("A: 3x3 matrix, 3 entries\n"), "{out}");
+    assert!(out.contains("C: 3x3 matrix, 3 entries\n"), "{out}");
+    assert!(!out.contains("B: 3x3 matrix"), "{out}");
+    assert!(out.contains("witness 2: PASS"), "{out}");
+    assert!(
+        out.contains("2 passed, 1 failed, 2 mismatching vectors"),
+        "{out}"
+    );
+}
+
+/// Overwrites the matrices of the fixture with the constraints `z1 z1 = z2` and `z0 z2 = z2`,
+/// and witness 0 with `z`.
+fn write_r1cs(fixture: &Fixture, z: [u64; 3]) {
+    let pick = |cols: [usize; 2]| SparseMatrix {
+        data: vec![Fr::from(1); 2],
+        indices: cols.to_vec(),
+        indptr: vec![0, 1, 2],
+        cols: 3,
+    };
+    for (label, matrix) in [
+        ("A_0", pick([1, 0])),
+        ("B_0", pick([1, 2])),
+        ("C_0", pick([2, 2])),
+    ] {
+        fixture
+            .config
+            .write(matrices_section(HASH), label, &matrix)
+            .unwrap();
+    }
+    let z: Vec<Fr> = z.into_iter().map(Fr::from).collect();
+    fixture
+        .config
+        .write(witness_section(HASH), "_0", &z)
+        .unwrap();
+}
+
+#[test]
+fn check_r1cs_needs_no_expected_results() {
+    let fixture = Fixture::new(1).without_results();
+    write_r1cs(&fixture, [1, 3, 9]);
+    let output = fixture.run(&["check-r1cs", HASH]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("witness 0: satisfied"), "{out}");
+    assert!(
+        out.contains("checked 1 witnesses against AZ ∘ BZ = CZ: 1 satisfied, 0 not"),
+        "{out}"
+    );
+
+    write_r1cs(&fixture, [2, 3, 9]);
+    let output = fixture.run(&["check-r1cs", HASH]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(
+        out.contains("witness 0: NOT satisfied, 1 of 2 constraints violated\n  [1] AZ 0x"),
+        "{out}"
+    );
+    assert!(
+        out.contains("RESULT fail matrices=3 witnesses=1 mismatches=1"),
+        "{out}"
+    );
+}
+
+/// Writes the relaxed instance `u`, `e` of witness 0 to `section`.
+fn write_relaxed(fixture: &Fixture, section: &str, u: Fr, e: [Fr; 2]) {
+    fixture.config.write(section, "u_0", &vec![u]).unwrap();
+    fixture.config.write(section, "E_0", &e.to_vec()).unwrap();
+}
+
+#[test]
+fn check_r1cs_relaxed() {
+    // AZ = [3, 2] and BZ = [3, 9], so AZ ∘ BZ = [9, 18], and CZ = [9, 9].
+    let fixture = Fixture::new(1).without_results();
+    write_r1cs(&fixture, [2, 3, 9]);
+    let output = fixture.run(&["check-r1cs", HASH]);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout(&output));
+
+    let output = fixture.run(&["check-r1cs", HASH, "--relaxed"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("relaxed_fixture is missing E_0, u_0"),
+        "{}",
+        stderr(&output)
+    );
+
+    let section = "relaxed_fixture";
+    write_relaxed(&fixture, section, Fr::from(2), [-Fr::from(9), Fr::from(0)]);
+    let output = fixture.run(&["check-r1cs", HASH, "--relaxed"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("checked 1 witnesses against AZ ∘ BZ = u CZ + E: 1 satisfied, 0 not"),
+        "{out}"
+    );
+
+    write_relaxed(&fixture, "folded", Fr::from(2), [Fr::from(0); 2]);
+    let output = fixture.run(&[
+        "check-r1cs",
+        HASH,
+        "--relaxed",
+        "--relaxed-section",
+        "folded",
+    ]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(
+        out.contains("witness 0: NOT satisfied with u = 0x"),
+        "{out}"
+    );
+    assert!(
+        out.contains(", 1 of 2 constraints violated\n  [0] AZ 0x"),
+        "{out}"
+    );
+    assert!(out.contains(", E 0x"), "{out}");
+}
+
+#[test]
+fn check_r1cs_lists_at_most_limit_violations() {
+    // The fixture matrices do not make an R1CS: rows 0 and 2 of every witness are violated.
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["check-r1cs", HASH, "--limit", "1"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(
+        out.contains("witness 1: NOT satisfied, 2 of 3 constraints violated\n  [0] AZ 0x"),
+        "{out}"
+    );
+    assert!(out.contains("  ... and 1 more\n"), "{out}");
+    assert!(out.contains("0 satisfied, 2 not"), "{out}");
+    assert!(
+        stderr(&output).contains("2 witnesses do not satisfy the relation"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn cross_term_compares_against_the_dumped_one() {
+    let fixture = Fixture::new(3).without_results();
+    let output = fixture.run(&["cross-term", HASH, "--i", "2", "--j", "0", "--u1", "5"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("products of witnesses 2 and 0 took: "),
+        "{out}"
+    );
+    assert!(out.contains("cross term took: "), "{out}");
+    assert!(
+        out.contains("cross_term_fixture/T_2_0 not found, nothing to compare against"),
+        "{out}"
+    );
+
+    let [a, b, c] = [1, 4, 5].map(matrix);
+    let (z1, z2) = (witness(2), witness(0));
+    let mut t = compute_cross_term(&a, &b, &c, &z1, &z2, Fr::from(5), Fr::from(1)).unwrap();
+    fixture
+        .config
+        .write("cross_term_fixture", "T_2_0", &t)
+        .unwrap();
+    let output = fixture.run(&["cross-term", HASH, "--i", "2", "--j", "0", "--u1", "5"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("cross term of witnesses 2 and 0: PASS"),
+        "{out}"
+    );
+
+    t[1] += Fr::from(1);
+    fixture
+        .config
+        .write("cross_term_fixture", "T_2_0", &t)
+        .unwrap();
+    let output = fixture.run(&["cross-term", HASH, "--i", "2", "--j", "0", "--u1", "5"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(
+        out.contains("cross term of witnesses 2 and 0: FAIL\nT_2_0: 1 of 3 positions differ"),
+        "{out}"
+    );
+}
+
+#[test]
+fn bench_without_result_section_times_only() {
+    let fixture = Fixture::new(2).without_results();
+    let output = fixture.run(&["bench", HASH, "--warmup", "1"]);
+    let (out, err) = (stdout(&output), stderr(&output));
+    assert!(output.status.success(), "{out}{err}");
+    assert!(
+        err.contains("not found, timing without verification"),
+        "{err}"
+    );
+    assert!(out.contains("grand total over 2 witnesses"), "{out}");
+    assert!(out.contains("decoded witness 1 in "), "{out}");
+    assert!(out.contains("decoding 2 witnesses took: "), "{out}");
+}
+
+#[test]
+fn bench_no_verify_ignores_wrong_results() {
+    let fixture = Fixture::new(2);
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "BZ_1", &wrong)
+        .unwrap();
+
+    let output = fixture.run(&["bench", HASH, "--no-verify"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+}
+
+#[test]
+fn bench_reports_every_mismatch_before_failing() {
+    let fixture = Fixture::new(3);
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "BZ_0", &wrong)
+        .unwrap();
+    fixture
+        .config
+        .write(result_section(HASH), "AZ_2", &wrong)
+        .unwrap();
+
+    let output = fixture.run(&["bench", HASH, "--diff-limit", "1"]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    assert!(
+        out.contains("witness 0 BZ: 2 of 3 positions differ"),
+        "{out}"
+    );
+    assert!(out.contains("  ... and 1 more"), "{out}");
+    assert!(out.contains("grand total over 3 witnesses"), "{out}");
+    assert!(out.contains("2 products did not match:"), "{out}");
+    assert!(
+        out.contains("  witness 2 AZ: 2 differing positions"),
+        "{out}"
+    );
+}
+
+#[test]
+fn result_line_and_exit_codes() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["verify", HASH]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).ends_with("RESULT ok matrices=3 witnesses=2 mismatches=0\n"));
+
+    let output = fixture.run(&["verify", HASH, "--format", "json"]);
+    assert!(stdout(&output)
+        .ends_with("{\"result\":\"ok\",\"matrices\":3,\"witnesses\":2,\"mismatches\":0}\n"));
+
+    let output = fixture.run(&["verify", "nope"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stdout(&output).starts_with("RESULT error "),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = fixture.run(&["verify"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stdout(&output).starts_with("RESULT usage "),
+        "{}",
+        stdout(&output)
+    );
+
+    fixture
+        .config
+        .write(result_section(HASH), "CZ_1", &vec![Fr::from(0); 3])
+        .unwrap();
+    let output = fixture.run(&["verify", HASH]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).ends_with("RESULT fail matrices=3 witnesses=2 mismatches=1\n"));
+}
+
+#[test]
+fn selecting_no_witnesses_is_a_usage_error() {
+    let fixture = Fixture::new(4);
+    for args in [
+        &["bench", HASH, "--start", "10"][..],
+        &["bench", HASH, "--iterations", "0"],
+        &["verify", HASH, "--start", "4"],
+    ] {
+        let output = fixture.run(args);
+        let out = stdout(&output);
+        assert_eq!(output.status.code(), Some(3), "{args:?}: {out}");
+        assert!(out.starts_with("RESULT usage "), "{args:?}: {out}");
+        let err = stderr(&output);
+        assert!(
+            err.contains("selects no witnesses; the section contains 0..=3"),
+            "{err}"
+        );
+    }
+
+    let overflow = usize::MAX.to_string();
+    let output = fixture.run(&["bench", HASH, "--start", &overflow, "--iterations", "2"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(stderr(&output).contains("overflows"), "{}", stderr(&output));
+}
+
+#[test]
+fn list_sections_and_labels() {
+    let fixture = Fixture::new(11);
+    let output = fixture.run(&["list"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    let line = out
+        .lines()
+        .find(|l| l.starts_with("witness_fixture "))
+        .unwrap();
+    assert!(line.contains(" 11 files "), "{out}");
+
+    let output = fixture.run(&["list", "witness_fixture", "--format", "json"]);
+    let first_line = stdout(&output).lines().next().unwrap().to_string();
+    let listed: Vec<serde_json::Value> = serde_json::from_str(&first_line).unwrap();
+    let labels: Vec<_> = listed
+        .iter()
+        .map(|entry| entry["label"].as_str().unwrap())
+        .collect();
+    let expected: Vec<String> = (0..11).map(|i| format!("_{i}")).collect();
+    assert_eq!(labels, expected);
+    assert_eq!(
+        listed[0],
+        serde_json::json!({
+            "label": "_0",
+            "format": "bincode",
+            "header": {"version": 1, "format": "bincode", "element": "vector"},
+        })
+    );
+
+    // A dump from arecibo has no headers, so its format is guessed from the bytes.
+    let legacy = fixture
+        .config
+        .root_dir()
+        .join(witness_section(HASH))
+        .join("_11");
+    std::fs::write(legacy, bincode::serialize(&witness(11)).unwrap()).unwrap();
+    let out = stdout(&fixture.run(&["list", "witness_fixture"]));
+    assert!(out.contains("\n_10  bincode vector, header v1\n"), "{out}");
+    assert!(out.contains("\n_11  bincode, no header\n"), "{out}");
+}
+
+#[test]
+fn check_lists_every_missing_file() {
+    let fixture = Fixture::new(4);
+    let output = fixture.run(&["check", HASH]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(
+        out.contains("dump fixture is complete for 4 witnesses"),
+        "{out}"
+    );
+
+    let root = fixture.config.root_dir();
+    std::fs::remove_file(root.join(witness_section(HASH)).join("_1")).unwrap();
+    std::fs::remove_file(root.join(result_section(HASH)).join("BZ_3")).unwrap();
+    let output = fixture.run(&["check", HASH]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(
+        out.contains("missing from dump fixture:\n  witness_fixture/_1\n  result_fixture/BZ_3\n"),
+        "{out}"
+    );
+}
+
+#[test]
+fn check_prints_content_hashes() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["check", HASH, "--content-hash"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    for (name, k) in [("A", 1), ("B", 4), ("C", 5)] {
+        let hash = bytes_to_hex(&matrix(k).content_hash());
+        assert!(
+            out.contains(&format!("{name}: content hash {hash}\n")),
+            "{out}"
+        );
+    }
+    // The cache written by the first load holds the same matrices.
+    let again = stdout(&fixture.run(&["check", HASH, "--content-hash"]));
+    assert!(again.contains("(cache hit)"), "{again}");
+    let hashes = |out: &str| -> Vec<String> {
+        out.lines()
+            .filter(|line| line.contains("content hash"))
+            .map(str::to_string)
+            .collect()
+    };
+    assert_eq!(hashes(&again), hashes(&out));
+}
+
+#[test]
+fn bench_preflight_fails_before_timing() {
+    let fixture = Fixture::new(3);
+    let root = fixture.config.root_dir();
+    std::fs::remove_file(root.join(result_section(HASH)).join("AZ_2")).unwrap();
+
+    let output = fixture.run(&["bench", HASH]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(out.contains("  result_fixture/AZ_2"), "{out}");
+    assert!(!out.contains("timing: 0"), "{out}");
+
+    let output = fixture.run(&["bench", HASH, "--no-preflight"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(out.contains("timing: 2"), "{out}");
+}
+
+const GENERATE: [&str; 11] = [
+    "generate",
+    "synthetic",
+    "--rows",
+    "6",
+    "--cols",
+    "5",
+    "--nnz-per-row",
+    "2",
+    "--witnesses",
+    "3",
+    "--seed",
+];
+
+#[test]
+fn generated_dump_benches_cleanly() {
+    let fixture = Fixture::empty();
+    let output = fixture.run(&[&GENERATE[..], &["42"]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = fixture.run(&["bench", "synthetic", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("grand total over 3 witnesses"), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=3 mismatches=0\n"),
+        "{out}"
+    );
+}
+
+#[test]
+fn witnesses_that_do_not_fit_the_matrices_are_errors() {
+    let fixture = Fixture::empty();
+    let output = fixture.run(&[
+        "generate",
+        "synthetic",
+        "--rows",
+        "20",
+        "--cols",
+        "20",
+        "--witnesses",
+        "2",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    fixture
+        .config
+        .write(witness_section("synthetic"), "_0", &vec![Fr::from(1); 25])
+        .unwrap();
+
+    for args in [
+        &["verify", "synthetic"][..],
+        &["bench", "synthetic"],
+        &["bench", "synthetic", "--low-memory"],
+        &["bench", "synthetic", "--layout", "csc"],
+    ] {
+        let output = fixture.run(args);
+        let out = stdout(&output);
+        assert_eq!(output.status.code(), Some(2), "{args:?}: {out}");
+        assert!(
+            stderr(&output)
+                .contains("A: a matrix of 20 columns cannot multiply a vector of 25 elements"),
+            "{args:?}: {}",
+            stderr(&output)
+        );
+        assert!(
+            out.lines().last().unwrap().starts_with("RESULT error "),
+            "{out}"
+        );
+    }
+
+    // verify reports the witness as failed and goes on to the next one.
+    let output = fixture.run(&["verify", "synthetic"]);
+    let out = stdout(&output);
+    assert!(
+        out.contains(
+            "witness 0: FAIL (A: a matrix of 20 columns cannot multiply a vector of 25 elements)\n\
+             witness 1: PASS\n"
+        ),
+        "{out}"
+    );
+    assert!(
+        out.contains("verified 2 witnesses: 1 passed, 1 failed, 0 mismatching vectors"),
+        "{out}"
+    );
+    assert!(
+        out.ends_with("RESULT error matrices=3 witnesses=2 mismatches=0\n"),
+        "{out}"
+    );
+}
+
+#[test]
+fn generate_is_deterministic() {
+    let read_all = |fixture: &Fixture| {
+        let mut files = Vec::new();
+        for section in fixture.config.sections().unwrap() {
+            for label in fixture.config.labels(&section.name).unwrap() {
+                let path = fixture.config.root_dir().join(&section.name).join(&label);
+                files.push((section.name.clone(), label, std::fs::read(path).unwrap()));
+            }
+        }
+        files
+    };
+    let generate = |seed: &str| {
+        let fixture = Fixture::empty();
+        let output = fixture.run(&[&GENERATE[..], &[seed]].concat());
+        assert!(output.status.success(), "{}", stderr(&output));
+        read_all(&fixture)
+    };
+
+    let first = generate("7");
+    assert_eq!(first.len(), 3 + 3 + 9);
+    assert_eq!(first, generate("7"));
+    assert_ne!(first, generate("8"));
+}
+
+#[test]
+fn generate_rejects_impossible_rows() {
+    let fixture = Fixture::empty();
+    let output = fixture.run(&["generate", "x", "--cols", "2", "--nnz-per-row", "3"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("--nnz-per-row 3 exceeds --cols 2"));
+}
+
+#[test]
+fn regen_results_refuses_to_overwrite_without_force() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["regen-results", HASH]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stdout(&output).starts_with("RESULT usage "),
+        "{}",
+        stdout(&output)
+    );
+    assert!(
+        stderr(&output).contains("result_fixture already holds AZ_0, BZ_0, CZ_0 and 3 more"),
+        "{}",
+        stderr(&output)
+    );
+
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "BZ_1", &wrong)
+        .unwrap();
+    let output = fixture.run(&["regen-results", HASH, "--force"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = fixture.run(&["verify", HASH]);
+    assert!(output.status.success(), "{}", stdout(&output));
+}
+
+#[test]
+fn regen_results_fills_a_missing_section() {
+    let fixture = Fixture::new(3).without_results();
+    let output = fixture.run(&["regen-results", HASH]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(out.contains("witness 2: wrote AZ_2, BZ_2, CZ_2"), "{out}");
+    let output = fixture.run(&["verify", HASH]);
+    assert!(
+        stdout(&output).contains("3 passed, 0 failed"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn unselected_matrices_are_never_loaded() {
+    let fixture = Fixture::new(2);
+    let root = fixture.config.root_dir();
+    std::fs::write(root.join(matrices_section(HASH)).join("A_0"), b"garbage").unwrap();
+    std::fs::remove_file(root.join(result_section(HASH)).join("AZ_1")).unwrap();
+
+    let output = fixture.run(&["bench", HASH, "--matrices", "C,B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(!out.contains("AZ took"), "{out}");
+    assert!(out.contains("BZ took") && out.contains("CZ took"), "{out}");
+    assert!(
+        out.contains("A: skipped, not selected by --matrices"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["verify", HASH]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = fixture.run(&["bench", HASH, "--matrices", "X"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn reports_record_the_thread_count() {
+    let fixture = Fixture::new(1);
+    let json = fixture.dir.path().join("report.json");
+    let csv = fixture.dir.path().join("report.csv");
+    let run = |threads: &str| {
+        let output = fixture.run(&[
+            "bench",
+            HASH,
+            "--threads",
+            threads,
+            "--output",
+            json.to_str().unwrap(),
+            "--csv",
+            csv.to_str().unwrap(),
+        ]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let report = BenchReport::read_json(&json).unwrap();
+        let csv = std::fs::read_to_string(&csv).unwrap();
+        let csv_threads: Vec<_> = csv
+            .lines()
+            .skip(1)
+            .map(|l| l.rsplit(',').next().unwrap().to_string())
+            .collect();
+        (report.threads, csv_threads)
+    };
+
+    let (threads, csv_threads) = run("3");
+    assert_eq!(threads, 3);
+    assert_eq!(csv_threads, ["3", "3", "3"]);
+
+    let (threads, _) = run("0");
+    assert_eq!(threads, std::thread::available_parallelism().unwrap().get());
+}
+
+#[test]
+fn serial_backend_and_cross_check() {
+    let fixture = Fixture::new(2).without_results();
+    let output = fixture.run(&["bench", HASH, "--backend", "serial"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = fixture.run(&["verify", HASH, "--cross-check"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("2 passed, 0 failed"), "{out}");
+}
+
+#[cfg(feature = "sprs")]
+#[test]
+fn sprs_backend_verifies() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--backend", "sprs"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=2 mismatches=0\n"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["bench", HASH, "--backend", "sprs", "--layout", "csc"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("cannot be combined with --layout csc"));
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn convert_round_trips_through_nalgebra() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&["convert", HASH, "--to", "nalgebra"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains(
+            "A: converted to a 3x3 CsrMatrix of 3 entries, sorting 0 rows, and back unchanged"
+        ),
+        "{out}"
+    );
+}
+
+#[test]
+fn fused_products_match_the_separate_ones() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--fused", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("fused: "), "{out}");
+    assert!(out.contains("x the separate products"), "{out}");
+    assert!(out.contains("fused overall:"), "{out}");
+
+    let output = fixture.run(&["verify", HASH, "--fused"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("2 passed, 0 failed"), "{out}");
+
+    let output = fixture.run(&["verify", HASH, "--fused", "--matrices", "A,B"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("--fused multiplies A, B, and C together"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn sparse_witness_products_match_the_dense_ones() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--sparse-witness", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("witness 1: 3 of 3 entries are nonzero, 100.0%"),
+        "{out}"
+    );
+    for product in ["AZ", "BZ", "CZ"] {
+        assert!(out.contains(&format!("{product} sparse: ")), "{out}");
+    }
+    assert!(out.contains("x the dense product"), "{out}");
+}
+
+#[test]
+fn blocked_products_verify() {
+    let fixture = Fixture::new(2);
+    let json = fixture.dir.path().join("blocked.json");
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--backend",
+        "blocked",
+        "--block-cols",
+        "2",
+        "--output",
+        json.to_str().unwrap(),
+    ]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("cut 3 matrices into 6 blocks of 2 columns in "),
+        "{out}"
+    );
+    assert!(out.contains("RESULT ok matrices=3 witnesses=2"), "{out}");
+    let report = BenchReport::read_json(&json).unwrap();
+    assert!(report.conversion_ns.is_some());
+
+    let output = fixture.run(&["bench", HASH, "--block-cols", "2"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("--block-cols sets the width of the blocks of --backend blocked"),
+        "{}",
+        stderr(&output)
+    );
+
+    // Reports of products of the matrices as loaded record no conversion.
+    let output = fixture.run(&["bench", HASH, "--output", json.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report = BenchReport::read_json(&json).unwrap();
+    assert_eq!(report.conversion_ns, None);
+    assert!(!std::fs::read_to_string(&json)
+        .unwrap()
+        .contains("conversion_ns"));
+}
+
+#[test]
+fn ell_products_verify() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--backend", "ell", "--ell-width", "1"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("padded 3 matrices to ELL of at most 1 entries per row in "),
+        "{out}"
+    );
+    assert!(
+        out.contains("A: width 1, 2 padding slots, 1 rows and 2 of 3 nonzeros spilled"),
+        "{out}"
+    );
+    assert!(out.contains("RESULT ok matrices=3 witnesses=2"), "{out}");
+
+    let output = fixture.run(&["bench", HASH, "--ell-width", "1"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "A"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains(
+            "   ell of width 2: 3 padding slots, 50.0% of the array; 0 rows spilled, 0.0% of \
+             nonzeros"
+        ),
+        "{out}"
+    );
+    let output = fixture.run(&["stats", HASH, "--matrices", "A", "--ell-width", "1"]);
+    let out = stdout(&output);
+    assert!(
+        out.contains("   ell of width 1: 2 padding slots, 66.7% of the array; 1 rows spilled, "),
+        "{out}"
+    );
+}
+
+#[test]
+fn reordered_products_verify() {
+    let fixture = Fixture::new(2);
+    // Row 1 of every matrix is empty, so it goes last and the products are permuted.
+    let output = fixture.run(&["bench", HASH, "--reorder", "--warmup", "1", "--fused"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("reordered the rows of 3 matrices by first column in "),
+        "{out}"
+    );
+    assert!(out.contains("RESULT ok matrices=3 witnesses=2"), "{out}");
+
+    let output = fixture.run(&["bench", HASH, "--reorder", "--backend", "ell"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+}
+
+#[test]
+fn unchecked_products_verify() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--backend", "unchecked", "--warmup", "1"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("validated 3 matrices in "), "{out}");
+    assert!(out.contains("RESULT ok matrices=3 witnesses=2"), "{out}");
+
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--backend",
+        "unchecked",
+        "--sweep-threads",
+        "1",
+    ]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("--backend unchecked validates the CSR matrices once"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn tagged_products_match_the_plain_ones() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["bench", HASH, "--backend", "tagged"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("tagged 3 matrices in "), "{out}");
+    assert!(out.contains("; 1 of 9 nonzeros are 1 or -1"), "{out}");
+
+    let output = fixture.run(&["verify", HASH, "--tagged"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("2 passed, 0 failed"), "{out}");
+
+    let output = fixture.run(&["bench", HASH, "--backend", "tagged", "--low-memory"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("--backend tagged tags the CSR matrices once"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "A"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("   coefficients: 1 are 1 and 0 are -1, 33.3% of nonzeros"),
+        "{out}"
+    );
+}
+
+#[test]
+fn profile_rows_times_chunks_of_one_witness() {
+    let fixture = Fixture::new(2);
+    let json = fixture.dir.path().join("profile.json");
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--profile-rows",
+        "--profile-witness",
+        "1",
+        "--output",
+        json.to_str().unwrap(),
+    ]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("profiling witness 1 in chunks of 4096 rows"),
+        "{out}"
+    );
+    assert!(out.contains("AZ: 1 chunks took"), "{out}");
+    assert!(out.contains("    rows 0..3: 3 nnz, "), "{out}");
+    assert!(out.contains("  chunk times:"), "{out}");
+    assert!(out.contains("  work per thread:"), "{out}");
+
+    let report = BenchReport::read_json(&json).unwrap();
+    for entry in &report.matrices {
+        let profile = entry.row_profile.as_ref().unwrap();
+        assert_eq!((profile.witness, profile.chunks), (1, 1));
+        assert_eq!(profile.slowest[0].end_row, entry.rows);
+    }
+
+    // Without the flag the report has no profile at all.
+    let output = fixture.run(&["bench", HASH, "--output", json.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let contents = std::fs::read_to_string(&json).unwrap();
+    assert!(!contents.contains("row_profile"), "{contents}");
+}
+
+#[test]
+fn bench_csc_layout_verifies_products() {
+    let fixture = Fixture::new(2);
+    for backend in ["parallel", "serial"] {
+        let output = fixture.run(&[
+            "bench",
+            HASH,
+            "--layout",
+            "csc",
+            "--backend",
+            backend,
+            "--warmup",
+            "1",
+        ]);
+        let out = stdout(&output);
+        assert!(output.status.success(), "{out}{}", stderr(&output));
+        assert!(out.contains("converted 3 matrices to CSC in "), "{out}");
+        assert!(out.contains("grand total over 2 witnesses"), "{out}");
+    }
+
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "CZ_1", &wrong)
+        .unwrap();
+    let output = fixture.run(&["bench", HASH, "--layout", "csc"]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    assert!(out.contains("  witness 1 CZ: "), "{out}");
+}
+
+#[test]
+fn both_partitions_verify() {
+    let fixture = Fixture::new(2);
+    for partition in ["rows", "nnz"] {
+        let output = fixture.run(&["bench", HASH, "--partition", partition, "--repeat", "2"]);
+        let out = stdout(&output);
+        assert!(
+            output.status.success(),
+            "{partition}: {out}{}",
+            stderr(&output)
+        );
+        assert!(
+            out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+            "{out}"
+        );
+    }
+}
+
+#[test]
+fn tune_records_the_chosen_policy() {
+    let fixture = Fixture::new(2);
+    let json = fixture.dir.path().join("tuned.json");
+    let output = fixture.run(&["bench", HASH, "--tune", "--output", json.to_str().unwrap()]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("tuning the chunk policy on witness 0:"),
+        "{out}"
+    );
+    assert!(out.contains("  rows=1024: "), "{out}");
+    let chosen = out
+        .lines()
+        .find_map(|line| line.strip_prefix("chose "))
+        .unwrap_or_else(|| panic!("{out}"));
+    let report = BenchReport::read_json(&json).unwrap();
+    assert_eq!(report.chunk_policy.to_string(), chosen);
+
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--chunk-policy",
+        "nnz=2",
+        "--output",
+        json.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report = BenchReport::read_json(&json).unwrap();
+    assert_eq!(report.chunk_policy, ChunkPolicy::Nnz(2));
+}
+
+#[test]
+fn sweep_threads_tabulates_speedups() {
+    let fixture = Fixture::new(2);
+    let json = fixture.dir.path().join("sweep.json");
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--sweep-threads",
+        "1,2",
+        "--output",
+        json.to_str().unwrap(),
+    ]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("threads 1:"), "{out}");
+    assert!(out.contains("threads 2:"), "{out}");
+    assert!(out.contains("vs 1t"), "{out}");
+    let speedups: Vec<_> = out.lines().filter(|l| l.ends_with('x')).collect();
+    assert_eq!(speedups.len(), 6, "{out}");
+    assert!(speedups[0].contains("1.00x"), "{out}");
+    assert!(
+        out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+
+    let report = BenchReport::read_json(&json).unwrap();
+    assert!(report.is_sweep());
+    assert_eq!(report.threads, 2);
+    let threads: Vec<_> = report.matrices[0]
+        .witnesses
+        .iter()
+        .map(|t| (t.witness, t.threads))
+        .collect();
+    assert_eq!(threads, [(0, 1), (1, 1), (0, 2), (1, 2)]);
+
+    let output = fixture.run(&["compare", json.to_str().unwrap(), json.to_str().unwrap()]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.starts_with("threads witness"), "{out}");
+    assert!(
+        out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+}
+
+#[test]
+fn parallel_witnesses_verifies_each_slot() {
+    let fixture = Fixture::new(4);
+    let output = fixture.run(&["bench", HASH, "--parallel-witnesses", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("holding 4 witnesses and their products in memory: about 2.62 KiB"),
+        "{out}"
+    );
+    assert!(out.contains("batch 1: 4 witnesses took"), "{out}");
+    assert!(out.contains("batch overall:"), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=4 mismatches=0\n"),
+        "{out}"
+    );
+
+    // A wrong result is pinned on its own witness, not whichever finished first.
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "CZ_2", &wrong)
+        .unwrap();
+    let output = fixture.run(&["bench", HASH, "--parallel-witnesses"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(out.contains("witness 2 CZ:"), "{out}");
+    assert!(
+        out.contains("RESULT fail matrices=3 witnesses=4 mismatches=1"),
+        "{out}"
+    );
+}
+
+#[test]
+fn spmm_verifies_every_witness() {
+    let fixture = Fixture::new(4);
+    let output = fixture.run(&["bench", HASH, "--spmm", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("round 1: one at a time took"), "{out}");
+    assert!(out.contains("spmm overall:"), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=4 mismatches=0\n"),
+        "{out}"
+    );
+
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "AZ_3", &wrong)
+        .unwrap();
+    let output = fixture.run(&["bench", HASH, "--spmm"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(out.contains("witness 3 AZ:"), "{out}");
+    assert!(
+        out.contains("RESULT fail matrices=3 witnesses=4 mismatches=1"),
+        "{out}"
+    );
+}
+
+#[test]
+fn validate_reports_the_offending_row_and_entry() {
+    let fixture = Fixture::new(1);
+    let section = fixture.config.root_dir().join(matrices_section(HASH));
+    let invalid = |name: &str| {
+        let path = format!(
+            "{}/tests/fixtures/invalid/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        std::fs::copy(path, section.join("A_0")).unwrap();
+    };
+
+    invalid("bad_index");
+    let output = fixture.run(&["--validate", "verify", HASH]);
+    let err = stderr(&output);
+    assert_eq!(output.status.code(), Some(2), "{err}");
+    assert!(
+        err.contains(
+            "invalid A: row 1: column 7 of the entry at 2 is out of bounds of the 3 columns"
+        ),
+        "{err}"
+    );
+    // `stats` validates without being asked.
+    let output = fixture.run(&["stats", HASH]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("invalid A: row 1: "));
+    // Nor does the unchecked kernel, before any product.
+    let output = fixture.run(&["bench", HASH, "--backend", "unchecked", "--no-preflight"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("invalid A: row 1: "));
+
+    invalid("decreasing_indptr");
+    let output = fixture.run(&["--validate", "bench", HASH]);
+    let err = stderr(&output);
+    assert_eq!(output.status.code(), Some(2), "{err}");
+    assert!(
+        err.contains("invalid A: indptr decreases at row 1, from 3 to 1"),
+        "{err}"
+    );
+
+    // Mismatched lengths fail as the matrix is read, with or without validation.
+    invalid("mismatched_lengths");
+    let output = fixture.run(&["verify", HASH]);
+    let err = stderr(&output);
+    assert_eq!(output.status.code(), Some(2), "{err}");
+    assert!(err.contains("failed to load A: "), "{err}");
+    assert!(err.contains("4 values but 3 column indices"), "{err}");
+
+    let output = fixture.run(&["--validate", "stats", HASH, "--matrices", "B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("validated B in "), "{out}");
+}
+
+#[test]
+fn every_matrix_that_fails_to_load_is_named() {
+    let fixture = Fixture::new(1);
+    let section = fixture.config.root_dir().join(matrices_section(HASH));
+    std::fs::write(section.join("A_0"), b"garbage").unwrap();
+    std::fs::remove_file(section.join("C_0")).unwrap();
+
+    let output = fixture.run(&["stats", HASH]);
+    let err = stderr(&output);
+    assert_eq!(output.status.code(), Some(2), "{err}");
+    assert!(err.contains("failed to load A: "), "{err}");
+    assert!(err.contains("is corrupt"), "{err}");
+    assert!(err.contains("; C: missing data file"), "{err}");
+    assert!(!err.contains("B: "), "{err}");
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.starts_with("loaded B in "), "{out}");
+    assert!(
+        out.contains("   disk: 184 B as bincode, 192 B raw with 4-byte indices"),
+        "{out}"
+    );
+}
+
+#[test]
+fn mmap_read_path_is_recorded() {
+    let fixture = Fixture::new(2);
+    let json = fixture.dir.path().join("report.json");
+    let output = fixture.run(&["--mmap", "bench", HASH, "--output", json.to_str().unwrap()]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+    assert_eq!(
+        BenchReport::read_json(&json).unwrap().read_path,
+        ReadPath::Mmap
+    );
+
+    let output = fixture.run(&["bench", HASH, "--output", json.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        BenchReport::read_json(&json).unwrap().read_path,
+        ReadPath::Buffered
+    );
+}
+
+#[cfg(feature = "rkyv")]
+#[test]
+fn convert_then_bench_archived() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["convert", HASH, "--to", "rkyv"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("A: wrote ") && out.contains("A_0.rkyv"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["bench", HASH, "--archived", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("mapped A in "), "{out}");
+    assert!(
+        out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+}
+
+#[test]
+fn low_memory_reports_mismatches_like_the_default() {
+    let fixture = Fixture::new(2);
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "BZ_1", &wrong)
+        .unwrap();
+
+    let default = stdout(&fixture.run(&["verify", HASH, "--no-cache"]));
+    let output = fixture.run(&["verify", HASH, "--no-cache", "--low-memory"]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    let diffs = |out: &str| -> Vec<String> {
+        out.lines()
+            .filter(|line| !line.starts_with("loaded "))
+            .map(str::to_string)
+            .collect()
+    };
+    assert_eq!(diffs(&out), diffs(&default));
+    assert!(out.contains("witness 1: FAIL (BZ)"), "{out}");
+
+    let output = fixture.run(&["bench", HASH, "--low-memory", "--repeat", "2"]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    assert!(out.contains("witness 1 BZ: "), "{out}");
+    assert!(out.contains("AZ: "), "{out}");
+}
+
+#[test]
+fn second_run_loads_from_the_cache() {
+    let fixture = Fixture::new(1);
+    let out = stdout(&fixture.run(&["verify", HASH]));
+    assert!(
+        out.contains("loaded A in ") && out.contains(" (cache miss)"),
+        "{out}"
+    );
+    let out = stdout(&fixture.run(&["verify", HASH]));
+    assert!(
+        out.contains(" (cache hit)") && !out.contains("miss"),
+        "{out}"
+    );
+    assert!(out.contains("1 passed, 0 failed"), "{out}");
+
+    let out = stdout(&fixture.run(&["verify", HASH, "--no-cache"]));
+    assert!(
+        out.contains("loaded A in ") && !out.contains("cache"),
+        "{out}"
+    );
+
+    let out = stdout(&fixture.run(&["cache", "clear", HASH]));
+    assert!(out.contains("removed 6 cache files"), "{out}");
+    let out = stdout(&fixture.run(&["verify", HASH]));
+    assert!(out.contains(" (cache miss)"), "{out}");
+}
+
+#[test]
+fn raw_dump_verifies_like_the_bincode_one() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["convert", HASH, "--to", "raw"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("A: wrote ") && out.contains("A_0.raw"),
+        "{out}"
+    );
+    assert!(
+        out.contains("witness 1: wrote ") && out.contains("_1.raw"),
+        "{out}"
+    );
+    assert!(
+        out.contains("RESULT ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+    let result = fixture.config.root_dir().join(result_section(HASH));
+    assert!(result.join("CZ_1.raw").is_file());
+
+    let output = fixture.run(&["verify", HASH, "--data-format", "raw"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("2 passed, 0 failed, 0 mismatching vectors"),
+        "{out}"
+    );
+
+    let witness = fixture.config.root_dir().join(witness_section(HASH));
+    std::fs::write(witness.join("_1.raw"), b"not raw").unwrap();
+    let output = fixture.run(&["verify", HASH, "--data-format", "raw"]);
+    let err = stderr(&output);
+    assert_eq!(output.status.code(), Some(2), "{err}");
+    assert!(err.contains("_1.raw is corrupt, reconvert it"), "{err}");
+}
+
+#[test]
+fn delta_encoded_dump_verifies_like_the_bincode_one() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["convert", HASH, "--to", "chunked", "--delta-indices"]);
+    let err = stderr(&output);
+    assert!(!output.status.success(), "{err}");
+    assert!(
+        err.contains("--delta-indices only applies to --to raw"),
+        "{err}"
+    );
+
+    let output = fixture.run(&["convert", HASH, "--to", "raw", "--delta-indices"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+
+    let output = fixture.run(&["verify", HASH, "--data-format", "raw"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("2 passed, 0 failed, 0 mismatching vectors"),
+        "{out}"
+    );
+}
+
+#[test]
+fn chunked_dump_verifies_like_the_bincode_one() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["verify", HASH, "--chunked"]);
+    let err = stderr(&output);
+    assert!(!output.status.success(), "{err}");
+    assert!(err.contains("A_0.chunked"), "{err}");
+
+    let output = fixture.run(&["convert", HASH, "--to", "chunked", "--rows-per-chunk", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("A: wrote ") && out.contains("A_0.chunked"),
+        "{out}"
+    );
+    assert!(out.contains("  2 chunks of 2 rows"), "{out}");
+
+    let output = fixture.run(&["verify", HASH, "--chunked"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("opened A: 3 rows in 2 chunks of 2"), "{out}");
+    assert!(
+        out.contains("2 passed, 0 failed, 0 mismatching vectors"),
+        "{out}"
+    );
+
+    let wrong = vec![Fr::from(0); 3];
+    fixture
+        .config
+        .write(result_section(HASH), "CZ_0", &wrong)
+        .unwrap();
+    let output = fixture.run(&["verify", HASH, "--chunked"]);
+    let out = stdout(&output);
+    assert!(!output.status.success(), "{out}");
+    assert!(out.contains("witness 0: FAIL (CZ)"), "{out}");
+
+    // A witness that does not fit is a data error, as with matrices loaded whole.
+    fixture
+        .config
+        .write(witness_section(HASH), "_1", &vec![Fr::from(1); 4])
+        .unwrap();
+    let output = fixture.run(&["verify", HASH, "--chunked"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(
+        out.contains(
+            "witness 1: FAIL (A: a matrix of 3 columns cannot multiply a vector of 4 elements)"
+        ),
+        "{out}"
+    );
+}
+
+#[test]
+fn compressed_dump_verifies_like_the_plain_one() {
+    let plain = Fixture::empty();
+    let packed = Fixture::empty();
+    let generate = [
+        "generate",
+        "gen",
+        "--rows",
+        "20",
+        "--cols",
+        "20",
+        "--nnz-per-row",
+        "3",
+    ];
+    assert!(plain.run(&generate).status.success());
+    let output = packed.run(&[&["--compress"], &generate[..]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let root = packed.config.root_dir();
+    assert!(root.join("sparse_matrices_gen/A_0.zst").exists());
+    assert!(!root.join("sparse_matrices_gen/A_0").exists());
+    for section in ["sparse_matrices_gen", "witness_gen", "result_gen"] {
+        assert_eq!(
+            packed.config.labels(section).unwrap(),
+            plain.config.labels(section).unwrap()
+        );
+    }
+
+    // Load times differ, everything else must not.
+    let untimed = |output: &std::process::Output| {
+        let out = stdout(output);
+        out.lines()
+            .filter(|line| !line.starts_with("loaded "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    for args in [&["verify", "gen"][..], &["check", "gen"]] {
+        let (a, b) = (plain.run(args), packed.run(args));
+        assert!(b.status.success(), "{}{}", stdout(&b), stderr(&b));
+        assert_eq!(untimed(&a), untimed(&b));
+    }
+    let out = stdout(&packed.run(&["list", "result_gen"]));
+    assert!(
+        out.starts_with("AZ_0  bincode+zstd vector, header v1\n"),
+        "{out}"
+    );
+}
+
+#[test]
+fn verify_files_names_the_corrupted_file() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["verify", HASH, "--verify-files"]);
+    let (out, err) = (stdout(&output), stderr(&output));
+    assert!(output.status.success(), "{out}{err}");
+    assert!(err.is_empty(), "{err}");
+    // Three matrices, two witnesses, and six expected results.
+    assert!(out.contains("verified 11 files ("), "{out}");
+
+    // A dump copied without its manifests gets new ones from `manifest create`.
+    let witnesses = fixture.config.root_dir().join(witness_section(HASH));
+    std::fs::remove_file(witnesses.join(MANIFEST_FILE)).unwrap();
+    let output = fixture.run(&["verify", HASH, "--verify-files"]);
+    let err = stderr(&output);
+    assert!(output.status.success(), "{err}");
+    assert!(
+        err.contains("witness_fixture/_1 is not in a manifest, so it is not verified"),
+        "{err}"
+    );
+    let out = stdout(&fixture.run(&["manifest", "create", HASH]));
+    assert!(out.contains("witness_fixture: hashed 2 files ("), "{out}");
+    assert!(out.contains("result_fixture: hashed 6 files ("), "{out}");
+
+    let path = witnesses.join("_1");
+    let expected = hash_file(&path).unwrap().hash;
+    let mut bytes = std::fs::read(&path).unwrap();
+    // The lowest bit of the first field element, after the header and the length.
+    bytes[HEADER_BYTES + 8] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let actual = hash_file(&path).unwrap().hash;
+
+    let output = fixture.run(&["verify", HASH, "--verify-files"]);
+    let err = stderr(&output);
+    assert!(!output.status.success(), "{err}");
+    assert!(
+        err.contains(&format!("{path} does not match its manifest")),
+        "{err}"
+    );
+    assert!(
+        err.contains(&format!("expected hash {expected}, found {actual}")),
+        "{err}"
+    );
+    // Without the flag the corruption goes unnoticed until the products disagree.
+    let output = fixture.run(&["verify", HASH]);
+    assert!(
+        stdout(&output).contains("witness 1: FAIL"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn both_circuits_verify_separately() {
+    let fixture = Fixture::new(2).with_secondary(3);
+    let output = fixture.run(&["verify", HASH, "--circuit", "both"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains(&format!("== primary circuit: {HASH} ==")),
+        "{out}"
+    );
+    assert!(
+        out.contains(&format!("== secondary circuit: {SECONDARY_HASH} ==")),
+        "{out}"
+    );
+    assert!(
+        out.contains("primary circuit: ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+    assert!(
+        out.contains("secondary circuit: ok matrices=3 witnesses=3 mismatches=0"),
+        "{out}"
+    );
+    assert!(
+        out.ends_with("RESULT ok matrices=6 witnesses=5 mismatches=0\n"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["verify", HASH, "--circuit", "secondary"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(!out.contains("== "), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=3 mismatches=0\n"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["--format", "json", "verify", HASH, "--circuit", "both"]);
+    let line = stdout(&output).lines().last().unwrap().to_string();
+    let result: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(result["witnesses"], 5);
+    assert_eq!(result["circuits"][0]["circuit"], "primary");
+    assert_eq!(result["circuits"][1]["circuit"], "secondary");
+    assert_eq!(result["circuits"][1]["witnesses"], 3);
+}
+
+#[test]
+fn both_circuits_bench_into_separate_reports() {
+    let fixture = Fixture::new(1).with_secondary(1);
+    let json = fixture.dir.path().join("report.json");
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        "--circuit",
+        "both",
+        "--output",
+        json.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!json.exists());
+
+    let primary = BenchReport::read_json(fixture.dir.path().join("report.primary.json")).unwrap();
+    let secondary =
+        BenchReport::read_json(fixture.dir.path().join("report.secondary.json")).unwrap();
+    assert_eq!(primary.hash, HASH);
+    assert_eq!(secondary.hash, SECONDARY_HASH);
+    let shapes = |report: &BenchReport| -> Vec<_> {
+        report
+            .matrices
+            .iter()
+            .map(|m| (m.rows, m.cols, m.nnz))
+            .collect()
+    };
+    assert_eq!(shapes(&primary), [(3, 3, 3); 3]);
+    assert_eq!(shapes(&secondary), [(2, 4, 3); 3]);
+}
+
+#[test]
+fn a_failing_circuit_does_not_stop_the_other() {
+    let fixture = Fixture::new(2);
+    let output = fixture.run(&["verify", HASH, "--circuit", "both"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(
+        out.contains("primary circuit: ok matrices=3 witnesses=2 mismatches=0"),
+        "{out}"
+    );
+    assert!(out.contains("secondary circuit: error"), "{out}");
+    assert!(out.contains("RESULT error matrices=3 witnesses=2"), "{out}");
+}
+
+#[test]
+fn several_hashes_bench_into_one_report() {
+    let fixture = Fixture::new(2).with_secondary(1);
+    let json = fixture.dir.path().join("report.json");
+    let csv = fixture.dir.path().join("report.csv");
+    let output = fixture.run(&[
+        "bench",
+        HASH,
+        SECONDARY_HASH,
+        "--output",
+        json.to_str().unwrap(),
+        "--csv",
+        csv.to_str().unwrap(),
+    ]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains(&format!("=== {SECONDARY_HASH} ===")), "{out}");
+    assert!(
+        out.contains(&format!("{HASH}: ok matrices=3 witnesses=2 mismatches=0")),
+        "{out}"
+    );
+    let header = out
+        .lines()
+        .position(|line| line.starts_with("hash "))
+        .expect(&out);
+    let rows: Vec<Vec<_>> = out.lines().collect::<Vec<_>>()[header + 1..header + 7]
+        .iter()
+        .map(|line| line.split_whitespace().take(4).collect())
+        .collect();
+    assert_eq!(rows[0], [HASH, "A", "3", "3"]);
+    assert_eq!(rows[5], [SECONDARY_HASH, "C", "2", "3"]);
+    assert!(
+        out.ends_with("RESULT ok matrices=6 witnesses=3 mismatches=0\n"),
+        "{out}"
+    );
+
+    let report = MultiBenchReport::read_json(&json).unwrap();
+    let hashes: Vec<_> = report
+        .hashes
+        .iter()
+        .map(|dump| dump.hash.as_str())
+        .collect();
+    assert_eq!(hashes, [HASH, SECONDARY_HASH]);
+    assert_eq!(report.hashes[1].reports[0].matrices[0].cols, 4);
+    assert!(fixture.dir.path().join("report.fixture.csv").exists());
+    assert!(fixture
+        .dir
+        .path()
+        .join("report.fixture_secondary.csv")
+        .exists());
+}
+
+#[test]
+fn a_failing_hash_does_not_stop_the_others() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&[&GENERATE[..], &["42"]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    fixture
+        .config
+        .write(result_section(HASH), "AZ_0", &vec![Fr::from(0); 3])
+        .unwrap();
+
+    let output = fixture.run(&["--format", "json", "bench", "missing", HASH, "synthetic"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(out.contains("missing    failed\n"), "{out}");
+    assert!(
+        out.contains("synthetic: ok matrices=3 witnesses=3"),
+        "{out}"
+    );
+    let result: serde_json::Value = serde_json::from_str(out.lines().last().unwrap()).unwrap();
+    assert_eq!(result["result"], "error");
+    assert_eq!(result["witnesses"], 4);
+    assert_eq!(result["mismatches"], 1);
+    assert_eq!(result["hashes"][0]["result"], "error");
+    assert_eq!(result["hashes"][1]["result"], "fail");
+    assert_eq!(result["hashes"][1]["mismatches"], 1);
+    assert_eq!(result["hashes"][2]["result"], "ok");
+}
+
+#[test]
+fn all_benches_every_dump_under_the_root() {
+    let fixture = Fixture::new(1).with_secondary(1);
+    let output = fixture.run(&[&GENERATE[..], &["42"]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // The secondary circuit is not a dump of its own.
+    let output = fixture.run(&["bench", "--all"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains(&format!("=== {HASH} ===")), "{out}");
+    assert!(out.contains("=== synthetic ==="), "{out}");
+    assert!(!out.contains(SECONDARY_HASH), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=6 witnesses=4 mismatches=0\n"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["bench", "--all", "--circuit", "both"]);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(2), "{out}");
+    assert!(
+        out.contains(&format!("== secondary circuit: {SECONDARY_HASH} ==")),
+        "{out}"
+    );
+    assert!(out.contains("synthetic: error"), "{out}");
+
+    let output = Fixture::empty().run(&["bench", "--all"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stderr(&output).contains("--all found no sparse_matrices_* sections"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn bench_times_a_witness_on_stdin() {
+    let fixture = Fixture::new(1).without_results();
+    let z = bincode::serialize(&witness(7)).unwrap();
+    let output = fixture.run_with_stdin(&["bench", HASH, "--witness-stdin", "--warmup", "1"], &z);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("decoded the witness on stdin in "), "{out}");
+    assert!(out.contains("warmup 0: witness on stdin"), "{out}");
+    assert!(out.contains("timing: 0"), "{out}");
+    assert!(out.contains("products were not verified"), "{out}");
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=1 mismatches=0\n"),
+        "{out}"
+    );
+
+    // Raw witnesses are detected by their magic bytes.
+    let mut raw = Vec::new();
+    witness(7).write_raw(&mut raw).unwrap();
+    let output = fixture.run_with_stdin(&["bench", HASH, "--witness-stdin"], &raw);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn bench_verifies_a_witness_on_stdin_against_expected_dir() {
+    let fixture = Fixture::new(1);
+    let expected = tempfile::tempdir().unwrap();
+    let z = witness(7);
+    for (name, matrix) in [("AZ", matrix(1)), ("BZ", matrix(4)), ("CZ", matrix(5))] {
+        let product = bincode::serialize(&matrix.multiply_vec(&z)).unwrap();
+        std::fs::write(expected.path().join(name), product).unwrap();
+    }
+    let dir = expected.path().to_str().unwrap();
+    let input = bincode::serialize(&z).unwrap();
+    let args = ["bench", HASH, "--witness-stdin", "--expected-dir", dir];
+    let output = fixture.run_with_stdin(&args, &input);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(!out.contains("not verified"), "{out}");
+
+    let wrong = bincode::serialize(&vec![Fr::from(0); 3]).unwrap();
+    std::fs::write(expected.path().join("BZ"), wrong).unwrap();
+    let output = fixture.run_with_stdin(&args, &input);
+    let out = stdout(&output);
+    assert_eq!(output.status.code(), Some(1), "{out}");
+    assert!(out.contains("witness 0 BZ: "), "{out}");
+    assert!(
+        out.ends_with("RESULT fail matrices=3 witnesses=1 mismatches=1\n"),
+        "{out}"
+    );
+
+    std::fs::remove_file(expected.path().join("CZ")).unwrap();
+    let output = fixture.run_with_stdin(&args, &input);
+    assert_eq!(output.status.code(), Some(2), "{}", stdout(&output));
+}
+
+#[test]
+fn bench_rejects_bad_witnesses_on_stdin() {
+    let fixture = Fixture::new(1);
+    let run = |input: &[u8]| fixture.run_with_stdin(&["bench", HASH, "--witness-stdin"], input);
+
+    let output = run(&bincode::serialize(&vec![Fr::from(1); 2]).unwrap());
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        stderr(&output).contains("the witness on stdin has 2 elements, but A has 3 columns"),
+        "{}",
+        stderr(&output)
+    );
+
+    // Longer witnesses than the matrices take, or length prefixes claiming them, are not read.
+    let output = run(&bincode::serialize(&vec![Fr::from(1); 4]).unwrap());
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("<stdin>"), "{}", stderr(&output));
+    let mut huge = u64::MAX.to_le_bytes().to_vec();
+    huge.extend([0; 96]);
+    let output = run(&huge);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("<stdin>"), "{}", stderr(&output));
+
+    let output = fixture.run_with_stdin(&["bench", HASH, "other", "--witness-stdin"], &[]);
+    assert_eq!(output.status.code(), Some(3));
+    let output = fixture.run(&["bench", HASH, "--expected-dir", "."]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn stats_counts_and_convert_prunes_explicit_zeros() {
+    let fixture = Fixture::new(1);
+    // `matrix(4)` with zeros at the start of the first row and all through the second.
+    let zeros = SparseMatrix {
+        data: [0, 4, 8, 0, 0, 12].map(Fr::from).to_vec(),
+        indices: vec![1, 0, 2, 0, 2, 1],
+        indptr: vec![0, 3, 5, 6],
+        cols: 3,
+    };
+    fixture
+        .config
+        .write(matrices_section(HASH), "B_0", &zeros)
+        .unwrap();
+    let output = fixture.run(&["verify", HASH]);
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "A,B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("   explicit zeros: 0, 0.0% of nonzeros"),
+        "{out}"
+    );
+    assert!(
+        out.contains("   explicit zeros: 3, 50.0% of nonzeros"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["convert", HASH, "--to", "mtx", "--prune"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("A: pruned 0 explicit zeros"), "{out}");
+    assert!(out.contains("B: pruned 3 explicit zeros"), "{out}");
+    let section = fixture.config.root_dir().join(matrices_section(HASH));
+    let file = std::fs::File::open(section.join("B_0.mtx")).unwrap();
+    let exported = SparseMatrix::<Fr>::from_matrix_market(file).unwrap();
+    assert_eq!(exported.nnz(), 3);
+    assert_eq!(exported.to_dense(), matrix(4).to_dense());
+    let output = fixture.run(&["convert", HASH, "--to", "chunked", "--prune"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout(&output));
+}
+
+#[test]
+fn stats_and_check_list_duplicate_entries() {
+    let fixture = Fixture::new(1);
+    // 60 rows that each store column 1 twice, and the last column 0 three times.
+    let rows = 60;
+    let mut indices: Vec<usize> = (0..rows).flat_map(|_| [1, 2, 1]).collect();
+    indices.extend([0, 0, 0]);
+    let injected = SparseMatrix {
+        data: vec![Fr::from(1); indices.len()],
+        indptr: (0..=rows)
+            .map(|row| 3 * row)
+            .chain([3 * rows + 3])
+            .collect(),
+        indices,
+        cols: 3,
+    };
+    fixture
+        .config
+        .write(matrices_section(HASH), "B_0", &injected)
+        .unwrap();
+
+    let output = fixture.run(&["check", HASH, "--duplicates"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("A: no duplicate entries"), "{out}");
+    assert!(
+        out.contains("B: 61 coordinates stored more than once, 62 extra entries"),
+        "{out}"
+    );
+    assert!(out.contains("\n  row 0, column 1: 2 entries\n"), "{out}");
+    assert!(out.contains("\n  row 49, column 1: 2 entries\n"), "{out}");
+    assert!(!out.contains("row 50, column 1"), "{out}");
+    assert!(out.contains("\n  ... and 11 more\n"), "{out}");
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("   duplicates: 61 coordinates stored more than once, 62 extra entries"),
+        "{out}"
+    );
+    assert!(out.contains("\n     row 0, column 1: 2 entries\n"), "{out}");
+    assert!(out.contains("\n     ... and 11 more\n"), "{out}");
+    let output = fixture.run(&["stats", HASH, "--matrices", "A"]);
+    assert!(
+        !stdout(&output).contains("duplicates"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn convert_imports_a_matrix_market_file() {
+    let fixture = Fixture::new(1);
+    let mtx = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/matrix_market/symmetric.mtx"
+    );
+    let output = fixture.run(&["convert", HASH, "--from-mtx", mtx, "--matrix", "B"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.contains("B: read ") && out.contains("(3x3, 6 entries)"),
+        "{out}"
+    );
+    assert!(
+        out.ends_with("RESULT ok matrices=1 witnesses=0 mismatches=0\n"),
+        "{out}"
+    );
+    let imported: SparseMatrix<Fr> = fixture.config.read(matrices_section(HASH), "B_0").unwrap();
+    let expected = SparseMatrix::from_matrix_market(std::fs::File::open(mtx).unwrap()).unwrap();
+    assert_eq!(imported, expected);
+    // The products no longer match those dumped for the old matrix.
+    let output = fixture.run(&["verify", HASH]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = fixture.run(&["convert", HASH, "--from-mtx", "missing.mtx"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("missing.mtx"),
+        "{}",
+        stderr(&output)
+    );
+    let output = fixture.run(&["convert", HASH, "--from-mtx", mtx, "--circuit", "both"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn convert_exports_matrix_market_files() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&["convert", HASH, "--to", "mtx"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(
+        out.ends_with("RESULT ok matrices=3 witnesses=0 mismatches=0\n"),
+        "{out}"
+    );
+    let section = fixture.config.root_dir().join(matrices_section(HASH));
+    for label in ["A_0", "B_0", "C_0"] {
+        let file = std::fs::File::open(section.join(format!("{label}.mtx"))).unwrap();
+        let exported = SparseMatrix::<Fr>::from_matrix_market(file).unwrap();
+        let dumped: SparseMatrix<Fr> = fixture.config.read(matrices_section(HASH), label).unwrap();
+        assert_eq!(exported, dumped, "{label}");
+    }
+}
+
+#[test]
+fn stats_shows_the_row_distribution() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&["stats", HASH, "--matrices", "A"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    // The rows of A hold 2, 0, and 1 nonzeros.
+    assert!(
+        out.contains("   rows by nonzeros: 0: 1, 1: 1, 2: 1\n"),
+        "{out}"
+    );
+    assert!(
+        out.contains("   imbalance: gini 0.444; the 1 densest rows hold 66.7% of nonzeros"),
+        "{out}"
+    );
+
+    let output = fixture.run(&["stats", HASH, "--matrices", "A", "--format", "json"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("\"row_distribution\":{"), "{out}");
+}
+
+#[test]
+fn stats_shows_the_structure() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&["stats", HASH, "--matrices", "A"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    // A has entries at (0, 0), (0, 2), and (2, 1).
+    assert!(
+        out.contains(
+            "   structure: not triangular, asymmetric pattern; 1 of 3 diagonal entries nonzero"
+        ),
+        "{out}"
+    );
+}
+
+#[test]
+fn stats_draws_spy_plots() {
+    let fixture = Fixture::new(1);
+    let spy = fixture.dir.path().join("spy.png");
+    let output = fixture.run(&["stats", HASH, "--spy", spy.to_str().unwrap()]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    for name in ["A", "B", "C"] {
+        let path = fixture.dir.path().join(format!("spy_{name}.png"));
+        assert!(
+            out.contains(&format!("{name}: drew {} (3x3)", path.display())),
+            "{out}"
+        );
+        assert!(std::fs::read(&path).unwrap().starts_with(b"\x89PNG"));
+    }
+
+    let output = fixture.run(&[
+        "stats",
+        HASH,
+        "--matrices",
+        "B",
+        "--spy",
+        spy.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(spy.is_file());
+
+    let output = fixture.run(&["stats", HASH, "--circuit", "both", "--spy", "x.png"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn inspect_prints_vectors_as_hex() {
+    let fixture = Fixture::empty();
+    let z: Vec<Fr> = (1..=7).map(Fr::from).collect();
+    fixture.config.write("vectors", "z", &z).unwrap();
+
+    let output = fixture.run(&["inspect", "vector", "vectors", "z", "--limit", "2"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    let zeros = "0".repeat(63);
+    let expected = format!(
+        "vectors/z: 7 elements\n0  0x{zeros}1\n1  0x{zeros}2\n   ... 3 more\n5  0x{zeros}6\n\
+         6  0x{zeros}7\n"
+    );
+    assert!(out.starts_with(&expected), "{out}");
+
+    let output = fixture.run(&["--format", "json", "inspect", "vector", "vectors", "z"]);
+    let json: serde_json::Value =
+        serde_json::from_str(stdout(&output).lines().next().unwrap()).unwrap();
+    assert_eq!(json["len"], 7);
+    assert_eq!(json["elements"][6]["index"], 6);
+    assert_eq!(json["elements"][6]["value"], format!("0x{zeros}7"));
+
+    let output = fixture.run(&["inspect", "vector", "vectors", "missing"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn exported_vectors_import_back() {
+    let fixture = Fixture::new(1);
+    let json = fixture.dir.path().join("z.json");
+    let json = json.to_str().unwrap();
+    let output = fixture.run(&[
+        "inspect",
+        "vector",
+        &witness_section(HASH),
+        "_0",
+        "--out",
+        json,
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("wrote 3 elements to"),
+        "{}",
+        stdout(&output)
+    );
+    let hex: Vec<String> = serde_json::from_str(&std::fs::read_to_string(json).unwrap()).unwrap();
+    assert_eq!(hex.len(), 3);
+
+    let output = fixture.run(&["import-vector", json, "imported", "z"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let z: Vec<Fr> = fixture.config.read("imported", "z").unwrap();
+    assert_eq!(z, witness(0));
+
+    std::fs::write(json, r#"["0x1", "0xzz"]"#).unwrap();
+    let output = fixture.run(&["import-vector", json, "imported", "z"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("element 1: 0xzz is not the hex of a field element"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn convert_round_trips_npz_files() {
+    let fixture = Fixture::new(1);
+    let output = fixture.run(&["convert", HASH, "--to", "npz", "--matrices", "C"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    assert!(out.contains("C: wrote "), "{out}");
+    let npz = fixture
+        .config
+        .root_dir()
+        .join(matrices_section(HASH))
+        .join("C_0.npz");
+    let output = fixture.run(&["list", &matrices_section(HASH)]);
+    assert!(stdout(&output).contains("C_0.npz"), "{}", stdout(&output));
+
+    let output = fixture.run(&["convert", HASH, "--from-npz", npz.as_str(), "--matrix", "A"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}{}", stderr(&output));
+    let imported: SparseMatrix<Fr> = fixture.config.read(matrices_section(HASH), "A_0").unwrap();
+    assert_eq!(imported, matrix(5));
+}
@@ -0,0 +1,170 @@
+//! Shared fixtures for the tests that run the `spmvm` binary end to end.
+#![allow(dead_code, non_snake_case)]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use camino::Utf8PathBuf;
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{matrices_section, result_section, witness_section},
+    DataConfig, SparseMatrix,
+};
+use tempfile::TempDir;
+
+pub const HASH: &str = "fixture";
+
+/// The hash the secondary circuit of [`HASH`] is stored under.
+pub const SECONDARY_HASH: &str = "fixture_secondary";
+
+/// A 3x3 matrix whose entries are scaled by `k`.
+pub fn matrix(k: u64) -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(k), Fr::from(2 * k), Fr::from(3 * k)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    }
+}
+
+pub fn witness(i: usize) -> Vec<Fr> {
+    vec![Fr::from(i as u64 + 1), Fr::from(2), Fr::from(3)]
+}
+
+/// A 2x4 matrix of the secondary circuit whose entries are scaled by `k`.
+pub fn secondary_matrix(k: u64) -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(k), Fr::from(3 * k), Fr::from(5 * k)],
+        indices: vec![1, 3, 0],
+        indptr: vec![0, 2, 3],
+        cols: 4,
+    }
+}
+
+pub fn secondary_witness(i: usize) -> Vec<Fr> {
+    vec![
+        Fr::from(i as u64 + 2),
+        Fr::from(1),
+        Fr::from(4),
+        Fr::from(6),
+    ]
+}
+
+/// A complete dump for [`HASH`] with `witnesses` witnesses and their expected products.
+pub struct Fixture {
+    pub dir: TempDir,
+    pub config: DataConfig,
+}
+
+impl Fixture {
+    /// An empty data root.
+    pub fn empty() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+        let config = DataConfig::new(root);
+        Self { dir, config }
+    }
+
+    pub fn new(witnesses: usize) -> Self {
+        let Self { dir, config } = Self::empty();
+
+        let matrices = [matrix(1), matrix(4), matrix(5)];
+        for (label, M) in ["A_0", "B_0", "C_0"].iter().zip(&matrices) {
+            config.write(matrices_section(HASH), label, M).unwrap();
+        }
+        for i in 0..witnesses {
+            let z = witness(i);
+            config
+                .write(witness_section(HASH), format!("_{i}"), &z)
+                .unwrap();
+            for (label, M) in ["AZ", "BZ", "CZ"].iter().zip(&matrices) {
+                config
+                    .write(
+                        result_section(HASH),
+                        format!("{label}_{i}"),
+                        &M.multiply_vec(&z),
+                    )
+                    .unwrap();
+            }
+        }
+
+        Self { dir, config }
+    }
+
+    /// Adds a secondary circuit with `witnesses` witnesses, whose matrices are 2x4 rather
+    /// than 3x3.
+    pub fn with_secondary(self, witnesses: usize) -> Self {
+        let matrices = [
+            secondary_matrix(1),
+            secondary_matrix(2),
+            secondary_matrix(7),
+        ];
+        for (label, M) in ["A_0", "B_0", "C_0"].iter().zip(&matrices) {
+            self.config
+                .write(matrices_section(SECONDARY_HASH), label, M)
+                .unwrap();
+        }
+        for i in 0..witnesses {
+            let z = secondary_witness(i);
+            self.config
+                .write(witness_section(SECONDARY_HASH), format!("_{i}"), &z)
+                .unwrap();
+            for (label, M) in ["AZ", "BZ", "CZ"].iter().zip(&matrices) {
+                self.config
+                    .write(
+                        result_section(SECONDARY_HASH),
+                        format!("{label}_{i}"),
+                        &M.multiply_vec(&z),
+                    )
+                    .unwrap();
+            }
+        }
+        self
+    }
+
+    /// Deletes the `result_<HASH>` section, as in dumps of only matrices and witnesses.
+    pub fn without_results(self) -> Self {
+        std::fs::remove_dir_all(self.config.root_dir().join(result_section(HASH))).unwrap();
+        self
+    }
+
+    pub fn root(&self) -> &str {
+        self.config.root_dir().as_str()
+    }
+
+    /// Runs the binary against this dump, on every core unless `args` pass `--threads`.
+    pub fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_spmvm"))
+            .arg("--data-dir")
+            .arg(self.root())
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Runs the binary like [`Fixture::run`], with `input` on its stdin.
+    pub fn run_with_stdin(&self, args: &[&str], input: &[u8]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_spmvm"))
+            .arg("--data-dir")
+            .arg(self.root())
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // The binary may fail before reading all of it.
+        let _ = child.stdin.take().unwrap().write_all(input);
+        child.wait_with_output().unwrap()
+    }
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
@@ -0,0 +1,152 @@
+use spmvm_test_example::{
+    compare::{Comparison, Mismatch},
+    report::{BenchReport, MatrixTiming, WitnessTiming},
+};
+
+/// A report for hash `abc` with one matrix `A` and the given runs per witness.
+fn report(witnesses: &[&[u64]]) -> BenchReport {
+    let mut report = BenchReport::new("abc", 1);
+    report.matrices.push(MatrixTiming {
+        name: "A".to_string(),
+        rows: 3,
+        cols: 3,
+        nnz: 3,
+        witnesses: witnesses
+            .iter()
+            .enumerate()
+            .map(|(witness, runs)| WitnessTiming {
+                witness,
+                threads: 0,
+                durations_ns: runs.to_vec(),
+            })
+            .collect(),
+        row_profile: None,
+    });
+    report
+}
+
+#[test]
+fn clean_pass() {
+    let baseline = report(&[&[100, 110, 90], &[200]]);
+    let candidate = report(&[&[102], &[190, 210, 195]]);
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    assert!(comparison.mismatches.is_empty());
+    assert_eq!(comparison.deltas.len(), 2);
+    assert!((comparison.deltas[0].percent() - 2.0).abs() < 1e-9);
+    assert!((comparison.deltas[1].percent() + 2.5).abs() < 1e-9);
+    assert_eq!(comparison.regressions(5.0).count(), 0);
+}
+
+#[test]
+fn regression_above_threshold() {
+    let baseline = report(&[&[100], &[200]]);
+    let candidate = report(&[&[104], &[220]]);
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    let regressions: Vec<_> = comparison.regressions(5.0).collect();
+    assert_eq!(regressions.len(), 1);
+    assert_eq!(regressions[0].witness, 1);
+    assert_eq!(comparison.regressions(15.0).count(), 0);
+}
+
+#[test]
+fn missing_entries_and_hash_are_reported() {
+    let baseline = report(&[&[100], &[200]]);
+    let mut candidate = report(&[&[100], &[200], &[300]]);
+    candidate.matrices[0].witnesses.remove(1);
+    candidate.hash = "def".to_string();
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    assert_eq!(comparison.deltas.len(), 1);
+    assert_eq!(
+        comparison.mismatches,
+        vec![
+            Mismatch::Hash {
+                baseline: "abc".to_string(),
+                candidate: "def".to_string(),
+            },
+            Mismatch::MissingInCandidate {
+                witness: 1,
+                matrix: "A".to_string(),
+                threads: None,
+            },
+            Mismatch::MissingInBaseline {
+                witness: 2,
+                matrix: "A".to_string(),
+                threads: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn shape_changes_are_reported() {
+    let baseline = report(&[&[100]]);
+    let mut candidate = report(&[&[100]]);
+    candidate.matrices[0].nnz = 4;
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    assert_eq!(
+        comparison.mismatches,
+        vec![Mismatch::Shape {
+            matrix: "A".to_string(),
+            baseline: (3, 3, 3),
+            candidate: (3, 3, 4),
+        }]
+    );
+}
+
+/// A sweep report over witness 0 with the given runs per thread count.
+fn sweep(runs: &[(usize, u64)]) -> BenchReport {
+    let mut report = report(&[]);
+    report.threads = runs.iter().map(|&(threads, _)| threads).max().unwrap();
+    report.matrices[0].witnesses = runs
+        .iter()
+        .map(|&(threads, run)| WitnessTiming {
+            witness: 0,
+            threads,
+            durations_ns: vec![run],
+        })
+        .collect();
+    report
+}
+
+#[test]
+fn sweeps_are_compared_per_thread_count() {
+    let baseline = sweep(&[(1, 400), (2, 200)]);
+    let candidate = sweep(&[(1, 400), (2, 300), (4, 100)]);
+    assert!(baseline.is_sweep());
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    let deltas: Vec<_> = comparison
+        .deltas
+        .iter()
+        .map(|d| (d.threads, d.percent()))
+        .collect();
+    assert_eq!(deltas, vec![(Some(1), 0.0), (Some(2), 50.0)]);
+    assert_eq!(
+        comparison.mismatches,
+        vec![Mismatch::MissingInBaseline {
+            witness: 0,
+            matrix: "A".to_string(),
+            threads: Some(4),
+        }]
+    );
+    assert_eq!(
+        comparison.mismatches[0].to_string(),
+        "A witness 0 at 4 threads is missing from the baseline"
+    );
+}
+
+#[test]
+fn single_runs_are_compared_across_thread_counts() {
+    let baseline = report(&[&[100]]);
+    let mut candidate = report(&[&[50]]);
+    candidate.threads = 8;
+    let comparison = Comparison::new(&baseline, &candidate);
+
+    assert!(comparison.mismatches.is_empty());
+    assert_eq!(comparison.deltas[0].threads, None);
+    assert_eq!(comparison.deltas[0].percent(), -50.0);
+}
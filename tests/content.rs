@@ -0,0 +1,79 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{hex::bytes_to_hex, sparse::CONTENT_CHUNK_ELEMENTS, SparseMatrix};
+
+/// A matrix with two chunks of entries, the second a short one.
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(73);
+    SparseMatrix::random(CONTENT_CHUNK_ELEMENTS / 8 + 5, 100, 8, &mut rng)
+}
+
+fn small_matrix() -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    }
+}
+
+#[test]
+fn content_hash_is_stable() {
+    // Changing this hash breaks every cache, so it must only change with the hashed layout.
+    assert_eq!(
+        bytes_to_hex(&small_matrix().content_hash()),
+        "049654989e86655643a57872138b2d4f56005be1645f20ef644aef33fd428baa"
+    );
+
+    let M = matrix();
+    let hash = M.content_hash();
+    for threads in [1, 3] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(|| M.content_hash()), hash, "{threads}");
+    }
+    assert_eq!(M.clone().content_hash(), hash);
+}
+
+#[test]
+fn content_hash_changes_with_any_entry() {
+    let M = matrix();
+    let hash = M.content_hash();
+    let last = M.nnz() - 1;
+
+    let mut flipped = M.clone();
+    flipped.data[last] += Fr::from(1);
+    assert_ne!(flipped.content_hash(), hash);
+    assert!(!flipped.par_eq(&M));
+
+    let mut moved = M.clone();
+    moved.indices[CONTENT_CHUNK_ELEMENTS] ^= 1;
+    assert_ne!(moved.content_hash(), hash);
+    assert!(!moved.par_eq(&M));
+
+    let mut widened = M.clone();
+    widened.cols += 1;
+    assert_ne!(widened.content_hash(), hash);
+    assert!(!widened.par_eq(&M));
+
+    // Moving an entry to the next row keeps the other arrays as they were.
+    let mut A = small_matrix();
+    A.indptr[1] = 1;
+    assert_ne!(A.content_hash(), small_matrix().content_hash());
+}
+
+#[test]
+fn par_eq_agrees_with_eq() {
+    let M = matrix();
+    assert!(M.par_eq(&M.clone()));
+    assert!(small_matrix().par_eq(&small_matrix()));
+    assert!(!small_matrix().par_eq(&M));
+    let empty = SparseMatrix::<Fr>::zero(0, 0);
+    assert!(empty.par_eq(&SparseMatrix::zero(0, 0)));
+    assert!(!empty.par_eq(&SparseMatrix::zero(1, 0)));
+}
@@ -0,0 +1,103 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    sparse::CooMatrix,
+    SparseMatrix,
+};
+
+#[test]
+fn unsorted_entries_become_sorted_rows() {
+    // ```text
+    // [0 2 1]
+    // [0 0 0]
+    // [3 0 4]
+    // ```
+    let coo = CooMatrix::from_triplets(
+        3,
+        3,
+        vec![
+            (2, 2, Fr::from(4)),
+            (0, 2, Fr::from(1)),
+            (2, 0, Fr::from(3)),
+            (0, 1, Fr::from(2)),
+        ],
+    );
+    assert_eq!(
+        coo.to_csr(),
+        SparseMatrix {
+            data: vec![Fr::from(2), Fr::from(1), Fr::from(3), Fr::from(4)],
+            indices: vec![1, 2, 0, 2],
+            indptr: vec![0, 2, 2, 4],
+            cols: 3,
+        }
+    );
+}
+
+#[test]
+fn duplicate_coordinates_are_added_up() {
+    let mut coo = CooMatrix::new(2, 2);
+    coo.push(1, 1, Fr::from(5));
+    coo.push(0, 0, Fr::from(1));
+    coo.push(1, 1, Fr::from(6));
+    coo.push(0, 0, -Fr::from(1));
+    coo.push(1, 1, Fr::from(7));
+    assert_eq!(coo.nnz(), 5);
+    // The entries at (0, 0) cancel, and stay as an explicit zero.
+    assert_eq!(
+        coo.into_csr(),
+        SparseMatrix {
+            data: vec![Fr::from(0), Fr::from(18)],
+            indices: vec![0, 1],
+            indptr: vec![0, 1, 2],
+            cols: 2,
+        }
+    );
+}
+
+#[test]
+fn empty_rows_and_columns_at_the_boundaries_are_kept() {
+    // ```text
+    // [0 0 0 0]
+    // [0 8 0 0]
+    // [0 0 9 0]
+    // [0 0 0 0]
+    // ```
+    let coo = CooMatrix::from_triplets(4, 4, vec![(2, 2, Fr::from(9)), (1, 1, Fr::from(8))]);
+    let M = coo.to_csr();
+    assert_eq!(M.indptr, [0, 0, 1, 2, 2]);
+    assert_eq!((M.cols, M.indices.clone()), (4, vec![1, 2]));
+    assert_eq!(M.to_coo().to_csr(), M);
+
+    let M = CooMatrix::<Fr>::new(3, 5).into_csr();
+    assert_eq!(M.indptr, [0, 0, 0, 0]);
+    assert_eq!((M.cols, M.nnz()), (5, 0));
+    assert_eq!(M.to_coo(), CooMatrix::new(3, 5));
+}
+
+#[test]
+fn to_coo_round_trips() {
+    let mut rng = ChaCha20Rng::seed_from_u64(60);
+    let shape = Shape {
+        rows: 40,
+        cols: 25,
+        nnz_per_row: 6,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let coo = M.to_coo();
+    assert_eq!((coo.rows, coo.cols, coo.nnz()), (40, 25, 240));
+    assert!(coo
+        .entries
+        .windows(2)
+        .all(|e| (e[0].0, e[0].1) < (e[1].0, e[1].1)));
+    assert_eq!(coo.into_csr(), M);
+}
+
+#[test]
+#[should_panic(expected = "entry (2, 0) is out of bounds of a 2x3 matrix")]
+fn push_rejects_entries_out_of_bounds() {
+    CooMatrix::new(2, 3).push(2, 0, Fr::from(1));
+}
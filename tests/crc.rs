@@ -0,0 +1,9 @@
+use spmvm_test_example::crc::crc32;
+
+#[test]
+fn crc32_matches_the_check_value() {
+    // The check value of CRC-32/ISO-HDLC, the CRC of zip and PNG.
+    assert_eq!(crc32([&b"123456789"[..]]), 0xcbf43926);
+    assert_eq!(crc32([&b"1234"[..], b"", b"56789"]), 0xcbf43926);
+    assert_eq!(crc32([]), 0);
+}
@@ -0,0 +1,97 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::CscMatrix,
+    SparseMatrix,
+};
+
+/// ```text
+/// [1 0 2 0]
+/// [0 0 0 0]
+/// [0 3 4 0]
+/// ```
+fn small_matrix() -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)],
+        indices: vec![0, 2, 1, 2],
+        indptr: vec![0, 2, 2, 4],
+        cols: 4,
+    }
+}
+
+fn fr_vec(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn to_csc_stores_columns() {
+    let C = small_matrix().to_csc();
+    assert_eq!(
+        C,
+        CscMatrix {
+            data: fr_vec(&[1, 3, 2, 4]),
+            indices: vec![0, 2, 0, 2],
+            indptr: vec![0, 1, 2, 4, 4],
+            rows: 3,
+        }
+    );
+    assert_eq!((C.cols(), C.nnz()), (4, 4));
+    assert_eq!(C.to_csr(), small_matrix());
+}
+
+#[test]
+fn multiply_vec_small() {
+    let C = small_matrix().to_csc();
+    let z = fr_vec(&[1, 2, 3, 4]);
+    assert_eq!(C.multiply_vec(&z), fr_vec(&[7, 0, 18]));
+    assert_eq!(C.multiply_vec_serial(&z), fr_vec(&[7, 0, 18]));
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn multiply_vec_rejects_wrong_length() {
+    small_matrix().to_csc().multiply_vec(&fr_vec(&[1, 2, 3]));
+}
+
+#[test]
+fn matches_csr_on_random_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(61);
+    for (rows, cols, nnz_per_row) in [(1, 1, 1), (50, 7, 3), (7, 50, 20), (300, 200, 9)] {
+        let shape = Shape {
+            rows,
+            cols,
+            nnz_per_row,
+        };
+        let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        let z = random_vector(&mut rng, cols);
+        let C = M.to_csc();
+        assert_eq!(C.to_csr(), M, "{shape:?}");
+        let expected = M.multiply_vec(&z);
+        assert_eq!(C.multiply_vec(&z), expected, "{shape:?}");
+        assert_eq!(C.multiply_vec_serial(&z), expected, "{shape:?}");
+        // Fewer columns than threads still splits into non-empty blocks.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(cols + 3)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(|| C.multiply_vec(&z)), expected, "{shape:?}");
+    }
+}
+
+#[test]
+fn empty_matrices_have_zero_products() {
+    let M: SparseMatrix<Fr> = SparseMatrix {
+        data: Vec::new(),
+        indices: Vec::new(),
+        indptr: vec![0, 0, 0],
+        cols: 0,
+    };
+    let C = M.to_csc();
+    assert_eq!(C.indptr, [0]);
+    assert_eq!(C.multiply_vec(&[]), fr_vec(&[0, 0]));
+    assert_eq!(C.to_csr(), M);
+}
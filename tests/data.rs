@@ -0,0 +1,733 @@
+mod common;
+
+use std::{fs, io::Read};
+
+use common::Fixture;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    data::{
+        format_size, index_gaps, natural_cmp, read_stream,
+        source::{cache_name, check_url, open_source},
+        Counter, ParallelDecode, ProgressReader, ProgressStyle, RawCodec, ReadPath, SectionInfo,
+    },
+    generate::{random_matrix, random_vector, Shape},
+    DataConfig, DataError, DataFormat, SparseMatrix,
+};
+
+#[test]
+fn read_existing_label() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let section = config.root_dir().join("witness_abc");
+    fs::create_dir_all(&section).unwrap();
+    let witness = vec![Fr::from(1), Fr::from(2)];
+    fs::write(section.join("_0"), bincode::serialize(&witness).unwrap()).unwrap();
+
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, witness);
+}
+
+#[test]
+fn missing_section() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::SectionNotFound(_)), "{err:?}");
+    assert_eq!(err.path(), Some(&*config.root_dir().join("witness_abc")));
+}
+
+#[test]
+fn missing_label() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    fs::create_dir_all(config.root_dir().join("witness_abc")).unwrap();
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::LabelNotFound(_)), "{err:?}");
+    assert_eq!(err.path(), Some(&*config.root_dir().join("witness_abc/_0")));
+}
+
+#[test]
+fn unreadable_label() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    // A directory where a file is expected opens fine but fails on read.
+    fs::create_dir_all(config.root_dir().join("witness_abc/_0")).unwrap();
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::Io { .. }), "{err:?}");
+    assert_eq!(err.path(), Some(&*config.root_dir().join("witness_abc/_0")));
+}
+
+#[test]
+fn corrupt_label() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let section = config.root_dir().join("witness_abc");
+    fs::create_dir_all(&section).unwrap();
+    // A length prefix promising one element, followed by a truncated element.
+    let mut bytes = bincode::serialize(&1u64).unwrap();
+    bytes.extend_from_slice(&[0xff; 7]);
+    fs::write(section.join("_0"), bytes).unwrap();
+
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+    assert_eq!(err.path(), Some(&*section.join("_0")));
+}
+
+#[test]
+fn write_then_read_round_trips() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let matrix = SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    };
+    let witness = vec![Fr::from(5), Fr::from(6), Fr::from(7)];
+
+    let path = config.write("sparse_matrices_abc", "A_0", &matrix).unwrap();
+    assert_eq!(path, config.root_dir().join("sparse_matrices_abc/A_0"));
+    config.write("witness_abc", "_0", &witness).unwrap();
+
+    let read: SparseMatrix<Fr> = config.read("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(read, matrix);
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, witness);
+}
+
+#[test]
+fn files_missing_from_the_root_are_fetched_from_its_source() {
+    let remote = Fixture::empty();
+    let witness = vec![Fr::from(5), Fr::from(6)];
+    remote.config.write("witness_abc", "_0", &witness).unwrap();
+    remote.config.write("witness_abc", "_1", &witness).unwrap();
+
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let source = open_source(&format!("file://{}", remote.root())).unwrap();
+    let config = config.with_source(source);
+    assert_eq!(config.labels("witness_abc").unwrap(), ["_0", "_1"]);
+    assert_eq!(config.label_indices("witness_abc", "_").unwrap(), [0, 1]);
+    let read: Vec<Fr> = config.read("witness_abc", "_1").unwrap();
+    assert_eq!(read, witness);
+    assert!(config.root_dir().join("witness_abc/_1").is_file());
+    assert!(!config.root_dir().join("witness_abc/_0").exists());
+    assert!(!config.has_section("witness_def"));
+}
+
+#[test]
+fn only_file_http_and_s3_urls_are_sources() {
+    for url in [
+        "file:///tmp/dumps",
+        "http://example.com:8080/dumps",
+        "https://example.com/dumps",
+        "s3://bucket/dumps",
+    ] {
+        assert_eq!(check_url(url), Ok(()), "{url}");
+    }
+    for url in ["ftp://example.com/dumps", "gs://bucket/dumps"] {
+        let err = check_url(url).unwrap_err();
+        assert!(
+            err.contains("expected an http://, https://, s3://, or file:// URL"),
+            "{err}"
+        );
+        let err = open_source(url).err().unwrap();
+        assert!(matches!(err, DataError::Fetch { .. }), "{err:?}");
+    }
+}
+
+#[test]
+fn cache_names_tell_urls_apart() {
+    let name = cache_name("http://example.com:8080/dumps/");
+    assert!(name.starts_with("http_example.com_8080_dumps_"), "{name}");
+    assert_eq!(name, cache_name("http://example.com:8080/dumps"));
+    assert_ne!(cache_name("http://h/a_b"), cache_name("http://h/a/b"));
+    assert_ne!(cache_name("http://h:80/a"), cache_name("http://h/80/a"));
+}
+
+#[test]
+fn label_indices_scans_numbered_labels() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let section = config.root_dir().join("witness_abc");
+    fs::create_dir_all(&section).unwrap();
+    for label in [
+        "_0",
+        "_1",
+        "_3",
+        "_10",
+        "_2x",
+        "_",
+        "_01",
+        "AZ_4",
+        "notes.txt",
+    ] {
+        fs::write(section.join(label), b"").unwrap();
+    }
+
+    let indices = config.label_indices("witness_abc", "_").unwrap();
+    assert_eq!(indices, vec![0, 1, 3, 10]);
+    assert_eq!(index_gaps(&indices), vec![2, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(config.label_indices("witness_abc", "AZ_").unwrap(), vec![4]);
+
+    let err = config.label_indices("witness_missing", "_").unwrap_err();
+    assert!(matches!(err, DataError::SectionNotFound(_)), "{err:?}");
+}
+
+#[test]
+fn index_gaps_edge_cases() {
+    assert_eq!(index_gaps(&[]), Vec::<usize>::new());
+    assert_eq!(index_gaps(&[0, 1, 2]), Vec::<usize>::new());
+    assert_eq!(index_gaps(&[2]), vec![0, 1]);
+}
+
+#[test]
+fn natural_order() {
+    let mut labels = vec!["_10", "_2", "_01", "_1", "AZ_10", "AZ_9", "_", "a", "_0"];
+    labels.sort_by(|a, b| natural_cmp(a, b));
+    assert_eq!(
+        labels,
+        ["AZ_9", "AZ_10", "_", "_0", "_01", "_1", "_2", "_10", "a"]
+    );
+    assert_eq!(
+        natural_cmp("_99999999999999999999999", "_100000000000000000000000"),
+        std::cmp::Ordering::Less
+    );
+}
+
+/// A data root with an assortment of awkward names, files at the root, and nested directories.
+fn synthetic_tree() -> Fixture {
+    let fixture = Fixture::empty();
+    let root = fixture.config.root_dir();
+    for section in ["witness_10", "witness_9", "with space", "ünïcode"] {
+        fs::create_dir_all(root.join(section)).unwrap();
+    }
+    for (label, len) in [
+        ("_10", 3),
+        ("_9", 2),
+        ("_1", 1),
+        (".hidden", 4),
+        ("name with spaces", 5),
+        ("_01", 6),
+    ] {
+        fs::write(root.join("witness_10").join(label), vec![0u8; len]).unwrap();
+    }
+    fs::create_dir_all(root.join("witness_10/nested")).unwrap();
+    fs::write(root.join("witness_10/nested/_0"), [0u8; 100]).unwrap();
+    fs::write(root.join("ünïcode/ø"), [0u8; 7]).unwrap();
+    fs::write(root.join("stray_file"), [0u8; 8]).unwrap();
+    fixture
+}
+
+#[test]
+fn list_sections() {
+    let Fixture { dir: _dir, config } = synthetic_tree();
+    let section = |name: &str, files, bytes| SectionInfo {
+        name: name.to_string(),
+        files,
+        bytes,
+    };
+    assert_eq!(
+        config.sections().unwrap(),
+        [
+            section("with space", 0, 0),
+            section("witness_9", 0, 0),
+            section("witness_10", 6, 21),
+            section("ünïcode", 1, 7),
+        ]
+    );
+}
+
+#[test]
+fn list_labels() {
+    let Fixture { dir: _dir, config } = synthetic_tree();
+    assert_eq!(
+        config.labels("witness_10").unwrap(),
+        [".hidden", "_01", "_1", "_9", "_10", "name with spaces"]
+    );
+    assert_eq!(config.labels("ünïcode").unwrap(), ["ø"]);
+    assert!(config.labels("with space").unwrap().is_empty());
+    let err = config.labels("stray_file").unwrap_err();
+    assert!(matches!(err, DataError::SectionNotFound(_)), "{err:?}");
+}
+
+#[test]
+fn check_dump_collects_everything_missing() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let witness = vec![Fr::from(1)];
+    config.write("sparse_matrices_h", "A_0", &witness).unwrap();
+    config.write("sparse_matrices_h", "C_0", &witness).unwrap();
+    for label in ["_0", "_2"] {
+        config.write("witness_h", label, &witness).unwrap();
+    }
+
+    let check = config
+        .check_dump("h", &["A", "B", "C"], &[0, 1, 2], false)
+        .unwrap();
+    assert!(!check.is_complete());
+    assert_eq!(check.witness_entries, 2);
+    assert_eq!(check.result_entries, None);
+    assert_eq!(check.missing, ["sparse_matrices_h/B_0", "witness_h/_1"]);
+
+    let check = config
+        .check_dump("h", &["A", "B", "C"], &[0], true)
+        .unwrap();
+    assert_eq!(check.missing, ["sparse_matrices_h/B_0", "result_h"]);
+
+    for label in ["AZ_0", "BZ_0", "CZ_0", "AZ_2"] {
+        config.write("result_h", label, &witness).unwrap();
+    }
+    let check = config
+        .check_dump("h", &["A", "B", "C"], &[0, 2], true)
+        .unwrap();
+    assert_eq!(check.result_entries, Some(4));
+    assert_eq!(
+        check.missing,
+        ["sparse_matrices_h/B_0", "result_h/BZ_2", "result_h/CZ_2"]
+    );
+}
+
+#[test]
+fn progress_lines_mark_each_quarter() {
+    let data = [0u8; 100];
+    let mut out = Vec::new();
+    let mut reader = ProgressReader::new(&data[..], "sec/A_0", 100, ProgressStyle::Lines, &mut out);
+    let mut chunk = [0u8; 10];
+    let mut copied = 0;
+    while let n @ 1.. = reader.read(&mut chunk).unwrap() {
+        copied += n;
+    }
+    assert_eq!(copied, 100);
+
+    let out = String::from_utf8(out).unwrap();
+    let percents: Vec<_> = out
+        .lines()
+        .map(|line| {
+            assert!(line.starts_with("reading sec/A_0: "), "{line}");
+            line.split(['(', ')']).nth(1).unwrap()
+        })
+        .collect();
+    assert_eq!(percents, ["30%", "50%", "80%", "100%"]);
+    assert!(out.ends_with("/s\n"), "{out}");
+}
+
+#[test]
+fn progress_redraw_ends_with_a_newline() {
+    let data = [0u8; 2048];
+    let mut out = Vec::new();
+    let mut reader = ProgressReader::new(&data[..], "A_0", 2048, ProgressStyle::Redraw, &mut out);
+    std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("\r\x1b[KA_0: "), "{out:?}");
+    assert!(out.ends_with("\n"), "{out:?}");
+    assert!(out.contains("2.00 KiB of 2.00 KiB (100%)"), "{out:?}");
+    assert_eq!(format_size(3 << 29), "1.50 GiB");
+}
+
+#[test]
+fn mmap_reads_match_buffered_reads() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let mapped = config.clone().with_read_path(ReadPath::Mmap);
+    assert_eq!(config.read_path(), ReadPath::Buffered);
+    let matrix = SparseMatrix {
+        data: vec![Fr::from(4), Fr::from(5)],
+        indices: vec![1, 0],
+        indptr: vec![0, 1, 2],
+        cols: 2,
+    };
+    config.write("sparse_matrices_abc", "A_0", &matrix).unwrap();
+    let read: SparseMatrix<Fr> = mapped.read("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(read.data, matrix.data);
+    assert_eq!(read.indices, matrix.indices);
+    assert_eq!(read.indptr, matrix.indptr);
+
+    // Truncated files are still reported as corrupt, and unmappable ones fall back.
+    let section = config.root_dir().join("witness_abc");
+    fs::create_dir_all(section.join("_1")).unwrap();
+    fs::write(section.join("_0"), [1, 0, 0, 0, 0, 0, 0, 0, 0xff]).unwrap();
+    let err = mapped.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+    let err = mapped.read::<Vec<Fr>>("witness_abc", "_1").unwrap_err();
+    assert!(matches!(err, DataError::Io { .. }), "{err:?}");
+}
+
+#[test]
+fn compressed_files_read_like_uncompressed_ones() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let compressed = config.clone().with_compression(true);
+    let matrix = SparseMatrix {
+        data: (1..=6).map(Fr::from).collect(),
+        indices: vec![0, 1, 2, 0, 1, 2],
+        indptr: vec![0, 3, 6],
+        cols: 3,
+    };
+    config.write("sparse_matrices_abc", "B_0", &matrix).unwrap();
+    let path = compressed
+        .write("sparse_matrices_abc", "A_0", &matrix)
+        .unwrap();
+    assert!(path.ends_with("sparse_matrices_abc/A_0.zst"));
+
+    let plain: SparseMatrix<Fr> = config.read("sparse_matrices_abc", "B_0").unwrap();
+    let unpacked: SparseMatrix<Fr> = config.read("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(unpacked, plain);
+    let mapped = config.clone().with_read_path(ReadPath::Mmap);
+    assert_eq!(
+        mapped
+            .read::<SparseMatrix<Fr>>("sparse_matrices_abc", "A_0")
+            .unwrap(),
+        plain
+    );
+    assert_eq!(
+        config.labels("sparse_matrices_abc").unwrap(),
+        ["A_0", "B_0"]
+    );
+
+    // Without the extension, the magic bytes give the compression away.
+    let section = config.root_dir().join("sparse_matrices_abc");
+    fs::rename(&path, section.join("C_0")).unwrap();
+    assert_eq!(
+        config
+            .read::<SparseMatrix<Fr>>("sparse_matrices_abc", "C_0")
+            .unwrap(),
+        plain
+    );
+
+    // Rewriting a label in the other form replaces the old file.
+    compressed
+        .write("sparse_matrices_abc", "B_0", &matrix)
+        .unwrap();
+    assert!(!section.join("B_0").exists());
+    assert_eq!(
+        config.labels("sparse_matrices_abc").unwrap(),
+        ["B_0", "C_0"]
+    );
+}
+
+#[test]
+fn streams_decode_like_the_files_they_hold() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let witness = vec![Fr::from(9); 4];
+    let compressed = config.clone().with_compression(true);
+    for (config, label) in [(&config, "_0"), (&compressed, "_1")] {
+        let path = config.write("witness_abc", label, &witness).unwrap();
+        let stream: Vec<Fr> = read_stream(fs::File::open(&path).unwrap(), "in", 1 << 10).unwrap();
+        assert_eq!(stream, witness);
+    }
+    let mut raw = Vec::new();
+    witness.write_raw(&mut raw).unwrap();
+    let stream: Vec<Fr> = read_stream(&raw[..], "in", 1 << 10).unwrap();
+    assert_eq!(stream, witness);
+
+    // Headerless bincode, as arecibo writes it.
+    let bytes = bincode::serialize(&witness).unwrap();
+    let err = read_stream::<Vec<Fr>>(&bytes[..], "in", bytes.len() as u64 - 1).unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+    let err = read_stream::<Vec<Fr>>(&raw[..], "in", raw.len() as u64 - 1).unwrap_err();
+    assert!(matches!(err, DataError::InvalidRaw { .. }), "{err:?}");
+    let err = read_stream::<SparseMatrix<Fr>>(&raw[..], "in", 1 << 10);
+    assert!(err.is_err());
+}
+
+#[test]
+fn compressed_witnesses_count_once() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let compressed = config.clone().with_compression(true);
+    let witness = vec![Fr::from(9); 4];
+    config.write("witness_abc", "_0", &witness).unwrap();
+    compressed.write("witness_abc", "_1", &witness).unwrap();
+    fs::write(config.root_dir().join("witness_abc/_0.zst"), b"").unwrap();
+    assert_eq!(config.label_indices("witness_abc", "_").unwrap(), [0, 1]);
+    let check = config.check_dump("abc", &[], &[0, 1], false).unwrap();
+    assert_eq!(check.witness_entries, 2);
+    assert_eq!(check.missing, ["sparse_matrices_abc"]);
+}
+
+#[test]
+fn parallel_decodes_match_serial_decodes() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let mut rng = ChaCha20Rng::seed_from_u64(5);
+    // Several decode chunks long, and a matrix with every kind of array.
+    let witness: Vec<Fr> = random_vector(&mut rng, 50_000);
+    let shape = Shape {
+        rows: 300,
+        cols: 200,
+        nnz_per_row: 7,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    config.write("witness_abc", "_0", &witness).unwrap();
+    config.write("sparse_matrices_abc", "A_0", &matrix).unwrap();
+
+    let serial = config.clone().with_parallel_decode_min(u64::MAX);
+    for read_path in [ReadPath::Buffered, ReadPath::Mmap] {
+        let parallel = config
+            .clone()
+            .with_parallel_decode_min(0)
+            .with_read_path(read_path);
+        let read: Vec<Fr> = parallel.read_with_format("witness_abc", "_0").unwrap();
+        let expected: Vec<Fr> = serial.read_with_format("witness_abc", "_0").unwrap();
+        assert_eq!(read, expected);
+        assert_eq!(read, witness);
+        let read: SparseMatrix<Fr> = parallel
+            .read_with_format("sparse_matrices_abc", "A_0")
+            .unwrap();
+        assert_eq!(read, matrix);
+    }
+
+    // Like bincode, trailing bytes are ignored; a short or non-canonical payload is an error.
+    let mut bytes = bincode::serialize(&witness[..3]).unwrap();
+    bytes.push(0);
+    assert_eq!(Vec::<Fr>::decode_parallel(&bytes).unwrap(), witness[..3]);
+    let err = Vec::<Fr>::decode_parallel(&bytes[..50]).unwrap_err();
+    assert_eq!(err, "expected 96 more bytes, found 42");
+    bytes[8..40].fill(0xff);
+    let err = Vec::<Fr>::decode_parallel(&bytes).unwrap_err();
+    assert_eq!(err, "a value is not a canonical field element");
+
+    fs::write(config.root_dir().join("witness_abc/_1"), &bytes).unwrap();
+    let err = config
+        .clone()
+        .with_parallel_decode_min(0)
+        .read_with_format::<Vec<Fr>>("witness_abc", "_1")
+        .unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+}
+
+/// The ways an uncompressed bincode matrix can be read: streamed, mapped, and decoded in
+/// parallel.
+fn matrix_readers(config: &DataConfig) -> [DataConfig; 3] {
+    [
+        config.clone(),
+        config.clone().with_read_path(ReadPath::Mmap),
+        config.clone().with_parallel_decode_min(0),
+    ]
+}
+
+fn read_matrix(config: &DataConfig) -> Result<SparseMatrix<Fr>, DataError> {
+    config.read_with_format("sparse_matrices_abc", "A_0")
+}
+
+#[test]
+fn truncated_matrices_fail_to_load() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let mut rng = ChaCha20Rng::seed_from_u64(11);
+    let shape = Shape {
+        rows: 4,
+        cols: 4,
+        nnz_per_row: 2,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let bytes = bincode::serialize(&matrix).unwrap();
+    let section = config.root_dir().join("sparse_matrices_abc");
+
+    for len in 0..bytes.len() {
+        config
+            .write_bytes("sparse_matrices_abc", "A_0", &bytes[..len])
+            .unwrap();
+        for reader in matrix_readers(&config) {
+            let err = read_matrix(&reader).unwrap_err();
+            assert!(
+                matches!(err, DataError::Deserialize { .. }),
+                "{len}: {err:?}"
+            );
+            if let DataError::Deserialize {
+                offset: Some(offset),
+                ..
+            } = err
+            {
+                assert!(offset <= len as u64, "{len}: {err}");
+            }
+        }
+
+        fs::remove_file(section.join("A_0")).unwrap();
+        let packed = zstd::encode_all(&bytes[..len], 3).unwrap();
+        fs::write(section.join("A_0.zst"), packed).unwrap();
+        let err = read_matrix(&config).unwrap_err();
+        assert!(
+            matches!(err, DataError::Deserialize { .. }),
+            "{len}: {err:?}"
+        );
+        fs::remove_file(section.join("A_0.zst")).unwrap();
+    }
+}
+
+#[test]
+fn bit_flipped_matrices_load_consistently_or_fail() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let matrix = SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    };
+    let bytes = bincode::serialize(&matrix).unwrap();
+    for i in 0..bytes.len() {
+        for bit in [0, 7] {
+            let mut flipped = bytes.clone();
+            flipped[i] ^= 1 << bit;
+            config
+                .write_bytes("sparse_matrices_abc", "A_0", &flipped)
+                .unwrap();
+            for reader in matrix_readers(&config) {
+                match read_matrix(&reader) {
+                    Ok(read) => read.check_lengths().unwrap(),
+                    Err(err) => {
+                        assert!(matches!(err, DataError::Deserialize { .. }), "{i}: {err:?}")
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hostile_lengths_stop_at_the_file_size() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    // A length prefix of 2^40 elements, 32 TiB, in a 40-byte file.
+    let mut bytes = (1u64 << 40).to_le_bytes().to_vec();
+    bytes.extend([0; 32]);
+    config.write_bytes("witness_abc", "_0", &bytes).unwrap();
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+    assert!(err.to_string().contains(" at byte 40: "), "{err}");
+}
+
+#[test]
+fn inconsistent_matrices_fail_to_load() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let cases = [
+        (vec![0], vec![0, 1, 2], "2 values but 1 column indices"),
+        (vec![0, 1], vec![], "indptr is empty"),
+        (
+            vec![0, 1],
+            vec![0, 1, 3],
+            "indptr ends at 3 but there are 2 values",
+        ),
+    ];
+    for (indices, indptr, message) in cases {
+        let matrix = SparseMatrix {
+            data: vec![Fr::from(1), Fr::from(2)],
+            indices,
+            indptr,
+            cols: 2,
+        };
+        assert_eq!(matrix.check_lengths().unwrap_err().to_string(), message);
+        config.write("sparse_matrices_abc", "A_0", &matrix).unwrap();
+        for reader in matrix_readers(&config) {
+            let err = read_matrix(&reader).unwrap_err();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+}
+
+#[test]
+fn read_many_sorts_matches_naturally() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    for i in [10, 2, 0, 9, 1] {
+        let z = vec![Fr::from(i)];
+        config.write("witness_abc", format!("_{i}"), &z).unwrap();
+    }
+
+    let read: Vec<(String, Vec<Fr>)> = config.read_many("witness_abc", "_*").unwrap();
+    let labels: Vec<_> = read.iter().map(|(label, _)| label.as_str()).collect();
+    assert_eq!(labels, ["_0", "_1", "_2", "_9", "_10"]);
+    for (label, z) in &read {
+        let i: u64 = label[1..].parse().unwrap();
+        assert_eq!(z, &[Fr::from(i)]);
+    }
+}
+
+#[test]
+fn read_many_skips_labels_that_do_not_match() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let z = vec![Fr::from(1)];
+    for label in ["AZ_0", "AZ_1", "BZ_0", "CAZ_0", "AZ_x1", "AZ_1_0"] {
+        config.write("result_abc", label, &z).unwrap();
+    }
+    config.write_raw("result_abc", "AZ_0", &z).unwrap();
+    config
+        .write_bytes("result_abc", "AZ_notes.txt", b"not a vector")
+        .unwrap();
+
+    assert_eq!(
+        config.matching_labels("result_abc", "AZ_*").unwrap(),
+        ["AZ_0", "AZ_1", "AZ_1_0", "AZ_x1"]
+    );
+    assert_eq!(
+        config.matching_labels("result_abc", "?Z_?").unwrap(),
+        ["AZ_0", "AZ_1", "BZ_0"]
+    );
+    assert_eq!(
+        config
+            .clone()
+            .with_format(DataFormat::Raw)
+            .matching_labels("result_abc", "AZ_*")
+            .unwrap(),
+        ["AZ_0"]
+    );
+    let read: Vec<(String, Vec<Fr>)> = config.read_many("result_abc", "*Z_?").unwrap();
+    assert_eq!(read.len(), 4);
+
+    let empty: Vec<(String, Vec<Fr>)> = config.read_many("result_abc", "CZ_*").unwrap();
+    assert!(empty.is_empty());
+    let err = config
+        .read_many::<Vec<Fr>>("witness_abc", "_*")
+        .unwrap_err();
+    assert!(matches!(err, DataError::SectionNotFound(_)), "{err:?}");
+}
+
+#[test]
+fn counted_labels_coexist_with_uncounted_ones() {
+    let mut fixture = Fixture::empty();
+    let config = &mut fixture.config;
+    let z = |i: u64| vec![Fr::from(i)];
+    config.write("witness_abc", "_0", &z(100)).unwrap();
+    for i in 0..3 {
+        let (counter, path) = config
+            .write_counted(Counter::Witness, "witness_abc", "_0", &z(i))
+            .unwrap();
+        assert_eq!(counter, i as usize);
+        assert_eq!(path.file_name(), Some(format!("_0_{i}").as_str()));
+    }
+    config.write("witness_abc", "_1", &z(101)).unwrap();
+
+    assert_eq!(
+        config.label_counters("witness_abc", "_0").unwrap(),
+        [0, 1, 2]
+    );
+    assert!(config
+        .label_counters("witness_abc", "_1")
+        .unwrap()
+        .is_empty());
+    assert_eq!(config.label_indices("witness_abc", "_").unwrap(), [0, 1]);
+    let read: Vec<Fr> = config.read_counted("witness_abc", "_0", 1).unwrap();
+    assert_eq!(read, z(1));
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, z(100));
+    let err = config
+        .read_counted::<Vec<Fr>>("witness_abc", "_1", 0)
+        .unwrap_err();
+    assert!(matches!(err, DataError::LabelNotFound(_)), "{err:?}");
+}
+
+#[test]
+fn counters_allocate_past_what_is_on_disk() {
+    let mut fixture = Fixture::empty();
+    let config = &mut fixture.config;
+    let z = vec![Fr::from(1)];
+    // Written by an earlier run, whose counters this config does not know.
+    config.write("witness_abc", "_0_4", &z).unwrap();
+    assert_eq!(config.counter(Counter::Witness), 0);
+
+    let (counter, _) = config
+        .write_counted(Counter::Witness, "witness_abc", "_0", &z)
+        .unwrap();
+    assert_eq!(counter, 5);
+    // The config's own counter is not lowered by a label with fewer counters.
+    let (counter, _) = config
+        .write_counted(Counter::Witness, "witness_abc", "_1", &z)
+        .unwrap();
+    assert_eq!(counter, 6);
+    assert_eq!(config.counter(Counter::Witness), 7);
+
+    // The cross-term counter is separate.
+    let (counter, _) = config
+        .write_counted(Counter::CrossTerm, "cross_term_abc", "T", &z)
+        .unwrap();
+    assert_eq!(counter, 0);
+    assert_eq!(config.counter(Counter::CrossTerm), 1);
+}
@@ -0,0 +1,34 @@
+//! Kept in its own test binary: it mutates the process environment and the global config.
+
+use std::fs;
+
+use camino::Utf8PathBuf;
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{read_arecibo_data, DataConfig, ARECIBO_DATA_DIR_ENV};
+
+#[test]
+fn env_override_resolves_reads() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().join("nested/root")).unwrap();
+    std::env::set_var(ARECIBO_DATA_DIR_ENV, &root);
+
+    // The override is created on demand.
+    let config = DataConfig::resolve(None).unwrap();
+    assert_eq!(config.root_dir(), root);
+    assert!(root.is_dir());
+
+    let witness = vec![Fr::from(42)];
+    fs::create_dir_all(root.join("witness_abc")).unwrap();
+    fs::write(
+        root.join("witness_abc/_0"),
+        bincode::serialize(&witness).unwrap(),
+    )
+    .unwrap();
+    let read: Vec<Fr> = read_arecibo_data("witness_abc", "_0").unwrap();
+    assert_eq!(read, witness);
+
+    // An explicit directory wins over the environment.
+    let flag = Utf8PathBuf::from_path_buf(dir.path().join("flag")).unwrap();
+    let config = DataConfig::resolve(Some(flag.clone())).unwrap();
+    assert_eq!(config.root_dir(), flag);
+}
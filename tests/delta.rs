@@ -0,0 +1,99 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    data::{
+        delta::{decode_deltas, encode_deltas},
+        raw::{IndexEncoding, RawCodec},
+    },
+    generate::{random_matrix, Shape},
+    SparseMatrix,
+};
+
+fn encode(M: &SparseMatrix<Fr>, encoding: IndexEncoding) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    M.write_raw_encoded(&mut bytes, encoding).unwrap();
+    bytes
+}
+
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(8);
+    let shape = Shape {
+        rows: 2000,
+        cols: 1 << 16,
+        nnz_per_row: 16,
+    };
+    random_matrix(&mut rng, shape)
+}
+
+#[test]
+fn delta_encoded_matrices_round_trip() {
+    let M = matrix();
+    let bytes = encode(&M, IndexEncoding::Delta);
+    assert_eq!(SparseMatrix::<Fr>::read_raw(&bytes).unwrap(), M);
+
+    // Empty rows, repeated columns, and a column too large for a single varint byte.
+    let M = SparseMatrix {
+        data: vec![Fr::from(1); 5],
+        indices: vec![0, 0, 300, 1 << 40, 7],
+        indptr: vec![0, 0, 4, 4, 5, 5],
+        cols: (1 << 40) + 1,
+    };
+    let bytes = encode(&M, IndexEncoding::Delta);
+    assert_eq!(SparseMatrix::<Fr>::read_raw(&bytes).unwrap(), M);
+}
+
+#[test]
+fn delta_encoding_shrinks_the_indices() {
+    let M = matrix();
+    let plain = encode(&M, IndexEncoding::Plain).len();
+    let delta = encode(&M, IndexEncoding::Delta).len();
+    let deltas = encode_deltas(&M.indices, &M.indptr).unwrap().len();
+    // 16 of 65536 columns per row leaves gaps of about 4096, two varint bytes each.
+    assert!(deltas <= 2 * M.nnz(), "{deltas} bytes of deltas");
+    assert_eq!(plain - delta, 4 * M.nnz() - deltas - 8);
+    println!("plain {plain} bytes, delta {delta} bytes");
+}
+
+#[test]
+fn unsorted_rows_are_rejected() {
+    let M = SparseMatrix {
+        data: vec![Fr::from(1); 4],
+        indices: vec![0, 1, 3, 2],
+        indptr: vec![0, 2, 4],
+        cols: 4,
+    };
+    let mut bytes = Vec::new();
+    let err = M
+        .write_raw_encoded(&mut bytes, IndexEncoding::Delta)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        "row 1 has unsorted column indices; sort them before delta encoding"
+    );
+}
+
+#[test]
+fn corrupt_deltas_are_rejected() {
+    let indptr = [0, 2, 3];
+    let bytes = encode_deltas(&[1, 5, 2], &indptr).unwrap();
+    assert_eq!(bytes, [1, 4, 2]);
+    assert_eq!(decode_deltas(&bytes, &indptr).unwrap(), [1, 5, 2]);
+
+    let err = "found 2 delta-encoded indices, which indptr does not account for";
+    assert_eq!(decode_deltas(&bytes[..2], &indptr).unwrap_err(), err);
+    let err = "found 4 delta-encoded indices, which indptr does not account for";
+    assert_eq!(decode_deltas(&[1, 4, 2, 9], &indptr).unwrap_err(), err);
+    // A varint cut off by the end of the stream.
+    let err = "found 3 delta-encoded indices, which indptr does not account for";
+    assert_eq!(decode_deltas(&[1, 4, 2, 0x80], &indptr).unwrap_err(), err);
+
+    let overflow = [[0xff; 10].as_slice(), &[0x7f]].concat();
+    assert_eq!(
+        decode_deltas(&overflow, &[0, 1]).unwrap_err(),
+        "a delta-encoded index does not fit in usize"
+    );
+}
@@ -0,0 +1,125 @@
+use halo2curves::bn256::Fr;
+use std::io;
+
+use spmvm_test_example::{
+    diff::{diff_vectors, verify_stream, STREAM_CHUNK_ELEMENTS},
+    hex::{field_from_hex, field_to_hex},
+};
+
+fn vector(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn equal_vectors() {
+    let v = vector(&[1, 2, 3]);
+    let diff = diff_vectors(&v, &v, 10);
+    assert!(diff.is_equal());
+    assert_eq!(diff.mismatches, 0);
+    assert!(diff.first.is_empty());
+    assert_eq!(diff.to_string(), "0 of 3 positions differ");
+}
+
+#[test]
+fn single_mismatch() {
+    let diff = diff_vectors(&vector(&[1, 5, 3]), &vector(&[1, 2, 3]), 10);
+    assert!(!diff.is_equal());
+    assert_eq!(diff.mismatches, 1);
+    assert_eq!(diff.first, vec![(1, Fr::from(5), Fr::from(2))]);
+    let text = diff.to_string();
+    assert!(
+        text.starts_with("1 of 3 positions differ\n  [1] got 0x"),
+        "{text}"
+    );
+    assert!(
+        text.ends_with(
+            "05, expected 0x{}02"
+                .replace("{}", &"0".repeat(62))
+                .as_str()
+        ),
+        "{text}"
+    );
+}
+
+#[test]
+fn length_mismatch() {
+    let diff = diff_vectors(&vector(&[1, 2]), &vector(&[1, 2, 3]), 10);
+    assert!(!diff.is_equal());
+    assert_eq!(diff.mismatches, 0);
+    assert_eq!(
+        diff.to_string(),
+        "length 2 differs from expected length 3\n0 of 2 positions differ"
+    );
+}
+
+#[test]
+fn limit_caps_listed_positions() {
+    let diff = diff_vectors(&vector(&[0, 0, 0, 0]), &vector(&[1, 2, 3, 4]), 2);
+    assert_eq!(diff.mismatches, 4);
+    assert_eq!(diff.first.len(), 2);
+    assert_eq!(diff.first[1].0, 1);
+    assert!(diff.to_string().ends_with("\n  ... and 2 more"));
+}
+
+#[test]
+fn hex_is_big_endian() {
+    assert_eq!(
+        field_to_hex(&Fr::from(1)),
+        format!("0x{}01", "0".repeat(62))
+    );
+    assert_eq!(
+        field_to_hex(&Fr::from(0x1234)),
+        format!("0x{}1234", "0".repeat(60))
+    );
+}
+
+#[test]
+fn hex_parses_back() {
+    for value in [Fr::from(0), Fr::from(0x1234), -Fr::from(1)] {
+        assert_eq!(field_from_hex::<Fr>(&field_to_hex(&value)), Ok(value));
+    }
+    assert_eq!(field_from_hex::<Fr>("0x1234"), Ok(Fr::from(0x1234)));
+    for hex in ["1234", "0x", "0x12g4", &format!("0x1{}", "0".repeat(64))] {
+        let err = field_from_hex::<Fr>(hex).unwrap_err();
+        assert!(err.contains("is not the hex of a field element"), "{err}");
+    }
+    let modulus = field_to_hex(&-Fr::from(1)).replace("0000000", "0000001");
+    let err = field_from_hex::<Fr>(&modulus).unwrap_err();
+    assert!(err.contains("is not below the modulus"), "{err}");
+}
+
+#[test]
+fn streamed_diffs_match_in_memory_diffs() {
+    // Mismatches on both sides of a chunk boundary, and more of them than the limit.
+    let len = 2 * STREAM_CHUNK_ELEMENTS + 5;
+    let expected: Vec<Fr> = (0..len as u64).map(Fr::from).collect();
+    let mut actual = expected.clone();
+    for i in [3, STREAM_CHUNK_ELEMENTS - 1, STREAM_CHUNK_ELEMENTS, len - 1] {
+        actual[i] = Fr::from(0) - actual[i];
+    }
+    let bytes = bincode::serialize(&expected).unwrap();
+    for limit in [0, 2, 10] {
+        let streamed = verify_stream(&bytes[..], &actual, limit).unwrap();
+        assert_eq!(streamed, diff_vectors(&actual, &expected, limit));
+    }
+
+    // Only the common length is compared, so a longer expected vector is not read to its end.
+    let streamed = verify_stream(&bytes[..bytes.len() - 32], &actual[..10], 10).unwrap();
+    assert_eq!(streamed, diff_vectors(&actual[..10], &expected, 10));
+    let streamed =
+        verify_stream(&bytes[..], &[actual.clone(), actual.clone()].concat(), 1).unwrap();
+    assert_eq!(streamed.expected_len, len);
+    assert_eq!(streamed.mismatches, 4);
+}
+
+#[test]
+fn streamed_diffs_reject_bad_input() {
+    let expected = vector(&[1, 2, 3]);
+    let mut bytes = bincode::serialize(&expected).unwrap();
+    let err = verify_stream(&bytes[..40], &expected, 10).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    bytes[8..40].fill(0xff);
+    let err = verify_stream(&bytes[..], &expected, 10).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
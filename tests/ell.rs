@@ -0,0 +1,95 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::random_vector,
+    sparse::{EllMatrix, EllShape},
+    SparseMatrix,
+};
+
+/// A `rows x cols` matrix whose rows have up to `max_row_nnz` entries, and every 50th row
+/// twenty times as many, as far as the columns allow.
+fn irregular(
+    rng: &mut ChaCha20Rng,
+    rows: usize,
+    cols: usize,
+    max_row_nnz: usize,
+) -> SparseMatrix<Fr> {
+    let dense: Vec<Vec<Fr>> = (0..rows)
+        .map(|row| {
+            let nnz = match row % 50 {
+                0 => 20 * max_row_nnz,
+                _ => rng.gen_range(0..=max_row_nnz),
+            };
+            let mut values = vec![Fr::from(0); cols];
+            for _ in 0..nnz.min(cols) {
+                values[rng.gen_range(0..cols)] = Fr::from(rng.gen_range(1..1000u64));
+            }
+            values
+        })
+        .collect();
+    match rows {
+        0 => SparseMatrix::zero(0, cols),
+        _ => SparseMatrix::from_dense(&dense),
+    }
+}
+
+#[test]
+fn ell_matches_the_plain_kernel() {
+    for seed in 0..24 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..120);
+        let (rows, max_row_nnz) = (rng.gen_range(0..3000), rng.gen_range(0..6));
+        let M = irregular(&mut rng, rows, cols, max_row_nnz);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec(&z);
+        for max_width in [0, 1, max_row_nnz, 20 * max_row_nnz, usize::MAX] {
+            let ell = EllMatrix::from_csr(&M, max_width);
+            assert_eq!(
+                ell.multiply_vec(&z),
+                expected,
+                "seed {seed}, {rows}x{cols}, width {max_width}"
+            );
+            let mut out = vec![Fr::from(1); 4];
+            ell.multiply_vec_into(&z, &mut out);
+            assert_eq!(out, M.multiply_vec_serial(&z));
+        }
+    }
+}
+
+#[test]
+fn long_rows_spill_into_the_tail() {
+    let M = SparseMatrix::from_dense(&[
+        vec![Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)],
+        vec![Fr::from(0), Fr::from(5), Fr::from(0), Fr::from(0)],
+        vec![Fr::from(0); 4],
+        vec![Fr::from(6), Fr::from(0), Fr::from(7), Fr::from(0)],
+    ]);
+    let ell = EllMatrix::from_csr(&M, 2);
+    assert_eq!((ell.num_rows(), ell.num_cols()), (4, 4));
+    let shape = EllShape {
+        width: 2,
+        padding: 5,
+        spilled_rows: 1,
+        spilled_nnz: 4,
+    };
+    assert_eq!(ell.shape(), shape);
+    assert_eq!(M.ell_shape(2), shape);
+    assert_eq!(shape.slots(4), 8);
+    assert_eq!(ell.tail().indptr, [0, 4, 4, 4, 4]);
+    assert_eq!(ell.tail().validate(), Ok(()));
+
+    // Wide enough for every row, nothing spills, and the width is that of the longest row.
+    let shape = M.ell_shape(100);
+    assert_eq!((shape.width, shape.padding), (4, 9));
+    assert_eq!((shape.spilled_rows, shape.spilled_nnz), (0, 0));
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn ell_rejects_a_short_vector() {
+    let M = SparseMatrix::<Fr>::identity(3);
+    EllMatrix::from_csr(&M, 1).multiply_vec(&[Fr::from(1); 2]);
+}
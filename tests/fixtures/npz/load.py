@@ -0,0 +1,33 @@
+"""Loads an npz written by `spmvm convert --to npz` into scipy.
+
+The field elements of `data` are rows of 32 little-endian bytes, which scipy
+cannot hold, so the structure is loaded as a matrix of ones and the values are
+decoded to Python integers alongside it, in the same order as `indices`:
+
+    python3 load.py sparse_matrices_<HASH>/A_0.npz
+"""
+
+import sys
+
+import numpy as np
+import scipy.sparse
+
+
+def load(path):
+    arrays = np.load(path)
+    assert arrays["format"] == b"csr"
+    rows, cols = arrays["shape"]
+    data = arrays["data"]
+    assert data.dtype == np.uint8 and data.shape == (len(arrays["indices"]), 32)
+    structure = scipy.sparse.csr_matrix(
+        (np.ones(len(data), dtype=np.int8), arrays["indices"], arrays["indptr"]),
+        shape=(rows, cols),
+    )
+    values = [int.from_bytes(row.tobytes(), "little") for row in data]
+    return structure, values
+
+
+if __name__ == "__main__":
+    structure, values = load(sys.argv[1])
+    print(f"{structure.shape[0]}x{structure.shape[1]}, {structure.nnz} entries")
+    print("first values:", [hex(value) for value in values[:4]])
@@ -0,0 +1,78 @@
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    folding::{compute_cross_term, cross_term},
+    generate::random_vector,
+    vec_ops::{add_vec, hadamard, scale_vec},
+    SparseMatrix,
+};
+
+fn fr_vec(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+fn dense(rows: &[[u64; 2]]) -> SparseMatrix<Fr> {
+    SparseMatrix::from_dense(&rows.iter().map(|row| fr_vec(row)).collect::<Vec<_>>())
+}
+
+#[test]
+fn cross_term_by_hand() {
+    // AZ1 = [1, 2], BZ1 = [3, 4], CZ1 = [2, 1]; AZ2 = [3, 4], BZ2 = [7, 8], CZ2 = [4, 3].
+    // With u1 = 2 and u2 = 1: T = [1 * 7 + 3 * 3 - 2 * 4 - 2, 2 * 8 + 4 * 4 - 2 * 3 - 1].
+    let A = dense(&[[1, 0], [0, 1]]);
+    let B = dense(&[[1, 1], [0, 2]]);
+    let C = dense(&[[0, 1], [1, 0]]);
+    let (z1, z2) = (fr_vec(&[1, 2]), fr_vec(&[3, 4]));
+    let t = compute_cross_term(&A, &B, &C, &z1, &z2, Fr::from(2), Fr::from(1)).unwrap();
+    assert_eq!(t, fr_vec(&[6, 25]));
+}
+
+/// The error vector `AZ ∘ BZ - u CZ` that makes `(z, u)` a relaxed instance.
+fn error_vector(matrices: [&SparseMatrix<Fr>; 3], z: &[Fr], u: Fr) -> Vec<Fr> {
+    let [A, B, C] = matrices;
+    let cz = scale_vec(-u, &C.multiply_vec(z));
+    add_vec(&hadamard(&A.multiply_vec(z), &B.multiply_vec(z)), &cz)
+}
+
+#[test]
+fn cross_term_folds_relaxed_instances() {
+    let mut rng = ChaCha20Rng::seed_from_u64(82);
+    let [A, B, C]: [SparseMatrix<Fr>; 3] =
+        std::array::from_fn(|_| SparseMatrix::random(30, 20, 4, &mut rng));
+    let (z1, z2): (Vec<Fr>, Vec<Fr>) = (random_vector(&mut rng, 20), random_vector(&mut rng, 20));
+    let (u1, u2, r) = (
+        Fr::random(&mut rng),
+        Fr::random(&mut rng),
+        Fr::random(&mut rng),
+    );
+    let (e1, e2) = (
+        error_vector([&A, &B, &C], &z1, u1),
+        error_vector([&A, &B, &C], &z2, u2),
+    );
+    let t = compute_cross_term(&A, &B, &C, &z1, &z2, u1, u2).unwrap();
+
+    // The folded instance `(z1 + r z2, u1 + r u2)` has error `E1 + r T + r^2 E2`.
+    let z = add_vec(&z1, &scale_vec(r, &z2));
+    let e = add_vec(&add_vec(&e1, &scale_vec(r, &t)), &scale_vec(r * r, &e2));
+    assert_eq!(error_vector([&A, &B, &C], &z, u1 + r * u2), e);
+}
+
+#[test]
+fn cross_term_from_products_and_shapes() {
+    let A = dense(&[[1, 0], [0, 1]]);
+    let products = |z: &[Fr]| (A.multiply_vec(z), A.multiply_vec(z), A.multiply_vec(z));
+    let (z1, z2) = (fr_vec(&[1, 2]), fr_vec(&[3, 4]));
+    // z1 ∘ z2 + z2 ∘ z1 - z2 - z1.
+    let t = cross_term(products(&z1), products(&z2), Fr::ONE, Fr::ONE);
+    assert_eq!(t, fr_vec(&[2, 10]));
+
+    let err = compute_cross_term(&A, &A, &A, &z1, &z2[1..], Fr::ONE, Fr::ONE).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "a matrix of 2 columns cannot multiply a vector of 1 elements"
+    );
+}
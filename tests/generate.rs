@@ -0,0 +1,102 @@
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    SparseMatrix,
+};
+
+const SHAPE: Shape = Shape {
+    rows: 50,
+    cols: 20,
+    nnz_per_row: 7,
+};
+
+#[test]
+fn random_matrix_has_requested_shape() {
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut ChaCha20Rng::seed_from_u64(1), SHAPE);
+    assert_eq!(matrix.cols, 20);
+    assert_eq!(matrix.indptr.len(), 51);
+    assert_eq!(matrix.nnz(), 350);
+    for row in matrix.indptr.windows(2) {
+        let columns = &matrix.indices[row[0]..row[1]];
+        assert_eq!(columns.len(), 7);
+        assert!(
+            columns.windows(2).all(|pair| pair[0] < pair[1]),
+            "{columns:?}"
+        );
+        assert!(columns.iter().all(|&col| col < 20));
+    }
+}
+
+#[test]
+fn same_seed_same_values() {
+    let generate = |seed| {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let matrix: SparseMatrix<Fr> = random_matrix(&mut rng, SHAPE);
+        let vector: Vec<Fr> = random_vector(&mut rng, 20);
+        (matrix, vector)
+    };
+    assert_eq!(generate(3), generate(3));
+    assert_ne!(generate(3), generate(4));
+}
+
+#[test]
+fn full_rows() {
+    let shape = Shape {
+        rows: 3,
+        cols: 4,
+        nnz_per_row: 4,
+    };
+    let matrix: SparseMatrix<Fr> = random_matrix(&mut ChaCha20Rng::seed_from_u64(0), shape);
+    assert_eq!(matrix.indices, [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "cannot place 5 nonzeros in 4 columns")]
+fn too_many_nonzeros_per_row() {
+    let shape = Shape {
+        rows: 1,
+        cols: 4,
+        nnz_per_row: 5,
+    };
+    let _: SparseMatrix<Fr> = random_matrix(&mut ChaCha20Rng::seed_from_u64(0), shape);
+}
+
+#[test]
+fn identity_and_zero_products() {
+    let mut rng = ChaCha20Rng::seed_from_u64(72);
+    for n in [0, 1, 2, 17, 100] {
+        let v: Vec<Fr> = random_vector(&mut rng, n);
+        let identity = SparseMatrix::identity(n);
+        identity.validate().unwrap();
+        assert_eq!(identity.multiply_vec(&v), v, "{n}");
+
+        let rows = rng.gen_range(0..50);
+        let zero = SparseMatrix::zero(rows, n);
+        zero.validate().unwrap();
+        assert_eq!((zero.shape(), zero.nnz()), ((rows, n), 0));
+        assert_eq!(zero.multiply_vec(&v), vec![Fr::ZERO; rows], "{rows}x{n}");
+    }
+}
+
+#[test]
+fn random_matrices_are_valid() {
+    let mut rng = ChaCha20Rng::seed_from_u64(72);
+    for _ in 0..64 {
+        let cols = rng.gen_range(0..60);
+        let rows = rng.gen_range(0..60);
+        let nnz_per_row = rng.gen_range(0..=cols);
+        let matrix: SparseMatrix<Fr> = SparseMatrix::random(rows, cols, nnz_per_row, &mut rng);
+        matrix.validate().unwrap();
+        assert_eq!(matrix.shape(), (rows, cols));
+        assert_eq!(matrix.nnz(), rows * nnz_per_row);
+        assert!(matrix.data.iter().all(|value| !bool::from(value.is_zero())));
+        assert!(matrix.rows().all(|row| {
+            let cols: Vec<_> = row.iter().map(|(col, _)| col).collect();
+            cols.windows(2).all(|pair| pair[0] < pair[1])
+        }));
+    }
+}
@@ -0,0 +1,271 @@
+#![allow(non_snake_case)]
+
+mod common;
+
+use std::fs;
+
+use common::{matrix, Fixture};
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{
+        header::{detect, HEADER_MAGIC, HEADER_VERSION},
+        Detected, ElementType, FileFormat, Header, IndexEncoding, RawCodec, HEADER_BYTES,
+    },
+    DataError, SparseMatrix,
+};
+
+fn tagged(format: FileFormat, element: ElementType) -> Detected {
+    Detected {
+        format,
+        header: Some(Header {
+            version: HEADER_VERSION,
+            format,
+            element,
+        }),
+    }
+}
+
+fn legacy(format: FileFormat) -> Detected {
+    Detected {
+        format,
+        header: None,
+    }
+}
+
+#[test]
+fn every_tag_round_trips() {
+    for format in [
+        FileFormat::Bincode,
+        FileFormat::Zstd,
+        FileFormat::Raw,
+        FileFormat::RawDelta,
+        FileFormat::Chunked,
+        FileFormat::Rkyv,
+    ] {
+        for element in [ElementType::Vector, ElementType::Matrix] {
+            let header = Header {
+                version: HEADER_VERSION,
+                format,
+                element,
+            };
+            let bytes = header.to_bytes();
+            assert_eq!(bytes[..4], HEADER_MAGIC);
+            assert_eq!(Header::parse(&bytes), Ok(Some(header)));
+            assert_eq!(detect("A_0", &bytes), Ok(tagged(format, element)));
+        }
+    }
+}
+
+#[test]
+fn written_files_carry_their_header() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let z = vec![Fr::from(4), Fr::from(5)];
+    config.write("witness_abc", "_0", &z).unwrap();
+    config.write_raw("witness_abc", "_0", &z).unwrap();
+    config
+        .write("sparse_matrices_abc", "A_0", &matrix(1))
+        .unwrap();
+    config
+        .write_raw_matrix(
+            "sparse_matrices_abc",
+            "A_0",
+            &matrix(1),
+            IndexEncoding::Delta,
+        )
+        .unwrap();
+    config
+        .clone()
+        .with_compression(true)
+        .write("sparse_matrices_abc", "B_0", &matrix(1))
+        .unwrap();
+
+    let cases = [
+        (
+            "witness_abc",
+            "_0",
+            FileFormat::Bincode,
+            ElementType::Vector,
+        ),
+        (
+            "witness_abc",
+            "_0.raw",
+            FileFormat::Raw,
+            ElementType::Vector,
+        ),
+        (
+            "sparse_matrices_abc",
+            "A_0",
+            FileFormat::Bincode,
+            ElementType::Matrix,
+        ),
+        (
+            "sparse_matrices_abc",
+            "A_0.raw",
+            FileFormat::RawDelta,
+            ElementType::Matrix,
+        ),
+        (
+            "sparse_matrices_abc",
+            "B_0",
+            FileFormat::Zstd,
+            ElementType::Matrix,
+        ),
+    ];
+    for (section, label, format, element) in cases {
+        assert_eq!(
+            config.detect_format(section, label).unwrap(),
+            tagged(format, element),
+            "{section}/{label}"
+        );
+    }
+
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, z);
+    let read: Vec<Fr> = config.read_raw("witness_abc", "_0").unwrap();
+    assert_eq!(read, z);
+    for label in ["A_0", "B_0"] {
+        let read: SparseMatrix<Fr> = config.read("sparse_matrices_abc", label).unwrap();
+        assert_eq!(read, matrix(1), "{label}");
+        let read: SparseMatrix<Fr> = config
+            .read_with_format("sparse_matrices_abc", label)
+            .unwrap();
+        assert_eq!(read, matrix(1), "{label}");
+    }
+    let read: SparseMatrix<Fr> = config.read_raw("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(read, matrix(1));
+}
+
+#[test]
+fn reads_dispatch_on_the_header_not_the_name() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let mut bytes = Header::new::<SparseMatrix<Fr>>(FileFormat::Raw)
+        .to_bytes()
+        .to_vec();
+    matrix(1).write_raw(&mut bytes).unwrap();
+    config
+        .write_bytes("sparse_matrices_abc", "A_0", &bytes)
+        .unwrap();
+
+    let read: SparseMatrix<Fr> = config
+        .read_with_format("sparse_matrices_abc", "A_0")
+        .unwrap();
+    assert_eq!(read, matrix(1));
+    // A plain bincode read cannot decode the raw codec, and says so.
+    let err = config
+        .read::<SparseMatrix<Fr>>("sparse_matrices_abc", "A_0")
+        .unwrap_err();
+    assert!(matches!(err, DataError::InvalidHeader { .. }), "{err:?}");
+    assert!(
+        err.to_string()
+            .ends_with("holds raw, which this read does not decode"),
+        "{err}"
+    );
+}
+
+#[test]
+fn headerless_files_fall_back_to_their_legacy_formats() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let section = config.root_dir().join("sparse_matrices_abc");
+    fs::create_dir_all(&section).unwrap();
+    let bincode = bincode::serialize(&matrix(1)).unwrap();
+    fs::write(section.join("A_0"), &bincode).unwrap();
+    fs::write(
+        section.join("B_0"),
+        zstd::encode_all(&bincode[..], 3).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        section.join("C_0.zst"),
+        zstd::encode_all(&bincode[..], 3).unwrap(),
+    )
+    .unwrap();
+    let mut raw = Vec::new();
+    matrix(1).write_raw(&mut raw).unwrap();
+    fs::write(section.join("A_0.raw"), &raw).unwrap();
+
+    let cases = [
+        ("A_0", FileFormat::Bincode),
+        ("B_0", FileFormat::Zstd),
+        ("C_0", FileFormat::Zstd),
+        ("A_0.raw", FileFormat::Raw),
+    ];
+    for (label, format) in cases {
+        assert_eq!(
+            config.detect_format("sparse_matrices_abc", label).unwrap(),
+            legacy(format),
+            "{label}"
+        );
+    }
+    for label in ["A_0", "B_0", "C_0"] {
+        let read: SparseMatrix<Fr> = config.read("sparse_matrices_abc", label).unwrap();
+        assert_eq!(read, matrix(1), "{label}");
+    }
+    let read: SparseMatrix<Fr> = config.read_raw("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(read, matrix(1));
+
+    // Chunked files and archives never get a header, and are recognized anyway.
+    matrix(1)
+        .write_chunked(2, fs::File::create(section.join("A_0.chunked")).unwrap())
+        .unwrap();
+    fs::write(section.join("A_0.rkyv"), [0; 64]).unwrap();
+    assert_eq!(
+        config
+            .detect_format("sparse_matrices_abc", "A_0.chunked")
+            .unwrap(),
+        legacy(FileFormat::Chunked)
+    );
+    assert_eq!(
+        config
+            .detect_format("sparse_matrices_abc", "A_0.rkyv")
+            .unwrap(),
+        legacy(FileFormat::Rkyv)
+    );
+}
+
+#[test]
+fn unusable_headers_are_rejected() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    config
+        .write("witness_abc", "_0", &vec![Fr::from(1)])
+        .unwrap();
+    config
+        .write("sparse_matrices_abc", "A_0", &matrix(1))
+        .unwrap();
+    let path = config.root_dir().join("witness_abc/_0");
+    let bytes = fs::read(&path).unwrap();
+
+    let err = config
+        .read::<SparseMatrix<Fr>>("witness_abc", "_0")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("{path} has an unusable header: holds a vector, not a matrix")
+    );
+    let err = config
+        .read_with_format::<Vec<Fr>>("sparse_matrices_abc", "A_0")
+        .unwrap_err();
+    assert!(
+        err.to_string().ends_with("holds a matrix, not a vector"),
+        "{err}"
+    );
+
+    let corruptions: [(usize, u8, &str); 3] = [
+        (4, 2, "header version 2 is newer than this build reads (1)"),
+        (6, 9, "unknown format tag 9"),
+        (7, 0, "unknown element type tag 0"),
+    ];
+    for (offset, value, message) in corruptions {
+        let mut bad = bytes.clone();
+        bad[offset] = value;
+        fs::write(&path, &bad).unwrap();
+        let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+        assert!(matches!(err, DataError::InvalidHeader { .. }), "{err:?}");
+        assert_eq!(err.path(), Some(&*path));
+        assert!(err.to_string().ends_with(message), "{err}");
+    }
+
+    // Shorter than a header, so taken for legacy bincode and failing as such.
+    fs::write(&path, &bytes[..HEADER_BYTES - 1]).unwrap();
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::Deserialize { .. }), "{err:?}");
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "delayed-reduction")]
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::{bn256, pasta::Fp};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    kernels::{self, multiply_vec_delayed},
+    SparseMatrix,
+};
+
+#[test]
+fn delayed_reduction_matches_the_generic_kernel() {
+    for seed in 0..32 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..200);
+        let shape = Shape {
+            rows: rng.gen_range(0..300),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(40)),
+        };
+        let M: SparseMatrix<bn256::Fr> = random_matrix(&mut rng, shape);
+        let z: Vec<bn256::Fr> = random_vector(&mut rng, cols);
+        assert_eq!(
+            kernels::bn256::multiply_vec(&M, &z),
+            M.multiply_vec_serial(&z),
+            "seed {seed}, {shape:?}"
+        );
+        assert_eq!(multiply_vec_delayed(&M, &z), M.multiply_vec(&z));
+    }
+}
+
+#[test]
+fn long_rows_of_the_largest_elements() {
+    // Products of `-1` by `-1` are the largest the sum can get, so thousands of them in a row
+    // carry into the top limb of the sum many times over.
+    let cols = 5000;
+    let minus_one = -bn256::Fr::ONE;
+    let M = SparseMatrix::from_dense(&[
+        vec![minus_one; cols],
+        vec![bn256::Fr::ZERO; cols],
+        (0..cols)
+            .map(|col| match col % 3 {
+                0 => minus_one,
+                1 => minus_one - bn256::Fr::ONE,
+                _ => bn256::Fr::ONE,
+            })
+            .collect(),
+    ]);
+    let mut rng = ChaCha20Rng::seed_from_u64(91);
+    for z in [vec![minus_one; cols], random_vector(&mut rng, cols)] {
+        assert_eq!(
+            kernels::bn256::multiply_vec(&M, &z),
+            M.multiply_vec_serial(&z)
+        );
+    }
+    assert_eq!(
+        kernels::bn256::multiply_vec(&M, &vec![minus_one; cols])[0],
+        bn256::Fr::from(cols as u64)
+    );
+}
+
+#[test]
+fn other_fields_use_the_generic_kernel() {
+    let mut rng = ChaCha20Rng::seed_from_u64(7);
+    let shape = Shape {
+        rows: 100,
+        cols: 50,
+        nnz_per_row: 6,
+    };
+    let M: SparseMatrix<Fp> = random_matrix(&mut rng, shape);
+    let z: Vec<Fp> = random_vector(&mut rng, 50);
+    assert_eq!(multiply_vec_delayed(&M, &z), M.multiply_vec_serial(&z));
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn delayed_reduction_rejects_a_short_vector() {
+    let M = SparseMatrix::<bn256::Fr>::identity(3);
+    kernels::bn256::multiply_vec(&M, &[bn256::Fr::ONE; 2]);
+}
@@ -0,0 +1,229 @@
+#![allow(non_snake_case)]
+
+mod common;
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    sync::Barrier,
+    thread,
+    time::Duration,
+};
+
+use common::Fixture;
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{lock::lock_file, read_arecibo_data, set_config, write_arecibo_data, CacheStatus},
+    DataError, SparseMatrix,
+};
+
+#[test]
+fn contending_loads_cache_once() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    config
+        .write(
+            "sparse_matrices_abc",
+            "A_0",
+            &SparseMatrix::<Fr>::identity(10_000),
+        )
+        .unwrap();
+
+    // Holding the lock of the entry lines both loads up behind it.
+    let cache_dir = config.cache_dir("abc");
+    fs::create_dir_all(&cache_dir).unwrap();
+    let held = lock_file(&cache_dir.join("A_0.lock"), Duration::ZERO).unwrap();
+    let barrier = Barrier::new(3);
+    let mut statuses = thread::scope(|s| {
+        let loads: Vec<_> = (0..2)
+            .map(|_| {
+                s.spawn(|| {
+                    barrier.wait();
+                    let (M, status) = config
+                        .read_cached::<SparseMatrix<Fr>>("abc", "A_0")
+                        .unwrap();
+                    assert_eq!(M, SparseMatrix::identity(10_000));
+                    status
+                })
+            })
+            .collect();
+        barrier.wait();
+        thread::sleep(Duration::from_millis(100));
+        drop(held);
+        loads
+            .into_iter()
+            .map(|load| load.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    statuses.sort_by_key(|status| status.as_str());
+    assert_eq!(statuses, [CacheStatus::Hit, CacheStatus::Miss]);
+}
+
+#[test]
+fn writes_give_up_on_a_held_lock() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_lock_timeout(Duration::from_millis(50));
+    config
+        .write("witness_abc", "_0", &vec![Fr::from(1)])
+        .unwrap();
+
+    let held = config.lock_section("witness_abc").unwrap();
+    let err = config
+        .write("witness_abc", "_0", &vec![Fr::from(2)])
+        .unwrap_err();
+    assert!(matches!(err, DataError::Locked { .. }), "{err}");
+    let lock = config.root_dir().join("witness_abc").join(".lock");
+    assert_eq!(
+        err.to_string(),
+        format!("another process holds the lock {lock}; gave up after waiting 50ms")
+    );
+
+    // Reads never wait for the lock.
+    let z: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(z, [Fr::from(1)]);
+    assert_eq!(config.labels("witness_abc").unwrap(), ["_0"]);
+
+    drop(held);
+    config
+        .write("witness_abc", "_0", &vec![Fr::from(2)])
+        .unwrap();
+}
+
+#[test]
+fn global_writes_wait_for_the_lock_without_holding_the_config() {
+    // The only test here that installs the global config.
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_lock_timeout(Duration::from_secs(5));
+    set_config(config.clone()).unwrap();
+    write_arecibo_data("witness_abc", "_0", &vec![Fr::from(1)]).unwrap();
+
+    let held = config.lock_section("witness_abc").unwrap();
+    thread::scope(|s| {
+        let write = s.spawn(|| write_arecibo_data("witness_abc", "_1", &vec![Fr::from(2)]));
+        thread::sleep(Duration::from_millis(100));
+        // The write is waiting for the lock, while reads through the config go ahead.
+        let z: Vec<Fr> = read_arecibo_data("witness_abc", "_0").unwrap();
+        assert_eq!(z, [Fr::from(1)]);
+        assert!(!write.is_finished());
+        drop(held);
+        write.join().unwrap().unwrap();
+    });
+    let z: Vec<Fr> = read_arecibo_data("witness_abc", "_1").unwrap();
+    assert_eq!(z, [Fr::from(2)]);
+}
+
+#[test]
+fn loads_skip_caching_on_a_held_lock() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_lock_timeout(Duration::from_millis(50));
+    config
+        .write(
+            "sparse_matrices_abc",
+            "A_0",
+            &SparseMatrix::<Fr>::identity(3),
+        )
+        .unwrap();
+    let cache_dir = config.cache_dir("abc");
+    fs::create_dir_all(&cache_dir).unwrap();
+    let held = lock_file(&cache_dir.join("A_0.lock"), Duration::ZERO).unwrap();
+
+    let read = || {
+        config
+            .read_cached::<SparseMatrix<Fr>>("abc", "A_0")
+            .unwrap()
+    };
+    assert_eq!(read(), (SparseMatrix::identity(3), CacheStatus::Miss));
+    assert!(!cache_dir.join("A_0.raw").exists());
+
+    drop(held);
+    assert_eq!(read(), (SparseMatrix::identity(3), CacheStatus::Miss));
+    assert_eq!(read(), (SparseMatrix::identity(3), CacheStatus::Hit));
+}
+
+/// Set to the lock file [`lock_holder`] takes when it runs as the child of
+/// [`locks_of_crashed_runs_are_released`].
+const HOLDER_ENV: &str = "SPMVM_TEST_LOCK_HOLDER";
+
+/// Takes the lock named by [`HOLDER_ENV`] and holds it until killed; does nothing otherwise.
+#[test]
+fn lock_holder() {
+    let Ok(path) = env::var(HOLDER_ENV) else {
+        return;
+    };
+    let _held = lock_file(path.as_str().into(), Duration::ZERO).unwrap();
+    println!("locked");
+    thread::sleep(Duration::from_secs(60));
+}
+
+#[test]
+fn locks_of_crashed_runs_are_released() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_lock_timeout(Duration::ZERO);
+    config
+        .write(
+            "sparse_matrices_abc",
+            "A_0",
+            &SparseMatrix::<Fr>::identity(3),
+        )
+        .unwrap();
+    let cache_dir = config.cache_dir("abc");
+    fs::create_dir_all(&cache_dir).unwrap();
+    let lock = cache_dir.join("A_0.lock");
+
+    // A run that dies while caching leaves its lock file behind, but not its lock.
+    let mut holder = Command::new(env::current_exe().unwrap())
+        .args(["lock_holder", "--exact", "--nocapture"])
+        .env(HOLDER_ENV, &lock)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = BufReader::new(holder.stdout.take().unwrap());
+    assert!(stdout.lines().any(|line| line.unwrap().ends_with("locked")));
+    assert!(matches!(
+        lock_file(&lock, Duration::ZERO),
+        Err(DataError::Locked { .. })
+    ));
+    holder.kill().unwrap();
+    holder.wait().unwrap();
+    assert!(lock.exists());
+
+    // Even without waiting, the next load takes the lock and caches the matrix.
+    let read = || {
+        config
+            .read_cached::<SparseMatrix<Fr>>("abc", "A_0")
+            .unwrap()
+    };
+    assert_eq!(read(), (SparseMatrix::identity(3), CacheStatus::Miss));
+    assert!(cache_dir.join("A_0.raw").exists());
+    assert_eq!(read(), (SparseMatrix::identity(3), CacheStatus::Hit));
+}
+
+#[test]
+fn leftover_lock_files_hold_no_lock() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_lock_timeout(Duration::ZERO);
+    config
+        .write(
+            "sparse_matrices_abc",
+            "A_0",
+            &SparseMatrix::<Fr>::identity(3),
+        )
+        .unwrap();
+    let cache_dir = config.cache_dir("abc");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("A_0.lock"), "12345\n").unwrap();
+    fs::write(config.root_dir().join("sparse_matrices_abc/.lock"), "").unwrap();
+
+    let (M, status) = config
+        .read_cached::<SparseMatrix<Fr>>("abc", "A_0")
+        .unwrap();
+    assert_eq!((M, status), (SparseMatrix::identity(3), CacheStatus::Miss));
+    assert!(cache_dir.join("A_0.raw").exists());
+    config
+        .write(
+            "sparse_matrices_abc",
+            "A_1",
+            &SparseMatrix::<Fr>::identity(2),
+        )
+        .unwrap();
+}
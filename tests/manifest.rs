@@ -0,0 +1,147 @@
+mod common;
+
+use std::fs;
+
+use common::{matrix, Fixture};
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    data::{
+        manifest::{hash_file, HASH_ALGORITHM},
+        Manifest, HEADER_BYTES, MANIFEST_FILE,
+    },
+    DataConfig, DataError,
+};
+
+fn manifest(config: &DataConfig, section: &str) -> Manifest {
+    Manifest::read(&config.root_dir().join(section))
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn writes_record_every_file() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let section = config.root_dir().join("sparse_matrices_abc");
+    config
+        .write("sparse_matrices_abc", "A_0", &matrix(1))
+        .unwrap();
+    config
+        .write_raw("sparse_matrices_abc", "A_0", &matrix(1))
+        .unwrap();
+    config
+        .write_bytes("sparse_matrices_abc", "notes", b"copied from elsewhere")
+        .unwrap();
+
+    let written = manifest(&config, "sparse_matrices_abc");
+    assert_eq!(written.algorithm, HASH_ALGORITHM);
+    assert_eq!(
+        written.files.keys().collect::<Vec<_>>(),
+        ["A_0", "A_0.raw", "notes"]
+    );
+    for (name, entry) in &written.files {
+        assert_eq!(entry, &hash_file(&section.join(name)).unwrap(), "{name}");
+    }
+    assert_eq!(written.files["notes"].bytes, 21);
+    assert_eq!(
+        written.files["notes"].hash,
+        blake3::hash(b"copied from elsewhere").to_hex().as_str()
+    );
+    assert_eq!(written.files["A_0"].hash.len(), 64);
+
+    // Compressing a file replaces its entry, as it replaces the file.
+    config
+        .clone()
+        .with_compression(true)
+        .write("sparse_matrices_abc", "A_0", &matrix(1))
+        .unwrap();
+    let written = manifest(&config, "sparse_matrices_abc");
+    assert_eq!(
+        written.files.keys().collect::<Vec<_>>(),
+        ["A_0.raw", "A_0.zst", "notes"]
+    );
+    assert_eq!(
+        config.labels("sparse_matrices_abc").unwrap(),
+        ["A_0", "A_0.raw", "notes"]
+    );
+}
+
+#[test]
+fn created_manifests_match_written_ones() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    config
+        .write("witness_abc", "_0", &vec![Fr::from(7)])
+        .unwrap();
+    config
+        .write("witness_abc", "_1", &vec![Fr::from(8)])
+        .unwrap();
+    let written = manifest(&config, "witness_abc");
+
+    fs::remove_file(config.root_dir().join("witness_abc").join(MANIFEST_FILE)).unwrap();
+    assert_eq!(config.create_manifest("witness_abc").unwrap(), written);
+    assert_eq!(manifest(&config, "witness_abc"), written);
+
+    let err = config.create_manifest("witness_def").unwrap_err();
+    assert!(matches!(err, DataError::SectionNotFound(_)), "{err:?}");
+}
+
+#[test]
+fn verified_reads_catch_modified_files() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_verify_files(true);
+    let witness = vec![Fr::from(1), Fr::from(2)];
+    config.write("witness_abc", "_0", &witness).unwrap();
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, witness);
+
+    let path = config.root_dir().join("witness_abc/_0");
+    let expected = manifest(&config, "witness_abc").files["_0"].hash.clone();
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[HEADER_BYTES + 8] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    let actual = hash_file(&path).unwrap().hash;
+
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert_eq!(err.path(), Some(&*path));
+    assert_eq!(
+        err.to_string(),
+        format!("{path} does not match its manifest: expected hash {expected}, found {actual}")
+    );
+
+    // Without verification the modified file loads, since it still decodes.
+    let read: Vec<Fr> = config
+        .clone()
+        .with_verify_files(false)
+        .read("witness_abc", "_0")
+        .unwrap();
+    assert_ne!(read, witness);
+}
+
+#[test]
+fn files_missing_from_the_manifest_still_load() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_verify_files(true);
+    let section = config.root_dir().join("witness_abc");
+    fs::create_dir_all(&section).unwrap();
+    let witness = vec![Fr::from(3)];
+    fs::write(section.join("_0"), bincode::serialize(&witness).unwrap()).unwrap();
+
+    let read: Vec<Fr> = config.read("witness_abc", "_0").unwrap();
+    assert_eq!(read, witness);
+}
+
+#[test]
+fn unreadable_manifests_are_reported() {
+    let Fixture { dir: _dir, config } = Fixture::empty();
+    let config = config.with_verify_files(true);
+    config
+        .write("witness_abc", "_0", &vec![Fr::from(3)])
+        .unwrap();
+    let path = config.root_dir().join("witness_abc").join(MANIFEST_FILE);
+    let json = fs::read_to_string(&path).unwrap();
+    fs::write(&path, json.replace(HASH_ALGORITHM, "md5")).unwrap();
+
+    let err = config.read::<Vec<Fr>>("witness_abc", "_0").unwrap_err();
+    assert!(matches!(err, DataError::InvalidManifest { .. }), "{err:?}");
+    assert_eq!(err.path(), Some(&*path));
+    assert!(err.to_string().contains("unsupported hash md5"), "{err}");
+}
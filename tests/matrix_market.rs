@@ -0,0 +1,210 @@
+#![allow(non_snake_case)]
+
+use std::fs;
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    SparseMatrix,
+};
+
+fn fixture(name: &str) -> SparseMatrix<Fr> {
+    let path = format!(
+        "{}/tests/fixtures/matrix_market/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    SparseMatrix::from_matrix_market(fs::File::open(path).unwrap()).unwrap()
+}
+
+fn parse(text: &str) -> Result<SparseMatrix<Fr>, String> {
+    SparseMatrix::from_matrix_market(text.as_bytes()).map_err(|err| err.to_string())
+}
+
+fn entries(values: &[i64]) -> Vec<Fr> {
+    let field = |v: i64| match v < 0 {
+        true => -Fr::from(v.unsigned_abs()),
+        false => Fr::from(v as u64),
+    };
+    values.iter().copied().map(field).collect()
+}
+
+fn write(M: &SparseMatrix<Fr>) -> String {
+    let mut bytes = Vec::new();
+    M.write_matrix_market(&mut bytes).unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn general_files_become_sorted_deduplicated_rows() {
+    // ```text
+    // [7 -3 0 0]
+    // [0  0 0 0]
+    // [9  0 0 7]
+    // [0  0 1 0]
+    // [0  0 0 0]
+    // ```
+    assert_eq!(
+        fixture("general.mtx"),
+        SparseMatrix {
+            data: entries(&[7, -3, 9, 7, 1]),
+            indices: vec![0, 1, 0, 3, 2],
+            indptr: vec![0, 2, 2, 4, 5, 5],
+            cols: 4,
+        }
+    );
+}
+
+#[test]
+fn symmetric_files_are_mirrored() {
+    assert_eq!(
+        fixture("symmetric.mtx"),
+        SparseMatrix {
+            data: entries(&[1, -20, -20, 4, 4, 0]),
+            indices: vec![0, 1, 0, 2, 1, 2],
+            indptr: vec![0, 2, 4, 6],
+            cols: 3,
+        }
+    );
+}
+
+#[test]
+fn pattern_entries_are_ones() {
+    assert_eq!(
+        fixture("pattern.mtx"),
+        SparseMatrix {
+            data: entries(&[1, 1, 1]),
+            indices: vec![0, 0, 2],
+            indptr: vec![0, 1, 3],
+            cols: 3,
+        }
+    );
+}
+
+#[test]
+fn an_empty_matrix_has_only_empty_rows() {
+    let M = parse("%%MatrixMarket matrix coordinate integer general\n3 2 0\n").unwrap();
+    assert_eq!(M.indptr, [0, 0, 0, 0]);
+    assert_eq!((M.cols, M.nnz()), (2, 0));
+}
+
+#[test]
+fn written_files_read_back_exactly() {
+    // The fixture ends in an empty row, which only the size line records.
+    let M = fixture("general.mtx");
+    let text = write(&M);
+    let mut lines = text.lines().filter(|line| !line.starts_with('%'));
+    assert_eq!(lines.next(), Some("5 4 5"));
+    assert_eq!(lines.count(), 5);
+    assert!(text.starts_with("%%MatrixMarket matrix coordinate integer general\n"));
+    assert!(text.contains("prime field of modulus 0x30644e72"), "{text}");
+    assert_eq!(parse(&text).unwrap(), M);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(5);
+    let shape = Shape {
+        rows: 30,
+        cols: 20,
+        nnz_per_row: 4,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    assert_eq!(parse(&write(&M)).unwrap(), M);
+}
+
+#[test]
+fn hex_values_are_field_elements() {
+    let text = "%%MatrixMarket matrix coordinate integer general\n1 2 2\n1 1 0x1f\n1 2 -0x2\n";
+    let M = parse(text).unwrap();
+    assert_eq!(M.data, entries(&[31, -2]));
+
+    let modulus = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+    for (value, message) in [
+        (modulus, "is not below the modulus"),
+        ("0x", "is not the hex of a field element"),
+        ("-0xg", "0xg is not the hex of a field element"),
+    ] {
+        let text =
+            format!("%%MatrixMarket matrix coordinate integer general\n1 1 1\n1 1 {value}\n");
+        let err = parse(&text).unwrap_err();
+        assert!(err.contains(message), "{value}: {err}");
+    }
+}
+
+#[test]
+fn invalid_banners_are_rejected() {
+    for (text, message) in [
+        ("", "line 1: expected a %%MatrixMarket matrix banner"),
+        (
+            "2 2 1\n1 1 1\n",
+            "line 1: expected a %%MatrixMarket matrix banner",
+        ),
+        (
+            "%%MatrixMarket matrix array integer general\n",
+            "array files are not supported",
+        ),
+        (
+            "%%MatrixMarket matrix coordinate complex general\n",
+            "complex values are not supported",
+        ),
+        (
+            "%%MatrixMarket matrix coordinate real hermitian\n",
+            "hermitian matrices are not supported",
+        ),
+        (
+            "%%MatrixMarket matrix coordinate real symmetric\n2 3 0\n",
+            "line 2: a symmetric matrix is square",
+        ),
+    ] {
+        let err = parse(text).unwrap_err();
+        assert!(err.contains(message), "{text:?}: {err}");
+    }
+}
+
+#[test]
+fn invalid_entries_are_rejected_with_their_line() {
+    for (banner, body, message) in [
+        (
+            "real",
+            "% c\n1 1 1\n1 1 0.5\n",
+            "line 4: 0.5 is not an integer",
+        ),
+        ("real", "1 1 1\n1 1 1e300\n", "too large to be read exactly"),
+        ("integer", "", "line 1: no size line"),
+        ("integer", "2 2\n", "line 2: expected 3 numbers"),
+        (
+            "integer",
+            "2 2 1\n0 1 1\n",
+            "line 3: row 0 is not between 1 and 2",
+        ),
+        (
+            "integer",
+            "2 2 1\n1 3 1\n",
+            "line 3: column 3 is not between 1 and 2",
+        ),
+        (
+            "integer",
+            "2 2 1\n1 1\n",
+            "expected a row, a column, and a value",
+        ),
+        (
+            "integer",
+            "2 2 1\n1 1 1 1\n",
+            "unexpected text after the entry",
+        ),
+        ("integer", "2 2 1\n1 1 x\n", "line 3: x is not an integer"),
+        (
+            "integer",
+            "2 2 2\n1 1 1\n",
+            "the file ends before its 2 entries",
+        ),
+        (
+            "integer",
+            "2 2 1\n1 1 1\n2 2 1\n",
+            "line 4: more than the 1 entries",
+        ),
+    ] {
+        let text = format!("%%MatrixMarket matrix coordinate {banner} general\n{body}");
+        let err = parse(&text).unwrap_err();
+        assert!(err.contains(message), "{text:?}: {err}");
+    }
+}
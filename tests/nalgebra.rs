@@ -0,0 +1,86 @@
+#![cfg(feature = "nalgebra")]
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use nalgebra_sparse::SparseFormatErrorKind;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    SparseMatrix,
+};
+
+fn values(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+fn error_kind(M: &SparseMatrix<Fr>) -> SparseFormatErrorKind {
+    *M.to_nalgebra_csr().unwrap_err().kind()
+}
+
+#[test]
+fn random_matrices_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(6);
+    let shape = Shape {
+        rows: 40,
+        cols: 25,
+        nnz_per_row: 3,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let csr = M.to_nalgebra_csr().unwrap();
+    assert_eq!((csr.nrows(), csr.ncols(), csr.nnz()), (40, 25, 120));
+    assert_eq!(SparseMatrix::from_nalgebra_csr(&csr), M);
+}
+
+#[test]
+fn unsorted_rows_are_sorted() {
+    let M = SparseMatrix {
+        data: values(&[1, 2, 3, 4, 5]),
+        indices: vec![3, 0, 2, 1, 0],
+        indptr: vec![0, 3, 3, 5, 5],
+        cols: 4,
+    };
+    let csr = M.to_nalgebra_csr().unwrap();
+    assert_eq!(csr.row_offsets(), [0, 3, 3, 5, 5]);
+    assert_eq!(csr.col_indices(), [0, 2, 3, 0, 1]);
+    assert_eq!(csr.values(), values(&[2, 3, 1, 5, 4]));
+
+    let back = SparseMatrix::from_nalgebra_csr(&csr);
+    assert_eq!(back.indptr, M.indptr);
+    assert_eq!(back.cols, 4);
+    let z = values(&[1, 10, 100, 1000]);
+    assert_eq!(back.multiply_vec(&z), M.multiply_vec(&z));
+}
+
+#[test]
+fn invalid_matrices_are_rejected() {
+    let repeated = SparseMatrix {
+        data: values(&[1, 2]),
+        indices: vec![1, 1],
+        indptr: vec![0, 2],
+        cols: 2,
+    };
+    assert_eq!(error_kind(&repeated), SparseFormatErrorKind::DuplicateEntry);
+
+    let out_of_bounds = SparseMatrix {
+        data: values(&[1]),
+        indices: vec![2],
+        indptr: vec![0, 1],
+        cols: 2,
+    };
+    assert_eq!(
+        error_kind(&out_of_bounds),
+        SparseFormatErrorKind::IndexOutOfBounds
+    );
+
+    let short_indptr = SparseMatrix {
+        data: values(&[1, 2]),
+        indices: vec![0, 1],
+        indptr: vec![0, 1],
+        cols: 2,
+    };
+    assert_eq!(
+        error_kind(&short_indptr),
+        SparseFormatErrorKind::InvalidStructure
+    );
+}
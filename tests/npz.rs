@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+
+use std::fs;
+
+use ff::PrimeField;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    SparseMatrix,
+};
+
+fn round_trip(M: &SparseMatrix<Fr>) -> SparseMatrix<Fr> {
+    let mut bytes = Vec::new();
+    M.write_npz(&mut bytes).unwrap();
+    SparseMatrix::from_npz(&bytes[..]).unwrap()
+}
+
+fn read(bytes: &[u8]) -> Result<SparseMatrix<Fr>, String> {
+    SparseMatrix::from_npz(bytes).map_err(|err| err.to_string())
+}
+
+#[test]
+fn written_files_read_back_exactly() {
+    let mut rng = ChaCha20Rng::seed_from_u64(9);
+    let shape = Shape {
+        rows: 40,
+        cols: 25,
+        nnz_per_row: 3,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    assert_eq!(round_trip(&M), M);
+
+    // Empty rows at the end are kept, and an empty matrix has no entries to write.
+    let M = SparseMatrix {
+        data: vec![Fr::from(3), -Fr::from(1)],
+        indices: vec![2, 0],
+        indptr: vec![0, 1, 2, 2, 2],
+        cols: 3,
+    };
+    assert_eq!(round_trip(&M), M);
+    let M = SparseMatrix::<Fr> {
+        data: vec![],
+        indices: vec![],
+        indptr: vec![0, 0],
+        cols: 0,
+    };
+    assert_eq!(round_trip(&M), M);
+}
+
+#[test]
+fn values_are_rows_of_canonical_bytes() {
+    let M = SparseMatrix {
+        data: vec![-Fr::from(1)],
+        indices: vec![0],
+        indptr: vec![0, 1],
+        cols: 1,
+    };
+    let mut bytes = Vec::new();
+    M.write_npz(&mut bytes).unwrap();
+    let header = "{'descr': '|u1', 'fortran_order': False, 'shape': (1, 32), }";
+    let at = bytes
+        .windows(header.len())
+        .position(|window| window == header.as_bytes())
+        .unwrap();
+    // The header is padded so the array starts 64-byte aligned within its `.npy`.
+    let data_start = at + bytes[at..].iter().position(|&b| b == b'\n').unwrap() + 1;
+    let repr = (-Fr::from(1)).to_repr();
+    assert_eq!(&bytes[data_start..data_start + 32], repr.as_ref());
+    assert_eq!((data_start - (at - 10)) % 64, 0);
+}
+
+#[test]
+fn compressed_files_saved_by_scipy_are_read() {
+    // A 50x50 tridiagonal matrix of `2` with `-1` either side, saved by `save_npz` with int64
+    // values, int32 indices, and every array deflated.
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/npz/tridiagonal.npz"
+    );
+    let M: SparseMatrix<Fr> = SparseMatrix::from_npz(fs::File::open(path).unwrap()).unwrap();
+    assert_eq!((M.indptr.len() - 1, M.cols, M.nnz()), (50, 50, 148));
+    assert_eq!(&M.indices[..5], [0, 1, 0, 1, 2]);
+    assert_eq!(&M.data[..3], [Fr::from(2), -Fr::from(1), -Fr::from(1)]);
+    let z = vec![Fr::from(1); 50];
+    let Mz = M.multiply_vec(&z);
+    assert_eq!(Mz[0], Fr::from(1));
+    assert_eq!(Mz[25], Fr::from(0));
+}
+
+#[test]
+fn corrupt_files_are_rejected() {
+    let M = SparseMatrix {
+        data: vec![Fr::from(5)],
+        indices: vec![1],
+        indptr: vec![0, 1],
+        cols: 2,
+    };
+    let mut bytes = Vec::new();
+    M.write_npz(&mut bytes).unwrap();
+
+    let err = read(b"not a zip").unwrap_err();
+    assert!(err.contains("not a zip archive"), "{err}");
+    let err = read(&bytes[..bytes.len() / 2]).unwrap_err();
+    assert!(err.contains("not a zip archive"), "{err}");
+
+    let mut flipped = bytes.clone();
+    let repr = Fr::from(5).to_repr();
+    let value = bytes
+        .windows(32)
+        .position(|window| window == repr.as_ref())
+        .unwrap();
+    flipped[value] ^= 1;
+    let err = read(&flipped).unwrap_err();
+    assert!(err.contains("data.npy is corrupt"), "{err}");
+
+    let too_few_cols = SparseMatrix { cols: 1, ..M };
+    let mut bytes = Vec::new();
+    too_few_cols.write_npz(&mut bytes).unwrap();
+    let err = read(&bytes).unwrap_err();
+    assert!(
+        err.contains("row 0: column 1 of the entry at 0 is out of bounds of the 1 columns"),
+        "{err}"
+    );
+}
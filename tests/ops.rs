@@ -0,0 +1,262 @@
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{generate::random_vector, SparseMatrix};
+
+fn random_matrix(seed: u64) -> SparseMatrix<Fr> {
+    SparseMatrix::random(40, 30, 5, &mut ChaCha20Rng::seed_from_u64(seed))
+}
+
+#[test]
+fn scale_by_one_is_the_identity() {
+    let A = random_matrix(74);
+    assert_eq!(A.scale(Fr::ONE), A);
+}
+
+#[test]
+fn scale_by_zero_keeps_the_structure() {
+    let A = random_matrix(74);
+    let Z = A.scale(Fr::ZERO);
+    assert_eq!(
+        (&Z.indices, &Z.indptr, Z.cols),
+        (&A.indices, &A.indptr, A.cols)
+    );
+    assert!(Z.data.iter().all(|value| bool::from(value.is_zero())));
+}
+
+#[test]
+fn scaled_products_are_scaled() {
+    let mut rng = ChaCha20Rng::seed_from_u64(74);
+    for seed in 0..16 {
+        let A = random_matrix(seed);
+        let r = Fr::random(&mut rng);
+        let z: Vec<Fr> = random_vector(&mut rng, A.cols);
+        let scaled: Vec<Fr> = A.multiply_vec(&z).iter().map(|value| r * value).collect();
+        assert_eq!(A.scale(r).multiply_vec(&z), scaled, "seed {seed}");
+
+        let mut B = A.clone();
+        B.scale_mut(r);
+        assert_eq!(B, A.scale(r));
+    }
+}
+
+#[test]
+fn scale_row_only_touches_that_row() {
+    let mut rng = ChaCha20Rng::seed_from_u64(74);
+    let A = random_matrix(1);
+    let z: Vec<Fr> = random_vector(&mut rng, A.cols);
+    let r = Fr::random(&mut rng);
+    let mut B = A.clone();
+    B.scale_row(7, r);
+
+    let (expected, actual) = (A.multiply_vec(&z), B.multiply_vec(&z));
+    for (row, (expected, actual)) in expected.iter().zip(&actual).enumerate() {
+        match row {
+            7 => assert_eq!(*actual, r * expected),
+            _ => assert_eq!(actual, expected, "row {row}"),
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "row 40 is out of bounds of the 40 rows")]
+fn scale_row_out_of_bounds() {
+    random_matrix(0).scale_row(40, Fr::ONE);
+}
+
+fn fr_vec(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn add_merges_disjoint_structures() {
+    // [1 0 2]   [0 3 0]
+    // [0 0 0] + [4 0 0]
+    let A = SparseMatrix::try_new(fr_vec(&[1, 2]), vec![0, 2], vec![0, 2, 2], 3).unwrap();
+    let B = SparseMatrix::try_new(fr_vec(&[3, 4]), vec![1, 0], vec![0, 1, 2], 3).unwrap();
+    let sum = A.add(&B, false).unwrap();
+    assert_eq!(sum.data, fr_vec(&[1, 3, 2, 4]));
+    assert_eq!(sum.indices, [0, 1, 2, 0]);
+    assert_eq!(sum.indptr, [0, 3, 4]);
+    assert_eq!(sum, B.add(&A, true).unwrap());
+}
+
+#[test]
+fn add_sums_overlapping_structures() {
+    let mut rng = ChaCha20Rng::seed_from_u64(75);
+    let A = random_matrix(75);
+    let r = Fr::random(&mut rng);
+    let sum = A.add(&A.scale(r), false).unwrap();
+    assert_eq!(sum, A.scale(r + Fr::ONE));
+
+    for seed in 0..16 {
+        let B = random_matrix(seed);
+        let z: Vec<Fr> = random_vector(&mut rng, A.cols);
+        let expected: Vec<Fr> = A
+            .multiply_vec(&z)
+            .iter()
+            .zip(B.multiply_vec(&z))
+            .map(|(a, b)| a + b)
+            .collect();
+        let sum = A.add(&B, false).unwrap();
+        sum.validate().unwrap();
+        assert_eq!(sum.multiply_vec(&z), expected, "seed {seed}");
+    }
+}
+
+#[test]
+fn add_sorts_rows_and_sums_duplicates() {
+    let A = SparseMatrix::new_unchecked(fr_vec(&[1, 2, 3]), vec![2, 0, 2], vec![0, 3], 3);
+    let B = SparseMatrix::zero(1, 3);
+    let sum = A.add(&B, false).unwrap();
+    assert_eq!((sum.data, sum.indices), (fr_vec(&[2, 4]), vec![0, 2]));
+}
+
+#[test]
+fn add_prunes_only_when_asked() {
+    let A = random_matrix(75);
+    let minus = A.scale(-Fr::ONE);
+    let kept = A.add(&minus, false).unwrap();
+    assert_eq!((&kept.indices, &kept.indptr), (&A.indices, &A.indptr));
+    assert!(kept.data.iter().all(|value| bool::from(value.is_zero())));
+
+    let pruned = A.add(&minus, true).unwrap();
+    assert_eq!(pruned, SparseMatrix::zero(A.num_rows(), A.cols));
+}
+
+#[test]
+fn add_requires_equal_shapes() {
+    let err = random_matrix(0).add(&SparseMatrix::zero(40, 31), false);
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "a 40x30 matrix cannot be combined with a 40x31 one"
+    );
+}
+
+#[test]
+fn combined_product_equals_combined_products() {
+    let mut rng = ChaCha20Rng::seed_from_u64(76);
+    let [A, B, C] = [1, 2, 3].map(random_matrix);
+    let r = Fr::random(&mut rng);
+    let z: Vec<Fr> = random_vector(&mut rng, A.cols);
+
+    let combined =
+        SparseMatrix::multiply_vec_combined(&[(&A, Fr::ONE), (&B, r), (&C, r * r)], &z).unwrap();
+    let (a, b, c) = (A.multiply_vec(&z), B.multiply_vec(&z), C.multiply_vec(&z));
+    let expected: Vec<Fr> = (0..a.len())
+        .map(|i| a[i] + r * b[i] + r * r * c[i])
+        .collect();
+    assert_eq!(combined, expected);
+
+    let sum = A
+        .add(&B.scale(r), false)
+        .unwrap()
+        .add(&C.scale(r * r), false)
+        .unwrap();
+    assert_eq!(sum.multiply_vec(&z), expected);
+    assert_eq!(SparseMatrix::multiply_vec_combined(&[], &z).unwrap(), []);
+}
+
+#[test]
+fn combined_product_checks_shapes() {
+    let (A, B) = (random_matrix(0), SparseMatrix::zero(40, 29));
+    let z = vec![Fr::ONE; 30];
+    let err = SparseMatrix::multiply_vec_combined(&[(&A, Fr::ONE), (&B, Fr::ONE)], &z);
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "a 40x30 matrix cannot be combined with a 40x29 one"
+    );
+    let err = SparseMatrix::multiply_vec_combined(&[(&A, Fr::ONE)], &z[1..]);
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "a matrix of 30 columns cannot multiply a vector of 29 elements"
+    );
+}
+
+/// A `rows`x`cols` matrix with about a quarter of its entries set, and every third row empty.
+fn random_dense(rng: &mut ChaCha20Rng, rows: usize, cols: usize) -> Vec<Vec<Fr>> {
+    (0..rows)
+        .map(|row| {
+            (0..cols)
+                .map(|_| match row % 3 != 2 && rng.gen_bool(0.25) {
+                    true => Fr::random(&mut *rng),
+                    false => Fr::ZERO,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn dense_product(a: &[Vec<Fr>], b: &[Vec<Fr>], cols: usize) -> Vec<Vec<Fr>> {
+    a.iter()
+        .map(|row| {
+            (0..cols)
+                .map(|j| row.iter().zip(b).map(|(x, b_row)| *x * b_row[j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn matmul_equals_the_dense_product() {
+    let mut rng = ChaCha20Rng::seed_from_u64(77);
+    for (m, k, n) in [
+        (1, 1, 1),
+        (6, 6, 6),
+        (9, 4, 13),
+        (13, 20, 3),
+        (0, 5, 4),
+        (7, 0, 5),
+    ] {
+        let (a, b) = (random_dense(&mut rng, m, k), random_dense(&mut rng, k, n));
+        let (A, B) = (SparseMatrix::from_dense(&a), SparseMatrix::from_dense(&b));
+        let (A, B) = (SparseMatrix { cols: k, ..A }, SparseMatrix { cols: n, ..B });
+        let product = A.matmul(&B).unwrap();
+        assert_eq!(product.shape(), (m, n), "{m}x{k} by {k}x{n}");
+        assert_eq!(product.to_dense().unwrap(), dense_product(&a, &b, n));
+        for row in 0..m {
+            let cols: Vec<usize> = product.row(row).unwrap().map(|(col, _)| col).collect();
+            assert!(cols.windows(2).all(|pair| pair[0] < pair[1]), "row {row}");
+        }
+    }
+}
+
+#[test]
+fn matmul_by_the_identity_is_the_matrix() {
+    let A = random_matrix(77);
+    let sorted = A.to_coo().into_csr();
+    assert_eq!(SparseMatrix::identity(40).matmul(&A).unwrap(), sorted);
+    assert_eq!(A.matmul(&SparseMatrix::identity(30)).unwrap(), sorted);
+}
+
+#[test]
+fn matmul_of_wide_matrices_multiplies_vectors_in_turn() {
+    let mut rng = ChaCha20Rng::seed_from_u64(77);
+    let A = random_matrix(0);
+    let B = SparseMatrix::random(30, (1 << 18) + 1, 3, &mut rng);
+    let z: Vec<Fr> = random_vector(&mut rng, B.cols);
+    let product = A.matmul(&B).unwrap();
+    assert_eq!(
+        product.multiply_vec(&z),
+        A.multiply_vec(&B.multiply_vec(&z))
+    );
+}
+
+#[test]
+fn matmul_checks_shapes_and_the_limit() {
+    let A = random_matrix(0);
+    let err = A.matmul(&A);
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "a 40x30 matrix cannot multiply a 40x30 one"
+    );
+    let ones = SparseMatrix::from_dense(&vec![vec![Fr::ONE; 4]; 4]);
+    assert_eq!(ones.matmul_with_limit(&ones, 16).unwrap().nnz(), 16);
+    assert_eq!(
+        ones.matmul_with_limit(&ones, 15).unwrap_err().to_string(),
+        "the product would have 16 entries, over the limit of 15"
+    );
+}
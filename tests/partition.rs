@@ -0,0 +1,148 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::ThreadPoolBuilder;
+use spmvm_test_example::{
+    generate::random_vector,
+    sparse::{ChunkPolicy, Partition, PARTITION_CHUNKS_PER_THREAD},
+    SparseMatrix,
+};
+
+/// A `rows x cols` matrix in which every 97th row is full and the others have two entries, or
+/// none every 5th, like the skew of arecibo's matrices.
+fn skewed(rows: usize, cols: usize) -> SparseMatrix<Fr> {
+    let (mut data, mut indices, mut indptr) = (Vec::new(), Vec::new(), vec![0]);
+    for row in 0..rows {
+        let row_cols: Vec<usize> = match row {
+            row if row % 97 == 0 => (0..cols).collect(),
+            row if row % 5 == 0 => Vec::new(),
+            row => vec![row % cols, (row + 1) % cols],
+        };
+        let mut row_cols = row_cols;
+        row_cols.sort_unstable();
+        row_cols.dedup();
+        for col in row_cols {
+            data.push(Fr::from((row * cols + col + 1) as u64));
+            indices.push(col);
+        }
+        indptr.push(indices.len());
+    }
+    SparseMatrix::try_new(data, indices, indptr, cols).unwrap()
+}
+
+#[test]
+fn partitions_give_the_same_product() {
+    let mut rng = ChaCha20Rng::seed_from_u64(86);
+    for (rows, cols) in [(0, 4), (1, 1), (5, 3), (1000, 64), (4099, 300)] {
+        let M = skewed(rows, cols);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec_serial(&z);
+        for threads in [1, 3, 8] {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            for partition in [Partition::Rows, Partition::Nnz] {
+                let mut out = vec![Fr::from(7); 3];
+                M.in_pool(&pool, |M| {
+                    M.multiply_vec_partitioned_into(&z, &mut out, partition)
+                });
+                assert_eq!(
+                    out, expected,
+                    "{rows}x{cols}, {threads} threads, {partition:?}"
+                );
+            }
+        }
+        assert_eq!(M.multiply_vec(&z), expected);
+    }
+}
+
+#[test]
+fn every_chunk_policy_gives_the_same_product() {
+    let mut rng = ChaCha20Rng::seed_from_u64(87);
+    let policies = [
+        ChunkPolicy::Auto,
+        ChunkPolicy::Rows(0),
+        ChunkPolicy::Rows(1),
+        ChunkPolicy::Rows(7),
+        ChunkPolicy::Rows(100_000),
+        ChunkPolicy::Nnz(0),
+        ChunkPolicy::Nnz(1),
+        ChunkPolicy::Nnz(50),
+        ChunkPolicy::Nnz(1 << 20),
+    ];
+    for (rows, cols) in [(0, 4), (1, 1), (5, 3), (1000, 64), (4099, 300)] {
+        let M = skewed(rows, cols);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec_serial(&z);
+        let pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        for policy in policies {
+            let mut out = Vec::new();
+            M.in_pool(&pool, |M| M.multiply_vec_chunked_into(&z, &mut out, policy));
+            assert_eq!(out, expected, "{rows}x{cols}, {policy}");
+        }
+    }
+}
+
+#[test]
+fn nnz_partition_balances_entries_and_rows() {
+    let M = skewed(4099, 300);
+    let chunks = 4 * PARTITION_CHUNKS_PER_THREAD;
+    let boundaries = M.nnz_partition(chunks);
+    assert_eq!(boundaries.first(), Some(&0));
+    assert_eq!(boundaries.last(), Some(&M.num_rows()));
+    assert!(
+        boundaries.windows(2).all(|run| run[0] < run[1]),
+        "{boundaries:?}"
+    );
+    assert!(boundaries.len() <= chunks + 1);
+
+    // No run does much more than its share, give or take the heaviest row.
+    let work = |row: usize| M.indptr[row] + row;
+    let share = work(M.num_rows()) / chunks;
+    for run in boundaries.windows(2) {
+        assert!(work(run[1]) - work(run[0]) <= share + 302, "{run:?}");
+    }
+}
+
+#[test]
+fn nnz_partition_of_degenerate_matrices() {
+    assert_eq!(SparseMatrix::<Fr>::zero(0, 3).nnz_partition(8), [0, 0]);
+    assert_eq!(SparseMatrix::<Fr>::zero(10, 3).nnz_partition(1), [0, 10]);
+    // Empty rows still count, so they are split too.
+    assert_eq!(SparseMatrix::<Fr>::zero(10, 3).nnz_partition(2), [0, 5, 10]);
+    assert_eq!(SparseMatrix::<Fr>::zero(3, 3).nnz_partition(0), [0, 3]);
+    // Never more runs than rows.
+    assert_eq!(
+        SparseMatrix::<Fr>::identity(3).nnz_partition(100),
+        [0, 1, 2, 3]
+    );
+
+    // A row holding every entry makes its run that much longer, so there are fewer.
+    let M = SparseMatrix::from_dense(&[
+        vec![Fr::from(0); 4],
+        vec![Fr::from(1); 4],
+        vec![Fr::from(0); 4],
+        vec![Fr::from(0); 4],
+    ]);
+    assert_eq!(M.nnz_partition(4), [0, 2, 4]);
+}
+
+#[test]
+fn multiply_rows_into_writes_only_the_given_rows() {
+    let M = skewed(20, 6);
+    let z: Vec<Fr> = (1..=6).map(Fr::from).collect();
+    let expected = M.multiply_vec_serial(&z);
+    let mut out = vec![Fr::from(0); 7];
+    M.multiply_rows_into(5..12, &z, &mut out);
+    assert_eq!(out, expected[5..12]);
+}
+
+#[test]
+#[should_panic(expected = "invalid output length")]
+fn multiply_rows_into_rejects_a_short_output() {
+    let M = skewed(20, 6);
+    M.multiply_rows_into(0..4, &[Fr::from(1); 6], &mut [Fr::from(0); 3]);
+}
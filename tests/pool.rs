@@ -0,0 +1,83 @@
+#![allow(non_snake_case)]
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    thread::{self, ThreadId},
+};
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::MatrixError,
+    statistics::MatrixStats,
+    SparseMatrix,
+};
+
+/// A pool of `threads` threads, with the ids of those threads as they start.
+fn recording_pool(threads: usize) -> (ThreadPool, Arc<Mutex<HashSet<ThreadId>>>) {
+    let started = Arc::new(Mutex::new(HashSet::new()));
+    let hook = Arc::clone(&started);
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .start_handler(move |_| {
+            hook.lock().unwrap().insert(thread::current().id());
+        })
+        .build()
+        .unwrap();
+    // The handler runs as each thread starts, so wait until all of them have.
+    pool.broadcast(|_| ());
+    (pool, started)
+}
+
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(85);
+    let shape = Shape {
+        rows: 4096,
+        cols: 64,
+        nnz_per_row: 8,
+    };
+    random_matrix(&mut rng, shape)
+}
+
+#[test]
+fn work_runs_on_the_threads_of_the_pool() {
+    let (pool, started) = recording_pool(3);
+    let M = matrix();
+    let ran: HashSet<ThreadId> = M.in_pool(&pool, |M| {
+        M.rows()
+            .with_min_len(1)
+            .map(|_| thread::current().id())
+            .collect()
+    });
+    let started = started.lock().unwrap();
+    assert_eq!(started.len(), 3);
+    assert!(!ran.is_empty());
+    assert!(ran.is_subset(&started), "{ran:?} not in {started:?}");
+    assert!(!ran.contains(&thread::current().id()));
+}
+
+#[test]
+fn operations_in_a_pool_match_the_global_ones() {
+    let (pool, _) = recording_pool(2);
+    let M = matrix();
+    let z: Vec<Fr> = random_vector(&mut ChaCha20Rng::seed_from_u64(1), M.cols);
+
+    assert_eq!(M.multiply_vec_in_pool(&z, &pool), M.multiply_vec(&z));
+    let mut out = Vec::new();
+    M.multiply_vec_into_in_pool(&z, &mut out, &pool);
+    assert_eq!(out, M.multiply_vec(&z));
+    assert_eq!(M.clone_in_pool(&pool), M);
+    assert_eq!(MatrixStats::new_in_pool(&M, &pool), MatrixStats::new(&M));
+    assert_eq!(M.validate_in_pool(&pool), Ok(()));
+
+    let mut broken = M.clone();
+    broken.indices[0] = broken.cols;
+    assert!(matches!(
+        broken.validate_in_pool(&pool),
+        Err(MatrixError::IndexOutOfBounds { row: 0, .. })
+    ));
+}
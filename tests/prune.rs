@@ -0,0 +1,90 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{generate::random_vector, SparseMatrix};
+
+/// A matrix with explicit zeros at the start and end of rows, in the middle of one, and
+/// filling whole rows, with the matrix it prunes to.
+fn with_zeros() -> (SparseMatrix<Fr>, SparseMatrix<Fr>) {
+    let M = SparseMatrix {
+        data: [0, 1, 2, 3, 0, 0, 0, 4, 0, 5, 0, 0].map(Fr::from).to_vec(),
+        indices: vec![0, 1, 2, 0, 3, 1, 2, 0, 1, 2, 1, 3],
+        indptr: vec![0, 3, 5, 7, 7, 10, 12],
+        cols: 4,
+    };
+    let pruned = SparseMatrix {
+        data: [1, 2, 3, 4, 5].map(Fr::from).to_vec(),
+        indices: vec![1, 2, 0, 0, 2],
+        indptr: vec![0, 2, 3, 3, 3, 5, 5],
+        cols: 4,
+    };
+    (M, pruned)
+}
+
+/// A `rows x cols` matrix storing about 30% of its entries, half of them zero.
+fn half_zeros(rng: &mut ChaCha20Rng, rows: usize, cols: usize) -> SparseMatrix<Fr> {
+    let (mut data, mut indices, mut indptr) = (Vec::new(), Vec::new(), vec![0]);
+    for _ in 0..rows {
+        for col in 0..cols {
+            if rng.gen_bool(0.3) {
+                data.push(Fr::from(rng.gen_range(0..2u64) * rng.gen_range(1..100u64)));
+                indices.push(col);
+            }
+        }
+        indptr.push(indices.len());
+    }
+    SparseMatrix::try_new(data, indices, indptr, cols).unwrap()
+}
+
+#[test]
+fn prune_drops_zeros_at_row_starts_ends_and_whole_rows() {
+    let (M, expected) = with_zeros();
+    assert_eq!(M.explicit_zeros(), 7);
+    assert_eq!(M.prune(), expected);
+    assert_eq!(expected.explicit_zeros(), 0);
+
+    let mut in_place = M.clone();
+    assert_eq!(in_place.prune_in_place(), 7);
+    assert_eq!(in_place, expected);
+    assert_eq!(in_place.prune_in_place(), 0);
+    assert_eq!(in_place, expected);
+}
+
+#[test]
+fn prune_keeps_the_product() {
+    let mut rng = ChaCha20Rng::seed_from_u64(96);
+    // Enough rows for several chunks of the parallel prune.
+    for (rows, cols) in [(1, 1), (7, 4), (10_000, 5)] {
+        let M = half_zeros(&mut rng, rows, cols);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let pruned = M.prune();
+        assert_eq!(pruned.nnz(), M.nnz() - M.explicit_zeros(), "{rows}x{cols}");
+        assert_eq!(pruned.explicit_zeros(), 0);
+        assert_eq!(pruned.multiply_vec(&z), M.multiply_vec(&z), "{rows}x{cols}");
+
+        let mut in_place = M.clone();
+        in_place.prune_in_place();
+        assert_eq!(in_place, pruned, "{rows}x{cols}");
+    }
+}
+
+#[test]
+fn prune_of_degenerate_matrices() {
+    for M in [
+        SparseMatrix::<Fr>::zero(0, 3),
+        SparseMatrix::zero(4, 0),
+        SparseMatrix::identity(3),
+    ] {
+        assert_eq!(M.prune(), M);
+        assert_eq!(M.clone().prune_in_place(), 0);
+    }
+    let all_zeros = SparseMatrix {
+        data: vec![Fr::from(0); 3],
+        indices: vec![0, 1, 0],
+        indptr: vec![0, 2, 3],
+        cols: 2,
+    };
+    assert_eq!(all_zeros.prune(), SparseMatrix::zero(2, 2));
+}
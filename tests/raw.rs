@@ -0,0 +1,160 @@
+#![allow(non_snake_case)]
+
+use camino::Utf8PathBuf;
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    data::{
+        raw::{index_width, raw_matrix_bytes, RawCodec, ARRAY_HEADER_BYTES},
+        DataFormat,
+    },
+    generate::{random_matrix, random_vector, Shape},
+    DataConfig, DataError, SparseMatrix,
+};
+
+fn encode(value: &impl RawCodec) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.write_raw(&mut bytes).unwrap();
+    bytes
+}
+
+fn matrix() -> SparseMatrix<Fr> {
+    let mut rng = ChaCha20Rng::seed_from_u64(3);
+    let shape = Shape {
+        rows: 50,
+        cols: 40,
+        nnz_per_row: 6,
+    };
+    random_matrix(&mut rng, shape)
+}
+
+#[test]
+fn vectors_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(1);
+    // Longer than one decode chunk, so several tasks contribute in order.
+    let vector: Vec<Fr> = random_vector(&mut rng, 40_000);
+    let bytes = encode(&vector);
+    assert_eq!(bytes.len(), ARRAY_HEADER_BYTES + 32 * vector.len());
+    assert_eq!(Vec::<Fr>::read_raw(&bytes).unwrap(), vector);
+    assert_eq!(Vec::<Fr>::read_raw(&encode(&Vec::<Fr>::new())).unwrap(), []);
+}
+
+#[test]
+fn matrices_round_trip() {
+    let M = matrix();
+    assert_eq!(SparseMatrix::<Fr>::read_raw(&encode(&M)).unwrap(), M);
+}
+
+#[test]
+fn indices_are_stored_in_four_bytes_when_they_fit() {
+    let M = matrix();
+    let bytes = encode(&M);
+    let index_bytes = 4 * (M.indices.len() + M.indptr.len() + 1);
+    assert_eq!(
+        bytes.len(),
+        4 * ARRAY_HEADER_BYTES + 32 * M.nnz() + index_bytes
+    );
+    assert_eq!(bytes.len() as u64, raw_matrix_bytes(&M));
+    assert_eq!(index_width(&M.indices), 4);
+
+    // Too many columns for a u32: that array alone falls back to 8 bytes.
+    let wide = SparseMatrix {
+        data: vec![Fr::from(7)],
+        indices: vec![1 << 33],
+        indptr: vec![0, 1],
+        cols: 1 << 34,
+    };
+    let bytes = encode(&wide);
+    assert_eq!(bytes.len() as u64, raw_matrix_bytes(&wide));
+    assert_eq!(bytes.len(), 4 * ARRAY_HEADER_BYTES + 32 + 8 + 4 * 2 + 8);
+    assert_eq!(SparseMatrix::<Fr>::read_raw(&bytes).unwrap(), wide);
+}
+
+#[test]
+fn eight_byte_indices_still_load() {
+    // The layout of files written before indices were narrowed.
+    let M = matrix();
+    let mut bytes = encode(&M.data);
+    for values in [&M.indices[..], &M.indptr, &[M.cols]] {
+        bytes.extend(b"SPMV");
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(8u16.to_le_bytes());
+        bytes.extend((values.len() as u64).to_le_bytes());
+        bytes.extend(
+            values
+                .iter()
+                .flat_map(|&value| (value as u64).to_le_bytes()),
+        );
+    }
+    assert_eq!(SparseMatrix::<Fr>::read_raw(&bytes).unwrap(), M);
+
+    // The width of the last array, holding `cols`.
+    let width = bytes.len() - 8 - ARRAY_HEADER_BYTES + 6;
+    bytes[width] = 2;
+    assert_eq!(
+        SparseMatrix::<Fr>::read_raw(&bytes).unwrap_err(),
+        "integers are 2 bytes wide, expected 4 or 8"
+    );
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let bytes = encode(&vec![Fr::from(1), Fr::from(2)]);
+    let read = |bytes: &[u8]| Vec::<Fr>::read_raw(bytes).unwrap_err();
+
+    assert_eq!(
+        read(&bytes[..bytes.len() - 1]),
+        "truncated array of 2 elements"
+    );
+    assert_eq!(read(&bytes[..4]), "truncated array header");
+    assert_eq!(read(&[bytes.as_slice(), &[0]].concat()), "1 trailing bytes");
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    assert_eq!(read(&bad_magic), "not a raw file");
+
+    let mut bad_version = bytes.clone();
+    bad_version[4] = 9;
+    assert_eq!(read(&bad_version), "unsupported version 9");
+
+    let mut non_canonical = bytes.clone();
+    non_canonical[ARRAY_HEADER_BYTES..ARRAY_HEADER_BYTES + 32].fill(0xff);
+    assert_eq!(
+        read(&non_canonical),
+        "a value is not a canonical field element"
+    );
+
+    // A vector is not a matrix: its single array is not followed by the index arrays.
+    let err = SparseMatrix::<Fr>::read_raw(&bytes).unwrap_err();
+    assert_eq!(err, "truncated array header");
+}
+
+#[test]
+fn config_reads_raw_files_by_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+    let config = DataConfig::new(root);
+    let M = matrix();
+
+    config.write("sparse_matrices_abc", "A_0", &M).unwrap();
+    let path = config.write_raw("sparse_matrices_abc", "A_0", &M).unwrap();
+    assert!(path.as_str().ends_with("A_0.raw"), "{path}");
+
+    let raw = config.clone().with_format(DataFormat::Raw);
+    let read: SparseMatrix<Fr> = raw.read_with_format("sparse_matrices_abc", "A_0").unwrap();
+    assert_eq!(read, M);
+
+    std::fs::write(&path, b"SPMV").unwrap();
+    let err = raw
+        .read_with_format::<SparseMatrix<Fr>>("sparse_matrices_abc", "A_0")
+        .unwrap_err();
+    assert!(matches!(err, DataError::InvalidRaw { .. }), "{err:?}");
+    assert_eq!(err.path(), Some(&*path));
+
+    // The bincode file is untouched and still read by default.
+    let read: SparseMatrix<Fr> = config
+        .read_with_format("sparse_matrices_abc", "A_0")
+        .unwrap();
+    assert_eq!(read, M);
+}
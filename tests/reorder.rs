@@ -0,0 +1,172 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::random_vector,
+    vec_ops::{permute_vec, unpermute_vec},
+    SparseMatrix,
+};
+
+/// A `rows x cols` matrix with up to four entries per row at random columns, and some rows
+/// empty.
+fn random_matrix(rng: &mut ChaCha20Rng, rows: usize, cols: usize) -> SparseMatrix<Fr> {
+    let dense: Vec<Vec<Fr>> = (0..rows)
+        .map(|_| {
+            let mut row = vec![Fr::from(0); cols];
+            for _ in 0..rng.gen_range(0..=4) {
+                row[rng.gen_range(0..cols)] = Fr::from(rng.gen_range(1..100u64));
+            }
+            row
+        })
+        .collect();
+    SparseMatrix::from_dense(&dense)
+}
+
+fn shuffled(rng: &mut ChaCha20Rng, len: usize) -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..len).collect();
+    perm.shuffle(rng);
+    perm
+}
+
+/// The permutation undoing `perm`.
+fn inverse(perm: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; perm.len()];
+    for (i, &j) in perm.iter().enumerate() {
+        inverse[j] = i;
+    }
+    inverse
+}
+
+#[test]
+fn permute_rows_moves_whole_rows() {
+    let M = SparseMatrix::from_dense(&[
+        vec![Fr::from(1), Fr::from(0), Fr::from(2)],
+        vec![Fr::from(0), Fr::from(0), Fr::from(0)],
+        vec![Fr::from(0), Fr::from(3), Fr::from(0)],
+    ]);
+    let permuted = M.permute_rows(&[2, 0, 1]);
+    assert_eq!(
+        permuted.to_dense().unwrap(),
+        [
+            vec![Fr::from(0), Fr::from(3), Fr::from(0)],
+            vec![Fr::from(1), Fr::from(0), Fr::from(2)],
+            vec![Fr::from(0), Fr::from(0), Fr::from(0)],
+        ]
+    );
+    assert_eq!(permuted.nnz(), M.nnz());
+    assert_eq!(M.permute_rows(&[0, 1, 2]), M);
+    let empty = SparseMatrix::<Fr>::zero(0, 3);
+    assert_eq!(empty.permute_rows(&[]), empty);
+}
+
+#[test]
+fn permute_cols_moves_whole_columns_and_keeps_rows_sorted() {
+    let M = SparseMatrix::from_dense(&[
+        vec![Fr::from(1), Fr::from(0), Fr::from(2)],
+        vec![Fr::from(0), Fr::from(0), Fr::from(0)],
+        vec![Fr::from(4), Fr::from(3), Fr::from(0)],
+    ]);
+    let permuted = M.permute_cols(&[2, 0, 1]);
+    assert_eq!(
+        permuted.to_dense().unwrap(),
+        [
+            vec![Fr::from(2), Fr::from(1), Fr::from(0)],
+            vec![Fr::from(0), Fr::from(0), Fr::from(0)],
+            vec![Fr::from(0), Fr::from(4), Fr::from(3)],
+        ]
+    );
+    assert_eq!(permuted.indices, [0, 1, 1, 2]);
+    assert_eq!(M.permute_cols(&[0, 1, 2]), M);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(96);
+    let M = random_matrix(&mut rng, 100, 40);
+    let permuted = M.permute_cols(&shuffled(&mut rng, 40));
+    for ptrs in permuted.indptr.windows(2) {
+        let row = &permuted.indices[ptrs[0]..ptrs[1]];
+        assert!(row.windows(2).all(|pair| pair[0] < pair[1]), "{row:?}");
+    }
+}
+
+#[test]
+fn permutations_and_their_inverses_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(97);
+    for (rows, cols) in [(1, 1), (5, 3), (200, 50)] {
+        let M = random_matrix(&mut rng, rows, cols);
+        let (row_perm, col_perm) = (shuffled(&mut rng, rows), shuffled(&mut rng, cols));
+        let permuted = M.permute_rows(&row_perm).permute_cols(&col_perm);
+        assert_eq!(
+            permuted
+                .permute_cols(&inverse(&col_perm))
+                .permute_rows(&inverse(&row_perm)),
+            M,
+            "{rows}x{cols}"
+        );
+    }
+}
+
+#[test]
+fn permuted_products_are_the_permuted_original() {
+    let mut rng = ChaCha20Rng::seed_from_u64(98);
+    for (rows, cols) in [(1, 1), (5, 3), (200, 50)] {
+        let M = random_matrix(&mut rng, rows, cols);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected = M.multiply_vec(&z);
+
+        for row_perm in [M.locality_ordering(), shuffled(&mut rng, rows)] {
+            let col_perm = shuffled(&mut rng, cols);
+            let permuted = M.permute_rows(&row_perm).permute_cols(&col_perm);
+            let product = permuted.multiply_vec(&permute_vec(&z, &col_perm));
+            for (i, &row) in row_perm.iter().enumerate() {
+                assert_eq!(product[i], expected[row], "{rows}x{cols}, row {row}");
+            }
+            assert_eq!(product, permute_vec(&expected, &row_perm));
+            assert_eq!(
+                unpermute_vec(&product, &row_perm),
+                expected,
+                "{rows}x{cols}"
+            );
+        }
+    }
+}
+
+#[test]
+fn locality_ordering_sorts_by_first_column() {
+    let M = SparseMatrix::from_dense(&[
+        vec![Fr::from(0), Fr::from(0), Fr::from(1)],
+        vec![Fr::from(0), Fr::from(0), Fr::from(0)],
+        vec![Fr::from(1), Fr::from(0), Fr::from(1)],
+        vec![Fr::from(0), Fr::from(1), Fr::from(0)],
+        vec![Fr::from(0), Fr::from(0), Fr::from(1)],
+        vec![Fr::from(1), Fr::from(0), Fr::from(0)],
+    ]);
+    // Ties keep their order and the empty row goes last.
+    assert_eq!(M.locality_ordering(), [2, 5, 3, 0, 4, 1]);
+    assert_eq!(
+        SparseMatrix::<Fr>::zero(3, 2).locality_ordering(),
+        [0, 1, 2]
+    );
+    assert_eq!(
+        SparseMatrix::<Fr>::identity(4).locality_ordering(),
+        [0, 1, 2, 3]
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid permutation")]
+fn permute_rows_rejects_a_repeated_row() {
+    SparseMatrix::<Fr>::identity(3).permute_rows(&[0, 1, 1]);
+}
+
+#[test]
+#[should_panic(expected = "invalid permutation")]
+fn permute_rows_rejects_a_short_permutation() {
+    SparseMatrix::<Fr>::identity(3).permute_rows(&[1, 0]);
+}
+
+#[test]
+#[should_panic(expected = "invalid permutation")]
+fn permute_cols_rejects_a_column_out_of_range() {
+    SparseMatrix::<Fr>::identity(3).permute_cols(&[0, 1, 3]);
+}
@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    report::{
+        BenchReport, ChunkTiming, HistogramBin, MatrixTiming, RowProfile, WitnessTiming, CSV_HEADER,
+    },
+    timing::Measurement,
+    SparseMatrix,
+};
+
+fn matrix() -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    }
+}
+
+#[test]
+fn record_keeps_only_the_matching_product() {
+    let mut entry = MatrixTiming::new("A", &matrix());
+    assert_eq!((entry.rows, entry.cols, entry.nnz), (3, 3, 3));
+
+    let measurements = vec![
+        Measurement::new("AZ", Duration::from_nanos(10)),
+        Measurement::new("AZ", Duration::from_nanos(12)),
+        Measurement::new("BZ", Duration::from_nanos(99)),
+    ];
+    entry.record(4, 2, "AZ", &measurements);
+    assert_eq!(
+        entry.witnesses,
+        vec![WitnessTiming {
+            witness: 4,
+            threads: 2,
+            durations_ns: vec![10, 12],
+        }]
+    );
+}
+
+#[test]
+fn json_round_trip() {
+    let mut report = BenchReport::new("abc", 8);
+    let mut entry = MatrixTiming::new("A", &matrix());
+    entry.record(
+        0,
+        8,
+        "AZ",
+        &[Measurement::new("AZ", Duration::from_micros(3))],
+    );
+    report.matrices.push(entry);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("results.json");
+    report.write_json(&path).unwrap();
+    assert_eq!(BenchReport::read_json(&path).unwrap(), report);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["hash"], "abc");
+    assert_eq!(json["threads"], 8);
+    assert_eq!(json["matrices"][0]["witnesses"][0]["durations_ns"][0], 3000);
+}
+
+fn chunk(start_row: usize, duration_ns: u64) -> ChunkTiming {
+    ChunkTiming {
+        start_row,
+        end_row: start_row + 2,
+        nnz: 5,
+        duration_ns,
+        thread: start_row % 3,
+    }
+}
+
+#[test]
+fn row_profiles_keep_the_slowest_chunks_and_bin_all() {
+    let chunks = vec![
+        chunk(0, 5),
+        chunk(2, 100),
+        chunk(4, 0),
+        chunk(6, 6),
+        chunk(8, 100),
+    ];
+    let profile = RowProfile::new(7, 2, chunks, 2);
+    assert_eq!((profile.witness, profile.chunk_rows), (7, 2));
+    assert_eq!((profile.chunks, profile.total_ns), (5, 211));
+    // Ties keep row order.
+    assert_eq!(profile.slowest, [chunk(2, 100), chunk(8, 100)]);
+    let bins: Vec<_> = profile
+        .histogram
+        .iter()
+        .map(|HistogramBin { min_ns, chunks }| (*min_ns, *chunks))
+        .collect();
+    assert_eq!(
+        bins,
+        [
+            (0, 1),
+            (1, 0),
+            (2, 0),
+            (4, 2),
+            (8, 0),
+            (16, 0),
+            (32, 0),
+            (64, 2)
+        ]
+    );
+
+    // Rows 0 and 6 went to thread 0, 4 to thread 1, and 2 and 8 to thread 2.
+    let threads: Vec<_> = profile
+        .threads
+        .iter()
+        .map(|work| (work.thread, work.chunks, work.rows, work.nnz, work.busy_ns))
+        .collect();
+    assert_eq!(
+        threads,
+        [(0, 2, 4, 10, 11), (1, 1, 2, 5, 0), (2, 2, 4, 10, 200)]
+    );
+
+    let empty = RowProfile::new(0, 2, Vec::new(), 2);
+    assert!(empty.slowest.is_empty() && empty.histogram.is_empty());
+}
+
+#[test]
+fn row_profiles_are_optional_in_json() {
+    let mut report = BenchReport::new("abc", 1);
+    report.matrices.push(MatrixTiming::new("A", &matrix()));
+    let json = serde_json::to_value(&report).unwrap();
+    assert!(json["matrices"][0].get("row_profile").is_none());
+
+    report.matrices[0].row_profile = Some(RowProfile::new(0, 2, vec![chunk(0, 3)], 20));
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("results.json");
+    report.write_json(&path).unwrap();
+    assert_eq!(BenchReport::read_json(&path).unwrap(), report);
+}
+
+fn two_matrix_report() -> BenchReport {
+    let mut report = BenchReport::new("abc", 1);
+    for (name, product) in [("A", "AZ"), ("B", "BZ")] {
+        let mut entry = MatrixTiming::new(name, &matrix());
+        for witness in 0..2 {
+            let run = Measurement::new(product, Duration::from_nanos(100 + witness as u64));
+            entry.record(witness, 1, product, &[run]);
+        }
+        report.matrices.push(entry);
+    }
+    report
+}
+
+#[test]
+fn csv_rows_have_stable_columns() {
+    let mut out = Vec::new();
+    two_matrix_report().write_csv(&mut out, true).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "{CSV_HEADER}\n0,A,100,3,3,3,1\n1,A,101,3,3,3,1\n0,B,100,3,3,3,1\n1,B,101,3,3,3,1\n"
+        )
+    );
+}
+
+#[test]
+fn csv_append_writes_header_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("timings.csv");
+    let report = two_matrix_report();
+
+    report.write_csv_file(&path, true).unwrap();
+    report.write_csv_file(&path, true).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.matches(CSV_HEADER).count(), 1);
+    assert_eq!(contents.lines().count(), 9);
+
+    // Without append the file starts over.
+    report.write_csv_file(&path, false).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 5);
+}
+
+#[test]
+fn sweeps_mix_thread_counts() {
+    let mut report = two_matrix_report();
+    assert!(!report.is_sweep());
+
+    // Entries written before sweeps existed fall back to the report's count.
+    report.matrices[0].witnesses[0].threads = 0;
+    assert_eq!(report.threads_of(&report.matrices[0].witnesses[0]), 1);
+    assert!(!report.is_sweep());
+
+    report.matrices[1].witnesses[1].threads = 4;
+    assert!(report.is_sweep());
+}
+
+#[test]
+fn entries_without_threads_still_parse() {
+    let json = r#"{"hash":"abc","timestamp":0,"threads":8,"matrices":[{"name":"A","rows":3,"cols":3,"nnz":3,"witnesses":[{"witness":0,"durations_ns":[5]}]}]}"#;
+    let report: BenchReport = serde_json::from_str(json).unwrap();
+    assert_eq!(report.matrices[0].witnesses[0].threads, 0);
+    assert_eq!(report.threads_of(&report.matrices[0].witnesses[0]), 8);
+}
@@ -0,0 +1,457 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::{multiply_view, CsrView, MatrixError, RowView},
+    SparseMatrix,
+};
+
+/// ```text
+/// [1 0 2]
+/// [0 0 0]
+/// [0 3 0]
+/// ```
+fn small_matrix() -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 3,
+    }
+}
+
+fn fr_vec(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn multiply_vec_small() {
+    let A = small_matrix();
+    let z = fr_vec(&[1, 2, 3]);
+    assert_eq!(A.multiply_vec(&z), fr_vec(&[7, 0, 6]));
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn multiply_vec_rejects_wrong_length() {
+    small_matrix().multiply_vec(&fr_vec(&[1, 2]));
+}
+
+#[test]
+fn multiply_vec_into_resizes_the_buffer() {
+    let mut rng = ChaCha20Rng::seed_from_u64(83);
+    let (A, B) = (small_matrix(), SparseMatrix::random(40, 3, 2, &mut rng));
+    let z = fr_vec(&[1, 2, 3]);
+    let mut out = vec![Fr::from(9); 7];
+    A.multiply_vec_into(&z, &mut out);
+    assert_eq!(out, fr_vec(&[7, 0, 6]));
+    B.multiply_vec_into(&z, &mut out);
+    assert_eq!(out, B.multiply_vec(&z));
+    A.multiply_vec_into(&z, &mut out);
+    assert_eq!(out, fr_vec(&[7, 0, 6]));
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn multiply_vec_into_rejects_wrong_length() {
+    small_matrix().multiply_vec_into(&fr_vec(&[1, 2]), &mut Vec::new());
+}
+
+#[test]
+fn clone_is_equal() {
+    let A = small_matrix();
+    assert_eq!(A.clone(), A);
+}
+
+#[test]
+fn get_row_unchecked_yields_row_entries() {
+    let A = small_matrix();
+    let row: Vec<_> = A.get_row_unchecked(&[0, 2]).collect();
+    assert_eq!(row, vec![(&Fr::from(1), &0), (&Fr::from(2), &2)]);
+}
+
+#[test]
+fn byte_accounting() {
+    let A = small_matrix();
+    let fr = std::mem::size_of::<Fr>();
+    let word = std::mem::size_of::<usize>();
+    assert_eq!(A.nnz(), 3);
+    assert_eq!(A.memory_footprint(), 3 * fr + (3 + 4) * word);
+    // The matrix itself, one gathered element per nonzero, one output per row.
+    assert_eq!(
+        A.multiply_vec_traffic(),
+        A.memory_footprint() + (3 + 3) * fr
+    );
+}
+
+#[test]
+fn row_nnz_iter() {
+    let nnz: Vec<usize> = small_matrix().row_nnz_iter().collect();
+    assert_eq!(nnz, [2, 0, 1]);
+}
+
+#[test]
+fn multiply_vec_serial_small() {
+    let A = small_matrix();
+    assert_eq!(
+        A.multiply_vec_serial(&fr_vec(&[1, 2, 3])),
+        fr_vec(&[7, 0, 6])
+    );
+}
+
+#[test]
+fn serial_matches_parallel_on_random_matrices() {
+    for seed in 0..64 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..100);
+        let shape = Shape {
+            rows: rng.gen_range(0..200),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(12)),
+        };
+        let A: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        let z: Vec<Fr> = random_vector(&mut rng, shape.cols);
+        assert_eq!(
+            A.multiply_vec_serial(&z),
+            A.multiply_vec(&z),
+            "seed {seed}, {shape:?}"
+        );
+    }
+}
+
+#[test]
+fn view_multiply_matches_kernel() {
+    let M = small_matrix();
+    assert_eq!(
+        (CsrView::rows(&M), CsrView::cols(&M), CsrView::nnz(&M)),
+        (3, 3, 3)
+    );
+    assert_eq!(M.row_range(1), 2..2);
+    assert_eq!(M.entry(2), (Fr::from(3), 1));
+
+    let z = fr_vec(&[1, 2, 3]);
+    assert_eq!(multiply_view(&M, &z), M.multiply_vec(&z));
+}
+
+fn dense(rows: &[&[u64]]) -> Vec<Vec<Fr>> {
+    rows.iter().map(|row| fr_vec(row)).collect()
+}
+
+#[test]
+fn from_dense_skips_zeros() {
+    let rows = dense(&[&[1, 0, 2], &[0, 0, 0], &[0, 3, 0]]);
+    let M = SparseMatrix::from_dense(&rows);
+    assert_eq!(M, small_matrix());
+    assert_eq!(M.to_dense().unwrap(), rows);
+
+    // An all-zero matrix has only empty rows, and a dense row stores every entry.
+    let zeros = dense(&[&[0, 0], &[0, 0]]);
+    let M = SparseMatrix::from_dense(&zeros);
+    assert_eq!((M.nnz(), M.cols, M.indptr), (0, 2, vec![0, 0, 0]));
+    let M = SparseMatrix::from_dense(&dense(&[&[4, 5, 6]]));
+    assert_eq!((M.indices, M.indptr), (vec![0, 1, 2], vec![0, 3]));
+    let M = SparseMatrix::<Fr>::from_dense(&[]);
+    assert_eq!((M.indptr, M.cols), (vec![0], 0));
+}
+
+#[test]
+#[should_panic(expected = "row 1 is not as long as the first")]
+fn from_dense_rejects_ragged_rows() {
+    SparseMatrix::from_dense(&dense(&[&[1, 2], &[3]]));
+}
+
+#[test]
+fn to_dense_refuses_large_matrices() {
+    let M = small_matrix();
+    assert!(M.to_dense_with_limit(9).is_ok());
+    let err = M.to_dense_with_limit(8).unwrap_err();
+    assert_eq!(err, "a 3x3 matrix is over the limit of 8 dense elements");
+    let huge = SparseMatrix::<Fr> {
+        data: vec![],
+        indices: vec![],
+        indptr: vec![0; 1 << 20],
+        cols: usize::MAX,
+    };
+    assert!(huge.to_dense().is_err());
+}
+
+#[test]
+fn multiply_vec_matches_the_dense_product() {
+    for seed in 0..32 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let (rows, cols) = (rng.gen_range(1..20), rng.gen_range(1..20));
+        // Mostly zeros, with whole rows left empty or filled in at random.
+        let rows: Vec<Vec<Fr>> = (0..rows)
+            .map(|_| {
+                let density = [0.0, 0.2, 1.0][rng.gen_range(0..3)];
+                (0..cols)
+                    .map(|_| match rng.gen_bool(density) {
+                        true => Fr::from(rng.gen_range(1..1000)),
+                        false => Fr::from(0),
+                    })
+                    .collect()
+            })
+            .collect();
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let expected: Vec<Fr> = rows
+            .iter()
+            .map(|row| row.iter().zip(&z).map(|(a, b)| *a * b).sum())
+            .collect();
+        let M = SparseMatrix::from_dense(&rows);
+        assert_eq!(M.multiply_vec(&z), expected, "seed {seed}");
+        assert_eq!(M.to_dense().unwrap(), rows, "seed {seed}");
+    }
+}
+
+#[test]
+fn try_new_accepts_valid_parts() {
+    let A = small_matrix();
+    let built = SparseMatrix::try_new(A.data.clone(), A.indices.clone(), A.indptr.clone(), 3);
+    assert_eq!(built.unwrap(), A);
+    // Unsorted columns and empty matrices are valid.
+    assert!(SparseMatrix::try_new(fr_vec(&[1, 2]), vec![2, 0], vec![0, 2], 3).is_ok());
+    assert!(SparseMatrix::<Fr>::try_new(Vec::new(), Vec::new(), vec![0], 0).is_ok());
+}
+
+#[test]
+fn try_new_names_the_broken_invariant() {
+    let cases = [
+        (
+            vec![0, 1],
+            vec![0, 3],
+            MatrixError::LengthMismatch {
+                data: 3,
+                indices: 2,
+            },
+        ),
+        (vec![0, 1, 2], vec![], MatrixError::EmptyIndptr),
+        (
+            vec![0, 1, 2],
+            vec![1, 3],
+            MatrixError::IndptrStart { start: 1 },
+        ),
+        (
+            vec![0, 1, 2],
+            vec![0, 2],
+            MatrixError::IndptrEnd { end: 2, nnz: 3 },
+        ),
+        (
+            vec![0, 1, 2],
+            vec![0, 2, 1, 3],
+            MatrixError::IndptrDecreasing {
+                row: 1,
+                start: 2,
+                end: 1,
+            },
+        ),
+        // A pointer past the entries in the middle is caught before any row is scanned.
+        (
+            vec![0, 1, 2],
+            vec![0, 5, 3],
+            MatrixError::IndptrDecreasing {
+                row: 1,
+                start: 5,
+                end: 3,
+            },
+        ),
+        (
+            vec![0, 1, 3],
+            vec![0, 1, 3],
+            MatrixError::IndexOutOfBounds {
+                row: 1,
+                position: 2,
+                col: 3,
+                cols: 3,
+            },
+        ),
+    ];
+    for (indices, indptr, expected) in cases {
+        let M = SparseMatrix::new_unchecked(fr_vec(&[1, 2, 3]), indices.clone(), indptr.clone(), 3);
+        assert_eq!(M.validate(), Err(expected.clone()));
+        let err = SparseMatrix::try_new(fr_vec(&[1, 2, 3]), indices, indptr, 3).unwrap_err();
+        assert_eq!(err, expected);
+    }
+    let err = SparseMatrix::try_new(fr_vec(&[1, 2, 3]), vec![0, 1, 3], vec![0, 1, 3], 3);
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "row 1: column 3 of the entry at 2 is out of bounds of the 3 columns"
+    );
+}
+
+#[test]
+fn get_finds_present_and_absent_entries() {
+    let A = small_matrix();
+    assert_eq!(A.get(0, 0), Some(Fr::from(1)));
+    assert_eq!(A.get(0, 2), Some(Fr::from(2)));
+    assert_eq!(A.get(2, 1), Some(Fr::from(3)));
+    assert_eq!(A.get(0, 1), Some(Fr::from(0)));
+    assert_eq!(A.get(1, 2), Some(Fr::from(0)));
+    for (row, col) in [(3, 0), (0, 3), (usize::MAX, 0), (0, usize::MAX)] {
+        assert_eq!(A.get(row, col), None, "({row}, {col})");
+    }
+
+    assert_eq!(A[(2, 1)], Fr::from(3));
+    assert_eq!(A[(2, 2)], Fr::from(0));
+
+    // Unsorted rows are scanned rather than binary-searched.
+    let unsorted = SparseMatrix {
+        data: fr_vec(&[5, 6, 7]),
+        indices: vec![2, 0, 1],
+        indptr: vec![0, 3],
+        cols: 4,
+    };
+    for (col, value) in [(0, 6), (1, 7), (2, 5), (3, 0)] {
+        assert_eq!(unsorted.get(0, col), Some(Fr::from(value)), "{col}");
+        assert_eq!(unsorted[(0, col)], Fr::from(value), "{col}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "(1, 3) is out of bounds of a 3x3 matrix")]
+fn index_panics_out_of_bounds() {
+    let _ = small_matrix()[(1, 3)];
+}
+
+#[test]
+fn row_iterates_entries_by_column() {
+    let A = small_matrix();
+    let row: Vec<_> = A.row(0).unwrap().collect();
+    assert_eq!(row, [(0, &Fr::from(1)), (2, &Fr::from(2))]);
+    assert_eq!(A.row(1).unwrap().count(), 0);
+    assert!(A.row(3).is_none());
+    assert!(A.row(usize::MAX).is_none());
+}
+
+#[test]
+fn iter_yields_every_entry_in_row_major_order() {
+    // Empty rows at the start, in the middle and at the end.
+    let A = SparseMatrix {
+        data: fr_vec(&[1, 2, 3, 4]),
+        indices: vec![1, 0, 2, 1],
+        indptr: vec![0, 0, 2, 2, 2, 4, 4],
+        cols: 3,
+    };
+    let entries: Vec<_> = A.iter().collect();
+    let expected = [(1, 1, 1), (1, 0, 2), (4, 2, 3), (4, 1, 4)];
+    let expected: Vec<_> = expected
+        .iter()
+        .zip(&A.data)
+        .map(|(&(row, col, value), data)| {
+            assert_eq!(*data, Fr::from(value));
+            (row, col, data)
+        })
+        .collect();
+    assert_eq!(entries, expected);
+    assert_eq!(A.iter().len(), A.nnz());
+    let mut reversed: Vec<_> = A.iter().rev().collect();
+    reversed.reverse();
+    assert_eq!(reversed, expected);
+
+    let par_entries: Vec<_> = A.par_iter().collect();
+    assert_eq!(par_entries, expected);
+    assert_eq!(A.par_iter().len(), A.nnz());
+
+    let empty = SparseMatrix::<Fr>::new_unchecked(vec![], vec![], vec![0, 0, 0], 2);
+    assert_eq!(empty.iter().count(), 0);
+    assert_eq!(empty.par_iter().count(), 0);
+}
+
+#[test]
+fn par_iter_matches_iter_on_every_split() {
+    let mut rng = ChaCha20Rng::seed_from_u64(68);
+    let shape = Shape {
+        rows: 300,
+        cols: 200,
+        nnz_per_row: 3,
+    };
+    let A: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let entries: Vec<_> = A.iter().collect();
+    assert_eq!(entries.len(), A.nnz());
+    assert!(entries.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    for min_len in [1, 7, A.nnz()] {
+        let par_entries: Vec<_> = A.par_iter().with_min_len(min_len).collect();
+        assert_eq!(par_entries, entries, "{min_len}");
+    }
+}
+
+#[test]
+fn rows_view_every_row() {
+    let A = small_matrix();
+    let rows: Vec<RowView<Fr>> = A.rows().collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(
+        rows.iter().map(RowView::nnz).collect::<Vec<_>>(),
+        A.row_nnz_iter().collect::<Vec<_>>()
+    );
+    let first: Vec<_> = rows[0].iter().collect();
+    assert_eq!(first, [(0, &Fr::from(1)), (2, &Fr::from(2))]);
+    assert_eq!(rows[1].iter().len(), 0);
+
+    let z = fr_vec(&[1, 2, 3]);
+    let dots: Vec<_> = rows.iter().map(|row| row.dot(&z)).collect();
+    assert_eq!(dots, A.multiply_vec_serial(&z));
+    assert_eq!(rows[1].dot(&z), Fr::from(0));
+}
+
+#[test]
+fn shape_of_small_and_unfilled_matrices() {
+    let A = small_matrix();
+    assert_eq!((A.num_rows(), A.num_cols(), A.shape()), (3, 3, (3, 3)));
+    assert!(!A.is_empty());
+
+    // A matrix whose `indptr` was never filled in has no rows, rather than underflowing.
+    let unfilled = SparseMatrix::<Fr>::new_unchecked(vec![], vec![], vec![], 2);
+    assert_eq!(unfilled.shape(), (0, 2));
+    assert_eq!(unfilled.nnz(), 0);
+    assert!(unfilled.is_empty());
+    assert_eq!(unfilled.multiply_vec(&fr_vec(&[1, 2])), []);
+    assert_eq!(unfilled.multiply_vec_serial(&fr_vec(&[1, 2])), []);
+    assert_eq!(unfilled.get(0, 0), None);
+    assert!(unfilled.row(0).is_none());
+    assert_eq!(unfilled.iter().count(), 0);
+}
+
+#[test]
+fn pretty_draws_an_aligned_grid() {
+    let mut A = small_matrix();
+    A.data[2] = -Fr::from(1);
+    assert_eq!(
+        A.to_string(),
+        [
+            "3x3 matrix, 3 entries",
+            "        0x1           ·         0x2",
+            "          ·           ·           ·",
+            "          · 0x3064…0000           ·",
+        ]
+        .join("\n")
+    );
+    assert!(A.pretty(3, 3).is_complete());
+}
+
+#[test]
+fn pretty_cuts_off_large_matrices() {
+    let A = small_matrix();
+    assert_eq!(
+        A.pretty(2, 1).to_string(),
+        "3x3 matrix, 3 entries\n0x1 …\n  · …\n  ⋮"
+    );
+    assert!(!A.pretty(2, 1).is_complete());
+
+    let huge = SparseMatrix {
+        data: fr_vec(&[7]),
+        indices: vec![0],
+        indptr: [vec![0], vec![1; 1 << 20]].concat(),
+        cols: 1 << 20,
+    };
+    let drawn = huge.pretty(usize::MAX, usize::MAX).to_string();
+    assert!(
+        drawn.starts_with("1048576x1048576 matrix, 1 entries\n0x7   ·"),
+        "{drawn}"
+    );
+    assert!(drawn.lines().count() <= 2 + 64, "{drawn}");
+    assert!(drawn.len() < 64 * 33 * 8, "{}", drawn.len());
+}
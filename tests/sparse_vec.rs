@@ -0,0 +1,80 @@
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, Shape},
+    sparse::{sparsify, SPARSE_VEC_MAX_DENSITY},
+    SparseMatrix,
+};
+
+/// A vector of `len` entries of which about `density` are random and the rest zero.
+fn padded(rng: &mut ChaCha20Rng, len: usize, density: f64) -> Vec<Fr> {
+    (0..len)
+        .map(|_| match rng.gen_bool(density) {
+            true => Fr::random(&mut *rng),
+            false => Fr::ZERO,
+        })
+        .collect()
+}
+
+#[test]
+fn sparsify_keeps_the_nonzero_entries() {
+    let z = [Fr::ZERO, Fr::from(3), Fr::ZERO, Fr::ZERO, -Fr::ONE];
+    assert_eq!(sparsify(&z), [(1, Fr::from(3)), (4, -Fr::ONE)]);
+    assert_eq!(sparsify::<Fr>(&[]), []);
+    assert_eq!(sparsify(&[Fr::ZERO; 4]), []);
+}
+
+#[test]
+fn sparse_product_matches_the_dense_one() {
+    let densities = [
+        0.0,
+        0.01,
+        SPARSE_VEC_MAX_DENSITY / 2.0,
+        SPARSE_VEC_MAX_DENSITY,
+        0.5,
+        1.0,
+    ];
+    for seed in 0..16 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..500);
+        let shape = Shape {
+            rows: rng.gen_range(0..300),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(20)),
+        };
+        let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        for density in densities {
+            let z = padded(&mut rng, cols, density);
+            assert_eq!(
+                M.multiply_sparse_vec(&sparsify(&z)),
+                M.multiply_vec_serial(&z),
+                "seed {seed}, {shape:?}, density {density}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sparse_product_of_matrices_without_rows_or_columns() {
+    assert_eq!(SparseMatrix::<Fr>::zero(0, 3).multiply_sparse_vec(&[]), []);
+    assert_eq!(
+        SparseMatrix::<Fr>::zero(2, 0).multiply_sparse_vec(&[]),
+        [Fr::ZERO; 2]
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid sparse vector")]
+fn sparse_vector_out_of_bounds() {
+    SparseMatrix::<Fr>::identity(3).multiply_sparse_vec(&[(3, Fr::ONE)]);
+}
+
+#[test]
+#[should_panic(expected = "invalid sparse vector")]
+fn sparse_vector_out_of_order() {
+    SparseMatrix::<Fr>::identity(3).multiply_sparse_vec(&[(2, Fr::ONE), (1, Fr::ONE)]);
+}
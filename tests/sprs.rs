@@ -0,0 +1,101 @@
+#![cfg(feature = "sprs")]
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::{sprs_multiply_vec, SprsError},
+    SparseMatrix,
+};
+use sprs::{CsMat, CsMatI};
+
+fn round_trip(M: &SparseMatrix<Fr>) -> SparseMatrix<Fr> {
+    SparseMatrix::try_from(CsMat::from(M)).unwrap()
+}
+
+fn values(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn random_matrices_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(5);
+    let shape = Shape {
+        rows: 50,
+        cols: 30,
+        nnz_per_row: 4,
+    };
+    let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let csmat = CsMat::from(&M);
+    assert_eq!(csmat.shape(), (50, 30));
+    assert_eq!(csmat.nnz(), M.data.len());
+    // The generated rows are sorted, so they come back as they were.
+    assert_eq!(round_trip(&M), M);
+
+    let z: Vec<Fr> = random_vector(&mut rng, 30);
+    assert_eq!(sprs_multiply_vec(&csmat, &z), M.multiply_vec(&z));
+}
+
+#[test]
+fn empty_rows_survive_the_round_trip() {
+    // Rows 0, 2, and the last two are empty, so `indptr` repeats, at the end too.
+    let M = SparseMatrix {
+        data: values(&[1, 2, 3]),
+        indices: vec![0, 3, 1],
+        indptr: vec![0, 0, 2, 2, 3, 3, 3],
+        cols: 4,
+    };
+    let csmat = CsMat::from(&M);
+    assert_eq!(csmat.shape(), (6, 4));
+    assert_eq!(csmat.indptr().raw_storage(), [0, 0, 2, 2, 3, 3, 3]);
+    assert_eq!(round_trip(&M), M);
+    assert_eq!(
+        sprs_multiply_vec(&csmat, &values(&[1, 10, 100, 1000])),
+        values(&[0, 2001, 0, 30, 0, 0])
+    );
+
+    let empty = SparseMatrix {
+        data: vec![],
+        indices: vec![],
+        indptr: vec![0, 0, 0],
+        cols: 5,
+    };
+    assert_eq!(CsMat::from(&empty).shape(), (2, 5));
+    assert_eq!(round_trip(&empty), empty);
+}
+
+#[test]
+fn rows_are_sorted_and_repeated_columns_added_up() {
+    let M = SparseMatrix {
+        data: values(&[1, 2, 3, 4, 5]),
+        indices: vec![2, 0, 2, 1, 0],
+        indptr: vec![0, 3, 5],
+        cols: 3,
+    };
+    let csmat = CsMat::from(&M);
+    assert_eq!(csmat.indices(), [0, 2, 0, 1]);
+    assert_eq!(csmat.data(), values(&[2, 4, 5, 4]));
+
+    let z = values(&[1, 10, 100]);
+    assert_eq!(sprs_multiply_vec(&csmat, &z), M.multiply_vec(&z));
+}
+
+#[test]
+fn csc_matrices_do_not_convert() {
+    let csc = CsMat::new_csc((2, 2), vec![0, 1, 2], vec![0, 1], values(&[1, 2]));
+    assert_eq!(SparseMatrix::try_from(csc), Err(SprsError::NotCsr));
+
+    // Narrower index types widen as they are.
+    let narrow: CsMatI<Fr, u32> = CsMatI::new((2, 3), vec![0, 1, 2], vec![2, 0], values(&[7, 8]));
+    assert_eq!(
+        SparseMatrix::try_from(narrow).unwrap(),
+        SparseMatrix {
+            data: values(&[7, 8]),
+            indices: vec![2, 0],
+            indptr: vec![0, 1, 2],
+            cols: 3,
+        }
+    );
+}
@@ -0,0 +1,59 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use image::{ColorType, ImageFormat};
+use spmvm_test_example::{spy::Occupancy, SparseMatrix};
+
+fn diagonal(n: usize) -> SparseMatrix<Fr> {
+    SparseMatrix {
+        data: vec![Fr::from(1); n],
+        indices: (0..n).collect(),
+        indptr: (0..=n).collect(),
+        cols: n,
+    }
+}
+
+#[test]
+fn small_matrices_get_a_pixel_per_entry() {
+    let occupancy = Occupancy::new(&diagonal(5), 1024, 1024);
+    assert_eq!((occupancy.width, occupancy.height), (5, 5));
+    let pixels = occupancy.pixels();
+    for (i, &pixel) in pixels.iter().enumerate() {
+        let on_diagonal = i / 5 == i % 5;
+        assert_eq!(pixel, if on_diagonal { 0 } else { 255 }, "pixel {i}");
+    }
+}
+
+#[test]
+fn large_matrices_are_downsampled() {
+    let occupancy = Occupancy::new(&diagonal(1000), 10, 20);
+    assert_eq!((occupancy.width, occupancy.height), (10, 20));
+    // Each of the 20 grid rows covers 50 matrix rows, all in one of the 10 grid columns.
+    for y in 0..20 {
+        let row = &occupancy.counts[y * 10..(y + 1) * 10];
+        assert_eq!(row.iter().sum::<u32>(), 50, "row {y}");
+        assert_eq!(row[y / 2], 50, "row {y}");
+    }
+
+    // A lone entry among dense ones is light, but still not white.
+    let mut M = diagonal(1000);
+    M.indices[999] = 0;
+    let pixels = Occupancy::new(&M, 10, 10).pixels();
+    // The first cell now holds the most entries, 101, and the last 99 of them.
+    assert_eq!(pixels[0], 0);
+    assert!(pixels[90] < 255 && pixels[90] > 200, "{}", pixels[90]);
+    assert!(pixels[99] < 10, "{}", pixels[99]);
+}
+
+#[test]
+fn images_are_grayscale_pngs() {
+    let occupancy = Occupancy::new(&diagonal(3), 1024, 1024);
+    let mut png = Vec::new();
+    occupancy.write_png(&mut png).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+    assert_eq!(image.color(), ColorType::L8);
+    assert_eq!((image.width(), image.height()), (3, 3));
+    // The diagonal is set, and nothing else.
+    assert_eq!(image.as_bytes(), [0, 255, 255, 255, 0, 255, 255, 255, 0]);
+}
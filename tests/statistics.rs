@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use halo2curves::bn256::Fr;
+use spmvm_test_example::{
+    sparse::EllShape,
+    statistics::{MatrixStats, RowBucket, RowDistribution, Summary},
+    SparseMatrix,
+};
+
+fn ms(values: &[u64]) -> Vec<Duration> {
+    values.iter().copied().map(Duration::from_millis).collect()
+}
+
+#[test]
+fn empty_has_no_summary() {
+    assert_eq!(Summary::from_durations(&[]), None);
+}
+
+#[test]
+fn single_sample() {
+    let summary = Summary::from_durations(&ms(&[5])).unwrap();
+    assert_eq!(summary.count, 1);
+    assert_eq!(summary.mean, Duration::from_millis(5));
+    assert_eq!(summary.median, Duration::from_millis(5));
+    assert_eq!(summary.stddev, Duration::ZERO);
+    assert_eq!(summary.min, summary.max);
+}
+
+#[test]
+fn odd_count() {
+    // mean 4, sample variance (2^2 + 1^2 + 3^2 + 3^2 + 1^2) / 4 = 6
+    let summary = Summary::from_durations(&ms(&[2, 5, 1, 7, 5])).unwrap();
+    assert_eq!(summary.count, 5);
+    assert_eq!(summary.mean, Duration::from_millis(4));
+    assert_eq!(summary.median, Duration::from_millis(5));
+    assert_eq!(summary.min, Duration::from_millis(1));
+    assert_eq!(summary.max, Duration::from_millis(7));
+    let expected = Duration::from_nanos((6f64.sqrt() * 1e6).round() as u64);
+    assert_eq!(summary.stddev, expected);
+}
+
+#[test]
+fn even_count_median_is_midpoint() {
+    let summary = Summary::from_durations(&ms(&[4, 1, 3, 2])).unwrap();
+    assert_eq!(summary.median, Duration::from_micros(2500));
+    assert_eq!(summary.mean, Duration::from_micros(2500));
+}
+
+#[test]
+fn matrix_stats() {
+    // [1 0 2 0]
+    // [0 0 0 0]
+    // [0 3 0 0]
+    let matrix = SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 2, 3],
+        cols: 4,
+    };
+    let stats = MatrixStats::new(&matrix);
+    assert_eq!(
+        stats,
+        MatrixStats {
+            rows: 3,
+            cols: 4,
+            nnz: 3,
+            density: 0.25,
+            min_row_nnz: 0,
+            max_row_nnz: 2,
+            mean_row_nnz: 1.0,
+            empty_rows: 1,
+            row_distribution: RowDistribution {
+                histogram: vec![
+                    RowBucket {
+                        min: 0,
+                        max: 0,
+                        rows: 1
+                    },
+                    RowBucket {
+                        min: 1,
+                        max: 1,
+                        rows: 1
+                    },
+                    RowBucket {
+                        min: 2,
+                        max: 2,
+                        rows: 1
+                    },
+                ],
+                // The rows hold 0, 1, and 2: 2 (1 * 0 + 2 * 1 + 3 * 2) / (3 * 3) - 4 / 3.
+                imbalance: 2.0 * 8.0 / 9.0 - 4.0 / 3.0,
+                top_rows: 1,
+                top_share: 2.0 / 3.0,
+            },
+            ones: 1,
+            minus_ones: 0,
+            explicit_zeros: 0,
+            duplicates: 0,
+            diagonal_nonzeros: 1,
+            lower_triangular: false,
+            upper_triangular: false,
+            symmetric_pattern: false,
+            memory_bytes: 3 * 32 + 7 * 8,
+            ell: EllShape {
+                width: 2,
+                padding: 3,
+                spilled_rows: 0,
+                spilled_nnz: 0,
+            },
+        }
+    );
+}
+
+#[test]
+fn matrix_stats_without_rows() {
+    let matrix = SparseMatrix::<Fr> {
+        data: vec![],
+        indices: vec![],
+        indptr: vec![0],
+        cols: 0,
+    };
+    let stats = MatrixStats::new(&matrix);
+    assert_eq!(
+        (stats.rows, stats.min_row_nnz, stats.max_row_nnz),
+        (0, 0, 0)
+    );
+    assert_eq!((stats.density, stats.mean_row_nnz), (0.0, 0.0));
+}
+
+/// A matrix whose rows hold `counts` nonzeros, each starting at column 0.
+fn with_row_counts(counts: &[usize]) -> SparseMatrix<Fr> {
+    let cols = counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut indptr = vec![0];
+    for &count in counts {
+        indptr.push(indptr[indptr.len() - 1] + count);
+    }
+    let indices = counts.iter().flat_map(|&count| 0..count).collect();
+    SparseMatrix::try_new(
+        vec![Fr::from(1); indptr[counts.len()]],
+        indices,
+        indptr,
+        cols,
+    )
+    .unwrap()
+}
+
+#[test]
+fn row_histogram_buckets_are_log_scaled() {
+    let stats = MatrixStats::new(&with_row_counts(&[0, 1, 2, 3, 4, 5, 8, 9, 9, 17, 0]));
+    let buckets: Vec<_> = stats
+        .row_distribution
+        .histogram
+        .iter()
+        .map(|bucket| (bucket.min, bucket.max, bucket.rows))
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            (0, 0, 2),
+            (1, 1, 1),
+            (2, 2, 1),
+            (3, 4, 2),
+            (5, 8, 2),
+            (9, 16, 2),
+            (17, 32, 1),
+        ]
+    );
+    assert_eq!(stats.row_distribution.histogram[3].to_string(), "3-4: 2");
+    assert_eq!(stats.row_distribution.histogram[1].to_string(), "1: 1");
+    assert_eq!(
+        (stats.min_row_nnz, stats.max_row_nnz, stats.empty_rows),
+        (0, 17, 2)
+    );
+}
+
+#[test]
+fn imbalance_of_known_distributions() {
+    // Every row alike is perfectly balanced.
+    let even = MatrixStats::new(&with_row_counts(&[4; 2000])).row_distribution;
+    assert!(even.imbalance.abs() < 1e-12, "{}", even.imbalance);
+    assert_eq!(even.top_rows, 2);
+    assert!((even.top_share - 2.0 / 2000.0).abs() < 1e-12);
+
+    // One row holding everything of n rows comes to (n - 1) / n.
+    let mut counts = vec![0; 999];
+    counts.push(50);
+    let skewed = MatrixStats::new(&with_row_counts(&counts)).row_distribution;
+    assert!(
+        (skewed.imbalance - 0.999).abs() < 1e-12,
+        "{}",
+        skewed.imbalance
+    );
+    assert_eq!((skewed.top_rows, skewed.top_share), (1, 1.0));
+
+    // Half the rows empty and half alike: 1/2.
+    let half = MatrixStats::new(&with_row_counts(&[0, 3, 0, 3])).row_distribution;
+    assert!((half.imbalance - 0.5).abs() < 1e-12, "{}", half.imbalance);
+
+    // Nothing to spread, over rows or no rows at all.
+    for counts in [&[0, 0, 0][..], &[]] {
+        let distribution = MatrixStats::new(&with_row_counts(counts)).row_distribution;
+        assert_eq!((distribution.imbalance, distribution.top_share), (0.0, 0.0));
+    }
+    let none = MatrixStats::new(&with_row_counts(&[])).row_distribution;
+    assert_eq!((none.histogram.len(), none.top_rows), (0, 0));
+}
+
+#[test]
+fn matrix_stats_with_empty_indptr() {
+    let matrix = SparseMatrix::<Fr>::new_unchecked(vec![], vec![], vec![], 3);
+    let stats = MatrixStats::new(&matrix);
+    assert_eq!((stats.rows, stats.cols, stats.empty_rows), (0, 3, 0));
+}
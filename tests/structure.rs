@@ -0,0 +1,102 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use spmvm_test_example::SparseMatrix;
+
+fn dense(rows: &[&[u64]]) -> SparseMatrix<Fr> {
+    let rows: Vec<Vec<Fr>> = rows
+        .iter()
+        .map(|row| row.iter().copied().map(Fr::from).collect())
+        .collect();
+    SparseMatrix::from_dense(&rows)
+}
+
+fn fr(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn diagonal_is_zero_where_absent() {
+    let M = dense(&[&[1, 0, 2], &[0, 0, 0], &[0, 3, 4]]);
+    assert_eq!(M.diagonal(), fr(&[1, 0, 4]));
+    // Its length is that of the shorter side.
+    let wide = dense(&[&[5, 1, 0, 0], &[0, 0, 6, 0]]);
+    assert_eq!(wide.diagonal(), fr(&[5, 0]));
+    assert_eq!(wide.transpose().diagonal(), fr(&[5, 0]));
+    assert_eq!(SparseMatrix::<Fr>::identity(4).diagonal(), fr(&[1; 4]));
+    assert!(SparseMatrix::<Fr>::zero(0, 3).diagonal().is_empty());
+}
+
+#[test]
+fn diagonal_adds_up_duplicates_in_unsorted_rows() {
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4]),
+        indices: vec![1, 0, 0, 1],
+        indptr: vec![0, 3, 4],
+        cols: 2,
+    };
+    assert_eq!(M.diagonal(), fr(&[5, 4]));
+}
+
+#[test]
+fn triangular_fixtures() {
+    let lower = dense(&[&[1, 0, 0], &[2, 3, 0], &[4, 0, 5]]);
+    assert!(lower.is_lower_triangular());
+    assert!(!lower.is_upper_triangular());
+    let upper = lower.transpose();
+    assert!(upper.is_upper_triangular());
+    assert!(!upper.is_lower_triangular());
+
+    let diagonal = dense(&[&[1, 0, 0], &[0, 0, 0], &[0, 0, 7]]);
+    assert!(diagonal.is_lower_triangular() && diagonal.is_upper_triangular());
+    for M in [SparseMatrix::<Fr>::zero(0, 0), SparseMatrix::zero(3, 2)] {
+        assert!(M.is_lower_triangular() && M.is_upper_triangular());
+    }
+
+    // One entry off the triangle is enough, even if it is stored as zero.
+    let almost = SparseMatrix::try_new(
+        fr(&[1, 0, 2, 3, 4, 5]),
+        vec![0, 2, 0, 1, 0, 2],
+        vec![0, 2, 4, 6],
+        3,
+    )
+    .unwrap();
+    assert!(!almost.is_lower_triangular());
+    assert!(!almost.is_upper_triangular());
+}
+
+#[test]
+fn symmetric_pattern_ignores_values() {
+    let M = dense(&[&[1, 2, 0], &[3, 0, 4], &[0, 5, 6]]);
+    assert!(M.symmetric_pattern());
+    assert!(dense(&[&[1, 0, 0], &[0, 0, 0], &[0, 0, 7]]).symmetric_pattern());
+    assert!(SparseMatrix::<Fr>::identity(5).symmetric_pattern());
+    assert!(SparseMatrix::<Fr>::zero(0, 0).symmetric_pattern());
+
+    // Entries at (0, 2) and (2, 0) are not mirrored.
+    let asymmetric = dense(&[&[1, 0, 2], &[0, 0, 0], &[0, 3, 0]]);
+    assert!(!asymmetric.symmetric_pattern());
+    assert!(!dense(&[&[1, 0, 0], &[2, 3, 0], &[4, 0, 5]]).symmetric_pattern());
+    // Only square matrices are symmetric.
+    assert!(!SparseMatrix::<Fr>::zero(2, 3).symmetric_pattern());
+}
+
+#[test]
+fn symmetric_pattern_of_unsorted_rows_with_duplicates() {
+    // [x x] with the entry at (0, 1) stored twice and the rows out of order.
+    // [x 0]
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3, 4]),
+        indices: vec![1, 0, 1, 0],
+        indptr: vec![0, 3, 4],
+        cols: 2,
+    };
+    assert!(M.symmetric_pattern());
+    let M = SparseMatrix {
+        data: fr(&[1, 2, 3]),
+        indices: vec![1, 0, 1],
+        indptr: vec![0, 3, 3],
+        cols: 2,
+    };
+    assert!(!M.symmetric_pattern());
+}
@@ -0,0 +1,99 @@
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::Coefficient,
+    statistics::MatrixStats,
+    SparseMatrix,
+};
+
+/// A random matrix in which about a third of the entries are 1 and a third are -1, as in the
+/// matrices circuit compilers emit.
+fn mostly_units(rng: &mut ChaCha20Rng, shape: Shape) -> SparseMatrix<Fr> {
+    let mut M: SparseMatrix<Fr> = random_matrix(rng, shape);
+    for value in &mut M.data {
+        match rng.gen_range(0..3) {
+            0 => *value = Fr::ONE,
+            1 => *value = -Fr::ONE,
+            _ => {}
+        }
+    }
+    M
+}
+
+#[test]
+fn coefficients_are_classified() {
+    assert_eq!(Coefficient::of(&Fr::ONE), Coefficient::One);
+    assert_eq!(Coefficient::of(&-Fr::ONE), Coefficient::MinusOne);
+    for other in [Fr::ZERO, Fr::from(2), -Fr::from(2)] {
+        assert_eq!(Coefficient::of(&other), Coefficient::Other);
+    }
+}
+
+#[test]
+fn tagged_matches_plain_on_random_matrices() {
+    for seed in 0..32 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..100);
+        let shape = Shape {
+            rows: rng.gen_range(0..200),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(12)),
+        };
+        let M = mostly_units(&mut rng, shape);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let tagged = M.tag_coefficients();
+        assert_eq!(tagged.tags().len(), M.nnz());
+        assert_eq!(
+            tagged.multiply_vec(&z),
+            M.multiply_vec(&z),
+            "seed {seed}, {shape:?}"
+        );
+
+        let mut out = vec![Fr::from(9); 2];
+        tagged.multiply_vec_into(&z, &mut out);
+        assert_eq!(out, M.multiply_vec_serial(&z), "seed {seed}, {shape:?}");
+    }
+}
+
+#[test]
+fn unit_coefficients_are_counted() {
+    let mut rng = ChaCha20Rng::seed_from_u64(88);
+    let shape = Shape {
+        rows: 300,
+        cols: 40,
+        nnz_per_row: 5,
+    };
+    let M = mostly_units(&mut rng, shape);
+    let ones = M.data.iter().filter(|&&value| value == Fr::ONE).count();
+    let minus_ones = M.data.iter().filter(|&&value| value == -Fr::ONE).count();
+    assert!(ones > 0 && minus_ones > 0);
+
+    assert_eq!(M.count_unit_coefficients(), (ones, minus_ones));
+    let tagged = M.tag_coefficients();
+    assert_eq!(tagged.count(Coefficient::One), ones);
+    assert_eq!(tagged.count(Coefficient::MinusOne), minus_ones);
+    assert_eq!(
+        tagged.count(Coefficient::Other),
+        M.nnz() - ones - minus_ones
+    );
+    let stats = MatrixStats::new(&M);
+    assert_eq!((stats.ones, stats.minus_ones), (ones, minus_ones));
+}
+
+#[test]
+fn tagged_matrices_without_rows() {
+    let unfilled = SparseMatrix::<Fr>::new_unchecked(vec![], vec![], vec![], 2);
+    assert_eq!(unfilled.tag_coefficients().multiply_vec(&[Fr::ONE; 2]), []);
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn tagged_rejects_wrong_length() {
+    let M = SparseMatrix::<Fr>::identity(3);
+    M.tag_coefficients().multiply_vec(&[Fr::ONE; 2]);
+}
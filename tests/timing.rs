@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use spmvm_test_example::timing::{self, Measurement, Work};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn fake_iteration(a: u64, b: u64, c: u64) -> Vec<Measurement> {
+    vec![
+        Measurement::new("AZ", ms(a)),
+        Measurement::new("BZ", ms(b)),
+        Measurement::new("CZ", ms(c)),
+    ]
+}
+
+#[test]
+fn iteration_report_times_each_product_independently() {
+    let report = timing::iteration_report(3, &fake_iteration(10, 20, 30));
+    assert_eq!(
+        report,
+        "timing: 3\nAZ took: 10ms\nBZ took: 20ms\nCZ took: 30ms\ntotal: 60ms\n"
+    );
+}
+
+#[test]
+fn summary_report_sums_every_witness() {
+    let iterations = vec![fake_iteration(1, 2, 3), fake_iteration(4, 5, 6)];
+    assert_eq!(
+        timing::summary_report(&iterations),
+        "grand total over 2 witnesses: 21ms\n"
+    );
+}
+
+#[test]
+fn time_records_label_and_value() {
+    let (value, m) = Measurement::time("AZ", || 7);
+    assert_eq!(value, 7);
+    assert_eq!(m.label, "AZ");
+}
+
+#[test]
+fn repeated_measurements_are_summarized() {
+    let measurements = vec![
+        Measurement::new("AZ", ms(1)),
+        Measurement::new("AZ", ms(3)),
+        Measurement::new("BZ", ms(2)),
+        Measurement::new("BZ", ms(2)),
+    ];
+    let report = timing::iteration_report(0, &measurements);
+    assert!(report.contains("AZ: mean 2ms, median 2ms"), "{report}");
+    assert!(
+        report.contains("BZ: mean 2ms, median 2ms, stddev 0ns"),
+        "{report}"
+    );
+    assert!(report.ends_with("total: 8ms\n"), "{report}");
+
+    let summary = timing::summary_report(&[measurements.clone(), measurements]);
+    assert!(summary.contains("AZ overall: mean 2ms"), "{summary}");
+    assert!(summary.contains("(4 runs)"), "{summary}");
+}
+
+#[test]
+fn throughput_is_shown_when_work_is_known() {
+    let work = Work {
+        nnz: 3_000_000,
+        bytes: 2_000_000_000,
+    };
+    let measurements = vec![
+        Measurement::new("AZ", ms(1000)).with_work(work),
+        Measurement::new("BZ", ms(500)),
+    ];
+    let report = timing::iteration_report(0, &measurements);
+    assert!(
+        report.contains("AZ took: 1s (3.00 Mnnz/s, 2.00 GB/s)\n"),
+        "{report}"
+    );
+    assert!(report.contains("BZ took: 500ms\n"), "{report}");
+}
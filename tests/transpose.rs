@@ -0,0 +1,148 @@
+#![allow(non_snake_case)]
+
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    SparseMatrix,
+};
+
+/// `A^T y`, one entry at a time.
+fn transposed_product(A: &SparseMatrix<Fr>, y: &[Fr]) -> Vec<Fr> {
+    let mut product = vec![Fr::from(0); A.cols];
+    for (row, ptrs) in A.indptr.windows(2).enumerate() {
+        for (value, &col) in A.get_row_unchecked(ptrs.try_into().unwrap()) {
+            product[col] += *value * y[row];
+        }
+    }
+    product
+}
+
+#[test]
+fn transpose_small() {
+    // ```text
+    // [1 0 2]      [1 0]
+    // [0 3 0]  ->  [0 3]
+    //              [2 0]
+    // ```
+    let A = SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+        indices: vec![0, 2, 1],
+        indptr: vec![0, 2, 3],
+        cols: 3,
+    };
+    assert_eq!(
+        A.transpose(),
+        SparseMatrix {
+            data: vec![Fr::from(1), Fr::from(3), Fr::from(2)],
+            indices: vec![0, 1, 0],
+            indptr: vec![0, 1, 2, 3],
+            cols: 2,
+        }
+    );
+}
+
+#[test]
+fn unsorted_rows_come_out_sorted() {
+    let A = SparseMatrix {
+        data: vec![Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)],
+        indices: vec![2, 0, 1, 0],
+        indptr: vec![0, 2, 4],
+        cols: 3,
+    };
+    let T = A.transpose();
+    assert_eq!(T.indices, [0, 1, 1, 0]);
+    assert_eq!(T.data, [Fr::from(2), Fr::from(4), Fr::from(3), Fr::from(1)]);
+    assert_eq!(T.transpose().to_dense(), A.to_dense());
+}
+
+#[test]
+fn empty_matrices_transpose_to_empty_matrices() {
+    for (rows, cols) in [(0, 0), (0, 4), (3, 0), (3, 4)] {
+        let A: SparseMatrix<Fr> = SparseMatrix {
+            data: Vec::new(),
+            indices: Vec::new(),
+            indptr: vec![0; rows + 1],
+            cols,
+        };
+        let T = A.transpose();
+        assert_eq!((T.cols, T.nnz()), (rows, 0));
+        assert_eq!(T.indptr, vec![0; cols + 1]);
+    }
+}
+
+#[test]
+fn transpose_is_an_involution_on_random_matrices() {
+    let mut rng = ChaCha20Rng::seed_from_u64(62);
+    for _ in 0..40 {
+        let rows = rng.gen_range(1..120);
+        let cols = rng.gen_range(1..120);
+        // Sparse rows of wide matrices leave some columns empty.
+        let shape = Shape {
+            rows,
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols.min(8)),
+        };
+        let A: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        let threads = rng.gen_range(1..9);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let T = pool.install(|| A.transpose());
+        assert_eq!((T.indptr.len(), T.cols, T.nnz()), (cols + 1, rows, A.nnz()));
+        assert!(
+            T.indptr
+                .windows(2)
+                .all(|ptrs| T.indices[ptrs[0]..ptrs[1]].windows(2).all(|w| w[0] < w[1])),
+            "{shape:?}"
+        );
+        assert_eq!(pool.install(|| T.transpose()), A, "{shape:?}");
+
+        let y = random_vector(&mut rng, rows);
+        let expected = transposed_product(&A, &y);
+        assert_eq!(T.multiply_vec(&y), expected, "{shape:?}");
+        assert_eq!(
+            pool.install(|| A.multiply_vec_transposed(&y)),
+            expected,
+            "{shape:?}"
+        );
+    }
+}
+
+#[test]
+fn multiply_vec_transposed_matches_the_explicit_transpose() {
+    let mut rng = ChaCha20Rng::seed_from_u64(63);
+    let shape = Shape {
+        rows: 500,
+        cols: 80,
+        nnz_per_row: 5,
+    };
+    let A: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+    let y = random_vector(&mut rng, 500);
+    assert_eq!(
+        A.multiply_vec_transposed(&y),
+        A.transpose().multiply_vec(&y)
+    );
+
+    let empty: SparseMatrix<Fr> = SparseMatrix {
+        data: Vec::new(),
+        indices: Vec::new(),
+        indptr: vec![0],
+        cols: 3,
+    };
+    assert_eq!(empty.multiply_vec_transposed(&[]), vec![Fr::from(0); 3]);
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn multiply_vec_transposed_rejects_wrong_length() {
+    let A: SparseMatrix<Fr> = SparseMatrix {
+        data: vec![Fr::from(1)],
+        indices: vec![2],
+        indptr: vec![0, 1, 1],
+        cols: 3,
+    };
+    A.multiply_vec_transposed(&[Fr::from(1), Fr::from(2), Fr::from(3)]);
+}
@@ -0,0 +1,77 @@
+#![allow(non_snake_case)]
+
+use ff::Field;
+use halo2curves::bn256::Fr;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use spmvm_test_example::{
+    generate::{random_matrix, random_vector, Shape},
+    sparse::MatrixError,
+    SparseMatrix,
+};
+
+// The matrices are small enough that `cargo miri test --test unchecked` checks every access
+// of the unchecked kernel in reasonable time.
+
+#[test]
+fn unchecked_matches_the_reference_on_small_matrices() {
+    for seed in 0..8 {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let cols = rng.gen_range(1..12);
+        let shape = Shape {
+            rows: rng.gen_range(0..12),
+            cols,
+            nnz_per_row: rng.gen_range(0..=cols),
+        };
+        let M: SparseMatrix<Fr> = random_matrix(&mut rng, shape);
+        let z: Vec<Fr> = random_vector(&mut rng, cols);
+        let validated = M.validated().unwrap();
+        assert_eq!(
+            validated.multiply_vec(&z),
+            M.multiply_vec_serial(&z),
+            "seed {seed}, {shape:?}"
+        );
+
+        let mut out = vec![Fr::from(5); 3];
+        validated.multiply_vec_into(&z, &mut out);
+        assert_eq!(out, M.multiply_vec(&z), "seed {seed}, {shape:?}");
+    }
+}
+
+#[test]
+fn unchecked_reaches_the_last_column() {
+    // Every entry is in the last column, the one an off-by-one bound would read past.
+    let M = SparseMatrix::try_new(vec![Fr::from(2); 3], vec![3; 3], vec![0, 1, 1, 3], 4).unwrap();
+    let z = [Fr::ZERO, Fr::ZERO, Fr::ZERO, Fr::from(7)];
+    assert_eq!(
+        M.validated().unwrap().multiply_vec(&z),
+        [Fr::from(14), Fr::ZERO, Fr::from(28)]
+    );
+    let unfilled = SparseMatrix::<Fr>::zero(0, 2);
+    assert_eq!(
+        unfilled.validated().unwrap().multiply_vec(&[Fr::ONE; 2]),
+        []
+    );
+}
+
+#[test]
+fn invalid_matrices_are_never_validated() {
+    let mut M = SparseMatrix::<Fr>::identity(3);
+    M.indices[1] = 3;
+    assert_eq!(
+        M.validated().map(|_| ()),
+        Err(MatrixError::IndexOutOfBounds {
+            row: 1,
+            position: 1,
+            col: 3,
+            cols: 3,
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid shape")]
+fn unchecked_rejects_a_short_vector() {
+    let M = SparseMatrix::<Fr>::identity(3);
+    M.validated().unwrap().multiply_vec(&[Fr::ONE; 2]);
+}
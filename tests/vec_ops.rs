@@ -0,0 +1,114 @@
+use halo2curves::bn256::Fr;
+use spmvm_test_example::vec_ops::{
+    add_vec, add_vec_assign, hadamard, hadamard_assign, is_permutation, permute_vec, scale_vec,
+    sub_scaled_vec_assign, unpermute_into, unpermute_vec,
+};
+
+fn fr_vec(values: &[u64]) -> Vec<Fr> {
+    values.iter().copied().map(Fr::from).collect()
+}
+
+#[test]
+fn hadamard_multiplies_element_by_element() {
+    assert_eq!(
+        hadamard(&fr_vec(&[1, 2, 3]), &fr_vec(&[4, 0, 6])),
+        fr_vec(&[4, 0, 18])
+    );
+    assert!(hadamard::<Fr>(&[], &[]).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "vectors of different lengths")]
+fn hadamard_checks_lengths() {
+    hadamard(&fr_vec(&[1, 2]), &fr_vec(&[1]));
+}
+
+#[test]
+fn scale_and_add() {
+    assert_eq!(
+        scale_vec(Fr::from(3), &fr_vec(&[1, 0, 5])),
+        fr_vec(&[3, 0, 15])
+    );
+    assert_eq!(
+        add_vec(&fr_vec(&[1, 2, 3]), &fr_vec(&[4, 0, 6])),
+        fr_vec(&[5, 2, 9])
+    );
+}
+
+#[test]
+#[should_panic(expected = "vectors of different lengths")]
+fn add_vec_checks_lengths() {
+    add_vec(&fr_vec(&[1]), &fr_vec(&[1, 2]));
+}
+
+/// Whether `az ∘ bz = u cz + e`.
+fn relaxed_holds(az: &[Fr], bz: &[Fr], cz: &[Fr], u: Fr, e: &[Fr]) -> bool {
+    hadamard(az, bz) == add_vec(&scale_vec(u, cz), e)
+}
+
+#[test]
+fn relaxed_relation_by_hand() {
+    // 2 * 4 = 3 * 2 + 2, and 3 * 5 = 3 * 5 + 0.
+    let (az, bz, cz) = (fr_vec(&[2, 3]), fr_vec(&[4, 5]), fr_vec(&[2, 5]));
+    let u = Fr::from(3);
+    assert!(relaxed_holds(&az, &bz, &cz, u, &fr_vec(&[2, 0])));
+    assert!(!relaxed_holds(&az, &bz, &cz, u, &fr_vec(&[2, 1])));
+    assert!(!relaxed_holds(&az, &bz, &cz, Fr::from(1), &fr_vec(&[2, 0])));
+    // With u = 1 and E = 0 it is plain R1CS.
+    assert!(relaxed_holds(
+        &az,
+        &bz,
+        &fr_vec(&[8, 15]),
+        Fr::from(1),
+        &fr_vec(&[0, 0])
+    ));
+}
+
+#[test]
+fn assign_in_place() {
+    let mut a = fr_vec(&[1, 2, 3]);
+    hadamard_assign(&mut a, &fr_vec(&[4, 0, 6]));
+    assert_eq!(a, fr_vec(&[4, 0, 18]));
+    add_vec_assign(&mut a, &fr_vec(&[1, 1, 1]));
+    assert_eq!(a, fr_vec(&[5, 1, 19]));
+    sub_scaled_vec_assign(&mut a, Fr::from(2), &fr_vec(&[2, 0, 9]));
+    assert_eq!(a, fr_vec(&[1, 1, 1]));
+}
+
+#[test]
+fn permute_and_unpermute_round_trip() {
+    let v = fr_vec(&[10, 20, 30, 40]);
+    assert_eq!(permute_vec(&v, &[2, 0, 3, 1]), fr_vec(&[30, 10, 40, 20]));
+    assert_eq!(unpermute_vec(&fr_vec(&[30, 10, 40, 20]), &[2, 0, 3, 1]), v);
+    for perm in [[0, 1, 2, 3], [3, 2, 1, 0], [1, 3, 0, 2]] {
+        assert_eq!(unpermute_vec(&permute_vec(&v, &perm), &perm), v, "{perm:?}");
+        assert_eq!(permute_vec(&unpermute_vec(&v, &perm), &perm), v, "{perm:?}");
+    }
+    assert!(permute_vec::<Fr>(&[], &[]).is_empty());
+}
+
+#[test]
+fn unpermuting_into_reuses_the_buffer() {
+    let v = fr_vec(&[30, 10, 40, 20]);
+    let mut out = fr_vec(&[1, 2, 3, 4, 5, 6]);
+    let buffer = out.as_ptr();
+    unpermute_into(&v, &[2, 0, 3, 1], &mut out);
+    assert_eq!(out, fr_vec(&[10, 20, 30, 40]));
+    assert_eq!(out.as_ptr(), buffer);
+}
+
+#[test]
+fn permutations_are_bijections_of_the_length() {
+    assert!(is_permutation(&[1, 2, 0], 3));
+    assert!(is_permutation(&[], 0));
+    assert!(!is_permutation(&[0, 0, 1], 3));
+    assert!(!is_permutation(&[0, 1], 3));
+    assert!(!is_permutation(&[0, 1, 2, 3], 3));
+    assert!(!is_permutation(&[0, 3, 1], 3));
+}
+
+#[test]
+#[should_panic(expected = "invalid permutation")]
+fn unpermute_checks_the_permutation() {
+    unpermute_vec(&fr_vec(&[1, 2]), &[0, 2]);
+}